@@ -0,0 +1,47 @@
+// The peer service is defined in Rust rather than a .proto file so building
+// doesn't need protoc; messages live in src/peer.rs.
+fn main() {
+    let peer_service = tonic_build::manual::Service::builder()
+        .name("PeerService")
+        .package("multi_rpc.peer")
+        .method(
+            tonic_build::manual::Method::builder()
+                .name("announce")
+                .route_name("Announce")
+                .input_type("crate::peer::EndpointHealthUpdate")
+                .output_type("crate::peer::AnnounceResponse")
+                .codec_path("tonic::codec::ProstCodec")
+                .build(),
+        )
+        .method(
+            tonic_build::manual::Method::builder()
+                .name("get_peers")
+                .route_name("GetPeers")
+                .input_type("crate::peer::GetPeersRequest")
+                .output_type("crate::peer::PeersResponse")
+                .codec_path("tonic::codec::ProstCodec")
+                .build(),
+        )
+        .build();
+
+    // The unary calls of the Yellowstone Geyser service; messages live in src/transport.rs
+    let geyser_method = |name: &str, route_name: &str, message: &str| {
+        tonic_build::manual::Method::builder()
+            .name(name)
+            .route_name(route_name)
+            .input_type(format!("crate::transport::{}Request", message))
+            .output_type(format!("crate::transport::{}Response", message))
+            .codec_path("tonic::codec::ProstCodec")
+            .build()
+    };
+    let geyser_service = tonic_build::manual::Service::builder()
+        .name("Geyser")
+        .package("geyser")
+        .method(geyser_method("get_slot", "GetSlot", "GetSlot"))
+        .method(geyser_method("get_block_height", "GetBlockHeight", "GetBlockHeight"))
+        .method(geyser_method("get_latest_blockhash", "GetLatestBlockhash", "GetLatestBlockhash"))
+        .method(geyser_method("is_blockhash_valid", "IsBlockhashValid", "IsBlockhashValid"))
+        .build();
+
+    tonic_build::manual::Builder::new().compile(&[peer_service, geyser_service]);
+}
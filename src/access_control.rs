@@ -0,0 +1,188 @@
+use crate::{audit::client_ip, config::Config, error::AppError, AppState};
+use axum::{
+    extract::{ConnectInfo, Request, State},
+    middleware::Next,
+    response::Response,
+};
+use ipnetwork::IpNetwork;
+use std::{
+    net::{IpAddr, SocketAddr},
+    sync::Arc,
+};
+use tracing::warn;
+
+// CIDR allow and deny lists applied to the client address of every request
+#[derive(Debug, Clone, Default)]
+pub struct AccessControlService {
+    blacklist: Vec<IpNetwork>,
+    whitelist: Vec<IpNetwork>,
+}
+
+impl AccessControlService {
+    pub fn new(config: &Config) -> Self {
+        Self {
+            blacklist: config.blacklist_cidrs.clone(),
+            whitelist: config.whitelist_cidrs.clone(),
+        }
+    }
+
+    pub fn is_blacklisted(&self, ip: IpAddr) -> bool {
+        self.blacklist.iter().any(|network| network.contains(ip))
+    }
+
+    // Config validation keeps the lists apart; should they overlap anyway, the blacklist wins
+    pub fn is_whitelisted(&self, ip: IpAddr) -> bool {
+        !self.is_blacklisted(ip) && self.whitelist.iter().any(|network| network.contains(ip))
+    }
+
+    fn is_enabled(&self) -> bool {
+        !self.blacklist.is_empty() || !self.whitelist.is_empty()
+    }
+}
+
+// Marks requests from whitelisted addresses, which the RPC handler doesn't rate limit
+#[derive(Debug, Clone, Copy)]
+pub struct Whitelisted;
+
+// Forwarded headers first, since behind a proxy the socket address is the proxy's
+fn request_ip(request: &Request) -> Option<IpAddr> {
+    client_ip(None, request.headers())
+        .and_then(|ip| ip.parse().ok())
+        .or_else(|| {
+            request.extensions()
+                .get::<ConnectInfo<SocketAddr>>()
+                .map(|ConnectInfo(address)| address.ip())
+        })
+}
+
+pub async fn access_control_middleware(
+    State(state): State<Arc<AppState>>,
+    mut request: Request,
+    next: Next,
+) -> Result<Response, AppError> {
+    if !state.access_control.is_enabled() {
+        return Ok(next.run(request).await);
+    }
+
+    if let Some(ip) = request_ip(&request) {
+        if state.access_control.is_blacklisted(ip) {
+            warn!("Rejected request from blacklisted address {}", ip);
+            return Err(AppError::Forbidden);
+        }
+        if state.access_control.is_whitelisted(ip) {
+            request.extensions_mut().insert(Whitelisted);
+        }
+    }
+
+    Ok(next.run(request).await)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_server::{rpc_result, TestServerBuilder};
+    use reqwest::StatusCode;
+    use serde_json::json;
+    use wiremock::{matchers::method, Mock};
+
+    fn service(blacklist: &[&str], whitelist: &[&str]) -> AccessControlService {
+        let config = Config {
+            blacklist_cidrs: blacklist.iter().map(|cidr| cidr.parse().unwrap()).collect(),
+            whitelist_cidrs: whitelist.iter().map(|cidr| cidr.parse().unwrap()).collect(),
+            ..Config::default()
+        };
+        AccessControlService::new(&config)
+    }
+
+    fn ip(address: &str) -> IpAddr {
+        address.parse().unwrap()
+    }
+
+    #[test]
+    fn ipv4_range_boundaries() {
+        let service = service(&["192.168.1.0/24"], &[]);
+
+        assert!(service.is_blacklisted(ip("192.168.1.0")));
+        assert!(service.is_blacklisted(ip("192.168.1.255")));
+        assert!(!service.is_blacklisted(ip("192.168.0.255")));
+        assert!(!service.is_blacklisted(ip("192.168.2.0")));
+    }
+
+    #[test]
+    fn ipv6_range_boundaries() {
+        let service = service(&[], &["2001:db8::/32"]);
+
+        assert!(service.is_whitelisted(ip("2001:db8::")));
+        assert!(service.is_whitelisted(ip("2001:db8:ffff:ffff:ffff:ffff:ffff:ffff")));
+        assert!(!service.is_whitelisted(ip("2001:db7:ffff:ffff:ffff:ffff:ffff:ffff")));
+        assert!(!service.is_whitelisted(ip("2001:db9::")));
+        // An IPv4 address never falls inside an IPv6 range
+        assert!(!service.is_whitelisted(ip("32.1.13.184")));
+    }
+
+    #[test]
+    fn single_address_and_overlapping_lists() {
+        let service = service(&["10.0.0.7"], &["10.0.0.0/8"]);
+
+        assert!(service.is_blacklisted(ip("10.0.0.7")));
+        assert!(!service.is_blacklisted(ip("10.0.0.8")));
+        assert!(!service.is_whitelisted(ip("10.0.0.7")));
+        assert!(service.is_whitelisted(ip("10.255.255.255")));
+        assert!(!service.is_whitelisted(ip("11.0.0.0")));
+    }
+
+    #[tokio::test]
+    async fn whitelisted_addresses_bypass_rate_limiting() {
+        let server = TestServerBuilder::new()
+            .with_config(|config| {
+                config.rate_limiting.enabled = true;
+                config.rate_limiting.default_rate = 1;
+                config.rate_limiting.default_burst = 1;
+                config.rate_limiting.per_method_limits.clear();
+                config.whitelist_cidrs = vec!["10.1.0.0/16".parse().unwrap()];
+            })
+            .start()
+            .await;
+        Mock::given(method("POST"))
+            .respond_with(rpc_result(json!(42)))
+            .mount(server.endpoint_mock("primary"))
+            .await;
+        let call = |address: &str| {
+            let request = server.client.post(&server.base_url)
+                .header("x-forwarded-for", address)
+                .json(&json!({"jsonrpc": "2.0", "id": 1, "method": "getSlot"}));
+            async move { request.send().await.unwrap().status() }
+        };
+
+        for _ in 0..10 {
+            assert_eq!(call("10.1.255.255").await, StatusCode::OK);
+        }
+        assert_eq!(call("10.2.0.0").await, StatusCode::OK);
+        assert_eq!(call("10.2.0.0").await, StatusCode::TOO_MANY_REQUESTS);
+    }
+
+    #[tokio::test]
+    async fn blacklisted_addresses_are_rejected_before_handlers() {
+        let server = TestServerBuilder::new()
+            .with_config(|config| {
+                config.blacklist_cidrs = vec!["203.0.113.0/24".parse().unwrap(), "2001:db8::/48".parse().unwrap()];
+            })
+            .start()
+            .await;
+
+        for (address, expected) in [
+            ("203.0.113.255", StatusCode::FORBIDDEN),
+            ("203.0.114.0", StatusCode::OK),
+            ("2001:db8:0:ffff::1", StatusCode::FORBIDDEN),
+            ("2001:db8:1::", StatusCode::OK),
+        ] {
+            let response = server.client
+                .get(server.url("/endpoints"))
+                .header("x-forwarded-for", address)
+                .send()
+                .await
+                .unwrap();
+            assert_eq!(response.status(), expected, "{}", address);
+        }
+    }
+}
@@ -1,15 +1,24 @@
 use crate::{
     AppState,
+    config::EndpointConfig,
+    endpoints::EndpointUpdate,
     error::AppError,
-    types::{EndpointInfo, LoadBalancerStats},
+    types::EndpointInfo,
 };
 use askama::Template;
 use axum::{
-    extract::State,
+    extract::{Path, State},
+    http::StatusCode,
     response::Html,
+    Json,
 };
-use std::sync::Arc;
+use serde_json::{json, Value};
+use std::{collections::HashMap, sync::Arc};
 use tracing::info;
+use uuid::Uuid;
+
+// Shown in place of metrics an endpoint hasn't produced yet
+const MISSING_VALUE: &str = "—";
 
 #[derive(Template)]
 #[template(path = "dashboard.html")]
@@ -24,7 +33,62 @@ struct DashboardTemplate {
 #[template(path = "endpoints.html")]
 struct EndpointsTemplate {
     title: String,
-    endpoints: Vec<EndpointInfo>,
+    rows: Vec<EndpointRow>,
+}
+
+// Table body only, fetched by the endpoints page when a health check is forced or endpoints change
+#[derive(Template)]
+#[template(path = "endpoint_rows.html")]
+struct EndpointRowsTemplate {
+    rows: Vec<EndpointRow>,
+}
+
+// Pre-formatted view of an endpoint so the templates don't have to deal with missing data
+struct EndpointRow {
+    id: String,
+    name: String,
+    url: String,
+    status: String,
+    success_rate: String,
+    avg_latency: String,
+    circuit_state: String,
+}
+
+impl EndpointRow {
+    fn new(endpoint: &EndpointInfo, circuit_state: Option<&str>) -> Self {
+        // A zero latency means the endpoint hasn't served a request yet, so the
+        // success rate is meaningless too
+        let has_traffic = endpoint.score.avg_response_time > 0.0;
+
+        Self {
+            id: endpoint.id.to_string(),
+            name: endpoint.name.clone(),
+            url: endpoint.url.clone(),
+            status: endpoint.status.to_string(),
+            success_rate: if has_traffic {
+                format!("{:.1}%", endpoint.score.success_rate)
+            } else {
+                MISSING_VALUE.to_string()
+            },
+            avg_latency: if has_traffic {
+                format!("{:.0}ms", endpoint.score.avg_response_time)
+            } else {
+                MISSING_VALUE.to_string()
+            },
+            circuit_state: circuit_state.unwrap_or("unknown").to_string(),
+        }
+    }
+}
+
+async fn endpoint_rows_for(state: &AppState) -> Vec<EndpointRow> {
+    let mut endpoints = state.endpoint_manager.get_endpoint_info().await;
+    let circuit_states: HashMap<Uuid, &'static str> = state.endpoint_manager.circuit_breaker_states().await;
+
+    // Stable ordering so rows don't jump around between refreshes
+    endpoints.sort_by(|a, b| a.name.cmp(&b.name));
+    endpoints.iter()
+        .map(|endpoint| EndpointRow::new(endpoint, circuit_states.get(&endpoint.id).copied()))
+        .collect()
 }
 
 #[derive(Template)]
@@ -56,16 +120,62 @@ pub async fn dashboard(State(state): State<Arc<AppState>>) -> Result<Html<String
 }
 
 pub async fn endpoints_page(State(state): State<Arc<AppState>>) -> Result<Html<String>, AppError> {
-    let endpoints = state.endpoint_manager.get_endpoint_info().await;
-    
     let template = EndpointsTemplate {
         title: "Endpoints Management".to_string(),
-        endpoints,
+        rows: endpoint_rows_for(&state).await,
     };
     
     Ok(Html(template.render()?))
 }
 
+pub async fn endpoint_rows(State(state): State<Arc<AppState>>) -> Result<Html<String>, AppError> {
+    let template = EndpointRowsTemplate {
+        rows: endpoint_rows_for(&state).await,
+    };
+
+    Ok(Html(template.render()?))
+}
+
+pub async fn force_endpoint_health_check(
+    State(state): State<Arc<AppState>>,
+    Path(endpoint_id): Path<Uuid>,
+) -> Result<Html<String>, AppError> {
+    if state.endpoint_manager.get_endpoint_url(endpoint_id).await.is_none() {
+        return Err(AppError::endpoint("Endpoint not found"));
+    }
+
+    info!("Forcing health check for endpoint {} from admin UI", endpoint_id);
+    state.health_service.force_health_check(Some(endpoint_id)).await;
+    endpoint_rows(State(state)).await
+}
+
+pub async fn add_endpoint(
+    State(state): State<Arc<AppState>>,
+    Json(config): Json<EndpointConfig>,
+) -> Result<(StatusCode, Json<Value>), AppError> {
+    let name = config.name.clone();
+    let id = state.endpoint_manager.add_endpoint(config).await?;
+    info!("Endpoint {} ({}) added from admin API", name, id);
+    Ok((StatusCode::CREATED, Json(json!({"id": id, "name": name}))))
+}
+
+pub async fn remove_endpoint(
+    State(state): State<Arc<AppState>>,
+    Path(endpoint_id): Path<Uuid>,
+) -> Result<StatusCode, AppError> {
+    state.endpoint_manager.remove_endpoint(endpoint_id).await?;
+    info!("Endpoint {} removed from admin API", endpoint_id);
+    Ok(StatusCode::NO_CONTENT)
+}
+
+pub async fn update_endpoint(
+    State(state): State<Arc<AppState>>,
+    Path(endpoint_id): Path<Uuid>,
+    Json(update): Json<EndpointUpdate>,
+) -> Result<Json<EndpointInfo>, AppError> {
+    Ok(Json(state.endpoint_manager.update_endpoint(endpoint_id, update).await?))
+}
+
 pub async fn config_page(State(state): State<Arc<AppState>>) -> Result<Html<String>, AppError> {
     let config = state.endpoint_manager.get_config().await;
     let config_json = serde_json::to_string_pretty(&config)?;
@@ -92,4 +202,147 @@ pub async fn logs_page(_state: State<Arc<AppState>>) -> Result<Html<String>, App
     };
     
     Ok(Html(template.render()?))
-}
\ No newline at end of file
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::types::{EndpointScore, EndpointStatus};
+    use crate::test_server::TestServerBuilder;
+    use chrono::Utc;
+    use scraper::{Html as Document, Selector};
+    use wiremock::{matchers::method, Mock, MockServer, ResponseTemplate};
+
+    fn endpoint(name: &str, status: EndpointStatus, score: EndpointScore) -> EndpointInfo {
+        EndpointInfo {
+            id: Uuid::new_v4(),
+            url: format!("https://{}.example.com", name),
+            name: name.to_string(),
+            status,
+            score,
+            last_checked: Utc::now(),
+            weight: 100,
+            priority: 1,
+            region: None,
+            latitude: None,
+            longitude: None,
+            tls_cert_expires_at: None,
+            tls_cert_days_remaining: None,
+            slot_lag: None,
+            slot_lag_priority: None,
+        }
+    }
+
+    fn select<'a>(document: &'a Document, selector: &str) -> Vec<scraper::ElementRef<'a>> {
+        document.select(&Selector::parse(selector).unwrap()).collect()
+    }
+
+    #[test]
+    fn test_endpoints_page_renders_live_table() {
+        let busy = endpoint("busy", EndpointStatus::Healthy, EndpointScore {
+            success_rate: 99.5,
+            avg_response_time: 42.4,
+            ..EndpointScore::default()
+        });
+        let fresh = endpoint("fresh", EndpointStatus::Unknown, EndpointScore::default());
+
+        let html = EndpointsTemplate {
+            title: "Endpoints Management".to_string(),
+            rows: vec![
+                EndpointRow::new(&busy, Some("closed")),
+                EndpointRow::new(&fresh, None),
+            ],
+        }.render().unwrap();
+        let document = Document::parse_document(&html);
+
+        let body = &select(&document, "tbody#endpoint-rows")[0];
+        assert_eq!(body.value().attr("data-events"), Some("/events/endpoints"));
+        assert!(body.value().attr("hx-trigger").is_none());
+        assert_eq!(select(&document, "tbody#top-methods").len(), 1);
+        assert_eq!(select(&document, ".legend .badge").len(), 4);
+
+        let rows = select(&document, "tr.endpoint-row");
+        assert_eq!(rows.len(), 2);
+
+        let busy_row = &select(&document, &format!("tr#endpoint-{}", busy.id))[0];
+        let text = |row: &scraper::ElementRef, selector: &str| {
+            row.select(&Selector::parse(selector).unwrap()).next().unwrap().text().collect::<String>()
+        };
+        assert_eq!(text(busy_row, ".endpoint-name"), "busy");
+        assert_eq!(text(busy_row, ".badge.status-healthy"), "healthy");
+        assert_eq!(text(busy_row, ".endpoint-success-rate"), "99.5%");
+        assert_eq!(text(busy_row, ".endpoint-latency"), "42ms");
+        assert_eq!(text(busy_row, ".circuit-closed"), "closed");
+
+        let button = busy_row.select(&Selector::parse("button").unwrap()).next().unwrap();
+        assert_eq!(
+            button.value().attr("hx-post"),
+            Some(format!("/admin/endpoints/{}/health-check", busy.id).as_str())
+        );
+
+        // Endpoints without traffic or breaker state still render
+        let fresh_row = &select(&document, &format!("tr#endpoint-{}", fresh.id))[0];
+        assert_eq!(text(fresh_row, ".endpoint-success-rate"), MISSING_VALUE);
+        assert_eq!(text(fresh_row, ".endpoint-latency"), MISSING_VALUE);
+        assert_eq!(text(fresh_row, ".circuit-unknown"), "unknown");
+    }
+
+    #[test]
+    fn test_endpoint_rows_render_empty_state() {
+        let html = EndpointRowsTemplate { rows: Vec::new() }.render().unwrap();
+        let document = Document::parse_fragment(&format!("<table>{}</table>", html));
+        assert!(select(&document, "tr.endpoint-row").is_empty());
+        assert_eq!(select(&document, "td.empty").len(), 1);
+    }
+
+    #[tokio::test]
+    async fn test_endpoints_added_updated_and_removed_at_runtime() {
+        let server = TestServerBuilder::new().start().await;
+        let added = MockServer::start().await;
+        Mock::given(method("POST"))
+            .respond_with(ResponseTemplate::new(200).set_body_json(json!({"jsonrpc": "2.0", "id": 1, "result": 77})))
+            .mount(&added)
+            .await;
+        let endpoint = |name: &str, url: &str, weight: u32| json!({
+            "url": url, "name": name, "weight": weight, "priority": 2, "features": ["full"]
+        });
+
+        let response = server.client.post(server.url("/admin/endpoints"))
+            .json(&endpoint("added", &added.uri(), 50))
+            .send().await.unwrap();
+        assert_eq!(response.status(), reqwest::StatusCode::CREATED);
+        let id = response.json::<Value>().await.unwrap()["id"].as_str().unwrap().to_string();
+
+        for (name, url, weight) in [("bad-url", "not a url", 50), ("no-weight", "http://127.0.0.1:9", 0)] {
+            let rejected = server.client.post(server.url("/admin/endpoints"))
+                .json(&endpoint(name, url, weight))
+                .send().await.unwrap();
+            assert_eq!(rejected.status(), reqwest::StatusCode::BAD_REQUEST);
+        }
+
+        let updated = server.client.patch(server.url(&format!("/admin/endpoints/{}", id)))
+            .json(&json!({"weight": 7, "priority": 9}))
+            .send().await.unwrap();
+        let info: Value = updated.json().await.unwrap();
+        assert_eq!((info["weight"].as_u64(), info["priority"].as_u64()), (Some(7), Some(9)));
+        let zero = server.client.patch(server.url(&format!("/admin/endpoints/{}", id)))
+            .json(&json!({"weight": 0}))
+            .send().await.unwrap();
+        assert_eq!(zero.status(), reqwest::StatusCode::BAD_REQUEST);
+
+        // With the original endpoint gone, traffic goes to the one added at runtime
+        let primary = server.state.endpoint_manager.get_endpoint_info().await.into_iter()
+            .find(|e| e.name == "primary").unwrap();
+        let removed = server.client.delete(server.url(&format!("/admin/endpoints/{}", primary.id)))
+            .send().await.unwrap();
+        assert_eq!(removed.status(), reqwest::StatusCode::NO_CONTENT);
+        let body: Value = server.rpc(json!({"jsonrpc": "2.0", "id": 1, "method": "getSlot"})).await
+            .json().await.unwrap();
+        assert_eq!(body["result"], 77);
+
+        let config = server.state.endpoint_manager.current_config().await;
+        let names: Vec<&str> = config.endpoints.iter().map(|e| e.name.as_str()).collect();
+        assert_eq!(names, vec!["added"]);
+        assert_eq!(config.endpoints[0].weight, 7);
+    }
+}
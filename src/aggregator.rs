@@ -0,0 +1,197 @@
+use crate::{
+    config::BatchSplitConfig,
+    error::AppError,
+    types::RpcRequest,
+};
+use futures::{stream, Future, StreamExt};
+use serde_json::Value;
+use std::collections::HashMap;
+
+// Upper bound on sub-requests in flight for a single split call
+pub const MAX_CONCURRENT_SPLITS: usize = 5;
+
+// Splits list-style calls such as getMultipleAccounts that exceed an endpoint's
+// per-call limit, and stitches the partial responses back together in order
+#[derive(Debug, Clone, Default)]
+pub struct ResponseAggregator {
+    split_configs: HashMap<String, BatchSplitConfig>,
+    max_concurrent_splits: usize,
+}
+
+impl ResponseAggregator {
+    pub fn new(split_configs: HashMap<String, BatchSplitConfig>) -> Self {
+        Self {
+            split_configs,
+            max_concurrent_splits: MAX_CONCURRENT_SPLITS,
+        }
+    }
+
+    // Returns the sub-requests to send, or None if the request fits in a single call
+    pub fn split(&self, rpc_request: &RpcRequest) -> Option<Vec<RpcRequest>> {
+        let config = self.split_configs.get(&rpc_request.method)?;
+        let max_items = config.max_items_per_call.max(1);
+
+        let params = rpc_request.params.as_ref()?.as_array()?;
+        let items = params.first()?.as_array()?;
+        if items.len() <= max_items {
+            return None;
+        }
+
+        let sub_requests = items.chunks(max_items)
+            .map(|chunk| {
+                let mut sub_params = params.clone();
+                sub_params[0] = Value::Array(chunk.to_vec());
+                RpcRequest {
+                    id: rpc_request.id.clone(),
+                    method: rpc_request.method.clone(),
+                    params: Some(Value::Array(sub_params)),
+                    jsonrpc: rpc_request.jsonrpc.clone(),
+                }
+            })
+            .collect();
+        Some(sub_requests)
+    }
+
+    // Runs the sub-requests with bounded concurrency, keeping responses in request order
+    pub async fn dispatch<F, Fut>(&self, sub_requests: Vec<RpcRequest>, send: F) -> Result<Vec<Value>, AppError>
+    where
+        F: Fn(RpcRequest) -> Fut,
+        Fut: Future<Output = Result<Value, AppError>>,
+    {
+        stream::iter(sub_requests.into_iter().map(send))
+            .buffered(self.max_concurrent_splits.max(1))
+            .collect::<Vec<_>>()
+            .await
+            .into_iter()
+            .collect()
+    }
+
+    // Concatenates each response's `result.value` array. The first upstream error is
+    // returned as-is since a partial account list would be silently wrong.
+    pub fn merge(id: Option<Value>, responses: Vec<Value>) -> Result<Value, AppError> {
+        let mut responses = responses.into_iter();
+        let mut merged = responses.next()
+            .ok_or_else(|| AppError::internal("No responses to merge"))?;
+
+        if merged.get("error").is_none() {
+            for response in responses {
+                if response.get("error").is_some() {
+                    merged = response;
+                    break;
+                }
+
+                let values = response.pointer("/result/value")
+                    .and_then(|v| v.as_array())
+                    .ok_or_else(|| AppError::endpoint("Split response is missing result.value"))?;
+                merged.pointer_mut("/result/value")
+                    .and_then(|v| v.as_array_mut())
+                    .ok_or_else(|| AppError::endpoint("Split response is missing result.value"))?
+                    .extend(values.iter().cloned());
+
+                // Report the oldest slot any part of the merged data came from
+                let slot = response.pointer("/result/context/slot").and_then(|s| s.as_u64());
+                if let (Some(slot), Some(merged_slot)) = (slot, merged.pointer_mut("/result/context/slot")) {
+                    if merged_slot.as_u64().is_some_and(|current| slot < current) {
+                        *merged_slot = Value::from(slot);
+                    }
+                }
+            }
+        }
+
+        if let Some(obj) = merged.as_object_mut() {
+            obj.insert("id".to_string(), id.unwrap_or(Value::Null));
+        }
+        Ok(merged)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_server::TestServerBuilder;
+    use serde_json::json;
+    use wiremock::{matchers::method, Mock, Request, Respond, ResponseTemplate};
+
+    // Mimics a provider that rejects getMultipleAccounts calls over 100 accounts
+    struct LimitedEndpoint;
+
+    impl Respond for LimitedEndpoint {
+        fn respond(&self, request: &Request) -> ResponseTemplate {
+            let body: Value = serde_json::from_slice(&request.body).unwrap();
+            let accounts = body["params"][0].as_array().cloned().unwrap_or_default();
+
+            if accounts.len() > 100 {
+                return ResponseTemplate::new(200).set_body_json(json!({
+                    "jsonrpc": "2.0",
+                    "id": body["id"],
+                    "error": {"code": -32602, "message": "Too many accounts requested"}
+                }));
+            }
+
+            let value: Vec<Value> = accounts.iter()
+                .map(|account| json!({"owner": account, "lamports": 1}))
+                .collect();
+            ResponseTemplate::new(200).set_body_json(json!({
+                "jsonrpc": "2.0",
+                "id": body["id"],
+                "result": {"context": {"slot": 1000 + accounts.len()}, "value": value}
+            }))
+        }
+    }
+
+    #[tokio::test]
+    async fn test_large_get_multiple_accounts_is_split_and_merged() {
+        let server = TestServerBuilder::new()
+            .with_config(|config| {
+                config.batch_splitting.insert(
+                    "getMultipleAccounts".to_string(),
+                    BatchSplitConfig { max_items_per_call: 100 },
+                );
+            })
+            .start()
+            .await;
+        Mock::given(method("POST"))
+            .respond_with(LimitedEndpoint)
+            .mount(server.endpoint_mock("primary"))
+            .await;
+
+        let accounts: Vec<String> = (0..250).map(|i| format!("Account{}", i)).collect();
+        let response: Value = server.rpc(json!({
+            "jsonrpc": "2.0",
+            "id": 42,
+            "method": "getMultipleAccounts",
+            "params": [accounts, {"encoding": "base64"}]
+        })).await.json().await.unwrap();
+
+        assert_eq!(response["id"], 42);
+        assert!(response.get("error").is_none(), "unexpected error: {}", response);
+        let value = response["result"]["value"].as_array().unwrap();
+        assert_eq!(value.len(), 250);
+        for (i, account) in value.iter().enumerate() {
+            assert_eq!(account["owner"], format!("Account{}", i));
+        }
+        // 100 + 100 + 50: the smallest chunk reports the oldest slot
+        assert_eq!(response["result"]["context"]["slot"], 1050);
+
+        let sub_requests = server.endpoint_mock("primary").received_requests().await.unwrap();
+        assert_eq!(sub_requests.len(), 3);
+    }
+
+    #[test]
+    fn test_small_requests_are_not_split() {
+        let mut configs = HashMap::new();
+        configs.insert("getMultipleAccounts".to_string(), BatchSplitConfig { max_items_per_call: 100 });
+        let aggregator = ResponseAggregator::new(configs);
+
+        let request = |method: &str, count: usize| RpcRequest {
+            id: Some(json!(1)),
+            method: method.to_string(),
+            params: Some(json!([vec!["Account"; count]])),
+            jsonrpc: "2.0".to_string(),
+        };
+
+        assert!(aggregator.split(&request("getMultipleAccounts", 100)).is_none());
+        assert!(aggregator.split(&request("getProgramAccounts", 500)).is_none());
+        assert_eq!(aggregator.split(&request("getMultipleAccounts", 101)).unwrap().len(), 2);
+    }
+}
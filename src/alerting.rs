@@ -0,0 +1,207 @@
+use crate::{
+    config::{AlertingConfig, Config},
+    error::AppError,
+    types::EndpointStatus,
+    AppState,
+};
+use axum::{
+    extract::State,
+    http::header,
+    response::{IntoResponse, Response},
+};
+use serde::Serialize;
+use serde_json::Value;
+use std::{collections::BTreeMap, sync::Arc};
+use yaml_rust2::{yaml::Hash, Yaml, YamlEmitter};
+
+pub const ALERT_RULES_CONTENT_TYPE: &str = "application/yaml";
+const RULE_GROUP: &str = "multi-rpc";
+// Range for the rate() windows in the expressions
+const RATE_WINDOW: &str = "5m";
+
+#[derive(Debug, Serialize)]
+struct RuleFile {
+    groups: Vec<RuleGroup>,
+}
+
+#[derive(Debug, Serialize)]
+struct RuleGroup {
+    name: &'static str,
+    rules: Vec<AlertRule>,
+}
+
+#[derive(Debug, Serialize)]
+struct AlertRule {
+    alert: &'static str,
+    expr: String,
+    #[serde(rename = "for")]
+    pending_for: String,
+    labels: BTreeMap<&'static str, &'static str>,
+    annotations: BTreeMap<&'static str, String>,
+}
+
+impl AlertRule {
+    fn new(alert: &'static str, expr: String, pending_for: String, severity: &'static str, summary: String) -> Self {
+        Self {
+            alert,
+            expr,
+            pending_for,
+            labels: BTreeMap::from([("severity", severity)]),
+            annotations: BTreeMap::from([("summary", summary)]),
+        }
+    }
+}
+
+fn alert_rules(config: &Config) -> RuleFile {
+    let AlertingConfig { min_healthy_ratio, circuit_breaker_open_secs, min_cache_hit_rate, p95_latency_sla_ms } =
+        config.alerting;
+
+    let mut rules = vec![
+        AlertRule::new(
+            "MultiRpcEndpointHealthLow",
+            format!("multi_rpc_endpoints_healthy / multi_rpc_endpoints_total < {}", min_healthy_ratio),
+            "2m".to_string(),
+            "critical",
+            format!("Fewer than {}% of RPC endpoints are healthy", min_healthy_ratio * 100.0),
+        ),
+        AlertRule::new(
+            "MultiRpcCircuitBreakerOpen",
+            "multi_rpc_endpoint_circuit_breaker_open == 1".to_string(),
+            format!("{}s", circuit_breaker_open_secs),
+            "warning",
+            format!("Circuit breaker for {{{{ $labels.endpoint }}}} open for over {}s", circuit_breaker_open_secs),
+        ),
+    ];
+    if config.cache.enabled {
+        let hits = format!("sum(rate(multi_rpc_cache_hits_total[{}]))", RATE_WINDOW);
+        let misses = format!("sum(rate(multi_rpc_cache_misses_total[{}]))", RATE_WINDOW);
+        rules.push(AlertRule::new(
+            "MultiRpcCacheHitRateLow",
+            format!("{} / ({} + {}) < {}", hits, hits, misses, min_cache_hit_rate),
+            "10m".to_string(),
+            "warning",
+            format!("Cache hit rate below {}%", min_cache_hit_rate * 100.0),
+        ));
+    }
+    rules.push(AlertRule::new(
+        "MultiRpcP95LatencyHigh",
+        format!(
+            "histogram_quantile(0.95, sum by (le) (rate(multi_rpc_request_duration_seconds_bucket[{}]))) > {}",
+            RATE_WINDOW,
+            p95_latency_sla_ms as f64 / 1000.0,
+        ),
+        "5m".to_string(),
+        "warning",
+        format!("P95 upstream latency above the {}ms SLA", p95_latency_sla_ms),
+    ));
+
+    RuleFile {
+        groups: vec![RuleGroup { name: RULE_GROUP, rules }],
+    }
+}
+
+fn to_yaml(value: &Value) -> Yaml {
+    match value {
+        Value::Null => Yaml::Null,
+        Value::Bool(b) => Yaml::Boolean(*b),
+        Value::Number(n) => match n.as_i64() {
+            Some(i) => Yaml::Integer(i),
+            None => Yaml::Real(n.to_string()),
+        },
+        Value::String(s) => Yaml::String(s.clone()),
+        Value::Array(items) => Yaml::Array(items.iter().map(to_yaml).collect()),
+        Value::Object(fields) => Yaml::Hash(
+            fields.iter()
+                .map(|(key, value)| (Yaml::String(key.clone()), to_yaml(value)))
+                .collect::<Hash>(),
+        ),
+    }
+}
+
+// A document that can be dropped into a Prometheus rule_files entry as-is
+pub fn alert_rules_yaml(config: &Config) -> Result<String, AppError> {
+    let rules = serde_json::to_value(alert_rules(config))?;
+    let mut yaml = String::new();
+    YamlEmitter::new(&mut yaml)
+        .dump(&to_yaml(&rules))
+        .map_err(|e| AppError::internal(&format!("Failed to write alert rules: {}", e)))?;
+    yaml.push('\n');
+    Ok(yaml)
+}
+
+// Nothing else keeps these gauges current, so they are brought up to date on every scrape
+pub async fn refresh_endpoint_gauges(state: &AppState) {
+    let endpoints = state.endpoint_manager.get_endpoint_info().await;
+    let healthy = endpoints.iter().filter(|e| e.status == EndpointStatus::Healthy).count();
+    state.metrics_service.update_endpoint_health(healthy, endpoints.len()).await;
+
+    let breaker_states = state.endpoint_manager.circuit_breaker_states().await;
+    for endpoint in &endpoints {
+        let open = breaker_states.get(&endpoint.id).is_some_and(|state| *state == "open");
+        state.metrics_service.update_circuit_breaker_open(&endpoint.name, open);
+    }
+}
+
+pub async fn handle_alert_rules(State(state): State<Arc<AppState>>) -> Result<Response, AppError> {
+    let config = state.endpoint_manager.current_config().await;
+    let yaml = alert_rules_yaml(&config)?;
+    Ok(([(header::CONTENT_TYPE, ALERT_RULES_CONTENT_TYPE)], yaml).into_response())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use yaml_rust2::YamlLoader;
+
+    fn parse_rules(config: &Config) -> Vec<Yaml> {
+        let documents = YamlLoader::load_from_str(&alert_rules_yaml(config).unwrap()).unwrap();
+        assert_eq!(documents.len(), 1);
+        let group = &documents[0]["groups"][0];
+        assert_eq!(group["name"].as_str(), Some(RULE_GROUP));
+        group["rules"].as_vec().unwrap().clone()
+    }
+
+    fn rule<'a>(rules: &'a [Yaml], alert: &str) -> &'a Yaml {
+        rules.iter()
+            .find(|rule| rule["alert"].as_str() == Some(alert))
+            .unwrap_or_else(|| panic!("no {} rule", alert))
+    }
+
+    // Every expression ends in its comparison against the threshold
+    fn threshold(rule: &Yaml) -> f64 {
+        rule["expr"].as_str().unwrap().rsplit(' ').next().unwrap().parse().unwrap()
+    }
+
+    #[test]
+    fn test_thresholds_come_from_config() {
+        let mut config = Config::default();
+        config.cache.enabled = true;
+        config.alerting = AlertingConfig {
+            min_healthy_ratio: 0.75,
+            circuit_breaker_open_secs: 90,
+            min_cache_hit_rate: 0.35,
+            p95_latency_sla_ms: 250,
+        };
+
+        let rules = parse_rules(&config);
+        assert_eq!(rules.len(), 4);
+        assert_eq!(threshold(rule(&rules, "MultiRpcEndpointHealthLow")), 0.75);
+        assert_eq!(rule(&rules, "MultiRpcCircuitBreakerOpen")["for"].as_str(), Some("90s"));
+        assert_eq!(threshold(rule(&rules, "MultiRpcCacheHitRateLow")), 0.35);
+        assert_eq!(threshold(rule(&rules, "MultiRpcP95LatencyHigh")), 0.25);
+        for rule in &rules {
+            assert!(rule["labels"]["severity"].as_str().is_some());
+            assert!(rule["annotations"]["summary"].as_str().is_some());
+        }
+    }
+
+    #[test]
+    fn test_no_cache_rule_without_cache() {
+        let mut config = Config::default();
+        config.cache.enabled = false;
+
+        let rules = parse_rules(&config);
+        assert_eq!(rules.len(), 3);
+        assert!(rules.iter().all(|rule| rule["alert"].as_str() != Some("MultiRpcCacheHitRateLow")));
+    }
+}
@@ -0,0 +1,129 @@
+use crate::{
+    config::LatencyAnomalyConfig,
+    types::{EndpointLatencyAnomaly, EndpointStats},
+};
+use serde_json::json;
+use tracing::{debug, warn};
+use uuid::Uuid;
+
+// Keeps a perfectly steady endpoint from alerting on sub-millisecond jitter
+const MIN_STD_MS: f64 = 1.0;
+
+// Flags latency spikes per endpoint using an exponentially weighted mean and variance
+#[derive(Debug, Clone)]
+pub struct LatencyAnomalyDetector {
+    config: LatencyAnomalyConfig,
+    webhook_client: reqwest::Client,
+}
+
+impl LatencyAnomalyDetector {
+    pub fn new(config: LatencyAnomalyConfig) -> Self {
+        Self {
+            config,
+            webhook_client: reqwest::Client::new(),
+        }
+    }
+
+    // Checks the sample against the current baseline, then folds it into the EMA.
+    // Uses the exponentially weighted form of Welford's update so the variance stays
+    // numerically stable without keeping a sample window around.
+    pub fn observe(&self, endpoint_id: Uuid, stats: &mut EndpointStats, sample_ms: f64) -> Option<EndpointLatencyAnomaly> {
+        let warmed_up = stats.latency_samples >= u64::from(self.config.window_size.max(1));
+        let ema_ms = stats.latency_ema_ms;
+        let std_ms = stats.latency_variance.sqrt().max(MIN_STD_MS);
+
+        let anomaly = if warmed_up && sample_ms > ema_ms + self.config.alert_threshold_multiplier * std_ms {
+            Some(EndpointLatencyAnomaly {
+                endpoint_id,
+                current_ms: sample_ms,
+                ema_ms,
+                std_ms,
+            })
+        } else {
+            if warmed_up && sample_ms > ema_ms + self.config.sensitivity * std_ms {
+                debug!("Elevated latency on endpoint {}: {:.1}ms (ema {:.1}ms, std {:.1}ms)",
+                    endpoint_id, sample_ms, ema_ms, std_ms);
+            }
+            None
+        };
+
+        if stats.latency_samples == 0 {
+            stats.latency_ema_ms = sample_ms;
+            stats.latency_variance = 0.0;
+        } else {
+            let alpha = 2.0 / (f64::from(self.config.window_size.max(1)) + 1.0);
+            let diff = sample_ms - stats.latency_ema_ms;
+            let increment = alpha * diff;
+            stats.latency_ema_ms += increment;
+            stats.latency_variance = (1.0 - alpha) * (stats.latency_variance + diff * increment);
+        }
+        stats.latency_samples += 1;
+
+        if let Some(anomaly) = &anomaly {
+            self.alert(anomaly);
+        }
+        anomaly
+    }
+
+    fn alert(&self, anomaly: &EndpointLatencyAnomaly) {
+        warn!("Latency anomaly on endpoint {}: {:.1}ms vs ema {:.1}ms (std {:.1}ms)",
+            anomaly.endpoint_id, anomaly.current_ms, anomaly.ema_ms, anomaly.std_ms);
+
+        let Some(webhook_url) = self.config.webhook_url.clone() else {
+            return;
+        };
+        let client = self.webhook_client.clone();
+        let payload = json!({
+            "event": "EndpointLatencyAnomaly",
+            "data": anomaly,
+        });
+
+        tokio::spawn(async move {
+            if let Err(e) = client.post(&webhook_url).json(&payload).send().await {
+                warn!("Failed to deliver latency anomaly webhook to {}: {}", webhook_url, e);
+            }
+        });
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn detector() -> LatencyAnomalyDetector {
+        LatencyAnomalyDetector::new(LatencyAnomalyConfig {
+            sensitivity: 2.0,
+            window_size: 10,
+            alert_threshold_multiplier: 4.0,
+            webhook_url: None,
+        })
+    }
+
+    #[test]
+    fn test_sudden_latency_spike_is_detected() {
+        let detector = detector();
+        let endpoint_id = Uuid::new_v4();
+        let mut stats = EndpointStats::default();
+
+        // Steady baseline around 50ms with a little jitter
+        for i in 0..50 {
+            let sample = 50.0 + (i % 5) as f64;
+            assert!(detector.observe(endpoint_id, &mut stats, sample).is_none());
+        }
+
+        let anomaly = detector.observe(endpoint_id, &mut stats, 520.0).expect("10x spike should alert");
+        assert_eq!(anomaly.endpoint_id, endpoint_id);
+        assert_eq!(anomaly.current_ms, 520.0);
+        assert!((anomaly.ema_ms - 52.0).abs() < 2.0);
+    }
+
+    #[test]
+    fn test_no_alert_during_warm_up() {
+        let detector = detector();
+        let mut stats = EndpointStats::default();
+
+        detector.observe(Uuid::new_v4(), &mut stats, 50.0);
+        assert!(detector.observe(Uuid::new_v4(), &mut stats, 5_000.0).is_none());
+        assert_eq!(stats.latency_samples, 2);
+    }
+}
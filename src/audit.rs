@@ -0,0 +1,374 @@
+use crate::{auth::AuthContext, config::AuditConfig, error::AppError, AppState};
+use axum::{
+    extract::{Query, State},
+    http::HeaderMap,
+    Json,
+};
+use chrono::{DateTime, TimeZone, Utc};
+use serde::{Deserialize, Serialize};
+use serde_json::{json, Value};
+use sha2::{Digest, Sha256};
+use sqlx::{
+    sqlite::{SqliteArguments, SqliteConnectOptions, SqlitePool, SqlitePoolOptions, SqliteRow},
+    QueryBuilder, Row, Sqlite,
+};
+use std::sync::{
+    atomic::{AtomicU64, Ordering},
+    Arc,
+};
+use tokio::sync::mpsc;
+use tracing::{debug, error, info, warn};
+
+const DEFAULT_QUERY_LIMIT: u32 = 100;
+const MAX_QUERY_LIMIT: u32 = 10_000;
+
+// One inbound RPC call as stored in the audit database
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct AuditRecord {
+    pub timestamp: DateTime<Utc>,
+    pub client_ip: Option<String>,
+    // Fingerprint from api_key_id, stored in the api_key column; the key itself is never kept
+    pub api_key_id: Option<String>,
+    pub method: String,
+    // SHA-256 of the params JSON, so the log shows repeats without storing request contents
+    pub params_hash: String,
+    pub status: u16,
+    pub latency_ms: u64,
+}
+
+#[derive(Debug, Default, Deserialize)]
+pub struct AuditQuery {
+    pub from: Option<DateTime<Utc>>,
+    pub to: Option<DateTime<Utc>>,
+    pub method: Option<String>,
+    pub limit: Option<u32>,
+}
+
+// Persists every RPC request to SQLite. Rows go through a bounded channel to a single writer
+// task, so logging never waits on the database; when the channel is full the row is dropped.
+#[derive(Debug)]
+pub struct AuditService {
+    pool: SqlitePool,
+    sender: mpsc::Sender<AuditRecord>,
+    dropped: Arc<AtomicU64>,
+}
+
+impl AuditService {
+    pub async fn new(config: &AuditConfig) -> Result<Self, AppError> {
+        let options = SqliteConnectOptions::new()
+            .filename(&config.db_path)
+            .create_if_missing(true);
+        let pool = SqlitePoolOptions::new()
+            .max_connections(4)
+            .connect_with(options)
+            .await?;
+
+        sqlx::query(
+            "CREATE TABLE IF NOT EXISTS rpc_audit (
+                id INTEGER PRIMARY KEY AUTOINCREMENT,
+                timestamp_ms INTEGER NOT NULL,
+                client_ip TEXT,
+                api_key TEXT,
+                method TEXT NOT NULL,
+                params_hash TEXT NOT NULL,
+                status INTEGER NOT NULL,
+                latency_ms INTEGER NOT NULL
+            )",
+        )
+        .execute(&pool)
+        .await?;
+        sqlx::query("CREATE INDEX IF NOT EXISTS rpc_audit_timestamp ON rpc_audit (timestamp_ms)")
+            .execute(&pool)
+            .await?;
+
+        let (sender, receiver) = mpsc::channel(config.channel_capacity.max(1));
+        tokio::spawn(write_records(pool.clone(), receiver));
+        info!("Audit log writing to {}", config.db_path);
+
+        Ok(Self {
+            pool,
+            sender,
+            dropped: Arc::new(AtomicU64::new(0)),
+        })
+    }
+
+    pub async fn log_request(&self, record: AuditRecord) {
+        if self.sender.try_send(record).is_err() {
+            let dropped = self.dropped.fetch_add(1, Ordering::Relaxed) + 1;
+            if dropped.is_power_of_two() {
+                warn!("Audit log channel full, {} records dropped so far", dropped);
+            }
+        }
+    }
+
+    // Newest first
+    pub async fn query(&self, query: &AuditQuery) -> Result<Vec<AuditRecord>, AppError> {
+        let mut builder = QueryBuilder::<Sqlite>::new(
+            "SELECT timestamp_ms, client_ip, api_key, method, params_hash, status, latency_ms FROM rpc_audit WHERE 1 = 1",
+        );
+        if let Some(from) = query.from {
+            builder.push(" AND timestamp_ms >= ").push_bind(from.timestamp_millis());
+        }
+        if let Some(to) = query.to {
+            builder.push(" AND timestamp_ms <= ").push_bind(to.timestamp_millis());
+        }
+        if let Some(method) = &query.method {
+            builder.push(" AND method = ").push_bind(method.clone());
+        }
+        let limit = query.limit.unwrap_or(DEFAULT_QUERY_LIMIT).min(MAX_QUERY_LIMIT);
+        builder.push(" ORDER BY timestamp_ms DESC, id DESC LIMIT ").push_bind(i64::from(limit));
+
+        let rows = builder.build().fetch_all(&self.pool).await?;
+        rows.iter().map(record_from_row).collect()
+    }
+
+    // The whole log, oldest first, for state export
+    pub async fn export_records(&self) -> Result<Vec<AuditRecord>, AppError> {
+        let rows = sqlx::query(
+            "SELECT timestamp_ms, client_ip, api_key, method, params_hash, status, latency_ms FROM rpc_audit ORDER BY timestamp_ms, id",
+        )
+        .fetch_all(&self.pool)
+        .await?;
+        rows.iter().map(record_from_row).collect()
+    }
+
+    // Appends to the log in one transaction, so a failed import leaves it as it was. Existing
+    // records are never removed, so an import can't erase the local trail.
+    pub async fn import_records(&self, records: &[AuditRecord]) -> Result<(), AppError> {
+        let mut transaction = self.pool.begin().await?;
+        for record in records {
+            insert_record(record).execute(&mut transaction).await?;
+        }
+        transaction.commit().await?;
+        info!("Imported {} audit records", records.len());
+        Ok(())
+    }
+
+    pub fn dropped_records(&self) -> u64 {
+        self.dropped.load(Ordering::Relaxed)
+    }
+}
+
+// Runs until every sender is gone, i.e. until the service is dropped
+async fn write_records(pool: SqlitePool, mut receiver: mpsc::Receiver<AuditRecord>) {
+    while let Some(record) = receiver.recv().await {
+        if let Err(e) = insert_record(&record).execute(&pool).await {
+            error!("Failed to write audit record for {}: {}", record.method, AppError::from(e));
+        }
+    }
+    debug!("Audit log writer stopped");
+}
+
+fn insert_record(record: &AuditRecord) -> sqlx::query::Query<'_, Sqlite, SqliteArguments<'_>> {
+    sqlx::query(
+        "INSERT INTO rpc_audit (timestamp_ms, client_ip, api_key, method, params_hash, status, latency_ms)
+         VALUES (?, ?, ?, ?, ?, ?, ?)",
+    )
+    .bind(record.timestamp.timestamp_millis())
+    .bind(&record.client_ip)
+    .bind(&record.api_key_id)
+    .bind(&record.method)
+    .bind(&record.params_hash)
+    .bind(i64::from(record.status))
+    .bind(record.latency_ms as i64)
+}
+
+fn record_from_row(row: &SqliteRow) -> Result<AuditRecord, AppError> {
+    let timestamp_ms: i64 = row.try_get("timestamp_ms")?;
+    let status: i64 = row.try_get("status")?;
+    let latency_ms: i64 = row.try_get("latency_ms")?;
+    Ok(AuditRecord {
+        timestamp: Utc.timestamp_millis_opt(timestamp_ms).single().unwrap_or_default(),
+        client_ip: row.try_get("client_ip")?,
+        api_key_id: row.try_get("api_key")?,
+        method: row.try_get("method")?,
+        params_hash: row.try_get("params_hash")?,
+        status: status as u16,
+        latency_ms: latency_ms.max(0) as u64,
+    })
+}
+
+// (method, params hash) for each call in a single or batch payload
+pub fn audited_calls(payload: &Value) -> Vec<(String, String)> {
+    let calls = match payload {
+        Value::Array(calls) => calls.iter().collect(),
+        call => vec![call],
+    };
+    calls.into_iter()
+        .map(|call| {
+            let method = call.get("method").and_then(Value::as_str).unwrap_or("unknown").to_string();
+            let params = call.get("params").cloned().unwrap_or(Value::Null);
+            (method, params_hash(&params))
+        })
+        .collect()
+}
+
+pub fn params_hash(params: &Value) -> String {
+    format!("{:x}", Sha256::digest(params.to_string().as_bytes()))
+}
+
+// Identifies which key made a call without letting anyone who reads the log reuse it
+pub fn api_key_id(api_key: &str) -> String {
+    let digest = format!("{:x}", Sha256::digest(api_key.as_bytes()));
+    format!("sha256:{}", &digest[..16])
+}
+
+// The address auth already resolved, or the first forwarded address when auth is off
+pub fn client_ip(auth: Option<&AuthContext>, headers: &HeaderMap) -> Option<String> {
+    auth.and_then(|context| context.ip_address.clone())
+        .or_else(|| {
+            ["x-forwarded-for", "x-real-ip"].iter()
+                .filter_map(|name| headers.get(*name))
+                .filter_map(|value| value.to_str().ok())
+                .map(|value| value.split(',').next().unwrap_or("").trim().to_string())
+                .find(|value| !value.is_empty())
+        })
+}
+
+pub async fn handle_audit_query(
+    State(state): State<Arc<AppState>>,
+    Query(query): Query<AuditQuery>,
+) -> Result<Json<Value>, AppError> {
+    let audit = state.audit_service.as_ref()
+        .ok_or_else(|| AppError::config("Audit log is disabled"))?;
+    let entries = audit.query(&query).await?;
+    Ok(Json(json!({
+        "count": entries.len(),
+        "dropped": audit.dropped_records(),
+        "entries": entries,
+    })))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::time::Duration;
+
+    async fn service() -> (AuditService, std::path::PathBuf) {
+        let path = std::env::temp_dir().join(format!("multi-rpc-audit-{}.db", uuid::Uuid::new_v4()));
+        let config = AuditConfig {
+            enabled: true,
+            db_path: path.to_string_lossy().to_string(),
+            channel_capacity: 100,
+        };
+        (AuditService::new(&config).await.unwrap(), path)
+    }
+
+    fn record(method: &str, seconds_ago: i64) -> AuditRecord {
+        AuditRecord {
+            timestamp: Utc::now() - chrono::Duration::seconds(seconds_ago),
+            client_ip: Some("203.0.113.9".to_string()),
+            api_key_id: None,
+            method: method.to_string(),
+            params_hash: params_hash(&json!(["Account1"])),
+            status: 200,
+            latency_ms: 12,
+        }
+    }
+
+    async fn wait_for_rows(audit: &AuditService, expected: usize) -> Vec<AuditRecord> {
+        for _ in 0..100 {
+            let rows = audit.query(&AuditQuery::default()).await.unwrap();
+            if rows.len() >= expected {
+                return rows;
+            }
+            tokio::time::sleep(Duration::from_millis(20)).await;
+        }
+        panic!("audit rows were not written");
+    }
+
+    #[tokio::test]
+    async fn test_records_are_written_and_filtered() {
+        let (audit, path) = service().await;
+        audit.log_request(record("getBalance", 120)).await;
+        audit.log_request(record("getSlot", 60)).await;
+        audit.log_request(record("getBalance", 0)).await;
+
+        let rows = wait_for_rows(&audit, 3).await;
+        assert_eq!(rows[0].method, "getBalance");
+        assert_eq!(rows[0].client_ip.as_deref(), Some("203.0.113.9"));
+        assert_eq!(rows[0].latency_ms, 12);
+
+        let balances = audit.query(&AuditQuery {
+            method: Some("getBalance".to_string()),
+            ..Default::default()
+        }).await.unwrap();
+        assert_eq!(balances.len(), 2);
+
+        let recent = audit.query(&AuditQuery {
+            from: Some(Utc::now() - chrono::Duration::seconds(90)),
+            to: Some(Utc::now() - chrono::Duration::seconds(30)),
+            ..Default::default()
+        }).await.unwrap();
+        assert_eq!(recent.len(), 1);
+        assert_eq!(recent[0].method, "getSlot");
+
+        let limited = audit.query(&AuditQuery { limit: Some(1), ..Default::default() }).await.unwrap();
+        assert_eq!(limited.len(), 1);
+
+        let _ = std::fs::remove_file(&path);
+    }
+
+    #[test]
+    fn test_batch_payload_audits_each_call() {
+        let calls = audited_calls(&json!([
+            {"jsonrpc": "2.0", "id": 1, "method": "getSlot"},
+            {"jsonrpc": "2.0", "id": 2, "method": "getBalance", "params": ["Account1"]},
+        ]));
+        assert_eq!(calls.len(), 2);
+        assert_eq!(calls[0], ("getSlot".to_string(), params_hash(&Value::Null)));
+        assert_eq!(calls[1].1, params_hash(&json!(["Account1"])));
+        assert_ne!(calls[0].1, calls[1].1);
+    }
+
+    #[tokio::test]
+    async fn test_rpc_requests_are_audited_through_server() {
+        use crate::test_server::TestServerBuilder;
+        use wiremock::{matchers::method, Mock, ResponseTemplate};
+
+        let path = std::env::temp_dir().join(format!("multi-rpc-audit-{}.db", uuid::Uuid::new_v4()));
+        let server = TestServerBuilder::new()
+            .with_config(|config| {
+                config.audit.enabled = true;
+                config.audit.db_path = path.to_string_lossy().to_string();
+                config.auth.enabled = true;
+                config.auth.api_keys.insert("audited-key".to_string(), crate::config::ApiKeyConfig {
+                    name: "audited".to_string(),
+                    rate_limit: 1000,
+                    allowed_methods: None,
+                    allowed_ips: None,
+                    created_at: "2024-01-01T00:00:00Z".to_string(),
+                    expires_at: None,
+                });
+            })
+            .start()
+            .await;
+        Mock::given(method("POST"))
+            .respond_with(ResponseTemplate::new(200).set_body_json(json!({"jsonrpc": "2.0", "id": 1, "result": 5})))
+            .mount(server.endpoint_mock("primary"))
+            .await;
+
+        server.client.post(&server.base_url)
+            .header("x-forwarded-for", "198.51.100.7")
+            .header("x-api-key", "audited-key")
+            .json(&json!({"jsonrpc": "2.0", "id": 1, "method": "getSlot"}))
+            .send()
+            .await
+            .unwrap();
+
+        let audit = server.state.audit_service.as_ref().unwrap();
+        let rows = wait_for_rows(audit, 1).await;
+        assert_eq!(rows[0].method, "getSlot");
+        assert_eq!(rows[0].status, 200);
+        assert_eq!(rows[0].client_ip.as_deref(), Some("198.51.100.7"));
+        assert_eq!(rows[0].api_key_id, Some(api_key_id("audited-key")));
+
+        let body = server.client.get(server.url("/admin/audit?method=getSlot&limit=5")).send().await.unwrap()
+            .text().await.unwrap();
+        assert!(!body.contains("audited-key"), "{}", body);
+        let body: Value = serde_json::from_str(&body).unwrap();
+        assert_eq!(body["count"], 1);
+
+        let _ = std::fs::remove_file(&path);
+    }
+}
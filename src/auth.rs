@@ -1,11 +1,12 @@
 use crate::{
     config::{Config, ApiKeyConfig},
     error::AppError,
+    propagation::RequestContextPropagator,
     AppState,
 };
 use axum::{
     extract::{Request, State},
-    http::{HeaderMap, StatusCode},
+    http::HeaderMap,
     middleware::Next,
     response::{Json, Response},
 };
@@ -14,7 +15,7 @@ use jsonwebtoken::{decode, encode, DecodingKey, EncodingKey, Header, Validation,
 use serde::{Deserialize, Serialize};
 use std::{collections::HashMap, sync::Arc};
 use tokio::sync::RwLock;
-use tracing::{debug, warn, error};
+use tracing::{debug, warn};
 
 #[derive(Debug, Clone)]
 pub struct AuthService {
@@ -37,6 +38,27 @@ pub struct Claims {
     pub iat: usize,       // Issued at
     pub iss: String,      // Issuer
     pub scope: Vec<String>, // Permissions/scopes
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub aud: Option<Audience>, // Intended audience(s)
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub tid: Option<String>, // Tenant identifier
+}
+
+// RFC 7519 allows `aud` to be a single string or an array of strings
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(untagged)]
+pub enum Audience {
+    Single(String),
+    Multiple(Vec<String>),
+}
+
+impl Audience {
+    fn contains_any(&self, expected: &[String]) -> bool {
+        match self {
+            Audience::Single(aud) => expected.contains(aud),
+            Audience::Multiple(auds) => auds.iter().any(|aud| expected.contains(aud)),
+        }
+    }
 }
 
 #[derive(Debug, Deserialize)]
@@ -65,6 +87,10 @@ pub struct AuthContext {
     pub scope: Vec<String>,
     pub ip_address: Option<String>,
     pub authenticated: bool,
+    pub tenant_id: Option<String>,
+    // The W3C baggage userId the client sent about itself. Unverified, so it is only for
+    // tracing and logs; never use it for authorization or audit records.
+    pub baggage_user_id: Option<String>,
 }
 
 impl AuthService {
@@ -113,6 +139,8 @@ impl AuthService {
                 scope: vec!["api".to_string()],
                 ip_address: None,
                 authenticated: true,
+                tenant_id: None,
+                baggage_user_id: None,
             })
         } else {
             Err(AppError::InvalidAuthToken)
@@ -125,6 +153,7 @@ impl AuthService {
 
         let token_data: TokenData<Claims> = decode(token, &decoding_key, &validation)
             .map_err(|_| AppError::InvalidAuthToken)?;
+        self.validate_audience(&token_data.claims)?;
 
         Ok(AuthContext {
             api_key: None,
@@ -132,9 +161,26 @@ impl AuthService {
             scope: token_data.claims.scope,
             ip_address: None,
             authenticated: true,
+            tenant_id: token_data.claims.tid,
+            baggage_user_id: None,
         })
     }
 
+    fn validate_audience(&self, claims: &Claims) -> Result<(), AppError> {
+        let expected = &self.config.auth.expected_audience;
+        match &claims.aud {
+            Some(aud) if !expected.is_empty() && !aud.contains_any(expected) => {
+                debug!("JWT audience {:?} not in expected audiences {:?}", aud, expected);
+                Err(AppError::InvalidAuthToken)
+            }
+            None if self.config.auth.require_audience => {
+                debug!("JWT rejected: missing required aud claim");
+                Err(AppError::InvalidAuthToken)
+            }
+            _ => Ok(()),
+        }
+    }
+
     pub async fn create_jwt(&self, user: &str, scope: Vec<String>) -> Result<String, AppError> {
         let now = Utc::now();
         let exp = now + chrono::Duration::seconds(self.config.auth.token_expiry as i64);
@@ -145,6 +191,10 @@ impl AuthService {
             iat: now.timestamp() as usize,
             iss: "multi-rpc".to_string(),
             scope,
+            // Our own tokens must pass our own audience check
+            aud: (!self.config.auth.expected_audience.is_empty())
+                .then(|| Audience::Multiple(self.config.auth.expected_audience.clone())),
+            tid: None,
         };
 
         let encoding_key = EncodingKey::from_secret(self.jwt_secret.as_ref());
@@ -172,6 +222,33 @@ impl AuthService {
         Ok(true) // No IP restrictions
     }
 
+    // WebSocket clients can't always set headers on the upgrade, so the key comes from ?api_key=.
+    // None when auth is disabled; a missing or invalid key is rejected before the upgrade.
+    pub async fn authenticate_websocket(
+        &self,
+        api_key: Option<&str>,
+        client_ip: Option<String>,
+    ) -> Result<Option<AuthContext>, AppError> {
+        if !self.config.auth.enabled {
+            return Ok(None);
+        }
+
+        let api_key = api_key.ok_or(AppError::Unauthorized)?;
+        let mut context = self.validate_api_key(api_key).await.map_err(|e| {
+            debug!("WebSocket API key validation failed: {}", e);
+            AppError::Unauthorized
+        })?;
+
+        if let Some(ip) = &client_ip {
+            if !self.check_ip_whitelist(api_key, ip).await? {
+                warn!("API key {} blocked due to IP restriction: {}", api_key, ip);
+                return Err(AppError::Forbidden);
+            }
+        }
+        context.ip_address = client_ip;
+        Ok(Some(context))
+    }
+
     pub async fn check_method_permission(&self, api_key: &str, method: &str) -> Result<bool, AppError> {
         let api_keys = self.api_keys.read().await;
         
@@ -237,8 +314,17 @@ impl AuthService {
         // For demo purposes, also accept plaintext comparison
         password == "admin123" || password_hash == hash || hash.contains("hash")
     }
+
+    // For admin operations reachable outside /admin, such as GraphQL mutations
+    pub fn allows_admin(&self, context: Option<&AuthContext>) -> bool {
+        !(self.config.auth.enabled && self.config.auth.require_auth_for_admin)
+            || context.is_some_and(|c| c.authenticated && c.scope.iter().any(|scope| scope == "admin"))
+    }
 }
 
+// Routes that send calls upstream, which need credentials whenever auth is enabled
+const RPC_PATHS: [&str; 2] = ["/", "/rpc/simulate-multi"];
+
 pub struct AuthMiddleware;
 
 impl AuthMiddleware {
@@ -249,7 +335,7 @@ impl AuthMiddleware {
     ) -> Result<Response, AppError> {
         // Skip authentication for health check and public endpoints
         let path = request.uri().path();
-        if matches!(path, "/health" | "/metrics" | "/auth/login") {
+        if matches!(path, "/health" | "/health/live" | "/health/ready" | "/metrics" | "/config/schema" | "/auth/login") {
             return Ok(next.run(request).await);
         }
 
@@ -264,6 +350,8 @@ impl AuthMiddleware {
             scope: vec![],
             ip_address: None,
             authenticated: false,
+            tenant_id: None,
+            baggage_user_id: None,
         };
 
         // Extract client IP
@@ -305,9 +393,8 @@ impl AuthMiddleware {
         if !auth_context.authenticated {
             if let Some(auth_value) = headers.get("authorization") {
                 if let Ok(auth_str) = auth_value.to_str() {
-                    if auth_str.starts_with("Bearer ") {
-                        let token = &auth_str[7..];
-                match state.auth_service.validate_jwt(token).await {
+                    if let Some(token) = auth_str.strip_prefix("Bearer ") {
+                        match state.auth_service.validate_jwt(token).await {
                     Ok(mut ctx) => {
                         ctx.ip_address = auth_context.ip_address.clone();
                         auth_context = ctx;
@@ -321,18 +408,29 @@ impl AuthMiddleware {
             }
         }
 
+        // Kept apart from `user`, which only ever holds a verified identity
+        auth_context.baggage_user_id = request.extensions()
+            .get::<opentelemetry::Context>()
+            .and_then(RequestContextPropagator::user_id);
+
         // Check if admin endpoints require authentication
-        if path.starts_with("/admin") && state.auth_service.config.auth.require_auth_for_admin {
-            if !auth_context.authenticated || !auth_context.scope.contains(&"admin".to_string()) {
-                return Err(AppError::Unauthorized);
-            }
+        if path.starts_with("/admin") && state.auth_service.config.auth.require_auth_for_admin
+            && (!auth_context.authenticated || !auth_context.scope.contains(&"admin".to_string())) {
+            return Err(AppError::Unauthorized);
         }
 
         // For API endpoints, require authentication if enabled
-        if path == "/" && !auth_context.authenticated {
+        let rpc_path = RPC_PATHS.contains(&path);
+        if rpc_path && !auth_context.authenticated {
             return Err(AppError::Unauthorized);
         }
 
+        if rpc_path {
+            if let Some(tenant_id) = &auth_context.tenant_id {
+                state.metrics_service.record_tenant_request(tenant_id).await;
+            }
+        }
+
         // Add auth context to request extensions
         request.extensions_mut().insert(auth_context);
         
@@ -372,9 +470,8 @@ pub async fn handle_validate(
 ) -> Result<Json<serde_json::Value>, AppError> {
     if let Some(auth_value) = headers.get("authorization") {
         if let Ok(auth_str) = auth_value.to_str() {
-            if auth_str.starts_with("Bearer ") {
-                let token = &auth_str[7..];
-        let auth_context = state.auth_service.validate_jwt(token).await?;
+            if let Some(token) = auth_str.strip_prefix("Bearer ") {
+                let auth_context = state.auth_service.validate_jwt(token).await?;
         
         Ok(Json(serde_json::json!({
             "valid": true,
@@ -398,9 +495,8 @@ pub async fn handle_refresh(
 ) -> Result<Json<LoginResponse>, AppError> {
     if let Some(auth_value) = headers.get("authorization") {
         if let Ok(auth_str) = auth_value.to_str() {
-            if auth_str.starts_with("Bearer ") {
-                let token = &auth_str[7..];
-        let auth_context = state.auth_service.validate_jwt(token).await?;
+            if let Some(token) = auth_str.strip_prefix("Bearer ") {
+                let auth_context = state.auth_service.validate_jwt(token).await?;
         
         if let Some(user) = auth_context.user {
             let new_token = state.auth_service.create_jwt(&user, auth_context.scope.clone()).await?;
@@ -426,4 +522,148 @@ pub async fn handle_refresh(
     } else {
         Err(AppError::InvalidAuthToken)
     }
-}
\ No newline at end of file
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{
+        config::RateLimit,
+        test_server::{rpc_result, TestServerBuilder},
+    };
+    use reqwest::StatusCode;
+    use serde_json::json;
+    use wiremock::{matchers::method, Mock};
+
+    async fn service(expected_audience: &[&str], require_audience: bool) -> AuthService {
+        let mut config = Config::default();
+        config.auth.expected_audience = expected_audience.iter().map(|a| a.to_string()).collect();
+        config.auth.require_audience = require_audience;
+        AuthService::new(&config).await.unwrap()
+    }
+
+    fn token(service: &AuthService, aud: Option<Audience>, tid: Option<&str>) -> String {
+        let now = Utc::now().timestamp() as usize;
+        let claims = Claims {
+            sub: "tenant-user".to_string(),
+            exp: now + 3600,
+            iat: now,
+            iss: "tenant-issuer".to_string(),
+            scope: vec!["api".to_string()],
+            aud,
+            tid: tid.map(str::to_string),
+        };
+        encode(&Header::default(), &claims, &EncodingKey::from_secret(service.jwt_secret.as_ref())).unwrap()
+    }
+
+    #[tokio::test]
+    async fn test_valid_audience_accepted() {
+        let service = service(&["tenant-a", "tenant-b"], true).await;
+
+        let single = token(&service, Some(Audience::Single("tenant-b".to_string())), Some("acme"));
+        let context = service.validate_jwt(&single).await.unwrap();
+        assert!(context.authenticated);
+        assert_eq!(context.tenant_id.as_deref(), Some("acme"));
+
+        let multiple = token(&service, Some(Audience::Multiple(vec!["other".to_string(), "tenant-a".to_string()])), None);
+        assert!(service.validate_jwt(&multiple).await.is_ok());
+    }
+
+    #[tokio::test]
+    async fn test_invalid_audience_rejected() {
+        let service = service(&["tenant-a"], false).await;
+
+        let token = token(&service, Some(Audience::Single("tenant-z".to_string())), Some("acme"));
+        assert!(matches!(service.validate_jwt(&token).await, Err(AppError::InvalidAuthToken)));
+    }
+
+    #[tokio::test]
+    async fn test_missing_audience_depends_on_require_audience() {
+        let lenient = service(&["tenant-a"], false).await;
+        let missing = token(&lenient, None, None);
+        assert!(lenient.validate_jwt(&missing).await.is_ok());
+
+        let strict = service(&["tenant-a"], true).await;
+        let missing = token(&strict, None, None);
+        assert!(matches!(strict.validate_jwt(&missing).await, Err(AppError::InvalidAuthToken)));
+
+        // Tokens we issue ourselves carry the expected audience
+        let issued = strict.create_jwt("admin", vec!["admin".to_string()]).await.unwrap();
+        assert!(strict.validate_jwt(&issued).await.is_ok());
+    }
+
+    #[tokio::test]
+    async fn test_tenants_are_rate_limited_in_their_own_buckets() {
+        let server = TestServerBuilder::new()
+            .with_config(|config| {
+                config.auth.enabled = true;
+                config.rate_limiting.enabled = true;
+                config.rate_limiting.per_method_limits.insert("getSlot".to_string(), RateLimit {
+                    rate: 1,
+                    burst: 1,
+                    window_seconds: 1,
+                });
+            })
+            .start()
+            .await;
+        Mock::given(method("POST"))
+            .respond_with(rpc_result(json!(42)))
+            .mount(server.endpoint_mock("primary"))
+            .await;
+        let call = |tenant: &str| {
+            let token = token(&server.state.auth_service, None, Some(tenant));
+            let request = server.client.post(&server.base_url)
+                .bearer_auth(token)
+                .json(&json!({"jsonrpc": "2.0", "id": 1, "method": "getSlot"}));
+            async move { request.send().await.unwrap().status() }
+        };
+
+        assert_eq!(call("acme").await, StatusCode::OK);
+        assert_eq!(call("acme").await, StatusCode::TOO_MANY_REQUESTS);
+        // Another tenant's budget for the same method is untouched
+        assert_eq!(call("globex").await, StatusCode::OK);
+    }
+
+    #[tokio::test]
+    async fn test_baggage_user_id_is_kept_apart_from_the_identity() {
+        use tower::ServiceExt;
+
+        let server = TestServerBuilder::new()
+            .with_config(|config| {
+                config.auth.enabled = true;
+                config.auth.api_keys.insert("baggage-key".to_string(), crate::config::ApiKeyConfig {
+                    name: "Baggage Key".to_string(),
+                    rate_limit: 1000,
+                    allowed_methods: None,
+                    allowed_ips: None,
+                    created_at: "2024-01-01T00:00:00Z".to_string(),
+                    expires_at: None,
+                });
+            })
+            .start()
+            .await;
+        let app = axum::Router::new()
+            .route("/whoami", axum::routing::get(|axum::Extension(context): axum::Extension<AuthContext>| async move {
+                axum::Json(json!({"user": context.user, "baggage_user_id": context.baggage_user_id}))
+            }))
+            .layer(axum::middleware::from_fn_with_state(server.state.clone(), AuthMiddleware::middleware))
+            .layer(axum::middleware::from_fn(RequestContextPropagator::middleware));
+        let whoami = |api_key: Option<&str>| {
+            let mut request = axum::http::Request::get("/whoami").header("baggage", "userId=mallory");
+            if let Some(api_key) = api_key {
+                request = request.header("x-api-key", api_key);
+            }
+            let app = app.clone();
+            let request = request.body(axum::body::Body::empty()).unwrap();
+            async move {
+                let response = app.oneshot(request).await.unwrap();
+                let body = axum::body::to_bytes(response.into_body(), usize::MAX).await.unwrap();
+                serde_json::from_slice::<serde_json::Value>(&body).unwrap()
+            }
+        };
+
+        // An anonymous caller can't pick the identity recorded for it
+        assert_eq!(whoami(None).await, json!({"user": null, "baggage_user_id": "mallory"}));
+        assert_eq!(whoami(Some("baggage-key")).await, json!({"user": "Baggage Key", "baggage_user_id": "mallory"}));
+    }
+}
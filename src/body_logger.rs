@@ -0,0 +1,373 @@
+use crate::{
+    config::{BodyLoggerConfig, RequestLoggingConfig},
+    router::X_ACCEL_BUFFERING,
+    AppState,
+};
+use axum::{
+    body::{Body, Bytes},
+    extract::{Request, State},
+    middleware::Next,
+    response::Response,
+};
+use futures::{stream, StreamExt};
+use serde_json::{json, Value};
+use std::{sync::Arc, time::{Duration, Instant}};
+use tracing::{error, info, trace, Level};
+
+pub const BODY_LOGGER_TARGET: &str = "multi_rpc::body_logger";
+pub const RPC_LOG_TARGET: &str = "multi_rpc::rpc_log";
+pub const REDACTED: &str = "[REDACTED]";
+// Logged in place of a body that was streamed or too large to buffer
+const NOT_BUFFERED: &str = "<not buffered>";
+
+// Formats JSON bodies for the debug body log and the RPC call log, with sensitive fields
+// blanked out
+#[derive(Debug, Clone)]
+pub struct BodyLogger {
+    sensitive_fields: Vec<String>,
+    max_log_body_bytes: usize,
+    max_buffered_body_bytes: usize,
+    // Each body at TRACE under BODY_LOGGER_TARGET
+    debug_request_logging: bool,
+    // One INFO line per RPC call under RPC_LOG_TARGET
+    rpc_log: bool,
+    rpc_log_bodies: bool,
+}
+
+impl BodyLogger {
+    // None when neither log is enabled
+    pub fn new(config: &BodyLoggerConfig, logging: &RequestLoggingConfig) -> Option<Self> {
+        (config.debug_request_logging || logging.enabled).then(|| Self {
+            sensitive_fields: config.sensitive_fields.iter().map(|field| field.to_lowercase()).collect(),
+            max_log_body_bytes: config.max_log_body_bytes,
+            max_buffered_body_bytes: config.max_buffered_body_bytes,
+            debug_request_logging: config.debug_request_logging,
+            rpc_log: logging.enabled,
+            rpc_log_bodies: logging.log_bodies,
+        })
+    }
+
+    pub fn redact(&self, value: &mut Value) {
+        redact(value, &self.sensitive_fields);
+    }
+
+    // What gets logged for a body. Anything that isn't JSON can't be redacted, so only its
+    // size is logged.
+    pub fn format_body(&self, bytes: &[u8]) -> String {
+        if bytes.is_empty() {
+            return String::new();
+        }
+        let Ok(mut value) = serde_json::from_slice::<Value>(bytes) else {
+            return format!("<{} bytes of non-JSON body>", bytes.len());
+        };
+        self.redact(&mut value);
+
+        let mut body = value.to_string();
+        if body.len() > self.max_log_body_bytes {
+            let mut end = self.max_log_body_bytes;
+            while !body.is_char_boundary(end) {
+                end -= 1;
+            }
+            body.truncate(end);
+            body.push_str("...(truncated)");
+        }
+        body
+    }
+
+    // A body as it appears in an RPC call log entry: redacted JSON, never truncated
+    fn body_entry(&self, bytes: Option<&[u8]>) -> Value {
+        let Some(bytes) = bytes else {
+            return json!(NOT_BUFFERED);
+        };
+        match serde_json::from_slice::<Value>(bytes) {
+            Ok(mut value) => {
+                self.redact(&mut value);
+                value
+            }
+            Err(_) if bytes.is_empty() => Value::Null,
+            Err(_) => json!(format!("<{} bytes of non-JSON body>", bytes.len())),
+        }
+    }
+
+    // A body of None was streamed or too large to buffer
+    pub fn rpc_log_entry(&self, request: Option<&[u8]>, status: u16, duration: Duration, response: Option<&[u8]>) -> Value {
+        let request = self.body_entry(request);
+        let rpc_method = match &request {
+            Value::Array(calls) => Value::Array(calls.iter().map(|call| call["method"].clone()).collect()),
+            call => call["method"].clone(),
+        };
+
+        let mut entry = json!({
+            "rpc_method": rpc_method,
+            "status": status,
+            "duration_ms": duration.as_millis() as u64,
+        });
+        if self.rpc_log_bodies {
+            entry["request"] = request;
+            entry["response"] = self.body_entry(response);
+        }
+        entry
+    }
+
+    // Buffers a body of up to max_buffered_body_bytes so it can be logged. A larger one is passed
+    // on unbuffered, as what was read so far followed by the rest of the stream.
+    async fn buffer(&self, body: Body) -> (Body, Option<Bytes>) {
+        let mut chunks = body.into_data_stream();
+        let mut buffered = Vec::new();
+        while let Some(chunk) = chunks.next().await {
+            let chunk = match chunk {
+                Ok(chunk) if buffered.len() + chunk.len() <= self.max_buffered_body_bytes => chunk,
+                chunk => {
+                    if let Err(e) = &chunk {
+                        error!("Failed to buffer body for logging: {}", e);
+                    }
+                    let head = stream::iter([Ok(Bytes::from(buffered)), chunk]);
+                    return (Body::from_stream(head.chain(chunks)), None);
+                }
+            };
+            buffered.extend_from_slice(&chunk);
+        }
+        let bytes = Bytes::from(buffered);
+        (Body::from(bytes.clone()), Some(bytes))
+    }
+}
+
+fn is_streamed(response: &Response) -> bool {
+    response.headers().get(X_ACCEL_BUFFERING).is_some_and(|value| value == "no")
+}
+
+// Replaces the value of every key in `sensitive_fields` (lowercase), at any depth and inside arrays
+pub fn redact(value: &mut Value, sensitive_fields: &[String]) {
+    match value {
+        Value::Object(map) => {
+            for (key, child) in map.iter_mut() {
+                if sensitive_fields.contains(&key.to_lowercase()) {
+                    *child = Value::String(REDACTED.to_string());
+                } else {
+                    redact(child, sensitive_fields);
+                }
+            }
+        }
+        Value::Array(items) => items.iter_mut().for_each(|item| redact(item, sensitive_fields)),
+        _ => {}
+    }
+}
+
+pub async fn body_logging_middleware(
+    State(state): State<Arc<AppState>>,
+    request: Request,
+    next: Next,
+) -> Response {
+    let Some(logger) = state.body_logger.as_ref().filter(|logger| logger.debug_request_logging) else {
+        return next.run(request).await;
+    };
+    // Buffering bodies isn't free, so skip it unless someone is listening
+    if !tracing::enabled!(target: BODY_LOGGER_TARGET, Level::TRACE) {
+        return next.run(request).await;
+    }
+
+    let method = request.method().clone();
+    let uri = request.uri().clone();
+    let (parts, body) = request.into_parts();
+    let (body, bytes) = logger.buffer(body).await;
+    match &bytes {
+        Some(bytes) => trace!(target: BODY_LOGGER_TARGET, %method, %uri, body_bytes = bytes.len(),
+            body = %logger.format_body(bytes), "request body"),
+        None => trace!(target: BODY_LOGGER_TARGET, %method, %uri, "request body too large to log"),
+    }
+
+    let response = next.run(Request::from_parts(parts, body)).await;
+
+    // Streamed responses would have to be buffered in full to be logged
+    if is_streamed(&response) {
+        trace!(target: BODY_LOGGER_TARGET, %method, %uri, status = %response.status(), "response body streamed");
+        return response;
+    }
+
+    let (parts, body) = response.into_parts();
+    let (body, bytes) = logger.buffer(body).await;
+    match &bytes {
+        Some(bytes) => trace!(target: BODY_LOGGER_TARGET, %method, %uri, status = %parts.status,
+            body_bytes = bytes.len(), body = %logger.format_body(bytes), "response body"),
+        None => trace!(target: BODY_LOGGER_TARGET, %method, %uri, status = %parts.status, "response body too large to log"),
+    }
+    Response::from_parts(parts, body)
+}
+
+// Logs each RPC call as one JSON object. Sits on the RPC route inside request decompression,
+// so the request body it sees is the decompressed one.
+pub async fn rpc_logging_middleware(
+    State(state): State<Arc<AppState>>,
+    request: Request,
+    next: Next,
+) -> Response {
+    let Some(logger) = state.body_logger.as_ref().filter(|logger| logger.rpc_log) else {
+        return next.run(request).await;
+    };
+
+    let started = Instant::now();
+    // Needed for the method even when bodies aren't logged
+    let (parts, body) = request.into_parts();
+    let (body, request_bytes) = logger.buffer(body).await;
+    let response = next.run(Request::from_parts(parts, body)).await;
+
+    let (response, response_bytes) = if logger.rpc_log_bodies && !is_streamed(&response) {
+        let (parts, body) = response.into_parts();
+        let (body, bytes) = logger.buffer(body).await;
+        (Response::from_parts(parts, body), bytes)
+    } else {
+        (response, None)
+    };
+    let entry = logger.rpc_log_entry(
+        request_bytes.as_deref(),
+        response.status().as_u16(),
+        started.elapsed(),
+        response_bytes.as_deref(),
+    );
+    info!(target: RPC_LOG_TARGET, entry = %entry, "rpc call");
+    response
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    fn logger(max_log_body_bytes: usize) -> BodyLogger {
+        BodyLogger::new(&BodyLoggerConfig {
+            debug_request_logging: true,
+            max_log_body_bytes,
+            ..BodyLoggerConfig::default()
+        }, &RequestLoggingConfig::default()).unwrap()
+    }
+
+    fn rpc_logger(log_bodies: bool) -> BodyLogger {
+        BodyLogger::new(&BodyLoggerConfig::default(), &RequestLoggingConfig { enabled: true, log_bodies }).unwrap()
+    }
+
+    #[test]
+    fn test_redacts_nested_fields_and_arrays() {
+        let mut body = json!({
+            "jsonrpc": "2.0",
+            "method": "getSignatureStatuses",
+            "params": [
+                {"wallet": {"privateKey": "abc", "pubkey": "A"}},
+                [{"signature": "5xyz", "slot": 10}, {"SecretKey": [1, 2, 3]}]
+            ],
+            "signature": {"nested": "object"}
+        });
+        logger(4096).redact(&mut body);
+
+        assert_eq!(body["params"][0]["wallet"]["privateKey"], REDACTED);
+        assert_eq!(body["params"][0]["wallet"]["pubkey"], "A");
+        assert_eq!(body["params"][1][0]["signature"], REDACTED);
+        assert_eq!(body["params"][1][0]["slot"], 10);
+        // Keys match case-insensitively, and whole subtrees are replaced
+        assert_eq!(body["params"][1][1]["SecretKey"], REDACTED);
+        assert_eq!(body["signature"], REDACTED);
+        assert_eq!(body["method"], "getSignatureStatuses");
+    }
+
+    #[test]
+    fn test_custom_sensitive_fields() {
+        let logger = BodyLogger::new(&BodyLoggerConfig {
+            debug_request_logging: true,
+            sensitive_fields: vec!["authToken".to_string()],
+            ..BodyLoggerConfig::default()
+        }, &RequestLoggingConfig::default()).unwrap();
+        let mut body = json!({"result": [{"authToken": "t", "signature": "s"}]});
+        logger.redact(&mut body);
+
+        assert_eq!(body["result"][0]["authToken"], REDACTED);
+        assert_eq!(body["result"][0]["signature"], "s");
+    }
+
+    #[test]
+    fn test_format_body_truncates_and_skips_non_json() {
+        let logger = logger(32);
+        let body = serde_json::to_vec(&json!({"privateKey": "k", "data": "x".repeat(100)})).unwrap();
+        let formatted = logger.format_body(&body);
+        assert!(formatted.starts_with(r#"{"data":"xxx"#));
+        assert!(formatted.ends_with("...(truncated)"));
+        assert_eq!(formatted.len(), 32 + "...(truncated)".len());
+
+        let formatted = logger.format_body(br#"{"privateKey":"k"}"#);
+        assert_eq!(formatted, r#"{"privateKey":"[REDACTED]"}"#);
+
+        assert_eq!(logger.format_body(b"privateKey=k"), "<12 bytes of non-JSON body>");
+        assert_eq!(logger.format_body(b""), "");
+    }
+
+    #[test]
+    fn test_disabled_without_either_log() {
+        assert!(BodyLogger::new(&BodyLoggerConfig::default(), &RequestLoggingConfig::default()).is_none());
+    }
+
+    #[test]
+    fn test_rpc_log_bodies_are_redacted_in_nested_objects_and_arrays() {
+        let request = json!({
+            "jsonrpc": "2.0",
+            "id": 1,
+            "method": "sendTransaction",
+            "params": [
+                {"payer": {"privateKey": [1, 2, 3], "pubkey": "A"}},
+                [{"signature": "5xyz", "slot": 10}, [{"PRIVATEKEY": "abc"}]]
+            ]
+        });
+        let response = json!({"jsonrpc": "2.0", "id": 1, "result": {"value": [{"signature": "5xyz", "err": null}]}});
+
+        let entry = rpc_logger(true).rpc_log_entry(
+            Some(request.to_string().as_bytes()),
+            200,
+            Duration::from_millis(12),
+            Some(response.to_string().as_bytes()),
+        );
+
+        assert_eq!(entry["rpc_method"], "sendTransaction");
+        assert_eq!(entry["status"], 200);
+        assert_eq!(entry["duration_ms"], 12);
+        assert_eq!(entry["request"]["params"][0]["payer"]["privateKey"], REDACTED);
+        assert_eq!(entry["request"]["params"][0]["payer"]["pubkey"], "A");
+        assert_eq!(entry["request"]["params"][1][0]["signature"], REDACTED);
+        assert_eq!(entry["request"]["params"][1][0]["slot"], 10);
+        assert_eq!(entry["request"]["params"][1][1][0]["PRIVATEKEY"], REDACTED);
+        assert_eq!(entry["response"]["result"]["value"][0]["signature"], REDACTED);
+        assert_eq!(entry["response"]["result"]["value"][0]["err"], Value::Null);
+    }
+
+    #[test]
+    fn test_rpc_log_bodies_are_left_out_unless_enabled() {
+        let batch = json!([
+            {"jsonrpc": "2.0", "id": 1, "method": "getSlot"},
+            {"jsonrpc": "2.0", "id": 2, "method": "getBalance", "params": ["A"]}
+        ]);
+
+        let entry = rpc_logger(false).rpc_log_entry(Some(batch.to_string().as_bytes()), 200, Duration::ZERO, None);
+        assert_eq!(entry["rpc_method"], json!(["getSlot", "getBalance"]));
+        assert!(entry.get("request").is_none());
+        assert!(entry.get("response").is_none());
+
+        let entry = rpc_logger(true).rpc_log_entry(Some(b"not json"), 400, Duration::ZERO, None);
+        assert_eq!(entry["request"], "<8 bytes of non-JSON body>");
+        assert_eq!(entry["response"], NOT_BUFFERED);
+    }
+
+    #[tokio::test]
+    async fn test_buffering_is_bounded_and_keeps_the_body() {
+        let logger = BodyLogger::new(&BodyLoggerConfig {
+            debug_request_logging: true,
+            max_buffered_body_bytes: 16,
+            ..BodyLoggerConfig::default()
+        }, &RequestLoggingConfig::default()).unwrap();
+
+        let (body, bytes) = logger.buffer(Body::from("0123456789")).await;
+        assert_eq!(bytes.as_deref(), Some(&b"0123456789"[..]));
+        assert_eq!(axum::body::to_bytes(body, usize::MAX).await.unwrap(), "0123456789");
+
+        // Chunked so the limit is crossed partway through; the full body still goes through
+        let chunks = ["0123456789", "abcdefghij", "klmnop"].map(|chunk| Ok::<_, std::io::Error>(Bytes::from(chunk)));
+        let (body, bytes) = logger.buffer(Body::from_stream(stream::iter(chunks))).await;
+        assert!(bytes.is_none());
+        assert_eq!(axum::body::to_bytes(body, usize::MAX).await.unwrap(), "0123456789abcdefghijklmnop");
+    }
+}
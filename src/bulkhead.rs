@@ -1,18 +1,44 @@
+use std::collections::HashMap;
 use std::sync::Arc;
 use std::time::{Duration, Instant};
-use tokio::sync::{Semaphore, SemaphorePermit};
+use tokio::sync::Semaphore;
 use tokio::time::timeout;
 use dashmap::DashMap;
-use tracing::{debug, warn, error, instrument};
+use schemars::JsonSchema;
+use serde::{Deserialize, Serialize};
+use tracing::{debug, warn, instrument};
 use crate::error::{AppError, AppResult};
+use crate::AppState;
+use axum::{extract::State, Json};
 
-#[derive(Debug, Clone)]
+#[derive(Debug, Clone, Serialize, Deserialize, JsonSchema)]
+#[serde(default)]
 pub struct BulkheadConfig {
     pub max_concurrent_calls: usize,
+    /// How long a call waits for a free slot before it is rejected
+    #[serde(rename = "max_wait_ms", with = "duration_ms")]
+    #[schemars(with = "u64")]
     pub max_wait_duration: Duration,
+    /// Accepted and rejected counts are reset after this long
+    #[serde(rename = "metrics_window_ms", with = "duration_ms")]
+    #[schemars(with = "u64")]
     pub metrics_window: Duration,
 }
 
+// Durations are written as whole milliseconds in the config file
+mod duration_ms {
+    use serde::{Deserialize, Deserializer, Serializer};
+    use std::time::Duration;
+
+    pub fn serialize<S: Serializer>(duration: &Duration, serializer: S) -> Result<S::Ok, S::Error> {
+        serializer.serialize_u64(duration.as_millis() as u64)
+    }
+
+    pub fn deserialize<'de, D: Deserializer<'de>>(deserializer: D) -> Result<Duration, D::Error> {
+        u64::deserialize(deserializer).map(Duration::from_millis)
+    }
+}
+
 impl Default for BulkheadConfig {
     fn default() -> Self {
         Self {
@@ -156,11 +182,7 @@ impl Bulkhead {
             avg_duration_ms: {
                 let total = self.metrics.total_duration.load(std::sync::atomic::Ordering::Relaxed);
                 let count = self.metrics.accepted_count.load(std::sync::atomic::Ordering::Relaxed);
-                if count > 0 {
-                    total / count
-                } else {
-                    0
-                }
+                total.checked_div(count).unwrap_or(0)
             },
         }
     }
@@ -187,7 +209,7 @@ impl Drop for BulkheadGuard {
     }
 }
 
-#[derive(Debug, Clone)]
+#[derive(Debug, Clone, Serialize)]
 pub struct BulkheadStats {
     pub name: String,
     pub accepted_count: u64,
@@ -198,6 +220,7 @@ pub struct BulkheadStats {
 }
 
 // Thread pool bulkhead for CPU-bound operations
+#[allow(dead_code)]
 pub struct ThreadPoolBulkhead {
     name: String,
     pool: tokio::runtime::Runtime,
@@ -206,11 +229,12 @@ pub struct ThreadPoolBulkhead {
     metrics: Arc<BulkheadMetrics>,
 }
 
+#[allow(dead_code)]
 impl ThreadPoolBulkhead {
     pub fn new(name: String, config: BulkheadConfig) -> AppResult<Self> {
         let pool = tokio::runtime::Builder::new_multi_thread()
             .worker_threads(config.max_concurrent_calls)
-            .thread_name(&format!("bulkhead-{}", name))
+            .thread_name(format!("bulkhead-{}", name))
             .enable_all()
             .build()
             .map_err(|e| AppError::internal(&format!("Failed to create thread pool: {}", e)))?;
@@ -248,19 +272,20 @@ impl ThreadPoolBulkhead {
         let result = self.pool.spawn_blocking(move || {
             let _guard = BulkheadGuard::new(permit, metrics);
             operation()
-        }).await
-        .map_err(|e| AppError::internal(&format!("Thread pool execution failed: {}", e)))?;
+        }).await?;
 
         Ok(result)
     }
 }
 
 // Bulkhead manager for managing multiple bulkheads
+#[allow(dead_code)]
 pub struct BulkheadManager {
     bulkheads: DashMap<String, Arc<Bulkhead>>,
     default_config: BulkheadConfig,
 }
 
+#[allow(dead_code)]
 impl BulkheadManager {
     pub fn new(default_config: BulkheadConfig) -> Self {
         Self {
@@ -307,7 +332,61 @@ impl BulkheadManager {
     }
 }
 
+// Config entry whose settings apply to every bulkhead that isn't configured by name
+pub const DEFAULT_BULKHEAD: &str = "default";
+
+// Named bulkheads from the `bulkheads` config section, so each subsystem has its own capacity
+pub struct BulkheadRegistry {
+    bulkheads: DashMap<String, Arc<Bulkhead>>,
+    default_config: BulkheadConfig,
+}
+
+impl BulkheadRegistry {
+    pub fn new(configs: &HashMap<String, BulkheadConfig>) -> Self {
+        let bulkheads = configs.iter()
+            .filter(|(name, _)| name.as_str() != DEFAULT_BULKHEAD)
+            .map(|(name, config)| (name.clone(), Arc::new(Bulkhead::new(name.clone(), config.clone()))))
+            .collect();
+        Self {
+            bulkheads,
+            default_config: configs.get(DEFAULT_BULKHEAD).cloned().unwrap_or_default(),
+        }
+    }
+
+    pub fn get(&self, name: &str) -> Option<Arc<Bulkhead>> {
+        self.bulkheads.get(name).map(|entry| entry.clone())
+    }
+
+    // Bulkheads missing from the config are created on first use with the default settings
+    pub fn get_or_default(&self, name: &str) -> Arc<Bulkhead> {
+        self.bulkheads
+            .entry(name.to_string())
+            .or_insert_with(|| Arc::new(Bulkhead::new(name.to_string(), self.default_config.clone())))
+            .clone()
+    }
+
+    // Sorted by name
+    pub fn get_all_stats(&self) -> Vec<BulkheadStats> {
+        let mut stats: Vec<_> = self.bulkheads.iter().map(|entry| entry.value().get_metrics()).collect();
+        stats.sort_by(|a, b| a.name.cmp(&b.name));
+        stats
+    }
+}
+
+impl Default for BulkheadRegistry {
+    fn default() -> Self {
+        Self::new(&HashMap::new())
+    }
+}
+
+pub async fn handle_list_bulkheads(
+    State(state): State<Arc<AppState>>,
+) -> Json<Vec<BulkheadStats>> {
+    Json(state.bulkheads.get_all_stats())
+}
+
 // Adaptive bulkhead that adjusts capacity based on performance
+#[allow(dead_code)]
 pub struct AdaptiveBulkhead {
     base_bulkhead: Arc<Bulkhead>,
     min_capacity: usize,
@@ -317,6 +396,7 @@ pub struct AdaptiveBulkhead {
     performance_history: std::sync::RwLock<Vec<f64>>,
 }
 
+#[allow(dead_code)]
 impl AdaptiveBulkhead {
     pub fn new(
         name: String,
@@ -477,4 +557,69 @@ mod tests {
         let stats = manager.get_all_stats();
         assert_eq!(stats.len(), 2);
     }
-}
\ No newline at end of file
+
+    fn registry() -> BulkheadRegistry {
+        let configs = HashMap::from([
+            ("websocket".to_string(), BulkheadConfig {
+                max_concurrent_calls: 1,
+                max_wait_duration: Duration::from_millis(10),
+                ..Default::default()
+            }),
+            (DEFAULT_BULKHEAD.to_string(), BulkheadConfig {
+                max_concurrent_calls: 3,
+                ..Default::default()
+            }),
+        ]);
+        BulkheadRegistry::new(&configs)
+    }
+
+    #[tokio::test]
+    async fn test_registry_uses_named_and_default_configs() {
+        let registry = registry();
+
+        assert_eq!(registry.get("websocket").unwrap().available_permits(), 1);
+        assert!(registry.get("consensus").is_none());
+        let consensus = registry.get_or_default("consensus");
+        assert_eq!(consensus.available_permits(), 3);
+        assert!(Arc::ptr_eq(&consensus, &registry.get_or_default("consensus")));
+        // The default entry only supplies settings, it is not a bulkhead of its own
+        assert!(registry.get(DEFAULT_BULKHEAD).is_none());
+
+        let names: Vec<_> = registry.get_all_stats().into_iter().map(|stats| stats.name).collect();
+        assert_eq!(names, ["consensus", "websocket"]);
+    }
+
+    #[tokio::test]
+    async fn test_full_bulkhead_does_not_affect_others() {
+        let registry = Arc::new(registry());
+        let (release, released) = tokio::sync::oneshot::channel::<()>();
+
+        let websocket = registry.get_or_default("websocket");
+        let holder = tokio::spawn(async move {
+            websocket.execute(|| async {
+                let _ = released.await;
+                Ok::<_, AppError>(())
+            }).await
+        });
+        tokio::time::sleep(Duration::from_millis(5)).await;
+
+        let rejected = registry.get_or_default("websocket").execute(|| async { Ok::<_, AppError>(()) }).await;
+        assert!(matches!(rejected, Err(AppError::BulkheadFull(_))));
+        let accepted = registry.get_or_default("consensus").execute(|| async { Ok::<_, AppError>(7) }).await;
+        assert_eq!(accepted.unwrap(), 7);
+
+        release.send(()).unwrap();
+        holder.await.unwrap().unwrap();
+        let stats = registry.get_all_stats();
+        assert_eq!((stats[0].accepted_count, stats[0].rejected_count), (1, 0));
+        assert_eq!((stats[1].accepted_count, stats[1].rejected_count), (1, 1));
+    }
+
+    #[test]
+    fn test_config_durations_are_milliseconds() {
+        let config: BulkheadConfig = toml::from_str("max_concurrent_calls = 4\nmax_wait_ms = 250").unwrap();
+        assert_eq!(config.max_concurrent_calls, 4);
+        assert_eq!(config.max_wait_duration, Duration::from_millis(250));
+        assert_eq!(config.metrics_window, BulkheadConfig::default().metrics_window);
+    }
+}
@@ -1,27 +1,52 @@
 use crate::{
     config::{Config, CacheConfig},
     error::AppError,
-    rpc::{get_method_category, is_method_cacheable, get_cache_ttl, RpcMethodCategory},
+    gossip::{key_hash, CacheGossipService},
+    request_trace::{in_span, set_attribute},
+    router::RpcRouter,
+    rpc::{is_method_cacheable, get_cache_ttl},
 };
+use axum::{
+    http::{header, HeaderMap, HeaderValue, StatusCode},
+    response::{IntoResponse, Response},
+    Json,
+};
+use dashmap::DashMap;
 use redis::{aio::ConnectionManager, AsyncCommands, Client, RedisResult};
+use serde::{Deserialize, Serialize};
 use serde_json::{json, Value};
+use sha2::{Digest, Sha256};
 use std::{
     collections::HashMap,
+    future::Future,
     sync::{
-        atomic::{AtomicU64, Ordering},
+        atomic::{AtomicBool, AtomicU64, AtomicUsize, Ordering},
         Arc,
     },
     time::{Duration, Instant},
 };
-use tokio::sync::RwLock;
+use tokio::sync::{Mutex, RwLock};
 use tracing::{debug, error, info, warn};
 
+pub const DEFAULT_LOCAL_CACHE_ENTRIES: usize = 10_000;
+
 #[derive(Clone)]
 pub struct CacheService {
     config: CacheConfig,
     redis_client: Option<Client>,
     connection_manager: Arc<RwLock<Option<ConnectionManager>>>,
     local_cache: Arc<RwLock<HashMap<String, CacheEntry>>>,
+    disk_cache: Option<sled::Db>,
+    // Entries received from peers, keyed by the hash of the cache key they were gossiped under
+    gossiped_cache: Arc<RwLock<HashMap<u64, CacheEntry>>>,
+    gossip: Option<Arc<CacheGossipService>>,
+    // Lowered under memory pressure, see MemoryPressureReactor
+    local_cache_limit: Arc<AtomicUsize>,
+    redis_writes_paused: Arc<AtomicBool>,
+    // Set once startup warmup has finished or been abandoned
+    initialized: Arc<AtomicBool>,
+    // One lock per key being filled by get_or_insert, removed again once nobody waits on it
+    fill_locks: Arc<DashMap<String, Arc<Mutex<()>>>>,
     stats: Arc<CacheStats>,
 }
 
@@ -32,6 +57,8 @@ impl std::fmt::Debug for CacheService {
             .field("redis_client", &self.redis_client.is_some())
             .field("connection_manager", &"<ConnectionManager>")
             .field("local_cache", &"<LocalCache>")
+            .field("disk_cache", &self.disk_cache.is_some())
+            .field("gossip", &self.gossip.is_some())
             .field("stats", &self.stats)
             .finish()
     }
@@ -40,18 +67,37 @@ impl std::fmt::Debug for CacheService {
 #[derive(Debug, Clone)]
 struct CacheEntry {
     value: Value,
+    etag: String,
     expires_at: Instant,
     access_count: u64,
     last_accessed: Instant,
 }
 
+// Stored in sled as MessagePack
+#[derive(Debug, Serialize, Deserialize)]
+struct DiskEntry {
+    value: Value,
+    expires_at: u64,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct CacheEntrySnapshot {
+    pub key: String,
+    pub value: Value,
+    pub ttl_remaining_secs: u64,
+}
+
 #[derive(Debug)]
 struct CacheStats {
     hits: AtomicU64,
     misses: AtomicU64,
+    l1_hits: AtomicU64,
+    l2_hits: AtomicU64,
+    l3_hits: AtomicU64,
     redis_errors: AtomicU64,
     evictions: AtomicU64,
     total_requests: AtomicU64,
+    stampedes_prevented: AtomicU64,
 }
 
 impl CacheService {
@@ -74,21 +120,56 @@ impl CacheService {
             (None, Arc::new(RwLock::new(None)))
         };
 
+        let disk_cache = match (&cache_config.sled_cache_path, cache_config.enabled) {
+            (Some(path), true) => {
+                let path = path.clone();
+                let opened = tokio::task::spawn_blocking(move || {
+                    let db = sled::open(&path)
+                        .map_err(|e| AppError::cache(&format!("Failed to open disk cache at {}: {}", path, e)))?;
+                    let evicted = evict_expired_disk_entries(&db);
+                    info!("Disk cache opened at {} with {} entries ({} expired entries evicted)", path, db.len(), evicted);
+                    Ok::<_, AppError>(db)
+                }).await;
+                Some(opened.map_err(|e| AppError::cache(&format!("Disk cache open task failed: {}", e)))??)
+            }
+            _ => None,
+        };
+
         Ok(Self {
             config: cache_config,
             redis_client,
             connection_manager,
             local_cache: Arc::new(RwLock::new(HashMap::new())),
+            disk_cache,
+            gossiped_cache: Arc::new(RwLock::new(HashMap::new())),
+            gossip: None,
+            local_cache_limit: Arc::new(AtomicUsize::new(DEFAULT_LOCAL_CACHE_ENTRIES)),
+            redis_writes_paused: Arc::new(AtomicBool::new(false)),
+            initialized: Arc::new(AtomicBool::new(false)),
+            fill_locks: Arc::new(DashMap::new()),
             stats: Arc::new(CacheStats {
                 hits: AtomicU64::new(0),
                 misses: AtomicU64::new(0),
+                l1_hits: AtomicU64::new(0),
+                l2_hits: AtomicU64::new(0),
+                l3_hits: AtomicU64::new(0),
                 redis_errors: AtomicU64::new(0),
                 evictions: AtomicU64::new(0),
                 total_requests: AtomicU64::new(0),
+                stampedes_prevented: AtomicU64::new(0),
             }),
         })
     }
 
+    pub fn with_gossip(mut self, gossip: Arc<CacheGossipService>) -> Self {
+        self.gossip = Some(gossip);
+        self
+    }
+
+    pub fn gossip(&self) -> Option<Arc<CacheGossipService>> {
+        self.gossip.clone()
+    }
+
     async fn create_redis_connection(config: &CacheConfig) -> Result<(Client, ConnectionManager), AppError> {
         let client = Client::open(config.redis_url.as_str())
             .map_err(|e| AppError::cache(&format!("Failed to create Redis client: {}", e)))?;
@@ -99,8 +180,20 @@ impl CacheService {
         Ok((client, manager))
     }
 
+    pub fn is_enabled(&self) -> bool {
+        self.config.enabled
+    }
+
+    pub async fn redis_connected(&self) -> bool {
+        self.connection_manager.read().await.is_some()
+    }
+
+    pub fn caches(&self, method: &str) -> bool {
+        self.config.enabled && is_method_cacheable(method)
+    }
+
     pub async fn get(&self, method: &str, params: &Value) -> Option<Value> {
-        if !self.config.enabled || !is_method_cacheable(method) {
+        if !self.caches(method) {
             return None;
         }
 
@@ -110,26 +203,54 @@ impl CacheService {
         // Try local cache first
         if let Some(value) = self.get_from_local_cache(&cache_key).await {
             self.stats.hits.fetch_add(1, Ordering::Relaxed);
+            self.stats.l1_hits.fetch_add(1, Ordering::Relaxed);
             debug!("Cache hit (local): {}", cache_key);
             return Some(value);
         }
 
+        // Try entries gossiped by peers, which are just as local
+        if let Some(value) = self.promote_gossiped(&cache_key, method).await {
+            self.stats.hits.fetch_add(1, Ordering::Relaxed);
+            self.stats.l1_hits.fetch_add(1, Ordering::Relaxed);
+            debug!("Cache hit (gossip): {}", cache_key);
+            return Some(value);
+        }
+
         // Try Redis cache
         if let Some(value) = self.get_from_redis(&cache_key).await {
             // Store in local cache for faster access
-            self.store_in_local_cache(&cache_key, &value, method).await;
+            self.store_in_local_cache(&cache_key, &value, self.get_ttl_for_method(method)).await;
             self.stats.hits.fetch_add(1, Ordering::Relaxed);
+            self.stats.l2_hits.fetch_add(1, Ordering::Relaxed);
             debug!("Cache hit (redis): {}", cache_key);
             return Some(value);
         }
 
+        // Try disk cache, promoting to local and refilling Redis in the background
+        if let Some((value, ttl)) = self.get_from_disk(&cache_key).await {
+            // Only for what is left of the disk entry's TTL, so it expires from both together
+            self.store_in_local_cache(&cache_key, &value, ttl).await;
+
+            let cache = self.clone();
+            let key = cache_key.clone();
+            let promoted = value.clone();
+            tokio::spawn(async move {
+                cache.store_in_redis(&key, &promoted, ttl).await;
+            });
+
+            self.stats.hits.fetch_add(1, Ordering::Relaxed);
+            self.stats.l3_hits.fetch_add(1, Ordering::Relaxed);
+            debug!("Cache hit (disk): {}", cache_key);
+            return Some(value);
+        }
+
         self.stats.misses.fetch_add(1, Ordering::Relaxed);
         debug!("Cache miss: {}", cache_key);
         None
     }
 
     pub async fn set(&self, method: &str, params: &Value, response: &Value) {
-        if !self.config.enabled || !is_method_cacheable(method) {
+        if !self.caches(method) {
             return;
         }
 
@@ -137,14 +258,85 @@ impl CacheService {
         let ttl = self.get_ttl_for_method(method);
 
         // Store in local cache
-        self.store_in_local_cache(&cache_key, response, method).await;
+        self.store_in_local_cache(&cache_key, response, ttl).await;
 
         // Store in Redis cache
         self.store_in_redis(&cache_key, response, ttl).await;
 
+        // Store in disk cache
+        self.store_in_disk(&cache_key, response, ttl).await;
+
+        // Share with peer instances
+        if let Some(gossip) = &self.gossip {
+            gossip.publish(&cache_key, response, ttl).await;
+        }
+
         debug!("Cached response: {} (TTL: {}s)", cache_key, ttl);
     }
 
+    // Only one caller per key runs `fill`, the others wait for it and are answered from L1.
+    // Failures are not cached, so after one the next waiter in line tries again.
+    pub async fn get_or_insert<F, Fut>(&self, method: &str, params: &Value, fill: F) -> Result<Value, AppError>
+    where
+        F: FnOnce() -> Fut,
+        Fut: Future<Output = Result<Value, AppError>>,
+    {
+        let cached = in_span("cache_check", async {
+            let cached = self.get(method, params).await;
+            set_attribute("hit", cached.is_some());
+            cached
+        }).await;
+        if let Some(value) = cached {
+            return Ok(value);
+        }
+
+        let cache_key = self.create_cache_key(method, params);
+        let fill_lock = self.caches(method)
+            .then(|| self.fill_locks.entry(cache_key.clone()).or_default().clone());
+        let guard = match &fill_lock {
+            Some(lock) => Some(lock.lock().await),
+            None => None,
+        };
+        if guard.is_some() {
+            if let Some(value) = self.get_from_local_cache(&cache_key).await {
+                self.stats.stampedes_prevented.fetch_add(1, Ordering::Relaxed);
+                debug!("Cache stampede prevented: {}", cache_key);
+                drop(guard);
+                self.release_fill_lock(&cache_key);
+                return Ok(value);
+            }
+        }
+
+        let result = fill().await;
+        if let Ok(value) = &result {
+            in_span("response_cached", self.set(method, params, value)).await;
+        }
+        drop(guard);
+        if fill_lock.is_some() {
+            self.release_fill_lock(&cache_key);
+        }
+        result
+    }
+
+    // The map and the caller releasing it hold the only references when nobody else is waiting
+    fn release_fill_lock(&self, cache_key: &str) {
+        self.fill_locks.remove_if(cache_key, |_, lock| Arc::strong_count(lock) <= 2);
+    }
+
+    // ETag of a live L1 entry; lower tiers are promoted to L1 on their first hit, so they get one then.
+    // Not counted as a lookup, the request it belongs to still goes through get.
+    pub async fn cached_etag(&self, method: &str, params: &Value) -> Option<String> {
+        if !self.caches(method) || !is_idempotent(method) {
+            return None;
+        }
+
+        let cache_key = self.create_cache_key(method, params);
+        let cache = self.local_cache.read().await;
+        cache.get(&cache_key)
+            .filter(|entry| entry.expires_at > Instant::now())
+            .map(|entry| entry.etag.clone())
+    }
+
     async fn get_from_local_cache(&self, key: &str) -> Option<Value> {
         let mut cache = self.local_cache.write().await;
         
@@ -163,17 +355,19 @@ impl CacheService {
         None
     }
 
-    async fn store_in_local_cache(&self, key: &str, value: &Value, method: &str) {
+    async fn store_in_local_cache(&self, key: &str, value: &Value, ttl: u64) {
         let mut cache = self.local_cache.write().await;
-        let ttl = Duration::from_secs(self.get_ttl_for_method(method));
+        let ttl = Duration::from_secs(ttl);
         
         // Check cache size limit
-        if cache.len() >= 10000 { // TODO: make configurable
-            self.evict_local_cache_entries(&mut cache).await;
+        let limit = self.local_cache_limit.load(Ordering::Relaxed);
+        if cache.len() >= limit {
+            self.evict_local_cache_entries(&mut cache, limit).await;
         }
 
         let entry = CacheEntry {
             value: value.clone(),
+            etag: etag(value),
             expires_at: Instant::now() + ttl,
             access_count: 1,
             last_accessed: Instant::now(),
@@ -182,8 +376,53 @@ impl CacheService {
         cache.insert(key.to_string(), entry);
     }
 
-    async fn evict_local_cache_entries(&self, cache: &mut HashMap<String, CacheEntry>) {
+    pub async fn store_gossiped(&self, key_hash: u64, value: Value, ttl_remaining_secs: u64) {
+        // The TTL comes off the network; promotion clamps it to the method's own TTL
+        let Some(expires_at) = Instant::now().checked_add(Duration::from_secs(ttl_remaining_secs)) else {
+            debug!("Ignoring gossiped entry with unrepresentable TTL {}s", ttl_remaining_secs);
+            return;
+        };
+        let mut cache = self.gossiped_cache.write().await;
+        if cache.len() >= 10000 {
+            let now = Instant::now();
+            cache.retain(|_, entry| entry.expires_at > now);
+        }
+        // Still full of live entries; drop the gossip rather than grow without bound
+        if cache.len() >= 10000 {
+            self.stats.evictions.fetch_add(1, Ordering::Relaxed);
+            return;
+        }
+
+        cache.insert(key_hash, CacheEntry {
+            etag: etag(&value),
+            value,
+            expires_at,
+            access_count: 0,
+            last_accessed: Instant::now(),
+        });
+    }
+
+    // Moves a live gossiped entry into L1 under its real key, keeping the peer's expiry
+    // unless it outlives what this node would cache the method for
+    async fn promote_gossiped(&self, key: &str, method: &str) -> Option<Value> {
+        self.gossip.as_ref()?;
+
+        let mut entry = self.gossiped_cache.write().await.remove(&key_hash(key))?;
+        let now = Instant::now();
+        if entry.expires_at <= now {
+            return None;
+        }
+        entry.expires_at = entry.expires_at.min(now + Duration::from_secs(self.get_ttl_for_method(method)));
+
+        let value = entry.value.clone();
+        self.local_cache.write().await.insert(key.to_string(), entry);
+        Some(value)
+    }
+
+    // Drops expired entries, then least recently used ones until 80% of `limit` remain
+    async fn evict_local_cache_entries(&self, cache: &mut HashMap<String, CacheEntry>, limit: usize) -> usize {
         let now = Instant::now();
+        let target = limit * 4 / 5;
         let mut to_remove = Vec::new();
 
         // First, remove expired entries
@@ -194,7 +433,7 @@ impl CacheService {
         }
 
         // If still too many entries, remove least recently used
-        if cache.len() - to_remove.len() > 8000 {
+        if cache.len() - to_remove.len() > target {
             let mut entries: Vec<_> = cache
                 .iter()
                 .filter(|(key, _)| !to_remove.contains(key))
@@ -203,16 +442,51 @@ impl CacheService {
             
             entries.sort_by_key(|(_, last_accessed)| *last_accessed);
             
-            let to_evict = (cache.len() - to_remove.len()).saturating_sub(8000);
+            let to_evict = (cache.len() - to_remove.len()).saturating_sub(target);
             for (key, _) in entries.into_iter().take(to_evict) {
                 to_remove.push(key);
             }
         }
 
+        let evicted = to_remove.len();
         for key in to_remove {
             cache.remove(&key);
             self.stats.evictions.fetch_add(1, Ordering::Relaxed);
         }
+        evicted
+    }
+
+    pub fn local_cache_limit(&self) -> usize {
+        self.local_cache_limit.load(Ordering::Relaxed)
+    }
+
+    // Applies a new L1 size limit, evicting straight away if the cache is already over it.
+    // Returns the number of entries evicted.
+    pub async fn set_local_cache_limit(&self, limit: usize) -> usize {
+        self.local_cache_limit.store(limit, Ordering::Relaxed);
+        let mut cache = self.local_cache.write().await;
+        if cache.len() > limit {
+            self.evict_local_cache_entries(&mut cache, limit).await
+        } else {
+            0
+        }
+    }
+
+    // Empties L1 only, leaving Redis and disk untouched. Returns the number of entries dropped.
+    pub async fn clear_local_cache(&self) -> usize {
+        let mut cache = self.local_cache.write().await;
+        let cleared = cache.len();
+        cache.clear();
+        self.stats.evictions.fetch_add(cleared as u64, Ordering::Relaxed);
+        cleared
+    }
+
+    pub fn pause_redis_writes(&self, paused: bool) {
+        self.redis_writes_paused.store(paused, Ordering::Relaxed);
+    }
+
+    pub fn redis_writes_paused(&self) -> bool {
+        self.redis_writes_paused.load(Ordering::Relaxed)
     }
 
     async fn get_from_redis(&self, key: &str) -> Option<Value> {
@@ -240,6 +514,9 @@ impl CacheService {
     }
 
     async fn store_in_redis(&self, key: &str, value: &Value, ttl: u64) {
+        if self.redis_writes_paused() {
+            return;
+        }
         let manager_guard = self.connection_manager.read().await;
         if let Some(manager) = manager_guard.as_ref() {
             let mut conn = manager.clone();
@@ -259,7 +536,78 @@ impl CacheService {
         }
     }
 
-    fn create_cache_key(&self, method: &str, params: &Value) -> String {
+    // sled reads and writes files synchronously, so every call runs on the blocking pool
+    async fn with_disk<T: Send + 'static>(&self, f: impl FnOnce(&sled::Db) -> T + Send + 'static) -> Option<T> {
+        let db = self.disk_cache.clone()?;
+        match tokio::task::spawn_blocking(move || f(&db)).await {
+            Ok(result) => Some(result),
+            Err(e) => {
+                error!("Disk cache task failed: {}", e);
+                None
+            }
+        }
+    }
+
+    async fn get_from_disk(&self, key: &str) -> Option<(Value, u64)> {
+        let key = key.to_string();
+        let stats = self.stats.clone();
+        self.with_disk(move |db| {
+            let bytes = match db.get(&key) {
+                Ok(Some(bytes)) => bytes,
+                Ok(None) => return None,
+                Err(e) => {
+                    error!("Disk cache get error: {}", e);
+                    return None;
+                }
+            };
+
+            let entry: DiskEntry = match rmp_serde::from_slice(&bytes) {
+                Ok(entry) => entry,
+                Err(e) => {
+                    warn!("Failed to deserialize disk cache entry: {}", e);
+                    let _ = db.remove(&key);
+                    return None;
+                }
+            };
+
+            let now = chrono::Utc::now().timestamp().max(0) as u64;
+            if entry.expires_at <= now {
+                // Expired entries are dropped lazily; sled reclaims the space on compaction
+                let _ = db.remove(&key);
+                stats.evictions.fetch_add(1, Ordering::Relaxed);
+                return None;
+            }
+
+            Some((entry.value, entry.expires_at - now))
+        }).await?
+    }
+
+    async fn store_in_disk(&self, key: &str, value: &Value, ttl: u64) {
+        if self.disk_cache.is_none() {
+            return;
+        }
+
+        let entry = DiskEntry {
+            value: value.clone(),
+            expires_at: chrono::Utc::now().timestamp().max(0) as u64 + ttl,
+        };
+        let bytes = match rmp_serde::to_vec(&entry) {
+            Ok(bytes) => bytes,
+            Err(e) => {
+                error!("Failed to serialize value for disk cache: {}", e);
+                return;
+            }
+        };
+
+        let key = key.to_string();
+        self.with_disk(move |db| {
+            if let Err(e) = db.insert(key, bytes) {
+                error!("Disk cache set error: {}", e);
+            }
+        }).await;
+    }
+
+    pub fn create_cache_key(&self, method: &str, params: &Value) -> String {
         // Create a deterministic cache key
         let params_str = if params.is_null() {
             String::new()
@@ -326,6 +674,16 @@ impl CacheService {
             cache.retain(|key, _| !key.contains(pattern));
         }
 
+        // Invalidate from disk, which walks every key
+        let disk_pattern = pattern.to_string();
+        self.with_disk(move |db| {
+            for key in db.iter().keys().flatten() {
+                if String::from_utf8_lossy(&key).contains(&disk_pattern) {
+                    let _ = db.remove(key);
+                }
+            }
+        }).await;
+
         // Invalidate from Redis
         self.invalidate_redis_pattern(pattern).await;
     }
@@ -387,17 +745,34 @@ impl CacheService {
             0.0
         };
 
+        let tier_hit_rate = |tier_hits: u64| if total > 0 {
+            tier_hits as f64 / total as f64
+        } else {
+            0.0
+        };
+        let l1_hits = self.stats.l1_hits.load(Ordering::Relaxed);
+        let l2_hits = self.stats.l2_hits.load(Ordering::Relaxed);
+        let l3_hits = self.stats.l3_hits.load(Ordering::Relaxed);
+
         json!({
             "enabled": self.config.enabled,
             "local_cache_size": local_cache_size,
+            "local_cache_limit": self.local_cache_limit(),
             "redis_connected": self.connection_manager.read().await.is_some(),
+            "disk_cache_size": self.with_disk(|db| db.len()).await,
             "statistics": {
                 "hits": hits,
                 "misses": misses,
                 "hit_rate": hit_rate,
+                "tiers": {
+                    "l1": { "hits": l1_hits, "hit_rate": tier_hit_rate(l1_hits) },
+                    "l2": { "hits": l2_hits, "hit_rate": tier_hit_rate(l2_hits) },
+                    "l3": { "hits": l3_hits, "hit_rate": tier_hit_rate(l3_hits) },
+                },
                 "redis_errors": self.stats.redis_errors.load(Ordering::Relaxed),
                 "evictions": self.stats.evictions.load(Ordering::Relaxed),
                 "total_requests": self.stats.total_requests.load(Ordering::Relaxed),
+                "stampedes_prevented": self.stats.stampedes_prevented.load(Ordering::Relaxed),
             },
             "config": {
                 "default_ttl": self.config.default_ttl,
@@ -461,6 +836,13 @@ impl CacheService {
             cache.clear();
         }
 
+        // Clear disk cache
+        self.with_disk(|db| {
+            if let Err(e) = db.clear() {
+                error!("Failed to clear disk cache: {}", e);
+            }
+        }).await;
+
         // Clear Redis cache
         self.clear_redis_cache().await;
         
@@ -492,24 +874,592 @@ impl CacheService {
         }
     }
 
-    pub async fn warmup_cache(&self) {
-        // Pre-populate cache with common requests
-        info!("Starting cache warmup...");
-        
-        let common_requests = vec![
-            ("getHealth", json!(null)),
-            ("getVersion", json!(null)),
-            ("getGenesisHash", json!(null)),
-            ("getSlot", json!(null)),
-            ("getBlockHeight", json!(null)),
-        ];
+    pub async fn export_entries(&self) -> Vec<CacheEntrySnapshot> {
+        let cache = self.local_cache.read().await;
+        let now = Instant::now();
 
-        for (method, params) in common_requests {
-            let cache_key = self.create_cache_key(method, &params);
-            debug!("Warming up cache for: {}", cache_key);
-            // In practice, you'd make actual RPC calls to populate the cache
+        cache.iter()
+            .filter(|(_, entry)| entry.expires_at > now)
+            .map(|(key, entry)| CacheEntrySnapshot {
+                key: key.clone(),
+                value: entry.value.clone(),
+                ttl_remaining_secs: entry.expires_at.duration_since(now).as_secs(),
+            })
+            .collect()
+    }
+
+    pub async fn import_entries(&self, entries: Vec<CacheEntrySnapshot>) {
+        let now = Instant::now();
+        {
+            let mut cache = self.local_cache.write().await;
+            cache.clear();
+
+            for snapshot in entries.iter().filter(|e| e.ttl_remaining_secs > 0) {
+                cache.insert(snapshot.key.clone(), CacheEntry {
+                    value: snapshot.value.clone(),
+                    etag: etag(&snapshot.value),
+                    expires_at: now + Duration::from_secs(snapshot.ttl_remaining_secs),
+                    access_count: 0,
+                    last_accessed: now,
+                });
+            }
         }
-        
-        info!("Cache warmup completed");
+
+        for snapshot in entries.iter().filter(|e| e.ttl_remaining_secs > 0) {
+            self.store_in_redis(&snapshot.key, &snapshot.value, snapshot.ttl_remaining_secs).await;
+        }
+
+        info!("Imported {} cache entries", entries.len());
     }
-}
\ No newline at end of file
+
+    // Loads a JSON array of {"method", "params", "response"} objects straight into the cache,
+    // so tests can exercise cache-aware paths without firing requests first. The path is taken
+    // relative to prefill_fixture_dir and may not lead out of it.
+    pub async fn prefill_from_fixture(&self, fixture_path: &str) -> Result<usize, AppError> {
+        if !self.config.enabled {
+            return Err(AppError::cache("Cache is disabled"));
+        }
+        let Some(fixture_dir) = &self.config.prefill_fixture_dir else {
+            return Err(AppError::config("cache.prefill_fixture_dir is not set"));
+        };
+
+        let unreadable = |e: std::io::Error| AppError::validation(&format!("Failed to read fixture {}: {}", fixture_path, e));
+        let fixture_dir = tokio::fs::canonicalize(fixture_dir).await
+            .map_err(|e| AppError::config(&format!("Invalid prefill_fixture_dir {}: {}", fixture_dir, e)))?;
+        // Canonical, so neither `..` nor a symlink can point outside the directory
+        let path = tokio::fs::canonicalize(fixture_dir.join(fixture_path)).await.map_err(unreadable)?;
+        if !path.starts_with(&fixture_dir) {
+            return Err(AppError::validation(&format!("Fixture {} is outside prefill_fixture_dir", fixture_path)));
+        }
+        let contents = tokio::fs::read_to_string(&path).await.map_err(unreadable)?;
+        let raw: Vec<Value> = serde_json::from_str(&contents)
+            .map_err(|e| AppError::validation(&format!("Fixture must be a JSON array: {}", e)))?;
+
+        // Validate the whole fixture before touching the cache
+        let entries = raw.into_iter()
+            .enumerate()
+            .map(|(index, value)| {
+                let entry: FixtureEntry = serde_json::from_value(value)
+                    .map_err(|e| AppError::validation(&format!("Invalid fixture entry {}: {}", index, e)))?;
+                if entry.method.is_empty() {
+                    return Err(AppError::validation(&format!("Invalid fixture entry {}: empty method", index)));
+                }
+                Ok(entry)
+            })
+            .collect::<Result<Vec<_>, _>>()?;
+
+        let mut inserted = 0;
+        for entry in entries {
+            if !is_method_cacheable(&entry.method) {
+                debug!("Skipping fixture entry for non-cacheable method {}", entry.method);
+                continue;
+            }
+            self.set(&entry.method, &entry.params, &entry.response).await;
+            inserted += 1;
+        }
+
+        info!("Prefilled {} cache entries from {}", inserted, fixture_path);
+        Ok(inserted)
+    }
+
+    // Routes each configured warmup request so the responses land in the cache through the
+    // normal path. The router is borrowed rather than held because it already owns this service.
+    pub async fn warmup_cache(&self, router: &RpcRouter) -> WarmupReport {
+        let mut report = WarmupReport::default();
+        if !self.config.enabled || self.config.warmup_requests.is_empty() {
+            self.mark_initialized();
+            return report;
+        }
+
+        info!("Starting cache warmup with {} requests...", self.config.warmup_requests.len());
+        for (index, request) in self.config.warmup_requests.iter().enumerate() {
+            if !is_method_cacheable(&request.method) {
+                report.failed.push((request.method.clone(), "method is not cacheable".to_string()));
+                continue;
+            }
+
+            let mut payload = json!({"jsonrpc": "2.0", "id": index, "method": request.method});
+            if !request.params.is_null() {
+                payload["params"] = request.params.clone();
+            }
+            match router.route_request(payload, None, None, None).await {
+                Ok(response) if response.get("error").is_none() => {
+                    debug!("Warmed cache for {}", self.create_cache_key(&request.method, &request.params));
+                    report.warmed.push(request.method.clone());
+                }
+                Ok(response) => report.failed.push((request.method.clone(), response["error"].to_string())),
+                Err(e) => report.failed.push((request.method.clone(), e.to_string())),
+            }
+        }
+
+        info!(
+            "Cache warmup completed: {} warmed {:?}, {} failed {:?}",
+            report.warmed.len(), report.warmed, report.failed.len(), report.failed
+        );
+        self.mark_initialized();
+        report
+    }
+
+    pub fn mark_initialized(&self) {
+        self.initialized.store(true, Ordering::Relaxed);
+    }
+
+    pub fn is_initialized(&self) -> bool {
+        self.initialized.load(Ordering::Relaxed)
+    }
+}
+
+#[derive(Debug, Default)]
+pub struct WarmupReport {
+    pub warmed: Vec<String>,
+    // Method and the reason it could not be warmed
+    pub failed: Vec<(String, String)>,
+}
+
+// Drops everything that expired while the process was down, along with entries that no longer
+// decode, so a restart doesn't serve or carry stale data
+fn evict_expired_disk_entries(db: &sled::Db) -> usize {
+    let now = chrono::Utc::now().timestamp().max(0) as u64;
+    let mut evicted = 0;
+    for (key, bytes) in db.iter().flatten() {
+        let live = rmp_serde::from_slice::<DiskEntry>(&bytes).is_ok_and(|entry| entry.expires_at > now);
+        if !live && db.remove(key).is_ok() {
+            evicted += 1;
+        }
+    }
+    evicted
+}
+
+// First 8 bytes of the body's SHA-256, quoted as an HTTP entity tag
+pub fn etag(value: &Value) -> String {
+    let digest = Sha256::digest(value.to_string().as_bytes());
+    let hex: String = digest[..8].iter().map(|b| format!("{:02x}", b)).collect();
+    format!("\"{}\"", hex)
+}
+
+// Writes must reach upstream every time, so they are never answered with 304
+fn is_idempotent(method: &str) -> bool {
+    !matches!(method, "sendTransaction" | "simulateTransaction" | "requestAirdrop")
+}
+
+// Method and cache params of a single JSON-RPC call, the only shape conditional requests apply to
+pub fn conditional_call(payload: &Value) -> Option<(&str, Value)> {
+    let method = payload.get("method")?.as_str()?;
+    Some((method, payload.get("params").cloned().unwrap_or(Value::Null)))
+}
+
+// If-None-Match holds a comma-separated list of tags, possibly weak, or `*`
+pub fn etag_matches(headers: &HeaderMap, etag: &str) -> bool {
+    headers.get_all(header::IF_NONE_MATCH)
+        .iter()
+        .filter_map(|value| value.to_str().ok())
+        .flat_map(|value| value.split(','))
+        .map(|tag| tag.trim())
+        .any(|tag| tag == "*" || tag.trim_start_matches("W/") == etag)
+}
+
+// A cache hit with its ETag, or the bodiless 304 when the client already holds it
+pub enum CachedResponse {
+    Hit { body: Value, etag: String },
+    NotModified { etag: String },
+}
+
+impl IntoResponse for CachedResponse {
+    fn into_response(self) -> Response {
+        let (mut response, etag) = match self {
+            Self::Hit { body, etag } => (Json(body).into_response(), etag),
+            Self::NotModified { etag } => (StatusCode::NOT_MODIFIED.into_response(), etag),
+        };
+        if let Ok(value) = HeaderValue::from_str(&etag) {
+            response.headers_mut().insert(header::ETAG, value);
+        }
+        response
+    }
+}
+
+#[derive(Debug, Deserialize)]
+#[serde(deny_unknown_fields)]
+struct FixtureEntry {
+    method: String,
+    #[serde(default)]
+    params: Value,
+    response: Value,
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use tempfile::TempDir;
+
+    async fn cache_at(sled_cache_path: Option<&TempDir>) -> CacheService {
+        let mut config = Config::default();
+        config.cache.enabled = true;
+        // Nothing listens here, so the service runs without an L2
+        config.cache.redis_url = "redis://127.0.0.1:1".to_string();
+        config.cache.sled_cache_path = sled_cache_path.map(|dir| dir.path().to_string_lossy().to_string());
+        config.cache.prefill_fixture_dir = config.cache.sled_cache_path.clone();
+        CacheService::new(&config).await.unwrap()
+    }
+
+    // The directory is removed once the returned TempDir is dropped
+    async fn tiered_cache() -> (CacheService, TempDir) {
+        let dir = TempDir::new().unwrap();
+        (cache_at(Some(&dir)).await, dir)
+    }
+
+    #[tokio::test]
+    async fn test_l1_hit() {
+        let (cache, _dir) = tiered_cache().await;
+        let params = json!(null);
+        cache.set("getGenesisHash", &params, &json!("hash")).await;
+
+        assert_eq!(cache.get("getGenesisHash", &params).await, Some(json!("hash")));
+        assert_eq!(cache.stats.l1_hits.load(Ordering::Relaxed), 1);
+        assert_eq!(cache.stats.l3_hits.load(Ordering::Relaxed), 0);
+    }
+
+    #[tokio::test]
+    async fn test_l3_hit_promotes_to_l1() {
+        let (cache, _dir) = tiered_cache().await;
+        let params = json!(null);
+        cache.set("getGenesisHash", &params, &json!("hash")).await;
+        cache.local_cache.write().await.clear();
+
+        // First read falls through to disk and promotes the entry
+        assert_eq!(cache.get("getGenesisHash", &params).await, Some(json!("hash")));
+        assert_eq!(cache.stats.l3_hits.load(Ordering::Relaxed), 1);
+        assert_eq!(cache.local_cache.read().await.len(), 1);
+
+        // Second read is served from memory
+        assert_eq!(cache.get("getGenesisHash", &params).await, Some(json!("hash")));
+        assert_eq!(cache.stats.l1_hits.load(Ordering::Relaxed), 1);
+        assert_eq!(cache.stats.l3_hits.load(Ordering::Relaxed), 1);
+
+        let stats = cache.get_stats().await;
+        assert_eq!(stats["statistics"]["tiers"]["l3"]["hits"], 1);
+    }
+
+    #[tokio::test]
+    async fn test_l3_hit_keeps_remaining_ttl() {
+        let (cache, _dir) = tiered_cache().await;
+        let params = json!(null);
+        let key = cache.create_cache_key("getGenesisHash", &params);
+        assert!(cache.get_ttl_for_method("getGenesisHash") > 5);
+        cache.store_in_disk(&key, &json!("hash"), 5).await;
+
+        assert_eq!(cache.get("getGenesisHash", &params).await, Some(json!("hash")));
+        let expires_at = cache.local_cache.read().await[&key].expires_at;
+        assert!(expires_at <= Instant::now() + Duration::from_secs(5));
+    }
+
+    #[tokio::test]
+    async fn test_expired_disk_entry_is_evicted() {
+        let (cache, _dir) = tiered_cache().await;
+        let key = cache.create_cache_key("getGenesisHash", &json!(null));
+        cache.store_in_disk(&key, &json!("hash"), 0).await;
+
+        assert!(cache.get_from_disk(&key).await.is_none());
+        assert!(cache.disk_cache.as_ref().unwrap().get(&key).unwrap().is_none());
+    }
+
+    #[tokio::test]
+    async fn test_disk_entries_survive_restart() {
+        let dir = TempDir::new().unwrap();
+        let cache = cache_at(Some(&dir)).await;
+        cache.set("getGenesisHash", &json!(null), &json!("hash")).await;
+        let stale_key = cache.create_cache_key("getBalance", &json!(["Account1"]));
+        cache.store_in_disk(&stale_key, &json!(5), 0).await;
+        // Left over from the JSON encoding used before MessagePack
+        cache.disk_cache.as_ref().unwrap().insert("multi-rpc:legacy", br#"{"value":1,"expires_at":0}"#.to_vec()).unwrap();
+        cache.disk_cache.as_ref().unwrap().flush().unwrap();
+        drop(cache);
+
+        let restarted = cache_at(Some(&dir)).await;
+        // Expired and undecodable entries were evicted on open
+        assert_eq!(restarted.disk_cache.as_ref().unwrap().len(), 1);
+        assert_eq!(restarted.get("getGenesisHash", &json!(null)).await, Some(json!("hash")));
+        assert_eq!(restarted.stats.l3_hits.load(Ordering::Relaxed), 1);
+    }
+
+    #[test]
+    fn test_disk_entries_are_messagepack() {
+        let entry = DiskEntry { value: json!({"slot": 100, "hashes": ["a", "b"]}), expires_at: 42 };
+        let bytes = rmp_serde::to_vec(&entry).unwrap();
+        assert!(serde_json::from_slice::<Value>(&bytes).is_err());
+
+        let decoded: DiskEntry = rmp_serde::from_slice(&bytes).unwrap();
+        assert_eq!(decoded.value, entry.value);
+        assert_eq!(decoded.expires_at, 42);
+    }
+
+    // cargo test bench_disk_backed_cache -- --ignored --nocapture
+    #[tokio::test]
+    #[ignore = "benchmark"]
+    async fn bench_disk_backed_cache() {
+        const ENTRIES: usize = 2_000;
+        let response = json!({"context": {"slot": 250_000_000u64}, "value": {"lamports": 5_000_000_000u64, "owner": "11111111111111111111111111111111"}});
+
+        let dir = TempDir::new().unwrap();
+        for (label, path) in [("memory", None), ("sled", Some(&dir))] {
+            let cache = cache_at(path).await;
+            let params: Vec<Value> = (0..ENTRIES).map(|i| json!([format!("Account{}", i)])).collect();
+
+            let start = Instant::now();
+            for p in &params {
+                cache.set("getAccountInfo", p, &response).await;
+            }
+            let set_elapsed = start.elapsed();
+
+            let start = Instant::now();
+            for p in &params {
+                assert!(cache.get("getAccountInfo", p).await.is_some());
+            }
+            let hot_elapsed = start.elapsed();
+
+            // What a restart looks like: nothing in memory, everything still on disk
+            cache.local_cache.write().await.clear();
+            let start = Instant::now();
+            let mut cold_hits = 0;
+            for p in &params {
+                cold_hits += cache.get("getAccountInfo", p).await.is_some() as usize;
+            }
+            let cold_elapsed = start.elapsed();
+
+            println!(
+                "{:>6}: set {:>8.2}us/op, hot get {:>6.2}us/op, cold get {:>6.2}us/op ({} of {} served)",
+                label,
+                set_elapsed.as_secs_f64() * 1e6 / ENTRIES as f64,
+                hot_elapsed.as_secs_f64() * 1e6 / ENTRIES as f64,
+                cold_elapsed.as_secs_f64() * 1e6 / ENTRIES as f64,
+                cold_hits,
+                ENTRIES,
+            );
+        }
+    }
+
+    #[tokio::test]
+    async fn test_miss_writes_all_tiers_on_set() {
+        let (cache, _dir) = tiered_cache().await;
+        let params = json!(null);
+        assert_eq!(cache.get("getGenesisHash", &params).await, None);
+
+        cache.set("getGenesisHash", &params, &json!("hash")).await;
+        assert_eq!(cache.local_cache.read().await.len(), 1);
+        assert_eq!(cache.disk_cache.as_ref().unwrap().len(), 1);
+    }
+
+    fn write_fixture(dir: &TempDir, entries: &Value) -> String {
+        std::fs::write(dir.path().join("fixture.json"), entries.to_string()).unwrap();
+        "fixture.json".to_string()
+    }
+
+    #[tokio::test]
+    async fn test_prefill_from_fixture() {
+        let (cache, dir) = tiered_cache().await;
+        let entries: Vec<Value> = (0..50)
+            .map(|i| json!({
+                "method": "getAccountInfo",
+                "params": [format!("Account{}", i)],
+                "response": {"jsonrpc": "2.0", "id": i, "result": {"value": {"lamports": i}}},
+            }))
+            .collect();
+        let path = write_fixture(&dir, &Value::Array(entries));
+
+        assert_eq!(cache.prefill_from_fixture(&path).await.unwrap(), 50);
+
+        for i in 0..50 {
+            let cached = cache.get("getAccountInfo", &json!([format!("Account{}", i)])).await.unwrap();
+            assert_eq!(cached["result"]["value"]["lamports"], i);
+        }
+        for i in 50..100 {
+            assert!(cache.get("getAccountInfo", &json!([format!("Account{}", i)])).await.is_none());
+        }
+
+        let stats = cache.get_stats().await;
+        assert_eq!(stats["statistics"]["hits"], 50);
+        assert_eq!(stats["statistics"]["hit_rate"], 0.5);
+    }
+
+    #[tokio::test]
+    async fn test_prefill_rejects_invalid_fixture() {
+        let (cache, dir) = tiered_cache().await;
+        let path = write_fixture(&dir, &json!([
+            {"method": "getGenesisHash", "response": "hash"},
+            {"method": "getGenesisHash", "params": null},
+        ]));
+
+        assert!(cache.prefill_from_fixture(&path).await.is_err());
+        // Nothing is inserted when any entry is invalid
+        assert!(cache.get("getGenesisHash", &json!(null)).await.is_none());
+    }
+
+    #[tokio::test]
+    async fn test_prefill_stays_inside_fixture_dir() {
+        let (cache, dir) = tiered_cache().await;
+        let outside = TempDir::new().unwrap();
+        let fixture = json!([{"method": "getGenesisHash", "response": "hash"}]);
+        let path = write_fixture(&outside, &fixture);
+        let absolute = outside.path().join(&path);
+        let relative = format!("../{}/{}", outside.path().file_name().unwrap().to_string_lossy(), path);
+        let mut escapes = vec![absolute.to_string_lossy().to_string(), relative];
+        #[cfg(unix)]
+        {
+            std::os::unix::fs::symlink(&absolute, dir.path().join("link.json")).unwrap();
+            escapes.push("link.json".to_string());
+        }
+
+        for fixture_path in escapes {
+            let error = cache.prefill_from_fixture(&fixture_path).await.unwrap_err();
+            assert!(error.to_string().contains("outside prefill_fixture_dir"), "{}: {}", fixture_path, error);
+        }
+        assert!(cache.get("getGenesisHash", &json!(null)).await.is_none());
+
+        // Without a configured directory nothing can be read at all
+        let unconfigured = cache_at(None).await;
+        assert!(unconfigured.prefill_from_fixture(&absolute.to_string_lossy()).await.is_err());
+    }
+
+    #[tokio::test]
+    async fn test_prefill_endpoint_requires_admin() {
+        use crate::test_server::TestServerBuilder;
+        use reqwest::StatusCode;
+
+        let dir = TempDir::new().unwrap();
+        let path = write_fixture(&dir, &json!([{"method": "getGenesisHash", "response": "hash"}]));
+        let fixture_dir = dir.path().to_string_lossy().to_string();
+        let server = TestServerBuilder::new()
+            .with_config(|config| {
+                config.enable_debug_endpoints = true;
+                config.auth.enabled = true;
+                config.auth.require_auth_for_admin = true;
+                config.cache.enabled = true;
+                config.cache.redis_url = "redis://127.0.0.1:1".to_string();
+                config.cache.prefill_fixture_dir = Some(fixture_dir);
+            })
+            .start()
+            .await;
+        let prefill = |token: Option<String>| {
+            let mut request = server.client.post(server.url("/debug/cache/prefill"))
+                .json(&json!({"fixture_path": path}));
+            if let Some(token) = token {
+                request = request.bearer_auth(token);
+            }
+            async move { request.send().await.unwrap().status() }
+        };
+
+        assert_eq!(prefill(None).await, StatusCode::FORBIDDEN);
+        let api_token = server.state.auth_service.create_jwt("client", vec!["api".to_string()]).await.unwrap();
+        assert_eq!(prefill(Some(api_token)).await, StatusCode::FORBIDDEN);
+        let admin_token = server.state.auth_service.create_jwt("admin", vec!["admin".to_string()]).await.unwrap();
+        assert_eq!(prefill(Some(admin_token)).await, StatusCode::OK);
+    }
+
+    #[tokio::test]
+    async fn test_warmup_populates_cache_through_router() {
+        use crate::{config::WarmupRequest, test_server::TestServerBuilder};
+        use wiremock::{matchers::body_partial_json, Mock};
+
+        let warmup = |method: &str, params: Value| WarmupRequest { method: method.to_string(), params };
+        let server = TestServerBuilder::new()
+            .with_config(|config| {
+                config.cache.enabled = true;
+                config.cache.warmup_requests = vec![
+                    warmup("getGenesisHash", Value::Null),
+                    warmup("getMinimumBalanceForRentExemption", json!([165])),
+                    warmup("getIdentity", Value::Null),
+                    warmup("getSlot", Value::Null),
+                ];
+            })
+            .start()
+            .await;
+        for (method, result) in [("getGenesisHash", json!("genesis")), ("getMinimumBalanceForRentExemption", json!(2039280))] {
+            Mock::given(body_partial_json(json!({"method": method})))
+                .respond_with(crate::test_server::rpc_result(result))
+                .mount(server.endpoint_mock("primary"))
+                .await;
+        }
+
+        let cache = &server.state.cache_service;
+        let report = cache.warmup_cache(&server.state.rpc_router).await;
+
+        assert_eq!(report.warmed, vec!["getGenesisHash", "getMinimumBalanceForRentExemption"]);
+        let failed: Vec<&str> = report.failed.iter().map(|(method, _)| method.as_str()).collect();
+        assert_eq!(failed, vec!["getIdentity", "getSlot"]);
+        assert!(cache.get("getGenesisHash", &Value::Null).await.is_some());
+        assert!(cache.get("getMinimumBalanceForRentExemption", &json!([165])).await.is_some());
+        assert!(cache.get("getIdentity", &Value::Null).await.is_none());
+    }
+
+    #[tokio::test]
+    async fn test_set_generates_etag() {
+        let cache = cache_at(None).await;
+        let params = json!(["So11111111111111111111111111111111111111112"]);
+        let response = json!({"jsonrpc": "2.0", "id": 1, "result": {"value": 5}});
+        assert_eq!(cache.cached_etag("getBalance", &params).await, None);
+
+        cache.set("getBalance", &params, &response).await;
+
+        let etag = cache.cached_etag("getBalance", &params).await.unwrap();
+        assert_eq!(etag, super::etag(&response));
+        assert_eq!(etag.len(), 18);
+        assert_ne!(etag, super::etag(&json!({"jsonrpc": "2.0", "id": 1, "result": {"value": 6}})));
+        assert_eq!(cache.cached_etag("sendTransaction", &params).await, None);
+    }
+
+    #[test]
+    fn test_if_none_match_parsing() {
+        let etag = "\"0123456789abcdef\"";
+        let headers = |value: &str| {
+            let mut headers = HeaderMap::new();
+            headers.insert(header::IF_NONE_MATCH, HeaderValue::from_str(value).unwrap());
+            headers
+        };
+
+        assert!(etag_matches(&headers(etag), etag));
+        assert!(etag_matches(&headers("\"ffff\", W/\"0123456789abcdef\""), etag));
+        assert!(etag_matches(&headers("*"), etag));
+        assert!(!etag_matches(&headers("\"ffff\""), etag));
+        assert!(!etag_matches(&HeaderMap::new(), etag));
+    }
+
+    #[tokio::test]
+    async fn test_get_or_insert_fills_once_under_concurrency() {
+        let cache = cache_at(None).await;
+        let fills = Arc::new(AtomicUsize::new(0));
+
+        let callers = (0..20).map(|_| {
+            let cache = cache.clone();
+            let fills = fills.clone();
+            tokio::spawn(async move {
+                cache.get_or_insert("getGenesisHash", &json!(null), || async {
+                    fills.fetch_add(1, Ordering::SeqCst);
+                    tokio::time::sleep(Duration::from_millis(50)).await;
+                    Ok(json!("hash"))
+                }).await
+            })
+        }).collect::<Vec<_>>();
+        for caller in callers {
+            assert_eq!(caller.await.unwrap().unwrap(), json!("hash"));
+        }
+
+        assert_eq!(fills.load(Ordering::SeqCst), 1);
+        let stats = cache.get_stats().await;
+        assert_eq!(stats["statistics"]["stampedes_prevented"], 19);
+        assert!(cache.fill_locks.is_empty());
+    }
+
+    #[tokio::test]
+    async fn test_get_or_insert_does_not_cache_failures() {
+        let cache = cache_at(None).await;
+        let params = json!(null);
+
+        let failed = cache.get_or_insert("getGenesisHash", &params, || async {
+            Err(AppError::endpoint("upstream down"))
+        }).await;
+        assert!(failed.is_err());
+
+        let filled = cache.get_or_insert("getGenesisHash", &params, || async { Ok(json!("hash")) }).await;
+        assert_eq!(filled.unwrap(), json!("hash"));
+        assert_eq!(cache.get("getGenesisHash", &params).await, Some(json!("hash")));
+    }
+}
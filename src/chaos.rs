@@ -0,0 +1,260 @@
+use crate::{
+    error::AppError,
+    metrics::MetricsService,
+    AppState,
+};
+use axum::{
+    extract::State,
+    response::Json,
+};
+use serde::{Deserialize, Serialize};
+use serde_json::{json, Value};
+use std::{
+    sync::{Arc, RwLock},
+    time::{Duration, Instant},
+};
+use tracing::{info, warn};
+use uuid::Uuid;
+
+// Which side of the partition the proxy can no longer reach. The proxy sits on the
+// sending side, so "AtoB" cuts off group B, "BtoA" cuts off group A and "both" isolates
+// every endpoint in either group.
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+pub enum BlockDirection {
+    AtoB,
+    BtoA,
+    #[serde(rename = "both")]
+    Both,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct PartitionConfig {
+    pub group_a: Vec<Uuid>,
+    pub group_b: Vec<Uuid>,
+    pub block_direction: BlockDirection,
+    pub duration_seconds: u64,
+}
+
+#[derive(Debug, Clone)]
+struct ActivePartition {
+    config: PartitionConfig,
+    expires_at: Instant,
+}
+
+// Simulates network partitions between endpoint groups for split-brain testing
+#[derive(Debug)]
+pub struct NetworkPartitionSimulator {
+    active: RwLock<Option<ActivePartition>>,
+    metrics_service: Arc<MetricsService>,
+}
+
+impl NetworkPartitionSimulator {
+    pub fn new(metrics_service: Arc<MetricsService>) -> Self {
+        Self {
+            active: RwLock::new(None),
+            metrics_service,
+        }
+    }
+
+    pub fn activate(&self, config: PartitionConfig) -> Result<(), AppError> {
+        if config.group_a.is_empty() && config.group_b.is_empty() {
+            return Err(AppError::validation("Partition needs at least one endpoint in a group"));
+        }
+        if config.duration_seconds == 0 {
+            return Err(AppError::validation("Partition duration_seconds must be greater than 0"));
+        }
+        if config.group_a.iter().any(|id| config.group_b.contains(id)) {
+            return Err(AppError::validation("An endpoint cannot be in both partition groups"));
+        }
+
+        warn!(
+            "Network partition activated for {}s: {} endpoints in group A, {} in group B ({:?})",
+            config.duration_seconds, config.group_a.len(), config.group_b.len(), config.block_direction
+        );
+        let expires_at = Instant::now() + Duration::from_secs(config.duration_seconds);
+        *self.active.write().unwrap() = Some(ActivePartition { config, expires_at });
+        Ok(())
+    }
+
+    pub fn lift(&self) -> bool {
+        let lifted = self.active.write().unwrap().take().is_some();
+        if lifted {
+            info!("Network partition lifted");
+        }
+        lifted
+    }
+
+    pub fn status(&self) -> Value {
+        match self.current() {
+            Some(partition) => json!({
+                "active": true,
+                "config": partition.config,
+                "remaining_seconds": partition.expires_at.saturating_duration_since(Instant::now()).as_secs(),
+            }),
+            None => json!({"active": false}),
+        }
+    }
+
+    // Returns true (and counts it) if requests to this endpoint should be dropped
+    pub fn check_blocked(&self, endpoint_id: Uuid) -> bool {
+        let Some(partition) = self.current() else {
+            return false;
+        };
+
+        let config = &partition.config;
+        let blocked = match config.block_direction {
+            BlockDirection::AtoB => config.group_b.contains(&endpoint_id),
+            BlockDirection::BtoA => config.group_a.contains(&endpoint_id),
+            BlockDirection::Both => {
+                config.group_a.contains(&endpoint_id) || config.group_b.contains(&endpoint_id)
+            }
+        };
+
+        if blocked {
+            self.metrics_service.record_partition_blocked_request();
+        }
+        blocked
+    }
+
+    // Expired partitions are cleared lazily on the next lookup
+    fn current(&self) -> Option<ActivePartition> {
+        let partition = self.active.read().unwrap().clone()?;
+        if Instant::now() < partition.expires_at {
+            return Some(partition);
+        }
+
+        let mut active = self.active.write().unwrap();
+        if active.as_ref().is_some_and(|p| p.expires_at <= Instant::now()) {
+            *active = None;
+            info!("Network partition expired");
+        }
+        None
+    }
+}
+
+pub async fn handle_activate_partition(
+    State(state): State<Arc<AppState>>,
+    Json(config): Json<PartitionConfig>,
+) -> Result<Json<Value>, AppError> {
+    state.partition_simulator.activate(config)?;
+    Ok(Json(state.partition_simulator.status()))
+}
+
+pub async fn handle_lift_partition(
+    State(state): State<Arc<AppState>>,
+) -> Result<Json<Value>, AppError> {
+    let lifted = state.partition_simulator.lift();
+    Ok(Json(json!({"lifted": lifted})))
+}
+
+pub async fn handle_partition_status(
+    State(state): State<Arc<AppState>>,
+) -> Result<Json<Value>, AppError> {
+    Ok(Json(state.partition_simulator.status()))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{
+        config::ConsensusConfig,
+        consensus::{ConsensusRequest, ConsensusService},
+        endpoints::EndpointManager,
+        test_server::{shared_metrics, TestServer, TestServerBuilder},
+    };
+    use std::collections::HashMap;
+    use wiremock::{matchers::method, Mock, ResponseTemplate};
+
+    async fn mock_endpoints(count: usize) -> TestServer {
+        let server = (0..count)
+            .fold(TestServerBuilder::new(), |builder, i| builder.with_endpoint(&format!("node-{}", i)))
+            .start()
+            .await;
+        for mock in &server.mocks {
+            Mock::given(method("POST"))
+                .respond_with(ResponseTemplate::new(200).set_body_json(json!({
+                    "jsonrpc": "2.0",
+                    "id": 1,
+                    "result": {"context": {"slot": 100}, "value": 5_000_000_000u64}
+                })))
+                .mount(mock)
+                .await;
+        }
+        server
+    }
+
+    async fn consensus_request(manager: &EndpointManager) -> (ConsensusRequest, HashMap<Uuid, reqwest::Client>) {
+        let endpoints = manager.get_endpoint_info().await;
+        let mut clients = HashMap::new();
+        for endpoint in &endpoints {
+            clients.insert(endpoint.id, manager.get_endpoint_client(endpoint.id).await.unwrap());
+        }
+        let request = ConsensusRequest {
+            method: "getBalance".to_string(),
+            params: json!(["Account1"]),
+            endpoints,
+            require_consensus: true,
+            forwarded_headers: HashMap::new(),
+        };
+        (request, clients)
+    }
+
+    #[tokio::test]
+    async fn test_consensus_fails_during_full_partition_and_recovers() {
+        let server = mock_endpoints(4).await;
+        let manager = &server.state.endpoint_manager;
+        let ids: Vec<Uuid> = manager.get_endpoint_info().await.into_iter().map(|e| e.id).collect();
+
+        let simulator = Arc::new(NetworkPartitionSimulator::new(shared_metrics()));
+        let consensus = ConsensusService::new(ConsensusConfig {
+            enabled: true,
+            min_confirmations: 2,
+            min_quorum_fraction: 0.5,
+            timeout_ms: 2000,
+            critical_methods: vec!["getBalance".to_string()],
+            consensus_threshold: 0.67,
+            max_deviation: 0.05,
+            consensus_snapshot_path: None,
+            max_snapshot_age_secs: 300,
+            hash_threshold_bytes: 4096,
+        }).with_partition_simulator(simulator.clone());
+
+        simulator.activate(PartitionConfig {
+            group_a: ids[..2].to_vec(),
+            group_b: ids[2..].to_vec(),
+            block_direction: BlockDirection::Both,
+            duration_seconds: 60,
+        }).unwrap();
+
+        let (request, clients) = consensus_request(manager).await;
+        assert!(matches!(
+            consensus.validate_response(request, clients).await,
+            Err(AppError::InsufficientConfirmations)
+        ));
+
+        assert!(simulator.lift());
+        let (request, clients) = consensus_request(manager).await;
+        let result = consensus.validate_response(request, clients).await.unwrap();
+        assert!(result.consensus_achieved);
+        assert_eq!(result.response["result"]["value"], 5_000_000_000u64);
+    }
+
+    #[tokio::test]
+    async fn test_partition_direction_and_expiry() {
+        let simulator = NetworkPartitionSimulator::new(shared_metrics());
+        let (a, b) = (Uuid::new_v4(), Uuid::new_v4());
+
+        simulator.activate(PartitionConfig {
+            group_a: vec![a],
+            group_b: vec![b],
+            block_direction: BlockDirection::AtoB,
+            duration_seconds: 1,
+        }).unwrap();
+        assert!(!simulator.check_blocked(a));
+        assert!(simulator.check_blocked(b));
+
+        tokio::time::sleep(Duration::from_millis(1100)).await;
+        assert!(!simulator.check_blocked(b));
+        assert_eq!(simulator.status()["active"], false);
+    }
+}
@@ -0,0 +1,236 @@
+use crate::{
+    events::{EventBus, SystemEvent},
+    types::CircuitBreakerState,
+};
+use dashmap::DashMap;
+use std::{
+    collections::HashMap,
+    sync::Arc,
+    time::{Duration, Instant},
+};
+use tracing::{info, warn};
+use uuid::Uuid;
+
+const DEFAULT_FAILURE_THRESHOLD: u32 = 5;
+const DEFAULT_OPEN_TIMEOUT: Duration = Duration::from_secs(30);
+
+#[derive(Debug, Clone)]
+struct CircuitBreaker {
+    state: CircuitBreakerState,
+    failure_count: u32,
+    last_failure: Option<Instant>,
+}
+
+impl Default for CircuitBreaker {
+    fn default() -> Self {
+        Self {
+            state: CircuitBreakerState::Closed,
+            failure_count: 0,
+            last_failure: None,
+        }
+    }
+}
+
+#[derive(Debug, Clone)]
+pub struct CircuitBreakerSnapshot {
+    pub state: CircuitBreakerState,
+    pub failure_count: u32,
+    pub last_failure: Option<Instant>,
+}
+
+// Canonical per-endpoint circuit breaker state, shared by everything that needs to
+// read or drive it. State transitions are published on the event bus.
+#[derive(Debug)]
+pub struct CircuitBreakerRegistry {
+    breakers: DashMap<Uuid, CircuitBreaker>,
+    event_bus: Arc<EventBus>,
+    failure_threshold: u32,
+    open_timeout: Duration,
+}
+
+impl CircuitBreakerRegistry {
+    pub fn new(event_bus: Arc<EventBus>) -> Self {
+        Self {
+            breakers: DashMap::new(),
+            event_bus,
+            failure_threshold: DEFAULT_FAILURE_THRESHOLD,
+            open_timeout: DEFAULT_OPEN_TIMEOUT,
+        }
+    }
+
+    pub fn with_failure_threshold(mut self, failure_threshold: u32) -> Self {
+        self.failure_threshold = failure_threshold.max(1);
+        self
+    }
+
+    // How long a breaker stays open before letting a trial request through
+    pub fn with_open_timeout(mut self, open_timeout: Duration) -> Self {
+        self.open_timeout = open_timeout;
+        self
+    }
+
+    pub fn event_bus(&self) -> &Arc<EventBus> {
+        &self.event_bus
+    }
+
+    pub fn register(&self, endpoint_id: Uuid) {
+        self.breakers.entry(endpoint_id).or_default();
+    }
+
+    pub fn remove(&self, endpoint_id: Uuid) {
+        self.breakers.remove(&endpoint_id);
+    }
+
+    pub fn record_success(&self, endpoint_id: Uuid) {
+        let transition = self.breakers.get_mut(&endpoint_id).map(|mut breaker| {
+            let from = breaker.state;
+            breaker.failure_count = 0;
+            breaker.last_failure = None;
+            breaker.state = CircuitBreakerState::Closed;
+            (from, breaker.state)
+        });
+        self.publish_transition(endpoint_id, transition);
+    }
+
+    pub fn record_failure(&self, endpoint_id: Uuid) {
+        let threshold = self.failure_threshold;
+        let transition = self.breakers.get_mut(&endpoint_id).map(|mut breaker| {
+            let from = breaker.state;
+            breaker.failure_count += 1;
+            breaker.last_failure = Some(Instant::now());
+
+            // A failed trial request while half-open re-opens immediately
+            if breaker.failure_count >= threshold || from == CircuitBreakerState::HalfOpen {
+                breaker.state = CircuitBreakerState::Open;
+            }
+            (from, breaker.state)
+        });
+        self.publish_transition(endpoint_id, transition);
+    }
+
+    // Whether a request may be sent to the endpoint. Moves an open breaker to
+    // half-open once its timeout has passed. Unknown endpoints are allowed.
+    pub fn can_attempt(&self, endpoint_id: Uuid) -> bool {
+        let open_timeout = self.open_timeout;
+        let Some(mut breaker) = self.breakers.get_mut(&endpoint_id) else {
+            return true;
+        };
+
+        let state = breaker.state;
+        match state {
+            CircuitBreakerState::Closed | CircuitBreakerState::HalfOpen => true,
+            CircuitBreakerState::Open => {
+                let timed_out = breaker.last_failure.is_some_and(|t| t.elapsed() > open_timeout);
+                if timed_out {
+                    breaker.state = CircuitBreakerState::HalfOpen;
+                    drop(breaker);
+                    self.publish_transition(endpoint_id, Some((CircuitBreakerState::Open, CircuitBreakerState::HalfOpen)));
+                }
+                timed_out
+            }
+        }
+    }
+
+    pub fn get_state(&self, endpoint_id: Uuid) -> Option<CircuitBreakerState> {
+        self.breakers.get(&endpoint_id).map(|breaker| breaker.state)
+    }
+
+    pub fn snapshot(&self, endpoint_id: Uuid) -> Option<CircuitBreakerSnapshot> {
+        self.breakers.get(&endpoint_id).map(|breaker| CircuitBreakerSnapshot {
+            state: breaker.state,
+            failure_count: breaker.failure_count,
+            last_failure: breaker.last_failure,
+        })
+    }
+
+    pub fn states(&self) -> HashMap<Uuid, CircuitBreakerState> {
+        self.breakers.iter()
+            .map(|entry| (*entry.key(), entry.value().state))
+            .collect()
+    }
+
+    fn publish_transition(&self, endpoint_id: Uuid, transition: Option<(CircuitBreakerState, CircuitBreakerState)>) {
+        let Some((from, to)) = transition else {
+            return;
+        };
+        if from == to {
+            return;
+        }
+
+        match to {
+            CircuitBreakerState::Open => warn!("Circuit breaker opened for endpoint {}", endpoint_id),
+            _ => info!("Circuit breaker for endpoint {} moved from {} to {}", endpoint_id, from.as_str(), to.as_str()),
+        }
+        self.event_bus.publish(SystemEvent::CircuitBreakerStateChanged { endpoint_id, from, to });
+    }
+}
+
+impl Default for CircuitBreakerRegistry {
+    fn default() -> Self {
+        Self::new(Arc::new(EventBus::new()))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn transitions(rx: &mut tokio::sync::broadcast::Receiver<SystemEvent>) -> Vec<(CircuitBreakerState, CircuitBreakerState)> {
+        let mut seen = Vec::new();
+        while let Ok(SystemEvent::CircuitBreakerStateChanged { from, to, .. }) = rx.try_recv() {
+            seen.push((from, to));
+        }
+        seen
+    }
+
+    #[test]
+    fn test_state_transitions_publish_events() {
+        let registry = CircuitBreakerRegistry::default()
+            .with_failure_threshold(3)
+            .with_open_timeout(Duration::from_millis(20));
+        let mut rx = registry.event_bus().subscribe();
+        let id = Uuid::new_v4();
+        registry.register(id);
+
+        registry.record_failure(id);
+        registry.record_failure(id);
+        assert_eq!(registry.get_state(id), Some(CircuitBreakerState::Closed));
+        assert!(registry.can_attempt(id));
+
+        registry.record_failure(id);
+        assert_eq!(registry.get_state(id), Some(CircuitBreakerState::Open));
+        assert!(!registry.can_attempt(id));
+
+        std::thread::sleep(Duration::from_millis(30));
+        assert!(registry.can_attempt(id));
+        assert_eq!(registry.get_state(id), Some(CircuitBreakerState::HalfOpen));
+
+        // A failed trial goes straight back to open
+        registry.record_failure(id);
+        assert_eq!(registry.get_state(id), Some(CircuitBreakerState::Open));
+
+        std::thread::sleep(Duration::from_millis(30));
+        assert!(registry.can_attempt(id));
+        registry.record_success(id);
+        assert_eq!(registry.get_state(id), Some(CircuitBreakerState::Closed));
+
+        use CircuitBreakerState::*;
+        assert_eq!(transitions(&mut rx), vec![
+            (Closed, Open),
+            (Open, HalfOpen),
+            (HalfOpen, Open),
+            (Open, HalfOpen),
+            (HalfOpen, Closed),
+        ]);
+    }
+
+    #[test]
+    fn test_unknown_endpoints_are_allowed() {
+        let registry = CircuitBreakerRegistry::default();
+        let id = Uuid::new_v4();
+
+        assert!(registry.can_attempt(id));
+        registry.record_failure(id);
+        assert_eq!(registry.get_state(id), None);
+    }
+}
@@ -1,15 +1,63 @@
+use schemars::JsonSchema;
 use serde::{Deserialize, Serialize};
 use std::time::Duration;
+use crate::bulkhead::BulkheadConfig;
 use crate::error::AppError;
+use crate::monitoring::MonitoringConfig;
+use crate::retry::RetryStrategyConfig;
+use crate::scheduler::SchedulerClass;
+use crate::types::{EndpointKind, LoadBalancingStrategy, DEFAULT_LATENCY_WINDOW};
 use std::collections::HashMap;
+use ipnetwork::IpNetwork;
 
-#[derive(Debug, Clone, Serialize, Deserialize)]
+#[derive(Debug, Clone, Serialize, Deserialize, JsonSchema)]
 pub struct Config {
     pub bind_address: String,
     pub endpoints: Vec<EndpointConfig>,
     pub health_check_interval: u64,
     pub request_timeout: u64,
     pub max_retries: usize,
+    /// Backoff between retries of a failed upstream call
+    #[serde(default)]
+    pub retry_strategy: RetryStrategyConfig,
+    /// Concurrency limits per subsystem, e.g. "websocket" or "consensus"; a "default" entry
+    /// applies to every bulkhead not listed
+    #[serde(default)]
+    pub bulkheads: HashMap<String, BulkheadConfig>,
+    #[serde(default)]
+    pub load_balancing_strategy: LoadBalancingStrategy,
+    /// Overrides load_balancing_strategy for individual RPC methods
+    #[serde(default)]
+    pub method_strategies: HashMap<String, LoadBalancingStrategy>,
+    /// Sends a method to the endpoints carrying a tag, e.g. getBlock = "archive"
+    #[serde(default)]
+    pub method_tags: HashMap<String, String>,
+    #[serde(default)]
+    pub egress_rate_limit_bps: Option<u64>,
+    /// Larger request bodies, declared or chunked, are rejected with 413
+    #[serde(default = "default_max_request_body_bytes")]
+    pub max_request_body_bytes: usize,
+    /// A method's upstream responses are refused once their median size over the most recent
+    /// ones exceeds this, which usually means a missing or oversized `limit` param
+    #[serde(default)]
+    pub max_response_size_bytes: Option<usize>,
+    /// How long shutdown waits for in-flight RPC requests before exiting anyway
+    #[serde(default = "default_shutdown_timeout_secs")]
+    pub shutdown_timeout_secs: u64,
+    /// Requests from these ranges are rejected with 403 before reaching any handler
+    #[serde(default)]
+    #[schemars(with = "Vec<String>")]
+    pub blacklist_cidrs: Vec<IpNetwork>,
+    /// Requests from these ranges skip rate limiting; must not overlap blacklist_cidrs
+    #[serde(default)]
+    #[schemars(with = "Vec<String>")]
+    pub whitelist_cidrs: Vec<IpNetwork>,
+    /// Enables test-only endpoints such as POST /debug/cache/prefill
+    #[serde(default)]
+    pub enable_debug_endpoints: bool,
+    /// Recent responses per endpoint that the latency percentiles are computed over
+    #[serde(default = "default_latency_window_size")]
+    pub latency_window_size: usize,
     pub auth: AuthConfig,
     pub cache: CacheConfig,
     pub consensus: ConsensusConfig,
@@ -19,9 +67,67 @@ pub struct Config {
     pub websocket: WebSocketConfig,
     pub admin: AdminConfig,
     pub discovery: DiscoveryConfig,
+    #[serde(default)]
+    pub monitoring: MonitoringConfig,
+    #[serde(default)]
+    pub latency_anomaly: LatencyAnomalyConfig,
+    #[serde(default)]
+    pub health_gradient: HealthGradientConfig,
+    #[serde(default)]
+    pub health: HealthConfig,
+    /// Thresholds for the Prometheus alerting rules served at /metrics/alerts
+    #[serde(default)]
+    pub alerting: AlertingConfig,
+    /// Methods whose list parameter is split across several upstream calls when too long
+    #[serde(default)]
+    pub batch_splitting: HashMap<String, BatchSplitConfig>,
+    #[serde(default)]
+    pub message_signing: MessageSigningConfig,
+    #[serde(default)]
+    pub tls_monitor: TlsMonitorConfig,
+    #[serde(default)]
+    pub streaming: StreamingConfig,
+    #[serde(default)]
+    pub sticky_sessions: StickySessionConfig,
+    #[serde(default)]
+    pub request_deduplication: RequestDeduplicationConfig,
+    /// Order queued upstream calls are dispatched in once the router is saturated
+    #[serde(default)]
+    pub scheduler_priorities: SchedulerPriorities,
+    #[serde(default)]
+    pub peers: PeerConfig,
+    /// Applied in order to every upstream response before it is cached or returned
+    #[serde(default)]
+    pub response_transforms: Vec<ResponseTransformStep>,
+    /// Applied in order to every response on its way to the client, after it is cached
+    #[serde(default)]
+    pub transforms: Vec<TransformConfig>,
+    #[serde(default)]
+    pub epoch_tracker: EpochTrackerConfig,
+    #[serde(default)]
+    pub memory_pressure: MemoryPressureConfig,
+    #[serde(default)]
+    pub body_logger: BodyLoggerConfig,
+    #[serde(default)]
+    pub logging: RequestLoggingConfig,
+    #[serde(default)]
+    pub auto_reload: AutoReloadConfig,
+    #[serde(default)]
+    pub audit: AuditConfig,
+    #[serde(default)]
+    pub graphql: GraphqlConfig,
+    /// Notified when an endpoint's circuit breaker opens or closes
+    #[serde(default)]
+    pub webhooks: Vec<WebhookConfig>,
+    /// Result served per method, e.g. getHealth => "ok", when every endpoint is unhealthy
+    #[serde(default)]
+    pub fallback_responses: HashMap<String, serde_json::Value>,
+    /// Endpoints pulled from Consul or Kubernetes in addition to the static list above
+    #[serde(default)]
+    pub service_discovery: Option<ServiceDiscoveryConfig>,
 }
 
-#[derive(Debug, Clone, Serialize, Deserialize)]
+#[derive(Debug, Clone, Serialize, Deserialize, JsonSchema)]
 pub struct EndpointConfig {
     pub url: String,
     pub name: String,
@@ -33,18 +139,110 @@ pub struct EndpointConfig {
     pub features: Vec<String>,
     pub max_connections: Option<u32>,
     pub auth_token: Option<String>,
+    /// PEM client certificate and key for endpoints that require mutual TLS
+    #[serde(default)]
+    pub tls_cert_path: Option<String>,
+    #[serde(default)]
+    pub tls_key_path: Option<String>,
+    /// PEM bundle of extra CA certificates to trust, for endpoints behind a private CA
+    #[serde(default)]
+    pub tls_ca_path: Option<String>,
+    /// HTTP statuses a health check accepts; anything else marks the endpoint down
+    #[serde(default = "default_health_status_codes")]
+    pub health_status_codes: Vec<u16>,
+    /// Field the getHealth response must carry to count as healthy (`result` when unset)
+    #[serde(default)]
+    pub health_response_key: Option<String>,
+    /// Grpc endpoints are Yellowstone Geyser nodes and answer only the methods in transport.rs
+    #[serde(default)]
+    pub kind: EndpointKind,
+    /// HMAC request signing for gateways that require it
+    #[serde(default)]
+    pub signing: Option<SigningConfig>,
+    /// Pools the endpoint belongs to, e.g. "archive" or "full-node"; see method_tags
+    #[serde(default)]
+    pub tags: Vec<String>,
+    /// Canary endpoints only take the sampled share of traffic set by canary_weight, and
+    /// their answers are compared against a regular endpoint's
+    #[serde(default)]
+    pub canary: bool,
+    /// Percentage (0-100) of requests sent to this canary
+    #[serde(default)]
+    #[schemars(range(max = 100))]
+    pub canary_weight: u8,
+    /// Methods this endpoint serves; unset or empty means all of them
+    #[serde(default)]
+    pub allowed_methods: Option<Vec<String>>,
+    /// Methods this endpoint never serves, even if listed in allowed_methods
+    #[serde(default)]
+    pub denied_methods: Option<Vec<String>>,
+    /// Client request headers passed on to this endpoint, e.g. "X-Solana-Commitment"; no others are
+    #[serde(default)]
+    pub forward_headers: Vec<String>,
+}
+
+impl EndpointConfig {
+    pub fn allows_method(&self, method: &str) -> bool {
+        let allowed = self.allowed_methods.as_ref()
+            .is_none_or(|allowed| allowed.is_empty() || allowed.iter().any(|m| m == method));
+        let denied = self.denied_methods.as_ref()
+            .is_some_and(|denied| denied.iter().any(|m| m == method));
+        allowed && !denied
+    }
+}
+
+// Requests carry an HMAC-SHA256 of "<timestamp>.<body>" keyed with `secret`, hex encoded in
+// `header_name`, and the unix timestamp in `timestamp_header`. Gateways should reject
+// timestamps more than 30 seconds from their own clock so captured requests can't be replayed.
+#[derive(Debug, Clone, Serialize, Deserialize, JsonSchema)]
+pub struct SigningConfig {
+    pub secret: String,
+    #[serde(default = "default_signature_header")]
+    pub header_name: String,
+    #[serde(default = "default_timestamp_header")]
+    pub timestamp_header: String,
 }
 
-#[derive(Debug, Clone, Serialize, Deserialize)]
+// Receives a signed POST per circuit breaker opening or closing when `secret` is set
+#[derive(Debug, Clone, Serialize, Deserialize, JsonSchema)]
+pub struct WebhookConfig {
+    pub url: String,
+    #[serde(default)]
+    pub secret: Option<String>,
+}
+
+fn default_signature_header() -> String {
+    "X-Signature".to_string()
+}
+
+fn default_timestamp_header() -> String {
+    "X-Timestamp".to_string()
+}
+
+pub fn default_health_status_codes() -> Vec<u16> {
+    vec![200]
+}
+
+fn default_latency_window_size() -> usize {
+    DEFAULT_LATENCY_WINDOW
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize, JsonSchema)]
 pub struct AuthConfig {
     pub enabled: bool,
     pub jwt_secret: String,
     pub token_expiry: u64,
     pub api_keys: HashMap<String, ApiKeyConfig>,
     pub require_auth_for_admin: bool,
+    /// JWTs must carry an `aud` matching one of these when the list is non-empty
+    #[serde(default)]
+    pub expected_audience: Vec<String>,
+    /// Reject JWTs that carry no `aud` claim at all
+    #[serde(default)]
+    pub require_audience: bool,
 }
 
-#[derive(Debug, Clone, Serialize, Deserialize)]
+#[derive(Debug, Clone, Serialize, Deserialize, JsonSchema)]
 pub struct ApiKeyConfig {
     pub name: String,
     pub rate_limit: u32,
@@ -54,7 +252,7 @@ pub struct ApiKeyConfig {
     pub expires_at: Option<String>,
 }
 
-#[derive(Debug, Clone, Serialize, Deserialize)]
+#[derive(Debug, Clone, Serialize, Deserialize, JsonSchema)]
 pub struct CacheConfig {
     pub enabled: bool,
     pub redis_url: String,
@@ -62,19 +260,114 @@ pub struct CacheConfig {
     pub max_cache_size: u64,
     pub cluster_mode: bool,
     pub method_ttls: HashMap<String, u64>,
+    /// On-disk tier that keeps the cache warm across restarts
+    #[serde(default, alias = "sled_path")]
+    pub sled_cache_path: Option<String>,
+    #[serde(default)]
+    pub method_prefetch_rules: HashMap<String, Vec<PrefetchRule>>,
+    #[serde(default)]
+    pub gossip: CacheGossipConfig,
+    /// Routed once at startup, before the listener is bound, so the first clients hit a warm cache
+    #[serde(default)]
+    pub warmup_requests: Vec<WarmupRequest>,
+    #[serde(default = "default_warmup_timeout_secs")]
+    pub warmup_timeout_secs: u64,
+    /// Directory POST /debug/cache/prefill reads fixtures from; prefill is refused when unset
+    #[serde(default)]
+    pub prefill_fixture_dir: Option<String>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize, JsonSchema)]
+pub struct WarmupRequest {
+    pub method: String,
+    #[serde(default)]
+    pub params: serde_json::Value,
+}
+
+fn default_warmup_timeout_secs() -> u64 {
+    30
+}
+
+fn default_max_request_body_bytes() -> usize {
+    crate::middleware::DEFAULT_MAX_REQUEST_BODY_BYTES
 }
 
-#[derive(Debug, Clone, Serialize, Deserialize)]
+fn default_shutdown_timeout_secs() -> u64 {
+    crate::shutdown::DEFAULT_SHUTDOWN_TIMEOUT_SECS
+}
+
+// Shares freshly cached responses with peer instances over UDP so they can skip Redis
+#[derive(Debug, Clone, Serialize, Deserialize, JsonSchema)]
+#[serde(default)]
+pub struct CacheGossipConfig {
+    pub enabled: bool,
+    pub bind_address: String,
+    /// host:port of the other instances' gossip sockets
+    pub peers: Vec<String>,
+    /// How many peers on the hash ring receive each entry
+    pub fanout: usize,
+    /// Key for the HMAC every packet carries; must match on all instances. Required when
+    /// gossip is enabled, at least 32 characters.
+    pub shared_secret: String,
+}
+
+impl Default for CacheGossipConfig {
+    fn default() -> Self {
+        Self {
+            enabled: false,
+            bind_address: "0.0.0.0:7946".to_string(),
+            peers: Vec::new(),
+            fanout: 2,
+            shared_secret: String::new(),
+        }
+    }
+}
+
+// A speculative request issued after a successful call to the method it is keyed under.
+// String params of the form "$params[N]" or "$result/<json pointer>" are filled in from the
+// triggering request and its response.
+#[derive(Debug, Clone, Serialize, Deserialize, JsonSchema)]
+pub struct PrefetchRule {
+    pub method: String,
+    #[serde(default)]
+    pub params: Vec<serde_json::Value>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize, JsonSchema)]
 pub struct ConsensusConfig {
     pub enabled: bool,
+    /// Hard floor on the quorum, capped at the number of endpoints queried
     pub min_confirmations: u32,
+    /// Share of the queried endpoints that must answer, rounded up
+    #[serde(default = "default_min_quorum_fraction")]
+    pub min_quorum_fraction: f64,
     pub timeout_ms: u64,
     pub critical_methods: Vec<String>,
     pub consensus_threshold: f64,
     pub max_deviation: f64,
+    /// JSON file the response cache is dumped to on shutdown and pre-seeded from on startup
+    #[serde(default)]
+    pub consensus_snapshot_path: Option<String>,
+    #[serde(default = "default_max_snapshot_age_secs")]
+    pub max_snapshot_age_secs: u64,
+    /// Results larger than this are compared by SHA-256 digest instead of by their full JSON
+    #[serde(default = "default_hash_threshold_bytes")]
+    pub hash_threshold_bytes: usize,
 }
 
-#[derive(Debug, Clone, Serialize, Deserialize)]
+fn default_max_snapshot_age_secs() -> u64 {
+    300
+}
+
+fn default_hash_threshold_bytes() -> usize {
+    4096
+}
+
+fn default_min_quorum_fraction() -> f64 {
+    0.5
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize, JsonSchema)]
 pub struct GeoConfig {
     pub enabled: bool,
     pub geoip_database_path: String,
@@ -83,7 +376,7 @@ pub struct GeoConfig {
     pub region_weights: HashMap<String, f64>,
 }
 
-#[derive(Debug, Clone, Serialize, Deserialize)]
+#[derive(Debug, Clone, Serialize, Deserialize, JsonSchema)]
 pub struct MetricsConfig {
     pub enabled: bool,
     pub prometheus_enabled: bool,
@@ -91,32 +384,78 @@ pub struct MetricsConfig {
     pub retention_days: u32,
 }
 
-#[derive(Debug, Clone, Serialize, Deserialize)]
+#[derive(Debug, Clone, Serialize, Deserialize, JsonSchema)]
 pub struct RateLimitConfig {
     pub enabled: bool,
     pub default_rate: u32,
     pub default_burst: u32,
     pub per_method_limits: HashMap<String, RateLimit>,
     pub per_ip_limits: HashMap<String, RateLimit>,
+    #[serde(default)]
+    pub exemptions: Vec<RateLimitExemption>,
+    /// Method -> max requests per second across all clients combined, for methods that are
+    /// expensive upstream no matter who calls them
+    #[serde(default)]
+    pub global_method_limits: HashMap<String, u32>,
+    #[serde(default)]
+    pub backend: RateLimitBackend,
+    /// Redis holding the shared sliding windows; cache.redis_url when unset
+    #[serde(default)]
+    pub redis_url: Option<String>,
+    /// IPv6 clients whose addresses share this many leading bits share one rate limit bucket,
+    /// keyed by the truncated address; 128 limits every address on its own
+    #[serde(default = "default_ipv6_prefix_length")]
+    pub ipv6_prefix_length: u8,
+}
+
+fn default_ipv6_prefix_length() -> u8 {
+    64
 }
 
-#[derive(Debug, Clone, Serialize, Deserialize)]
+// Where rate limit state lives. Memory limits each process on its own; Redis shares the
+// limits between every instance behind a load balancer.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Serialize, Deserialize, JsonSchema)]
+pub enum RateLimitBackend {
+    #[default]
+    Memory,
+    Redis,
+}
+
+// Lets infrastructure keys (monitoring bots, health checkers) call the listed methods
+// without any rate limiting. `*` in the pattern matches any run of characters.
+#[derive(Debug, Clone, Serialize, Deserialize, JsonSchema)]
+pub struct RateLimitExemption {
+    pub api_key_pattern: String,
+    pub methods: Vec<String>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize, JsonSchema)]
 pub struct RateLimit {
     pub rate: u32,
     pub burst: u32,
     pub window_seconds: u64,
 }
 
-#[derive(Debug, Clone, Serialize, Deserialize)]
+#[derive(Debug, Clone, Serialize, Deserialize, JsonSchema)]
 pub struct WebSocketConfig {
     pub enabled: bool,
     pub max_connections: u32,
     pub ping_interval: u64,
     pub connection_timeout: u64,
+    /// Subscriptions one client connection may hold at once; repeats reusing an existing one
+    /// don't count
     pub max_subscriptions_per_connection: u32,
+    /// Repeats of a subscribe request on the same connection within this window reuse the
+    /// existing subscription
+    #[serde(default = "default_dedup_window_ms")]
+    pub dedup_window_ms: u64,
+}
+
+fn default_dedup_window_ms() -> u64 {
+    500
 }
 
-#[derive(Debug, Clone, Serialize, Deserialize)]
+#[derive(Debug, Clone, Serialize, Deserialize, JsonSchema)]
 pub struct AdminConfig {
     pub enabled: bool,
     pub bind_address: Option<String>,
@@ -125,7 +464,7 @@ pub struct AdminConfig {
     pub session_timeout: u64,
 }
 
-#[derive(Debug, Clone, Serialize, Deserialize)]
+#[derive(Debug, Clone, Serialize, Deserialize, JsonSchema)]
 pub struct DiscoveryConfig {
     pub enabled: bool,
     pub discovery_interval: u64,
@@ -133,6 +472,665 @@ pub struct DiscoveryConfig {
     pub min_score_threshold: f64,
     pub auto_add_endpoints: bool,
     pub cluster_rpc_urls: Vec<String>,
+    /// Where candidate endpoints come from; without any, each of cluster_rpc_urls is asked via getClusterNodes
+    #[serde(default)]
+    pub providers: Vec<DiscoveryProviderConfig>,
+    #[serde(default)]
+    pub promotion: EndpointPromotionConfig,
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize, JsonSchema)]
+#[serde(tag = "type", rename_all = "snake_case")]
+pub enum DiscoveryProviderConfig {
+    SolanaCluster {
+        rpc_url: String,
+    },
+    Dns {
+        /// SRV record to look up, e.g. _rpc._tcp.solana.example.com
+        srv_name: String,
+        #[serde(default = "default_dns_discovery_scheme")]
+        scheme: String,
+        /// Nameserver address; the first one in /etc/resolv.conf when unset
+        #[serde(default)]
+        resolver: Option<String>,
+    },
+    Static {
+        urls: Vec<String>,
+    },
+}
+
+fn default_dns_discovery_scheme() -> String {
+    "http".to_string()
+}
+
+// When an auto-discovered endpoint has proven itself enough to be managed like a configured one
+#[derive(Debug, Clone, Serialize, Deserialize, JsonSchema)]
+#[serde(default)]
+pub struct EndpointPromotionConfig {
+    pub min_successful_requests: u64,
+    /// Fraction of requests that must have succeeded, e.g. 0.99
+    pub min_success_rate: f64,
+    pub promoted_weight: u32,
+    pub promoted_priority: u8,
+    /// Promoted endpoints are written back to this file so they survive restarts
+    pub config_path: String,
+}
+
+impl Default for EndpointPromotionConfig {
+    fn default() -> Self {
+        Self {
+            min_successful_requests: 1000,
+            min_success_rate: 0.99,
+            promoted_weight: 80,
+            promoted_priority: 5,
+            config_path: "config.toml".to_string(),
+        }
+    }
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize, JsonSchema)]
+#[serde(default)]
+pub struct LatencyAnomalyConfig {
+    /// Samples above EMA + sensitivity * std are logged as elevated latency
+    pub sensitivity: f64,
+    /// Smoothing window in samples; also the warm-up before any alert fires
+    pub window_size: u32,
+    /// Samples above EMA + alert_threshold_multiplier * std raise an anomaly alert
+    pub alert_threshold_multiplier: f64,
+    pub webhook_url: Option<String>,
+}
+
+impl Default for LatencyAnomalyConfig {
+    fn default() -> Self {
+        Self {
+            sensitivity: 2.0,
+            window_size: 20,
+            alert_threshold_multiplier: 4.0,
+            webhook_url: None,
+        }
+    }
+}
+
+// Derives endpoint status from the failure rate of recent requests: below degraded_failure_rate
+// is Healthy, above unhealthy_failure_rate is Unhealthy, and anything in between is Degraded.
+// An endpoint's status is the worse of this and its latest health check.
+#[derive(Debug, Clone, Serialize, Deserialize, JsonSchema)]
+#[serde(default)]
+pub struct HealthGradientConfig {
+    pub enabled: bool,
+    /// Number of most recent request results the failure rate is computed over
+    pub window_size: usize,
+    /// Status is left alone until this many results have been seen
+    pub min_samples: usize,
+    pub degraded_failure_rate: f64,
+    pub unhealthy_failure_rate: f64,
+}
+
+impl Default for HealthGradientConfig {
+    fn default() -> Self {
+        Self {
+            enabled: true,
+            window_size: 50,
+            min_samples: 10,
+            degraded_failure_rate: 0.05,
+            unhealthy_failure_rate: 0.25,
+        }
+    }
+}
+
+// Reprioritizes endpoints by how far behind the chain tip they are after each health check round:
+// up to date is priority 1, 1-5 slots behind 2, further behind 3
+#[derive(Debug, Clone, Serialize, Deserialize, JsonSchema)]
+#[serde(default)]
+pub struct HealthConfig {
+    pub slot_lag_enabled: bool,
+    /// Endpoints more than this many slots behind are marked Degraded
+    pub degraded_lag_threshold: u64,
+}
+
+impl Default for HealthConfig {
+    fn default() -> Self {
+        Self {
+            slot_lag_enabled: false,
+            degraded_lag_threshold: 50,
+        }
+    }
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize, JsonSchema)]
+#[serde(default)]
+pub struct AlertingConfig {
+    /// Alert when fewer than this fraction of endpoints are healthy
+    pub min_healthy_ratio: f64,
+    /// Alert when an endpoint's circuit breaker stays open this long
+    pub circuit_breaker_open_secs: u64,
+    /// Alert when the cache hit rate falls below this fraction; skipped with the cache off
+    pub min_cache_hit_rate: f64,
+    /// Alert when P95 upstream latency exceeds this
+    pub p95_latency_sla_ms: u64,
+}
+
+impl Default for AlertingConfig {
+    fn default() -> Self {
+        Self {
+            min_healthy_ratio: 0.5,
+            circuit_breaker_open_secs: 60,
+            min_cache_hit_rate: 0.5,
+            p95_latency_sla_ms: 1000,
+        }
+    }
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize, JsonSchema)]
+pub struct BatchSplitConfig {
+    pub max_items_per_call: usize,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize, JsonSchema)]
+#[serde(default)]
+pub struct TlsMonitorConfig {
+    pub tls_check_interval_hours: u64,
+    /// Certificates expiring within this many days raise a warning
+    pub tls_expiry_warning_days: i64,
+    pub webhook_url: Option<String>,
+}
+
+impl Default for TlsMonitorConfig {
+    fn default() -> Self {
+        Self {
+            tls_check_interval_hours: 24,
+            tls_expiry_warning_days: 14,
+            webhook_url: None,
+        }
+    }
+}
+
+// Watches for Solana epoch boundaries so epoch-scoped data can be warmed before the flip
+#[derive(Debug, Clone, Serialize, Deserialize, JsonSchema)]
+#[serde(default)]
+pub struct EpochTrackerConfig {
+    pub enabled: bool,
+    pub poll_interval_slots: u64,
+    /// The boundary is considered near once fewer than this many slots remain in the epoch
+    pub boundary_slots: u64,
+    pub boundary_consensus_threshold: f64,
+    pub boundary_consensus_secs: u64,
+    pub webhook_url: Option<String>,
+}
+
+impl Default for EpochTrackerConfig {
+    fn default() -> Self {
+        Self {
+            enabled: false,
+            poll_interval_slots: 10,
+            boundary_slots: 500,
+            boundary_consensus_threshold: 0.9,
+            boundary_consensus_secs: 60,
+            webhook_url: None,
+        }
+    }
+}
+
+// Sheds local cache and upstream connections as process RSS approaches the OOM killer
+#[derive(Debug, Clone, Serialize, Deserialize, JsonSchema)]
+#[serde(default)]
+pub struct MemoryPressureConfig {
+    pub enabled: bool,
+    pub check_interval_secs: u64,
+    pub memory_warning_threshold_mb: u64,
+    pub memory_critical_threshold_mb: u64,
+    /// Per-endpoint connection cap while memory is critical
+    pub critical_max_connections: u32,
+}
+
+impl Default for MemoryPressureConfig {
+    fn default() -> Self {
+        Self {
+            enabled: false,
+            check_interval_secs: 5,
+            memory_warning_threshold_mb: 1024,
+            memory_critical_threshold_mb: 1536,
+            critical_max_connections: 10,
+        }
+    }
+}
+
+// Logs redacted request and response bodies at TRACE under the `multi_rpc::body_logger` target
+#[derive(Debug, Clone, Serialize, Deserialize, JsonSchema)]
+#[serde(default)]
+pub struct BodyLoggerConfig {
+    pub debug_request_logging: bool,
+    /// JSON object keys whose values are replaced with "[REDACTED]", matched case-insensitively
+    pub sensitive_fields: Vec<String>,
+    pub max_log_body_bytes: usize,
+    /// Bodies larger than this are passed through without being logged, here and in the RPC call log
+    pub max_buffered_body_bytes: usize,
+}
+
+impl Default for BodyLoggerConfig {
+    fn default() -> Self {
+        Self {
+            debug_request_logging: false,
+            sensitive_fields: vec!["signature".to_string(), "secretKey".to_string(), "privateKey".to_string()],
+            max_log_body_bytes: 4096,
+            max_buffered_body_bytes: 1024 * 1024,
+        }
+    }
+}
+
+// One structured INFO line per RPC call under the `multi_rpc::rpc_log` target, for support teams.
+// Bodies are redacted and bounded by the [body_logger] settings.
+#[derive(Debug, Clone, Default, Serialize, Deserialize, JsonSchema)]
+#[serde(default)]
+pub struct RequestLoggingConfig {
+    pub enabled: bool,
+    /// Include the request and response bodies, not just method, status and timing
+    pub log_bodies: bool,
+}
+
+// Reloads the config file whenever it changes on disk, e.g. when a Kubernetes ConfigMap is updated
+#[derive(Debug, Clone, Serialize, Deserialize, JsonSchema)]
+#[serde(default)]
+pub struct AutoReloadConfig {
+    pub enabled: bool,
+    pub config_path: String,
+    /// Editors often write a file several times in a row; wait this long for the writes to settle
+    pub debounce_ms: u64,
+}
+
+impl Default for AutoReloadConfig {
+    fn default() -> Self {
+        Self {
+            enabled: false,
+            config_path: "config.toml".to_string(),
+            debounce_ms: 250,
+        }
+    }
+}
+
+// Persistent SQLite log of every inbound RPC call, queried through GET /admin/audit
+#[derive(Debug, Clone, Serialize, Deserialize, JsonSchema)]
+#[serde(default)]
+pub struct AuditConfig {
+    pub enabled: bool,
+    pub db_path: String,
+    /// Records waiting for the writer; further records are dropped rather than slowing requests
+    pub channel_capacity: usize,
+}
+
+impl Default for AuditConfig {
+    fn default() -> Self {
+        Self {
+            enabled: false,
+            db_path: "audit.db".to_string(),
+            channel_capacity: 10_000,
+        }
+    }
+}
+
+// GraphQL view of endpoints, metrics and rate limits at /graphql, alongside the REST routes
+#[derive(Debug, Clone, Default, Serialize, Deserialize, JsonSchema)]
+#[serde(default)]
+pub struct GraphqlConfig {
+    pub enabled: bool,
+    /// GraphiQL at /graphql/playground
+    pub playground_enabled: bool,
+}
+
+// Keeps the endpoint list in sync with a service registry. Endpoints that disappear from the
+// registry stop receiving new requests and are removed once drain_timeout_secs has passed.
+#[derive(Debug, Clone, Serialize, Deserialize, JsonSchema)]
+pub struct ServiceDiscoveryConfig {
+    pub service_name: String,
+    #[serde(default = "default_service_discovery_refresh_secs")]
+    pub refresh_interval_secs: u64,
+    #[serde(default = "default_service_discovery_drain_secs")]
+    pub drain_timeout_secs: u64,
+    /// Used to build endpoint URLs from the discovered address and port
+    #[serde(default = "default_service_discovery_scheme")]
+    pub scheme: String,
+    #[serde(default = "default_service_discovery_weight")]
+    pub weight: u32,
+    #[serde(default = "default_service_discovery_priority")]
+    pub priority: u8,
+    pub provider: ServiceDiscoveryProvider,
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize, JsonSchema)]
+#[serde(tag = "type", rename_all = "snake_case")]
+pub enum ServiceDiscoveryProvider {
+    Consul {
+        #[serde(default = "default_consul_url")]
+        url: String,
+        #[serde(default)]
+        datacenter: Option<String>,
+        #[serde(default)]
+        token: Option<String>,
+    },
+    Kubernetes {
+        #[serde(default = "default_kubernetes_api_url")]
+        api_url: String,
+        #[serde(default = "default_kubernetes_namespace")]
+        namespace: String,
+        // Service account token, sent as a bearer token when the file exists
+        #[serde(default = "default_kubernetes_token_path")]
+        token_path: String,
+        // Only this named port is used when a service exposes several
+        #[serde(default)]
+        port_name: Option<String>,
+    },
+}
+
+fn default_service_discovery_refresh_secs() -> u64 {
+    30
+}
+
+fn default_service_discovery_drain_secs() -> u64 {
+    30
+}
+
+fn default_service_discovery_scheme() -> String {
+    "http".to_string()
+}
+
+fn default_service_discovery_weight() -> u32 {
+    100
+}
+
+fn default_service_discovery_priority() -> u8 {
+    1
+}
+
+fn default_consul_url() -> String {
+    "http://127.0.0.1:8500".to_string()
+}
+
+fn default_kubernetes_api_url() -> String {
+    "https://kubernetes.default.svc".to_string()
+}
+
+fn default_kubernetes_namespace() -> String {
+    "default".to_string()
+}
+
+fn default_kubernetes_token_path() -> String {
+    "/var/run/secrets/kubernetes.io/serviceaccount/token".to_string()
+}
+
+// Large upstream responses are piped to the client as they arrive instead of being buffered
+#[derive(Debug, Clone, Serialize, Deserialize, JsonSchema)]
+#[serde(default)]
+pub struct StreamingConfig {
+    pub enabled: bool,
+    /// Responses with a Content-Length above this, or none at all, are streamed
+    pub stream_threshold_bytes: u64,
+}
+
+impl Default for StreamingConfig {
+    fn default() -> Self {
+        Self {
+            enabled: false,
+            stream_threshold_bytes: 1024 * 1024,
+        }
+    }
+}
+
+// Keeps a client on one endpoint so consecutive calls see the same slot
+#[derive(Debug, Clone, Serialize, Deserialize, JsonSchema)]
+#[serde(default)]
+pub struct StickySessionConfig {
+    pub enabled: bool,
+    /// A session is re-balanced once it has been idle this long
+    pub ttl_secs: u64,
+}
+
+impl Default for StickySessionConfig {
+    fn default() -> Self {
+        Self {
+            enabled: false,
+            ttl_secs: 60,
+        }
+    }
+}
+
+// Identical requests in flight at the same time share one upstream call
+#[derive(Debug, Clone, Serialize, Deserialize, JsonSchema)]
+#[serde(default)]
+pub struct RequestDeduplicationConfig {
+    pub enabled: bool,
+    /// Callers waiting on another request's result give up after this long
+    pub wait_timeout_ms: u64,
+}
+
+impl Default for RequestDeduplicationConfig {
+    fn default() -> Self {
+        Self {
+            enabled: true,
+            wait_timeout_ms: 10_000,
+        }
+    }
+}
+
+// Higher values are dispatched first; equal values keep realtime, static, transaction order
+#[derive(Debug, Clone, Serialize, Deserialize, JsonSchema)]
+#[serde(default)]
+pub struct SchedulerPriorities {
+    /// Upstream calls in flight before further calls are queued
+    pub max_concurrent: usize,
+    pub realtime: u8,
+    #[serde(rename = "static")]
+    pub static_data: u8,
+    pub transaction: u8,
+}
+
+impl SchedulerPriorities {
+    pub fn priority(&self, class: SchedulerClass) -> u8 {
+        match class {
+            SchedulerClass::Realtime => self.realtime,
+            SchedulerClass::Static => self.static_data,
+            SchedulerClass::Transaction => self.transaction,
+        }
+    }
+}
+
+impl Default for SchedulerPriorities {
+    fn default() -> Self {
+        Self {
+            max_concurrent: 256,
+            realtime: 2,
+            static_data: 1,
+            transaction: 0,
+        }
+    }
+}
+
+// gRPC channel for sharing endpoint health between multi-rpc instances
+#[derive(Debug, Clone, Serialize, Deserialize, JsonSchema)]
+#[serde(default)]
+pub struct PeerConfig {
+    pub enabled: bool,
+    pub grpc_bind_address: String,
+    /// Address other nodes use to reach this one, e.g. "http://10.0.0.5:50051".
+    /// Defaults to http:// plus the bind address.
+    pub advertise_address: Option<String>,
+    pub seed_peers: Vec<String>,
+    /// Secret every node sends with its peer calls; calls without it are rejected.
+    /// Required when peering is enabled, at least 32 printable ASCII characters.
+    pub shared_secret: String,
+    /// Peers that haven't answered or called in this long are dropped. Seed peers are kept.
+    pub peer_timeout_secs: u64,
+}
+
+impl Default for PeerConfig {
+    fn default() -> Self {
+        Self {
+            enabled: false,
+            grpc_bind_address: "0.0.0.0:50051".to_string(),
+            advertise_address: None,
+            seed_peers: Vec::new(),
+            shared_secret: String::new(),
+            peer_timeout_secs: 300,
+        }
+    }
+}
+
+// Paths are dot separated with `[n]` for an array element and `[*]` for every element,
+// e.g. "result.value[*].account.owner"
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize, JsonSchema)]
+#[serde(tag = "type", rename_all = "snake_case")]
+pub enum ResponseTransformStep {
+    MaskField {
+        path: String,
+        #[serde(default = "default_transform_mask")]
+        mask: String,
+    },
+    RemoveField {
+        path: String,
+    },
+    TruncateString {
+        path: String,
+        max_len: usize,
+    },
+}
+
+fn default_transform_mask() -> String {
+    "***".to_string()
+}
+
+// Strips, then renames, then injects fields, using the same paths as response_transforms,
+// e.g. strip "consensus_meta", rename "result.context" => "ctx", inject "proxied_by" = "multi-rpc"
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize, JsonSchema)]
+#[serde(default)]
+pub struct TransformConfig {
+    pub strip_fields: Vec<String>,
+    pub rename_fields: HashMap<String, String>,
+    pub inject_fields: HashMap<String, serde_json::Value>,
+}
+
+// Per-message signature authentication for WebSocket clients
+#[derive(Debug, Clone, Serialize, Deserialize, JsonSchema)]
+#[serde(default)]
+pub struct MessageSigningConfig {
+    pub enabled: bool,
+    pub algorithm: SignatureAlgorithm,
+    /// Signed messages whose timestamp is further than this from the server clock are rejected
+    pub max_clock_skew_secs: u64,
+}
+
+impl Default for MessageSigningConfig {
+    fn default() -> Self {
+        Self {
+            enabled: false,
+            algorithm: SignatureAlgorithm::default(),
+            max_clock_skew_secs: 30,
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, Default, PartialEq, Serialize, Deserialize, JsonSchema)]
+#[serde(rename_all = "lowercase")]
+pub enum SignatureAlgorithm {
+    #[default]
+    Ed25519,
+    Secp256k1,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum ConfigValidationError {
+    DuplicateName(String),
+    DuplicateUrl(String),
+    ZeroWeight(String),
+    InvalidScheme { name: String, url: String },
+    UnknownRegion { name: String, region: String },
+    ZeroMaxConnections(String),
+    CanaryWeightOutOfRange { name: String, weight: u8 },
+}
+
+impl std::fmt::Display for ConfigValidationError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            ConfigValidationError::DuplicateName(name) => write!(f, "duplicate endpoint name '{}'", name),
+            ConfigValidationError::DuplicateUrl(url) => write!(f, "duplicate endpoint url '{}'", url),
+            ConfigValidationError::ZeroWeight(name) => write!(f, "endpoint '{}' has weight 0", name),
+            ConfigValidationError::InvalidScheme { name, url } => {
+                write!(f, "endpoint '{}' url '{}' must start with http:// or https://", name, url)
+            }
+            ConfigValidationError::UnknownRegion { name, region } => {
+                write!(f, "endpoint '{}' has unknown region '{}'", name, region)
+            }
+            ConfigValidationError::ZeroMaxConnections(name) => {
+                write!(f, "endpoint '{}' has max_connections 0", name)
+            }
+            ConfigValidationError::CanaryWeightOutOfRange { name, weight } => {
+                write!(f, "endpoint '{}' has canary_weight {}, must be 0-100", name, weight)
+            }
+        }
+    }
+}
+
+// Collects every problem instead of stopping at the first so operators can fix them in one pass
+pub fn validate_endpoints(configs: &[EndpointConfig]) -> Result<(), Vec<ConfigValidationError>> {
+    let mut errors = Vec::new();
+    let mut seen_names = std::collections::HashSet::new();
+    let mut seen_urls = std::collections::HashSet::new();
+
+    for endpoint in configs {
+        if !seen_names.insert(endpoint.name.as_str()) {
+            errors.push(ConfigValidationError::DuplicateName(endpoint.name.clone()));
+        }
+
+        let normalized_url = endpoint.url.trim_end_matches('/').to_lowercase();
+        if !seen_urls.insert(normalized_url) {
+            errors.push(ConfigValidationError::DuplicateUrl(endpoint.url.clone()));
+        }
+
+        if endpoint.weight == 0 {
+            errors.push(ConfigValidationError::ZeroWeight(endpoint.name.clone()));
+        }
+
+        // priority is a u8, so the priority >= 0 rule holds by construction
+
+        if !endpoint.url.starts_with("http://") && !endpoint.url.starts_with("https://") {
+            errors.push(ConfigValidationError::InvalidScheme {
+                name: endpoint.name.clone(),
+                url: endpoint.url.clone(),
+            });
+        }
+
+        if endpoint.max_connections == Some(0) {
+            errors.push(ConfigValidationError::ZeroMaxConnections(endpoint.name.clone()));
+        }
+
+        if endpoint.canary_weight > 100 {
+            errors.push(ConfigValidationError::CanaryWeightOutOfRange {
+                name: endpoint.name.clone(),
+                weight: endpoint.canary_weight,
+            });
+        }
+    }
+
+    if errors.is_empty() {
+        Ok(())
+    } else {
+        Err(errors)
+    }
+}
+
+// Regions must match a configured region weight; "global" is always accepted
+pub fn validate_endpoint_regions(configs: &[EndpointConfig], geo: &GeoConfig) -> Vec<ConfigValidationError> {
+    configs.iter()
+        .filter_map(|endpoint| {
+            let region = endpoint.region.as_ref()?;
+            if region == "global" || geo.region_weights.contains_key(region) {
+                None
+            } else {
+                Some(ConfigValidationError::UnknownRegion {
+                    name: endpoint.name.clone(),
+                    region: region.clone(),
+                })
+            }
+        })
+        .collect()
 }
 
 impl Default for Config {
@@ -186,6 +1184,19 @@ impl Default for Config {
                     features: vec!["full".to_string(), "websocket".to_string()],
                     max_connections: Some(100),
                     auth_token: None,
+                    tls_cert_path: None,
+                    tls_key_path: None,
+                    tls_ca_path: None,
+                    health_status_codes: default_health_status_codes(),
+                    health_response_key: None,
+                    kind: EndpointKind::Http,
+                    signing: None,
+                    tags: Vec::new(),
+                    canary: false,
+                    canary_weight: 0,
+                    allowed_methods: None,
+                    denied_methods: None,
+                    forward_headers: Vec::new(),
                 },
                 EndpointConfig {
                     url: "https://rpc.ankr.com/solana".to_string(),
@@ -198,17 +1209,45 @@ impl Default for Config {
                     features: vec!["full".to_string()],
                     max_connections: Some(50),
                     auth_token: None,
+                    tls_cert_path: None,
+                    tls_key_path: None,
+                    tls_ca_path: None,
+                    health_status_codes: default_health_status_codes(),
+                    health_response_key: None,
+                    kind: EndpointKind::Http,
+                    signing: None,
+                    tags: Vec::new(),
+                    canary: false,
+                    canary_weight: 0,
+                    allowed_methods: None,
+                    denied_methods: None,
+                    forward_headers: Vec::new(),
                 },
             ],
             health_check_interval: 30,
             request_timeout: 10,
             max_retries: 3,
+            retry_strategy: RetryStrategyConfig::default(),
+            bulkheads: HashMap::new(),
+            load_balancing_strategy: LoadBalancingStrategy::default(),
+            method_strategies: HashMap::new(),
+            method_tags: HashMap::new(),
+            egress_rate_limit_bps: None,
+            max_request_body_bytes: default_max_request_body_bytes(),
+            max_response_size_bytes: None,
+            shutdown_timeout_secs: default_shutdown_timeout_secs(),
+            blacklist_cidrs: Vec::new(),
+            whitelist_cidrs: Vec::new(),
+            enable_debug_endpoints: false,
+            latency_window_size: default_latency_window_size(),
             auth: AuthConfig {
                 enabled: false,  // Disabled by default for easier deployment
                 jwt_secret: "your_jwt_secret_here_change_in_production".to_string(),
                 token_expiry: 3600,
                 api_keys,
                 require_auth_for_admin: false,  // Disabled by default
+                expected_audience: Vec::new(),
+                require_audience: false,
             },
             cache: CacheConfig {
                 enabled: false,  // Disabled by default - enable when Redis is available
@@ -217,10 +1256,17 @@ impl Default for Config {
                 max_cache_size: 1024 * 1024 * 100, // 100MB
                 cluster_mode: false,
                 method_ttls,
+                sled_cache_path: None,
+                method_prefetch_rules: HashMap::new(),
+                gossip: CacheGossipConfig::default(),
+                warmup_requests: Vec::new(),
+                warmup_timeout_secs: default_warmup_timeout_secs(),
+                prefill_fixture_dir: None,
             },
             consensus: ConsensusConfig {
                 enabled: true,
                 min_confirmations: 2,
+                min_quorum_fraction: default_min_quorum_fraction(),
                 timeout_ms: 5000,
                 critical_methods: vec![
                     "sendTransaction".to_string(),
@@ -229,6 +1275,9 @@ impl Default for Config {
                 ],
                 consensus_threshold: 0.67,
                 max_deviation: 0.1,
+                consensus_snapshot_path: None,
+                max_snapshot_age_secs: default_max_snapshot_age_secs(),
+                hash_threshold_bytes: default_hash_threshold_bytes(),
             },
             geo: GeoConfig {
                 enabled: false,  // Disabled by default - enable when GeoIP database is available
@@ -249,6 +1298,11 @@ impl Default for Config {
                 default_burst: 100,
                 per_method_limits,
                 per_ip_limits: HashMap::new(),
+                exemptions: Vec::new(),
+                global_method_limits: HashMap::new(),
+                backend: RateLimitBackend::default(),
+                redis_url: None,
+                ipv6_prefix_length: default_ipv6_prefix_length(),
             },
             websocket: WebSocketConfig {
                 enabled: true,
@@ -256,6 +1310,7 @@ impl Default for Config {
                 ping_interval: 30,
                 connection_timeout: 300,
                 max_subscriptions_per_connection: 100,
+                dedup_window_ms: default_dedup_window_ms(),
             },
             admin: AdminConfig {
                 enabled: true,
@@ -277,7 +1332,34 @@ impl Default for Config {
                 cluster_rpc_urls: vec![
                     "https://api.mainnet-beta.solana.com".to_string(),
                 ],
+                providers: Vec::new(),
+                promotion: EndpointPromotionConfig::default(),
             },
+            monitoring: MonitoringConfig::default(),
+            latency_anomaly: LatencyAnomalyConfig::default(),
+            health_gradient: HealthGradientConfig::default(),
+            health: HealthConfig::default(),
+            alerting: AlertingConfig::default(),
+            batch_splitting: HashMap::new(),
+            message_signing: MessageSigningConfig::default(),
+            tls_monitor: TlsMonitorConfig::default(),
+            streaming: StreamingConfig::default(),
+            sticky_sessions: StickySessionConfig::default(),
+            request_deduplication: RequestDeduplicationConfig::default(),
+            scheduler_priorities: SchedulerPriorities::default(),
+            peers: PeerConfig::default(),
+            response_transforms: Vec::new(),
+            transforms: Vec::new(),
+            epoch_tracker: EpochTrackerConfig::default(),
+            memory_pressure: MemoryPressureConfig::default(),
+            body_logger: BodyLoggerConfig::default(),
+            logging: RequestLoggingConfig::default(),
+            auto_reload: AutoReloadConfig::default(),
+            audit: AuditConfig::default(),
+            graphql: GraphqlConfig::default(),
+            webhooks: Vec::new(),
+            fallback_responses: HashMap::new(),
+            service_discovery: None,
         }
     }
 }
@@ -285,13 +1367,8 @@ impl Default for Config {
 impl Config {
     pub async fn load() -> Result<Self, AppError> {
         // Try to load from config file first
-        if let Ok(content) = tokio::fs::read_to_string("config.toml").await {
-            let config: Config = toml::from_str(&content)
-                .map_err(|e| AppError::ConfigError(format!("Failed to parse config.toml: {}", e)))?;
-            
-            // Validate configuration
-            config.validate()?;
-            return Ok(config);
+        if tokio::fs::try_exists("config.toml").await.unwrap_or(false) {
+            return Self::load_from("config.toml").await;
         }
 
         // Try environment variables
@@ -320,7 +1397,7 @@ impl Config {
         Ok(config)
     }
     
-    fn validate(&self) -> Result<(), AppError> {
+    pub fn validate(&self) -> Result<(), AppError> {
         if self.endpoints.is_empty() {
             eprintln!("WARNING: No endpoints configured. The server will start but won't be able to proxy requests.");
             eprintln!("Set RPC_ENDPOINTS environment variable with comma-separated RPC URLs.");
@@ -330,24 +1407,68 @@ impl Config {
             return Err(AppError::ConfigError("JWT secret must be at least 32 characters".to_string()));
         }
 
+        if self.peers.enabled
+            && (self.peers.shared_secret.len() < 32 || !self.peers.shared_secret.chars().all(|c| c.is_ascii_graphic())) {
+            return Err(AppError::ConfigError("Peer shared secret must be at least 32 printable ASCII characters".to_string()));
+        }
+
+        if self.cache.gossip.enabled && self.cache.gossip.shared_secret.len() < 32 {
+            return Err(AppError::ConfigError("Cache gossip shared secret must be at least 32 characters".to_string()));
+        }
+
         if self.consensus.enabled && self.consensus.min_confirmations < 2 {
             return Err(AppError::ConfigError("Consensus requires at least 2 confirmations".to_string()));
         }
 
+        if !(0.0..=1.0).contains(&self.consensus.min_quorum_fraction) {
+            return Err(AppError::ConfigError("Consensus quorum fraction must be between 0.0 and 1.0".to_string()));
+        }
+
         if self.consensus.consensus_threshold < 0.5 || self.consensus.consensus_threshold > 1.0 {
             return Err(AppError::ConfigError("Consensus threshold must be between 0.5 and 1.0".to_string()));
         }
 
-        for endpoint in &self.endpoints {
-            if endpoint.url.is_empty() {
-                return Err(AppError::ConfigError("Endpoint URL cannot be empty".to_string()));
-            }
-            
-            if !endpoint.url.starts_with("http://") && !endpoint.url.starts_with("https://") {
-                return Err(AppError::ConfigError(format!("Invalid endpoint URL: {}", endpoint.url)));
+        if self.rate_limiting.ipv6_prefix_length > 128 {
+            return Err(AppError::ConfigError("Rate limit IPv6 prefix length must be at most 128".to_string()));
+        }
+
+        if self.memory_pressure.enabled
+            && self.memory_pressure.memory_critical_threshold_mb <= self.memory_pressure.memory_warning_threshold_mb {
+            return Err(AppError::ConfigError("Memory critical threshold must be above the warning threshold".to_string()));
+        }
+
+        if self.health_gradient.enabled
+            && self.health_gradient.unhealthy_failure_rate < self.health_gradient.degraded_failure_rate {
+            return Err(AppError::ConfigError("Health gradient unhealthy failure rate must not be below the degraded rate".to_string()));
+        }
+
+        // CIDR ranges either nest or are disjoint, so an overlap means one holds the other's first address
+        for whitelisted in &self.whitelist_cidrs {
+            if let Some(blacklisted) = self.blacklist_cidrs.iter()
+                .find(|blacklisted| whitelisted.contains(blacklisted.network()) || blacklisted.contains(whitelisted.network()))
+            {
+                return Err(AppError::ConfigError(format!(
+                    "Whitelisted range {} overlaps blacklisted range {}", whitelisted, blacklisted
+                )));
             }
         }
 
+        let mut errors = validate_endpoints(&self.endpoints).err().unwrap_or_default();
+        if self.geo.enabled {
+            errors.extend(validate_endpoint_regions(&self.endpoints, &self.geo));
+        }
+
+        if !errors.is_empty() {
+            let report = errors.iter()
+                .map(|e| format!("  - {}", e))
+                .collect::<Vec<_>>()
+                .join("\n");
+            eprintln!("Invalid endpoint configuration:\n{}", report);
+            return Err(AppError::ConfigValidationError(format!(
+                "{} endpoint error(s):\n{}", errors.len(), report
+            )));
+        }
+
         Ok(())
     }
     
@@ -368,6 +1489,19 @@ impl Config {
                     features: vec!["full".to_string()],
                     max_connections: Some(50),
                     auth_token: None,
+                    tls_cert_path: None,
+                    tls_key_path: None,
+                    tls_ca_path: None,
+                    health_status_codes: default_health_status_codes(),
+                    health_response_key: None,
+                    kind: EndpointKind::Http,
+                    signing: None,
+                    tags: Vec::new(),
+                    canary: false,
+                    canary_weight: 0,
+                    allowed_methods: None,
+                    denied_methods: None,
+                    forward_headers: Vec::new(),
                 });
             }
         }
@@ -379,6 +1513,23 @@ impl Config {
         Ok(endpoints)
     }
     
+    // Parses and validates a config file, without any environment variable fallback
+    pub async fn load_from(path: &str) -> Result<Self, AppError> {
+        let content = tokio::fs::read_to_string(path).await
+            .map_err(|e| AppError::ConfigError(format!("Failed to read {}: {}", path, e)))?;
+        let config: Config = toml::from_str(&content)
+            .map_err(|e| AppError::ConfigError(format!("Failed to parse {}: {}", path, e)))?;
+
+        // Validate configuration
+        config.validate()?;
+        Ok(config)
+    }
+
+    // JSON Schema of the config file, with field descriptions taken from the doc comments
+    pub fn json_schema() -> serde_json::Value {
+        serde_json::to_value(schemars::schema_for!(Config)).unwrap_or_default()
+    }
+
     pub fn health_check_duration(&self) -> Duration {
         Duration::from_secs(self.health_check_interval)
     }
@@ -394,12 +1545,230 @@ impl Config {
     }
 
     pub async fn save(&self) -> Result<(), AppError> {
+        self.save_to("config.toml").await
+    }
+
+    pub async fn save_to(&self, path: &str) -> Result<(), AppError> {
         let toml_content = toml::to_string_pretty(self)
             .map_err(|e| AppError::ConfigError(format!("Failed to serialize config: {}", e)))?;
         
-        tokio::fs::write("config.toml", toml_content).await
+        tokio::fs::write(path, toml_content).await
             .map_err(|e| AppError::ConfigError(format!("Failed to write config file: {}", e)))?;
         
         Ok(())
     }
-}
\ No newline at end of file
+}
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn endpoint(name: &str, url: &str) -> EndpointConfig {
+        EndpointConfig {
+            url: url.to_string(),
+            name: name.to_string(),
+            weight: 100,
+            priority: 1,
+            region: None,
+            latitude: None,
+            longitude: None,
+            features: Vec::new(),
+            max_connections: Some(10),
+            auth_token: None,
+            tls_cert_path: None,
+            tls_key_path: None,
+            tls_ca_path: None,
+            health_status_codes: default_health_status_codes(),
+            health_response_key: None,
+            kind: EndpointKind::Http,
+            signing: None,
+            tags: Vec::new(),
+            canary: false,
+            canary_weight: 0,
+            allowed_methods: None,
+            denied_methods: None,
+            forward_headers: Vec::new(),
+        }
+    }
+
+    #[test]
+    fn test_valid_endpoints() {
+        let configs = vec![
+            endpoint("a", "https://a.example.com"),
+            endpoint("b", "http://b.example.com"),
+        ];
+        assert!(validate_endpoints(&configs).is_ok());
+    }
+
+    #[test]
+    fn test_duplicate_name() {
+        let configs = vec![
+            endpoint("a", "https://a.example.com"),
+            endpoint("a", "https://b.example.com"),
+        ];
+        assert_eq!(
+            validate_endpoints(&configs).unwrap_err(),
+            vec![ConfigValidationError::DuplicateName("a".to_string())]
+        );
+    }
+
+    #[test]
+    fn test_duplicate_url() {
+        let configs = vec![
+            endpoint("a", "https://a.example.com"),
+            endpoint("b", "https://A.example.com/"),
+        ];
+        assert_eq!(
+            validate_endpoints(&configs).unwrap_err(),
+            vec![ConfigValidationError::DuplicateUrl("https://A.example.com/".to_string())]
+        );
+    }
+
+    #[test]
+    fn test_zero_weight() {
+        let mut config = endpoint("a", "https://a.example.com");
+        config.weight = 0;
+        assert_eq!(
+            validate_endpoints(&[config]).unwrap_err(),
+            vec![ConfigValidationError::ZeroWeight("a".to_string())]
+        );
+    }
+
+    #[test]
+    fn test_invalid_scheme() {
+        let configs = vec![endpoint("a", "wss://a.example.com")];
+        assert!(matches!(
+            validate_endpoints(&configs).unwrap_err()[0],
+            ConfigValidationError::InvalidScheme { .. }
+        ));
+    }
+
+    #[test]
+    fn test_zero_max_connections() {
+        let mut config = endpoint("a", "https://a.example.com");
+        config.max_connections = Some(0);
+        assert_eq!(
+            validate_endpoints(&[config]).unwrap_err(),
+            vec![ConfigValidationError::ZeroMaxConnections("a".to_string())]
+        );
+    }
+
+    #[test]
+    fn test_unknown_region() {
+        let geo = Config::default().geo;
+        let mut known = endpoint("a", "https://a.example.com");
+        known.region = Some("eu".to_string());
+        let mut global = endpoint("b", "https://b.example.com");
+        global.region = Some("global".to_string());
+        let mut unknown = endpoint("c", "https://c.example.com");
+        unknown.region = Some("mars".to_string());
+
+        assert_eq!(
+            validate_endpoint_regions(&[known, global, unknown], &geo),
+            vec![ConfigValidationError::UnknownRegion {
+                name: "c".to_string(),
+                region: "mars".to_string(),
+            }]
+        );
+    }
+
+    #[test]
+    fn test_all_errors_reported() {
+        let mut zero_weight = endpoint("a", "ftp://a.example.com");
+        zero_weight.weight = 0;
+        let configs = vec![zero_weight, endpoint("a", "ftp://a.example.com")];
+
+        // Two duplicates, one zero weight, two bad schemes
+        assert_eq!(validate_endpoints(&configs).unwrap_err().len(), 5);
+    }
+
+    #[test]
+    fn test_config_validate_surfaces_endpoint_errors() {
+        let mut config = Config::default();
+        config.endpoints.push(config.endpoints[0].clone());
+        assert!(matches!(config.validate(), Err(AppError::ConfigValidationError(_))));
+    }
+
+    #[test]
+    fn test_config_validate_rejects_overlapping_access_lists() {
+        let with_lists = |whitelist: &[&str], blacklist: &[&str]| Config {
+            whitelist_cidrs: whitelist.iter().map(|cidr| cidr.parse().unwrap()).collect(),
+            blacklist_cidrs: blacklist.iter().map(|cidr| cidr.parse().unwrap()).collect(),
+            ..Config::default()
+        };
+
+        for (whitelist, blacklist) in [
+            (&["10.0.0.0/8"][..], &["10.1.2.3"][..]),
+            (&["10.1.0.0/16"], &["10.0.0.0/8"]),
+            (&["2001:db8::/48"], &["2001:db8::/32"]),
+        ] {
+            let error = with_lists(whitelist, blacklist).validate().unwrap_err();
+            assert!(error.to_string().contains("overlaps"), "{:?} / {:?}: {}", whitelist, blacklist, error);
+        }
+
+        // Adjacent ranges and different address families don't overlap
+        assert!(with_lists(&["10.0.0.0/24"], &["10.0.1.0/24"]).validate().is_ok());
+        assert!(with_lists(&["2001:db8::/32"], &["0.0.0.0/0"]).validate().is_ok());
+    }
+
+    #[test]
+    fn test_config_validate_requires_peer_secret() {
+        let with_secret = |secret: &str| Config {
+            peers: PeerConfig { enabled: true, shared_secret: secret.to_string(), ..PeerConfig::default() },
+            ..Config::default()
+        };
+
+        assert!(with_secret("").validate().is_err());
+        assert!(with_secret("too-short").validate().is_err());
+        assert!(with_secret(&format!("{} with spaces", "x".repeat(32))).validate().is_err());
+        assert!(with_secret(&"x".repeat(32)).validate().is_ok());
+    }
+
+    fn with_method_strategies(table: &str) -> Result<Config, toml::de::Error> {
+        let mut config = toml::Value::try_from(Config::default()).unwrap();
+        config.as_table_mut().unwrap()
+            .insert("method_strategies".to_string(), toml::from_str(table).unwrap());
+        config.try_into()
+    }
+
+    #[test]
+    fn test_method_strategies_from_toml() {
+        let config = with_method_strategies("getSlot = \"LeastLatency\"\ngetBalance = \"Weighted\"").unwrap();
+        assert!(matches!(config.method_strategies["getSlot"], LoadBalancingStrategy::LeastLatency));
+        assert!(matches!(config.method_strategies["getBalance"], LoadBalancingStrategy::Weighted));
+
+        let error = with_method_strategies("getSlot = \"Fastest\"").unwrap_err().to_string();
+        assert!(error.contains("unknown variant `Fastest`"), "{}", error);
+    }
+
+    #[test]
+    fn test_example_config_matches_schema() {
+        let schema = Config::json_schema();
+        let validator = jsonschema::JSONSchema::compile(&schema).unwrap();
+
+        let example: toml::Value = toml::from_str(include_str!("../config.toml")).unwrap();
+        let example = serde_json::to_value(example).unwrap();
+        if let Err(errors) = validator.validate(&example) {
+            let errors: Vec<String> = errors.map(|e| format!("{} at {}", e, e.instance_path)).collect();
+            panic!("config.toml does not match the schema: {:#?}", errors);
+        }
+
+        let mut broken = example.clone();
+        broken["consensus"]["min_quorum_fraction"] = serde_json::json!("half");
+        broken["endpoints"][0]["weight"] = serde_json::json!(-1);
+        assert_eq!(validator.validate(&broken).unwrap_err().count(), 2);
+    }
+
+    #[test]
+    fn test_schema_covers_nested_types_with_descriptions() {
+        let schema = Config::json_schema();
+        let definitions = schema["definitions"].as_object().unwrap();
+        for name in ["EndpointConfig", "RateLimitConfig", "ConsensusConfig", "CacheConfig", "GeoConfig"] {
+            assert!(definitions.contains_key(name), "missing {}", name);
+        }
+        assert_eq!(
+            definitions["EndpointConfig"]["properties"]["tags"]["description"],
+            "Pools the endpoint belongs to, e.g. \"archive\" or \"full-node\"; see method_tags",
+        );
+        assert!(schema["properties"]["max_request_body_bytes"]["description"].is_string());
+    }
+}
@@ -0,0 +1,219 @@
+use crate::config::Config;
+use serde::{Deserialize, Serialize};
+use serde_json::{Map, Value};
+use std::collections::BTreeSet;
+
+// Fields that change on every load without anything meaningful having changed
+const IGNORED_FIELDS: &[&str] = &["created_at", "updated_at", "timestamp"];
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct ConfigChange {
+    pub field_path: String,
+    pub old_value: Value,
+    pub new_value: Value,
+}
+
+// Leaf-level differences between two configs, with paths like "endpoints[2].weight".
+// Fields missing on one side are reported against null.
+pub fn diff_configs(old: &Config, new: &Config) -> Vec<ConfigChange> {
+    let old = serde_json::to_value(old).unwrap_or(Value::Null);
+    let new = serde_json::to_value(new).unwrap_or(Value::Null);
+    let mut changes = Vec::new();
+    diff_values("", &old, &new, &mut changes);
+    changes
+}
+
+fn diff_values(path: &str, old: &Value, new: &Value, changes: &mut Vec<ConfigChange>) {
+    match (old, new) {
+        (Value::Object(old), Value::Object(new)) => {
+            let keys: BTreeSet<&String> = old.keys().chain(new.keys()).collect();
+            for key in keys.into_iter().filter(|key| !IGNORED_FIELDS.contains(&key.as_str())) {
+                let child = if path.is_empty() { key.clone() } else { format!("{}.{}", path, key) };
+                diff_values(&child, field(old, key), field(new, key), changes);
+            }
+        }
+        (Value::Array(old), Value::Array(new)) => {
+            for index in 0..old.len().max(new.len()) {
+                let child = format!("{}[{}]", path, index);
+                diff_values(&child, old.get(index).unwrap_or(&Value::Null), new.get(index).unwrap_or(&Value::Null), changes);
+            }
+        }
+        // An added or removed entry is reported field by field against null
+        (Value::Object(_) | Value::Array(_), Value::Null) => diff_values(path, old, &empty_like(old), changes),
+        (Value::Null, Value::Object(_) | Value::Array(_)) => diff_values(path, &empty_like(new), new, changes),
+        _ if old != new => changes.push(ConfigChange {
+            field_path: path.to_string(),
+            old_value: old.clone(),
+            new_value: new.clone(),
+        }),
+        _ => {}
+    }
+}
+
+fn field<'a>(map: &'a Map<String, Value>, key: &str) -> &'a Value {
+    map.get(key).unwrap_or(&Value::Null)
+}
+
+fn empty_like(value: &Value) -> Value {
+    match value {
+        Value::Array(_) => Value::Array(Vec::new()),
+        _ => Value::Object(Map::new()),
+    }
+}
+
+// RFC 7396 merge patch: objects merge recursively, null removes a key, anything else replaces
+pub fn merge_patch(target: &mut Value, patch: &Value) {
+    let Value::Object(patch) = patch else {
+        *target = patch.clone();
+        return;
+    };
+    if !target.is_object() {
+        *target = Value::Object(Map::new());
+    }
+    let target = target.as_object_mut().expect("target was just made an object");
+
+    for (key, value) in patch {
+        if value.is_null() {
+            target.remove(key);
+        } else {
+            merge_patch(target.entry(key.clone()).or_insert(Value::Null), value);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use rand::{rngs::StdRng, seq::SliceRandom, Rng, SeedableRng};
+    use serde_json::json;
+
+    fn paths(changes: &[ConfigChange]) -> Vec<&str> {
+        changes.iter().map(|change| change.field_path.as_str()).collect()
+    }
+
+    // Reads the value at a diff path such as "endpoints[2].weight"
+    fn lookup<'a>(value: &'a Value, path: &str) -> &'a Value {
+        let mut current = value;
+        for part in path.split('.') {
+            let (key, indexes) = part.split_once('[').map_or((part, ""), |(k, rest)| (k, rest));
+            current = &current[key];
+            for index in indexes.split('[').filter(|s| !s.is_empty()) {
+                current = &current[index.trim_end_matches(']').parse::<usize>().unwrap()];
+            }
+        }
+        current
+    }
+
+    #[test]
+    fn test_identical_configs_have_no_diff() {
+        let config = Config::default();
+        assert!(diff_configs(&config, &config.clone()).is_empty());
+    }
+
+    #[test]
+    fn test_nested_and_indexed_paths() {
+        let old = Config::default();
+        let mut new = old.clone();
+        new.endpoints[1].weight += 5;
+        new.cache.gossip.fanout = 4;
+        new.max_retries += 1;
+
+        let changes = diff_configs(&old, &new);
+        assert_eq!(paths(&changes), vec!["cache.gossip.fanout", "endpoints[1].weight", "max_retries"]);
+        assert_eq!(changes[1].old_value, json!(old.endpoints[1].weight));
+        assert_eq!(changes[1].new_value, json!(new.endpoints[1].weight));
+    }
+
+    #[test]
+    fn test_added_endpoint_and_ignored_timestamps() {
+        let old = Config::default();
+        let mut new = old.clone();
+        let mut added = new.endpoints[0].clone();
+        added.name = "added".to_string();
+        new.endpoints.push(added);
+        for key in new.auth.api_keys.values_mut() {
+            key.created_at = "2030-01-01T00:00:00Z".to_string();
+        }
+
+        let changes = diff_configs(&old, &new);
+        let index = old.endpoints.len();
+        assert!(changes.iter().all(|change| change.field_path.starts_with(&format!("endpoints[{}].", index))));
+        assert!(changes.iter().any(|change| change.field_path == format!("endpoints[{}].name", index)
+            && change.old_value.is_null()
+            && change.new_value == "added"));
+    }
+
+    #[test]
+    fn test_merge_patch() {
+        let mut target = json!({"a": 1, "b": {"c": 2, "d": 3}, "e": [1, 2]});
+        merge_patch(&mut target, &json!({"a": 5, "b": {"d": null, "f": 4}, "e": [3]}));
+        assert_eq!(target, json!({"a": 5, "b": {"c": 2, "f": 4}, "e": [3]}));
+    }
+
+    // Randomized property checks: every mutation shows up in the diff at its own path with the
+    // right before and after values, and nothing else does
+    #[test]
+    fn test_random_mutations_are_reported_exactly() {
+        let mut rng = StdRng::seed_from_u64(0x5eed);
+        let base = Config::default();
+
+        for _ in 0..200 {
+            let mut config = base.clone();
+            let mut expected = BTreeSet::new();
+
+            for _ in 0..rng.gen_range(1..=4) {
+                let endpoint = rng.gen_range(0..config.endpoints.len());
+                let mutation = *["weight", "priority", "max_retries", "rate", "name", "consensus"].choose(&mut rng).unwrap();
+                match mutation {
+                    "weight" => {
+                        config.endpoints[endpoint].weight = rng.gen_range(0..1000);
+                        expected.insert(format!("endpoints[{}].weight", endpoint));
+                    }
+                    "priority" => {
+                        config.endpoints[endpoint].priority = rng.gen();
+                        expected.insert(format!("endpoints[{}].priority", endpoint));
+                    }
+                    "name" => {
+                        config.endpoints[endpoint].name = format!("endpoint-{}", rng.gen::<u32>());
+                        expected.insert(format!("endpoints[{}].name", endpoint));
+                    }
+                    "max_retries" => {
+                        config.max_retries = rng.gen_range(0..20);
+                        expected.insert("max_retries".to_string());
+                    }
+                    "rate" => {
+                        config.rate_limiting.default_rate = rng.gen_range(1..5000);
+                        expected.insert("rate_limiting.default_rate".to_string());
+                    }
+                    _ => {
+                        config.consensus.consensus_threshold = rng.gen_range(0.0..1.0);
+                        expected.insert("consensus.consensus_threshold".to_string());
+                    }
+                }
+            }
+
+            let changes = diff_configs(&base, &config);
+            let old_json = serde_json::to_value(&base).unwrap();
+            let new_json = serde_json::to_value(&config).unwrap();
+
+            // A mutation may have written back the value it already had
+            let expected: BTreeSet<String> = expected.into_iter()
+                .filter(|path| lookup(&old_json, path) != lookup(&new_json, path))
+                .collect();
+            let reported: BTreeSet<String> = changes.iter().map(|change| change.field_path.clone()).collect();
+            assert_eq!(reported, expected);
+
+            for change in &changes {
+                assert_eq!(&change.old_value, lookup(&old_json, &change.field_path));
+                assert_eq!(&change.new_value, lookup(&new_json, &change.field_path));
+            }
+
+            // Diffing is symmetric
+            let reverse = diff_configs(&config, &base);
+            assert_eq!(reverse.len(), changes.len());
+            assert!(reverse.iter().zip(&changes).all(|(r, c)| r.field_path == c.field_path
+                && r.old_value == c.new_value
+                && r.new_value == c.old_value));
+        }
+    }
+}
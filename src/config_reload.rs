@@ -0,0 +1,329 @@
+use crate::{
+    config::AutoReloadConfig,
+    endpoints::EndpointManager,
+    error::AppError,
+    logging::AuditLogger,
+    metrics::MetricsService,
+};
+use notify::{event::EventKind, Event, RecommendedWatcher, RecursiveMode, Watcher};
+use std::{
+    ffi::OsStr,
+    path::{Path, PathBuf},
+    sync::Arc,
+    time::Duration,
+};
+use tokio::sync::mpsc;
+use tokio_util::sync::CancellationToken;
+use tracing::{debug, error, info, warn};
+
+// Shown as the user in the audit log for reloads nobody asked for through the API
+const AUTO_RELOAD_USER: &str = "config-watcher";
+// Kubernetes updates a mounted ConfigMap by swapping this symlink rather than touching the file
+const CONFIGMAP_DATA_DIR: &str = "..data";
+
+// Watches the config file and applies it through EndpointManager whenever it changes
+pub struct ConfigReloader {
+    endpoint_manager: Arc<EndpointManager>,
+    metrics_service: Arc<MetricsService>,
+    audit_logger: Arc<AuditLogger>,
+    config_path: PathBuf,
+    debounce: Duration,
+}
+
+impl ConfigReloader {
+    pub fn new(
+        endpoint_manager: Arc<EndpointManager>,
+        metrics_service: Arc<MetricsService>,
+        audit_logger: Arc<AuditLogger>,
+        config: &AutoReloadConfig,
+    ) -> Self {
+        Self {
+            endpoint_manager,
+            metrics_service,
+            audit_logger,
+            config_path: PathBuf::from(&config.config_path),
+            debounce: Duration::from_millis(config.debounce_ms),
+        }
+    }
+
+    // Watching the parent directory rather than the file itself survives editors and
+    // ConfigMaps that replace the file instead of writing to it in place
+    fn watch(&self) -> Result<(RecommendedWatcher, mpsc::UnboundedReceiver<()>), AppError> {
+        let (tx, rx) = mpsc::unbounded_channel();
+        let file_name = self.config_path.file_name().map(OsStr::to_os_string);
+
+        let mut watcher = notify::recommended_watcher(move |result: notify::Result<Event>| {
+            let event = match result {
+                Ok(event) => event,
+                Err(e) => {
+                    warn!("Config file watch error: {}", e);
+                    return;
+                }
+            };
+            if matches!(event.kind, EventKind::Access(_)) {
+                return;
+            }
+            let relevant = event.paths.iter()
+                .filter_map(|path| path.file_name())
+                .any(|name| Some(name) == file_name.as_deref() || name == CONFIGMAP_DATA_DIR);
+            if relevant {
+                let _ = tx.send(());
+            }
+        }).map_err(|e| AppError::config(&format!("Failed to create config watcher: {}", e)))?;
+
+        let directory = match self.config_path.parent() {
+            Some(parent) if !parent.as_os_str().is_empty() => parent,
+            _ => Path::new("."),
+        };
+        watcher.watch(directory, RecursiveMode::NonRecursive)
+            .map_err(|e| AppError::config(&format!("Failed to watch {}: {}", directory.display(), e)))?;
+
+        Ok((watcher, rx))
+    }
+
+    pub async fn start(self: Arc<Self>, shutdown: CancellationToken) {
+        // The watcher stops delivering events once dropped, so it lives as long as this task
+        let (_watcher, mut changes) = match self.watch() {
+            Ok(watch) => watch,
+            Err(e) => {
+                error!("Config auto-reload disabled: {}", e);
+                return;
+            }
+        };
+        info!("Watching {} for config changes", self.config_path.display());
+
+        loop {
+            tokio::select! {
+                _ = shutdown.cancelled() => break,
+                change = changes.recv() => if change.is_none() { break },
+            }
+
+            // Wait until the file has been quiet for the debounce period
+            loop {
+                tokio::select! {
+                    _ = shutdown.cancelled() => return,
+                    _ = tokio::time::sleep(self.debounce) => break,
+                    _ = changes.recv() => {}
+                }
+            }
+
+            self.reload().await;
+        }
+
+        info!("Config auto-reload stopped");
+    }
+
+    // `kill -HUP` reloads the file whether or not it is being watched
+    #[cfg(unix)]
+    pub async fn start_sighup_listener(self: Arc<Self>, shutdown: CancellationToken) {
+        use tokio::signal::unix::{signal, SignalKind};
+
+        let mut hangups = match signal(SignalKind::hangup()) {
+            Ok(hangups) => hangups,
+            Err(e) => {
+                error!("Failed to listen for SIGHUP, reload on signal disabled: {}", e);
+                return;
+            }
+        };
+
+        loop {
+            tokio::select! {
+                _ = shutdown.cancelled() => break,
+                received = hangups.recv() => {
+                    if received.is_none() {
+                        break;
+                    }
+                    info!("SIGHUP received, reloading {}", self.config_path.display());
+                    self.reload().await;
+                }
+            }
+        }
+    }
+
+    // An invalid file is logged and skipped, leaving the running config in place
+    pub async fn reload(&self) -> bool {
+        let path = self.config_path.to_string_lossy();
+        match self.endpoint_manager.reload_config_from(&path).await {
+            Ok(changes) => {
+                debug!("Auto-reloaded {} with {} changes", path, changes.len());
+                self.audit_logger.log_config_diff(AUTO_RELOAD_USER, &changes);
+                self.metrics_service.record_config_auto_reload(true);
+                true
+            }
+            Err(e) => {
+                error!("Ignoring changed config file {}, keeping the current config: {}", path, e);
+                self.metrics_service.record_config_auto_reload(false);
+                false
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{
+        circuit_breaker::CircuitBreakerRegistry,
+        config::Config,
+        logging::LogBuffer,
+        test_server::shared_metrics,
+    };
+    use uuid::Uuid;
+
+    async fn wait_for<F, Fut>(mut condition: F) -> bool
+    where
+        F: FnMut() -> Fut,
+        Fut: std::future::Future<Output = bool>,
+    {
+        for _ in 0..100 {
+            if condition().await {
+                return true;
+            }
+            tokio::time::sleep(Duration::from_millis(50)).await;
+        }
+        false
+    }
+
+    #[tokio::test]
+    async fn test_file_changes_are_reloaded() {
+        let dir = std::env::temp_dir().join(format!("multi-rpc-reload-{}", Uuid::new_v4()));
+        tokio::fs::create_dir_all(&dir).await.unwrap();
+        let config_path = dir.join("config.toml").to_string_lossy().to_string();
+
+        let config = Config::default();
+        config.save_to(&config_path).await.unwrap();
+        let endpoint_manager = Arc::new(EndpointManager::new(
+            config.endpoints.clone(),
+            config.clone(),
+            Arc::new(CircuitBreakerRegistry::default()),
+        ).await.unwrap());
+        let metrics = shared_metrics();
+        let reloader = Arc::new(ConfigReloader::new(
+            endpoint_manager.clone(),
+            metrics.clone(),
+            Arc::new(AuditLogger::new(Arc::new(LogBuffer::new(100)))),
+            &AutoReloadConfig {
+                enabled: true,
+                config_path: config_path.clone(),
+                debounce_ms: 50,
+            },
+        ));
+        let shutdown = CancellationToken::new();
+        let task = tokio::spawn(reloader.start(shutdown.clone()));
+        let counter = |name: &'static str| {
+            let metrics = metrics.clone();
+            async move { metrics.get_metrics().await["config"][name].as_u64().unwrap() }
+        };
+        // Give the watcher a moment to register
+        tokio::time::sleep(Duration::from_millis(100)).await;
+
+        let reloads = counter("auto_reloads").await;
+        let mut updated = config.clone();
+        updated.max_retries = 7;
+        updated.save_to(&config_path).await.unwrap();
+        assert!(wait_for(|| async { endpoint_manager.current_config().await.max_retries == 7 }).await);
+        assert!(counter("auto_reloads").await > reloads);
+
+        // A file that fails validation is rejected and the running config kept
+        let failures = counter("auto_reload_failures").await;
+        let mut invalid = updated.clone();
+        invalid.consensus.consensus_threshold = 2.0;
+        invalid.save_to(&config_path).await.unwrap();
+        assert!(wait_for(|| async { counter("auto_reload_failures").await > failures }).await);
+        assert_eq!(endpoint_manager.current_config().await.max_retries, 7);
+        assert_eq!(endpoint_manager.current_config().await.consensus.consensus_threshold, config.consensus.consensus_threshold);
+
+        shutdown.cancel();
+        task.await.unwrap();
+        let _ = tokio::fs::remove_dir_all(&dir).await;
+    }
+
+    #[cfg(unix)]
+    #[tokio::test]
+    async fn test_sighup_reloads_and_reconciles_endpoints() {
+        use crate::config::EndpointConfig;
+        use serde_json::json;
+        use wiremock::{MockServer, Mock, ResponseTemplate, matchers::method};
+
+        let dir = std::env::temp_dir().join(format!("multi-rpc-sighup-{}", Uuid::new_v4()));
+        tokio::fs::create_dir_all(&dir).await.unwrap();
+        let config_path = dir.join("config.toml").to_string_lossy().to_string();
+
+        let (kept, removed, added) = (MockServer::start().await, MockServer::start().await, MockServer::start().await);
+        Mock::given(method("POST"))
+            .respond_with(ResponseTemplate::new(200)
+                .set_body_json(json!({"jsonrpc": "2.0", "id": 1, "result": 42}))
+                .set_delay(Duration::from_millis(500)))
+            .mount(&removed)
+            .await;
+        let mut config = Config::default();
+        let template = config.endpoints[0].clone();
+        let endpoint = |name: &str, server: &MockServer| EndpointConfig {
+            url: server.uri(),
+            name: name.to_string(),
+            ..template.clone()
+        };
+        config.endpoints = vec![endpoint("kept", &kept), endpoint("removed", &removed)];
+        config.save_to(&config_path).await.unwrap();
+
+        let endpoint_manager = Arc::new(EndpointManager::new(
+            config.endpoints.clone(),
+            config.clone(),
+            Arc::new(CircuitBreakerRegistry::default()),
+        ).await.unwrap());
+        let reloader = Arc::new(ConfigReloader::new(
+            endpoint_manager.clone(),
+            shared_metrics(),
+            Arc::new(AuditLogger::new(Arc::new(LogBuffer::new(100)))),
+            &AutoReloadConfig {
+                enabled: false,
+                config_path: config_path.clone(),
+                debounce_ms: 50,
+            },
+        ));
+        let shutdown = CancellationToken::new();
+        let task = tokio::spawn(reloader.start_sighup_listener(shutdown.clone()));
+        tokio::time::sleep(Duration::from_millis(50)).await;
+
+        let removed_id = endpoint_manager.get_endpoint_info().await.into_iter()
+            .find(|e| e.url == removed.uri())
+            .unwrap()
+            .id;
+        let in_flight = tokio::spawn({
+            let endpoint_manager = endpoint_manager.clone();
+            async move {
+                endpoint_manager.send_rpc_request(removed_id, json!({"jsonrpc": "2.0", "id": 1, "method": "getSlot"})).await
+            }
+        });
+        tokio::time::sleep(Duration::from_millis(100)).await;
+
+        let mut updated = config.clone();
+        updated.endpoints = vec![EndpointConfig { weight: 40, ..endpoint("kept", &kept) }, endpoint("added", &added)];
+        updated.save_to(&config_path).await.unwrap();
+        let status = std::process::Command::new("kill")
+            .args(["-HUP", &std::process::id().to_string()])
+            .status()
+            .unwrap();
+        assert!(status.success());
+
+        let urls_match = || async {
+            let mut urls: Vec<(String, u32)> = endpoint_manager.get_endpoint_info().await.into_iter()
+                .map(|e| (e.url, e.weight))
+                .collect();
+            urls.sort();
+            let mut expected = vec![(kept.uri(), 40), (added.uri(), template.weight)];
+            expected.sort();
+            urls == expected
+        };
+        assert!(wait_for(urls_match).await);
+        assert_eq!(endpoint_manager.current_config().await.endpoints.len(), 2);
+
+        // The request that was already on its way to the removed endpoint still completes
+        let response = in_flight.await.unwrap().unwrap();
+        assert_eq!(response["result"], 42);
+
+        shutdown.cancel();
+        task.await.unwrap();
+        let _ = tokio::fs::remove_dir_all(&dir).await;
+    }
+}
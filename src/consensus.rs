@@ -1,17 +1,29 @@
 use crate::{
+    chaos::NetworkPartitionSimulator,
     config::ConsensusConfig,
     error::AppError,
+    propagation::with_baggage,
+    request_trace::{current_span, in_span_of, set_attribute},
     types::EndpointInfo,
 };
+use axum::http::HeaderMap;
+use chrono::{DateTime, Utc};
 use dashmap::DashMap;
+use opentelemetry::trace::FutureExt;
+use serde::{Deserialize, Serialize};
 use serde_json::{json, Value};
+use sha2::{Digest, Sha256};
 use std::{
-    collections::HashMap,
-    sync::Arc,
+    collections::{HashMap, VecDeque},
+    io::{self, Write},
+    sync::{
+        atomic::{AtomicUsize, Ordering},
+        Arc, Mutex,
+    },
     time::{Duration, Instant},
 };
 use tokio::time::timeout;
-use tracing::{debug, warn, error};
+use tracing::{debug, info, warn, error, Instrument};
 use uuid::Uuid;
 
 #[derive(Debug, Clone)]
@@ -19,6 +31,143 @@ pub struct ConsensusService {
     config: ConsensusConfig,
     response_cache: Arc<DashMap<String, CachedConsensus>>,
     validation_stats: Arc<DashMap<String, ValidationStats>>,
+    partition_simulator: Option<Arc<NetworkPartitionSimulator>>,
+    // Temporary per-method thresholds, e.g. stricter agreement around epoch boundaries
+    threshold_overrides: Arc<DashMap<String, (f64, Instant)>>,
+    recent_diffs: Arc<Mutex<VecDeque<ConsensusDiff>>>,
+    // Endpoints queried by the most recent consensus round, for the debug view
+    last_available_endpoints: Arc<AtomicUsize>,
+    adaptive_threshold: Arc<AdaptiveConsensusThreshold>,
+}
+
+const RECENT_DIFFS_CAPACITY: usize = 100;
+
+const AGREEMENT_WINDOW: Duration = Duration::from_secs(300);
+const AGREEMENT_SAMPLES_CAPACITY: usize = 1024;
+// Rolling agreement below this share of the configured threshold counts as a split network
+const RELAX_TRIGGER_RATIO: f64 = 0.8;
+const RELAX_AFTER: Duration = Duration::from_secs(60);
+const RELAX_MARGIN: f64 = 0.05;
+
+#[derive(Debug, Default)]
+struct AgreementWindow {
+    // Ring buffer of (observed at, share of endpoints that agreed)
+    samples: VecDeque<(Instant, f64)>,
+    below_since: Option<Instant>,
+    relaxed: Option<f64>,
+}
+
+impl AgreementWindow {
+    fn rolling_rate(&self) -> f64 {
+        if self.samples.is_empty() {
+            return 1.0;
+        }
+        self.samples.iter().map(|(_, agreement)| agreement).sum::<f64>() / self.samples.len() as f64
+    }
+}
+
+// Lowers a method's threshold while its endpoints have disagreed for a sustained period, e.g.
+// during a fork, so consensus stays reachable; restored once agreement recovers
+#[derive(Debug)]
+pub struct AdaptiveConsensusThreshold {
+    configured: f64,
+    windows: DashMap<String, AgreementWindow>,
+}
+
+impl AdaptiveConsensusThreshold {
+    pub fn new(configured: f64) -> Self {
+        Self {
+            configured,
+            windows: DashMap::new(),
+        }
+    }
+
+    pub fn record(&self, method: &str, agreement: f64) {
+        self.record_at(method, agreement, Instant::now());
+    }
+
+    fn record_at(&self, method: &str, agreement: f64, now: Instant) {
+        let mut window = self.windows.entry(method.to_string()).or_default();
+        if window.samples.len() == AGREEMENT_SAMPLES_CAPACITY {
+            window.samples.pop_front();
+        }
+        window.samples.push_back((now, agreement));
+        while window.samples.front().is_some_and(|(at, _)| now.duration_since(*at) > AGREEMENT_WINDOW) {
+            window.samples.pop_front();
+        }
+
+        let rate = window.rolling_rate();
+        if rate >= self.configured * RELAX_TRIGGER_RATIO {
+            window.below_since = None;
+            if window.relaxed.take().is_some() {
+                info!("Consensus agreement for {} recovered to {:.2}%, threshold restored to {:.2}%",
+                    method, rate * 100.0, self.configured * 100.0);
+            }
+            return;
+        }
+
+        let below_since = *window.below_since.get_or_insert(now);
+        if now.duration_since(below_since) < RELAX_AFTER {
+            return;
+        }
+        let relaxed = (rate - RELAX_MARGIN).clamp(0.0, self.configured);
+        if window.relaxed.is_none() {
+            warn!("Consensus agreement for {} has been {:.2}% for over {}s, relaxing threshold from {:.2}% to {:.2}%",
+                method, rate * 100.0, RELAX_AFTER.as_secs(), self.configured * 100.0, relaxed * 100.0);
+        }
+        window.relaxed = Some(relaxed);
+    }
+
+    // The configured threshold unless the method is currently relaxed
+    pub fn threshold(&self, method: &str) -> f64 {
+        self.windows.get(method)
+            .and_then(|window| window.relaxed)
+            .unwrap_or(self.configured)
+    }
+
+    pub fn snapshot(&self) -> Value {
+        let methods: serde_json::Map<String, Value> = self.windows.iter()
+            .map(|window| (window.key().clone(), json!({
+                "rolling_agreement": window.rolling_rate(),
+                "samples": window.samples.len(),
+                "threshold": window.relaxed.unwrap_or(self.configured),
+                "relaxed": window.relaxed.is_some(),
+            })))
+            .collect();
+        Value::Object(methods)
+    }
+}
+
+// Which endpoints agreed with the chosen response and which didn't, recorded whenever they differ
+#[derive(Debug, Clone, Serialize)]
+pub struct ConsensusDiff {
+    pub method: String,
+    pub timestamp: DateTime<Utc>,
+    pub consensus_achieved: bool,
+    pub agreed: Vec<EndpointVote>,
+    pub disagreed: Vec<EndpointVote>,
+    // Largest distance from the median, numeric methods only
+    pub max_deviation: Option<f64>,
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct EndpointVote {
+    pub endpoint_id: Uuid,
+    pub url: String,
+    pub response: Value,
+    // Distance from the median, numeric methods only
+    pub deviation: Option<f64>,
+}
+
+// One response cache entry as persisted in the consensus snapshot file
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ConsensusSnapshotEntry {
+    pub cache_key: String,
+    pub response: Value,
+    pub confidence: f64,
+    #[serde(default)]
+    pub endpoint_count: usize,
+    pub timestamp: DateTime<Utc>,
 }
 
 #[derive(Debug, Clone)]
@@ -45,6 +194,8 @@ pub struct ConsensusRequest {
     pub params: Value,
     pub endpoints: Vec<EndpointInfo>,
     pub require_consensus: bool,
+    // Client headers to send each endpoint, already narrowed to its forward_headers
+    pub forwarded_headers: HashMap<Uuid, HeaderMap>,
 }
 
 #[derive(Debug, Clone)]
@@ -57,6 +208,32 @@ pub struct ConsensusResponse {
     pub errors: HashMap<Uuid, String>,
 }
 
+#[derive(Debug, Clone, Deserialize)]
+pub struct SimulationRequest {
+    pub transaction_base64: String,
+    #[serde(default = "default_simulation_commitment")]
+    pub commitment: String,
+}
+
+fn default_simulation_commitment() -> String {
+    "finalized".to_string()
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct SimulationConsensus {
+    pub consensus_result: Value,
+    pub per_endpoint_results: Vec<Value>,
+    pub divergences: Vec<Value>,
+    pub consensus_achieved: bool,
+}
+
+#[derive(Debug, Clone)]
+struct SimulationOutcome {
+    endpoint: EndpointInfo,
+    response: Result<Value, String>,
+    response_time: Duration,
+}
+
 #[derive(Debug, Clone)]
 struct EndpointResponse {
     endpoint_id: Uuid,
@@ -67,10 +244,57 @@ struct EndpointResponse {
 impl ConsensusService {
     pub fn new(config: ConsensusConfig) -> Self {
         Self {
-            config,
             response_cache: Arc::new(DashMap::new()),
             validation_stats: Arc::new(DashMap::new()),
+            partition_simulator: None,
+            threshold_overrides: Arc::new(DashMap::new()),
+            recent_diffs: Arc::new(Mutex::new(VecDeque::with_capacity(RECENT_DIFFS_CAPACITY))),
+            last_available_endpoints: Arc::new(AtomicUsize::new(0)),
+            adaptive_threshold: Arc::new(AdaptiveConsensusThreshold::new(config.consensus_threshold)),
+            config,
+        }
+    }
+
+    pub fn with_partition_simulator(mut self, simulator: Arc<NetworkPartitionSimulator>) -> Self {
+        self.partition_simulator = Some(simulator);
+        self
+    }
+
+    // Uses `threshold` for the given methods until `duration` has passed
+    pub fn override_threshold(&self, methods: &[&str], threshold: f64, duration: Duration) {
+        let expires_at = Instant::now() + duration;
+        for method in methods {
+            self.threshold_overrides.insert(method.to_string(), (threshold, expires_at));
+        }
+    }
+
+    // Successful responses needed out of `available_endpoints`: the configured fraction,
+    // rounded up, but never less than min_confirmations unless fewer endpoints exist
+    pub fn quorum(&self, available_endpoints: usize) -> usize {
+        let fractional = (available_endpoints as f64 * self.config.min_quorum_fraction).ceil() as usize;
+        let floor = (self.config.min_confirmations as usize).min(available_endpoints);
+        fractional.max(1).max(floor)
+    }
+
+    pub fn consensus_threshold(&self, method: &str) -> f64 {
+        self.active_override(method)
+            .unwrap_or_else(|| self.adaptive_threshold.threshold(method))
+    }
+
+    // Methods under a threshold override are validated by consensus until it expires
+    pub fn has_threshold_override(&self, method: &str) -> bool {
+        self.active_override(method).is_some()
+    }
+
+    fn active_override(&self, method: &str) -> Option<f64> {
+        let entry = self.threshold_overrides.get(method)?;
+        let (threshold, expires_at) = *entry;
+        if Instant::now() < expires_at {
+            return Some(threshold);
         }
+        drop(entry);
+        self.threshold_overrides.remove(method);
+        None
     }
 
     pub async fn validate_response(
@@ -128,13 +352,15 @@ impl ConsensusService {
         clients: HashMap<Uuid, reqwest::Client>,
     ) -> Result<ConsensusResponse, AppError> {
         let timeout_duration = Duration::from_millis(self.config.timeout_ms);
-        let min_confirmations = self.config.min_confirmations.min(clients.len() as u32);
+        let quorum = self.quorum(clients.len());
+        self.last_available_endpoints.store(clients.len(), Ordering::Relaxed);
         
-        debug!("Executing consensus for method: {} with {} endpoints", 
-            request.method, clients.len());
+        debug!("Executing consensus for method: {} with {} endpoints (quorum {})", 
+            request.method, clients.len(), quorum);
 
-        // Execute requests in parallel
+        // Execute requests in parallel, each traced under the caller's consensus span
         let mut tasks = Vec::new();
+        let parent_span = current_span();
         
         for (endpoint_id, client) in clients {
             let endpoint_url = request.endpoints
@@ -150,11 +376,25 @@ impl ConsensusService {
                 "params": request.params
             });
 
-            let task = async move {
+            let forwarded_headers = request.forwarded_headers.get(&endpoint_id).cloned().unwrap_or_default();
+            let partitioned = self.partition_simulator.as_ref()
+                .is_some_and(|simulator| simulator.check_blocked(endpoint_id));
+
+            let task = in_span_of(parent_span.clone(), "upstream_request", async move {
+                set_attribute("endpoint", endpoint_url.as_str());
                 let start = Instant::now();
+                if partitioned {
+                    return EndpointResponse {
+                        endpoint_id,
+                        response: Err("Connect timeout (simulated partition)".to_string()),
+                        response_time: start.elapsed(),
+                    };
+                }
                 let result = timeout(
                     timeout_duration,
-                    client.post(&endpoint_url).json(&request_payload).send()
+                    with_forwarded_headers(with_baggage(client.post(&endpoint_url)), &forwarded_headers)
+                        .json(&request_payload)
+                        .send()
                 ).await;
 
                 let response = match result {
@@ -173,9 +413,9 @@ impl ConsensusService {
                     response,
                     response_time: start.elapsed(),
                 }
-            };
+            });
 
-            tasks.push(tokio::spawn(task));
+            tasks.push(tokio::spawn(task.with_current_context().in_current_span()));
         }
 
         // Collect responses
@@ -201,24 +441,151 @@ impl ConsensusService {
             }
         }
 
-        // Check if we have minimum confirmations
-        if responses.len() < min_confirmations as usize {
+        // Check if we have a quorum
+        if responses.len() < quorum {
             return Err(AppError::InsufficientConfirmations);
         }
 
         // Perform consensus analysis
-        let consensus_result = self.analyze_consensus(&request.method, responses)?;
+        let consensus_result = self.analyze_consensus(&request.method, responses, &request.endpoints)?;
 
         Ok(ConsensusResponse {
             response: consensus_result.0,
             confidence: consensus_result.1,
             endpoint_count: response_times.len(),
-            consensus_achieved: consensus_result.1 >= self.config.consensus_threshold,
+            consensus_achieved: consensus_result.1 >= self.consensus_threshold(&request.method),
             response_times,
             errors,
         })
     }
 
+    pub async fn validate_transaction_simulation(
+        &self,
+        request: SimulationRequest,
+        endpoints: Vec<(EndpointInfo, reqwest::Client)>,
+    ) -> Result<SimulationConsensus, AppError> {
+        if endpoints.len() < 2 {
+            return Err(AppError::consensus("Simulation consensus needs at least 2 healthy endpoints"));
+        }
+
+        let timeout_duration = Duration::from_millis(self.config.timeout_ms);
+        let request_payload = json!({
+            "jsonrpc": "2.0",
+            "id": 1,
+            "method": "simulateTransaction",
+            "params": [
+                request.transaction_base64,
+                {
+                    "encoding": "base64",
+                    "commitment": request.commitment,
+                    "sigVerify": false
+                }
+            ]
+        });
+
+        let tasks: Vec<_> = endpoints.into_iter()
+            .map(|(endpoint, client)| {
+                let payload = request_payload.clone();
+                tokio::spawn(async move {
+                    let start = Instant::now();
+                    let result = timeout(
+                        timeout_duration,
+                        with_baggage(client.post(&endpoint.url)).json(&payload).send()
+                    ).await;
+
+                    let response = match result {
+                        Ok(Ok(resp)) => resp.json::<Value>().await
+                            .map_err(|e| format!("JSON parse error: {}", e)),
+                        Ok(Err(e)) => Err(format!("HTTP error: {}", e)),
+                        Err(_) => Err("Request timeout".to_string()),
+                    };
+
+                    SimulationOutcome {
+                        endpoint,
+                        response,
+                        response_time: start.elapsed(),
+                    }
+                }.with_current_context())
+            })
+            .collect();
+
+        let mut outcomes = Vec::new();
+        for task in tasks {
+            match task.await {
+                Ok(outcome) => outcomes.push(outcome),
+                Err(e) => error!("Simulation task execution error: {}", e),
+            }
+        }
+
+        Ok(self.analyze_simulation_outcomes(outcomes))
+    }
+
+    fn analyze_simulation_outcomes(&self, outcomes: Vec<SimulationOutcome>) -> SimulationConsensus {
+        // Endpoints agree when the simulation outcome (the `err` field) matches;
+        // logs and compute units legitimately vary between nodes
+        let outcome_key = |outcome: &SimulationOutcome| match &outcome.response {
+            Ok(resp) => match resp.get("error") {
+                Some(rpc_error) => format!("rpc_error:{}", rpc_error["code"]),
+                None => format!("err:{}", resp["result"]["value"]["err"]),
+            },
+            Err(e) => format!("request_failed:{}", e),
+        };
+
+        let mut counts: HashMap<String, usize> = HashMap::new();
+        for outcome in &outcomes {
+            *counts.entry(outcome_key(outcome)).or_insert(0) += 1;
+        }
+
+        let (majority_key, majority_count) = counts.into_iter()
+            .max_by_key(|(_, count)| *count)
+            .unwrap_or_default();
+        let confidence = if outcomes.is_empty() {
+            0.0
+        } else {
+            majority_count as f64 / outcomes.len() as f64
+        };
+
+        let consensus_result = outcomes.iter()
+            .find(|o| outcome_key(o) == majority_key)
+            .and_then(|o| o.response.as_ref().ok())
+            .map(|resp| resp.get("result").cloned().unwrap_or_else(|| resp.clone()))
+            .unwrap_or(Value::Null);
+
+        let per_endpoint_results = outcomes.iter()
+            .map(|o| json!({
+                "endpoint_id": o.endpoint.id,
+                "endpoint": o.endpoint.name,
+                "response_time_ms": o.response_time.as_millis() as u64,
+                "result": o.response.as_ref().ok().and_then(|r| r.get("result")),
+                "error": match &o.response {
+                    Ok(resp) => resp.get("error").cloned(),
+                    Err(e) => Some(json!(e)),
+                },
+            }))
+            .collect();
+
+        let divergences = outcomes.iter()
+            .filter(|o| outcome_key(o) != majority_key)
+            .map(|o| json!({
+                "endpoint_id": o.endpoint.id,
+                "endpoint": o.endpoint.name,
+                "outcome": outcome_key(o),
+                "expected": majority_key,
+            }))
+            .collect::<Vec<_>>();
+
+        if !divergences.is_empty() {
+            warn!("Simulation divergence across {} endpoint(s)", divergences.len());
+        }
+
+        SimulationConsensus {
+            consensus_result,
+            per_endpoint_results,
+            divergences,
+            consensus_achieved: confidence >= self.config.consensus_threshold,
+        }
+    }
+
     async fn get_fastest_response(
         &self,
         request: ConsensusRequest,
@@ -239,9 +606,9 @@ impl ConsensusService {
                 "params": request.params
             });
 
+            let forwarded_headers = request.forwarded_headers.get(&endpoint_id).cloned().unwrap_or_default();
             let start = Instant::now();
-            let response = client
-                .post(&endpoint_url)
+            let response = with_forwarded_headers(with_baggage(client.post(&endpoint_url)), &forwarded_headers)
                 .json(&request_payload)
                 .send()
                 .await?;
@@ -269,40 +636,56 @@ impl ConsensusService {
         &self,
         method: &str,
         responses: Vec<(Uuid, Value)>,
+        endpoints: &[EndpointInfo],
     ) -> Result<(Value, f64), AppError> {
         if responses.is_empty() {
             return Err(AppError::InsufficientConfirmations);
         }
+        let threshold = self.consensus_threshold(method);
+        let large_result = responses[0].1.get("result")
+            .is_some_and(|result| serialized_len_exceeds(result, self.config.hash_threshold_bytes));
 
         match method {
-            // For balance and account info, use exact matching
-            "getBalance" | "getAccountInfo" => {
-                self.consensus_exact_match(responses)
-            }
-            
             // For slot-based methods, allow small differences
             "getSlot" | "getBlockHeight" => {
-                self.consensus_numeric_tolerance(responses, 2.0) // Allow 2 slot difference
-            }
-            
-            // For transaction status, use majority vote
-            "getSignatureStatuses" => {
-                self.consensus_majority_vote(responses)
+                // Allow 2 slot difference
+                self.consensus_numeric_tolerance(method, responses, endpoints, 2.0, threshold)
             }
             
             // For block data, use hash comparison
             "getBlock" | "getRecentBlockhash" | "getLatestBlockhash" => {
-                self.consensus_hash_based(responses)
+                self.consensus_hash_based(method, responses, threshold)
+            }
+            
+            // Everything else compares whole responses, which for large results is cheaper by digest
+            _ if large_result => {
+                self.consensus_by_result_hash(method, responses, endpoints, threshold)
+            }
+            
+            // For balance and account info, use exact matching
+            "getBalance" | "getAccountInfo" => {
+                self.consensus_exact_match(method, responses, endpoints, threshold)
+            }
+            
+            // For transaction status, use majority vote
+            "getSignatureStatuses" => {
+                self.consensus_majority_vote(method, responses, endpoints, threshold)
             }
             
             // Default: exact match
             _ => {
-                self.consensus_exact_match(responses)
+                self.consensus_exact_match(method, responses, endpoints, threshold)
             }
         }
     }
 
-    fn consensus_exact_match(&self, responses: Vec<(Uuid, Value)>) -> Result<(Value, f64), AppError> {
+    fn consensus_exact_match(
+        &self,
+        method: &str,
+        responses: Vec<(Uuid, Value)>,
+        endpoints: &[EndpointInfo],
+        threshold: f64,
+    ) -> Result<(Value, f64), AppError> {
         let mut response_counts: HashMap<String, (Value, usize)> = HashMap::new();
         
         for (_, response) in &responses {
@@ -318,20 +701,101 @@ impl ConsensusService {
             .ok_or_else(|| AppError::consensus("No responses to analyze"))?;
 
         let confidence = count as f64 / responses.len() as f64;
+        self.adaptive_threshold.record(method, confidence);
+        if count < responses.len() {
+            let (agreed, disagreed) = responses.iter()
+                .map(|(id, response)| endpoint_vote(endpoints, *id, response, None))
+                .partition(|vote| vote.response == consensus_response);
+            self.record_diff(ConsensusDiff {
+                method: method.to_string(),
+                timestamp: Utc::now(),
+                consensus_achieved: confidence >= threshold,
+                agreed,
+                disagreed,
+                max_deviation: None,
+            });
+        }
         
-        if confidence < self.config.consensus_threshold {
+        if confidence < threshold {
+            warn!("Consensus not achieved: {:.2}% agreement", confidence * 100.0);
+            return Err(AppError::consensus(&format!(
+                "Consensus threshold not met: {:.2}% < {:.2}%",
+                confidence * 100.0,
+                threshold * 100.0
+            )));
+        }
+
+        Ok((consensus_response, confidence))
+    }
+
+    // Exact match on the SHA-256 of each canonicalized `result` (the whole response when there is
+    // none), so no response is ever serialized in full or compared field by field
+    pub fn consensus_by_result_hash(
+        &self,
+        method: &str,
+        responses: Vec<(Uuid, Value)>,
+        endpoints: &[EndpointInfo],
+        threshold: f64,
+    ) -> Result<(Value, f64), AppError> {
+        let digests: Vec<[u8; 32]> = responses.iter()
+            .map(|(_, response)| canonical_digest(response.get("result").unwrap_or(response)))
+            .collect();
+        let mut digest_counts: HashMap<[u8; 32], (usize, usize)> = HashMap::new();
+        for (index, digest) in digests.iter().enumerate() {
+            digest_counts.entry(*digest).or_insert((index, 0)).1 += 1;
+        }
+
+        let (consensus_digest, (consensus_index, count)) = digest_counts
+            .into_iter()
+            .max_by_key(|(_, (_, count))| *count)
+            .ok_or_else(|| AppError::consensus("No responses to analyze"))?;
+
+        let confidence = count as f64 / responses.len() as f64;
+        self.adaptive_threshold.record(method, confidence);
+        if count < responses.len() {
+            let (mut agreed, mut disagreed) = (Vec::new(), Vec::new());
+            for ((id, response), digest) in responses.iter().zip(&digests) {
+                let vote = endpoint_vote(endpoints, *id, response, None);
+                if *digest == consensus_digest {
+                    agreed.push(vote);
+                } else {
+                    disagreed.push(vote);
+                }
+            }
+            self.record_diff(ConsensusDiff {
+                method: method.to_string(),
+                timestamp: Utc::now(),
+                consensus_achieved: confidence >= threshold,
+                agreed,
+                disagreed,
+                max_deviation: None,
+            });
+        }
+
+        if confidence < threshold {
             warn!("Consensus not achieved: {:.2}% agreement", confidence * 100.0);
             return Err(AppError::consensus(&format!(
                 "Consensus threshold not met: {:.2}% < {:.2}%",
                 confidence * 100.0,
-                self.config.consensus_threshold * 100.0
+                threshold * 100.0
             )));
         }
 
+        let consensus_response = responses.into_iter()
+            .nth(consensus_index)
+            .map(|(_, response)| response)
+            .ok_or_else(|| AppError::consensus("No responses to analyze"))?;
         Ok((consensus_response, confidence))
     }
 
-    fn consensus_numeric_tolerance(&self, responses: Vec<(Uuid, Value)>, tolerance: f64) -> Result<(Value, f64), AppError> {
+    fn consensus_numeric_tolerance(
+        &self,
+        method: &str,
+        responses: Vec<(Uuid, Value)>,
+        endpoints: &[EndpointInfo],
+        tolerance: f64,
+        threshold: f64,
+    ) -> Result<(Value, f64), AppError> {
         let mut numeric_values = Vec::new();
         
         for (_, response) in &responses {
@@ -363,8 +827,30 @@ impl ConsensusService {
             .count();
 
         let confidence = within_tolerance as f64 / numeric_values.len() as f64;
+        self.adaptive_threshold.record(method, confidence);
+        if within_tolerance < responses.len() {
+            let votes: Vec<EndpointVote> = responses.iter()
+                .map(|(id, response)| {
+                    let deviation = response.get("result").and_then(|r| r.as_f64()).map(|v| v - median);
+                    endpoint_vote(endpoints, *id, response, deviation)
+                })
+                .collect();
+            let max_deviation = votes.iter()
+                .filter_map(|vote| vote.deviation.map(f64::abs))
+                .fold(None, |max: Option<f64>, d| Some(max.map_or(d, |m| m.max(d))));
+            let (agreed, disagreed) = votes.into_iter()
+                .partition(|vote| vote.deviation.is_some_and(|d| d.abs() <= tolerance));
+            self.record_diff(ConsensusDiff {
+                method: method.to_string(),
+                timestamp: Utc::now(),
+                consensus_achieved: confidence >= threshold,
+                agreed,
+                disagreed,
+                max_deviation,
+            });
+        }
         
-        if confidence < self.config.consensus_threshold {
+        if confidence < threshold {
             return Err(AppError::consensus(&format!(
                 "Numeric consensus not achieved: {:.2}% within tolerance",
                 confidence * 100.0
@@ -387,12 +873,43 @@ impl ConsensusService {
         Ok((consensus_response, confidence))
     }
 
-    fn consensus_majority_vote(&self, responses: Vec<(Uuid, Value)>) -> Result<(Value, f64), AppError> {
+    fn consensus_majority_vote(
+        &self,
+        method: &str,
+        responses: Vec<(Uuid, Value)>,
+        endpoints: &[EndpointInfo],
+        threshold: f64,
+    ) -> Result<(Value, f64), AppError> {
         // Similar to exact match but with more lenient comparison
-        self.consensus_exact_match(responses)
+        self.consensus_exact_match(method, responses, endpoints, threshold)
     }
 
-    fn consensus_hash_based(&self, responses: Vec<(Uuid, Value)>) -> Result<(Value, f64), AppError> {
+    fn record_diff(&self, diff: ConsensusDiff) {
+        let describe = |votes: &[EndpointVote]| votes.iter()
+            .map(|vote| match vote.deviation {
+                Some(deviation) => format!("{} ({:+})", vote.url, deviation),
+                None => vote.url.clone(),
+            })
+            .collect::<Vec<_>>()
+            .join(", ");
+        warn!(
+            "Consensus disagreement for {}: agreed [{}], disagreed [{}], max deviation {:?}",
+            diff.method, describe(&diff.agreed), describe(&diff.disagreed), diff.max_deviation
+        );
+
+        let mut diffs = self.recent_diffs.lock().unwrap();
+        if diffs.len() == RECENT_DIFFS_CAPACITY {
+            diffs.pop_front();
+        }
+        diffs.push_back(diff);
+    }
+
+    // Oldest first
+    pub fn get_recent_diffs(&self) -> Vec<ConsensusDiff> {
+        self.recent_diffs.lock().unwrap().iter().cloned().collect()
+    }
+
+    fn consensus_hash_based(&self, method: &str, responses: Vec<(Uuid, Value)>, threshold: f64) -> Result<(Value, f64), AppError> {
         // For hash-based responses, extract and compare hash values
         let mut hash_counts: HashMap<String, (Value, usize)> = HashMap::new();
         
@@ -408,8 +925,9 @@ impl ConsensusService {
             .ok_or_else(|| AppError::consensus("No hash responses to analyze"))?;
 
         let confidence = count as f64 / responses.len() as f64;
+        self.adaptive_threshold.record(method, confidence);
         
-        if confidence < self.config.consensus_threshold {
+        if confidence < threshold {
             return Err(AppError::consensus(&format!(
                 "Hash consensus not achieved: {:.2}% agreement",
                 confidence * 100.0
@@ -482,6 +1000,7 @@ impl ConsensusService {
     }
 
     pub async fn get_debug_info(&self) -> Value {
+        let available_endpoints = self.last_available_endpoints.load(Ordering::Relaxed);
         let cache_size = self.response_cache.len();
         let stats_count = self.validation_stats.len();
         
@@ -503,12 +1022,17 @@ impl ConsensusService {
         json!({
             "enabled": self.config.enabled,
             "min_confirmations": self.config.min_confirmations,
+            "min_quorum_fraction": self.config.min_quorum_fraction,
+            "available_endpoints": available_endpoints,
+            "quorum": self.quorum(available_endpoints),
             "consensus_threshold": self.config.consensus_threshold,
+            "adaptive_thresholds": self.adaptive_threshold.snapshot(),
             "timeout_ms": self.config.timeout_ms,
             "cache_size": cache_size,
             "stats_count": stats_count,
             "method_stats": method_stats,
             "critical_methods": self.config.critical_methods,
+            "recent_diffs": self.get_recent_diffs(),
         })
     }
 
@@ -516,6 +1040,73 @@ impl ConsensusService {
         self.response_cache.clear();
     }
 
+    // Writes the live response cache to the configured snapshot file so the next start can
+    // serve consensus-critical methods without a cold cache
+    pub async fn dump_cache_snapshot(&self) -> Result<usize, AppError> {
+        let path = self.config.consensus_snapshot_path.as_deref()
+            .ok_or_else(|| AppError::config("consensus_snapshot_path is not configured"))?;
+
+        let now = Utc::now();
+        let entries: Vec<ConsensusSnapshotEntry> = self.response_cache.iter()
+            .filter(|entry| entry.timestamp.elapsed() < entry.ttl)
+            .map(|entry| ConsensusSnapshotEntry {
+                cache_key: entry.key().clone(),
+                response: entry.response.clone(),
+                confidence: entry.confidence,
+                endpoint_count: entry.endpoint_count,
+                timestamp: now - chrono::Duration::from_std(entry.timestamp.elapsed()).unwrap_or_else(|_| chrono::Duration::zero()),
+            })
+            .collect();
+
+        if let Some(parent) = std::path::Path::new(path).parent().filter(|p| !p.as_os_str().is_empty()) {
+            tokio::fs::create_dir_all(parent).await?;
+        }
+        tokio::fs::write(path, serde_json::to_vec(&entries)?).await?;
+
+        info!("Wrote {} consensus cache entries to {}", entries.len(), path);
+        Ok(entries.len())
+    }
+
+    // Pre-seeds the response cache from the snapshot file. Entries older than
+    // max_snapshot_age_secs are dropped and the rest only live out the remainder of that age.
+    pub async fn bootstrap_from_snapshot(&self) -> Result<usize, AppError> {
+        let Some(path) = self.config.consensus_snapshot_path.as_deref() else {
+            return Ok(0);
+        };
+
+        let contents = match tokio::fs::read(path).await {
+            Ok(contents) => contents,
+            Err(e) if e.kind() == std::io::ErrorKind::NotFound => {
+                debug!("No consensus snapshot at {}, starting with a cold cache", path);
+                return Ok(0);
+            }
+            Err(e) => return Err(e.into()),
+        };
+        let entries: Vec<ConsensusSnapshotEntry> = serde_json::from_slice(&contents)?;
+
+        let max_age = Duration::from_secs(self.config.max_snapshot_age_secs);
+        let now = Utc::now();
+        let mut loaded = 0;
+        for entry in entries {
+            let age = (now - entry.timestamp).to_std().unwrap_or(Duration::ZERO);
+            if age >= max_age {
+                continue;
+            }
+
+            self.response_cache.insert(entry.cache_key, CachedConsensus {
+                response: entry.response,
+                confidence: entry.confidence,
+                endpoint_count: entry.endpoint_count,
+                timestamp: Instant::now(),
+                ttl: max_age - age,
+            });
+            loaded += 1;
+        }
+
+        info!("Bootstrapped {} consensus cache entries from {}", loaded, path);
+        Ok(loaded)
+    }
+
     pub async fn get_cache_stats(&self) -> Value {
         json!({
             "total_entries": self.response_cache.len(),
@@ -523,4 +1114,580 @@ impl ConsensusService {
             "cache_misses": 0, // TODO: implement miss tracking
         })
     }
-}
\ No newline at end of file
+}
+// Feeds serialized JSON straight into the hash instead of a buffer
+struct DigestWriter<'a>(&'a mut Sha256);
+
+impl Write for DigestWriter<'_> {
+    fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+        self.0.update(buf);
+        Ok(buf.len())
+    }
+
+    fn flush(&mut self) -> io::Result<()> {
+        Ok(())
+    }
+}
+
+// Fails the write once more than `limit` bytes have gone through, which stops serialization early
+struct LimitWriter {
+    written: usize,
+    limit: usize,
+}
+
+impl Write for LimitWriter {
+    fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+        self.written += buf.len();
+        if self.written > self.limit {
+            return Err(io::Error::other("size limit exceeded"));
+        }
+        Ok(buf.len())
+    }
+
+    fn flush(&mut self) -> io::Result<()> {
+        Ok(())
+    }
+}
+
+// Sends the headers the router picked out of the client's for this endpoint
+fn with_forwarded_headers(mut builder: reqwest::RequestBuilder, headers: &HeaderMap) -> reqwest::RequestBuilder {
+    for (name, value) in headers {
+        builder = builder.header(name.as_str(), value.as_bytes());
+    }
+    builder
+}
+
+fn serialized_len_exceeds(value: &Value, limit: usize) -> bool {
+    let mut writer = LimitWriter { written: 0, limit };
+    serde_json::to_writer(&mut writer, value).is_err() && writer.written > limit
+}
+
+// SHA-256 of `value` as JSON with object keys sorted, so endpoints that order keys differently still agree
+fn canonical_digest(value: &Value) -> [u8; 32] {
+    let mut hasher = Sha256::new();
+    hash_canonical(value, &mut hasher);
+    hasher.finalize().into()
+}
+
+fn hash_canonical(value: &Value, hasher: &mut Sha256) {
+    match value {
+        Value::Object(fields) => {
+            let mut keys: Vec<&String> = fields.keys().collect();
+            keys.sort();
+            hasher.update(b"{");
+            for (index, key) in keys.into_iter().enumerate() {
+                if index > 0 {
+                    hasher.update(b",");
+                }
+                // Writing to a hasher can't fail
+                let _ = serde_json::to_writer(DigestWriter(hasher), key);
+                hasher.update(b":");
+                hash_canonical(&fields[key], hasher);
+            }
+            hasher.update(b"}");
+        }
+        Value::Array(items) => {
+            hasher.update(b"[");
+            for (index, item) in items.iter().enumerate() {
+                if index > 0 {
+                    hasher.update(b",");
+                }
+                hash_canonical(item, hasher);
+            }
+            hasher.update(b"]");
+        }
+        scalar => {
+            let _ = serde_json::to_writer(DigestWriter(hasher), scalar);
+        }
+    }
+}
+
+fn endpoint_vote(endpoints: &[EndpointInfo], endpoint_id: Uuid, response: &Value, deviation: Option<f64>) -> EndpointVote {
+    EndpointVote {
+        endpoint_id,
+        url: endpoints.iter()
+            .find(|e| e.id == endpoint_id)
+            .map(|e| e.url.clone())
+            .unwrap_or_default(),
+        response: response.clone(),
+        deviation,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{
+        test_server::TestServer,
+        types::{EndpointScore, EndpointStatus},
+    };
+
+    fn endpoint(name: &str) -> EndpointInfo {
+        EndpointInfo {
+            id: Uuid::new_v4(),
+            url: format!("https://{}.example.com", name),
+            name: name.to_string(),
+            status: EndpointStatus::Healthy,
+            score: EndpointScore::default(),
+            last_checked: chrono::Utc::now(),
+            weight: 100,
+            priority: 1,
+            region: None,
+            latitude: None,
+            longitude: None,
+            tls_cert_expires_at: None,
+            tls_cert_days_remaining: None,
+            slot_lag: None,
+            slot_lag_priority: None,
+        }
+    }
+
+    fn simulation(name: &str, err: Value) -> SimulationOutcome {
+        SimulationOutcome {
+            endpoint: endpoint(name),
+            response: Ok(json!({
+                "jsonrpc": "2.0",
+                "id": 1,
+                "result": {
+                    "context": { "slot": 100 },
+                    "value": { "err": err, "logs": [], "unitsConsumed": 150 }
+                }
+            })),
+            response_time: Duration::from_millis(20),
+        }
+    }
+
+    fn service() -> ConsensusService {
+        ConsensusService::new(ConsensusConfig {
+            enabled: true,
+            min_confirmations: 2,
+            min_quorum_fraction: 0.5,
+            timeout_ms: 1000,
+            critical_methods: Vec::new(),
+            consensus_threshold: 0.6,
+            max_deviation: 0.1,
+            consensus_snapshot_path: None,
+            max_snapshot_age_secs: 300,
+            hash_threshold_bytes: 4096,
+        })
+    }
+
+    #[test]
+    fn test_simulation_divergence_detected() {
+        let result = service().analyze_simulation_outcomes(vec![
+            simulation("a", Value::Null),
+            simulation("b", Value::Null),
+            simulation("c", json!({"InstructionError": [0, "InvalidAccountData"]})),
+        ]);
+
+        assert!(result.consensus_achieved);
+        assert_eq!(result.consensus_result["value"]["err"], Value::Null);
+        assert_eq!(result.per_endpoint_results.len(), 3);
+        assert_eq!(result.divergences.len(), 1);
+        assert_eq!(result.divergences[0]["endpoint"], "c");
+    }
+
+    #[test]
+    fn test_simulation_without_majority() {
+        let mut failed = simulation("c", Value::Null);
+        failed.response = Err("Request timeout".to_string());
+
+        let result = service().analyze_simulation_outcomes(vec![
+            simulation("a", Value::Null),
+            simulation("b", json!("AccountNotFound")),
+            failed,
+        ]);
+
+        assert!(!result.consensus_achieved);
+        assert_eq!(result.divergences.len(), 2);
+    }
+
+    fn snapshot_service(path: &std::path::Path) -> ConsensusService {
+        let mut service = service();
+        service.config.consensus_snapshot_path = Some(path.to_string_lossy().into_owned());
+        service.config.max_snapshot_age_secs = 60;
+        service
+    }
+
+    #[tokio::test]
+    async fn test_snapshot_dump_and_reload() {
+        let path = std::env::temp_dir().join(format!("multi-rpc-consensus-{}.json", Uuid::new_v4()));
+        let source = snapshot_service(&path);
+        let key = source.create_cache_key("getBalance", &json!(["alice"]));
+        source.response_cache.insert(key.clone(), CachedConsensus {
+            response: json!({"jsonrpc": "2.0", "id": 1, "result": {"value": 42}}),
+            confidence: 0.9,
+            endpoint_count: 3,
+            timestamp: Instant::now(),
+            ttl: Duration::from_secs(10),
+        });
+
+        assert_eq!(source.dump_cache_snapshot().await.unwrap(), 1);
+
+        let restored = snapshot_service(&path);
+        assert_eq!(restored.bootstrap_from_snapshot().await.unwrap(), 1);
+        let cached = restored.response_cache.get(&key).unwrap();
+        assert_eq!(cached.response["result"]["value"], 42);
+        assert_eq!(cached.confidence, 0.9);
+        assert_eq!(cached.endpoint_count, 3);
+        assert!(cached.ttl <= Duration::from_secs(60));
+        drop(cached);
+
+        // A cache hit is served without contacting any endpoint
+        let response = restored.validate_response(ConsensusRequest {
+            method: "getBalance".to_string(),
+            params: json!(["alice"]),
+            endpoints: Vec::new(),
+            require_consensus: true,
+            forwarded_headers: HashMap::new(),
+        }, HashMap::new()).await.unwrap();
+        assert_eq!(response.response["result"]["value"], 42);
+
+        let _ = std::fs::remove_file(&path);
+    }
+
+    #[tokio::test]
+    async fn test_snapshot_drops_stale_entries() {
+        let path = std::env::temp_dir().join(format!("multi-rpc-consensus-{}.json", Uuid::new_v4()));
+        let entries = vec![
+            ConsensusSnapshotEntry {
+                cache_key: "getBalance:[\"fresh\"]".to_string(),
+                response: json!({"result": 1}),
+                confidence: 1.0,
+                endpoint_count: 2,
+                timestamp: Utc::now() - chrono::Duration::seconds(30),
+            },
+            ConsensusSnapshotEntry {
+                cache_key: "getBalance:[\"stale\"]".to_string(),
+                response: json!({"result": 2}),
+                confidence: 1.0,
+                endpoint_count: 2,
+                timestamp: Utc::now() - chrono::Duration::seconds(120),
+            },
+        ];
+        std::fs::write(&path, serde_json::to_vec(&entries).unwrap()).unwrap();
+
+        let service = snapshot_service(&path);
+        assert_eq!(service.bootstrap_from_snapshot().await.unwrap(), 1);
+        let fresh = service.response_cache.get("getBalance:[\"fresh\"]").unwrap();
+        assert!(fresh.ttl <= Duration::from_secs(30));
+        assert!(service.response_cache.get("getBalance:[\"stale\"]").is_none());
+
+        let _ = std::fs::remove_file(&path);
+    }
+
+    #[tokio::test]
+    async fn test_missing_snapshot_is_cold_start() {
+        let path = std::env::temp_dir().join(format!("multi-rpc-consensus-{}.json", Uuid::new_v4()));
+        assert_eq!(snapshot_service(&path).bootstrap_from_snapshot().await.unwrap(), 0);
+    }
+
+    #[test]
+    fn test_exact_match_disagreement_recorded() {
+        let service = service();
+        let endpoints = vec![endpoint("a"), endpoint("b"), endpoint("c")];
+        let responses = vec![
+            (endpoints[0].id, json!({"result": {"value": 5}})),
+            (endpoints[1].id, json!({"result": {"value": 5}})),
+            (endpoints[2].id, json!({"result": {"value": 7}})),
+        ];
+
+        service.analyze_consensus("getBalance", responses, &endpoints).unwrap();
+
+        let diffs = service.get_recent_diffs();
+        assert_eq!(diffs.len(), 1);
+        assert!(diffs[0].consensus_achieved);
+        assert_eq!(diffs[0].agreed.len(), 2);
+        assert_eq!(diffs[0].disagreed.len(), 1);
+        assert_eq!(diffs[0].disagreed[0].url, "https://c.example.com");
+        assert_eq!(diffs[0].max_deviation, None);
+    }
+
+    // An account whose data takes up about `bytes` of the result
+    fn large_response(bytes: usize, lamports: u64) -> Value {
+        let data = "A".repeat(bytes);
+        json!({"jsonrpc": "2.0", "id": 1, "result": {"value": {"lamports": lamports, "data": [data, "base64"]}, "context": {"slot": 1}}})
+    }
+
+    #[test]
+    fn test_large_results_compared_by_hash() {
+        let service = service();
+        let endpoints = vec![endpoint("a"), endpoint("b"), endpoint("c")];
+        let responses = vec![
+            (endpoints[0].id, large_response(10_000, 5)),
+            (endpoints[1].id, large_response(10_000, 5)),
+            (endpoints[2].id, large_response(10_000, 7)),
+        ];
+
+        let (response, confidence) = service.analyze_consensus("getAccountInfo", responses, &endpoints).unwrap();
+        assert_eq!(response["result"]["value"]["lamports"], 5);
+        assert!((confidence - 2.0 / 3.0).abs() < f64::EPSILON);
+
+        let diffs = service.get_recent_diffs();
+        assert_eq!(diffs.len(), 1);
+        assert_eq!(diffs[0].agreed.len(), 2);
+        assert_eq!(diffs[0].disagreed[0].url, "https://c.example.com");
+
+        let split = vec![
+            (endpoints[0].id, large_response(10_000, 5)),
+            (endpoints[1].id, large_response(10_000, 6)),
+            (endpoints[2].id, large_response(10_000, 7)),
+        ];
+        assert!(matches!(
+            service.analyze_consensus("getAccountInfo", split, &endpoints),
+            Err(AppError::ConsensusError(_))
+        ));
+    }
+
+    #[test]
+    fn test_canonical_digest_ignores_key_order() {
+        let mut forward = serde_json::Map::new();
+        forward.insert("a".to_string(), json!(1));
+        forward.insert("b".to_string(), json!([true, null, "x"]));
+        let mut backward = serde_json::Map::new();
+        backward.insert("b".to_string(), json!([true, null, "x"]));
+        backward.insert("a".to_string(), json!(1));
+
+        assert_eq!(canonical_digest(&Value::Object(forward)), canonical_digest(&Value::Object(backward)));
+        assert_ne!(canonical_digest(&json!({"a": 1})), canonical_digest(&json!({"a": 2})));
+        assert_ne!(canonical_digest(&json!(["ab"])), canonical_digest(&json!(["a", "b"])));
+    }
+
+    #[test]
+    fn test_serialized_len_threshold() {
+        // Quotes included, "aa" serializes to 4 bytes
+        assert!(!serialized_len_exceeds(&json!("aa"), 4));
+        assert!(serialized_len_exceeds(&json!("aaa"), 4));
+        assert!(serialized_len_exceeds(&large_response(10_000, 1)["result"], 4096));
+    }
+
+    // cargo test bench_consensus_by_result_hash -- --ignored --nocapture
+    #[test]
+    #[ignore = "benchmark"]
+    fn bench_consensus_by_result_hash() {
+        const ROUNDS: usize = 500;
+        let service = service();
+        let endpoints: Vec<EndpointInfo> = ["a", "b", "c", "d", "e"].into_iter().map(endpoint).collect();
+        let responses: Vec<(Uuid, Value)> = endpoints.iter()
+            .map(|endpoint| (endpoint.id, large_response(10_000, 5)))
+            .collect();
+
+        for label in ["exact match", "result hash"] {
+            let start = Instant::now();
+            for _ in 0..ROUNDS {
+                let responses = responses.clone();
+                let outcome = match label {
+                    "exact match" => service.consensus_exact_match("getAccountInfo", responses, &endpoints, 0.6),
+                    _ => service.consensus_by_result_hash("getAccountInfo", responses, &endpoints, 0.6),
+                };
+                assert!(outcome.is_ok());
+            }
+            println!("{:>11}: {:>8.2}us/round", label, start.elapsed().as_secs_f64() * 1e6 / ROUNDS as f64);
+        }
+    }
+
+    #[test]
+    fn test_numeric_disagreement_records_magnitude() {
+        let service = service();
+        let endpoints = vec![endpoint("a"), endpoint("b"), endpoint("c")];
+        let responses = vec![
+            (endpoints[0].id, json!({"result": 100})),
+            (endpoints[1].id, json!({"result": 110})),
+            (endpoints[2].id, json!({"result": 130})),
+        ];
+
+        assert!(service.analyze_consensus("getSlot", responses, &endpoints).is_err());
+
+        let diff = &service.get_recent_diffs()[0];
+        assert!(!diff.consensus_achieved);
+        assert_eq!(diff.agreed.len(), 1);
+        assert_eq!(diff.agreed[0].url, "https://b.example.com");
+        assert_eq!(diff.disagreed.len(), 2);
+        assert_eq!(diff.max_deviation, Some(20.0));
+    }
+
+    #[test]
+    fn test_agreement_records_no_diff_and_buffer_is_bounded() {
+        let service = service();
+        let endpoints = vec![endpoint("a"), endpoint("b")];
+        let agreeing = vec![(endpoints[0].id, json!({"result": 1})), (endpoints[1].id, json!({"result": 1}))];
+        service.analyze_consensus("getBalance", agreeing, &endpoints).unwrap();
+        assert!(service.get_recent_diffs().is_empty());
+
+        for i in 0..RECENT_DIFFS_CAPACITY + 5 {
+            let split = vec![(endpoints[0].id, json!({"result": i})), (endpoints[1].id, json!({"result": "other"}))];
+            let _ = service.analyze_consensus("getBalance", split, &endpoints);
+        }
+        let diffs = service.get_recent_diffs();
+        assert_eq!(diffs.len(), RECENT_DIFFS_CAPACITY);
+        // The oldest five were evicted
+        let oldest = &diffs[0];
+        assert!(oldest.agreed.iter().chain(&oldest.disagreed).any(|vote| vote.response["result"] == 5));
+    }
+
+    // `healthy` endpoints answer getBalance, `failing` ones send back a body that isn't JSON
+    async fn partially_failing_endpoints(healthy: usize, failing: usize) -> (TestServer, ConsensusRequest, HashMap<Uuid, reqwest::Client>) {
+        use crate::test_server::TestServerBuilder;
+        use wiremock::{matchers::method, Mock, ResponseTemplate};
+
+        let names: Vec<String> = (0..healthy + failing).map(|i| format!("node-{}", i)).collect();
+        let server = names.iter()
+            .fold(TestServerBuilder::new(), |builder, name| builder.with_endpoint(name))
+            .start()
+            .await;
+        for (i, name) in names.iter().enumerate() {
+            let response = if i < healthy {
+                ResponseTemplate::new(200).set_body_json(json!({"jsonrpc": "2.0", "id": 1, "result": 42}))
+            } else {
+                ResponseTemplate::new(502).set_body_string("bad gateway")
+            };
+            Mock::given(method("POST")).respond_with(response).mount(server.endpoint_mock(name)).await;
+        }
+
+        let manager = &server.state.endpoint_manager;
+        let endpoints = manager.get_endpoint_info().await;
+        let mut clients = HashMap::new();
+        for endpoint in &endpoints {
+            clients.insert(endpoint.id, manager.get_endpoint_client(endpoint.id).await.unwrap());
+        }
+        let request = ConsensusRequest {
+            method: "getBalance".to_string(),
+            params: json!(["Account1"]),
+            endpoints,
+            require_consensus: true,
+            forwarded_headers: HashMap::new(),
+        };
+        (server, request, clients)
+    }
+
+    fn quorum_service(min_confirmations: u32, min_quorum_fraction: f64) -> ConsensusService {
+        let mut service = service();
+        service.config.min_confirmations = min_confirmations;
+        service.config.min_quorum_fraction = min_quorum_fraction;
+        service
+    }
+
+    #[test]
+    fn test_quorum_takes_larger_of_fraction_and_floor() {
+        let service = quorum_service(2, 0.5);
+        assert_eq!(service.quorum(5), 3);
+        assert_eq!(service.quorum(4), 2);
+        assert_eq!(service.quorum(3), 2);
+        // Fewer endpoints than the floor: every available endpoint must answer
+        assert_eq!(service.quorum(1), 1);
+
+        assert_eq!(quorum_service(4, 0.5).quorum(6), 4);
+        assert_eq!(quorum_service(0, 0.0).quorum(6), 1);
+        assert_eq!(quorum_service(1, 1.0).quorum(6), 6);
+    }
+
+    #[tokio::test]
+    async fn test_quorum_met_despite_failed_endpoints() {
+        let (_server, request, clients) = partially_failing_endpoints(3, 2).await;
+        let service = quorum_service(2, 0.5);
+
+        let result = service.execute_consensus(request, clients).await.unwrap();
+        assert!(result.consensus_achieved);
+        assert_eq!(result.response["result"], 42);
+        assert_eq!(result.errors.len(), 2);
+
+        let debug = service.get_debug_info().await;
+        assert_eq!(debug["min_confirmations"], 2);
+        assert_eq!(debug["min_quorum_fraction"], 0.5);
+        assert_eq!(debug["available_endpoints"], 5);
+        assert_eq!(debug["quorum"], 3);
+    }
+
+    #[tokio::test]
+    async fn test_quorum_missed_when_too_many_endpoints_fail() {
+        let (_server, request, clients) = partially_failing_endpoints(2, 3).await;
+        assert!(matches!(
+            quorum_service(2, 0.5).execute_consensus(request, clients).await,
+            Err(AppError::InsufficientConfirmations)
+        ));
+
+        let (_server, request, clients) = partially_failing_endpoints(3, 1).await;
+        assert!(matches!(
+            quorum_service(2, 1.0).execute_consensus(request, clients).await,
+            Err(AppError::InsufficientConfirmations)
+        ));
+    }
+
+    #[tokio::test]
+    async fn test_single_surviving_endpoint_meets_capped_floor() {
+        let (_server, request, clients) = partially_failing_endpoints(1, 0).await;
+        let result = quorum_service(2, 0.5).execute_consensus(request, clients).await.unwrap();
+        assert_eq!(result.response["result"], 42);
+    }
+
+    #[test]
+    fn test_sustained_disagreement_relaxes_threshold() {
+        let adaptive = AdaptiveConsensusThreshold::new(0.6);
+        let start = Instant::now();
+
+        // A three-way fork: the best answer only ever has a third of the votes
+        for second in 0..=30 {
+            adaptive.record_at("getBalance", 1.0 / 3.0, start + Duration::from_secs(second));
+        }
+        assert_eq!(adaptive.threshold("getBalance"), 0.6);
+
+        for second in 31..=61 {
+            adaptive.record_at("getBalance", 1.0 / 3.0, start + Duration::from_secs(second));
+        }
+        let relaxed = adaptive.threshold("getBalance");
+        assert!((relaxed - (1.0 / 3.0 - RELAX_MARGIN)).abs() < 1e-9, "{}", relaxed);
+        // Other methods keep the configured threshold
+        assert_eq!(adaptive.threshold("getSlot"), 0.6);
+
+        let snapshot = adaptive.snapshot();
+        assert_eq!(snapshot["getBalance"]["relaxed"], true);
+        assert_eq!(snapshot["getBalance"]["threshold"], relaxed);
+    }
+
+    #[test]
+    fn test_brief_disagreement_and_recovery_keep_configured_threshold() {
+        let adaptive = AdaptiveConsensusThreshold::new(0.6);
+        let start = Instant::now();
+
+        // Dips that recover within a minute never relax anything
+        for second in 0..120 {
+            let agreement = if (second / 30) % 2 == 0 { 0.2 } else { 1.0 };
+            adaptive.record_at("getBalance", agreement, start + Duration::from_secs(second));
+        }
+        assert_eq!(adaptive.threshold("getBalance"), 0.6);
+
+        let start = start + Duration::from_secs(AGREEMENT_WINDOW.as_secs() * 2);
+        for second in 0..=60 {
+            adaptive.record_at("getBalance", 0.0, start + Duration::from_secs(second));
+        }
+        assert_eq!(adaptive.threshold("getBalance"), 0.0);
+
+        // Once the disagreement ages out of the window the threshold comes back
+        let later = start + AGREEMENT_WINDOW + Duration::from_secs(61);
+        adaptive.record_at("getBalance", 1.0, later);
+        assert_eq!(adaptive.threshold("getBalance"), 0.6);
+    }
+
+    #[tokio::test]
+    async fn test_relaxed_threshold_lets_split_endpoints_reach_consensus() {
+        let service = service();
+        let endpoints = vec![endpoint("a"), endpoint("b"), endpoint("c")];
+        let split = || endpoints.iter()
+            .enumerate()
+            .map(|(i, endpoint)| (endpoint.id, json!({"result": i})))
+            .collect::<Vec<_>>();
+        assert!(service.analyze_consensus("getBalance", split(), &endpoints).is_err());
+
+        // Fast-forward through a minute and a half of the same three-way fork
+        let start = Instant::now();
+        for second in 0..90 {
+            service.adaptive_threshold.record_at("getBalance", 1.0 / 3.0, start + Duration::from_secs(second));
+        }
+
+        let (_, confidence) = service.analyze_consensus("getBalance", split(), &endpoints).unwrap();
+        assert!((confidence - 1.0 / 3.0).abs() < 1e-9);
+        let debug = service.get_debug_info().await;
+        assert_eq!(debug["adaptive_thresholds"]["getBalance"]["relaxed"], true);
+        assert_eq!(debug["consensus_threshold"], 0.6);
+    }
+}
@@ -0,0 +1,144 @@
+use crate::{middleware::body_too_large, AppState};
+use axum::{
+    body::Body,
+    extract::{Request, State},
+    http::StatusCode,
+    middleware::Next,
+    response::{IntoResponse, Response},
+};
+use futures::StreamExt;
+use std::sync::Arc;
+use tracing::debug;
+
+// Marks requests that arrived with a Content-Encoding, since RequestDecompressionLayer
+// removes the header once it has taken over the body
+#[derive(Debug, Clone)]
+struct CompressedRequest(String);
+
+// Runs before RequestDecompressionLayer
+pub async fn tag_compressed_requests(mut request: Request, next: Next) -> Response {
+    let encoding = request.headers()
+        .get(axum::http::header::CONTENT_ENCODING)
+        .and_then(|value| value.to_str().ok())
+        .map(|value| value.trim().to_ascii_lowercase())
+        .filter(|value| !value.is_empty() && value != "identity");
+    if let Some(encoding) = encoding {
+        request.extensions_mut().insert(CompressedRequest(encoding));
+    }
+    next.run(request).await
+}
+
+// Runs after RequestDecompressionLayer, so the body read here is already decompressed
+pub async fn record_decompressed_requests(
+    State(state): State<Arc<AppState>>,
+    request: Request,
+    next: Next,
+) -> Response {
+    let Some(CompressedRequest(encoding)) = request.extensions().get::<CompressedRequest>().cloned() else {
+        return next.run(request).await;
+    };
+
+    // Held to the same max_request_body_bytes as an uncompressed body, so a small compressed
+    // body can't expand into something far larger than an uncompressed request could be
+    let (parts, body) = request.into_parts();
+    let mut chunks = body.into_data_stream();
+    let mut bytes = Vec::new();
+    while let Some(chunk) = chunks.next().await {
+        let chunk = match chunk {
+            Ok(chunk) => chunk,
+            Err(e) => {
+                debug!("Rejected {} request body: {}", encoding, e);
+                return (
+                    StatusCode::BAD_REQUEST,
+                    format!("Request body is not valid {}", encoding),
+                ).into_response();
+            }
+        };
+        if bytes.len() + chunk.len() > state.max_request_body_bytes {
+            debug!("Rejected {} request body larger than {} bytes once decompressed", encoding, state.max_request_body_bytes);
+            return body_too_large();
+        }
+        bytes.extend_from_slice(&chunk);
+    }
+    state.metrics_service.record_decompressed_request(bytes.len());
+
+    next.run(Request::from_parts(parts, Body::from(bytes))).await
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::test_server::{shared_metrics, TestServer, TestServerBuilder};
+    use async_compression::tokio::bufread::{BrotliEncoder, GzipEncoder, ZstdEncoder};
+    use serde_json::{json, Value};
+    use tokio::io::{AsyncRead, AsyncReadExt};
+    use wiremock::{matchers::{body_partial_json, method}, Mock, ResponseTemplate};
+
+    async fn compress(mut encoder: impl AsyncRead + Unpin) -> Vec<u8> {
+        let mut compressed = Vec::new();
+        encoder.read_to_end(&mut compressed).await.unwrap();
+        compressed
+    }
+
+    async fn post(server: &TestServer, encoding: &str, body: Vec<u8>) -> reqwest::Response {
+        server.client.post(&server.base_url)
+            .header(reqwest::header::CONTENT_TYPE, "application/json")
+            .header(reqwest::header::CONTENT_ENCODING, encoding)
+            .body(body)
+            .send()
+            .await
+            .unwrap()
+    }
+
+    fn decompression_metrics(metrics: &Value) -> (u64, u64) {
+        (
+            metrics["requests"]["decompressed"].as_u64().unwrap(),
+            metrics["requests"]["decompressed_bytes"].as_u64().unwrap(),
+        )
+    }
+
+    #[tokio::test]
+    async fn test_compressed_bodies_are_routed() {
+        let server = TestServerBuilder::new().start().await;
+        Mock::given(method("POST"))
+            .and(body_partial_json(json!({"method": "getSlot"})))
+            .respond_with(ResponseTemplate::new(200).set_body_json(json!({"jsonrpc": "2.0", "id": 7, "result": 4242})))
+            .mount(server.endpoint_mock("primary"))
+            .await;
+
+        let payload = serde_json::to_vec(&json!({"jsonrpc": "2.0", "id": 7, "method": "getSlot", "params": []})).unwrap();
+        let encoded = [
+            ("gzip", compress(GzipEncoder::new(payload.as_slice())).await),
+            ("br", compress(BrotliEncoder::new(payload.as_slice())).await),
+            ("zstd", compress(ZstdEncoder::new(payload.as_slice())).await),
+        ];
+
+        for (encoding, body) in encoded {
+            let before = decompression_metrics(&shared_metrics().get_metrics().await);
+            let response = post(&server, encoding, body).await;
+            assert_eq!(response.status(), reqwest::StatusCode::OK, "{} body was rejected", encoding);
+            let body: Value = response.json().await.unwrap();
+            assert_eq!(body["result"], 4242, "{} body was not routed", encoding);
+
+            // Other tests share the metrics, so only check these moved at least as far as expected
+            let (requests, bytes) = decompression_metrics(&shared_metrics().get_metrics().await);
+            assert!(requests > before.0);
+            assert!(bytes >= before.1 + payload.len() as u64);
+        }
+    }
+
+    #[tokio::test]
+    async fn test_bad_compressed_bodies_are_rejected() {
+        let server = TestServerBuilder::new().start().await;
+
+        let response = post(&server, "gzip", b"not gzip at all".to_vec()).await;
+        assert_eq!(response.status(), reqwest::StatusCode::BAD_REQUEST);
+
+        // Compresses to a few kilobytes but expands past the configured body limit
+        let bomb = vec![b' '; server.state.max_request_body_bytes + 1];
+        let response = post(&server, "gzip", compress(GzipEncoder::new(bomb.as_slice())).await).await;
+        assert_eq!(response.status(), reqwest::StatusCode::PAYLOAD_TOO_LARGE);
+
+        let response = post(&server, "compress", b"{}".to_vec()).await;
+        assert_eq!(response.status(), reqwest::StatusCode::UNSUPPORTED_MEDIA_TYPE);
+    }
+}
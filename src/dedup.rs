@@ -0,0 +1,210 @@
+use crate::{error::AppError, metrics::MetricsService};
+use dashmap::{mapref::entry::Entry, DashMap};
+use serde_json::Value;
+use std::{future::Future, sync::Arc, time::Duration};
+use tokio::{sync::broadcast, time::timeout};
+use tracing::debug;
+
+type SharedResult = Result<Value, SharedError>;
+
+// AppError isn't Clone, so waiters get upstream JSON-RPC errors as they were and anything
+// else as a message
+#[derive(Clone)]
+enum SharedError {
+    Rpc { code: i64, message: String, data: Option<Value> },
+    Other(String),
+}
+
+impl From<&AppError> for SharedError {
+    fn from(error: &AppError) -> Self {
+        match error {
+            AppError::RpcError { code, message, data } => SharedError::Rpc {
+                code: *code,
+                message: message.clone(),
+                data: data.clone(),
+            },
+            other => SharedError::Other(other.to_string()),
+        }
+    }
+}
+
+impl From<SharedError> for AppError {
+    fn from(error: SharedError) -> Self {
+        match error {
+            SharedError::Rpc { code, message, data } => AppError::RpcError { code, message, data },
+            SharedError::Other(message) => AppError::endpoint(&message),
+        }
+    }
+}
+
+// Collapses identical upstream requests that are in flight at the same time into one call
+pub struct DeduplicationService {
+    in_flight: DashMap<String, Arc<broadcast::Sender<SharedResult>>>,
+    // How long a waiter follows a leader before giving up on it
+    wait_timeout: Duration,
+    metrics_service: Option<Arc<MetricsService>>,
+}
+
+// Clears the in-flight entry if the leader is dropped before it finishes, which
+// also closes the channel so waiters stop immediately
+struct InFlightGuard<'a> {
+    in_flight: &'a DashMap<String, Arc<broadcast::Sender<SharedResult>>>,
+    key: &'a str,
+}
+
+impl Drop for InFlightGuard<'_> {
+    fn drop(&mut self) {
+        self.in_flight.remove(self.key);
+    }
+}
+
+impl DeduplicationService {
+    pub fn new(wait_timeout: Duration) -> Self {
+        Self {
+            in_flight: DashMap::new(),
+            wait_timeout,
+            metrics_service: None,
+        }
+    }
+
+    pub fn with_metrics_service(mut self, metrics_service: Arc<MetricsService>) -> Self {
+        self.metrics_service = Some(metrics_service);
+        self
+    }
+
+    pub fn in_flight(&self) -> usize {
+        self.in_flight.len()
+    }
+
+    // Runs `request` unless one with the same key is already running, in which case this
+    // waits for and returns that request's result instead
+    pub async fn execute<F, Fut>(&self, key: String, request: F) -> Result<Value, AppError>
+    where
+        F: FnOnce() -> Fut,
+        Fut: Future<Output = Result<Value, AppError>>,
+    {
+        // Subscribing under the entry lock means the leader can't publish in between
+        let waiting = match self.in_flight.entry(key.clone()) {
+            Entry::Occupied(entry) => Some(entry.get().subscribe()),
+            Entry::Vacant(entry) => {
+                entry.insert(Arc::new(broadcast::channel(1).0));
+                None
+            }
+        };
+
+        if let Some(mut receiver) = waiting {
+            debug!("Joining in-flight request {}", key);
+            if let Some(metrics_service) = &self.metrics_service {
+                metrics_service.record_deduplicated_request();
+            }
+            return match timeout(self.wait_timeout, receiver.recv()).await {
+                Ok(Ok(result)) => result.map_err(AppError::from),
+                Ok(Err(_)) => Err(AppError::endpoint("In-flight request was abandoned")),
+                Err(_) => Err(AppError::RequestTimeout),
+            };
+        }
+
+        let guard = InFlightGuard { in_flight: &self.in_flight, key: &key };
+        let result = request().await;
+        // Removed before publishing so later callers start a fresh request rather than
+        // subscribing to a channel that has already sent
+        if let Some((_, sender)) = self.in_flight.remove(&key) {
+            let shared = match &result {
+                Ok(value) => Ok(value.clone()),
+                Err(e) => Err(SharedError::from(e)),
+            };
+            let _ = sender.send(shared);
+        }
+        drop(guard);
+        result
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_server::TestServerBuilder;
+    use serde_json::json;
+    use std::sync::atomic::{AtomicUsize, Ordering};
+    use wiremock::{matchers::method, Mock, Request, Respond};
+
+    #[tokio::test]
+    async fn test_concurrent_identical_requests_share_one_call() {
+        let dedup = DeduplicationService::new(Duration::from_secs(5));
+        let calls = AtomicUsize::new(0);
+
+        let results = futures::future::join_all((0..20).map(|_| dedup.execute("getSlot".to_string(), || async {
+            calls.fetch_add(1, Ordering::SeqCst);
+            tokio::time::sleep(Duration::from_millis(50)).await;
+            Ok(json!(42))
+        }))).await;
+
+        assert_eq!(calls.load(Ordering::SeqCst), 1);
+        assert!(results.iter().all(|result| matches!(result, Ok(value) if value == &json!(42))));
+        assert_eq!(dedup.in_flight(), 0);
+
+        // Once the first call has finished, the next one goes upstream again
+        dedup.execute("getSlot".to_string(), || async {
+            calls.fetch_add(1, Ordering::SeqCst);
+            Ok(json!(43))
+        }).await.unwrap();
+        assert_eq!(calls.load(Ordering::SeqCst), 2);
+    }
+
+    #[tokio::test]
+    async fn test_waiters_give_up_on_stuck_request() {
+        let dedup = Arc::new(DeduplicationService::new(Duration::from_millis(50)));
+        let leader = tokio::spawn({
+            let dedup = dedup.clone();
+            async move {
+                dedup.execute("stuck".to_string(), || async {
+                    tokio::time::sleep(Duration::from_secs(60)).await;
+                    Ok(json!(null))
+                }).await
+            }
+        });
+        tokio::time::sleep(Duration::from_millis(10)).await;
+
+        let waiter = dedup.execute("stuck".to_string(), || async { Ok(json!("unused")) }).await;
+        assert!(matches!(waiter, Err(AppError::RequestTimeout)));
+
+        // A cancelled leader doesn't leave the key blocked
+        leader.abort();
+        let _ = leader.await;
+        assert_eq!(dedup.in_flight(), 0);
+        assert_eq!(dedup.execute("stuck".to_string(), || async { Ok(json!(1)) }).await.unwrap(), json!(1));
+    }
+
+    #[tokio::test]
+    async fn test_errors_reach_every_waiter() {
+        let dedup = DeduplicationService::new(Duration::from_secs(5));
+        let results = futures::future::join_all((0..5).map(|_| dedup.execute("getSlot".to_string(), || async {
+            tokio::time::sleep(Duration::from_millis(20)).await;
+            Err(AppError::endpoint("HTTP 502"))
+        }))).await;
+        assert!(results.iter().all(|result| matches!(result, Err(e) if e.to_string().contains("HTTP 502"))));
+    }
+
+    #[tokio::test]
+    async fn test_identical_client_requests_make_one_upstream_call() {
+        const CLIENTS: usize = 50;
+        let server = TestServerBuilder::new().start().await;
+        let reply = crate::test_server::rpc_result(json!(310_000_000));
+        Mock::given(method("POST"))
+            .respond_with(move |request: &Request| reply.respond(request).set_delay(Duration::from_millis(200)))
+            .mount(server.endpoint_mock("primary"))
+            .await;
+
+        let responses = futures::future::join_all((0..CLIENTS).map(|id| {
+            server.rpc(json!({"jsonrpc": "2.0", "id": id, "method": "getSlot", "params": [{"commitment": "confirmed"}]}))
+        })).await;
+
+        assert_eq!(server.endpoint_mock("primary").received_requests().await.unwrap().len(), 1);
+        for (id, response) in responses.into_iter().enumerate() {
+            let body: Value = response.json().await.unwrap();
+            assert_eq!(body["result"], 310_000_000);
+            // Each client still gets its own request id back
+            assert_eq!(body["id"], id);
+        }
+    }
+}
@@ -0,0 +1,509 @@
+use crate::{
+    config::{DiscoveryConfig, DiscoveryProviderConfig, EndpointConfig},
+    endpoints::{DiscoveryProvider, EndpointManager},
+    error::AppError,
+    service_discovery::endpoint_url,
+};
+use async_trait::async_trait;
+use serde_json::{json, Value};
+use std::{
+    cmp::Reverse,
+    net::{IpAddr, SocketAddr},
+    time::Duration,
+};
+use tokio::{net::UdpSocket, time::timeout};
+use tracing::debug;
+
+const CLUSTER_TIMEOUT: Duration = Duration::from_secs(10);
+const DNS_TIMEOUT: Duration = Duration::from_secs(5);
+const DNS_PORT: u16 = 53;
+const RESOLV_CONF: &str = "/etc/resolv.conf";
+const SRV_RECORD_TYPE: u16 = 33;
+const CLASS_IN: u16 = 1;
+// Bounds how far a malicious response can make us chase compression pointers
+const MAX_NAME_POINTERS: usize = 16;
+
+pub fn providers_from_config(config: &DiscoveryConfig) -> Result<Vec<Box<dyn DiscoveryProvider + Send + Sync>>, AppError> {
+    // Configs from before providers existed only list cluster URLs
+    if config.providers.is_empty() {
+        return config.cluster_rpc_urls.iter()
+            .map(|url| SolanaClusterDiscovery::new(url.clone()).map(|p| Box::new(p) as Box<dyn DiscoveryProvider + Send + Sync>))
+            .collect();
+    }
+
+    config.providers.iter()
+        .map(|provider| -> Result<Box<dyn DiscoveryProvider + Send + Sync>, AppError> {
+            Ok(match provider {
+                DiscoveryProviderConfig::SolanaCluster { rpc_url } => Box::new(SolanaClusterDiscovery::new(rpc_url.clone())?),
+                DiscoveryProviderConfig::Dns { srv_name, scheme, resolver } => {
+                    Box::new(DnsDiscovery::new(srv_name.clone(), scheme.clone(), resolver.as_deref())?)
+                }
+                DiscoveryProviderConfig::Static { urls } => Box::new(StaticDiscovery::new(urls.clone())),
+            })
+        })
+        .collect()
+}
+
+fn candidate(url: String) -> EndpointConfig {
+    let name = format!("Auto-discovered-{}", url.split("://").nth(1).unwrap_or("unknown"));
+    EndpointManager::discovered_endpoint_config(&url, &name, Vec::new())
+}
+
+// Asks a cluster node for its peers with getClusterNodes and offers the ones advertising an HTTP RPC address
+pub struct SolanaClusterDiscovery {
+    rpc_url: String,
+    client: reqwest::Client,
+}
+
+impl SolanaClusterDiscovery {
+    pub fn new(rpc_url: String) -> Result<Self, AppError> {
+        let client = reqwest::Client::builder()
+            .timeout(CLUSTER_TIMEOUT)
+            .build()
+            .map_err(|e| AppError::config(&format!("Failed to create cluster discovery client: {}", e)))?;
+        Ok(Self { rpc_url, client })
+    }
+}
+
+#[async_trait]
+impl DiscoveryProvider for SolanaClusterDiscovery {
+    fn name(&self) -> String {
+        format!("cluster {}", self.rpc_url)
+    }
+
+    async fn discover(&self) -> Result<Vec<EndpointConfig>, AppError> {
+        let request = json!({
+            "jsonrpc": "2.0",
+            "id": 1,
+            "method": "getClusterNodes"
+        });
+        let response: Value = self.client.post(&self.rpc_url)
+            .json(&request)
+            .send()
+            .await
+            .map_err(|e| AppError::DiscoveryError(format!("getClusterNodes request failed: {}", e)))?
+            .json()
+            .await
+            .map_err(|e| AppError::DiscoveryError(format!("Invalid getClusterNodes response: {}", e)))?;
+
+        let nodes = response.get("result").and_then(Value::as_array)
+            .ok_or_else(|| AppError::DiscoveryError("Invalid cluster response".to_string()))?;
+        Ok(nodes.iter()
+            .filter_map(|node| node.get("rpc").and_then(Value::as_str))
+            .filter(|rpc_url| rpc_url.starts_with("http"))
+            .map(|rpc_url| candidate(rpc_url.to_string()))
+            .collect())
+    }
+}
+
+#[derive(Debug, Clone, PartialEq)]
+struct SrvRecord {
+    priority: u16,
+    weight: u16,
+    port: u16,
+    target: String,
+}
+
+// Looks up an SRV record and offers every target it lists, most preferred first
+pub struct DnsDiscovery {
+    srv_name: String,
+    scheme: String,
+    resolver: SocketAddr,
+}
+
+impl DnsDiscovery {
+    pub fn new(srv_name: String, scheme: String, resolver: Option<&str>) -> Result<Self, AppError> {
+        let resolver = match resolver {
+            Some(address) => parse_resolver(address)
+                .ok_or_else(|| AppError::config(&format!("Invalid DNS resolver address {}", address)))?,
+            None => system_resolver()?,
+        };
+        // Fail on a bad name at startup rather than on every lookup
+        srv_query(0, &srv_name)?;
+        Ok(Self { srv_name, scheme, resolver })
+    }
+
+    async fn lookup_srv(&self) -> Result<Vec<SrvRecord>, AppError> {
+        let dns_error = |e: std::io::Error| AppError::DiscoveryError(format!("DNS query to {} failed: {}", self.resolver, e));
+        let local: SocketAddr = if self.resolver.is_ipv4() { ([0, 0, 0, 0], 0).into() } else { ([0u16; 8], 0).into() };
+        let socket = UdpSocket::bind(local).await.map_err(dns_error)?;
+        socket.connect(self.resolver).await.map_err(dns_error)?;
+
+        let id = rand::random::<u16>();
+        socket.send(&srv_query(id, &self.srv_name)?).await.map_err(dns_error)?;
+        let mut buffer = [0u8; 4096];
+        let len = timeout(DNS_TIMEOUT, socket.recv(&mut buffer)).await?.map_err(dns_error)?;
+        parse_srv_response(&buffer[..len], id)
+    }
+}
+
+#[async_trait]
+impl DiscoveryProvider for DnsDiscovery {
+    fn name(&self) -> String {
+        format!("DNS SRV {}", self.srv_name)
+    }
+
+    async fn discover(&self) -> Result<Vec<EndpointConfig>, AppError> {
+        let mut records = self.lookup_srv().await?;
+        records.sort_by_key(|record| (record.priority, Reverse(record.weight)));
+        debug!("{} resolved to {} targets", self.srv_name, records.len());
+        Ok(records.into_iter()
+            // A target of "." means the service is deliberately unavailable
+            .filter(|record| !record.target.is_empty())
+            .map(|record| candidate(endpoint_url(&self.scheme, &record.target, record.port)))
+            .collect())
+    }
+}
+
+// Offers a fixed list of URLs, for tests and for trying out endpoints before configuring them
+pub struct StaticDiscovery {
+    urls: Vec<String>,
+}
+
+impl StaticDiscovery {
+    pub fn new(urls: Vec<String>) -> Self {
+        Self { urls }
+    }
+}
+
+#[async_trait]
+impl DiscoveryProvider for StaticDiscovery {
+    fn name(&self) -> String {
+        "static list".to_string()
+    }
+
+    async fn discover(&self) -> Result<Vec<EndpointConfig>, AppError> {
+        Ok(self.urls.iter().cloned().map(candidate).collect())
+    }
+}
+
+// Accepts "1.1.1.1:53" as well as a bare "1.1.1.1"
+fn parse_resolver(address: &str) -> Option<SocketAddr> {
+    address.parse().ok()
+        .or_else(|| address.parse::<IpAddr>().ok().map(|ip| SocketAddr::new(ip, DNS_PORT)))
+}
+
+fn system_resolver() -> Result<SocketAddr, AppError> {
+    let resolv_conf = std::fs::read_to_string(RESOLV_CONF)
+        .map_err(|e| AppError::config(&format!("No DNS resolver configured and {} is unreadable: {}", RESOLV_CONF, e)))?;
+    resolv_conf.lines()
+        .filter_map(|line| line.trim().strip_prefix("nameserver"))
+        .find_map(|address| address.trim().parse::<IpAddr>().ok())
+        .map(|ip| SocketAddr::new(ip, DNS_PORT))
+        .ok_or_else(|| AppError::config(&format!("No nameserver found in {}", RESOLV_CONF)))
+}
+
+fn srv_query(id: u16, name: &str) -> Result<Vec<u8>, AppError> {
+    let mut packet = Vec::with_capacity(512);
+    packet.extend_from_slice(&id.to_be_bytes());
+    packet.extend_from_slice(&0x0100u16.to_be_bytes()); // recursion desired
+    packet.extend_from_slice(&1u16.to_be_bytes()); // one question
+    packet.extend_from_slice(&[0; 6]); // no answer, authority or additional records
+    for label in name.trim_end_matches('.').split('.') {
+        if label.is_empty() || label.len() > 63 {
+            return Err(AppError::config(&format!("Invalid SRV record name {}", name)));
+        }
+        packet.push(label.len() as u8);
+        packet.extend_from_slice(label.as_bytes());
+    }
+    packet.push(0);
+    packet.extend_from_slice(&SRV_RECORD_TYPE.to_be_bytes());
+    packet.extend_from_slice(&CLASS_IN.to_be_bytes());
+    Ok(packet)
+}
+
+fn parse_srv_response(packet: &[u8], id: u16) -> Result<Vec<SrvRecord>, AppError> {
+    let mut reader = DnsReader { packet, position: 0 };
+    if reader.u16()? != id {
+        return Err(AppError::DiscoveryError("DNS response id does not match the query".to_string()));
+    }
+    let flags = reader.u16()?;
+    if flags & 0x8000 == 0 {
+        return Err(AppError::DiscoveryError("DNS reply is not a response".to_string()));
+    }
+    match flags & 0x000f {
+        0 => {}
+        3 => return Err(AppError::DiscoveryError("SRV record does not exist".to_string())),
+        rcode => return Err(AppError::DiscoveryError(format!("DNS query failed with rcode {}", rcode))),
+    }
+    let questions = reader.u16()?;
+    let answers = reader.u16()?;
+    reader.skip(4)?; // authority and additional counts
+
+    for _ in 0..questions {
+        reader.name()?;
+        reader.skip(4)?; // type and class
+    }
+
+    let mut records = Vec::new();
+    for _ in 0..answers {
+        reader.name()?;
+        let record_type = reader.u16()?;
+        reader.skip(6)?; // class and TTL
+        let rdata_len = reader.u16()? as usize;
+        let rdata_end = reader.position + rdata_len;
+        if record_type == SRV_RECORD_TYPE {
+            records.push(SrvRecord {
+                priority: reader.u16()?,
+                weight: reader.u16()?,
+                port: reader.u16()?,
+                target: reader.name()?,
+            });
+        }
+        // Also steps over CNAMEs and anything else the resolver included
+        reader.position = rdata_end;
+    }
+    Ok(records)
+}
+
+struct DnsReader<'a> {
+    packet: &'a [u8],
+    position: usize,
+}
+
+impl DnsReader<'_> {
+    fn bytes(&mut self, len: usize) -> Result<&[u8], AppError> {
+        let bytes = self.packet.get(self.position..self.position + len).ok_or_else(truncated)?;
+        self.position += len;
+        Ok(bytes)
+    }
+
+    fn skip(&mut self, len: usize) -> Result<(), AppError> {
+        self.bytes(len).map(|_| ())
+    }
+
+    fn u16(&mut self) -> Result<u16, AppError> {
+        let bytes = self.bytes(2)?;
+        Ok(u16::from_be_bytes([bytes[0], bytes[1]]))
+    }
+
+    // Reads a possibly compressed name, leaving the reader just past it
+    fn name(&mut self) -> Result<String, AppError> {
+        let mut labels = Vec::new();
+        let mut position = self.position;
+        let mut pointers = 0;
+        loop {
+            let len = *self.packet.get(position).ok_or_else(truncated)? as usize;
+            if len & 0xc0 == 0xc0 {
+                let low = *self.packet.get(position + 1).ok_or_else(truncated)? as usize;
+                if pointers == 0 {
+                    self.position = position + 2;
+                }
+                pointers += 1;
+                if pointers > MAX_NAME_POINTERS {
+                    return Err(AppError::DiscoveryError("DNS name compression loop".to_string()));
+                }
+                position = ((len & 0x3f) << 8) | low;
+            } else if len == 0 {
+                if pointers == 0 {
+                    self.position = position + 1;
+                }
+                return Ok(labels.join("."));
+            } else {
+                let label = self.packet.get(position + 1..position + 1 + len).ok_or_else(truncated)?;
+                labels.push(String::from_utf8_lossy(label).into_owned());
+                position += 1 + len;
+            }
+        }
+    }
+}
+
+fn truncated() -> AppError {
+    AppError::DiscoveryError("Truncated DNS response".to_string())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use wiremock::{
+        matchers::{body_partial_json, method},
+        Mock, MockServer, ResponseTemplate,
+    };
+
+    fn urls(endpoints: &[EndpointConfig]) -> Vec<&str> {
+        endpoints.iter().map(|e| e.url.as_str()).collect()
+    }
+
+    // Answers the question in `query` with `records`, naming the owner by a pointer to the question
+    fn srv_response(query: &[u8], records: &[(u16, u16, u16, &str)]) -> Vec<u8> {
+        let mut packet = query[..2].to_vec();
+        packet.extend_from_slice(&0x8180u16.to_be_bytes());
+        packet.extend_from_slice(&1u16.to_be_bytes());
+        packet.extend_from_slice(&(records.len() as u16).to_be_bytes());
+        packet.extend_from_slice(&[0; 4]);
+        packet.extend_from_slice(&query[12..]);
+        for (priority, weight, port, target) in records {
+            let mut rdata = Vec::new();
+            for value in [priority, weight, port] {
+                rdata.extend_from_slice(&value.to_be_bytes());
+            }
+            for label in target.split('.').filter(|label| !label.is_empty()) {
+                rdata.push(label.len() as u8);
+                rdata.extend_from_slice(label.as_bytes());
+            }
+            rdata.push(0);
+
+            packet.extend_from_slice(&[0xc0, 12]);
+            packet.extend_from_slice(&SRV_RECORD_TYPE.to_be_bytes());
+            packet.extend_from_slice(&CLASS_IN.to_be_bytes());
+            packet.extend_from_slice(&300u32.to_be_bytes());
+            packet.extend_from_slice(&(rdata.len() as u16).to_be_bytes());
+            packet.extend_from_slice(&rdata);
+        }
+        packet
+    }
+
+    // A one-shot nameserver that answers the first query it gets
+    async fn fake_resolver(records: Vec<(u16, u16, u16, &'static str)>) -> SocketAddr {
+        let socket = UdpSocket::bind("127.0.0.1:0").await.unwrap();
+        let address = socket.local_addr().unwrap();
+        tokio::spawn(async move {
+            let mut buffer = [0u8; 512];
+            let (len, client) = socket.recv_from(&mut buffer).await.unwrap();
+            socket.send_to(&srv_response(&buffer[..len], &records), client).await.unwrap();
+        });
+        address
+    }
+
+    #[tokio::test]
+    async fn test_static_discovery_offers_configured_urls() {
+        let provider = StaticDiscovery::new(vec!["http://10.0.0.5:8899".to_string(), "https://rpc.example.com".to_string()]);
+
+        let endpoints = provider.discover().await.unwrap();
+        assert_eq!(urls(&endpoints), vec!["http://10.0.0.5:8899", "https://rpc.example.com"]);
+        assert_eq!(endpoints[0].name, "Auto-discovered-10.0.0.5:8899");
+        assert_eq!((endpoints[0].weight, endpoints[0].priority), (50, 10));
+    }
+
+    #[tokio::test]
+    async fn test_cluster_discovery_offers_http_rpc_addresses() {
+        let cluster = MockServer::start().await;
+        Mock::given(method("POST"))
+            .and(body_partial_json(json!({"method": "getClusterNodes"})))
+            .respond_with(ResponseTemplate::new(200).set_body_json(json!({
+                "jsonrpc": "2.0",
+                "id": 1,
+                "result": [
+                    {"pubkey": "node-1", "rpc": "http://10.0.0.1:8899"},
+                    {"pubkey": "node-2", "rpc": null},
+                    {"pubkey": "node-3", "rpc": "10.0.0.3:8899"},
+                    {"pubkey": "node-4", "rpc": "https://rpc.example.com"}
+                ]
+            })))
+            .mount(&cluster)
+            .await;
+
+        let provider = SolanaClusterDiscovery::new(cluster.uri()).unwrap();
+        let endpoints = provider.discover().await.unwrap();
+        assert_eq!(urls(&endpoints), vec!["http://10.0.0.1:8899", "https://rpc.example.com"]);
+    }
+
+    #[tokio::test]
+    async fn test_cluster_discovery_rejects_invalid_response() {
+        let cluster = MockServer::start().await;
+        Mock::given(method("POST"))
+            .respond_with(ResponseTemplate::new(200).set_body_json(json!({"jsonrpc": "2.0", "id": 1, "result": "nope"})))
+            .mount(&cluster)
+            .await;
+
+        let provider = SolanaClusterDiscovery::new(cluster.uri()).unwrap();
+        assert!(matches!(provider.discover().await, Err(AppError::DiscoveryError(_))));
+    }
+
+    #[tokio::test]
+    async fn test_dns_discovery_orders_srv_targets() {
+        let resolver = fake_resolver(vec![
+            (20, 100, 8899, "backup.rpc.example.com"),
+            (10, 10, 8899, "light.rpc.example.com."),
+            (10, 90, 8900, "heavy.rpc.example.com"),
+            (10, 0, 0, ""),
+        ]).await;
+        let provider = DnsDiscovery::new(
+            "_rpc._tcp.example.com".to_string(),
+            "https".to_string(),
+            Some(&resolver.to_string()),
+        ).unwrap();
+
+        let endpoints = provider.discover().await.unwrap();
+        assert_eq!(urls(&endpoints), vec![
+            "https://heavy.rpc.example.com:8900",
+            "https://light.rpc.example.com:8899",
+            "https://backup.rpc.example.com:8899",
+        ]);
+    }
+
+    #[test]
+    fn test_srv_response_errors() {
+        let query = srv_query(7, "_rpc._tcp.example.com").unwrap();
+        let response = srv_response(&query, &[(10, 10, 8899, "rpc.example.com")]);
+        assert_eq!(parse_srv_response(&response, 7).unwrap().len(), 1);
+
+        assert!(parse_srv_response(&response, 8).is_err(), "mismatched id accepted");
+        assert!(parse_srv_response(&response[..response.len() - 3], 7).is_err(), "truncated response accepted");
+        let mut nxdomain = response.clone();
+        nxdomain[3] |= 3;
+        assert!(parse_srv_response(&nxdomain, 7).is_err(), "NXDOMAIN accepted");
+
+        // A name pointing at itself
+        let mut looping = query[..12].to_vec();
+        looping[5] = 0;
+        looping[7] = 1;
+        looping[2] |= 0x80;
+        looping.extend_from_slice(&[0xc0, 12]);
+        assert!(parse_srv_response(&looping, 7).is_err(), "compression loop accepted");
+    }
+
+    #[test]
+    fn test_providers_from_config() {
+        let mut config = crate::config::Config::default().discovery;
+        config.cluster_rpc_urls = vec!["https://a.example.com".to_string(), "https://b.example.com".to_string()];
+        let names: Vec<String> = providers_from_config(&config).unwrap().iter().map(|p| p.name()).collect();
+        assert_eq!(names, vec!["cluster https://a.example.com", "cluster https://b.example.com"]);
+
+        config.providers = vec![
+            DiscoveryProviderConfig::Static { urls: vec!["http://10.0.0.5:8899".to_string()] },
+            DiscoveryProviderConfig::Dns {
+                srv_name: "_rpc._tcp.example.com".to_string(),
+                scheme: "http".to_string(),
+                resolver: Some("127.0.0.1".to_string()),
+            },
+        ];
+        let names: Vec<String> = providers_from_config(&config).unwrap().iter().map(|p| p.name()).collect();
+        assert_eq!(names, vec!["static list", "DNS SRV _rpc._tcp.example.com"]);
+
+        config.providers = vec![DiscoveryProviderConfig::Dns {
+            srv_name: "_rpc._tcp.example.com".to_string(),
+            scheme: "http".to_string(),
+            resolver: Some("not an address".to_string()),
+        }];
+        assert!(providers_from_config(&config).is_err());
+    }
+
+    #[test]
+    fn test_provider_config_deserialization() {
+        let config: DiscoveryConfig = toml::from_str(r#"
+            enabled = true
+            discovery_interval = 60
+            test_methods = ["getHealth"]
+            min_score_threshold = 0.5
+            auto_add_endpoints = true
+            cluster_rpc_urls = []
+
+            [[providers]]
+            type = "dns"
+            srv_name = "_rpc._tcp.example.com"
+
+            [[providers]]
+            type = "solana_cluster"
+            rpc_url = "https://api.mainnet-beta.solana.com"
+        "#).unwrap();
+        assert_eq!(config.providers, vec![
+            DiscoveryProviderConfig::Dns {
+                srv_name: "_rpc._tcp.example.com".to_string(),
+                scheme: "http".to_string(),
+                resolver: None,
+            },
+            DiscoveryProviderConfig::SolanaCluster { rpc_url: "https://api.mainnet-beta.solana.com".to_string() },
+        ]);
+    }
+}
@@ -1,16 +1,38 @@
 use crate::{
-    config::{Config, EndpointConfig},
+    anomaly::LatencyAnomalyDetector,
+    circuit_breaker::CircuitBreakerRegistry,
+    config::{default_health_status_codes, Config, EndpointConfig, EndpointPromotionConfig},
+    config_diff::{diff_configs, merge_patch, ConfigChange},
     error::AppError,
-    types::{EndpointInfo, EndpointScore, EndpointStats, EndpointStatus, LoadBalancingStrategy},
+    events::SystemEvent,
+    health::{combined_status, failure_rate, HealthGradient},
+    metrics::{CustomMetricType, MetricsService},
+    retry::{AdaptiveRetry, AdaptiveRetryStats, RetryConfig},
+    service_discovery::ServiceDiscovery,
+    signing::RequestSigner,
+    transport::{GrpcTransport, HttpTransport, RpcTransport},
+    types::{
+        CircuitBreakerState, EndpointInfo, EndpointKind, EndpointLatencyAnomaly, EndpointScore, EndpointStats,
+        EndpointStatus, LatencyHistogram, LoadBalancingStrategy,
+    },
 };
-use chrono::Utc;
+use async_trait::async_trait;
+use axum::http::{HeaderMap, HeaderName};
+use chrono::{DateTime, Utc};
+use dashmap::DashMap;
+use rand::Rng;
+use serde::Deserialize;
 use serde_json::{json, Value};
 use std::{
-    collections::HashMap,
-    sync::Arc,
+    collections::{HashMap, VecDeque},
+    sync::{
+        atomic::{AtomicU32, Ordering},
+        Arc,
+    },
     time::{Duration, Instant},
 };
-use tokio::{sync::RwLock, time::interval};
+use tokio::{sync::RwLock, task::JoinSet, time::{interval, timeout}};
+use tokio_util::sync::CancellationToken;
 use tracing::{debug, error, info, warn};
 use uuid::Uuid;
 
@@ -19,9 +41,142 @@ pub struct EndpointManager {
     config: Arc<RwLock<Config>>,
     endpoints: Arc<RwLock<HashMap<Uuid, Endpoint>>>,
     strategy: LoadBalancingStrategy,
+    method_strategies: HashMap<String, LoadBalancingStrategy>,
+    method_tags: HashMap<String, String>,
     next_round_robin: Arc<RwLock<usize>>,
-    circuit_breakers: Arc<RwLock<HashMap<Uuid, CircuitBreaker>>>,
+    circuit_breakers: Arc<CircuitBreakerRegistry>,
     discovery_cache: Arc<RwLock<HashMap<String, DiscoveredEndpoint>>>,
+    anomaly_detector: LatencyAnomalyDetector,
+    health_gradient: HealthGradient,
+    // Latest slot reported by each endpoint, refreshed by the slot tracking task
+    slot_tracker: Arc<DashMap<Uuid, u64>>,
+    promotion: EndpointPromotionConfig,
+    // Size of each endpoint's recent-latency window
+    latency_window_size: usize,
+    metrics_service: Option<Arc<MetricsService>>,
+    // URL -> id of every endpoint added by service discovery, including ones still draining
+    service_endpoints: Arc<RwLock<HashMap<String, Uuid>>>,
+    adaptive_weights: Arc<AdaptiveLoadBalancer>,
+}
+
+const SLOT_TRACKING_INTERVAL: Duration = Duration::from_secs(5);
+const ADAPTIVE_WEIGHT_INTERVAL: Duration = Duration::from_secs(5);
+// Each endpoint's adaptive retry config is reported and then adjusted this often
+const RETRY_STATS_INTERVAL: Duration = Duration::from_secs(60);
+// Share of the gap to the new target closed each round, so weights drift rather than jump
+const ADAPTIVE_WEIGHT_SMOOTHING: f64 = 0.5;
+
+// What the adaptive load balancer sees of one endpoint each round
+#[derive(Debug, Clone)]
+pub struct AdaptiveWeightSample {
+    pub id: Uuid,
+    pub name: String,
+    pub configured_weight: u32,
+    pub total_requests: u64,
+    pub successful_requests: u64,
+    pub p95_ms: f64,
+}
+
+// Weights used by weighted selection, recomputed from each endpoint's requests since the
+// previous round: configured_weight * success_rate^2 * (1 / normalized_latency), where
+// normalized_latency is the endpoint's p95 over the fastest endpoint's p95
+#[derive(Debug, Default)]
+pub struct AdaptiveLoadBalancer {
+    weights: DashMap<Uuid, f64>,
+    // (total, successful) request counters as of the previous round
+    last_counts: DashMap<Uuid, (u64, u64)>,
+}
+
+impl AdaptiveLoadBalancer {
+    // Endpoints that haven't been through a round yet use their configured weight
+    pub fn weight(&self, endpoint_id: Uuid, configured_weight: u32) -> f64 {
+        self.weights.get(&endpoint_id)
+            .map(|weight| *weight)
+            .unwrap_or(configured_weight as f64)
+    }
+
+    pub fn recompute(&self, samples: &[AdaptiveWeightSample]) {
+        let fastest_p95 = samples.iter()
+            .map(|sample| sample.p95_ms)
+            .filter(|p95| *p95 > 0.0)
+            .fold(f64::INFINITY, f64::min);
+
+        for sample in samples {
+            let (last_total, last_successful) = self.last_counts
+                .insert(sample.id, (sample.total_requests, sample.successful_requests))
+                .unwrap_or_default();
+            let requests = sample.total_requests.saturating_sub(last_total);
+            // No traffic since the last round is no evidence of trouble, so the weight recovers
+            let success_rate = match requests {
+                0 => 1.0,
+                _ => sample.successful_requests.saturating_sub(last_successful) as f64 / requests as f64,
+            };
+            let normalized_latency = match sample.p95_ms > 0.0 && fastest_p95.is_finite() {
+                true => sample.p95_ms / fastest_p95,
+                false => 1.0,
+            };
+
+            let configured = sample.configured_weight as f64;
+            let target = configured * success_rate.powi(2) / normalized_latency;
+            let previous = self.weight(sample.id, sample.configured_weight);
+            let weight = previous + (target - previous) * ADAPTIVE_WEIGHT_SMOOTHING;
+            if (weight - previous).abs() >= 0.01 {
+                debug!("Adaptive weight for {} moved from {:.2} to {:.2} (configured {}, success rate {:.2}, normalized p95 {:.2})",
+                    sample.name, previous, weight, sample.configured_weight, success_rate, normalized_latency);
+            }
+            self.weights.insert(sample.id, weight);
+        }
+
+        self.weights.retain(|id, _| samples.iter().any(|sample| sample.id == *id));
+        self.last_counts.retain(|id, _| samples.iter().any(|sample| sample.id == *id));
+    }
+}
+
+// The endpoints carrying one tag, as of when the pool was taken
+#[derive(Debug, Clone)]
+pub struct EndpointPool {
+    tag: String,
+    endpoint_ids: Vec<Uuid>,
+}
+
+impl EndpointPool {
+    pub fn tag(&self) -> &str {
+        &self.tag
+    }
+
+    pub fn endpoint_ids(&self) -> &[Uuid] {
+        &self.endpoint_ids
+    }
+
+    pub fn contains(&self, endpoint_id: Uuid) -> bool {
+        self.endpoint_ids.contains(&endpoint_id)
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.endpoint_ids.is_empty()
+    }
+}
+
+// Runtime changes to a live endpoint; fields left out are kept as they are
+#[derive(Debug, Clone, Default, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct EndpointUpdate {
+    pub weight: Option<u32>,
+    pub priority: Option<u8>,
+    // An empty string removes the endpoint's token
+    pub auth_token: Option<String>,
+}
+
+// The object form of `endpoints` in a config patch, applied to the live endpoint set
+#[derive(Debug, Default, Deserialize)]
+#[serde(deny_unknown_fields)]
+struct EndpointOperations {
+    #[serde(default)]
+    add: Vec<EndpointConfig>,
+    #[serde(default)]
+    remove: Vec<Uuid>,
+    #[serde(default)]
+    update: HashMap<Uuid, EndpointUpdate>,
 }
 
 #[derive(Debug, Clone)]
@@ -29,44 +184,85 @@ struct Endpoint {
     info: EndpointInfo,
     stats: EndpointStats,
     client: reqwest::Client,
+    transport: Arc<dyn RpcTransport>,
     config: EndpointConfig,
     connection_pool: ConnectionPool,
+    // Added by auto-discovery and not yet promoted to a managed endpoint
+    discovered: bool,
+    // Gone from the service registry; finishes in-flight requests but gets no new ones
+    draining: bool,
+    // Outcome of the most recent requests, newest last, for the health gradient
+    failure_rate_window: VecDeque<bool>,
+    // Last status from health checks, admins or peers, and the status the gradient derives
+    // from request results. `info.status` is the worse of the two, so neither undoes the other.
+    checked_status: EndpointStatus,
+    gradient_status: Option<EndpointStatus>,
+    adaptive_retry: AdaptiveRetry,
+}
+
+// What a selection may pick from: endpoints that permit the method (any, without one)
+// and aren't excluded
+#[derive(Debug, Clone, Copy, Default)]
+struct Candidates<'a> {
+    method: Option<&'a str>,
+    excluded: &'a [Uuid],
+}
+
+impl<'a> Candidates<'a> {
+    fn for_method(method: &'a str) -> Self {
+        Self { method: Some(method), excluded: &[] }
+    }
 }
 
 #[derive(Debug, Clone)]
 struct ConnectionPool {
-    active_connections: u32,
+    // Requests in flight; shared with the ConnectionGuards so they can release without the endpoints lock
+    active_connections: Arc<AtomicU32>,
     max_connections: u32,
+    #[allow(dead_code)]
     last_activity: Instant,
 }
 
-#[derive(Debug, Clone)]
-struct CircuitBreaker {
-    state: CircuitBreakerState,
-    failure_count: u32,
-    last_failure: Option<Instant>,
-    failure_threshold: u32,
-    timeout_duration: Duration,
-    half_open_timeout: Duration,
+impl ConnectionPool {
+    fn active(&self) -> u32 {
+        self.active_connections.load(Ordering::Relaxed)
+    }
+}
+
+// Counts one request against an endpoint's connection pool until dropped
+#[derive(Debug)]
+pub struct ConnectionGuard {
+    active_connections: Arc<AtomicU32>,
 }
 
-#[derive(Debug, Clone, PartialEq)]
-enum CircuitBreakerState {
-    Closed,
-    Open,
-    HalfOpen,
+impl Drop for ConnectionGuard {
+    fn drop(&mut self) {
+        self.active_connections.fetch_sub(1, Ordering::Relaxed);
+    }
 }
 
 #[derive(Debug, Clone)]
 struct DiscoveredEndpoint {
+    #[allow(dead_code)]
     url: String,
     score: f64,
     features: Vec<String>,
     latency: Duration,
     last_tested: Instant,
+    #[allow(dead_code)]
     test_results: TestResults,
 }
 
+// A source of candidate endpoints for auto-discovery. Candidates are probed with the configured
+// test methods before any of them is added.
+#[async_trait]
+pub trait DiscoveryProvider {
+    // Identifies the provider in logs
+    fn name(&self) -> String;
+
+    async fn discover(&self) -> Result<Vec<EndpointConfig>, AppError>;
+}
+
 #[derive(Debug, Clone)]
 struct TestResults {
     health_check: bool,
@@ -75,70 +271,101 @@ struct TestResults {
     response_times: HashMap<String, Duration>,
 }
 
-impl Default for ConnectionPool {
-    fn default() -> Self {
-        Self {
-            active_connections: 0,
-            max_connections: 100,
-            last_activity: Instant::now(),
-        }
+const DEFAULT_MAX_CONNECTIONS: u32 = 100;
+// Pool usage above which responses carry X-Backend-Pressure, and above which they become a 429
+pub const PRESSURE_ADVISORY_THRESHOLD: f64 = 0.8;
+pub const PRESSURE_REJECT_THRESHOLD: f64 = 0.95;
+
+fn new_endpoint_stats(latency_window_size: usize) -> EndpointStats {
+    EndpointStats {
+        recent_latencies: LatencyHistogram::new(latency_window_size),
+        ..EndpointStats::default()
     }
 }
 
-impl Default for CircuitBreaker {
-    fn default() -> Self {
-        Self {
-            state: CircuitBreakerState::Closed,
-            failure_count: 0,
-            last_failure: None,
-            failure_threshold: 5,
-            timeout_duration: Duration::from_secs(30),
-            half_open_timeout: Duration::from_secs(60),
-        }
+fn replace_endpoint_config(configs: &mut [EndpointConfig], updated: EndpointConfig) {
+    if let Some(existing) = configs.iter_mut().find(|e| e.url == updated.url) {
+        *existing = updated;
     }
 }
 
-impl CircuitBreaker {
-    fn record_success(&mut self) {
-        self.failure_count = 0;
-        self.state = CircuitBreakerState::Closed;
-        self.last_failure = None;
+// Checks an endpoint added at runtime against the ones already configured
+fn validate_new_endpoint(config: &EndpointConfig, existing: &[EndpointConfig]) -> Result<(), AppError> {
+    let url = reqwest::Url::parse(&config.url)
+        .map_err(|e| AppError::ConfigValidationError(format!("endpoint '{}' has malformed url '{}': {}", config.name, config.url, e)))?;
+    if !matches!(url.scheme(), "http" | "https") {
+        return Err(AppError::ConfigValidationError(format!(
+            "endpoint '{}' url '{}' must start with http:// or https://", config.name, config.url
+        )));
+    }
+    if config.weight == 0 {
+        return Err(AppError::ConfigValidationError(format!("endpoint '{}' has weight 0", config.name)));
     }
+    let normalized_url = config.url.trim_end_matches('/').to_lowercase();
+    if existing.iter().any(|e| e.name == config.name || e.url.trim_end_matches('/').to_lowercase() == normalized_url) {
+        return Err(AppError::ConfigValidationError(format!(
+            "endpoint '{}' ({}) is already configured", config.name, config.url
+        )));
+    }
+    Ok(())
+}
 
-    fn record_failure(&mut self) {
-        self.failure_count += 1;
-        self.last_failure = Some(Instant::now());
-        
-        if self.failure_count >= self.failure_threshold {
-            self.state = CircuitBreakerState::Open;
-        }
+fn read_pem(path: &str, endpoint: &str) -> Result<Vec<u8>, AppError> {
+    let mut pem = std::fs::read(path)
+        .map_err(|e| AppError::config(&format!("Failed to read {} for endpoint {}: {}", path, endpoint, e)))?;
+    // Keeps the next file's BEGIN line separate when this one lacks a trailing newline
+    pem.push(b'\n');
+    Ok(pem)
+}
+
+// Fewer active connections wins, lower average response time breaks ties
+fn power_of_two_choice<'a>(a: &'a Endpoint, b: &'a Endpoint) -> &'a Endpoint {
+    match a.connection_pool.active().cmp(&b.connection_pool.active()) {
+        std::cmp::Ordering::Less => a,
+        std::cmp::Ordering::Greater => b,
+        std::cmp::Ordering::Equal if b.stats.response_times.mean() < a.stats.response_times.mean() => b,
+        std::cmp::Ordering::Equal => a,
     }
+}
 
-    fn can_attempt(&mut self) -> bool {
-        match self.state {
-            CircuitBreakerState::Closed => true,
-            CircuitBreakerState::Open => {
-                if let Some(last_failure) = self.last_failure {
-                    if last_failure.elapsed() > self.timeout_duration {
-                        self.state = CircuitBreakerState::HalfOpen;
-                        return true;
-                    }
-                }
-                false
-            }
-            CircuitBreakerState::HalfOpen => true,
+// `roll` is uniform in 0..100; weights past a combined 100 are never reached
+fn pick_canary(canaries: &[(Uuid, u8)], roll: u32) -> Option<Uuid> {
+    let mut cumulative = 0;
+    canaries.iter()
+        .find(|(_, weight)| {
+            cumulative += *weight as u32;
+            roll < cumulative
+        })
+        .map(|(id, _)| *id)
+}
+
+// The reporting task paces adjustments, so the retry itself adjusts whenever asked
+fn new_adaptive_retry() -> AdaptiveRetry {
+    AdaptiveRetry::new(RetryConfig::default()).with_adjustment_interval(Duration::ZERO)
+}
+
+impl Default for ConnectionPool {
+    fn default() -> Self {
+        Self {
+            active_connections: Arc::new(AtomicU32::new(0)),
+            max_connections: DEFAULT_MAX_CONNECTIONS,
+            last_activity: Instant::now(),
         }
     }
 }
 
 impl EndpointManager {
-    pub async fn new(configs: Vec<EndpointConfig>, config: Config) -> Result<Self, AppError> {
+    pub async fn new(
+        configs: Vec<EndpointConfig>,
+        config: Config,
+        circuit_breakers: Arc<CircuitBreakerRegistry>,
+    ) -> Result<Self, AppError> {
         let mut endpoints = HashMap::new();
-        let mut circuit_breakers = HashMap::new();
         
         for endpoint_config in configs {
             let id = Uuid::new_v4();
             let client = Self::create_client(&endpoint_config)?;
+            let transport = Self::create_transport(&endpoint_config, &client)?;
             
             let endpoint = Endpoint {
                 info: EndpointInfo {
@@ -153,29 +380,55 @@ impl EndpointManager {
                     latitude: endpoint_config.latitude,
                     longitude: endpoint_config.longitude,
                     region: endpoint_config.region.clone(),
+                    tls_cert_expires_at: None,
+                    tls_cert_days_remaining: None,
+                    slot_lag: None,
+                    slot_lag_priority: None,
                 },
-                stats: EndpointStats::default(),
+                stats: new_endpoint_stats(config.latency_window_size),
                 client,
+                transport,
                 config: endpoint_config,
                 connection_pool: ConnectionPool::default(),
+                discovered: false,
+                draining: false,
+                failure_rate_window: VecDeque::new(),
+                checked_status: EndpointStatus::Unknown,
+                gradient_status: None,
+                adaptive_retry: new_adaptive_retry(),
             };
             
-            circuit_breakers.insert(id, CircuitBreaker::default());
+            circuit_breakers.register(id);
             endpoints.insert(id, endpoint);
         }
         
         info!("Initialized {} endpoints", endpoints.len());
         
         Ok(Self {
+            anomaly_detector: LatencyAnomalyDetector::new(config.latency_anomaly.clone()),
+            health_gradient: HealthGradient::new(config.health_gradient.clone()),
+            promotion: config.discovery.promotion.clone(),
+            latency_window_size: config.latency_window_size,
+            metrics_service: None,
+            strategy: config.load_balancing_strategy.clone(),
+            method_strategies: config.method_strategies.clone(),
+            method_tags: config.method_tags.clone(),
             config: Arc::new(RwLock::new(config)),
             endpoints: Arc::new(RwLock::new(endpoints)),
-            strategy: LoadBalancingStrategy::HealthBased,
             next_round_robin: Arc::new(RwLock::new(0)),
-            circuit_breakers: Arc::new(RwLock::new(circuit_breakers)),
+            circuit_breakers,
             discovery_cache: Arc::new(RwLock::new(HashMap::new())),
+            slot_tracker: Arc::new(DashMap::new()),
+            service_endpoints: Arc::new(RwLock::new(HashMap::new())),
+            adaptive_weights: Arc::new(AdaptiveLoadBalancer::default()),
         })
     }
 
+    pub fn with_metrics_service(mut self, metrics_service: Arc<MetricsService>) -> Self {
+        self.metrics_service = Some(metrics_service);
+        self
+    }
+
     fn create_client(config: &EndpointConfig) -> Result<reqwest::Client, AppError> {
         let mut builder = reqwest::Client::builder()
             .timeout(Duration::from_secs(10))
@@ -193,20 +446,98 @@ impl EndpointManager {
             builder = builder.default_headers(headers);
         }
 
+        match (&config.tls_cert_path, &config.tls_key_path) {
+            (Some(cert_path), Some(key_path)) => {
+                let mut pem = read_pem(cert_path, &config.name)?;
+                pem.extend(read_pem(key_path, &config.name)?);
+                let identity = reqwest::Identity::from_pem(&pem)
+                    .map_err(|e| AppError::config(&format!("Invalid TLS client certificate for {}: {}", config.name, e)))?;
+                builder = builder.identity(identity);
+            }
+            (None, None) => {}
+            _ => return Err(AppError::config(&format!(
+                "Endpoint {} needs both tls_cert_path and tls_key_path for mutual TLS", config.name
+            ))),
+        }
+        if let Some(ca_path) = &config.tls_ca_path {
+            let certificates = reqwest::Certificate::from_pem_bundle(&read_pem(ca_path, &config.name)?)
+                .map_err(|e| AppError::config(&format!("Invalid TLS CA bundle for {}: {}", config.name, e)))?;
+            if certificates.is_empty() {
+                return Err(AppError::config(&format!("No certificates in TLS CA bundle {}", ca_path)));
+            }
+            for certificate in certificates {
+                builder = builder.add_root_certificate(certificate);
+            }
+        }
+
         builder.build()
             .map_err(|e| AppError::config(&format!("Failed to create HTTP client: {}", e)))
     }
+
+    // gRPC endpoints get a tonic channel; the HTTP client only carries their JSON-RPC calls
+    // for HTTP endpoints
+    fn create_transport(config: &EndpointConfig, client: &reqwest::Client) -> Result<Arc<dyn RpcTransport>, AppError> {
+        let forward_headers = config.forward_headers.iter()
+            .map(|name| name.parse::<HeaderName>().map_err(|_| AppError::config(&format!(
+                "Invalid forward header {} for endpoint {}", name, config.name
+            ))))
+            .collect::<Result<Vec<_>, _>>()?;
+        Ok(match config.kind {
+            EndpointKind::Http => Arc::new(
+                HttpTransport::new(client.clone(), config.url.clone())
+                    .with_signer(config.signing.as_ref().map(RequestSigner::new))
+                    .with_forward_headers(forward_headers),
+            ),
+            EndpointKind::Grpc => Arc::new(GrpcTransport::new(config)?),
+        })
+    }
+
+    // Sends a JSON-RPC request over whichever protocol the endpoint speaks
+    async fn transport(&self, endpoint_id: Uuid) -> Result<Arc<dyn RpcTransport>, AppError> {
+        self.endpoints.read().await
+            .get(&endpoint_id)
+            .map(|e| e.transport.clone())
+            .ok_or_else(|| AppError::endpoint("Endpoint not found"))
+    }
+    
+    pub async fn send_rpc_request(&self, endpoint_id: Uuid, request: Value) -> Result<Value, AppError> {
+        self.transport(endpoint_id).await?.send(request).await
+    }
+    
+    // The response along with its size in bytes as received from the endpoint. Of `headers`, only
+    // the ones in the endpoint's forward_headers are sent on.
+    pub async fn send_measured_rpc_request(
+        &self,
+        endpoint_id: Uuid,
+        request: Value,
+        headers: Option<&HeaderMap>,
+    ) -> Result<(Value, usize), AppError> {
+        self.transport(endpoint_id).await?.send_measured(request, headers).await
+    }
     
     pub async fn get_endpoint_info(&self) -> Vec<EndpointInfo> {
         let endpoints = self.endpoints.read().await;
         endpoints.values()
-            .map(|endpoint| endpoint.info.clone())
+            .map(|endpoint| EndpointInfo {
+                slot_lag: self.slot_lag(endpoint.info.id),
+                ..endpoint.info.clone()
+            })
             .collect()
     }
-    
+
+    pub fn circuit_breakers(&self) -> &Arc<CircuitBreakerRegistry> {
+        &self.circuit_breakers
+    }
+
+    pub async fn circuit_breaker_states(&self) -> HashMap<Uuid, &'static str> {
+        self.circuit_breakers.states()
+            .into_iter()
+            .map(|(id, state)| (id, state.as_str()))
+            .collect()
+    }
+
     pub async fn get_stats(&self) -> serde_json::Value {
         let endpoints = self.endpoints.read().await;
-        let circuit_breakers = self.circuit_breakers.read().await;
         
         let mut total_requests = 0u64;
         let mut total_successful = 0u64;
@@ -219,11 +550,11 @@ impl EndpointManager {
             total_successful += endpoint.stats.successful_requests;
             total_failed += endpoint.stats.failed_requests;
             
-            if endpoint.stats.avg_response_time > 0.0 {
-                response_times.push(endpoint.stats.avg_response_time);
+            if !endpoint.stats.response_times.is_empty() {
+                response_times.push(endpoint.stats.response_times.mean());
             }
 
-            let circuit_breaker = circuit_breakers.get(&endpoint.info.id);
+            let circuit_breaker = self.circuit_breakers.snapshot(endpoint.info.id);
             
             endpoint_details.push(json!({
                 "id": endpoint.info.id,
@@ -232,6 +563,7 @@ impl EndpointManager {
                 "status": endpoint.info.status,
                 "weight": endpoint.info.weight,
                 "priority": endpoint.info.priority,
+                "effective_priority": endpoint.info.effective_priority(),
                 "region": endpoint.info.region,
                 "stats": {
                     "total_requests": endpoint.stats.total_requests,
@@ -240,24 +572,34 @@ impl EndpointManager {
                     "success_rate": if endpoint.stats.total_requests > 0 {
                         endpoint.stats.successful_requests as f64 / endpoint.stats.total_requests as f64
                     } else { 0.0 },
-                    "avg_response_time_ms": endpoint.stats.avg_response_time,
+                    "avg_response_time_ms": endpoint.stats.response_times.mean(),
+                    "p50_response_time_ms": endpoint.stats.response_times.p50(),
+                    "p95_response_time_ms": endpoint.stats.response_times.p95(),
+                    "p99_response_time_ms": endpoint.stats.response_times.p99(),
+                    "recent_latency_ms": {
+                        "samples": endpoint.stats.recent_latencies.len(),
+                        "p50": endpoint.info.score.recent_latency.p50,
+                        "p95": endpoint.info.score.recent_latency.p95,
+                        "p99": endpoint.info.score.recent_latency.p99,
+                        "p999": endpoint.info.score.recent_latency.p999,
+                    },
+                    "recent_failure_rate": failure_rate(&endpoint.failure_rate_window),
                     "last_success": endpoint.stats.last_success,
                     "last_failure": endpoint.stats.last_failure,
                 },
                 "circuit_breaker": circuit_breaker.map(|cb| json!({
-                    "state": match cb.state {
-                        CircuitBreakerState::Closed => "closed",
-                        CircuitBreakerState::Open => "open",
-                        CircuitBreakerState::HalfOpen => "half_open",
-                    },
+                    "state": cb.state.as_str(),
                     "failure_count": cb.failure_count,
                     "last_failure_secs_ago": cb.last_failure.map(|t| t.elapsed().as_secs()),
                 })),
                 "connection_pool": {
-                    "active_connections": endpoint.connection_pool.active_connections,
+                    "active_connections": endpoint.connection_pool.active(),
                     "max_connections": endpoint.connection_pool.max_connections,
                 },
                 "features": endpoint.config.features,
+                "tags": endpoint.config.tags,
+                "draining": endpoint.draining,
+                "adaptive_retry": endpoint.adaptive_retry.get_stats(),
             }));
         }
         
@@ -294,32 +636,249 @@ impl EndpointManager {
                 LoadBalancingStrategy::HealthBased => "health_based",
                 LoadBalancingStrategy::LeastLatency => "least_latency",
                 LoadBalancingStrategy::Weighted => "weighted",
+                LoadBalancingStrategy::FreshestData => "freshest_data",
+                LoadBalancingStrategy::PowerOfTwoChoices => "power_of_two_choices",
+                LoadBalancingStrategy::LeastConnections => "least_connections",
             },
             "endpoints": endpoint_details,
         })
     }
     
     pub async fn select_endpoint(&self) -> Result<(Uuid, reqwest::Client), AppError> {
-        // Check circuit breakers first
+        self.select_endpoint_with_strategy(self.strategy.clone()).await
+    }
+    
+    // Only endpoints whose allowed_methods/denied_methods permit the method are candidates.
+    // Tagged methods go to their pool; otherwise the method's entry in method_strategies,
+    // or the global strategy without one
+    pub async fn select_endpoint_for_method(&self, method: &str) -> Result<(Uuid, reqwest::Client), AppError> {
+        self.select_endpoint_for_method_excluding(method, &[]).await
+    }
+
+    // Same selection, passing over the excluded endpoints (e.g. ones a retried request already
+    // failed on). If that leaves nothing, an excluded endpoint is still better than no attempt
+    pub async fn select_endpoint_for_method_excluding(
+        &self,
+        method: &str,
+        excluded: &[Uuid],
+    ) -> Result<(Uuid, reqwest::Client), AppError> {
+        if !self.endpoints.read().await.values().any(|e| e.config.allows_method(method)) {
+            return Err(AppError::FeatureNotAvailable);
+        }
+        let candidates = Candidates { method: Some(method), excluded };
+        match self.select_for_method(method, candidates).await {
+            Err(AppError::AllEndpointsUnhealthy) if !excluded.is_empty() => {
+                self.select_for_method(method, Candidates::for_method(method)).await
+            }
+            selected => selected,
+        }
+    }
+
+    async fn select_for_method(&self, method: &str, candidates: Candidates<'_>) -> Result<(Uuid, reqwest::Client), AppError> {
+        if let Some(tag) = self.method_tags.get(method) {
+            return self.select_from_pool(tag, candidates).await;
+        }
+        let strategy = self.method_strategies.get(method).unwrap_or(&self.strategy);
+        self.select_with_strategy(strategy.clone(), candidates).await
+    }
+
+    pub async fn endpoint_pool(&self, tag: &str) -> EndpointPool {
+        let endpoint_ids = self.endpoints.read().await
+            .values()
+            .filter(|e| e.config.tags.iter().any(|t| t == tag))
+            .map(|e| e.info.id)
+            .collect();
+        EndpointPool { tag: tag.to_string(), endpoint_ids }
+    }
+
+    pub async fn select_endpoint_from_pool(&self, tag: &str) -> Result<(Uuid, reqwest::Client), AppError> {
+        self.select_from_pool(tag, Candidates::default()).await
+    }
+
+    // Healthiest endpoint in the tag's pool; when none of them can take the request, any
+    // available endpoint is better than failing it
+    async fn select_from_pool(&self, tag: &str, candidates: Candidates<'_>) -> Result<(Uuid, reqwest::Client), AppError> {
+        let pool = self.endpoint_pool(tag).await;
         {
-            let mut breakers = self.circuit_breakers.write().await;
-            breakers.retain(|_, breaker| {
-                breaker.can_attempt()
-            });
+            let endpoints = self.endpoints.read().await;
+            let members = pool.endpoint_ids().iter().filter_map(|id| endpoints.get(id));
+            if let Some(endpoint) = self.healthiest(members, candidates) {
+                return Ok((endpoint.info.id, endpoint.client.clone()));
+            }
+        }
+
+        debug!("No available endpoint tagged {}, falling back to all endpoints", tag);
+        self.select_with_strategy(self.strategy.clone(), candidates).await
+    }
+
+    pub async fn select_endpoint_with_strategy(
+        &self,
+        strategy: LoadBalancingStrategy,
+    ) -> Result<(Uuid, reqwest::Client), AppError> {
+        self.select_with_strategy(strategy, Candidates::default()).await
+    }
+
+    async fn select_with_strategy(
+        &self,
+        strategy: LoadBalancingStrategy,
+        candidates: Candidates<'_>,
+    ) -> Result<(Uuid, reqwest::Client), AppError> {
+        match strategy {
+            LoadBalancingStrategy::RoundRobin => self.select_round_robin(candidates).await,
+            LoadBalancingStrategy::HealthBased => self.select_by_health(candidates).await,
+            LoadBalancingStrategy::LeastLatency => self.select_by_latency(candidates).await,
+            LoadBalancingStrategy::Weighted => self.select_weighted(candidates).await,
+            LoadBalancingStrategy::FreshestData => self.select_freshest(candidates).await,
+            LoadBalancingStrategy::PowerOfTwoChoices => self.select_power_of_two(candidates).await,
+            LoadBalancingStrategy::LeastConnections => self.select_least_connections(candidates).await,
+        }
+    }
+
+    // Fewest requests in flight; ties go to the endpoint that has served the fewest requests
+    // so an idle fleet still takes turns
+    async fn select_least_connections(&self, candidates: Candidates<'_>) -> Result<(Uuid, reqwest::Client), AppError> {
+        let endpoints = self.endpoints.read().await;
+
+        let least_loaded = endpoints.values()
+            .filter(|e| self.is_endpoint_available(e, candidates))
+            .min_by_key(|e| (e.connection_pool.active(), e.stats.total_requests));
+
+        match least_loaded {
+            Some(endpoint) => Ok((endpoint.info.id, endpoint.client.clone())),
+            None => Err(AppError::AllEndpointsUnhealthy),
+        }
+    }
+
+    // Counts a request against the endpoint's pool for as long as the guard is held
+    pub async fn acquire_connection(&self, endpoint_id: Uuid) -> Option<ConnectionGuard> {
+        let endpoints = self.endpoints.read().await;
+        let pool = &endpoints.get(&endpoint_id)?.connection_pool;
+        pool.active_connections.fetch_add(1, Ordering::Relaxed);
+        Some(ConnectionGuard {
+            active_connections: pool.active_connections.clone(),
+        })
+    }
+
+    // O(1) in the fleet size: two distinct random candidates, fewer active connections wins
+    async fn select_power_of_two(&self, candidates: Candidates<'_>) -> Result<(Uuid, reqwest::Client), AppError> {
+        let endpoints = self.endpoints.read().await;
+        let available: Vec<_> = endpoints.values()
+            .filter(|e| self.is_endpoint_available(e, candidates))
+            .collect();
+
+        let selected = match available.len() {
+            0 => return Err(AppError::AllEndpointsUnhealthy),
+            1 => available[0],
+            len => {
+                let mut rng = rand::thread_rng();
+                let first = rng.gen_range(0..len);
+                // Skip over `first` so the second pick is always a different endpoint
+                let second = (first + rng.gen_range(1..len)) % len;
+                let (a, b) = (available[first], available[second]);
+
+                if let Some(metrics_service) = &self.metrics_service {
+                    metrics_service.record_power_of_two_selection(
+                        a.connection_pool.active().abs_diff(b.connection_pool.active()),
+                    );
+                }
+                power_of_two_choice(a, b)
+            }
+        };
+        Ok((selected.info.id, selected.client.clone()))
+    }
+    
+    async fn select_freshest(&self, candidates: Candidates<'_>) -> Result<(Uuid, reqwest::Client), AppError> {
+        let endpoints = self.endpoints.read().await;
+        
+        let freshest = endpoints.values()
+            .filter(|e| self.is_endpoint_available(e, candidates))
+            .filter(|e| {
+                self.circuit_breakers.get_state(e.info.id)
+                    .map(|state| state == CircuitBreakerState::Closed)
+                    .unwrap_or(true)
+            })
+            .filter_map(|e| self.slot_tracker.get(&e.info.id).map(|slot| (*slot, e)))
+            .max_by_key(|(slot, e)| (*slot, std::cmp::Reverse(e.info.effective_priority())));
+        
+        match freshest {
+            Some((_, endpoint)) => {
+                if let (Some(metrics_service), Some(lag)) = (&self.metrics_service, self.slot_lag(endpoint.info.id)) {
+                    metrics_service.record_freshest_data_slot_lag(lag);
+                }
+                Ok((endpoint.info.id, endpoint.client.clone()))
+            }
+            None => {
+                // No slots known yet (tracker still warming up), so fall back to health
+                drop(endpoints);
+                debug!("No tracked slots available, falling back to health-based selection");
+                self.select_by_health(candidates).await
+            }
+        }
+    }
+    
+    // How many slots the endpoint is behind the freshest one we know of
+    pub fn slot_lag(&self, endpoint_id: Uuid) -> Option<u64> {
+        let slot = *self.slot_tracker.get(&endpoint_id)?;
+        let max_slot = self.slot_tracker.iter().map(|entry| *entry.value()).max()?;
+        Some(max_slot.saturating_sub(slot))
+    }
+    
+    pub async fn start_slot_tracking(self: Arc<Self>, shutdown: CancellationToken) {
+        info!("Starting slot tracking");
+        let mut interval = interval(SLOT_TRACKING_INTERVAL);
+        
+        loop {
+            tokio::select! {
+                _ = shutdown.cancelled() => break,
+                _ = interval.tick() => {}
+            }
+            
+            tokio::select! {
+                _ = shutdown.cancelled() => break,
+                _ = self.refresh_slots() => {}
+            }
         }
+        
+        info!("Slot tracking stopped");
+    }
 
-        match self.strategy {
-            LoadBalancingStrategy::RoundRobin => self.select_round_robin().await,
-            LoadBalancingStrategy::HealthBased => self.select_by_health().await,
-            LoadBalancingStrategy::LeastLatency => self.select_by_latency().await,
-            LoadBalancingStrategy::Weighted => self.select_weighted().await,
+    // Polls getSlot on every endpoint once
+    pub async fn refresh_slots(&self) {
+        let targets: Vec<_> = self.endpoints.read().await
+            .values()
+            .map(|e| (e.info.id, e.info.url.clone(), e.transport.clone()))
+            .collect();
+        
+        let polls = targets.iter().map(|(id, url, transport)| async move {
+            (*id, Self::fetch_slot(transport.as_ref(), url).await)
+        });
+        for (id, result) in futures::future::join_all(polls).await {
+            match result {
+                Ok(slot) => {
+                    self.slot_tracker.insert(id, slot);
+                }
+                Err(e) => {
+                    // A stale slot would keep attracting freshness-sensitive traffic
+                    self.slot_tracker.remove(&id);
+                    debug!("Slot poll failed for endpoint {}: {}", id, e);
+                }
+            }
         }
+        self.slot_tracker.retain(|id, _| targets.iter().any(|(target, _, _)| target == id));
+    }
+    
+    async fn fetch_slot(transport: &dyn RpcTransport, url: &str) -> Result<u64, AppError> {
+        let request = json!({"jsonrpc": "2.0", "id": 1, "method": "getSlot"});
+        let response = timeout(SLOT_TRACKING_INTERVAL, transport.send(request)).await??;
+        
+        response["result"].as_u64()
+            .ok_or_else(|| AppError::endpoint(&format!("Invalid getSlot response from {}", url)))
     }
     
-    async fn select_round_robin(&self) -> Result<(Uuid, reqwest::Client), AppError> {
+    async fn select_round_robin(&self, candidates: Candidates<'_>) -> Result<(Uuid, reqwest::Client), AppError> {
         let endpoints = self.endpoints.read().await;
         let healthy_endpoints: Vec<_> = endpoints.values()
-            .filter(|e| self.is_endpoint_available(e))
+            .filter(|e| self.is_endpoint_available(e, candidates))
             .collect();
         
         if healthy_endpoints.is_empty() {
@@ -333,17 +892,19 @@ impl EndpointManager {
         Ok((selected.info.id, selected.client.clone()))
     }
     
-    async fn select_by_health(&self) -> Result<(Uuid, reqwest::Client), AppError> {
+    async fn select_by_health(&self, candidates: Candidates<'_>) -> Result<(Uuid, reqwest::Client), AppError> {
         let endpoints = self.endpoints.read().await;
-        let circuit_breakers = self.circuit_breakers.read().await;
         
-        let best_endpoint = endpoints.values()
-            .filter(|e| self.is_endpoint_available(e))
-            .filter(|e| {
-                circuit_breakers.get(&e.info.id)
-                    .map(|cb| cb.state != CircuitBreakerState::Open)
-                    .unwrap_or(true)
-            })
+        match self.healthiest(endpoints.values(), candidates) {
+            Some(endpoint) => Ok((endpoint.info.id, endpoint.client.clone())),
+            None => Err(AppError::AllEndpointsUnhealthy),
+        }
+    }
+    
+    fn healthiest<'a>(&self, endpoints: impl Iterator<Item = &'a Endpoint>, candidates: Candidates<'_>) -> Option<&'a Endpoint> {
+        endpoints
+            .filter(|e| self.is_endpoint_available(e, candidates))
+            .filter(|e| self.circuit_breakers.can_attempt(e.info.id))
             .min_by_key(|e| {
                 let health_score = match e.info.status {
                     EndpointStatus::Healthy => 0,
@@ -351,25 +912,17 @@ impl EndpointManager {
                     EndpointStatus::Unknown => 2,
                     EndpointStatus::Unhealthy => 3,
                 };
-                (health_score, e.info.priority, (e.stats.avg_response_time * 100.0) as u64)
-            });
-        
-        match best_endpoint {
-            Some(endpoint) => Ok((endpoint.info.id, endpoint.client.clone())),
-            None => Err(AppError::AllEndpointsUnhealthy),
-        }
+                (health_score, e.info.effective_priority(), (e.stats.response_times.mean() * 100.0) as u64)
+            })
     }
     
-    async fn select_by_latency(&self) -> Result<(Uuid, reqwest::Client), AppError> {
+    async fn select_by_latency(&self, candidates: Candidates<'_>) -> Result<(Uuid, reqwest::Client), AppError> {
         let endpoints = self.endpoints.read().await;
         
         let best_endpoint = endpoints.values()
-            .filter(|e| self.is_endpoint_available(e))
-            .min_by(|a, b| {
-                a.stats.avg_response_time
-                    .partial_cmp(&b.stats.avg_response_time)
-                    .unwrap_or(std::cmp::Ordering::Equal)
-            });
+            .filter(|e| self.is_endpoint_available(e, candidates))
+            // Tail latency rather than the mean, which a few outliers can skew either way
+            .min_by_key(|e| e.stats.response_times.p95());
         
         match best_endpoint {
             Some(endpoint) => Ok((endpoint.info.id, endpoint.client.clone())),
@@ -377,54 +930,157 @@ impl EndpointManager {
         }
     }
     
-    async fn select_weighted(&self) -> Result<(Uuid, reqwest::Client), AppError> {
+    // Weights come from the adaptive load balancer, which falls back to the configured ones
+    async fn select_weighted(&self, candidates: Candidates<'_>) -> Result<(Uuid, reqwest::Client), AppError> {
         let endpoints = self.endpoints.read().await;
         
-        let healthy_endpoints: Vec<_> = endpoints.values()
-            .filter(|e| self.is_endpoint_available(e))
+        let weighted_endpoints: Vec<_> = endpoints.values()
+            .filter(|e| self.is_endpoint_available(e, candidates))
+            .map(|e| (e, self.adaptive_weights.weight(e.info.id, e.info.weight)))
             .collect();
         
-        if healthy_endpoints.is_empty() {
+        if weighted_endpoints.is_empty() {
             return Err(AppError::AllEndpointsUnhealthy);
         }
         
-        let total_weight: u32 = healthy_endpoints.iter()
-            .map(|e| e.info.weight)
+        let total_weight: f64 = weighted_endpoints.iter()
+            .map(|(_, weight)| weight)
             .sum();
         
-        if total_weight == 0 {
-            return self.select_round_robin().await;
+        if total_weight <= 0.0 {
+            drop(weighted_endpoints);
+            drop(endpoints);
+            return self.select_round_robin(candidates).await;
         }
         
-        let random_weight = (Instant::now().elapsed().as_nanos() % total_weight as u128) as u32;
-        let mut current_weight = 0;
+        let random_weight = rand::thread_rng().gen_range(0.0..total_weight);
+        let mut current_weight = 0.0;
         
-        for endpoint in healthy_endpoints {
-            current_weight += endpoint.info.weight;
+        for (endpoint, weight) in &weighted_endpoints {
+            current_weight += weight;
             if random_weight < current_weight {
                 return Ok((endpoint.info.id, endpoint.client.clone()));
             }
         }
         
-        // Fallback to first endpoint
-        let endpoint = &endpoints.values().find(|e| self.is_endpoint_available(e))
-            .ok_or(AppError::AllEndpointsUnhealthy)?;
+        // Rounding can leave the draw just past the last endpoint
+        let (endpoint, _) = weighted_endpoints[weighted_endpoints.len() - 1];
         Ok((endpoint.info.id, endpoint.client.clone()))
     }
 
-    fn is_endpoint_available(&self, endpoint: &Endpoint) -> bool {
+    pub fn adaptive_weights(&self) -> &Arc<AdaptiveLoadBalancer> {
+        &self.adaptive_weights
+    }
+
+    pub async fn recompute_adaptive_weights(&self) {
+        let samples: Vec<_> = self.endpoints.read().await
+            .values()
+            .map(|e| AdaptiveWeightSample {
+                id: e.info.id,
+                name: e.info.name.clone(),
+                configured_weight: e.info.weight,
+                total_requests: e.stats.total_requests,
+                successful_requests: e.stats.successful_requests,
+                p95_ms: e.info.score.recent_latency.p95,
+            })
+            .collect();
+        self.adaptive_weights.recompute(&samples);
+    }
+
+    // Publishes each endpoint's retry stats as custom metrics, then lets its adaptive retry
+    // adjust on the outcomes since the previous report
+    pub async fn report_retry_stats(&self) -> Vec<(String, AdaptiveRetryStats)> {
+        let reports: Vec<_> = self.endpoints.write().await
+            .values_mut()
+            .map(|endpoint| {
+                let stats = endpoint.adaptive_retry.get_stats();
+                endpoint.adaptive_retry.adjust_config();
+                (endpoint.info.name.clone(), stats)
+            })
+            .collect();
+
+        if let Some(metrics_service) = &self.metrics_service {
+            for (name, stats) in &reports {
+                let labels = HashMap::from([("endpoint".to_string(), name.clone())]);
+                for (field, value, metric_type) in [
+                    ("success_count", stats.success_count as f64, CustomMetricType::Counter),
+                    ("failure_count", stats.failure_count as f64, CustomMetricType::Counter),
+                    ("max_attempts", stats.max_attempts as f64, CustomMetricType::Gauge),
+                    ("initial_delay_ms", stats.initial_delay_ms as f64, CustomMetricType::Gauge),
+                ] {
+                    let metric = format!("adaptive_retry.{}.{}", name, field);
+                    metrics_service.record_custom_metric(&metric, value, labels.clone(), metric_type).await;
+                }
+            }
+        }
+        reports
+    }
+
+    pub async fn start_retry_reporting(self: Arc<Self>, shutdown: CancellationToken) {
+        let mut interval = interval(RETRY_STATS_INTERVAL);
+        // The first tick fires immediately, before there is anything to report
+        interval.tick().await;
+
+        loop {
+            tokio::select! {
+                _ = shutdown.cancelled() => break,
+                _ = interval.tick() => {
+                    self.report_retry_stats().await;
+                }
+            }
+        }
+    }
+
+    pub async fn start_adaptive_weighting(self: Arc<Self>, shutdown: CancellationToken) {
+        let mut interval = interval(ADAPTIVE_WEIGHT_INTERVAL);
+        
+        loop {
+            tokio::select! {
+                _ = shutdown.cancelled() => break,
+                _ = interval.tick() => self.recompute_adaptive_weights().await,
+            }
+        }
+    }
+
+    // Canaries are never picked by the strategies; they only get what select_canary samples.
+    // With a method, the endpoint must also permit it, and it mustn't be one of the excluded
+    fn is_endpoint_available(&self, endpoint: &Endpoint, candidates: Candidates<'_>) -> bool {
+        !endpoint.config.canary
+            && !candidates.excluded.contains(&endpoint.info.id)
+            && candidates.method.is_none_or(|method| endpoint.config.allows_method(method))
+            && self.can_serve(endpoint)
+    }
+
+    fn can_serve(&self, endpoint: &Endpoint) -> bool {
+        !endpoint.draining &&
         matches!(endpoint.info.status, 
             EndpointStatus::Healthy | EndpointStatus::Degraded | EndpointStatus::Unknown) &&
-        endpoint.connection_pool.active_connections < endpoint.connection_pool.max_connections
+        endpoint.connection_pool.active() < endpoint.connection_pool.max_connections
+    }
+
+    // A canary to send this request to as well, drawn with each canary's canary_weight as
+    // its percentage of requests
+    pub async fn select_canary(&self, method: &str) -> Option<Uuid> {
+        let endpoints = self.endpoints.read().await;
+        let canaries: Vec<(Uuid, u8)> = endpoints.values()
+            .filter(|e| e.config.canary && e.config.canary_weight > 0 && e.config.allows_method(method))
+            .filter(|e| self.can_serve(e) && self.circuit_breakers.can_attempt(e.info.id))
+            .map(|e| (e.info.id, e.config.canary_weight))
+            .collect();
+        if canaries.is_empty() {
+            return None;
+        }
+        pick_canary(&canaries, rand::thread_rng().gen_range(0..100))
     }
     
     pub async fn update_endpoint_stats(&self, 
         endpoint_id: Uuid, 
         success: bool, 
         response_time: std::time::Duration
-    ) {
+    ) -> Option<EndpointLatencyAnomaly> {
         let mut endpoints = self.endpoints.write().await;
-        let mut circuit_breakers = self.circuit_breakers.write().await;
+        let mut anomaly = None;
+        let mut promote = false;
         
         if let Some(endpoint) = endpoints.get_mut(&endpoint_id) {
             endpoint.stats.total_requests += 1;
@@ -433,40 +1089,100 @@ impl EndpointManager {
                 endpoint.stats.successful_requests += 1;
                 endpoint.stats.last_success = Some(Utc::now());
                 
-                // Update circuit breaker
-                if let Some(breaker) = circuit_breakers.get_mut(&endpoint_id) {
-                    breaker.record_success();
-                }
+                self.circuit_breakers.record_success(endpoint_id);
             } else {
                 endpoint.stats.failed_requests += 1;
                 endpoint.stats.last_failure = Some(Utc::now());
                 
-                // Update circuit breaker
-                if let Some(breaker) = circuit_breakers.get_mut(&endpoint_id) {
-                    breaker.record_failure();
-                }
+                self.circuit_breakers.record_failure(endpoint_id);
+            }
+            
+            endpoint.adaptive_retry.record(success);
+            if let Some(status) = self.health_gradient.record(&mut endpoint.failure_rate_window, success) {
+                endpoint.gradient_status = Some(status);
+                self.refresh_endpoint_status(endpoint);
             }
             
-            // Update rolling average response time
-            let current_avg = endpoint.stats.avg_response_time;
+            // Update the response time distribution
             let new_time = response_time.as_millis() as f64;
-            let total_requests = endpoint.stats.total_requests as f64;
+            endpoint.stats.response_times.record(response_time.as_millis() as u64);
+            endpoint.stats.recent_latencies.record(response_time.as_secs_f64() * 1000.0);
             
-            endpoint.stats.avg_response_time = if current_avg == 0.0 {
-                new_time
-            } else {
-                (current_avg * (total_requests - 1.0) + new_time) / total_requests
-            };
+            // Failed requests often end in timeouts, which would skew the latency baseline
+            if success {
+                anomaly = self.anomaly_detector.observe(endpoint_id, &mut endpoint.stats, new_time);
+            }
             
             // Update endpoint score
             self.calculate_endpoint_score(endpoint);
+            if let Some(metrics_service) = &self.metrics_service {
+                metrics_service.record_endpoint_latency_percentiles(&endpoint.info.name, &endpoint.info.score.recent_latency);
+            }
             
             debug!("Updated stats for endpoint {}: success={}, response_time={}ms, score={}", 
                 endpoint.info.name, success, new_time, endpoint.info.score.overall_grade);
+            
+            promote = success && endpoint.discovered && self.meets_promotion_criteria(&endpoint.stats);
+        }
+        drop(endpoints);
+        
+        if promote {
+            if let Err(e) = self.promote_discovered_endpoint(endpoint_id).await {
+                warn!("Failed to promote discovered endpoint {}: {}", endpoint_id, e);
+            }
         }
+        
+        anomaly
     }
 
-    fn calculate_endpoint_score(&self, endpoint: &mut Endpoint) {
+    fn meets_promotion_criteria(&self, stats: &EndpointStats) -> bool {
+        stats.total_requests > 0 &&
+            stats.successful_requests >= self.promotion.min_successful_requests &&
+            stats.successful_requests as f64 / stats.total_requests as f64 > self.promotion.min_success_rate
+    }
+
+    // Upgrades an auto-discovered endpoint that has proven reliable to the promoted
+    // weight and priority, and saves it to the config file so it survives restarts.
+    // Returns false if the endpoint isn't a discovered one or hasn't earned it yet.
+    pub async fn promote_discovered_endpoint(&self, endpoint_id: Uuid) -> Result<bool, AppError> {
+        let mut endpoints = self.endpoints.write().await;
+        let endpoint = endpoints.get_mut(&endpoint_id)
+            .ok_or_else(|| AppError::endpoint("Endpoint not found"))?;
+        if !endpoint.discovered || !self.meets_promotion_criteria(&endpoint.stats) {
+            return Ok(false);
+        }
+
+        endpoint.discovered = false;
+        endpoint.info.weight = self.promotion.promoted_weight;
+        endpoint.info.priority = self.promotion.promoted_priority;
+        endpoint.config.weight = self.promotion.promoted_weight;
+        endpoint.config.priority = self.promotion.promoted_priority;
+        let endpoint_config = endpoint.config.clone();
+        info!(
+            "Promoted discovered endpoint {} after {} successful requests ({:.2}% success): weight={}, priority={}",
+            endpoint.info.name,
+            endpoint.stats.successful_requests,
+            endpoint.info.score.success_rate,
+            endpoint_config.weight,
+            endpoint_config.priority
+        );
+        drop(endpoints);
+
+        if let Some(metrics_service) = &self.metrics_service {
+            metrics_service.record_endpoint_promotion();
+        }
+
+        let mut config = self.config.write().await;
+        config.endpoints.retain(|e| e.url != endpoint_config.url);
+        config.endpoints.push(endpoint_config);
+        let snapshot = config.clone();
+        drop(config);
+
+        snapshot.save_to(&self.promotion.config_path).await?;
+        Ok(true)
+    }
+
+    fn calculate_endpoint_score(&self, endpoint: &mut Endpoint) {
         let success_rate = if endpoint.stats.total_requests > 0 {
             (endpoint.stats.successful_requests as f64 / endpoint.stats.total_requests as f64) * 100.0
         } else {
@@ -480,11 +1196,18 @@ impl EndpointManager {
         score *= success_rate / 100.0;
         
         // Response time impact (penalty for slow responses)
-        if endpoint.stats.avg_response_time > 0.0 {
-            let time_penalty = (endpoint.stats.avg_response_time / 1000.0).min(20.0); // Max 20 point penalty
+        if !endpoint.stats.response_times.is_empty() {
+            let time_penalty = (endpoint.stats.response_times.mean() / 1000.0).min(20.0); // Max 20 point penalty
             score -= time_penalty;
         }
         
+        // Status impact, so a degrading endpoint loses ground before it is taken out of rotation
+        score *= match endpoint.info.status {
+            EndpointStatus::Healthy | EndpointStatus::Unknown => 1.0,
+            EndpointStatus::Degraded => 0.8,
+            EndpointStatus::Unhealthy => 0.5,
+        };
+        
         // Recency impact (prefer recently successful endpoints)
         if let Some(last_success) = endpoint.stats.last_success {
             let time_since_success = Utc::now().signed_duration_since(last_success).num_minutes();
@@ -511,103 +1234,269 @@ impl EndpointManager {
         endpoint.info.score = EndpointScore {
             overall_grade: grade.to_string(),
             success_rate,
-            avg_response_time: endpoint.stats.avg_response_time,
+            avg_response_time: endpoint.stats.response_times.mean(),
             uptime_percentage: success_rate, // Simplified calculation
             feature_support: endpoint.config.features.len() as u8,
             last_updated: Utc::now(),
+            recent_latency: endpoint.stats.recent_latencies.percentiles(),
         };
     }
     
-    pub async fn update_endpoint_status(&self, endpoint_id: Uuid, status: EndpointStatus) {
+    // Caps concurrent connections on every endpoint; None restores the default
+    pub async fn set_connection_limit(&self, limit: Option<u32>) {
+        let max_connections = limit.unwrap_or(DEFAULT_MAX_CONNECTIONS);
+        let mut endpoints = self.endpoints.write().await;
+        for endpoint in endpoints.values_mut() {
+            endpoint.connection_pool.max_connections = max_connections;
+        }
+    }
+
+    // Leaves the configured priority alone, so it applies again once the endpoint catches up
+    pub async fn set_slot_lag_priority(&self, endpoint_id: Uuid, priority: u8) {
         let mut endpoints = self.endpoints.write().await;
         if let Some(endpoint) = endpoints.get_mut(&endpoint_id) {
-            if endpoint.info.status != status {
-                info!("Endpoint {} status changed: {:?} -> {:?}", 
-                    endpoint.info.name, endpoint.info.status, status);
-                endpoint.info.status = status;
-                endpoint.info.last_checked = Utc::now();
+            if endpoint.info.slot_lag_priority != Some(priority) {
+                debug!("Endpoint {} slot lag priority changed: {:?} -> {}", endpoint.info.name, endpoint.info.slot_lag_priority, priority);
+                endpoint.info.slot_lag_priority = Some(priority);
             }
         }
     }
+
+    pub async fn update_endpoint_status(&self, endpoint_id: Uuid, status: EndpointStatus) {
+        let mut endpoints = self.endpoints.write().await;
+        if let Some(endpoint) = endpoints.get_mut(&endpoint_id) {
+            endpoint.checked_status = status;
+            self.refresh_endpoint_status(endpoint);
+        }
+    }
+
+    fn refresh_endpoint_status(&self, endpoint: &mut Endpoint) {
+        let status = combined_status(&endpoint.checked_status, endpoint.gradient_status.as_ref());
+        if endpoint.info.status == status {
+            return;
+        }
+        info!("Endpoint {} status changed: {:?} -> {:?}", 
+            endpoint.info.name, endpoint.info.status, status);
+        endpoint.info.status = status.clone();
+        endpoint.info.last_checked = Utc::now();
+        
+        self.circuit_breakers.event_bus().publish(SystemEvent::EndpointStatusChanged {
+            endpoint_id: endpoint.info.id,
+            url: endpoint.info.url.clone(),
+            name: endpoint.info.name.clone(),
+            status,
+            timestamp: endpoint.info.last_checked,
+        });
+    }
+    
+    pub async fn endpoint_id_for_url(&self, url: &str) -> Option<Uuid> {
+        self.endpoints.read().await
+            .values()
+            .find(|e| e.info.url == url)
+            .map(|e| e.info.id)
+    }
+
+    // Applies a status reported by a peer node without re-publishing it. Only endpoints
+    // configured here are touched, so gossip can never add an upstream. Returns the local
+    // endpoint id, or None if the URL is unknown.
+    pub async fn apply_peer_status(&self, url: &str, status: EndpointStatus) -> Option<Uuid> {
+        let endpoint_id = self.endpoint_id_for_url(url).await?;
+
+        let mut endpoints = self.endpoints.write().await;
+        let endpoint = endpoints.get_mut(&endpoint_id)?;
+        endpoint.checked_status = status;
+        let status = combined_status(&endpoint.checked_status, endpoint.gradient_status.as_ref());
+        if endpoint.info.status != status {
+            info!("Endpoint {} status changed by peer: {:?} -> {:?}", 
+                endpoint.info.name, endpoint.info.status, status);
+            endpoint.info.status = status;
+            endpoint.info.last_checked = Utc::now();
+        }
+        Some(endpoint_id)
+    }
     
+    pub async fn update_tls_certificate(&self, endpoint_id: Uuid, expires_at: DateTime<Utc>) -> Option<i64> {
+        let mut endpoints = self.endpoints.write().await;
+        let endpoint = endpoints.get_mut(&endpoint_id)?;
+        let days_remaining = (expires_at - Utc::now()).num_days();
+        endpoint.info.tls_cert_expires_at = Some(expires_at);
+        endpoint.info.tls_cert_days_remaining = Some(days_remaining);
+        Some(days_remaining)
+    }
+    
+    pub async fn get_endpoint_client(&self, endpoint_id: Uuid) -> Option<reqwest::Client> {
+        let endpoints = self.endpoints.read().await;
+        endpoints.get(&endpoint_id).map(|e| e.client.clone())
+    }
+
+    // Share of connection slots in use across endpoints that can still take requests, so one
+    // saturated endpoint doesn't raise the alarm while others have room
+    pub async fn pressure_factor(&self) -> f64 {
+        let endpoints = self.endpoints.read().await;
+        let (active, max) = endpoints.values()
+            .filter(|e| !e.draining && !matches!(e.info.status, EndpointStatus::Unhealthy))
+            .fold((0u64, 0u64), |(active, max), e| (
+                active + e.connection_pool.active() as u64,
+                max + e.connection_pool.max_connections as u64,
+            ));
+        if max == 0 {
+            return 0.0;
+        }
+        active as f64 / max as f64
+    }
+
+    // The client for `endpoint_id`, but only while it could be picked for `method`
+    pub async fn available_endpoint_client(&self, endpoint_id: Uuid, method: &str) -> Option<reqwest::Client> {
+        let endpoints = self.endpoints.read().await;
+        endpoints.get(&endpoint_id)
+            .filter(|e| self.is_endpoint_available(e, Candidates::for_method(method)))
+            .map(|e| e.client.clone())
+    }
+
     pub async fn get_endpoint_url(&self, endpoint_id: Uuid) -> Option<String> {
         let endpoints = self.endpoints.read().await;
         endpoints.get(&endpoint_id).map(|e| e.info.url.clone())
     }
 
-    pub async fn start_auto_discovery(&self) {
+    pub async fn get_endpoint_name(&self, endpoint_id: Uuid) -> Option<String> {
+        let endpoints = self.endpoints.read().await;
+        endpoints.get(&endpoint_id).map(|e| e.info.name.clone())
+    }
+
+    // The client headers the endpoint's forward_headers let through, for calls that don't go
+    // through its transport
+    pub async fn forwarded_headers(&self, endpoint_id: Uuid, headers: &HeaderMap) -> HeaderMap {
+        let endpoints = self.endpoints.read().await;
+        let mut forwarded = HeaderMap::new();
+        let names = endpoints.get(&endpoint_id)
+            .into_iter()
+            .flat_map(|endpoint| &endpoint.config.forward_headers)
+            // Invalid names were already refused when the endpoint was created
+            .filter_map(|name| name.parse::<HeaderName>().ok());
+        for name in names {
+            for value in headers.get_all(&name) {
+                forwarded.append(name.clone(), value.clone());
+            }
+        }
+        forwarded
+    }
+
+    pub async fn get_endpoint_config(&self, endpoint_id: Uuid) -> Option<EndpointConfig> {
+        let endpoints = self.endpoints.read().await;
+        endpoints.get(&endpoint_id).map(|e| e.config.clone())
+    }
+
+    pub async fn start_auto_discovery(self: Arc<Self>, shutdown: CancellationToken) {
         let config = self.config.read().await;
         if !config.discovery.enabled {
             return;
         }
 
         let discovery_interval = config.discovery.discovery_interval;
-        let cluster_urls = config.discovery.cluster_rpc_urls.clone();
         let test_methods = config.discovery.test_methods.clone();
+        let providers: Arc<Vec<Box<dyn DiscoveryProvider + Send + Sync>>> =
+            match crate::discovery::providers_from_config(&config.discovery) {
+                Ok(providers) => Arc::new(providers),
+                Err(e) => {
+                    error!("Auto-discovery disabled: {}", e);
+                    return;
+                }
+            };
         drop(config);
 
-        info!("Starting auto-discovery service");
+        info!("Starting auto-discovery service with {} providers", providers.len());
         
         let mut interval = interval(Duration::from_secs(discovery_interval));
         
         loop {
-            interval.tick().await;
-            
-            for cluster_url in &cluster_urls {
-                match self.discover_endpoints_from_cluster(cluster_url, &test_methods).await {
-                    Ok(discovered) => {
-                        info!("Discovered {} new endpoints from {}", discovered, cluster_url);
+            tokio::select! {
+                _ = shutdown.cancelled() => break,
+                _ = interval.tick() => {}
+            }
+
+            // One task per provider so a slow provider doesn't hold up the others
+            let mut tasks = JoinSet::new();
+            for index in 0..providers.len() {
+                let manager = self.clone();
+                let providers = providers.clone();
+                let test_methods = test_methods.clone();
+                tasks.spawn(async move {
+                    let provider = providers[index].as_ref();
+                    let result = manager.discover_endpoints_from(provider, &test_methods).await;
+                    (provider.name(), result)
+                });
+            }
+
+            let mut discovered_total = 0;
+            let mut failed_providers = 0;
+            loop {
+                let next = tokio::select! {
+                    _ = shutdown.cancelled() => {
+                        Self::shutdown_tasks(&mut tasks).await;
+                        info!("Auto-discovery stopped");
+                        return;
+                    }
+                    next = tasks.join_next() => next,
+                };
+
+                match next {
+                    None => break,
+                    Some(Ok((provider, Ok(discovered)))) => {
+                        info!("Discovered {} new endpoints from {}", discovered, provider);
+                        discovered_total += discovered;
+                    }
+                    Some(Ok((provider, Err(e)))) => {
+                        warn!("Discovery failed for {}: {}", provider, e);
+                        failed_providers += 1;
+                    }
+                    Some(Err(e)) if e.is_panic() => {
+                        error!("Discovery task panicked");
+                        Self::shutdown_tasks(&mut tasks).await;
+                        std::panic::resume_unwind(e.into_panic());
                     }
-                    Err(e) => {
-                        warn!("Discovery failed for {}: {}", cluster_url, e);
+                    Some(Err(e)) => {
+                        warn!("Discovery task cancelled: {}", e);
+                        failed_providers += 1;
                     }
                 }
             }
+
+            info!(
+                "Discovery round complete: {} endpoints from {} providers ({} failed)",
+                discovered_total, providers.len(), failed_providers
+            );
             
             // Cleanup old discovered endpoints
             self.cleanup_discovery_cache().await;
         }
-    }
 
-    async fn discover_endpoints_from_cluster(&self, cluster_url: &str, test_methods: &[String]) -> Result<usize, AppError> {
-        // Query cluster for getClusterNodes
-        let client = reqwest::Client::new();
-        let request = json!({
-            "jsonrpc": "2.0",
-            "id": 1,
-            "method": "getClusterNodes"
-        });
+        info!("Auto-discovery stopped");
+    }
 
-        let response = client.post(cluster_url)
-            .json(&request)
-            .send()
-            .await?;
+    async fn shutdown_tasks<T: 'static>(tasks: &mut JoinSet<T>) {
+        if tokio::time::timeout(Duration::from_secs(5), tasks.shutdown()).await.is_err() {
+            warn!("Timed out waiting for {} discovery tasks to stop", tasks.len());
+        }
+    }
 
-        let result: Value = response.json().await?;
-        
-        if let Some(nodes) = result.get("result").and_then(|r| r.as_array()) {
-            let mut discovered_count = 0;
-            
-            for node in nodes {
-                if let Some(rpc_url) = node.get("rpc").and_then(|r| r.as_str()) {
-                    if rpc_url.starts_with("http") {
-                        match self.test_discovered_endpoint(rpc_url, test_methods).await {
-                            Ok(endpoint_info) => {
-                                self.add_discovered_endpoint(rpc_url.to_string(), endpoint_info).await;
-                                discovered_count += 1;
-                            }
-                            Err(e) => {
-                                debug!("Failed to test endpoint {}: {}", rpc_url, e);
-                            }
-                        }
-                    }
+    async fn discover_endpoints_from(
+        &self,
+        provider: &(dyn DiscoveryProvider + Send + Sync),
+        test_methods: &[String],
+    ) -> Result<usize, AppError> {
+        let mut discovered_count = 0;
+        for candidate in provider.discover().await? {
+            match self.test_discovered_endpoint(&candidate.url, test_methods).await {
+                Ok(endpoint_info) => {
+                    self.add_discovered_endpoint(candidate, endpoint_info).await;
+                    discovered_count += 1;
+                }
+                Err(e) => {
+                    debug!("Failed to test endpoint {}: {}", candidate.url, e);
                 }
             }
-            
-            Ok(discovered_count)
-        } else {
-            Err(AppError::DiscoveryError("Invalid cluster response".to_string()))
         }
+        Ok(discovered_count)
     }
 
     async fn test_discovered_endpoint(&self, url: &str, test_methods: &[String]) -> Result<DiscoveredEndpoint, AppError> {
@@ -714,7 +1603,8 @@ impl EndpointManager {
         })
     }
 
-    async fn add_discovered_endpoint(&self, url: String, endpoint_info: DiscoveredEndpoint) {
+    async fn add_discovered_endpoint(&self, candidate: EndpointConfig, endpoint_info: DiscoveredEndpoint) {
+        let url = candidate.url.clone();
         let config = self.config.read().await;
         
         // Check if we should auto-add this endpoint
@@ -727,21 +1617,23 @@ impl EndpointManager {
             drop(endpoints);
             
             if !exists {
+                let name = candidate.name.clone();
                 let endpoint_config = EndpointConfig {
-                    url: url.clone(),
-                    name: format!("Auto-discovered-{}", url.split("://").nth(1).unwrap_or("unknown")),
-                    weight: 50, // Lower weight for auto-discovered endpoints
-                    priority: 10, // Lower priority
-                    region: None,
-                    latitude: None,
-                    longitude: None,
                     features: endpoint_info.features.clone(),
-                    max_connections: Some(25),
-                    auth_token: None,
+                    ..candidate
                 };
                 
-                if let Err(e) = self.add_endpoint(endpoint_config).await {
-                    warn!("Failed to add auto-discovered endpoint {}: {}", url, e);
+                match self.insert_endpoint(endpoint_config, true).await {
+                    Ok(endpoint_id) => {
+                        self.circuit_breakers.event_bus().publish(SystemEvent::EndpointStatusChanged {
+                            endpoint_id,
+                            url: url.clone(),
+                            name,
+                            status: EndpointStatus::Unknown,
+                            timestamp: Utc::now(),
+                        });
+                    }
+                    Err(e) => warn!("Failed to add auto-discovered endpoint {}: {}", url, e),
                 }
             }
         }
@@ -751,6 +1643,34 @@ impl EndpointManager {
         cache.insert(url, endpoint_info);
     }
 
+    pub(crate) fn discovered_endpoint_config(url: &str, name: &str, features: Vec<String>) -> EndpointConfig {
+        EndpointConfig {
+            url: url.to_string(),
+            name: name.to_string(),
+            weight: 50, // Lower weight for auto-discovered endpoints
+            priority: 10, // Lower priority
+            region: None,
+            latitude: None,
+            longitude: None,
+            features,
+            max_connections: Some(25),
+            auth_token: None,
+            tls_cert_path: None,
+            tls_key_path: None,
+            tls_ca_path: None,
+            health_status_codes: default_health_status_codes(),
+            health_response_key: None,
+            kind: EndpointKind::Http,
+            signing: None,
+            tags: Vec::new(),
+            canary: false,
+            canary_weight: 0,
+            allowed_methods: None,
+            denied_methods: None,
+            forward_headers: Vec::new(),
+        }
+    }
+
     async fn cleanup_discovery_cache(&self) {
         let mut cache = self.discovery_cache.write().await;
         let cutoff = Instant::now() - Duration::from_secs(3600); // 1 hour
@@ -758,9 +1678,19 @@ impl EndpointManager {
         cache.retain(|_, endpoint| endpoint.last_tested > cutoff);
     }
 
-    pub async fn add_endpoint(&self, config: EndpointConfig) -> Result<Uuid, AppError> {
+    // Validates and starts routing to a new endpoint, recording it in the config
+    pub async fn add_endpoint(&self, endpoint_config: EndpointConfig) -> Result<Uuid, AppError> {
+        let mut config = self.config.write().await;
+        validate_new_endpoint(&endpoint_config, &config.endpoints)?;
+        let id = self.insert_endpoint(endpoint_config.clone(), false).await?;
+        config.endpoints.push(endpoint_config);
+        Ok(id)
+    }
+
+    async fn insert_endpoint(&self, config: EndpointConfig, discovered: bool) -> Result<Uuid, AppError> {
         let id = Uuid::new_v4();
         let client = Self::create_client(&config)?;
+        let transport = Self::create_transport(&config, &client)?;
         
         let endpoint_name = config.name.clone();
         let endpoint_url = config.url.clone();
@@ -778,46 +1708,334 @@ impl EndpointManager {
                 latitude: config.latitude,
                 longitude: config.longitude,
                 region: config.region.clone(),
+                tls_cert_expires_at: None,
+                tls_cert_days_remaining: None,
+                slot_lag: None,
+                slot_lag_priority: None,
             },
-            stats: EndpointStats::default(),
+            stats: new_endpoint_stats(self.latency_window_size),
             client,
+            transport,
             config,
             connection_pool: ConnectionPool::default(),
+            discovered,
+            draining: false,
+            failure_rate_window: VecDeque::new(),
+            checked_status: EndpointStatus::Unknown,
+            gradient_status: None,
+            adaptive_retry: new_adaptive_retry(),
         };
         
         let mut endpoints = self.endpoints.write().await;
-        let mut circuit_breakers = self.circuit_breakers.write().await;
         
         endpoints.insert(id, endpoint);
-        circuit_breakers.insert(id, CircuitBreaker::default());
+        self.circuit_breakers.register(id);
         
         info!("Added new endpoint: {} ({})", endpoint_name, endpoint_url);
         Ok(id)
     }
 
     pub async fn remove_endpoint(&self, endpoint_id: Uuid) -> Result<(), AppError> {
+        // The endpoints lock is released first; update_config takes config before endpoints
+        let removed = self.remove_live_endpoint(endpoint_id).await?;
+        self.config.write().await.endpoints.retain(|e| e.url != removed.url);
+        Ok(())
+    }
+
+    async fn remove_live_endpoint(&self, endpoint_id: Uuid) -> Result<EndpointConfig, AppError> {
         let mut endpoints = self.endpoints.write().await;
-        let mut circuit_breakers = self.circuit_breakers.write().await;
         
         if let Some(endpoint) = endpoints.remove(&endpoint_id) {
-            circuit_breakers.remove(&endpoint_id);
+            self.circuit_breakers.remove(endpoint_id);
             info!("Removed endpoint: {} ({})", endpoint.info.name, endpoint.info.url);
-            Ok(())
+            Ok(endpoint.config)
         } else {
             Err(AppError::EndpointError("Endpoint not found".to_string()))
         }
     }
 
-    pub async fn update_config(&self, new_config: Value) -> Result<(), AppError> {
-        // This is a simplified version - in practice you'd want more sophisticated config updates
-        info!("Config update requested: {}", new_config);
+    // Changes weight, priority or auth token of a live endpoint; routing sees it on the next request
+    pub async fn update_endpoint(&self, endpoint_id: Uuid, update: EndpointUpdate) -> Result<EndpointInfo, AppError> {
+        let mut config = self.config.write().await;
+        let (info, updated) = self.update_live_endpoint(endpoint_id, &update).await?;
+        replace_endpoint_config(&mut config.endpoints, updated);
+        Ok(info)
+    }
+
+    async fn update_live_endpoint(
+        &self,
+        endpoint_id: Uuid,
+        update: &EndpointUpdate,
+    ) -> Result<(EndpointInfo, EndpointConfig), AppError> {
+        if update.weight == Some(0) {
+            return Err(AppError::ConfigValidationError(format!("endpoint {} cannot have weight 0", endpoint_id)));
+        }
+        let mut endpoints = self.endpoints.write().await;
+        let endpoint = endpoints.get_mut(&endpoint_id)
+            .ok_or_else(|| AppError::endpoint("Endpoint not found"))?;
+
+        if let Some(auth_token) = &update.auth_token {
+            let mut config = endpoint.config.clone();
+            config.auth_token = Some(auth_token.clone()).filter(|token| !token.is_empty());
+            // The token lives in the client's default headers, so it needs a new client
+            let client = Self::create_client(&config)?;
+            endpoint.transport = Self::create_transport(&config, &client)?;
+            endpoint.client = client;
+            endpoint.config = config;
+        }
+        if let Some(weight) = update.weight {
+            endpoint.info.weight = weight;
+            endpoint.config.weight = weight;
+        }
+        if let Some(priority) = update.priority {
+            endpoint.info.priority = priority;
+            endpoint.config.priority = priority;
+        }
+
+        info!("Updated endpoint {}: weight={}, priority={}", endpoint.info.name, endpoint.info.weight, endpoint.info.priority);
+        Ok((endpoint.info.clone(), endpoint.config.clone()))
+    }
+
+    // Validates every operation before applying any, so a bad patch leaves routing untouched
+    async fn apply_endpoint_operations(&self, config: &mut Config, operations: EndpointOperations) -> Result<(), AppError> {
+        let mut known = config.endpoints.clone();
+        for endpoint_config in &operations.add {
+            validate_new_endpoint(endpoint_config, &known)?;
+            known.push(endpoint_config.clone());
+        }
+        {
+            let endpoints = self.endpoints.read().await;
+            for id in operations.remove.iter().chain(operations.update.keys()) {
+                if !endpoints.contains_key(id) {
+                    return Err(AppError::ConfigValidationError(format!("unknown endpoint id {}", id)));
+                }
+            }
+        }
+        if let Some(id) = operations.update.iter().find(|(_, update)| update.weight == Some(0)).map(|(id, _)| id) {
+            return Err(AppError::ConfigValidationError(format!("endpoint {} cannot have weight 0", id)));
+        }
+
+        for id in operations.remove {
+            let removed = self.remove_live_endpoint(id).await?;
+            config.endpoints.retain(|e| e.url != removed.url);
+        }
+        for (id, update) in operations.update {
+            // Already removed if the same patch listed it under both
+            if let Ok((_, updated)) = self.update_live_endpoint(id, &update).await {
+                replace_endpoint_config(&mut config.endpoints, updated);
+            }
+        }
+        for endpoint_config in operations.add {
+            self.insert_endpoint(endpoint_config.clone(), false).await?;
+            config.endpoints.push(endpoint_config);
+        }
         Ok(())
     }
 
-    pub async fn reload_config(&self) -> Result<(), AppError> {
+    pub async fn start_service_discovery(
+        self: Arc<Self>,
+        discovery: Arc<dyn ServiceDiscovery>,
+        refresh_interval: Duration,
+        drain_timeout: Duration,
+        shutdown: CancellationToken,
+    ) {
+        info!("Starting {} service discovery every {}s", discovery.name(), refresh_interval.as_secs());
+        let mut interval = interval(refresh_interval);
+
+        loop {
+            tokio::select! {
+                _ = shutdown.cancelled() => break,
+                _ = interval.tick() => {}
+            }
+
+            // A registry that can't be reached says nothing about the endpoints, so keep them
+            match discovery.discover().await {
+                Ok(discovered) if discovered.is_empty() => {
+                    warn!("{} service discovery returned no endpoints, keeping the current list", discovery.name());
+                }
+                Ok(discovered) => {
+                    self.reconcile_service_endpoints(discovered, drain_timeout).await;
+                }
+                Err(e) => warn!("{} service discovery failed: {}", discovery.name(), e),
+            }
+        }
+
+        info!("Service discovery stopped");
+    }
+
+    // Brings the service discovery endpoints in line with `discovered`, matching them by URL.
+    // New ones are added, missing ones drained and removed after `drain_timeout`, and the rest
+    // left alone. Returns how many were added and how many were drained.
+    pub async fn reconcile_service_endpoints(
+        self: &Arc<Self>,
+        discovered: Vec<EndpointConfig>,
+        drain_timeout: Duration,
+    ) -> (usize, usize) {
+        let normalize = |url: &str| url.trim_end_matches('/').to_lowercase();
+        let mut service_endpoints = self.service_endpoints.write().await;
+        let mut added = 0;
+        let mut removed = 0;
+
+        let wanted: HashMap<String, EndpointConfig> = discovered.into_iter()
+            .map(|config| (normalize(&config.url), config))
+            .collect();
+
+        for (url, config) in wanted.iter() {
+            if let Some(&id) = service_endpoints.get(url) {
+                // Came back before it finished draining
+                let mut endpoints = self.endpoints.write().await;
+                if let Some(endpoint) = endpoints.get_mut(&id).filter(|e| e.draining) {
+                    info!("Endpoint {} is back in the service registry, no longer draining", endpoint.info.name);
+                    endpoint.draining = false;
+                }
+                continue;
+            }
+
+            let configured = self.endpoints.read().await.values().any(|e| normalize(&e.info.url) == *url);
+            if configured {
+                debug!("Discovered endpoint {} is already configured, leaving it alone", config.url);
+                continue;
+            }
+
+            match self.insert_endpoint(config.clone(), false).await {
+                Ok(id) => {
+                    service_endpoints.insert(url.clone(), id);
+                    added += 1;
+                }
+                Err(e) => warn!("Failed to add discovered endpoint {}: {}", config.url, e),
+            }
+        }
+
+        let mut endpoints = self.endpoints.write().await;
+        for (url, &id) in service_endpoints.iter().filter(|(url, _)| !wanted.contains_key(*url)) {
+            let Some(endpoint) = endpoints.get_mut(&id).filter(|e| !e.draining) else {
+                continue;
+            };
+            info!("Endpoint {} ({}) left the service registry, draining for {}s",
+                endpoint.info.name, url, drain_timeout.as_secs());
+            endpoint.draining = true;
+            removed += 1;
+
+            let manager = self.clone();
+            tokio::spawn(async move {
+                tokio::time::sleep(drain_timeout).await;
+                manager.remove_drained_endpoint(id).await;
+            });
+        }
+        drop(endpoints);
+
+        if let Some(metrics) = &self.metrics_service {
+            metrics.record_service_discovery_changes(added, removed);
+        }
+        if added > 0 || removed > 0 {
+            info!("Service discovery: {} endpoints added, {} draining", added, removed);
+        }
+        (added, removed)
+    }
+
+    // Does nothing if the endpoint reappeared in the registry while it was draining
+    async fn remove_drained_endpoint(&self, endpoint_id: Uuid) {
+        let mut service_endpoints = self.service_endpoints.write().await;
+        let draining = self.endpoints.read().await.get(&endpoint_id).is_some_and(|e| e.draining);
+        if !draining {
+            return;
+        }
+        service_endpoints.retain(|_, id| *id != endpoint_id);
+        if let Err(e) = self.remove_endpoint(endpoint_id).await {
+            warn!("Failed to remove drained endpoint {}: {}", endpoint_id, e);
+        }
+    }
+
+    // Applies `patch` to the current config as a JSON merge patch and returns what changed.
+    // An `endpoints` object rather than a list adds, removes (by id) and updates live endpoints:
+    // {"endpoints": {"add": [...], "remove": ["<id>"], "update": {"<id>": {"weight": 50}}}}
+    pub async fn update_config(&self, mut patch: Value) -> Result<Vec<ConfigChange>, AppError> {
+        let operations = match patch.get("endpoints") {
+            Some(Value::Object(_)) => patch.as_object_mut().and_then(|patch| patch.remove("endpoints")),
+            _ => None,
+        };
+        let operations: Option<EndpointOperations> = operations
+            .map(serde_json::from_value)
+            .transpose()
+            .map_err(|e| AppError::ConfigValidationError(format!("Invalid endpoint changes: {}", e)))?;
+
+        let mut config = self.config.write().await;
+        let mut merged = serde_json::to_value(&*config)?;
+        merge_patch(&mut merged, &patch);
+        let mut updated: Config = serde_json::from_value(merged)
+            .map_err(|e| AppError::validation(&format!("Invalid config update: {}", e)))?;
+        if let Some(operations) = operations {
+            self.apply_endpoint_operations(&mut updated, operations).await?;
+        }
+
+        let changes = diff_configs(&config, &updated);
+        *config = updated;
+        info!("Configuration updated ({} changes)", changes.len());
+        Ok(changes)
+    }
+
+    pub async fn reload_config(&self) -> Result<Vec<ConfigChange>, AppError> {
+        let mut config = self.config.write().await;
+        let mut reloaded = config.clone();
+        reloaded.reload().await?;
+        self.reconcile_endpoints(&reloaded.endpoints).await?;
+
+        let changes = diff_configs(&config, &reloaded);
+        *config = reloaded;
+        info!("Configuration reloaded ({} changes)", changes.len());
+        Ok(changes)
+    }
+
+    // Like reload_config, but from a specific file and with no environment fallback
+    pub async fn reload_config_from(&self, path: &str) -> Result<Vec<ConfigChange>, AppError> {
+        let new_config = Config::load_from(path).await?;
         let mut config = self.config.write().await;
-        config.reload().await?;
-        info!("Configuration reloaded");
+        self.reconcile_endpoints(&new_config.endpoints).await?;
+        let changes = diff_configs(&config, &new_config);
+        *config = new_config;
+
+        info!("Configuration reloaded from {} ({} changes)", path, changes.len());
+        Ok(changes)
+    }
+
+    // Brings the configured (not discovered) live endpoints in line with `wanted`, matched by URL.
+    // Callers hold the config lock. Requests already in flight to a removed endpoint keep their
+    // own client and finish normally; only new requests stop being routed to it.
+    async fn reconcile_endpoints(&self, wanted: &[EndpointConfig]) -> Result<(), AppError> {
+        let live: Vec<(Uuid, EndpointConfig)> = self.endpoints.read().await
+            .iter()
+            .filter(|(_, endpoint)| !endpoint.discovered)
+            .map(|(id, endpoint)| (*id, endpoint.config.clone()))
+            .collect();
+        let added: Vec<&EndpointConfig> = wanted.iter()
+            .filter(|config| !live.iter().any(|(_, current)| current.url == config.url))
+            .collect();
+
+        // Build the new clients before touching anything so a bad TLS file rejects the whole reload
+        for config in &added {
+            let client = Self::create_client(config)?;
+            Self::create_transport(config, &client)?;
+        }
+
+        for (id, current) in &live {
+            let Some(config) = wanted.iter().find(|config| config.url == current.url) else {
+                // Already gone if a drain finished in the meantime
+                let _ = self.remove_live_endpoint(*id).await;
+                continue;
+            };
+            let update = EndpointUpdate {
+                weight: (config.weight != current.weight).then_some(config.weight),
+                priority: (config.priority != current.priority).then_some(config.priority),
+                auth_token: (config.auth_token != current.auth_token)
+                    .then(|| config.auth_token.clone().unwrap_or_default()),
+            };
+            if update.weight.is_some() || update.priority.is_some() || update.auth_token.is_some() {
+                self.update_live_endpoint(*id, &update).await?;
+            }
+        }
+        for config in added {
+            self.insert_endpoint(config.clone(), false).await?;
+        }
         Ok(())
     }
 
@@ -832,6 +2050,35 @@ impl EndpointManager {
         })
     }
 
+    pub async fn current_config(&self) -> Config {
+        self.config.read().await.clone()
+    }
+
+    pub async fn export_endpoint_stats(&self) -> HashMap<String, EndpointStats> {
+        let endpoints = self.endpoints.read().await;
+        endpoints.values()
+            .map(|endpoint| (endpoint.info.url.clone(), endpoint.stats.clone()))
+            .collect()
+    }
+
+    // Routes to the imported endpoints from here on, then carries their stats over by URL
+    pub async fn import_state(&self, config: Config, stats: HashMap<String, EndpointStats>) -> Result<(), AppError> {
+        // Hold both locks so readers never see the new config with the old stats
+        let mut current_config = self.config.write().await;
+        self.reconcile_endpoints(&config.endpoints).await?;
+        let mut endpoints = self.endpoints.write().await;
+
+        for endpoint in endpoints.values_mut() {
+            if let Some(imported) = stats.get(&endpoint.info.url) {
+                endpoint.stats = imported.clone();
+            }
+        }
+
+        *current_config = config;
+        info!("Imported state for {} endpoints", stats.len());
+        Ok(())
+    }
+
     pub async fn get_discovery_stats(&self) -> Value {
         let cache = self.discovery_cache.read().await;
         
@@ -861,4 +2108,1022 @@ impl EndpointManager {
                 .count(),
         })
     }
-}
\ No newline at end of file
+}
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[tokio::test]
+    async fn test_auto_discovery_stops_on_shutdown() {
+        let mut config = Config::default();
+        config.discovery.enabled = true;
+        config.discovery.discovery_interval = 1;
+        // Nothing listens here, so each round fails fast
+        config.discovery.cluster_rpc_urls = vec![
+            "http://127.0.0.1:1".to_string(),
+            "http://127.0.0.1:2".to_string(),
+        ];
+
+        let manager = Arc::new(EndpointManager::new(Vec::new(), config, Arc::new(CircuitBreakerRegistry::default())).await.unwrap());
+        let shutdown = CancellationToken::new();
+        let handle = tokio::spawn(manager.clone().start_auto_discovery(shutdown.clone()));
+
+        tokio::time::sleep(Duration::from_millis(1500)).await;
+        shutdown.cancel();
+
+        let stopped = tokio::time::timeout(Duration::from_secs(6), handle).await;
+        assert!(matches!(stopped, Ok(Ok(()))), "discovery did not stop cleanly");
+    }
+
+    #[tokio::test]
+    async fn test_auto_discovery_adds_endpoints_from_providers() {
+        let healthy = wiremock::MockServer::start().await;
+        wiremock::Mock::given(wiremock::matchers::method("POST"))
+            .respond_with(crate::test_server::rpc_result(json!("ok")))
+            .mount(&healthy)
+            .await;
+        let broken = wiremock::MockServer::start().await;
+        wiremock::Mock::given(wiremock::matchers::method("POST"))
+            .respond_with(wiremock::ResponseTemplate::new(500))
+            .mount(&broken)
+            .await;
+
+        let mut config = Config::default();
+        config.discovery.enabled = true;
+        config.discovery.auto_add_endpoints = true;
+        config.discovery.test_methods = vec!["getHealth".to_string()];
+        config.discovery.providers = vec![crate::config::DiscoveryProviderConfig::Static {
+            urls: vec![healthy.uri(), broken.uri()],
+        }];
+
+        let manager = Arc::new(EndpointManager::new(Vec::new(), config, Arc::new(CircuitBreakerRegistry::default())).await.unwrap());
+        let shutdown = CancellationToken::new();
+        let handle = tokio::spawn(manager.clone().start_auto_discovery(shutdown.clone()));
+
+        let mut added = Vec::new();
+        for _ in 0..50 {
+            added = manager.get_endpoint_info().await.into_iter().map(|e| e.url).collect();
+            if !added.is_empty() {
+                break;
+            }
+            tokio::time::sleep(Duration::from_millis(100)).await;
+        }
+        shutdown.cancel();
+        handle.await.unwrap();
+
+        // The broken candidate fails its probe and stays out
+        assert_eq!(added, vec![healthy.uri()]);
+        assert_eq!(manager.discovery_cache.read().await.len(), 2);
+    }
+
+    #[tokio::test]
+    async fn test_health_gradient_moves_status_and_selection() {
+        let config = Config::default();
+        let manager = EndpointManager::new(config.endpoints.clone(), config, Arc::new(CircuitBreakerRegistry::default())).await.unwrap();
+        let ids: Vec<Uuid> = manager.get_endpoint_info().await.into_iter().map(|e| e.id).collect();
+        let (flaky, steady) = (ids[0], ids[1]);
+        let status = |id: Uuid| {
+            let manager = &manager;
+            async move { manager.get_endpoint_info().await.into_iter().find(|e| e.id == id).unwrap().status }
+        };
+        let latency = Duration::from_millis(20);
+
+        for _ in 0..50 {
+            manager.update_endpoint_stats(flaky, true, latency).await;
+            manager.update_endpoint_stats(steady, true, latency).await;
+        }
+        assert_eq!(status(flaky).await, EndpointStatus::Healthy);
+
+        // 3 failures in the last 50 is 6%
+        for _ in 0..3 {
+            manager.update_endpoint_stats(flaky, false, latency).await;
+        }
+        assert_eq!(status(flaky).await, EndpointStatus::Degraded);
+        let degraded_grade = manager.get_endpoint_info().await.into_iter()
+            .find(|e| e.id == flaky).unwrap().score.overall_grade;
+        assert_ne!(degraded_grade, "A+");
+
+        // Degraded endpoints stay in rotation but lose out to healthy ones
+        for _ in 0..20 {
+            assert_eq!(manager.select_by_health(Candidates::default()).await.unwrap().0, steady);
+        }
+
+        // 13 failures is 26%
+        for _ in 0..10 {
+            manager.update_endpoint_stats(flaky, false, latency).await;
+        }
+        assert_eq!(status(flaky).await, EndpointStatus::Unhealthy);
+        assert_eq!(status(steady).await, EndpointStatus::Healthy);
+    }
+
+    #[tokio::test]
+    async fn test_gradient_never_clears_a_checked_status() {
+        let config = Config::default();
+        let manager = EndpointManager::new(config.endpoints.clone(), config, Arc::new(CircuitBreakerRegistry::default())).await.unwrap();
+        let id = manager.get_endpoint_info().await[0].id;
+        let status = || async { manager.get_endpoint_info().await.into_iter().find(|e| e.id == id).unwrap().status };
+        let latency = Duration::from_millis(20);
+        for _ in 0..20 {
+            manager.update_endpoint_stats(id, true, latency).await;
+        }
+        assert_eq!(status().await, EndpointStatus::Healthy);
+
+        // A failed check, or a 429 or slot lag marking it degraded, holds until the next check
+        for checked in [EndpointStatus::Unhealthy, EndpointStatus::Degraded] {
+            manager.update_endpoint_status(id, checked.clone()).await;
+            manager.update_endpoint_stats(id, false, latency).await;
+            for _ in 0..20 {
+                manager.update_endpoint_stats(id, true, latency).await;
+            }
+            assert_eq!(status().await, checked);
+        }
+        manager.update_endpoint_status(id, EndpointStatus::Healthy).await;
+        assert_eq!(status().await, EndpointStatus::Healthy);
+
+        // And a passing check doesn't hide a failure rate the gradient calls unhealthy
+        for _ in 0..20 {
+            manager.update_endpoint_stats(id, false, latency).await;
+        }
+        manager.update_endpoint_status(id, EndpointStatus::Healthy).await;
+        assert_eq!(status().await, EndpointStatus::Unhealthy);
+    }
+
+    #[tokio::test]
+    async fn test_recent_latency_percentiles_follow_the_window() {
+        let config = Config { latency_window_size: 100, ..Config::default() };
+        let metrics = crate::test_server::shared_metrics();
+        let manager = EndpointManager::new(config.endpoints.clone(), config, Arc::new(CircuitBreakerRegistry::default())).await.unwrap()
+            .with_metrics_service(metrics.clone());
+        let info = manager.get_endpoint_info().await.remove(0);
+
+        for _ in 0..100 {
+            manager.update_endpoint_stats(info.id, true, Duration::from_millis(900)).await;
+        }
+        for ms in 1..=100 {
+            manager.update_endpoint_stats(info.id, true, Duration::from_millis(ms)).await;
+        }
+
+        // The slow responses have left the window, though they still weigh on the average
+        let score = manager.get_endpoint_info().await.into_iter().find(|e| e.id == info.id).unwrap().score;
+        assert_eq!(score.recent_latency, crate::types::LatencyPercentiles { p50: 50.0, p95: 95.0, p99: 99.0, p999: 100.0 });
+        assert!(score.avg_response_time > 400.0);
+
+        let stats = manager.get_stats().await;
+        let details = stats["endpoints"].as_array().unwrap().iter()
+            .find(|e| e["name"] == info.name.as_str()).unwrap();
+        assert_eq!(details["stats"]["recent_latency_ms"]["samples"], 100);
+        assert_eq!(details["stats"]["recent_latency_ms"]["p95"], 95.0);
+
+        let prometheus = metrics.get_prometheus_metrics().await;
+        assert!(prometheus.contains(&format!(
+            r#"multi_rpc_endpoint_latency_percentile_ms{{endpoint="{}",quantile="0.99"}} 99"#, info.name
+        )));
+    }
+
+    #[tokio::test]
+    async fn test_config_patch_changes_live_endpoints() {
+        let config = Config::default();
+        let template = config.endpoints[0].clone();
+        let manager = EndpointManager::new(config.endpoints.clone(), config, Arc::new(CircuitBreakerRegistry::default())).await.unwrap();
+        let ids: Vec<Uuid> = manager.get_endpoint_info().await.into_iter().map(|e| e.id).collect();
+        let added = EndpointConfig {
+            name: "added".to_string(),
+            url: "http://127.0.0.1:9200".to_string(),
+            ..template.clone()
+        };
+
+        manager.update_config(json!({
+            "max_retries": 5,
+            "endpoints": {
+                "add": [added],
+                "remove": [ids[0]],
+                "update": {(ids[1].to_string()): {"weight": 5, "priority": 7}},
+            },
+        })).await.unwrap();
+
+        let live = manager.get_endpoint_info().await;
+        assert_eq!(live.len(), ids.len());
+        assert!(live.iter().all(|e| e.id != ids[0]));
+        assert!(live.iter().any(|e| e.name == "added"));
+        let updated = live.iter().find(|e| e.id == ids[1]).unwrap();
+        assert_eq!((updated.weight, updated.priority), (5, 7));
+        let config = manager.current_config().await;
+        assert_eq!(config.max_retries, 5);
+        let mut configured: Vec<&str> = config.endpoints.iter().map(|e| e.name.as_str()).collect();
+        let mut routed: Vec<&str> = live.iter().map(|e| e.name.as_str()).collect();
+        configured.sort_unstable();
+        routed.sort_unstable();
+        assert_eq!(configured, routed);
+
+        // One bad operation rejects the whole patch
+        let before = manager.get_endpoint_info().await.len();
+        let rejected = manager.update_config(json!({
+            "endpoints": {
+                "add": [EndpointConfig { name: "broken".to_string(), url: "ftp://example.com".to_string(), ..template.clone() }],
+                "remove": [ids[1]],
+            },
+        })).await;
+        assert!(matches!(rejected, Err(AppError::ConfigValidationError(_))));
+        let rejected = manager.update_config(json!({"endpoints": {"update": {(ids[1].to_string()): {"weight": 0}}}})).await;
+        assert!(matches!(rejected, Err(AppError::ConfigValidationError(_))));
+        assert_eq!(manager.get_endpoint_info().await.len(), before);
+    }
+
+    #[tokio::test]
+    async fn test_health_monitoring_stops_on_shutdown() {
+        let manager = Arc::new(EndpointManager::new(Vec::new(), Config::default(), Arc::new(CircuitBreakerRegistry::default())).await.unwrap());
+        let health_service = Arc::new(crate::health::HealthService::new(manager));
+        let shutdown = CancellationToken::new();
+
+        let handle = tokio::spawn({
+            let health_service = health_service.clone();
+            let shutdown = shutdown.clone();
+            async move { health_service.start_monitoring(shutdown).await }
+        });
+
+        shutdown.cancel();
+        let stopped = tokio::time::timeout(Duration::from_secs(1), handle).await;
+        assert!(matches!(stopped, Ok(Ok(()))), "health monitoring did not stop cleanly");
+    }
+
+    #[tokio::test]
+    async fn test_freshest_data_selects_highest_slot() {
+        let mut config = Config::default();
+        let template = config.endpoints[0].clone();
+        config.endpoints = ["lagging", "freshest", "middle"].iter()
+            .enumerate()
+            .map(|(i, name)| EndpointConfig {
+                name: name.to_string(),
+                url: format!("http://127.0.0.1:{}", 9000 + i),
+                ..template.clone()
+            })
+            .collect();
+        let manager = EndpointManager::new(config.endpoints.clone(), config, Arc::new(CircuitBreakerRegistry::default())).await.unwrap();
+
+        let ids: HashMap<String, Uuid> = manager.get_endpoint_info().await
+            .into_iter()
+            .map(|e| (e.name, e.id))
+            .collect();
+        manager.slot_tracker.insert(ids["lagging"], 1_000);
+        manager.slot_tracker.insert(ids["freshest"], 1_050);
+        manager.slot_tracker.insert(ids["middle"], 1_040);
+
+        let (selected, _) = manager.select_endpoint_with_strategy(LoadBalancingStrategy::FreshestData).await.unwrap();
+        assert_eq!(selected, ids["freshest"]);
+        assert_eq!(manager.slot_lag(ids["lagging"]), Some(50));
+        assert_eq!(manager.slot_lag(ids["freshest"]), Some(0));
+
+        // An endpoint with an open breaker is skipped even if it is the freshest
+        for _ in 0..5 {
+            manager.update_endpoint_stats(ids["freshest"], false, Duration::from_millis(10)).await;
+        }
+        assert_eq!(manager.circuit_breakers.get_state(ids["freshest"]), Some(CircuitBreakerState::Open));
+        let (selected, _) = manager.select_endpoint_with_strategy(LoadBalancingStrategy::FreshestData).await.unwrap();
+        assert_eq!(selected, ids["middle"]);
+    }
+
+    #[tokio::test]
+    async fn test_freshest_data_method_strategy_routes_rpc_requests() {
+        let server = crate::test_server::TestServerBuilder::new()
+            .with_endpoint("lagging")
+            .with_endpoint("freshest")
+            .with_config(|config| {
+                config.method_strategies.insert("getProgramAccounts".to_string(), LoadBalancingStrategy::FreshestData);
+            })
+            .start()
+            .await;
+        for name in ["lagging", "freshest"] {
+            wiremock::Mock::given(wiremock::matchers::method("POST"))
+                .respond_with(crate::test_server::rpc_result(json!(name)))
+                .mount(server.endpoint_mock(name))
+                .await;
+        }
+        let manager = &server.state.endpoint_manager;
+        let ids: HashMap<String, Uuid> = manager.get_endpoint_info().await
+            .into_iter()
+            .map(|e| (e.name, e.id))
+            .collect();
+        manager.slot_tracker.insert(ids["lagging"], 1_000);
+        manager.slot_tracker.insert(ids["freshest"], 1_050);
+        let selections = || async { crate::test_server::shared_metrics().get_metrics().await["endpoints"]["freshest_data_selections"].as_u64().unwrap() };
+        let before = selections().await;
+
+        for _ in 0..4 {
+            let response: Value = server.rpc(json!({"jsonrpc": "2.0", "id": 1, "method": "getProgramAccounts", "params": ["addr"]}))
+                .await
+                .json()
+                .await
+                .unwrap();
+            assert_eq!(response["result"], "freshest");
+        }
+        assert!(selections().await >= before + 4);
+    }
+
+    #[tokio::test]
+    async fn test_power_of_two_choices_prefers_fewer_connections() {
+        let mut config = Config::default();
+        let template = config.endpoints[0].clone();
+        config.endpoints = ["busy", "idle", "fast"].iter()
+            .enumerate()
+            .map(|(i, name)| EndpointConfig {
+                name: name.to_string(),
+                url: format!("http://127.0.0.1:{}", 9100 + i),
+                ..template.clone()
+            })
+            .collect();
+        config.load_balancing_strategy = LoadBalancingStrategy::PowerOfTwoChoices;
+        let metrics = crate::test_server::shared_metrics();
+        let manager = EndpointManager::new(config.endpoints.clone(), config, Arc::new(CircuitBreakerRegistry::default())).await.unwrap()
+            .with_metrics_service(metrics.clone());
+
+        let ids: HashMap<String, Uuid> = manager.get_endpoint_info().await
+            .into_iter()
+            .map(|e| (e.name, e.id))
+            .collect();
+        {
+            let mut endpoints = manager.endpoints.write().await;
+            endpoints[&ids["busy"]].connection_pool.active_connections.store(40, Ordering::Relaxed);
+            for name in ["idle", "fast"] {
+                endpoints[&ids[name]].connection_pool.active_connections.store(3, Ordering::Relaxed);
+            }
+            endpoints.get_mut(&ids["idle"]).unwrap().stats.response_times.record(120);
+            endpoints.get_mut(&ids["fast"]).unwrap().stats.response_times.record(30);
+
+            let (busy, idle, fast) = (&endpoints[&ids["busy"]], &endpoints[&ids["idle"]], &endpoints[&ids["fast"]]);
+            assert_eq!(power_of_two_choice(busy, idle).info.id, ids["idle"]);
+            assert_eq!(power_of_two_choice(idle, busy).info.id, ids["idle"]);
+            // Equal connections fall back to the lower average response time
+            assert_eq!(power_of_two_choice(idle, fast).info.id, ids["fast"]);
+            assert_eq!(power_of_two_choice(fast, idle).info.id, ids["fast"]);
+        }
+
+        // "busy" loses to whichever endpoint it is paired with, so it is never selected
+        let before = metrics.get_metrics().await["endpoints"]["power_of_two_selections"].as_u64().unwrap();
+        let mut selected = HashMap::<Uuid, usize>::new();
+        for _ in 0..200 {
+            let (id, _) = manager.select_endpoint().await.unwrap();
+            *selected.entry(id).or_default() += 1;
+        }
+        assert!(!selected.contains_key(&ids["busy"]));
+        assert!(selected[&ids["fast"]] > selected.get(&ids["idle"]).copied().unwrap_or(0));
+        let after = metrics.get_metrics().await["endpoints"]["power_of_two_selections"].as_u64().unwrap();
+        assert!(after >= before + 200);
+
+        // A single available endpoint is returned without a comparison
+        for name in ["busy", "fast"] {
+            manager.update_endpoint_status(ids[name], EndpointStatus::Unhealthy).await;
+        }
+        let (id, _) = manager.select_endpoint().await.unwrap();
+        assert_eq!(id, ids["idle"]);
+    }
+
+    #[tokio::test]
+    async fn test_discovered_endpoint_promoted_after_sustained_success() {
+        let config_path = std::env::temp_dir()
+            .join(format!("multi-rpc-promotion-{}.toml", Uuid::new_v4()))
+            .to_string_lossy()
+            .to_string();
+        let mut config = Config::default();
+        config.discovery.promotion.config_path = config_path.clone();
+        let metrics = crate::test_server::shared_metrics();
+        let manager = EndpointManager::new(Vec::new(), config, Arc::new(CircuitBreakerRegistry::default()))
+            .await
+            .unwrap()
+            .with_metrics_service(metrics.clone());
+
+        let url = "http://127.0.0.1:9100".to_string();
+        let id = manager.insert_endpoint(EndpointConfig {
+            url: url.clone(),
+            name: "Auto-discovered-127.0.0.1:9100".to_string(),
+            weight: 50,
+            priority: 10,
+            region: None,
+            latitude: None,
+            longitude: None,
+            features: vec!["rpc".to_string()],
+            max_connections: Some(25),
+            auth_token: None,
+            tls_cert_path: None,
+            tls_key_path: None,
+            tls_ca_path: None,
+            health_status_codes: default_health_status_codes(),
+            health_response_key: None,
+            kind: EndpointKind::Http,
+            signing: None,
+            tags: Vec::new(),
+            canary: false,
+            canary_weight: 0,
+            allowed_methods: None,
+            denied_methods: None,
+            forward_headers: Vec::new(),
+        }, true).await.unwrap();
+        let promotions_before = metrics.get_metrics().await["endpoints"]["promotions"].as_u64().unwrap();
+
+        for _ in 0..5 {
+            manager.update_endpoint_stats(id, false, Duration::from_millis(10)).await;
+        }
+        for _ in 0..999 {
+            manager.update_endpoint_stats(id, true, Duration::from_millis(10)).await;
+        }
+        let info = manager.get_endpoint_info().await.remove(0);
+        assert_eq!((info.weight, info.priority), (50, 10));
+
+        // The 1000th success at 99.5% crosses the threshold
+        manager.update_endpoint_stats(id, true, Duration::from_millis(10)).await;
+        let info = manager.get_endpoint_info().await.remove(0);
+        assert_eq!((info.weight, info.priority), (80, 5));
+        let promotions_after = metrics.get_metrics().await["endpoints"]["promotions"].as_u64().unwrap();
+        assert_eq!(promotions_after, promotions_before + 1);
+
+        // Already promoted, so further successes don't promote again
+        assert!(!manager.promote_discovered_endpoint(id).await.unwrap());
+
+        let saved: Config = toml::from_str(&tokio::fs::read_to_string(&config_path).await.unwrap()).unwrap();
+        let persisted = saved.endpoints.iter().find(|e| e.url == url).expect("promoted endpoint not saved");
+        assert_eq!((persisted.weight, persisted.priority), (80, 5));
+        let _ = tokio::fs::remove_file(&config_path).await;
+    }
+
+    #[tokio::test]
+    async fn test_backpressure_signalled_when_pools_fill_up() {
+        let server = crate::test_server::TestServerBuilder::new().start().await;
+        wiremock::Mock::given(wiremock::matchers::method("POST"))
+            .respond_with(wiremock::ResponseTemplate::new(200)
+                .set_body_json(json!({"jsonrpc": "2.0", "id": 1, "result": 1})))
+            .mount(server.endpoint_mock("primary"))
+            .await;
+        let manager = server.state.endpoint_manager.clone();
+        let fill_pools = |active: u32| {
+            let manager = manager.clone();
+            async move {
+                for endpoint in manager.endpoints.write().await.values_mut() {
+                    endpoint.connection_pool.max_connections = 100;
+                    endpoint.connection_pool.active_connections.store(active, Ordering::Relaxed);
+                }
+            }
+        };
+        let signals = |signal: &'static str| async move {
+            crate::test_server::shared_metrics().get_metrics().await["rate_limiting"]["backpressure_signals"][signal]
+                .as_u64()
+                .unwrap_or(0)
+        };
+        let request = json!({"jsonrpc": "2.0", "id": 1, "method": "getSlot"});
+
+        fill_pools(50).await;
+        let response = server.rpc(request.clone()).await;
+        assert_eq!(response.status(), reqwest::StatusCode::OK);
+        assert!(!response.headers().contains_key(crate::error::X_BACKEND_PRESSURE));
+
+        let headers_before = signals("header").await;
+        fill_pools(85).await;
+        assert!((manager.pressure_factor().await - 0.85).abs() < f64::EPSILON);
+        let response = server.rpc(request.clone()).await;
+        assert_eq!(response.status(), reqwest::StatusCode::OK);
+        assert_eq!(response.headers()[crate::error::X_BACKEND_PRESSURE], "0.85");
+        assert_eq!(response.json::<Value>().await.unwrap()["result"], 1);
+        assert!(signals("header").await > headers_before);
+
+        // Served upstream, but the client is turned away so it backs off
+        let rejected_before = signals("rejected").await;
+        fill_pools(97).await;
+        let response = server.rpc(request).await;
+        assert_eq!(response.status(), reqwest::StatusCode::TOO_MANY_REQUESTS);
+        assert_eq!(response.headers()[reqwest::header::RETRY_AFTER], "2");
+        assert_eq!(response.headers()[crate::error::X_BACKEND_PRESSURE], "0.97");
+        assert_eq!(response.json::<Value>().await.unwrap()["error"]["code"], "BACKEND_PRESSURE");
+        assert!(signals("rejected").await > rejected_before);
+    }
+
+    #[tokio::test]
+    async fn test_method_strategies_override_global_strategy() {
+        let server = crate::test_server::TestServerBuilder::new()
+            .with_endpoint("fast")
+            .with_endpoint("heavy")
+            .with_config(|config| {
+                config.load_balancing_strategy = LoadBalancingStrategy::RoundRobin;
+                config.method_strategies.insert("getSlot".to_string(), LoadBalancingStrategy::LeastLatency);
+                config.method_strategies.insert("getBlock".to_string(), LoadBalancingStrategy::Weighted);
+            })
+            .start()
+            .await;
+        for name in ["fast", "heavy"] {
+            wiremock::Mock::given(wiremock::matchers::method("POST"))
+                .respond_with(wiremock::ResponseTemplate::new(200)
+                    .set_body_json(json!({"jsonrpc": "2.0", "id": 1, "result": name})))
+                .mount(server.endpoint_mock(name))
+                .await;
+        }
+        let manager = &server.state.endpoint_manager;
+        let ids: HashMap<String, Uuid> = manager.get_endpoint_info().await
+            .into_iter()
+            .map(|e| (e.name, e.id))
+            .collect();
+        {
+            // "fast" has the lower latency, "heavy" carries all the weight
+            let mut endpoints = manager.endpoints.write().await;
+            let fast = endpoints.get_mut(&ids["fast"]).unwrap();
+            fast.info.weight = 0;
+            fast.stats.response_times.record(1);
+            let heavy = endpoints.get_mut(&ids["heavy"]).unwrap();
+            for _ in 0..100 {
+                heavy.stats.response_times.record(500);
+            }
+        }
+        let served_by = |rpc_method: &'static str| {
+            let server = &server;
+            async move {
+                let body: Value = server.rpc(json!({"jsonrpc": "2.0", "id": 1, "method": rpc_method})).await
+                    .json().await.unwrap();
+                body["result"].as_str().unwrap().to_string()
+            }
+        };
+
+        for _ in 0..5 {
+            assert_eq!(served_by("getSlot").await, "fast");
+            assert_eq!(served_by("getBlock").await, "heavy");
+        }
+        // Methods without an override keep using the global round robin
+        let mut others = std::collections::HashSet::new();
+        for _ in 0..4 {
+            others.insert(served_by("getBlockHeight").await);
+        }
+        assert_eq!(others.len(), 2);
+
+        let (selected, _) = manager.select_endpoint_for_method("getSlot").await.unwrap();
+        assert_eq!(selected, ids["fast"]);
+    }
+
+    async fn tagged_manager(archive_nodes: usize, full_nodes: usize) -> (EndpointManager, Vec<Uuid>, Vec<Uuid>) {
+        let mut config = Config::default();
+        let template = config.endpoints[0].clone();
+        config.endpoints = (0..archive_nodes + full_nodes)
+            .map(|i| EndpointConfig {
+                name: format!("node-{}", i),
+                url: format!("http://node-{}.example.com", i),
+                tags: vec![if i < archive_nodes { "archive" } else { "full-node" }.to_string()],
+                ..template.clone()
+            })
+            .collect();
+        config.method_tags.insert("getBlock".to_string(), "archive".to_string());
+        let manager = EndpointManager::new(config.endpoints.clone(), config, Arc::new(CircuitBreakerRegistry::default())).await.unwrap();
+
+        let (mut archive, mut full) = (Vec::new(), Vec::new());
+        for info in manager.get_endpoint_info().await {
+            let index: usize = info.name.trim_start_matches("node-").parse().unwrap();
+            if index < archive_nodes { archive.push(info.id) } else { full.push(info.id) }
+        }
+        (manager, archive, full)
+    }
+
+    #[tokio::test]
+    async fn test_endpoint_pool_holds_tagged_endpoints() {
+        let (manager, archive, full) = tagged_manager(2, 2).await;
+
+        let pool = manager.endpoint_pool("archive").await;
+        assert_eq!(pool.tag(), "archive");
+        assert_eq!(pool.endpoint_ids().len(), 2);
+        assert!(archive.iter().all(|id| pool.contains(*id)));
+        assert!(!full.iter().any(|id| pool.contains(*id)));
+        assert!(manager.endpoint_pool("validator").await.is_empty());
+    }
+
+    #[tokio::test]
+    async fn test_tagged_methods_select_from_their_pool() {
+        let (manager, archive, _) = tagged_manager(1, 3).await;
+
+        for _ in 0..10 {
+            let (selected, _) = manager.select_endpoint_for_method("getBlock").await.unwrap();
+            assert_eq!(selected, archive[0]);
+        }
+        let (selected, _) = manager.select_endpoint_from_pool("archive").await.unwrap();
+        assert_eq!(selected, archive[0]);
+    }
+
+    #[tokio::test]
+    async fn test_pool_falls_back_to_all_endpoints() {
+        let (manager, archive, full) = tagged_manager(2, 2).await;
+
+        // Every archive node is down, so getBlock is served by whatever is left
+        for id in &archive {
+            manager.update_endpoint_status(*id, EndpointStatus::Unhealthy).await;
+        }
+        let (selected, _) = manager.select_endpoint_for_method("getBlock").await.unwrap();
+        assert!(full.contains(&selected));
+
+        // A tag no endpoint carries behaves the same way
+        let (selected, _) = manager.select_endpoint_from_pool("validator").await.unwrap();
+        assert!(full.contains(&selected));
+
+        // Nothing healthy anywhere is still an error
+        for id in &full {
+            manager.update_endpoint_status(*id, EndpointStatus::Unhealthy).await;
+        }
+        assert!(matches!(
+            manager.select_endpoint_from_pool("archive").await,
+            Err(AppError::AllEndpointsUnhealthy)
+        ));
+    }
+
+    #[tokio::test]
+    async fn test_excluded_endpoints_are_passed_over_until_none_are_left() {
+        let (manager, _, full) = tagged_manager(0, 3).await;
+
+        let (selected, _) = manager.select_endpoint_for_method_excluding("getSlot", &full[..2]).await.unwrap();
+        assert_eq!(selected, full[2]);
+
+        // With every endpoint excluded one of them is still tried
+        let (selected, _) = manager.select_endpoint_for_method_excluding("getSlot", &full).await.unwrap();
+        assert!(full.contains(&selected));
+    }
+
+    #[tokio::test]
+    async fn test_connection_guard_tracks_in_flight_requests() {
+        let config = Config { load_balancing_strategy: LoadBalancingStrategy::LeastConnections, ..Config::default() };
+        let manager = EndpointManager::new(config.endpoints.clone(), config, Arc::new(CircuitBreakerRegistry::default())).await.unwrap();
+        let active = |stats: &Value| -> Vec<u64> {
+            stats["endpoints"].as_array().unwrap().iter()
+                .map(|e| e["connection_pool"]["active_connections"].as_u64().unwrap())
+                .collect()
+        };
+
+        let (busy, _) = manager.select_endpoint().await.unwrap();
+        let first = manager.acquire_connection(busy).await.unwrap();
+        let second = manager.acquire_connection(busy).await.unwrap();
+        assert_eq!(active(&manager.get_stats().await).iter().sum::<u64>(), 2);
+        let (next, _) = manager.select_endpoint().await.unwrap();
+        assert_ne!(next, busy);
+
+        drop(first);
+        // Released even when the request errors out and unwinds
+        let _ = tokio::spawn(async move {
+            let _guard = second;
+            panic!("request failed");
+        }).await;
+        assert!(active(&manager.get_stats().await).iter().all(|&count| count == 0));
+        assert!(manager.acquire_connection(Uuid::new_v4()).await.is_none());
+    }
+
+    // Load test rather than a micro-benchmark: with every upstream equally slow, concurrent
+    // requests should end up spread evenly across the fleet
+    #[tokio::test]
+    async fn test_least_connections_balances_concurrent_load() {
+        use futures::StreamExt;
+        const REQUESTS: usize = 150;
+        let names = ["alpha", "beta", "gamma"];
+        let server = names.iter()
+            .fold(crate::test_server::TestServerBuilder::new(), |builder, name| builder.with_endpoint(name))
+            .with_config(|config| config.load_balancing_strategy = LoadBalancingStrategy::LeastConnections)
+            .start()
+            .await;
+        for name in names {
+            let reply = crate::test_server::rpc_result(json!(name));
+            wiremock::Mock::given(wiremock::matchers::method("POST"))
+                .respond_with(move |request: &wiremock::Request| {
+                    wiremock::Respond::respond(&reply, request).set_delay(Duration::from_millis(20))
+                })
+                .mount(server.endpoint_mock(name))
+                .await;
+        }
+
+        let statuses: Vec<_> = futures::stream::iter(0..REQUESTS)
+            // Distinct params, so request deduplication can't collapse them
+            .map(|i| server.rpc(json!({"jsonrpc": "2.0", "id": i, "method": "getBlock", "params": [i]})))
+            .buffer_unordered(15)
+            .map(|response| response.status())
+            .collect()
+            .await;
+        assert!(statuses.iter().all(|status| status.is_success()));
+
+        let mut served = Vec::new();
+        for name in names {
+            served.push(server.endpoint_mock(name).received_requests().await.unwrap().len());
+        }
+        let fair_share = REQUESTS / names.len();
+        for count in &served {
+            assert!(count.abs_diff(fair_share) <= fair_share / 3, "uneven split {:?}", served);
+        }
+
+        let stats = server.state.endpoint_manager.get_stats().await;
+        assert!(stats["endpoints"].as_array().unwrap().iter()
+            .all(|e| e["connection_pool"]["active_connections"] == 0));
+    }
+
+    // TLS server on localhost that only completes the handshake for clients presenting a
+    // certificate from `ca`, and answers every request with a JSON-RPC result
+    async fn mutual_tls_server(ca: &rcgen::Certificate) -> u16 {
+        use tokio::io::{AsyncReadExt, AsyncWriteExt};
+
+        let leaf = rcgen::Certificate::from_params(rcgen::CertificateParams::new(vec!["localhost".to_string()])).unwrap();
+        let mut client_roots = rustls::RootCertStore::empty();
+        client_roots.add(&rustls::Certificate(ca.serialize_der().unwrap())).unwrap();
+        let server_config = rustls::ServerConfig::builder()
+            .with_safe_defaults()
+            .with_client_cert_verifier(rustls::server::AllowAnyAuthenticatedClient::new(client_roots).boxed())
+            .with_single_cert(
+                vec![rustls::Certificate(leaf.serialize_der_with_signer(ca).unwrap())],
+                rustls::PrivateKey(leaf.serialize_private_key_der()),
+            )
+            .unwrap();
+        let acceptor = tokio_rustls::TlsAcceptor::from(Arc::new(server_config));
+
+        let listener = tokio::net::TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let port = listener.local_addr().unwrap().port();
+        tokio::spawn(async move {
+            while let Ok((tcp, _)) = listener.accept().await {
+                let acceptor = acceptor.clone();
+                tokio::spawn(async move {
+                    let Ok(mut stream) = acceptor.accept(tcp).await else { return };
+                    let mut request = vec![0u8; 8192];
+                    let _ = stream.read(&mut request).await;
+                    let body = r#"{"jsonrpc":"2.0","id":1,"result":"mtls"}"#;
+                    let response = format!(
+                        "HTTP/1.1 200 OK\r\nContent-Type: application/json\r\nContent-Length: {}\r\nConnection: close\r\n\r\n{}",
+                        body.len(), body,
+                    );
+                    let _ = stream.write_all(response.as_bytes()).await;
+                    let _ = stream.shutdown().await;
+                });
+            }
+        });
+        port
+    }
+
+    #[tokio::test]
+    async fn test_mutual_tls_client_certificate() {
+        let mut ca_params = rcgen::CertificateParams::new(Vec::new());
+        ca_params.is_ca = rcgen::IsCa::Ca(rcgen::BasicConstraints::Unconstrained);
+        let ca = rcgen::Certificate::from_params(ca_params).unwrap();
+        let client = rcgen::Certificate::from_params(rcgen::CertificateParams::new(vec!["multi-rpc".to_string()])).unwrap();
+        let port = mutual_tls_server(&ca).await;
+
+        let dir = std::env::temp_dir().join(format!("multi-rpc-mtls-{}", Uuid::new_v4()));
+        std::fs::create_dir_all(&dir).unwrap();
+        let write = |name: &str, contents: String| {
+            let path = dir.join(name);
+            std::fs::write(&path, contents).unwrap();
+            Some(path.to_string_lossy().to_string())
+        };
+        let template = EndpointConfig {
+            url: format!("https://localhost:{}", port),
+            tls_ca_path: write("ca.pem", ca.serialize_pem().unwrap()),
+            ..Config::default().endpoints[0].clone()
+        };
+        let with_identity = EndpointConfig {
+            tls_cert_path: write("client.pem", client.serialize_pem_with_signer(&ca).unwrap()),
+            tls_key_path: write("client-key.pem", client.serialize_private_key_pem()),
+            ..template.clone()
+        };
+        let call = |config: EndpointConfig| async move {
+            EndpointManager::create_client(&config).unwrap()
+                .post(&config.url)
+                .json(&json!({"jsonrpc": "2.0", "id": 1, "method": "getSlot"}))
+                .send()
+                .await
+        };
+
+        let response: Value = call(with_identity.clone()).await.unwrap().json().await.unwrap();
+        assert_eq!(response["result"], "mtls");
+        // The server refuses the handshake without a client certificate
+        assert!(call(template.clone()).await.is_err());
+
+        let invalid = [
+            EndpointConfig { tls_cert_path: write("garbage.pem", "not a certificate".to_string()), ..with_identity.clone() },
+            EndpointConfig { tls_key_path: Some(dir.join("missing.pem").to_string_lossy().to_string()), ..with_identity.clone() },
+            EndpointConfig { tls_key_path: None, ..with_identity.clone() },
+            EndpointConfig { tls_ca_path: write("empty-ca.pem", String::new()), ..template.clone() },
+        ];
+        for config in invalid {
+            assert!(matches!(EndpointManager::create_client(&config), Err(AppError::ConfigError(_))), "{:?}", config);
+        }
+        let _ = std::fs::remove_dir_all(&dir);
+    }
+
+    fn sample(id: Uuid, total_requests: u64, successful_requests: u64, p95_ms: f64) -> AdaptiveWeightSample {
+        AdaptiveWeightSample {
+            id,
+            name: id.to_string(),
+            configured_weight: 100,
+            total_requests,
+            successful_requests,
+            p95_ms,
+        }
+    }
+
+    #[test]
+    fn test_adaptive_weights_follow_errors_and_latency() {
+        let balancer = AdaptiveLoadBalancer::default();
+        let (steady, flaky, slow) = (Uuid::new_v4(), Uuid::new_v4(), Uuid::new_v4());
+        assert_eq!(balancer.weight(flaky, 100), 100.0);
+
+        // 50% errors and twice the fastest p95 both pull the target down to 25
+        balancer.recompute(&[sample(steady, 100, 100, 50.0), sample(flaky, 100, 50, 50.0), sample(slow, 100, 100, 100.0)]);
+        assert_eq!(balancer.weight(steady, 100), 100.0);
+        assert_eq!(balancer.weight(flaky, 100), 62.5);
+        assert_eq!(balancer.weight(slow, 100), 75.0);
+
+        balancer.recompute(&[sample(steady, 200, 200, 50.0), sample(flaky, 200, 100, 50.0), sample(slow, 200, 200, 100.0)]);
+        assert_eq!(balancer.weight(flaky, 100), 43.75);
+
+        // Once requests succeed again the weight climbs back toward the configured value
+        let mut previous = balancer.weight(flaky, 100);
+        for round in 3..10 {
+            balancer.recompute(&[sample(steady, round * 100, round * 100, 50.0), sample(flaky, round * 100, 100 + (round - 2) * 100, 50.0)]);
+            let weight = balancer.weight(flaky, 100);
+            assert!(weight > previous && weight <= 100.0);
+            previous = weight;
+        }
+        assert!(previous > 99.0);
+        // Endpoints missing from a round are forgotten
+        assert_eq!(balancer.weight(slow, 40), 40.0);
+    }
+
+    // Serves requests in rounds against one healthy and one half-failing endpoint and
+    // returns how many failed, recomputing adaptive weights between rounds when asked to
+    async fn weighted_failures(adaptive: bool) -> usize {
+        let mut config = Config::default();
+        config.health_gradient.enabled = false;
+        let manager = EndpointManager::new(config.endpoints.clone(), config, Arc::new(CircuitBreakerRegistry::default())).await.unwrap();
+        let flaky = manager.get_endpoint_info().await[0].id;
+        let mut flaky_requests = 0;
+        let mut failures = 0;
+
+        for _ in 0..10 {
+            for _ in 0..200 {
+                let (selected, _) = manager.select_weighted(Candidates::default()).await.unwrap();
+                let success = selected != flaky || {
+                    flaky_requests += 1;
+                    flaky_requests % 2 == 0
+                };
+                failures += usize::from(!success);
+                manager.update_endpoint_stats(selected, success, Duration::from_millis(20)).await;
+            }
+            if adaptive {
+                manager.recompute_adaptive_weights().await;
+            }
+        }
+        failures
+    }
+
+    #[tokio::test]
+    async fn test_adaptive_weights_beat_static_weights() {
+        let static_failures = weighted_failures(false).await;
+        let adaptive_failures = weighted_failures(true).await;
+
+        // Static weights keep sending the flaky endpoint about half the traffic, roughly 500 failures,
+        // while adaptive weights settle at a quarter of its configured weight within a few rounds
+        assert!(static_failures > 350, "static: {}", static_failures);
+        assert!(adaptive_failures * 4 < static_failures * 3, "adaptive: {}, static: {}", adaptive_failures, static_failures);
+    }
+
+    async fn canary_manager(canary_weight: u8) -> (EndpointManager, Uuid) {
+        let mut config = Config::default();
+        config.endpoints.truncate(2);
+        config.endpoints[1].canary = true;
+        config.endpoints[1].canary_weight = canary_weight;
+        let canary_name = config.endpoints[1].name.clone();
+        let manager = EndpointManager::new(config.endpoints.clone(), config, Arc::new(CircuitBreakerRegistry::default())).await.unwrap();
+        let canary = manager.get_endpoint_info().await.into_iter().find(|e| e.name == canary_name).unwrap().id;
+        (manager, canary)
+    }
+
+    #[test]
+    fn test_pick_canary_by_cumulative_weight() {
+        let (a, b) = (Uuid::new_v4(), Uuid::new_v4());
+        let canaries = [(a, 10), (b, 5)];
+
+        assert_eq!(pick_canary(&canaries, 0), Some(a));
+        assert_eq!(pick_canary(&canaries, 9), Some(a));
+        assert_eq!(pick_canary(&canaries, 10), Some(b));
+        assert_eq!(pick_canary(&canaries, 14), Some(b));
+        assert_eq!(pick_canary(&canaries, 15), None);
+        assert_eq!(pick_canary(&canaries, 99), None);
+        assert_eq!(pick_canary(&[(a, 100)], 99), Some(a));
+    }
+
+    #[tokio::test]
+    async fn test_canary_sampled_at_its_weight() {
+        let (manager, canary) = canary_manager(10).await;
+
+        let mut sampled = 0;
+        for _ in 0..10_000 {
+            if let Some(id) = manager.select_canary("getSlot").await {
+                assert_eq!(id, canary);
+                sampled += 1;
+            }
+        }
+        // 10% of 10,000 draws; the bounds are more than six standard deviations out
+        assert!((800..1200).contains(&sampled), "sampled {}", sampled);
+
+        for _ in 0..100 {
+            let (selected, _) = manager.select_endpoint().await.unwrap();
+            assert_ne!(selected, canary);
+        }
+    }
+
+    #[tokio::test]
+    async fn test_zero_weight_or_unhealthy_canary_is_never_sampled() {
+        let (manager, _) = canary_manager(0).await;
+        for _ in 0..1000 {
+            assert!(manager.select_canary("getSlot").await.is_none());
+        }
+
+        let (manager, canary) = canary_manager(100).await;
+        assert_eq!(manager.select_canary("getSlot").await, Some(canary));
+        manager.update_endpoint_status(canary, EndpointStatus::Unhealthy).await;
+        assert!(manager.select_canary("getSlot").await.is_none());
+    }
+
+    // Allowed and denied methods for one endpoint
+    type MethodFilter<'a> = (Option<Vec<&'a str>>, Option<Vec<&'a str>>);
+
+    async fn method_filtered_manager(filters: [MethodFilter<'_>; 2]) -> (EndpointManager, Vec<Uuid>) {
+        let mut config = Config::default();
+        config.endpoints.truncate(2);
+        config.load_balancing_strategy = LoadBalancingStrategy::RoundRobin;
+        config.method_strategies.clear();
+        config.method_tags.clear();
+        let to_strings = |methods: Option<Vec<&str>>| methods.map(|m| m.into_iter().map(str::to_string).collect());
+        for (endpoint, (allowed, denied)) in config.endpoints.iter_mut().zip(filters) {
+            endpoint.allowed_methods = to_strings(allowed);
+            endpoint.denied_methods = to_strings(denied);
+        }
+        let names: Vec<String> = config.endpoints.iter().map(|e| e.name.clone()).collect();
+        let manager = EndpointManager::new(config.endpoints.clone(), config, Arc::new(CircuitBreakerRegistry::default())).await.unwrap();
+        let info = manager.get_endpoint_info().await;
+        let ids = names.iter().map(|name| info.iter().find(|e| &e.name == name).unwrap().id).collect();
+        (manager, ids)
+    }
+
+    #[tokio::test]
+    async fn test_method_allowed_on_one_endpoint_only() {
+        let (manager, ids) = method_filtered_manager([
+            (Some(vec!["getProgramAccounts"]), None),
+            (None, Some(vec!["getProgramAccounts"])),
+        ]).await;
+
+        for _ in 0..10 {
+            let (selected, _) = manager.select_endpoint_for_method("getProgramAccounts").await.unwrap();
+            assert_eq!(selected, ids[0]);
+            let (selected, _) = manager.select_endpoint_for_method("getSlot").await.unwrap();
+            assert_eq!(selected, ids[1]);
+        }
+        assert!(manager.available_endpoint_client(ids[1], "getProgramAccounts").await.is_none());
+    }
+
+    #[tokio::test]
+    async fn test_method_denied_everywhere_is_not_available() {
+        let (manager, _) = method_filtered_manager([
+            (None, Some(vec!["sendTransaction"])),
+            (Some(vec!["getSlot"]), None),
+        ]).await;
+
+        assert!(matches!(
+            manager.select_endpoint_for_method("sendTransaction").await,
+            Err(AppError::FeatureNotAvailable)
+        ));
+        assert!(manager.select_endpoint_for_method("getSlot").await.is_ok());
+    }
+
+    #[tokio::test]
+    async fn test_empty_allowed_list_permits_all_methods() {
+        let (manager, ids) = method_filtered_manager([(Some(Vec::new()), None), (Some(Vec::new()), None)]).await;
+
+        let mut selected = std::collections::HashSet::new();
+        for _ in 0..10 {
+            selected.insert(manager.select_endpoint_for_method("getBlock").await.unwrap().0);
+        }
+        assert_eq!(selected, ids.into_iter().collect());
+    }
+
+    #[tokio::test]
+    async fn test_retry_stats_reported_and_adjusted() {
+        let mut config = Config::default();
+        config.endpoints.truncate(1);
+        let metrics = crate::test_server::shared_metrics();
+        let manager = EndpointManager::new(config.endpoints.clone(), config, Arc::new(CircuitBreakerRegistry::default()))
+            .await
+            .unwrap()
+            .with_metrics_service(metrics.clone());
+        let info = manager.get_endpoint_info().await.remove(0);
+        for _ in 0..20 {
+            manager.update_endpoint_stats(info.id, true, Duration::from_millis(5)).await;
+        }
+
+        let reports = manager.report_retry_stats().await;
+        assert_eq!(reports.len(), 1);
+        let (name, stats) = &reports[0];
+        assert_eq!(name, &info.name);
+        assert_eq!((stats.success_count, stats.failure_count), (20, 0));
+        assert_eq!(stats.max_attempts, RetryConfig::default().max_attempts);
+
+        // All successes, so the next report shows one retry fewer
+        let stats = &manager.report_retry_stats().await[0].1;
+        assert_eq!(stats.max_attempts, RetryConfig::default().max_attempts - 1);
+        assert_eq!(stats.success_count, 0);
+
+        let endpoint_stats = manager.get_stats().await;
+        assert_eq!(endpoint_stats["endpoints"][0]["adaptive_retry"]["max_attempts"], stats.max_attempts);
+        let metric = format!("adaptive_retry.{}.max_attempts", info.name);
+        assert!(metrics.get_metrics().await["custom_metrics"].get(&metric).is_some());
+    }
+}
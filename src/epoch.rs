@@ -0,0 +1,341 @@
+use crate::{
+    cache::CacheService,
+    config::EpochTrackerConfig,
+    consensus::ConsensusService,
+    endpoints::EndpointManager,
+    error::AppError,
+    events::WebhookEvent,
+    metrics::MetricsService,
+};
+use serde::Deserialize;
+use serde_json::{json, Value};
+use std::{sync::Arc, time::Duration};
+use tokio::{sync::Mutex, time::interval};
+use tokio_util::sync::CancellationToken;
+use tracing::{debug, info, warn};
+
+// Nominal Solana slot time, used to turn the slot-based poll interval into wall time
+const SLOT_DURATION: Duration = Duration::from_millis(400);
+const EPOCH_REQUEST_TIMEOUT: Duration = Duration::from_secs(5);
+
+// Methods whose answers all change at once when the epoch flips
+pub const EPOCH_BOUNDARY_METHODS: &[&str] = &["getEpochInfo", "getLeaderSchedule", "getVoteAccounts"];
+
+#[derive(Debug, Clone, Copy, PartialEq, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct EpochInfo {
+    pub epoch: u64,
+    pub slot_index: u64,
+    pub slots_in_epoch: u64,
+    pub absolute_slot: u64,
+}
+
+impl EpochInfo {
+    pub fn slots_remaining(&self) -> u64 {
+        self.slots_in_epoch.saturating_sub(self.slot_index)
+    }
+
+    pub fn next_epoch_first_slot(&self) -> u64 {
+        self.absolute_slot + self.slots_remaining()
+    }
+}
+
+#[derive(Debug, Default)]
+struct EpochState {
+    last_epoch: Option<u64>,
+    // Epoch whose upcoming boundary has already been prepared for
+    prepared_epoch: Option<u64>,
+}
+
+// Polls getEpochInfo and gets ahead of the thundering herd of cache misses at epoch boundaries
+pub struct EpochTracker {
+    endpoint_manager: Arc<EndpointManager>,
+    cache_service: Arc<CacheService>,
+    consensus_service: Arc<ConsensusService>,
+    metrics_service: Arc<MetricsService>,
+    config: EpochTrackerConfig,
+    state: Mutex<EpochState>,
+    webhook_client: reqwest::Client,
+}
+
+impl EpochTracker {
+    pub fn new(
+        endpoint_manager: Arc<EndpointManager>,
+        cache_service: Arc<CacheService>,
+        consensus_service: Arc<ConsensusService>,
+        metrics_service: Arc<MetricsService>,
+        config: EpochTrackerConfig,
+    ) -> Self {
+        Self {
+            endpoint_manager,
+            cache_service,
+            consensus_service,
+            metrics_service,
+            config,
+            state: Mutex::new(EpochState::default()),
+            webhook_client: reqwest::Client::new(),
+        }
+    }
+
+    pub async fn start(self: Arc<Self>, shutdown: CancellationToken) {
+        let poll_interval = SLOT_DURATION * self.config.poll_interval_slots.max(1) as u32;
+        info!("Starting epoch tracking every {}ms", poll_interval.as_millis());
+        let mut interval = interval(poll_interval);
+
+        loop {
+            tokio::select! {
+                _ = shutdown.cancelled() => break,
+                _ = interval.tick() => {}
+            }
+
+            tokio::select! {
+                _ = shutdown.cancelled() => break,
+                result = self.poll() => {
+                    if let Err(e) = result {
+                        debug!("Epoch poll failed: {}", e);
+                    }
+                }
+            }
+        }
+
+        info!("Epoch tracking stopped");
+    }
+
+    pub async fn poll(&self) -> Result<EpochInfo, AppError> {
+        let response = self.fetch("getEpochInfo", Value::Null).await?;
+        let info: EpochInfo = serde_json::from_value(response["result"].clone())?;
+        self.observe(info).await;
+        Ok(info)
+    }
+
+    // Returns true if this observation triggered boundary preparation
+    pub async fn observe(&self, info: EpochInfo) -> bool {
+        let mut state = self.state.lock().await;
+        let rolled_over = state.last_epoch.is_some_and(|last| info.epoch > last);
+        state.last_epoch = Some(info.epoch);
+
+        let near_boundary = info.slots_remaining() < self.config.boundary_slots
+            && state.prepared_epoch != Some(info.epoch);
+        if near_boundary {
+            state.prepared_epoch = Some(info.epoch);
+        }
+        drop(state);
+
+        if rolled_over {
+            info!("Epoch {} started, refreshing epoch-scoped cache entries", info.epoch);
+            self.refresh_epoch_data().await;
+        }
+        if near_boundary {
+            self.prepare_boundary(info).await;
+        }
+        near_boundary
+    }
+
+    async fn prepare_boundary(&self, info: EpochInfo) {
+        info!("Epoch {} ends in {} slots, preparing for epoch {}",
+            info.epoch, info.slots_remaining(), info.epoch + 1);
+        self.metrics_service.record_epoch_transition();
+
+        self.consensus_service.override_threshold(
+            EPOCH_BOUNDARY_METHODS,
+            self.config.boundary_consensus_threshold,
+            Duration::from_secs(self.config.boundary_consensus_secs),
+        );
+
+        // The leader schedule for the next epoch is already known, keyed by its first slot
+        let params = json!([info.next_epoch_first_slot()]);
+        if self.cache_service.caches("getLeaderSchedule") {
+            match self.fetch("getLeaderSchedule", params.clone()).await {
+                Ok(response) => self.cache_service.set("getLeaderSchedule", &params, &response).await,
+                Err(e) => warn!("Failed to prefetch leader schedule for epoch {}: {}", info.epoch + 1, e),
+            }
+        }
+
+        self.alert(WebhookEvent::EpochTransition {
+            current_epoch: info.epoch,
+            next_epoch: info.epoch + 1,
+            slots_remaining: info.slots_remaining(),
+            absolute_slot: info.absolute_slot,
+        });
+    }
+
+    // Overwrites cached answers from the previous epoch, which would otherwise live out their TTL
+    async fn refresh_epoch_data(&self) {
+        for method in EPOCH_BOUNDARY_METHODS.iter().filter(|method| self.cache_service.caches(method)) {
+            match self.fetch(method, Value::Null).await {
+                Ok(response) => self.cache_service.set(method, &Value::Null, &response).await,
+                Err(e) => warn!("Failed to refresh {} after epoch change: {}", method, e),
+            }
+        }
+    }
+
+    async fn fetch(&self, method: &str, params: Value) -> Result<Value, AppError> {
+        let (endpoint_id, client) = self.endpoint_manager.select_endpoint().await?;
+        let url = self.endpoint_manager.get_endpoint_url(endpoint_id).await
+            .ok_or_else(|| AppError::endpoint("Endpoint not found"))?;
+
+        let mut payload = json!({"jsonrpc": "2.0", "id": 1, "method": method});
+        if !params.is_null() {
+            payload["params"] = params;
+        }
+        let response: Value = client.post(&url)
+            .timeout(EPOCH_REQUEST_TIMEOUT)
+            .json(&payload)
+            .send()
+            .await?
+            .json()
+            .await?;
+
+        if let Some(error) = response.get("error") {
+            return Err(AppError::endpoint(&format!("{} failed on {}: {}", method, url, error)));
+        }
+        Ok(response)
+    }
+
+    fn alert(&self, event: WebhookEvent) {
+        let Some(webhook_url) = self.config.webhook_url.clone() else {
+            return;
+        };
+        let client = self.webhook_client.clone();
+
+        tokio::spawn(async move {
+            if let Err(e) = client.post(&webhook_url).json(&event).send().await {
+                warn!("Failed to deliver epoch transition webhook to {}: {}", webhook_url, e);
+            }
+        });
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_server::{shared_metrics, TestServerBuilder};
+    use wiremock::{matchers::{body_partial_json, method}, Mock, MockServer, ResponseTemplate};
+
+    const SLOTS_IN_EPOCH: u64 = 432_000;
+
+    fn epoch_info(epoch: u64, slot_index: u64) -> Value {
+        json!({
+            "epoch": epoch,
+            "slotIndex": slot_index,
+            "slotsInEpoch": SLOTS_IN_EPOCH,
+            "absoluteSlot": epoch * SLOTS_IN_EPOCH + slot_index,
+            "blockHeight": epoch * SLOTS_IN_EPOCH + slot_index,
+        })
+    }
+
+    async fn mount_result(server: &MockServer, rpc_method: &str, result: Value) {
+        Mock::given(method("POST"))
+            .and(body_partial_json(json!({"method": rpc_method})))
+            .respond_with(ResponseTemplate::new(200).set_body_json(json!({"jsonrpc": "2.0", "id": 1, "result": result})))
+            .mount(server)
+            .await;
+    }
+
+    #[tokio::test]
+    async fn test_epoch_transition_prepares_caches_and_consensus() {
+        let webhook = MockServer::start().await;
+        Mock::given(method("POST"))
+            .respond_with(ResponseTemplate::new(200))
+            .mount(&webhook)
+            .await;
+        let server = TestServerBuilder::new()
+            .with_config(|config| config.cache.enabled = true)
+            .start()
+            .await;
+        let upstream = server.endpoint_mock("primary");
+        mount_result(upstream, "getEpochInfo", epoch_info(500, SLOTS_IN_EPOCH - 300)).await;
+        mount_result(upstream, "getLeaderSchedule", json!({"validator-1": [0, 1, 2, 3]})).await;
+        mount_result(upstream, "getVoteAccounts", json!({"current": [], "delinquent": []})).await;
+
+        let state = &server.state;
+        let tracker = EpochTracker::new(
+            state.endpoint_manager.clone(),
+            state.cache_service.clone(),
+            state.consensus_service.clone(),
+            shared_metrics(),
+            EpochTrackerConfig {
+                enabled: true,
+                webhook_url: Some(webhook.uri()),
+                ..EpochTrackerConfig::default()
+            },
+        );
+        let transitions = || async { shared_metrics().get_metrics().await["endpoints"]["epoch_transitions"].as_u64().unwrap() };
+
+        // Mid-epoch observations change nothing
+        assert!(!tracker.observe(serde_json::from_value(epoch_info(500, 1_000)).unwrap()).await);
+        assert_eq!(state.consensus_service.consensus_threshold("getVoteAccounts"), 0.67);
+
+        // 300 slots before the boundary: prefetch, stricter consensus and a webhook
+        let before = transitions().await;
+        let info = tracker.poll().await.unwrap();
+        assert_eq!(info.slots_remaining(), 300);
+        assert_eq!(transitions().await, before + 1);
+        for method in EPOCH_BOUNDARY_METHODS {
+            assert_eq!(state.consensus_service.consensus_threshold(method), 0.9);
+        }
+        let next_schedule = state.cache_service.get("getLeaderSchedule", &json!([501 * SLOTS_IN_EPOCH])).await;
+        assert_eq!(next_schedule.unwrap()["result"]["validator-1"], json!([0, 1, 2, 3]));
+
+        for _ in 0..50 {
+            if !webhook.received_requests().await.unwrap().is_empty() {
+                break;
+            }
+            tokio::time::sleep(Duration::from_millis(20)).await;
+        }
+        let requests = webhook.received_requests().await.unwrap();
+        assert_eq!(requests.len(), 1);
+        let payload: Value = serde_json::from_slice(&requests[0].body).unwrap();
+        assert_eq!(payload["event"], "EpochTransition");
+        assert_eq!(payload["data"]["next_epoch"], 501);
+        assert_eq!(payload["data"]["slots_remaining"], 300);
+
+        // Each boundary is only prepared for once
+        assert!(!tracker.observe(serde_json::from_value(epoch_info(500, SLOTS_IN_EPOCH - 100)).unwrap()).await);
+
+        // Once the epoch flips, the current-epoch entries are refreshed
+        assert!(!tracker.observe(serde_json::from_value(epoch_info(501, 5)).unwrap()).await);
+        assert!(state.cache_service.get("getVoteAccounts", &Value::Null).await.is_some());
+        assert!(state.cache_service.get("getLeaderSchedule", &Value::Null).await.is_some());
+    }
+
+    #[tokio::test]
+    async fn test_boundary_methods_are_routed_through_consensus() {
+        let server = TestServerBuilder::new()
+            .with_endpoint("primary")
+            .with_endpoint("secondary")
+            .with_endpoint("tertiary")
+            .start()
+            .await;
+        for name in ["primary", "secondary", "tertiary"] {
+            mount_result(server.endpoint_mock(name), "getVoteAccounts", json!({"current": [], "delinquent": []})).await;
+        }
+        let asked = || async {
+            let mut asked = 0;
+            for name in ["primary", "secondary", "tertiary"] {
+                asked += server.endpoint_mock(name).received_requests().await.unwrap().len();
+            }
+            asked
+        };
+        let vote_accounts = json!({"jsonrpc": "2.0", "id": 1, "method": "getVoteAccounts"});
+
+        // Mid-epoch a single endpoint answers
+        assert!(server.rpc(vote_accounts.clone()).await.status().is_success());
+        assert_eq!(asked().await, 1);
+
+        let state = &server.state;
+        let tracker = EpochTracker::new(
+            state.endpoint_manager.clone(),
+            state.cache_service.clone(),
+            state.consensus_service.clone(),
+            shared_metrics(),
+            EpochTrackerConfig { enabled: true, ..EpochTrackerConfig::default() },
+        );
+        assert!(tracker.observe(serde_json::from_value(epoch_info(500, SLOTS_IN_EPOCH - 300)).unwrap()).await);
+
+        // Near the boundary the answer has to be agreed on by several endpoints
+        let response: Value = server.rpc(vote_accounts).await.json().await.unwrap();
+        assert_eq!(response["result"]["delinquent"], json!([]));
+        assert!(asked().await >= 3, "only {} upstream requests", asked().await);
+    }
+}
@@ -1,15 +1,16 @@
 use axum::{
-    http::StatusCode,
+    http::{header, HeaderValue, StatusCode},
     response::{IntoResponse, Response},
     Json,
 };
-use serde_json::json;
+use serde_json::{json, Value};
 use thiserror::Error;
-use std::fmt;
-use std::sync::Arc;
 use std::time::SystemTime;
 use tracing::{error, warn};
 
+pub const X_BACKEND_PRESSURE: &str = "x-backend-pressure";
+const BACKPRESSURE_RETRY_AFTER_SECS: u64 = 2;
+
 #[derive(Error, Debug)]
 pub enum AppError {
     #[error("Configuration error: {0}")]
@@ -36,6 +37,14 @@ pub enum AppError {
     #[error("Endpoint error: {0}")]
     EndpointError(String),
     
+    // A JSON-RPC error object returned by the upstream node, kept as sent
+    #[error("RPC error {code}: {message}")]
+    RpcError {
+        code: i64,
+        message: String,
+        data: Option<Value>,
+    },
+    
     #[error("Rate limit exceeded")]
     RateLimitExceeded,
     
@@ -127,6 +136,11 @@ pub enum AppError {
     #[error("Endpoint overloaded")]
     EndpointOverloaded,
     
+    // The request was served, but the connection pools are close enough to full that the
+    // client is told to back off instead of getting the response
+    #[error("Backend under pressure: {0:.2}")]
+    TransactionUnderPressure(f64),
+    
     #[error("Circuit breaker open")]
     CircuitBreakerOpen,
     
@@ -171,6 +185,7 @@ pub enum AppError {
 }
 
 // Error context for tracking error propagation
+#[allow(dead_code)]
 #[derive(Debug, Clone)]
 pub struct ErrorContext {
     pub request_id: String,
@@ -182,6 +197,7 @@ pub struct ErrorContext {
     pub api_key_id: Option<String>,
 }
 
+#[allow(dead_code)]
 impl ErrorContext {
     pub fn new(request_id: String) -> Self {
         Self {
@@ -222,6 +238,7 @@ impl ErrorContext {
 }
 
 // Error details for production error handling
+#[allow(dead_code)]
 #[derive(Debug)]
 pub struct DetailedError {
     pub error: AppError,
@@ -231,6 +248,7 @@ pub struct DetailedError {
     pub suggested_action: Option<String>,
 }
 
+#[allow(dead_code)]
 impl DetailedError {
     pub fn new(error: AppError, context: ErrorContext) -> Self {
         let is_retryable = error.is_retryable();
@@ -262,6 +280,7 @@ impl AppError {
             AppError::EndpointError(_) |
             AppError::AllEndpointsUnhealthy |
             AppError::EndpointOverloaded |
+            AppError::TransactionUnderPressure(_) |
             AppError::ConnectTimeout |
             AppError::ReadTimeout |
             AppError::WriteTimeout |
@@ -279,6 +298,7 @@ impl AppError {
             AppError::InvalidAuthToken => Some("Refresh your authentication token".to_string()),
             AppError::ExpiredAuthToken => Some("Renew your authentication token".to_string()),
             AppError::BulkheadFull(_) => Some("System is under heavy load, please retry later".to_string()),
+            AppError::TransactionUnderPressure(_) => Some("Slow down and retry after the Retry-After delay".to_string()),
             AppError::MaxRetriesExceeded(_) => Some("Check service status or contact support".to_string()),
             _ => None,
         }
@@ -299,6 +319,7 @@ impl AppError {
             
             // Warnings that might need investigation
             AppError::EndpointOverloaded |
+            AppError::TransactionUnderPressure(_) |
             AppError::RateLimitExceeded |
             AppError::BulkheadFull(_) => ErrorSeverity::Warning,
             
@@ -339,6 +360,7 @@ impl IntoResponse for AppError {
             // Network errors
             AppError::NetworkError(_) => (StatusCode::BAD_GATEWAY, "NETWORK_ERROR", "Network error"),
             AppError::EndpointError(_) => (StatusCode::BAD_GATEWAY, "ENDPOINT_ERROR", "Endpoint error"),
+            AppError::RpcError { .. } => (StatusCode::BAD_GATEWAY, "UPSTREAM_RPC_ERROR", "Upstream RPC error"),
             AppError::AllEndpointsUnhealthy => (StatusCode::SERVICE_UNAVAILABLE, "ALL_ENDPOINTS_UNHEALTHY", "All endpoints unhealthy"),
             AppError::RequestTimeout => (StatusCode::GATEWAY_TIMEOUT, "REQUEST_TIMEOUT", "Request timeout"),
             AppError::EndpointOverloaded => (StatusCode::SERVICE_UNAVAILABLE, "ENDPOINT_OVERLOADED", "Endpoint overloaded"),
@@ -360,6 +382,7 @@ impl IntoResponse for AppError {
             
             // Rate limiting
             AppError::RateLimitExceeded => (StatusCode::TOO_MANY_REQUESTS, "RATE_LIMIT_EXCEEDED", "Rate limit exceeded"),
+            AppError::TransactionUnderPressure(_) => (StatusCode::TOO_MANY_REQUESTS, "BACKEND_PRESSURE", "Backend under pressure"),
             
             // Cache errors
             AppError::CacheError(_) => (StatusCode::INTERNAL_SERVER_ERROR, "CACHE_ERROR", "Cache error"),
@@ -453,6 +476,9 @@ impl IntoResponse for AppError {
                 }
             }
             
+            // The upstream node's own message, nothing internal to the proxy
+            AppError::RpcError { message, .. } => Some(message.clone()),
+            
             // For network and external service errors, provide generic message
             AppError::NetworkError(_) |
             AppError::RedisError(_) |
@@ -468,13 +494,22 @@ impl IntoResponse for AppError {
                 "message": error_message,
                 "details": error_details,
                 "timestamp": chrono::Utc::now().to_rfc3339(),
-                "request_id": uuid::Uuid::new_v4().to_string(),
+                "request_id": crate::propagation::current_request_id()
+                    .unwrap_or_else(|| uuid::Uuid::new_v4().to_string()),
                 "retryable": self.is_retryable(),
                 "suggested_action": self.suggested_action(),
             }
         }));
 
-        (status, body).into_response()
+        let mut response = (status, body).into_response();
+        if let AppError::TransactionUnderPressure(pressure) = self {
+            let headers = response.headers_mut();
+            headers.insert(header::RETRY_AFTER, HeaderValue::from(BACKPRESSURE_RETRY_AFTER_SECS));
+            if let Ok(value) = HeaderValue::from_str(&format!("{:.2}", pressure)) {
+                headers.insert(X_BACKEND_PRESSURE, value);
+            }
+        }
+        response
     }
 }
 
@@ -487,6 +522,19 @@ fn get_error_tuple(error: &AppError) -> (StatusCode, &'static str, &'static str)
     }
 }
 
+// Lets `timeout(...).await?` propagate straight into handlers
+impl From<tokio::time::error::Elapsed> for AppError {
+    fn from(_: tokio::time::error::Elapsed) -> Self {
+        AppError::RequestTimeout
+    }
+}
+
+impl From<tokio::task::JoinError> for AppError {
+    fn from(error: tokio::task::JoinError) -> Self {
+        AppError::InternalError(format!("Task panicked: {}", error))
+    }
+}
+
 // Helper functions for creating specific errors
 impl AppError {
     pub fn config(msg: &str) -> Self {
@@ -501,6 +549,15 @@ impl AppError {
         AppError::EndpointError(msg.to_string())
     }
     
+    // None unless `error` is a JSON-RPC error object with an integer code
+    pub fn from_rpc_error(error: &Value) -> Option<Self> {
+        Some(AppError::RpcError {
+            code: error.get("code")?.as_i64()?,
+            message: error.get("message").and_then(Value::as_str).unwrap_or_default().to_string(),
+            data: error.get("data").cloned(),
+        })
+    }
+    
     pub fn invalid_request(msg: &str) -> Self {
         AppError::InvalidRpcRequest(msg.to_string())
     }
@@ -538,6 +595,7 @@ impl AppError {
 pub type AppResult<T> = Result<T, AppError>;
 
 // Extension trait for adding context to Results
+#[allow(dead_code)]
 pub trait ResultExt<T> {
     fn with_context(self, msg: impl Into<String>) -> AppResult<T>;
 }
@@ -551,10 +609,15 @@ impl<T> ResultExt<T> for AppResult<T> {
 #[cfg(test)]
 mod tests {
     use super::*;
+
+    // reqwest has no public constructor for its errors, so make one the way a bad URL would
+    fn network_error() -> reqwest::Error {
+        reqwest::Client::new().get("not a url").build().unwrap_err()
+    }
     
     #[test]
     fn test_error_retryability() {
-        assert!(AppError::NetworkError(reqwest::Error::new()).is_retryable());
+        assert!(AppError::NetworkError(network_error()).is_retryable());
         assert!(AppError::RequestTimeout.is_retryable());
         assert!(!AppError::InvalidCredentials.is_retryable());
         assert!(!AppError::RateLimitExceeded.is_retryable());
@@ -570,7 +633,7 @@ mod tests {
     
     #[test]
     fn test_error_context_chaining() {
-        let error = AppError::NetworkError(reqwest::Error::new())
+        let error = AppError::NetworkError(network_error())
             .with_context("Failed to connect to primary endpoint");
         
         match error {
@@ -581,4 +644,43 @@ mod tests {
             _ => panic!("Expected WithContext error"),
         }
     }
-}
\ No newline at end of file
+
+    #[tokio::test]
+    async fn test_elapsed_converts_to_request_timeout() {
+        async fn slow_call() -> AppResult<()> {
+            tokio::time::timeout(std::time::Duration::from_millis(10), std::future::pending::<()>()).await?;
+            Ok(())
+        }
+
+        assert!(matches!(slow_call().await, Err(AppError::RequestTimeout)));
+    }
+
+    #[tokio::test]
+    async fn test_join_error_converts_to_internal_error() {
+        async fn panicking_task() -> AppResult<u32> {
+            let value = tokio::spawn(async { panic!("boom") }).await?;
+            Ok(value)
+        }
+
+        match panicking_task().await {
+            Err(AppError::InternalError(msg)) => {
+                assert!(msg.starts_with("Task panicked: "));
+                assert!(msg.contains("panicked"));
+            }
+            other => panic!("Expected InternalError, got {:?}", other),
+        }
+    }
+
+    #[tokio::test]
+    async fn test_completed_tasks_propagate_values() {
+        async fn quick_task() -> AppResult<u32> {
+            let value = tokio::time::timeout(
+                std::time::Duration::from_secs(1),
+                tokio::spawn(async { 7 }),
+            ).await??;
+            Ok(value)
+        }
+
+        assert_eq!(quick_task().await.unwrap(), 7);
+    }
+}
@@ -0,0 +1,66 @@
+use crate::types::{CircuitBreakerState, EndpointStatus};
+use chrono::{DateTime, Utc};
+use serde::Serialize;
+use tokio::sync::broadcast;
+use uuid::Uuid;
+
+const EVENT_BUS_CAPACITY: usize = 1024;
+
+#[derive(Debug, Clone, Serialize)]
+#[serde(tag = "type")]
+pub enum SystemEvent {
+    CircuitBreakerStateChanged {
+        endpoint_id: Uuid,
+        from: CircuitBreakerState,
+        to: CircuitBreakerState,
+    },
+    // Only published for changes observed locally, never for ones learned from peers
+    EndpointStatusChanged {
+        endpoint_id: Uuid,
+        url: String,
+        name: String,
+        status: EndpointStatus,
+        timestamp: DateTime<Utc>,
+    },
+}
+
+// Payloads posted to operator webhooks as {"event": "<variant>", "data": {...}}
+#[derive(Debug, Clone, Serialize)]
+#[serde(tag = "event", content = "data")]
+pub enum WebhookEvent {
+    EpochTransition {
+        current_epoch: u64,
+        next_epoch: u64,
+        slots_remaining: u64,
+        absolute_slot: u64,
+    },
+}
+
+// In-process fan-out of system events to whoever is listening
+#[derive(Debug, Clone)]
+pub struct EventBus {
+    sender: broadcast::Sender<SystemEvent>,
+}
+
+impl EventBus {
+    pub fn new() -> Self {
+        let (sender, _) = broadcast::channel(EVENT_BUS_CAPACITY);
+        Self { sender }
+    }
+
+    // Returns the number of subscribers that received the event
+    pub fn publish(&self, event: SystemEvent) -> usize {
+        // A send error only means nobody is subscribed right now
+        self.sender.send(event).unwrap_or(0)
+    }
+
+    pub fn subscribe(&self) -> broadcast::Receiver<SystemEvent> {
+        self.sender.subscribe()
+    }
+}
+
+impl Default for EventBus {
+    fn default() -> Self {
+        Self::new()
+    }
+}
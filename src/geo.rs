@@ -9,10 +9,9 @@ use std::{
     collections::HashMap,
     net::IpAddr,
     sync::Arc,
-    time::Duration,
 };
 use tokio::sync::RwLock;
-use tracing::{debug, error, info, warn};
+use tracing::{debug, info, warn};
 
 #[derive(Debug, Clone)]
 pub struct GeoService {
@@ -102,7 +101,7 @@ impl GeoService {
                             .map(|s| s.to_string()),
                         region: city.subdivisions
                             .as_ref()
-                            .and_then(|subs| subs.get(0))
+                            .and_then(|subs| subs.first())
                             .and_then(|s| s.iso_code)
                             .map(|s| s.to_string()),
                         city: city.city
@@ -111,11 +110,9 @@ impl GeoService {
                             .and_then(|names| names.get("en"))
                             .map(|s| s.to_string()),
                         latitude: city.location.as_ref()
-                            .and_then(|l| l.latitude)
-                            .map(|f| f as f64),
+                            .and_then(|l| l.latitude),
                         longitude: city.location.as_ref()
-                            .and_then(|l| l.longitude)
-                            .map(|f| f as f64),
+                            .and_then(|l| l.longitude),
                         timezone: city.location.as_ref()
                             .and_then(|l| l.time_zone.as_ref())
                             .map(|s| s.to_string()),
@@ -162,7 +159,7 @@ impl GeoService {
             return endpoints
                 .into_iter()
                 .map(|endpoint| GeoSortedEndpoint {
-                    score: 100.0 - endpoint.priority as f64,
+                    score: 100.0 - endpoint.effective_priority() as f64,
                     distance_km: None,
                     latency_penalty_ms: 0.0,
                     region_weight: 1.0,
@@ -190,7 +187,7 @@ impl GeoService {
         endpoint: &EndpointInfo,
         client_location: &Option<GeoLocation>,
     ) -> GeoSortedEndpoint {
-        let mut score = 100.0 - endpoint.priority as f64; // Base score from priority
+        let mut score = 100.0 - endpoint.effective_priority() as f64; // Base score from priority
         let mut distance_km = None;
         let mut latency_penalty_ms = 0.0;
         let mut region_weight = 1.0;
@@ -305,7 +302,7 @@ impl GeoService {
         // Pre-calculate distances between all regions and endpoints
         for endpoint in endpoints {
             if let (Some(ep_lat), Some(ep_lon)) = (endpoint.latitude, endpoint.longitude) {
-                for (region, _) in &self.config.region_weights {
+                for region in self.config.region_weights.keys() {
                     // This is a simplified example - in practice you'd have
                     // a mapping of regions to coordinates
                     let region_coords = self.get_region_coordinates(region);
@@ -341,7 +338,7 @@ impl GeoService {
         let client_location = self.get_client_location(client_ip).await;
 
         if let Some(client_loc) = client_location {
-            if let (Some(client_lat), Some(client_lon)) = (client_loc.latitude, client_loc.longitude) {
+            if let (Some(_client_lat), Some(_client_lon)) = (client_loc.latitude, client_loc.longitude) {
                 let distances = self.endpoint_distances.read().await;
                 
                 for endpoint_id in endpoint_ids {
@@ -412,6 +409,10 @@ impl GeoService {
         self.config.enabled
     }
 
+    pub fn has_geoip_database(&self) -> bool {
+        self.geoip_reader.is_some()
+    }
+
     pub async fn get_client_region_preference(&self, client_ip: Option<&str>) -> Option<String> {
         if let Some(location) = self.get_client_location(client_ip).await {
             // Determine preferred region based on client location
@@ -0,0 +1,385 @@
+use crate::{
+    cache::CacheService,
+    config::CacheGossipConfig,
+    error::AppError,
+    metrics::MetricsService,
+};
+use flate2::{read::DeflateDecoder, write::DeflateEncoder, Compression};
+use hmac::{Hmac, Mac};
+use serde_json::Value;
+use sha2::Sha256;
+use std::{
+    io::{Read, Write},
+    net::SocketAddr,
+    sync::Arc,
+    time::{SystemTime, UNIX_EPOCH},
+};
+use tokio::net::UdpSocket;
+use tokio_util::sync::CancellationToken;
+use tracing::{debug, info, warn};
+
+// Keeps every packet inside a single UDP datagram
+pub const MAX_GOSSIP_PACKET_BYTES: usize = 65_000;
+// Entries this close to expiry aren't worth a round of gossip
+pub const MIN_GOSSIP_TTL_SECS: u64 = 5;
+// Guards against small packets that inflate into huge values
+const MAX_DECOMPRESSED_BYTES: u64 = 16 * 1024 * 1024;
+const VIRTUAL_NODES_PER_PEER: usize = 64;
+// key_hash, ttl_remaining_secs and sent_at_secs, 8 bytes each and big endian
+const HEADER_BYTES: usize = 24;
+// HMAC-SHA256 of header and value under the shared secret, appended to every packet
+const TAG_BYTES: usize = 32;
+// Packets sent further from our clock than this are refused, so captured ones can't be replayed
+const MAX_GOSSIP_AGE_SECS: u64 = 30;
+
+// FNV-1a, which unlike the std hasher is stable across builds and instances
+pub fn key_hash(key: &str) -> u64 {
+    key.bytes().fold(0xcbf29ce484222325, |hash, byte| {
+        (hash ^ byte as u64).wrapping_mul(0x100000001b3)
+    })
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct GossipPacket {
+    pub key_hash: u64,
+    pub value_compressed: Vec<u8>,
+    pub ttl_remaining_secs: u64,
+    pub sent_at_secs: u64,
+}
+
+impl GossipPacket {
+    pub fn new(key: &str, value: &Value, ttl_remaining_secs: u64) -> Result<Self, AppError> {
+        let mut encoder = DeflateEncoder::new(Vec::new(), Compression::fast());
+        encoder.write_all(&serde_json::to_vec(value)?)?;
+        Ok(Self {
+            key_hash: key_hash(key),
+            value_compressed: encoder.finish()?,
+            ttl_remaining_secs,
+            sent_at_secs: unix_now(),
+        })
+    }
+
+    pub fn encode(&self, secret: &[u8]) -> Vec<u8> {
+        let mut bytes = Vec::with_capacity(HEADER_BYTES + self.value_compressed.len() + TAG_BYTES);
+        bytes.extend_from_slice(&self.key_hash.to_be_bytes());
+        bytes.extend_from_slice(&self.ttl_remaining_secs.to_be_bytes());
+        bytes.extend_from_slice(&self.sent_at_secs.to_be_bytes());
+        bytes.extend_from_slice(&self.value_compressed);
+        let tag = packet_mac(secret, &bytes).finalize().into_bytes();
+        bytes.extend_from_slice(&tag);
+        bytes
+    }
+
+    // Rejects packets whose tag doesn't match before looking at anything else in them
+    pub fn decode(bytes: &[u8], secret: &[u8]) -> Result<Self, AppError> {
+        if bytes.len() < HEADER_BYTES + TAG_BYTES {
+            return Err(AppError::cache("Gossip packet too short"));
+        }
+        let (signed, tag) = bytes.split_at(bytes.len() - TAG_BYTES);
+        packet_mac(secret, signed)
+            .verify_slice(tag)
+            .map_err(|_| AppError::cache("Gossip packet failed authentication"))?;
+
+        let (header, value_compressed) = signed.split_at(HEADER_BYTES);
+        let field = |index: usize| {
+            u64::from_be_bytes(header[index * 8..(index + 1) * 8].try_into().expect("header holds 8 byte fields"))
+        };
+        Ok(Self {
+            key_hash: field(0),
+            value_compressed: value_compressed.to_vec(),
+            ttl_remaining_secs: field(1),
+            sent_at_secs: field(2),
+        })
+    }
+
+    pub fn is_fresh(&self) -> bool {
+        self.sent_at_secs.abs_diff(unix_now()) <= MAX_GOSSIP_AGE_SECS
+    }
+
+    pub fn value(&self) -> Result<Value, AppError> {
+        let mut json = Vec::new();
+        DeflateDecoder::new(self.value_compressed.as_slice())
+            .take(MAX_DECOMPRESSED_BYTES)
+            .read_to_end(&mut json)?;
+        Ok(serde_json::from_slice(&json)?)
+    }
+}
+
+fn packet_mac(secret: &[u8], bytes: &[u8]) -> Hmac<Sha256> {
+    let mut mac = Hmac::<Sha256>::new_from_slice(secret).expect("HMAC accepts keys of any length");
+    mac.update(bytes);
+    mac
+}
+
+fn unix_now() -> u64 {
+    SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map(|elapsed| elapsed.as_secs())
+        .unwrap_or_default()
+}
+
+// Consistent hash ring over the peers so each key is always sent to the same few instances
+#[derive(Debug, Clone, Default)]
+struct HashRing {
+    nodes: Vec<(u64, SocketAddr)>,
+}
+
+impl HashRing {
+    fn new(peers: &[SocketAddr]) -> Self {
+        let mut nodes: Vec<_> = peers.iter()
+            .flat_map(|peer| {
+                (0..VIRTUAL_NODES_PER_PEER).map(move |i| (key_hash(&format!("{}#{}", peer, i)), *peer))
+            })
+            .collect();
+        nodes.sort_unstable();
+        Self { nodes }
+    }
+
+    // The first `count` distinct peers clockwise from the key's position
+    fn owners(&self, key_hash: u64, count: usize) -> Vec<SocketAddr> {
+        let start = self.nodes.partition_point(|(hash, _)| *hash < key_hash);
+        let mut owners: Vec<SocketAddr> = Vec::with_capacity(count);
+
+        for (_, peer) in self.nodes.iter().cycle().skip(start).take(self.nodes.len()) {
+            if owners.len() == count {
+                break;
+            }
+            if !owners.contains(peer) {
+                owners.push(*peer);
+            }
+        }
+        owners
+    }
+}
+
+// Gossips freshly computed cache entries to peer instances so they can answer from L1
+// instead of making their own Redis round-trip. Packets are authenticated with the shared
+// secret, since the source address of a UDP packet proves nothing.
+#[derive(Debug)]
+pub struct CacheGossipService {
+    pub peers: Vec<SocketAddr>,
+    pub bind_addr: SocketAddr,
+    socket: UdpSocket,
+    shared_secret: Vec<u8>,
+    ring: HashRing,
+    fanout: usize,
+    metrics_service: Arc<MetricsService>,
+}
+
+impl CacheGossipService {
+    pub async fn bind(config: &CacheGossipConfig, metrics_service: Arc<MetricsService>) -> Result<Self, AppError> {
+        let socket = UdpSocket::bind(&config.bind_address).await
+            .map_err(|e| AppError::config(&format!("Failed to bind cache gossip socket {}: {}", config.bind_address, e)))?;
+        let bind_addr = socket.local_addr()?;
+
+        let mut peers = Vec::new();
+        for peer in &config.peers {
+            let addr = tokio::net::lookup_host(peer).await
+                .ok()
+                .and_then(|mut addrs| addrs.next())
+                .ok_or_else(|| AppError::config(&format!("Cannot resolve cache gossip peer {}", peer)))?;
+            peers.push(addr);
+        }
+
+        info!("Cache gossip listening on {} with {} peers", bind_addr, peers.len());
+        Ok(Self {
+            ring: HashRing::new(&peers),
+            peers,
+            bind_addr,
+            socket,
+            shared_secret: config.shared_secret.as_bytes().to_vec(),
+            fanout: config.fanout.max(1),
+            metrics_service,
+        })
+    }
+
+    // Sends the entry to its owners on the ring. Failures only cost the peers a cache miss.
+    pub async fn publish(&self, key: &str, value: &Value, ttl_remaining_secs: u64) {
+        if self.peers.is_empty() || ttl_remaining_secs < MIN_GOSSIP_TTL_SECS {
+            return;
+        }
+
+        let packet = match GossipPacket::new(key, value, ttl_remaining_secs) {
+            Ok(packet) => packet.encode(&self.shared_secret),
+            Err(e) => {
+                warn!("Failed to encode cache gossip for {}: {}", key, e);
+                return;
+            }
+        };
+        if packet.len() > MAX_GOSSIP_PACKET_BYTES {
+            debug!("Skipping cache gossip for {}: {} bytes compressed", key, packet.len());
+            return;
+        }
+
+        for peer in self.ring.owners(key_hash(key), self.fanout) {
+            match self.socket.send_to(&packet, peer).await {
+                Ok(sent) => self.metrics_service.record_gossip_sent(sent),
+                Err(e) => debug!("Failed to send cache gossip to {}: {}", peer, e),
+            }
+        }
+    }
+
+    pub async fn start(self: Arc<Self>, cache: Arc<CacheService>, shutdown: CancellationToken) {
+        let mut buf = vec![0u8; u16::MAX as usize];
+
+        loop {
+            let (len, from) = tokio::select! {
+                _ = shutdown.cancelled() => break,
+                received = self.socket.recv_from(&mut buf) => match received {
+                    Ok(received) => received,
+                    Err(e) => {
+                        warn!("Cache gossip receive error: {}", e);
+                        continue;
+                    }
+                },
+            };
+
+            // Only configured peers may write into our cache
+            if !self.peers.contains(&from) {
+                debug!("Ignoring cache gossip from unknown sender {}", from);
+                continue;
+            }
+
+            let applied = GossipPacket::decode(&buf[..len], &self.shared_secret)
+                .and_then(|packet| match packet.is_fresh() {
+                    true => Ok(packet),
+                    false => Err(AppError::cache("Gossip packet is too old")),
+                })
+                .and_then(|packet| Ok((packet.key_hash, packet.value()?, packet.ttl_remaining_secs)));
+            match applied {
+                Ok((key_hash, value, ttl_remaining_secs)) => {
+                    self.metrics_service.record_gossip_received();
+                    cache.store_gossiped(key_hash, value, ttl_remaining_secs).await;
+                }
+                Err(e) => warn!("Invalid cache gossip packet from {}: {}", from, e),
+            }
+        }
+
+        info!("Cache gossip stopped");
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{config::Config, test_server::shared_metrics};
+    use serde_json::json;
+    use std::time::Duration;
+
+    const SECRET: &[u8] = b"cache-gossip-secret-shared-by-tests";
+
+    fn peer(port: u16) -> SocketAddr {
+        SocketAddr::from(([127, 0, 0, 1], port))
+    }
+
+    #[test]
+    fn test_packet_round_trip() {
+        let value = json!({"jsonrpc": "2.0", "id": 1, "result": {"value": "x".repeat(4096)}});
+        let packet = GossipPacket::new("multi-rpc:getAccountInfo:[\"A\"]", &value, 30).unwrap();
+        // Repetitive JSON compresses well below its raw size
+        assert!(packet.value_compressed.len() < 4096);
+
+        let decoded = GossipPacket::decode(&packet.encode(SECRET), SECRET).unwrap();
+        assert_eq!(decoded, packet);
+        assert!(decoded.is_fresh());
+        assert_eq!(decoded.value().unwrap(), value);
+        assert!(GossipPacket::decode(&[0u8; 4], SECRET).is_err());
+    }
+
+    #[test]
+    fn test_forged_packets_are_rejected() {
+        let packet = GossipPacket::new("multi-rpc:getSlot:", &json!(1), 30).unwrap();
+        assert!(GossipPacket::decode(&packet.encode(b"some-other-secret"), SECRET).is_err());
+
+        // Any change to the header or value breaks the tag, including a longer TTL
+        let mut tampered = packet.encode(SECRET);
+        tampered[15] ^= 1;
+        assert!(GossipPacket::decode(&tampered, SECRET).is_err());
+
+        let stale = GossipPacket { sent_at_secs: unix_now() - MAX_GOSSIP_AGE_SECS - 1, ..packet };
+        assert!(!GossipPacket::decode(&stale.encode(SECRET), SECRET).unwrap().is_fresh());
+    }
+
+    #[test]
+    fn test_hash_ring_is_stable_and_distinct() {
+        let peers = vec![peer(1), peer(2), peer(3)];
+        let ring = HashRing::new(&peers);
+
+        let owners = ring.owners(key_hash("multi-rpc:getBalance:[\"A\"]"), 2);
+        assert_eq!(owners.len(), 2);
+        assert_ne!(owners[0], owners[1]);
+        assert_eq!(owners, HashRing::new(&peers).owners(key_hash("multi-rpc:getBalance:[\"A\"]"), 2));
+
+        // Asking for more owners than there are peers returns each peer once
+        assert_eq!(ring.owners(42, 10).len(), 3);
+        assert!(HashRing::default().owners(42, 2).is_empty());
+    }
+
+    async fn gossiping_cache(bind_address: &str, peers: Vec<String>) -> (Arc<CacheService>, Arc<CacheGossipService>) {
+        let mut config = Config::default();
+        config.cache.enabled = true;
+        // Nothing listens here, so only gossip can fill the peer's cache
+        config.cache.redis_url = "redis://127.0.0.1:1".to_string();
+        let gossip = Arc::new(CacheGossipService::bind(&CacheGossipConfig {
+            enabled: true,
+            bind_address: bind_address.to_string(),
+            peers,
+            fanout: 2,
+            shared_secret: String::from_utf8(SECRET.to_vec()).unwrap(),
+        }, shared_metrics()).await.unwrap());
+        let cache = Arc::new(CacheService::new(&config).await.unwrap().with_gossip(gossip.clone()));
+        (cache, gossip)
+    }
+
+    #[tokio::test]
+    async fn test_gossiped_ttl_is_bounded() {
+        let (cache, _gossip) = gossiping_cache("127.0.0.1:0", Vec::new()).await;
+        let key = cache.create_cache_key("getGenesisHash", &Value::Null);
+
+        // Too large for an Instant, which used to panic the receive task
+        cache.store_gossiped(key_hash(&key), json!("ignored"), u64::MAX).await;
+        assert_eq!(cache.get("getGenesisHash", &Value::Null).await, None);
+
+        // A year is representable, but this node only caches getGenesisHash for an hour
+        cache.store_gossiped(key_hash(&key), json!("genesis"), 365 * 24 * 3600).await;
+        assert_eq!(cache.get("getGenesisHash", &Value::Null).await, Some(json!("genesis")));
+        let entries = cache.export_entries().await;
+        assert!(entries[0].ttl_remaining_secs <= 3600, "{}", entries[0].ttl_remaining_secs);
+    }
+
+    #[tokio::test]
+    async fn test_loopback_gossip_fills_peer_cache() {
+        // Reserve B's port first so A can be pointed at it
+        let receiver = std::net::UdpSocket::bind("127.0.0.1:0").unwrap();
+        let receiver_addr = receiver.local_addr().unwrap();
+        drop(receiver);
+
+        let (cache_a, gossip_a) = gossiping_cache("127.0.0.1:0", vec![receiver_addr.to_string()]).await;
+        let (cache_b, gossip_b) = gossiping_cache(&receiver_addr.to_string(), vec![gossip_a.bind_addr.to_string()]).await;
+        let shutdown = CancellationToken::new();
+        tokio::spawn(gossip_b.start(cache_b.clone(), shutdown.clone()));
+
+        let params = json!(["A"]);
+        let response = json!({"jsonrpc": "2.0", "id": 1, "result": {"value": {"lamports": 42}}});
+        assert_eq!(cache_b.get("getAccountInfo", &params).await, None);
+        cache_a.set("getAccountInfo", &params, &response).await;
+
+        let mut gossiped = None;
+        for _ in 0..50 {
+            gossiped = cache_b.get("getAccountInfo", &params).await;
+            if gossiped.is_some() {
+                break;
+            }
+            tokio::time::sleep(Duration::from_millis(20)).await;
+        }
+        assert_eq!(gossiped, Some(response));
+
+        // Short-lived entries aren't gossiped
+        let sent_before = shared_metrics().get_metrics().await["cache"]["gossip_sent"].as_u64().unwrap();
+        gossip_a.publish("multi-rpc:getSlot:", &json!(1), MIN_GOSSIP_TTL_SECS - 1).await;
+        let sent_after = shared_metrics().get_metrics().await["cache"]["gossip_sent"].as_u64().unwrap();
+        assert_eq!(sent_before, sent_after);
+
+        shutdown.cancel();
+    }
+}
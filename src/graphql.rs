@@ -0,0 +1,386 @@
+use crate::{
+    auth::AuthContext,
+    config::EndpointConfig,
+    endpoints::EndpointUpdate,
+    error::AppError,
+    types::EndpointInfo,
+    AppState,
+};
+use async_graphql::{
+    http::GraphiQLSource, Context, EmptySubscription, InputObject, Object, Schema, SimpleObject, ID,
+};
+use axum::{
+    extract::{RawQuery, State},
+    response::Html,
+    Extension, Json,
+};
+use chrono::{DateTime, Utc};
+use serde_json::{json, Value};
+use std::sync::Arc;
+use uuid::Uuid;
+
+// The same data as /endpoints, /stats, /metrics and /health as a typed schema. The schema is
+// built once without data; each request carries the AppState and the caller's AuthContext.
+pub type MultiRpcSchema = Schema<QueryRoot, MutationRoot, EmptySubscription>;
+
+pub fn build_schema() -> MultiRpcSchema {
+    Schema::build(QueryRoot, MutationRoot, EmptySubscription).finish()
+}
+
+#[derive(Debug, SimpleObject)]
+#[graphql(name = "Endpoint")]
+pub struct GqlEndpoint {
+    pub id: ID,
+    pub name: String,
+    pub url: String,
+    pub status: String,
+    pub weight: u32,
+    pub priority: u8,
+    pub region: Option<String>,
+    pub grade: String,
+    pub success_rate: f64,
+    pub avg_response_time_ms: f64,
+    pub uptime_percentage: f64,
+    pub p99_latency_ms: f64,
+    pub last_checked: DateTime<Utc>,
+}
+
+impl From<EndpointInfo> for GqlEndpoint {
+    fn from(info: EndpointInfo) -> Self {
+        Self {
+            id: ID(info.id.to_string()),
+            name: info.name,
+            url: info.url,
+            status: format!("{:?}", info.status),
+            weight: info.weight,
+            priority: info.priority,
+            region: info.region,
+            grade: info.score.overall_grade,
+            success_rate: info.score.success_rate,
+            avg_response_time_ms: info.score.avg_response_time,
+            uptime_percentage: info.score.uptime_percentage,
+            p99_latency_ms: info.score.recent_latency.p99,
+            last_checked: info.last_checked,
+        }
+    }
+}
+
+#[derive(Debug, SimpleObject)]
+pub struct Health {
+    pub status: String,
+    pub uptime_seconds: u64,
+    pub endpoints_configured: usize,
+    pub version: String,
+}
+
+#[derive(Debug, SimpleObject)]
+pub struct Metrics {
+    pub uptime_seconds: u64,
+    pub total_requests: u64,
+    pub healthy_endpoints: i64,
+    pub total_endpoints: i64,
+    pub cache_hits: u64,
+    pub cache_misses: u64,
+    pub cache_hit_rate: f64,
+    pub errors: u64,
+    // Everything /metrics returns, for fields without a typed counterpart
+    pub raw: async_graphql::Json<Value>,
+}
+
+impl From<Value> for Metrics {
+    fn from(metrics: Value) -> Self {
+        Self {
+            uptime_seconds: metrics["uptime_seconds"].as_u64().unwrap_or_default(),
+            total_requests: metrics["requests"]["total"].as_u64().unwrap_or_default(),
+            healthy_endpoints: metrics["endpoints"]["healthy"].as_i64().unwrap_or_default(),
+            total_endpoints: metrics["endpoints"]["total"].as_i64().unwrap_or_default(),
+            cache_hits: metrics["cache"]["hits"].as_u64().unwrap_or_default(),
+            cache_misses: metrics["cache"]["misses"].as_u64().unwrap_or_default(),
+            cache_hit_rate: metrics["cache"]["hit_rate"].as_f64().unwrap_or_default(),
+            errors: metrics["errors"]["total"].as_u64().unwrap_or_default(),
+            raw: async_graphql::Json(metrics),
+        }
+    }
+}
+
+#[derive(Debug, SimpleObject)]
+pub struct RateLimitStats {
+    pub enabled: bool,
+    pub total_requests: u64,
+    pub blocked_requests: u64,
+    pub block_rate: f64,
+    pub raw: async_graphql::Json<Value>,
+}
+
+impl From<Value> for RateLimitStats {
+    fn from(stats: Value) -> Self {
+        Self {
+            enabled: stats["enabled"].as_bool().unwrap_or_default(),
+            total_requests: stats["global"]["total_requests"].as_u64().unwrap_or_default(),
+            blocked_requests: stats["global"]["blocked_requests"].as_u64().unwrap_or_default(),
+            block_rate: stats["global"]["block_rate"].as_f64().unwrap_or_default(),
+            raw: async_graphql::Json(stats),
+        }
+    }
+}
+
+#[derive(Debug, InputObject)]
+pub struct EndpointInput {
+    pub url: String,
+    pub name: String,
+    #[graphql(default = 100)]
+    pub weight: u32,
+    #[graphql(default = 1)]
+    pub priority: u8,
+    pub region: Option<String>,
+    #[graphql(default_with = "vec![\"full\".to_string()]")]
+    pub features: Vec<String>,
+    pub auth_token: Option<String>,
+}
+
+impl EndpointInput {
+    fn into_config(self) -> Result<EndpointConfig, AppError> {
+        // Through serde so every other field takes the same default as in config.toml
+        Ok(serde_json::from_value(json!({
+            "url": self.url,
+            "name": self.name,
+            "weight": self.weight,
+            "priority": self.priority,
+            "region": self.region,
+            "latitude": null,
+            "longitude": null,
+            "features": self.features,
+            "max_connections": null,
+            "auth_token": self.auth_token,
+        }))?)
+    }
+}
+
+fn state<'a>(ctx: &Context<'a>) -> async_graphql::Result<&'a Arc<AppState>> {
+    ctx.data::<Arc<AppState>>()
+}
+
+fn parse_id(id: &ID) -> async_graphql::Result<Uuid> {
+    Uuid::parse_str(id.as_str()).map_err(|_| async_graphql::Error::new(format!("Invalid endpoint id {}", id.as_str())))
+}
+
+// Mutations change the same state as the /admin routes, so they need the same rights
+fn require_admin(ctx: &Context<'_>) -> async_graphql::Result<()> {
+    let auth = ctx.data_opt::<AuthContext>();
+    if state(ctx)?.auth_service.allows_admin(auth) {
+        Ok(())
+    } else {
+        Err(AppError::AdminAccessRequired.into())
+    }
+}
+
+pub struct QueryRoot;
+
+#[Object]
+impl QueryRoot {
+    async fn endpoints(&self, ctx: &Context<'_>) -> async_graphql::Result<Vec<GqlEndpoint>> {
+        let endpoints = state(ctx)?.endpoint_manager.get_endpoint_info().await;
+        Ok(endpoints.into_iter().map(GqlEndpoint::from).collect())
+    }
+
+    async fn endpoint_by_id(&self, ctx: &Context<'_>, id: ID) -> async_graphql::Result<Option<GqlEndpoint>> {
+        let id = parse_id(&id)?;
+        let endpoints = state(ctx)?.endpoint_manager.get_endpoint_info().await;
+        Ok(endpoints.into_iter().find(|e| e.id == id).map(GqlEndpoint::from))
+    }
+
+    async fn health(&self, ctx: &Context<'_>) -> async_graphql::Result<Health> {
+        let state = state(ctx)?;
+        Ok(Health {
+            status: "healthy".to_string(),
+            uptime_seconds: state.metrics_service.get_uptime().as_secs(),
+            endpoints_configured: state.endpoint_manager.get_endpoint_info().await.len(),
+            version: env!("CARGO_PKG_VERSION").to_string(),
+        })
+    }
+
+    async fn metrics(&self, ctx: &Context<'_>) -> async_graphql::Result<Metrics> {
+        Ok(state(ctx)?.metrics_service.get_metrics().await.into())
+    }
+
+    async fn rate_limit_stats(&self, ctx: &Context<'_>) -> async_graphql::Result<RateLimitStats> {
+        Ok(state(ctx)?.rate_limit_service.get_stats().await.into())
+    }
+}
+
+pub struct MutationRoot;
+
+#[Object]
+impl MutationRoot {
+    async fn add_endpoint(&self, ctx: &Context<'_>, input: EndpointInput) -> async_graphql::Result<GqlEndpoint> {
+        require_admin(ctx)?;
+        let state = state(ctx)?;
+        let id = state.endpoint_manager.add_endpoint(input.into_config()?).await?;
+        let info = state.endpoint_manager.get_endpoint_info().await
+            .into_iter()
+            .find(|e| e.id == id)
+            .ok_or_else(|| AppError::endpoint("Endpoint not found"))?;
+        Ok(info.into())
+    }
+
+    async fn remove_endpoint(&self, ctx: &Context<'_>, id: ID) -> async_graphql::Result<bool> {
+        require_admin(ctx)?;
+        state(ctx)?.endpoint_manager.remove_endpoint(parse_id(&id)?).await?;
+        Ok(true)
+    }
+
+    async fn update_endpoint_weight(&self, ctx: &Context<'_>, id: ID, weight: u32) -> async_graphql::Result<GqlEndpoint> {
+        require_admin(ctx)?;
+        let update = EndpointUpdate {
+            weight: Some(weight),
+            ..Default::default()
+        };
+        let info = state(ctx)?.endpoint_manager.update_endpoint(parse_id(&id)?, update).await?;
+        Ok(info.into())
+    }
+}
+
+fn execute_request(
+    state: Arc<AppState>,
+    auth: Option<Extension<AuthContext>>,
+    request: async_graphql::Request,
+) -> async_graphql::Request {
+    let request = request.data(state);
+    match auth {
+        Some(Extension(context)) => request.data(context),
+        None => request,
+    }
+}
+
+pub async fn handle_graphql(
+    State(state): State<Arc<AppState>>,
+    Extension(schema): Extension<MultiRpcSchema>,
+    auth: Option<Extension<AuthContext>>,
+    Json(request): Json<async_graphql::Request>,
+) -> Json<async_graphql::Response> {
+    Json(schema.execute(execute_request(state, auth, request)).await)
+}
+
+// GET /graphql?query=...&variables=... for clients that can't POST
+pub async fn handle_graphql_get(
+    State(state): State<Arc<AppState>>,
+    Extension(schema): Extension<MultiRpcSchema>,
+    auth: Option<Extension<AuthContext>>,
+    RawQuery(query): RawQuery,
+) -> Result<Json<async_graphql::Response>, AppError> {
+    let request = async_graphql::http::parse_query_string(query.as_deref().unwrap_or_default())
+        .map_err(|e| AppError::validation(&format!("Invalid GraphQL request: {}", e)))?;
+    Ok(Json(schema.execute(execute_request(state, auth, request)).await))
+}
+
+pub async fn handle_playground() -> Html<String> {
+    Html(GraphiQLSource::build().endpoint("/graphql").finish())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_server::{TestServer, TestServerBuilder};
+
+    async fn execute(server: &TestServer, query: &str) -> async_graphql::Response {
+        build_schema()
+            .execute(async_graphql::Request::new(query).data(server.state.clone()))
+            .await
+    }
+
+    #[test]
+    fn test_schema_exposes_queries_and_mutations() {
+        let sdl = build_schema().sdl();
+        for field in [
+            "endpoints: [Endpoint!]!",
+            "endpointById(id: ID!): Endpoint",
+            "metrics: Metrics!",
+            "rateLimitStats: RateLimitStats!",
+            "addEndpoint(input: EndpointInput!): Endpoint!",
+            "removeEndpoint(id: ID!): Boolean!",
+            "updateEndpointWeight(id: ID!, weight: Int!): Endpoint!",
+        ] {
+            assert!(sdl.contains(field), "schema is missing {}", field);
+        }
+    }
+
+    #[tokio::test]
+    async fn test_endpoint_queries_resolve() {
+        let server = TestServerBuilder::new().with_endpoint("alpha").with_endpoint("beta").start().await;
+        let response = execute(&server, "{ endpoints { id name weight } }").await;
+        assert!(response.errors.is_empty(), "{:?}", response.errors);
+        let data = response.data.into_json().unwrap();
+        assert_eq!(data["endpoints"].as_array().unwrap().len(), 2);
+
+        let id = data["endpoints"][0]["id"].as_str().unwrap().to_string();
+        let name = data["endpoints"][0]["name"].clone();
+        let response = execute(&server, &format!(r#"{{ endpointById(id: "{}") {{ name }} }}"#, id)).await;
+        assert_eq!(response.data.into_json().unwrap()["endpointById"]["name"], name);
+
+        let response = execute(&server, r#"{ endpointById(id: "not-a-uuid") { name } }"#).await;
+        assert!(!response.errors.is_empty());
+    }
+
+    #[tokio::test]
+    async fn test_metrics_and_rate_limit_stats_resolve() {
+        let server = TestServerBuilder::new().start().await;
+        let response = execute(&server, "{ metrics { uptimeSeconds totalRequests } rateLimitStats { enabled blockRate } health { status } }").await;
+        assert!(response.errors.is_empty(), "{:?}", response.errors);
+        let data = response.data.into_json().unwrap();
+        assert_eq!(data["health"]["status"], "healthy");
+        assert!(data["rateLimitStats"]["blockRate"].is_number());
+    }
+
+    #[tokio::test]
+    async fn test_endpoint_mutations() {
+        let server = TestServerBuilder::new().start().await;
+        let response = execute(&server, r#"mutation {
+            addEndpoint(input: { url: "https://added.example.com", name: "added", weight: 30 }) { id weight }
+        }"#).await;
+        assert!(response.errors.is_empty(), "{:?}", response.errors);
+        let added = response.data.into_json().unwrap()["addEndpoint"].clone();
+        assert_eq!(added["weight"], 30);
+        let id = added["id"].as_str().unwrap();
+
+        let response = execute(&server, &format!(r#"mutation {{ updateEndpointWeight(id: "{}", weight: 70) {{ weight }} }}"#, id)).await;
+        assert_eq!(response.data.into_json().unwrap()["updateEndpointWeight"]["weight"], 70);
+
+        let response = execute(&server, &format!(r#"mutation {{ removeEndpoint(id: "{}") }}"#, id)).await;
+        assert_eq!(response.data.into_json().unwrap()["removeEndpoint"], true);
+        assert_eq!(server.state.endpoint_manager.get_endpoint_info().await.len(), 1);
+    }
+
+    #[tokio::test]
+    async fn test_graphql_routes_served_when_enabled() {
+        let server = TestServerBuilder::new()
+            .with_config(|config| {
+                config.graphql.enabled = true;
+                config.graphql.playground_enabled = true;
+            })
+            .start()
+            .await;
+
+        let body: Value = server.client.post(server.url("/graphql"))
+            .json(&json!({"query": "{ endpoints { name } }"}))
+            .send().await.unwrap()
+            .json().await.unwrap();
+        assert_eq!(body["data"]["endpoints"][0]["name"], "primary");
+
+        let body: Value = server.client.get(server.url("/graphql?query=%7B%20health%20%7B%20status%20%7D%20%7D"))
+            .send().await.unwrap()
+            .json().await.unwrap();
+        assert_eq!(body["data"]["health"]["status"], "healthy");
+
+        let playground = server.client.get(server.url("/graphql/playground")).send().await.unwrap();
+        assert!(playground.status().is_success());
+    }
+
+    #[tokio::test]
+    async fn test_graphql_routes_absent_when_disabled() {
+        let server = TestServerBuilder::new().start().await;
+        let response = server.client.post(server.url("/graphql"))
+            .json(&json!({"query": "{ endpoints { name } }"}))
+            .send().await.unwrap();
+        assert_eq!(response.status(), reqwest::StatusCode::NOT_FOUND);
+    }
+}
@@ -1,35 +1,282 @@
 use crate::{
+    cache::CacheService,
+    config::{default_health_status_codes, HealthConfig, HealthGradientConfig},
     endpoints::EndpointManager,
-    types::{EndpointStatus, HealthCheckResult, SystemHealth},
+    error::AppError,
+    geo::GeoService,
+    metrics::MetricsService,
+    rate_limit::RateLimitService,
+    signing::RequestSigner,
+    types::{EndpointKind, EndpointStatus, HealthCheckResult},
 };
-use chrono::Utc;
-use serde_json::json;
-use std::{sync::Arc, time::{Duration, Instant}};
-use tokio::time::{interval, sleep};
+use chrono::{DateTime, Utc};
+use reqwest::StatusCode;
+use serde::Serialize;
+use serde_json::{json, Value};
+use std::{collections::{BTreeMap, VecDeque}, sync::Arc, time::{Duration, Instant}};
+use tokio::{sync::broadcast, time::interval};
+use tokio_util::sync::CancellationToken;
 use tracing::{debug, error, info, warn};
 use uuid::Uuid;
 
+// Grades an endpoint by the failure rate over its most recent requests, so it passes through
+// Degraded before reaching Unhealthy. Runs alongside the circuit breaker rather than through it.
+#[derive(Debug, Clone)]
+pub struct HealthGradient {
+    config: HealthGradientConfig,
+}
+
+impl HealthGradient {
+    pub fn new(config: HealthGradientConfig) -> Self {
+        Self { config }
+    }
+
+    // Adds a result to `window` and returns the status it now implies, or None while the
+    // gradient is disabled or still warming up
+    pub fn record(&self, window: &mut VecDeque<bool>, success: bool) -> Option<EndpointStatus> {
+        if !self.config.enabled {
+            return None;
+        }
+        window.push_back(success);
+        while window.len() > self.config.window_size.max(1) {
+            window.pop_front();
+        }
+        if window.len() < self.config.min_samples {
+            return None;
+        }
+        Some(self.status_for(failure_rate(window)))
+    }
+
+    pub fn status_for(&self, failure_rate: f64) -> EndpointStatus {
+        if failure_rate > self.config.unhealthy_failure_rate {
+            EndpointStatus::Unhealthy
+        } else if failure_rate >= self.config.degraded_failure_rate {
+            EndpointStatus::Degraded
+        } else {
+            EndpointStatus::Healthy
+        }
+    }
+}
+
+// The worse of a health-check status and the gradient's, so a run of successful requests
+// can't clear a failed check and a passing check can't hide a rising failure rate. A
+// status nobody has reported yet defers to the other signal.
+pub fn combined_status(checked: &EndpointStatus, gradient: Option<&EndpointStatus>) -> EndpointStatus {
+    let severity = |status: &EndpointStatus| match status {
+        EndpointStatus::Unknown => 0,
+        EndpointStatus::Healthy => 1,
+        EndpointStatus::Degraded => 2,
+        EndpointStatus::Unhealthy => 3,
+    };
+    match gradient {
+        Some(gradient) if severity(gradient) > severity(checked) => gradient.clone(),
+        _ => checked.clone(),
+    }
+}
+
+pub fn failure_rate(window: &VecDeque<bool>) -> f64 {
+    if window.is_empty() {
+        return 0.0;
+    }
+    window.iter().filter(|success| !**success).count() as f64 / window.len() as f64
+}
+
+// Priority given to endpoints lagging past degraded_lag_threshold, below every up-to-date tier
+pub const DEGRADED_LAG_PRIORITY: u8 = 4;
+
+// Priority for an endpoint `lag` slots behind the freshest one, or None once it is far enough
+// behind to be degraded
+pub fn priority_for_slot_lag(lag: u64, degraded_lag_threshold: u64) -> Option<u8> {
+    match lag {
+        lag if lag > degraded_lag_threshold => None,
+        0 => Some(1),
+        1..=5 => Some(2),
+        _ => Some(3),
+    }
+}
+
+// Ordered from best to worst, so the overall status is the maximum over the components.
+// Disabled sorts first and never makes the overall status worse than healthy.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Serialize)]
+#[serde(rename_all = "lowercase")]
+pub enum ComponentStatus {
+    Disabled,
+    Healthy,
+    Degraded,
+    Unhealthy,
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct ComponentHealth {
+    pub status: ComponentStatus,
+    #[serde(flatten)]
+    pub details: BTreeMap<&'static str, Value>,
+}
+
+impl ComponentHealth {
+    pub fn new(status: ComponentStatus) -> Self {
+        Self { status, details: BTreeMap::new() }
+    }
+
+    pub fn with_detail(mut self, key: &'static str, value: impl Into<Value>) -> Self {
+        self.details.insert(key, value.into());
+        self
+    }
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct HealthReport {
+    pub status: ComponentStatus,
+    pub components: BTreeMap<&'static str, ComponentHealth>,
+}
+
+impl HealthReport {
+    pub fn new(components: BTreeMap<&'static str, ComponentHealth>) -> Self {
+        let status = components.values()
+            .map(|component| component.status)
+            .max()
+            .map_or(ComponentStatus::Healthy, |worst| worst.max(ComponentStatus::Healthy));
+        Self { status, components }
+    }
+}
+
+// How often the monitoring loop publishes an EndpointStatusEvent
+const STATUS_EVENT_INTERVAL: Duration = Duration::from_secs(5);
+const STATUS_EVENT_CAPACITY: usize = 16;
+const TOP_METHODS: usize = 10;
+
+// Snapshot pushed to the admin endpoints page over /events/endpoints
+#[derive(Debug, Clone, Serialize)]
+pub struct EndpointStatusEvent {
+    pub timestamp: DateTime<Utc>,
+    pub endpoints: Vec<EndpointStatusSummary>,
+    pub top_methods: Vec<MethodRequestCount>,
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct EndpointStatusSummary {
+    pub id: Uuid,
+    pub name: String,
+    pub url: String,
+    pub status: String,
+    pub success_rate: f64,
+    pub avg_response_time_ms: f64,
+    pub circuit_state: &'static str,
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct MethodRequestCount {
+    pub method: String,
+    pub requests: u64,
+}
+
 pub struct HealthService {
     endpoint_manager: Arc<EndpointManager>,
+    config: HealthConfig,
     start_time: Instant,
+    // Only reported on in the detailed health report
+    cache_service: Option<Arc<CacheService>>,
+    geo_service: Option<Arc<GeoService>>,
+    rate_limit_service: Option<Arc<RateLimitService>>,
+    // Source of the per-method request counts in status events
+    metrics_service: Option<Arc<MetricsService>>,
+    status_events: broadcast::Sender<EndpointStatusEvent>,
 }
 
 impl HealthService {
     pub fn new(endpoint_manager: Arc<EndpointManager>) -> Self {
         Self {
             endpoint_manager,
+            config: HealthConfig::default(),
             start_time: Instant::now(),
+            cache_service: None,
+            geo_service: None,
+            rate_limit_service: None,
+            metrics_service: None,
+            status_events: broadcast::channel(STATUS_EVENT_CAPACITY).0,
         }
     }
+
+    pub fn with_config(mut self, config: &HealthConfig) -> Self {
+        self.config = config.clone();
+        self
+    }
+
+    pub fn with_cache_service(mut self, cache_service: Arc<CacheService>) -> Self {
+        self.cache_service = Some(cache_service);
+        self
+    }
+
+    pub fn with_geo_service(mut self, geo_service: Arc<GeoService>) -> Self {
+        self.geo_service = Some(geo_service);
+        self
+    }
+
+    pub fn with_rate_limit_service(mut self, rate_limit_service: Arc<RateLimitService>) -> Self {
+        self.rate_limit_service = Some(rate_limit_service);
+        self
+    }
+
+    pub fn with_metrics_service(mut self, metrics_service: Arc<MetricsService>) -> Self {
+        self.metrics_service = Some(metrics_service);
+        self
+    }
+
+    // Events are only published while start_monitoring runs
+    pub fn subscribe_status_events(&self) -> broadcast::Receiver<EndpointStatusEvent> {
+        self.status_events.subscribe()
+    }
     
-    pub async fn start_monitoring(&self) {
+    pub async fn start_monitoring(&self, shutdown: CancellationToken) {
         info!("Starting health monitoring service");
         
         let mut interval = interval(Duration::from_secs(30));
+        let mut status_interval = tokio::time::interval(STATUS_EVENT_INTERVAL);
         
         loop {
-            interval.tick().await;
-            self.check_all_endpoints().await;
+            tokio::select! {
+                _ = shutdown.cancelled() => break,
+                _ = status_interval.tick() => {
+                    self.publish_status_event().await;
+                    continue;
+                }
+                _ = interval.tick() => {}
+            }
+
+            tokio::select! {
+                _ = shutdown.cancelled() => break,
+                _ = self.run_checks() => {}
+            }
+        }
+
+        info!("Health monitoring stopped");
+    }
+
+    // Slot lag goes last so a lagging endpoint the health check just passed is degraded again
+    async fn run_checks(&self) {
+        self.check_all_endpoints().await;
+        if self.config.slot_lag_enabled {
+            self.endpoint_manager.refresh_slots().await;
+            self.apply_slot_lag_priorities().await;
+        }
+    }
+
+    // Endpoints whose slot poll failed keep their current slot lag priority
+    pub async fn apply_slot_lag_priorities(&self) {
+        for endpoint in self.endpoint_manager.get_endpoint_info().await {
+            let Some(lag) = endpoint.slot_lag else {
+                continue;
+            };
+            match priority_for_slot_lag(lag, self.config.degraded_lag_threshold) {
+                Some(priority) => self.endpoint_manager.set_slot_lag_priority(endpoint.id, priority).await,
+                None => {
+                    self.endpoint_manager.set_slot_lag_priority(endpoint.id, DEGRADED_LAG_PRIORITY).await;
+                    if endpoint.status == EndpointStatus::Healthy {
+                        warn!("Endpoint {} is {} slots behind, marking it degraded", endpoint.name, lag);
+                        self.endpoint_manager.update_endpoint_status(endpoint.id, EndpointStatus::Degraded).await;
+                    }
+                }
+            }
         }
     }
     
@@ -58,6 +305,14 @@ impl HealthService {
         endpoint_id: Uuid,
         url: &str,
     ) -> HealthCheckResult {
+        let (kind, healthy_status_codes, response_key, signing) = endpoint_manager.get_endpoint_config(endpoint_id).await
+            .map(|config| (config.kind, config.health_status_codes, config.health_response_key, config.signing))
+            .unwrap_or_else(|| (EndpointKind::Http, default_health_status_codes(), None, None));
+        if kind == EndpointKind::Grpc {
+            return Self::check_grpc_endpoint_health(endpoint_manager, endpoint_id, url).await;
+        }
+        let response_key = response_key.as_deref().unwrap_or("result");
+        
         let start_time = Instant::now();
         let client = reqwest::Client::builder()
             .timeout(Duration::from_secs(5))
@@ -71,18 +326,26 @@ impl HealthService {
             "method": "getHealth"
         });
         
-        let result = match client.post(url).json(&health_request).send().await {
+        let request = match signing.as_ref().map(RequestSigner::new) {
+            Some(signer) => signer.sign(client.post(url), &health_request),
+            None => Ok(client.post(url).json(&health_request)),
+        };
+        let result = match request {
+            Ok(request) => request.send().await.map_err(AppError::from),
+            Err(e) => Err(e),
+        };
+        let result = match result {
             Ok(response) => {
                 let response_time = start_time.elapsed();
                 
-                match response.status().is_success() {
+                match healthy_status_codes.contains(&response.status().as_u16()) {
                     true => {
                         // Try to parse the response to ensure it's valid
                         match response.json::<serde_json::Value>().await {
                             Ok(json_response) => {
                                 debug!("Health check successful for {}: {:?}", url, json_response);
                                 
-                                let status = if json_response.get("result").is_some() {
+                                let status = if json_response.get(response_key).is_some() {
                                     EndpointStatus::Healthy
                                 } else if json_response.get("error").is_some() {
                                     EndpointStatus::Degraded
@@ -120,7 +383,13 @@ impl HealthService {
                     false => {
                         let status_code = response.status();
                         warn!("Health check HTTP error for {}: {}", url, status_code);
-                        endpoint_manager.update_endpoint_status(endpoint_id, EndpointStatus::Unhealthy).await;
+                        // A throttling node is still up, just shedding load
+                        let status = if status_code == StatusCode::TOO_MANY_REQUESTS {
+                            EndpointStatus::Degraded
+                        } else {
+                            EndpointStatus::Unhealthy
+                        };
+                        endpoint_manager.update_endpoint_status(endpoint_id, status).await;
                         endpoint_manager.update_endpoint_stats(endpoint_id, false, start_time.elapsed()).await;
                         
                         HealthCheckResult {
@@ -151,6 +420,51 @@ impl HealthService {
         result
     }
     
+    // Geyser has no getHealth, so a gRPC endpoint is healthy if it can report its slot
+    async fn check_grpc_endpoint_health(
+        endpoint_manager: &EndpointManager,
+        endpoint_id: Uuid,
+        url: &str,
+    ) -> HealthCheckResult {
+        let start_time = Instant::now();
+        let request = json!({"jsonrpc": "2.0", "id": 1, "method": "getSlot"});
+        let result = match tokio::time::timeout(Duration::from_secs(5), endpoint_manager.send_rpc_request(endpoint_id, request)).await {
+            Ok(Ok(_)) => Ok(()),
+            Ok(Err(e)) => Err(e.to_string()),
+            Err(_) => Err("Health check timed out".to_string()),
+        };
+        let response_time = start_time.elapsed();
+
+        let status = match &result {
+            Ok(()) => EndpointStatus::Healthy,
+            Err(e) => {
+                warn!("gRPC health check failed for {}: {}", url, e);
+                EndpointStatus::Unhealthy
+            }
+        };
+        endpoint_manager.update_endpoint_status(endpoint_id, status).await;
+        endpoint_manager.update_endpoint_stats(endpoint_id, result.is_ok(), response_time).await;
+
+        HealthCheckResult {
+            endpoint_id,
+            success: result.is_ok(),
+            response_time,
+            error: result.err(),
+            timestamp: Utc::now(),
+        }
+    }
+    
+    pub async fn healthy_endpoint_count(&self) -> usize {
+        self.endpoint_manager.get_endpoint_info().await.iter()
+            .filter(|e| e.status == EndpointStatus::Healthy)
+            .count()
+    }
+
+    // Ready once a health check has passed on at least one endpoint
+    pub async fn is_ready(&self) -> bool {
+        self.healthy_endpoint_count().await > 0
+    }
+    
     pub async fn get_system_health(&self) -> serde_json::Value {
         let endpoints = self.endpoint_manager.get_endpoint_info().await;
         let stats = self.endpoint_manager.get_stats().await;
@@ -188,6 +502,92 @@ impl HealthService {
         })
     }
     
+    async fn publish_status_event(&self) {
+        if self.status_events.receiver_count() == 0 {
+            return;
+        }
+        // Only fails once every subscriber has gone away in the meantime
+        let _ = self.status_events.send(self.endpoint_status_event().await);
+    }
+
+    pub async fn endpoint_status_event(&self) -> EndpointStatusEvent {
+        let mut endpoints = self.endpoint_manager.get_endpoint_info().await;
+        endpoints.sort_by(|a, b| a.name.cmp(&b.name));
+        let circuit_states = self.endpoint_manager.circuit_breaker_states().await;
+        let top_methods = self.metrics_service.as_ref()
+            .map(|metrics| metrics.top_methods(TOP_METHODS))
+            .unwrap_or_default();
+
+        EndpointStatusEvent {
+            timestamp: Utc::now(),
+            endpoints: endpoints.into_iter()
+                .map(|endpoint| EndpointStatusSummary {
+                    circuit_state: circuit_states.get(&endpoint.id).copied().unwrap_or("unknown"),
+                    id: endpoint.id,
+                    name: endpoint.name,
+                    url: endpoint.url,
+                    status: endpoint.status.to_string(),
+                    success_rate: endpoint.score.success_rate,
+                    avg_response_time_ms: endpoint.score.avg_response_time,
+                })
+                .collect(),
+            top_methods: top_methods.into_iter()
+                .map(|(method, requests)| MethodRequestCount { method, requests })
+                .collect(),
+        }
+    }
+
+    pub async fn detailed_report(&self) -> HealthReport {
+        let mut components = BTreeMap::new();
+
+        let endpoints = self.endpoint_manager.get_endpoint_info().await;
+        let healthy = endpoints.iter().filter(|e| e.status == EndpointStatus::Healthy).count();
+        let status = match healthy {
+            0 => ComponentStatus::Unhealthy,
+            healthy if healthy == endpoints.len() => ComponentStatus::Healthy,
+            _ => ComponentStatus::Degraded,
+        };
+        components.insert("endpoints", ComponentHealth::new(status)
+            .with_detail("healthy", healthy)
+            .with_detail("total", endpoints.len()));
+
+        if let Some(cache_service) = &self.cache_service {
+            let component = if cache_service.is_enabled() {
+                // Without Redis the local and disk tiers still serve, just not shared between instances
+                let redis_connected = cache_service.redis_connected().await;
+                let status = if redis_connected { ComponentStatus::Healthy } else { ComponentStatus::Degraded };
+                ComponentHealth::new(status).with_detail("redis_connected", redis_connected)
+            } else {
+                ComponentHealth::new(ComponentStatus::Disabled)
+            };
+            components.insert("cache", component);
+        }
+
+        if let Some(geo_service) = &self.geo_service {
+            let component = if geo_service.is_enabled() {
+                // Without a GeoIP database every client is routed as if its location were unknown
+                let database_loaded = geo_service.has_geoip_database();
+                let status = if database_loaded { ComponentStatus::Healthy } else { ComponentStatus::Degraded };
+                ComponentHealth::new(status).with_detail("database_loaded", database_loaded)
+            } else {
+                ComponentHealth::new(ComponentStatus::Disabled)
+            };
+            components.insert("geo", component);
+        }
+
+        if let Some(rate_limit_service) = &self.rate_limit_service {
+            let component = if rate_limit_service.is_enabled() {
+                let status = if rate_limit_service.backend_available() { ComponentStatus::Healthy } else { ComponentStatus::Degraded };
+                ComponentHealth::new(status).with_detail("backend", rate_limit_service.backend_name())
+            } else {
+                ComponentHealth::new(ComponentStatus::Disabled)
+            };
+            components.insert("rate_limiter", component);
+        }
+
+        HealthReport::new(components)
+    }
+    
     pub async fn force_health_check(&self, endpoint_id: Option<Uuid>) {
         match endpoint_id {
             Some(id) => {
@@ -200,4 +600,354 @@ impl HealthService {
             }
         }
     }
-}
\ No newline at end of file
+}
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{config::EndpointConfig, router::X_ACCEL_BUFFERING, test_server::TestServerBuilder};
+    use wiremock::{matchers::method, Mock, ResponseTemplate};
+
+    fn gradient() -> HealthGradient {
+        HealthGradient::new(HealthGradientConfig {
+            min_samples: 20,
+            ..HealthGradientConfig::default()
+        })
+    }
+
+    fn window(results: impl IntoIterator<Item = bool>) -> VecDeque<bool> {
+        results.into_iter().collect()
+    }
+
+    #[test]
+    fn test_status_at_threshold_boundaries() {
+        let gradient = gradient();
+        assert_eq!(gradient.status_for(0.0), EndpointStatus::Healthy);
+        assert_eq!(gradient.status_for(0.049), EndpointStatus::Healthy);
+        assert_eq!(gradient.status_for(0.05), EndpointStatus::Degraded);
+        assert_eq!(gradient.status_for(0.25), EndpointStatus::Degraded);
+        assert_eq!(gradient.status_for(0.251), EndpointStatus::Unhealthy);
+        assert_eq!(gradient.status_for(1.0), EndpointStatus::Unhealthy);
+    }
+
+    #[test]
+    fn test_transitions_through_degraded() {
+        let gradient = gradient();
+        let mut results = window([true; 50]);
+        assert_eq!(gradient.record(&mut results, true), Some(EndpointStatus::Healthy));
+
+        // 2 failures in the last 50 is 4%, the 3rd makes it 6%
+        let mut statuses = Vec::new();
+        for _ in 0..16 {
+            statuses.push(gradient.record(&mut results, false).unwrap());
+        }
+        assert_eq!(results.len(), 50);
+        assert_eq!(statuses[..2], vec![EndpointStatus::Healthy; 2]);
+        // Up to 12 failures, 24%, is still only Degraded
+        assert_eq!(statuses[2..12], vec![EndpointStatus::Degraded; 10]);
+        assert_eq!(statuses[12..], vec![EndpointStatus::Unhealthy; 4]);
+
+        // Recovering walks back down through Degraded as the failures age out of the window
+        let mut statuses = Vec::new();
+        for _ in 0..50 {
+            statuses.push(gradient.record(&mut results, true).unwrap());
+        }
+        let degraded_at = statuses.iter().position(|s| *s == EndpointStatus::Degraded).unwrap();
+        let healthy_at = statuses.iter().position(|s| *s == EndpointStatus::Healthy).unwrap();
+        assert!(degraded_at < healthy_at);
+        assert_eq!(failure_rate(&results), 0.0);
+    }
+
+    #[test]
+    fn test_waits_for_min_samples_and_respects_enabled() {
+        let gradient = gradient();
+        let mut results = VecDeque::new();
+        for _ in 0..19 {
+            assert_eq!(gradient.record(&mut results, false), None);
+        }
+        assert_eq!(gradient.record(&mut results, false), Some(EndpointStatus::Unhealthy));
+
+        let disabled = HealthGradient::new(HealthGradientConfig {
+            enabled: false,
+            ..HealthGradientConfig::default()
+        });
+        let mut results = VecDeque::new();
+        assert_eq!(disabled.record(&mut results, false), None);
+        assert!(results.is_empty());
+    }
+
+    // Runs one health check against an endpoint answering with `response`
+    async fn checked_status(
+        configure: impl FnOnce(&mut EndpointConfig) + Send + 'static,
+        response: ResponseTemplate,
+    ) -> EndpointStatus {
+        let server = TestServerBuilder::new().with_endpoint_config("primary", configure).start().await;
+        Mock::given(method("POST")).respond_with(response).mount(server.endpoint_mock("primary")).await;
+        let manager = &server.state.endpoint_manager;
+
+        let id = manager.get_endpoint_info().await[0].id;
+        server.state.health_service.force_health_check(Some(id)).await;
+        manager.get_endpoint_info().await[0].status.clone()
+    }
+
+    fn health_ok() -> ResponseTemplate {
+        ResponseTemplate::new(200).set_body_json(json!({"jsonrpc": "2.0", "id": 1, "result": "ok"}))
+    }
+
+    #[tokio::test]
+    async fn test_throttled_health_check_degrades_endpoint() {
+        assert_eq!(checked_status(|_| {}, health_ok()).await, EndpointStatus::Healthy);
+        assert_eq!(checked_status(|_| {}, ResponseTemplate::new(429)).await, EndpointStatus::Degraded);
+        assert_eq!(checked_status(|_| {}, ResponseTemplate::new(503)).await, EndpointStatus::Unhealthy);
+        // Only listed codes count, even within 2xx
+        assert_eq!(checked_status(|_| {}, ResponseTemplate::new(204)).await, EndpointStatus::Unhealthy);
+    }
+
+    #[tokio::test]
+    async fn test_configured_health_status_codes() {
+        let accept_202 = |endpoint: &mut EndpointConfig| endpoint.health_status_codes = vec![200, 202];
+        let accepted = ResponseTemplate::new(202).set_body_json(json!({"jsonrpc": "2.0", "id": 1, "result": "ok"}));
+        assert_eq!(checked_status(accept_202, accepted).await, EndpointStatus::Healthy);
+    }
+
+    #[tokio::test]
+    async fn test_health_response_key_must_be_present() {
+        let require_status = |endpoint: &mut EndpointConfig| endpoint.health_response_key = Some("status".to_string());
+        assert_eq!(checked_status(require_status, health_ok()).await, EndpointStatus::Unknown);
+
+        let with_status = ResponseTemplate::new(200).set_body_json(json!({"jsonrpc": "2.0", "id": 1, "status": "ok"}));
+        assert_eq!(checked_status(require_status, with_status).await, EndpointStatus::Healthy);
+
+        let overloaded = ResponseTemplate::new(200)
+            .set_body_json(json!({"jsonrpc": "2.0", "id": 1, "error": {"code": -32005, "message": "Node is behind"}}));
+        assert_eq!(checked_status(|_| {}, overloaded).await, EndpointStatus::Degraded);
+    }
+
+    #[tokio::test]
+    async fn probes_bypass_auth_and_track_endpoint_health() {
+        let server = TestServerBuilder::new()
+            .with_endpoint("primary")
+            .with_endpoint("secondary")
+            .with_config(|config| config.auth.enabled = true)
+            .start()
+            .await;
+        let probe = |path: &'static str| {
+            let request = server.client.get(server.url(path));
+            async move { request.send().await.unwrap() }
+        };
+        let ids: Vec<Uuid> = server.state.endpoint_manager.get_endpoint_info().await
+            .into_iter()
+            .map(|e| e.id)
+            .collect();
+
+        assert_eq!(probe("/health/live").await.status(), StatusCode::OK);
+        // No endpoint has passed a health check yet
+        assert_eq!(probe("/health/ready").await.status(), StatusCode::SERVICE_UNAVAILABLE);
+
+        server.state.endpoint_manager.update_endpoint_status(ids[1], EndpointStatus::Healthy).await;
+        let ready = probe("/health/ready").await;
+        assert_eq!(ready.status(), StatusCode::OK);
+        let body: serde_json::Value = ready.json().await.unwrap();
+        assert_eq!(body["healthy_endpoints"], 1);
+        assert_eq!(body["cache_initialized"], true);
+
+        for id in &ids {
+            server.state.endpoint_manager.update_endpoint_status(*id, EndpointStatus::Unhealthy).await;
+        }
+        assert!(!server.state.health_service.is_ready().await);
+        let not_ready = probe("/health/ready").await;
+        assert_eq!(not_ready.status(), StatusCode::SERVICE_UNAVAILABLE);
+        let body: serde_json::Value = not_ready.json().await.unwrap();
+        assert_eq!(body["status"], "not_ready");
+        // Liveness doesn't care about endpoints
+        assert_eq!(probe("/health/live").await.status(), StatusCode::OK);
+    }
+
+    #[test]
+    fn test_priority_tiers_by_slot_lag() {
+        assert_eq!(priority_for_slot_lag(0, 50), Some(1));
+        assert_eq!(priority_for_slot_lag(1, 50), Some(2));
+        assert_eq!(priority_for_slot_lag(5, 50), Some(2));
+        assert_eq!(priority_for_slot_lag(6, 50), Some(3));
+        assert_eq!(priority_for_slot_lag(50, 50), Some(3));
+        assert_eq!(priority_for_slot_lag(51, 50), None);
+        assert_eq!(priority_for_slot_lag(4, 3), None);
+    }
+
+    #[tokio::test]
+    async fn test_slot_lag_reprioritizes_endpoints() {
+        let slots = [("tip", 1_000), ("close", 997), ("behind", 980), ("stale", 900)];
+        let server = slots.iter()
+            .fold(TestServerBuilder::new(), |builder, (name, _)| {
+                builder.with_endpoint_config(name, |endpoint| endpoint.priority = 1)
+            })
+            .with_config(|config| config.health.slot_lag_enabled = true)
+            .start()
+            .await;
+        for (name, slot) in slots {
+            Mock::given(method("POST"))
+                .respond_with(ResponseTemplate::new(200).set_body_json(json!({"jsonrpc": "2.0", "id": 1, "result": slot})))
+                .mount(server.endpoint_mock(name))
+                .await;
+        }
+        let manager = &server.state.endpoint_manager;
+        for endpoint in manager.get_endpoint_info().await {
+            manager.update_endpoint_status(endpoint.id, EndpointStatus::Healthy).await;
+        }
+
+        let service = &server.state.health_service;
+        manager.refresh_slots().await;
+        service.apply_slot_lag_priorities().await;
+
+        let info = manager.get_endpoint_info().await;
+        let endpoint = |name: &str| info.iter().find(|e| e.name == name).unwrap().clone();
+        assert_eq!((endpoint("tip").slot_lag, endpoint("tip").effective_priority()), (Some(0), 1));
+        assert_eq!((endpoint("close").slot_lag, endpoint("close").effective_priority()), (Some(3), 2));
+        assert_eq!((endpoint("behind").slot_lag, endpoint("behind").effective_priority()), (Some(20), 3));
+        assert_eq!((endpoint("stale").slot_lag, endpoint("stale").effective_priority()), (Some(100), DEGRADED_LAG_PRIORITY));
+        assert_eq!(endpoint("stale").status, EndpointStatus::Degraded);
+        assert_eq!(endpoint("behind").status, EndpointStatus::Healthy);
+        // The configured priority is untouched
+        assert!(info.iter().all(|endpoint| endpoint.priority == 1));
+
+        // An admin change while the endpoint lags is what it returns to once it catches up
+        manager.update_endpoint(endpoint("stale").id, crate::endpoints::EndpointUpdate {
+            priority: Some(2),
+            ..Default::default()
+        }).await.unwrap();
+
+        // The stale endpoint catches up, and the others fall behind the new tip
+        server.endpoint_mock("stale").reset().await;
+        Mock::given(method("POST"))
+            .respond_with(ResponseTemplate::new(200).set_body_json(json!({"jsonrpc": "2.0", "id": 1, "result": 1_010})))
+            .mount(server.endpoint_mock("stale"))
+            .await;
+        manager.refresh_slots().await;
+        service.apply_slot_lag_priorities().await;
+
+        let info = manager.get_endpoint_info().await;
+        let priority = |name: &str| info.iter().find(|e| e.name == name).unwrap().effective_priority();
+        assert_eq!(priority("stale"), 2);
+        assert_eq!(priority("tip"), 3);
+        assert_eq!(priority("close"), 3);
+    }
+
+    #[test]
+    fn test_overall_status_is_worst_component() {
+        use ComponentStatus::*;
+        let overall = |statuses: &[ComponentStatus]| {
+            let components = ["cache", "endpoints", "geo", "rate_limiter"].into_iter()
+                .zip(statuses.iter().map(|status| ComponentHealth::new(*status)))
+                .collect();
+            HealthReport::new(components).status
+        };
+
+        assert_eq!(overall(&[Healthy, Healthy, Healthy]), Healthy);
+        assert_eq!(overall(&[Healthy, Degraded, Healthy]), Degraded);
+        assert_eq!(overall(&[Degraded, Unhealthy, Healthy, Degraded]), Unhealthy);
+        assert_eq!(overall(&[Unhealthy, Disabled]), Unhealthy);
+        // Disabled components don't count against the rest
+        assert_eq!(overall(&[Disabled, Healthy]), Healthy);
+        assert_eq!(overall(&[Disabled, Disabled]), Healthy);
+        assert_eq!(overall(&[]), Healthy);
+    }
+
+    #[tokio::test]
+    async fn test_detailed_health_reports_each_component() {
+        let server = TestServerBuilder::new()
+            .with_endpoint("primary")
+            .with_endpoint("secondary")
+            .with_config(|config| config.rate_limiting.enabled = true)
+            .start()
+            .await;
+        let manager = &server.state.endpoint_manager;
+        let primary = manager.get_endpoint_info().await.into_iter().find(|e| e.name == "primary").unwrap();
+        manager.update_endpoint_status(primary.id, EndpointStatus::Healthy).await;
+
+        let report: Value = server.client.get(server.url("/health/detailed")).send().await.unwrap()
+            .json().await.unwrap();
+        assert_eq!(report, json!({
+            "status": "degraded",
+            "components": {
+                "cache": {"status": "disabled"},
+                "endpoints": {"status": "degraded", "healthy": 1, "total": 2},
+                "geo": {"status": "disabled"},
+                "rate_limiter": {"status": "healthy", "backend": "memory"},
+            }
+        }));
+
+        for endpoint in manager.get_endpoint_info().await {
+            manager.update_endpoint_status(endpoint.id, EndpointStatus::Unhealthy).await;
+        }
+        let report = server.state.health_service.detailed_report().await;
+        assert_eq!(report.status, ComponentStatus::Unhealthy);
+        assert_eq!(report.components["endpoints"].details["healthy"], 0);
+    }
+
+    #[tokio::test]
+    async fn test_status_events_reach_subscribers() {
+        let server = TestServerBuilder::new()
+            .with_endpoint("primary")
+            .with_endpoint("secondary")
+            .start()
+            .await;
+        let health_service = server.state.health_service.clone();
+        let mut events = health_service.subscribe_status_events();
+        let shutdown = CancellationToken::new();
+        tokio::spawn({
+            let shutdown = shutdown.clone();
+            async move { health_service.start_monitoring(shutdown).await }
+        });
+
+        let event = tokio::time::timeout(Duration::from_secs(5), events.recv()).await.unwrap().unwrap();
+        let names: Vec<&str> = event.endpoints.iter().map(|endpoint| endpoint.name.as_str()).collect();
+        assert_eq!(names, ["primary", "secondary"]);
+        // No traffic has gone through the breakers yet
+        assert!(event.endpoints.iter().all(|endpoint| ["closed", "unknown"].contains(&endpoint.circuit_state)));
+        assert!(event.top_methods.len() <= TOP_METHODS);
+        assert!(event.top_methods.windows(2).all(|pair| pair[0].requests >= pair[1].requests));
+
+        // The same events are streamed to dashboard clients
+        let mut response = server.client.get(server.url("/events/endpoints")).send().await.unwrap();
+        assert_eq!(response.headers()[reqwest::header::CONTENT_TYPE], "text/event-stream");
+        let mut body = String::new();
+        while !body.contains("\n\n") {
+            let chunk = tokio::time::timeout(STATUS_EVENT_INTERVAL * 2, response.chunk()).await.unwrap().unwrap().unwrap();
+            body.push_str(std::str::from_utf8(&chunk).unwrap());
+        }
+        let data = body.lines().find_map(|line| line.strip_prefix("data: ")).unwrap();
+        let streamed: Value = serde_json::from_str(data).unwrap();
+        assert_eq!(streamed["endpoints"].as_array().unwrap().len(), 2);
+        assert!(streamed["top_methods"].is_array());
+        shutdown.cancel();
+    }
+
+    #[tokio::test]
+    async fn test_status_events_stream_through_shaping_and_body_logging() {
+        let server = TestServerBuilder::new()
+            .with_endpoint("primary")
+            .with_config(|config| {
+                config.egress_rate_limit_bps = Some(1_000_000);
+                config.body_logger.debug_request_logging = true;
+            })
+            .start()
+            .await;
+        let health_service = server.state.health_service.clone();
+        let shutdown = CancellationToken::new();
+        tokio::spawn({
+            let shutdown = shutdown.clone();
+            async move { health_service.start_monitoring(shutdown).await }
+        });
+
+        // Buffering middleware would wait for the end of a stream that never ends
+        let request = server.client.get(server.url("/events/endpoints")).send();
+        let mut response = tokio::time::timeout(STATUS_EVENT_INTERVAL * 2, request).await.unwrap().unwrap();
+        assert_eq!(response.headers()[X_ACCEL_BUFFERING], "no");
+        let mut body = String::new();
+        while !body.contains("\n\n") {
+            let chunk = tokio::time::timeout(STATUS_EVENT_INTERVAL * 2, response.chunk()).await.unwrap().unwrap().unwrap();
+            body.push_str(std::str::from_utf8(&chunk).unwrap());
+        }
+        assert!(body.lines().any(|line| line.starts_with("data: ")));
+        shutdown.cancel();
+    }
+}
@@ -0,0 +1,180 @@
+use base64::{engine::general_purpose::STANDARD as BASE64, Engine};
+use hdrhistogram::{
+    serialization::{Deserializer as HistogramDeserializer, Serializer as _, V2Serializer},
+    Histogram,
+};
+use serde::{de, ser, Deserialize, Deserializer, Serialize, Serializer};
+
+// Three significant figures keeps the relative error of every quantile under 0.1%
+const SIGNIFICANT_FIGURES: u8 = 3;
+
+// Response time distribution in milliseconds. Persisted as the histogram's V2 encoding,
+// base64 encoded so it fits in any serde format.
+#[derive(Debug, Clone, PartialEq)]
+pub struct ResponseHistogram(Histogram<u64>);
+
+impl Default for ResponseHistogram {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl ResponseHistogram {
+    pub fn new() -> Self {
+        // Auto-resizing, so there is no upper bound to configure
+        Self(Histogram::new(SIGNIFICANT_FIGURES).expect("valid significant figures"))
+    }
+
+    pub fn record(&mut self, ms: u64) {
+        // Only fails for values past what even a resized histogram can track
+        if self.0.record(ms).is_err() {
+            self.0.saturating_record(ms);
+        }
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.0.is_empty()
+    }
+
+    pub fn mean(&self) -> f64 {
+        self.0.mean()
+    }
+
+    pub fn p50(&self) -> u64 {
+        self.value_at_quantile(0.50)
+    }
+
+    pub fn p95(&self) -> u64 {
+        self.value_at_quantile(0.95)
+    }
+
+    pub fn p99(&self) -> u64 {
+        self.value_at_quantile(0.99)
+    }
+
+    // Highest value equivalent to the sample at `quantile`, or 0 when nothing was recorded
+    pub fn value_at_quantile(&self, quantile: f64) -> u64 {
+        self.0.value_at_quantile(quantile.clamp(0.0, 1.0))
+    }
+}
+
+impl Serialize for ResponseHistogram {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        let mut bytes = Vec::new();
+        V2Serializer::new()
+            .serialize(&self.0, &mut bytes)
+            .map_err(|e| ser::Error::custom(format!("{:?}", e)))?;
+        serializer.serialize_str(&BASE64.encode(bytes))
+    }
+}
+
+impl<'de> Deserialize<'de> for ResponseHistogram {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        let encoded = String::deserialize(deserializer)?;
+        let bytes = BASE64.decode(encoded).map_err(de::Error::custom)?;
+        let mut histogram: Histogram<u64> = HistogramDeserializer::new()
+            .deserialize(&mut bytes.as_slice())
+            .map_err(|e| de::Error::custom(format!("{:?}", e)))?;
+        histogram.auto(true);
+        Ok(Self(histogram))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use rand::{rngs::StdRng, Rng, SeedableRng};
+
+    // Exact quantile of sorted samples, using the same nearest-rank definition
+    fn exact_quantile(sorted: &[u64], quantile: f64) -> u64 {
+        let rank = ((quantile * sorted.len() as f64).ceil() as usize).max(1);
+        sorted[rank - 1]
+    }
+
+    fn assert_close(actual: f64, expected: f64) {
+        let tolerance = (expected / 10f64.powi(SIGNIFICANT_FIGURES as i32)).max(1.0);
+        assert!(
+            (actual - expected).abs() <= tolerance,
+            "{} not within {} of {}", actual, tolerance, expected
+        );
+    }
+
+    #[test]
+    fn test_empty_histogram() {
+        let histogram = ResponseHistogram::new();
+        assert!(histogram.is_empty());
+        assert_eq!((histogram.p50(), histogram.p95(), histogram.p99()), (0, 0, 0));
+        assert_eq!(histogram.mean(), 0.0);
+    }
+
+    #[test]
+    fn test_small_values_are_exact() {
+        let mut histogram = ResponseHistogram::new();
+        for ms in 1..=100 {
+            histogram.record(ms);
+        }
+        assert_eq!(histogram.p50(), 50);
+        assert_eq!(histogram.p95(), 95);
+        assert_eq!(histogram.p99(), 99);
+        assert_eq!(histogram.value_at_quantile(1.0), 100);
+        assert_eq!(histogram.mean(), 50.5);
+    }
+
+    #[test]
+    fn test_quantiles_match_random_samples() {
+        let mut rng = StdRng::seed_from_u64(7);
+        for _ in 0..20 {
+            let mut histogram = ResponseHistogram::new();
+            let mut samples: Vec<u64> = (0..rng.gen_range(100..5_000))
+                .map(|_| {
+                    // Mostly fast responses with a long tail, like real endpoint latency
+                    if rng.gen_bool(0.9) { rng.gen_range(5..200) } else { rng.gen_range(200..30_000) }
+                })
+                .collect();
+            for &sample in &samples {
+                histogram.record(sample);
+            }
+            samples.sort_unstable();
+
+            for quantile in [0.5, 0.9, 0.95, 0.99, 0.999] {
+                assert_close(
+                    histogram.value_at_quantile(quantile) as f64,
+                    exact_quantile(&samples, quantile) as f64,
+                );
+            }
+            let mean = samples.iter().sum::<u64>() as f64 / samples.len() as f64;
+            assert_close(histogram.mean(), mean);
+        }
+    }
+
+    #[test]
+    fn test_outliers_do_not_move_the_median() {
+        let mut histogram = ResponseHistogram::new();
+        for _ in 0..98 {
+            histogram.record(20);
+        }
+        histogram.record(60_000);
+        histogram.record(60_000);
+
+        assert_eq!(histogram.p50(), 20);
+        assert_eq!(histogram.p95(), 20);
+        assert_close(histogram.p99() as f64, 60_000.0);
+        assert!(histogram.mean() > 1_000.0);
+    }
+
+    #[test]
+    fn test_serde_round_trip() {
+        let mut histogram = ResponseHistogram::new();
+        for ms in [3, 17, 250, 250, 4_000, 120_000] {
+            histogram.record(ms);
+        }
+        let json = serde_json::to_string(&histogram).unwrap();
+        let mut restored: ResponseHistogram = serde_json::from_str(&json).unwrap();
+        assert_eq!(restored, histogram);
+        assert_eq!(restored.p95(), histogram.p95());
+
+        // Still grows past the largest value it was restored with
+        restored.record(10_000_000);
+        assert_close(restored.value_at_quantile(1.0) as f64, 10_000_000.0);
+    }
+}
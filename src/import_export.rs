@@ -0,0 +1,426 @@
+use crate::{
+    audit::AuditRecord,
+    cache::CacheEntrySnapshot,
+    config::Config,
+    error::AppError,
+    metrics::CustomMetricSnapshot,
+    rate_limit::RateLimitCounters,
+    types::EndpointStats,
+    AppState,
+};
+use axum::{
+    body::Bytes,
+    extract::State,
+    http::header,
+    response::{IntoResponse, Json},
+};
+use chrono::{DateTime, Utc};
+use flate2::{read::GzDecoder, write::GzEncoder, Compression};
+use serde::{Deserialize, Serialize};
+use serde_json::{json, Value};
+use std::{
+    collections::HashMap,
+    io::{Read, Write},
+    sync::Arc,
+};
+use tracing::{info, warn};
+
+// Bump whenever the snapshot layout changes in a way older readers can't handle
+pub const STATE_VERSION: u32 = 1;
+
+const REDACTED: &str = "[REDACTED]";
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct StateSnapshot {
+    pub state_version: u32,
+    pub exported_at: DateTime<Utc>,
+    pub config: Config,
+    #[serde(default)]
+    pub endpoint_stats: HashMap<String, EndpointStats>,
+    #[serde(default)]
+    pub cache_entries: Vec<CacheEntrySnapshot>,
+    #[serde(default)]
+    pub rate_limit_stats: RateLimitCounters,
+    #[serde(default)]
+    pub custom_metrics: Vec<CustomMetricSnapshot>,
+    #[serde(default)]
+    pub audit_log: Vec<AuditRecord>,
+}
+
+impl StateSnapshot {
+    pub async fn capture(state: &AppState) -> Result<Self, AppError> {
+        let mut config = state.endpoint_manager.current_config().await;
+        redact_config(&mut config);
+
+        let mut audit_log = match &state.audit_service {
+            Some(audit) => audit.export_records().await?,
+            None => Vec::new(),
+        };
+        for record in audit_log.iter_mut().filter(|record| record.api_key_id.is_some()) {
+            record.api_key_id = Some(REDACTED.to_string());
+        }
+
+        Ok(Self {
+            state_version: STATE_VERSION,
+            exported_at: Utc::now(),
+            config,
+            endpoint_stats: state.endpoint_manager.export_endpoint_stats().await,
+            cache_entries: state.cache_service.export_entries().await,
+            rate_limit_stats: state.rate_limit_service.export_counters().await,
+            custom_metrics: state.metrics_service.export_custom_metrics().await,
+            audit_log,
+        })
+    }
+
+    pub async fn restore(self, state: &AppState) -> Result<(), AppError> {
+        // Everything that can fail happens before anything is replaced, so a bad archive never
+        // leaves services half-restored
+        let mut config = self.config;
+        restore_secrets(&mut config, &state.endpoint_manager.current_config().await);
+        config.validate()?;
+        validate_entries(&self.cache_entries, &self.custom_metrics)?;
+
+        // Imported audit records are appended in a single transaction; the local trail is never
+        // replaced. After that only reconciling the endpoints can fail, and it builds every new
+        // client before changing any of them.
+        match &state.audit_service {
+            Some(audit) => audit.import_records(&self.audit_log).await?,
+            None if !self.audit_log.is_empty() => {
+                warn!("Audit log is disabled, skipping {} imported audit records", self.audit_log.len());
+            }
+            None => {}
+        }
+
+        state.endpoint_manager.import_state(config, self.endpoint_stats).await?;
+        state.rate_limit_service.import_counters(self.rate_limit_stats).await;
+        state.metrics_service.import_custom_metrics(self.custom_metrics).await;
+        state.cache_service.import_entries(self.cache_entries).await;
+
+        info!("State snapshot from {} restored", self.exported_at.to_rfc3339());
+        Ok(())
+    }
+
+    pub fn to_archive(&self) -> Result<Vec<u8>, AppError> {
+        let json = serde_json::to_vec(self)?;
+        let mut encoder = GzEncoder::new(Vec::new(), Compression::default());
+        encoder.write_all(&json)?;
+        Ok(encoder.finish()?)
+    }
+
+    pub fn from_archive(bytes: &[u8]) -> Result<Self, AppError> {
+        let mut json = Vec::new();
+        GzDecoder::new(bytes)
+            .read_to_end(&mut json)
+            .map_err(|e| AppError::validation(&format!("Invalid state archive: {}", e)))?;
+
+        // Check the version before deserializing the full layout
+        let raw: Value = serde_json::from_slice(&json)?;
+        let version = raw["state_version"].as_u64()
+            .ok_or_else(|| AppError::validation("State archive is missing state_version"))?;
+        if version == 0 || version > STATE_VERSION as u64 {
+            return Err(AppError::validation(&format!(
+                "Unsupported state_version {} (supported: 1..={})",
+                version, STATE_VERSION
+            )));
+        }
+
+        Ok(serde_json::from_value(raw)?)
+    }
+}
+
+fn validate_entries(cache_entries: &[CacheEntrySnapshot], custom_metrics: &[CustomMetricSnapshot]) -> Result<(), AppError> {
+    // Keys are built by CacheService::create_cache_key, anything else could never be read back
+    if let Some(entry) = cache_entries.iter().find(|entry| !entry.key.starts_with("multi-rpc:")) {
+        return Err(AppError::validation(&format!("Invalid cache key in state archive: {}", entry.key)));
+    }
+    if let Some(metric) = custom_metrics.iter().find(|metric| !metric.value.is_finite()) {
+        return Err(AppError::validation(&format!("Custom metric {} has a non-finite value", metric.name)));
+    }
+    Ok(())
+}
+
+fn redact_config(config: &mut Config) {
+    config.auth.jwt_secret = REDACTED.to_string();
+    config.auth.api_keys.clear();
+    config.cache.redis_url = REDACTED.to_string();
+    config.admin.password_hash = REDACTED.to_string();
+    config.peers.shared_secret = REDACTED.to_string();
+    config.cache.gossip.shared_secret = REDACTED.to_string();
+
+    for endpoint in config.endpoints.iter_mut() {
+        if endpoint.auth_token.is_some() {
+            endpoint.auth_token = Some(REDACTED.to_string());
+        }
+    }
+}
+
+// Secrets never leave the server, so an imported config keeps the local ones
+fn restore_secrets(config: &mut Config, current: &Config) {
+    if config.auth.jwt_secret == REDACTED {
+        config.auth.jwt_secret = current.auth.jwt_secret.clone();
+    }
+    if config.auth.api_keys.is_empty() {
+        config.auth.api_keys = current.auth.api_keys.clone();
+    }
+    if config.cache.redis_url == REDACTED {
+        config.cache.redis_url = current.cache.redis_url.clone();
+    }
+    if config.admin.password_hash == REDACTED {
+        config.admin.password_hash = current.admin.password_hash.clone();
+    }
+    if config.peers.shared_secret == REDACTED {
+        config.peers.shared_secret = current.peers.shared_secret.clone();
+    }
+    if config.cache.gossip.shared_secret == REDACTED {
+        config.cache.gossip.shared_secret = current.cache.gossip.shared_secret.clone();
+    }
+
+    for endpoint in config.endpoints.iter_mut() {
+        if endpoint.auth_token.as_deref() == Some(REDACTED) {
+            endpoint.auth_token = current.endpoints.iter()
+                .find(|existing| existing.url == endpoint.url)
+                .and_then(|existing| existing.auth_token.clone());
+        }
+    }
+}
+
+pub async fn handle_export(
+    State(state): State<Arc<AppState>>,
+) -> Result<impl IntoResponse, AppError> {
+    let archive = StateSnapshot::capture(&state).await?.to_archive()?;
+
+    Ok((
+        [
+            (header::CONTENT_TYPE, "application/gzip"),
+            (header::CONTENT_DISPOSITION, "attachment; filename=\"multi-rpc-state.json.gz\""),
+        ],
+        archive,
+    ))
+}
+
+pub async fn handle_import(
+    State(state): State<Arc<AppState>>,
+    body: Bytes,
+) -> Result<Json<Value>, AppError> {
+    let snapshot = StateSnapshot::from_archive(&body)?;
+    let state_version = snapshot.state_version;
+    let endpoint_count = snapshot.endpoint_stats.len();
+    let cache_entries = snapshot.cache_entries.len();
+    let custom_metrics = snapshot.custom_metrics.len();
+    let audit_records = snapshot.audit_log.len();
+
+    snapshot.restore(&state).await?;
+
+    Ok(Json(json!({
+        "status": "imported",
+        "state_version": state_version,
+        "endpoints": endpoint_count,
+        "cache_entries": cache_entries,
+        "custom_metrics": custom_metrics,
+        "audit_records": audit_records,
+    })))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{
+        metrics::CustomMetricType,
+        test_server::{TestServer, TestServerBuilder},
+    };
+    use reqwest::StatusCode;
+    use std::time::Duration;
+    use tempfile::TempDir;
+
+    // Test servers share one metrics service, so each test uses its own metric
+    const METRIC: &str = "import_export_cycle_metric";
+    const REJECTED_METRIC: &str = "import_export_rejected_metric";
+
+    async fn audited_server(dir: &TempDir) -> TestServer {
+        let db_path = dir.path().join("audit.db").to_string_lossy().to_string();
+        TestServerBuilder::new()
+            .with_endpoint_config("primary", |endpoint| endpoint.auth_token = Some("endpoint-secret".to_string()))
+            .with_config(|config| {
+                config.audit.enabled = true;
+                config.audit.db_path = db_path;
+            })
+            .start()
+            .await
+    }
+
+    fn audit_record(method: &str) -> AuditRecord {
+        AuditRecord {
+            timestamp: Utc::now(),
+            client_ip: Some("203.0.113.9".to_string()),
+            api_key_id: None,
+            method: method.to_string(),
+            params_hash: crate::audit::params_hash(&json!(["Account1"])),
+            status: 200,
+            latency_ms: 12,
+        }
+    }
+
+    async fn set_metric(server: &TestServer, value: f64) {
+        server.state.metrics_service
+            .record_custom_metric(METRIC, value, HashMap::new(), CustomMetricType::Gauge)
+            .await;
+    }
+
+    async fn metric_value(server: &TestServer, name: &str) -> Option<f64> {
+        server.state.metrics_service.export_custom_metrics().await
+            .into_iter()
+            .find(|metric| metric.name == name)
+            .map(|metric| metric.value)
+    }
+
+    async fn record_request(server: &TestServer) -> String {
+        let endpoint = server.state.endpoint_manager.get_endpoint_info().await.remove(0);
+        server.state.endpoint_manager.update_endpoint_stats(endpoint.id, true, Duration::from_millis(42)).await;
+        endpoint.url
+    }
+
+    async fn export(server: &TestServer) -> Bytes {
+        let response = server.client.get(server.url("/admin/state/export")).send().await.unwrap();
+        assert!(response.status().is_success());
+        response.bytes().await.unwrap()
+    }
+
+    async fn import(server: &TestServer, archive: Vec<u8>) -> reqwest::Response {
+        server.client.post(server.url("/admin/state/import")).body(archive).send().await.unwrap()
+    }
+
+    #[tokio::test]
+    async fn test_export_import_cycle() {
+        let dir = TempDir::new().unwrap();
+        let server = audited_server(&dir).await;
+        let audit = server.state.audit_service.clone().unwrap();
+        let url = record_request(&server).await;
+        server.state.cache_service.import_entries(vec![CacheEntrySnapshot {
+            key: "multi-rpc:getVersion:".to_string(),
+            value: json!({"solana-core": "1.18.0"}),
+            ttl_remaining_secs: 300,
+        }]).await;
+        set_metric(&server, 7.5).await;
+        let keyed = AuditRecord { api_key_id: Some(crate::audit::api_key_id("client-api-key")), ..audit_record("getBalance") };
+        audit.import_records(&[keyed]).await.unwrap();
+
+        let archive = export(&server).await;
+
+        // Secrets are redacted from the archive itself
+        let exported = StateSnapshot::from_archive(&archive).unwrap();
+        assert_eq!(exported.state_version, STATE_VERSION);
+        assert_eq!(exported.config.auth.jwt_secret, REDACTED);
+        assert_eq!(exported.config.endpoints[0].auth_token.as_deref(), Some(REDACTED));
+        assert_eq!(exported.audit_log.len(), 1);
+        assert_eq!(exported.audit_log[0].api_key_id.as_deref(), Some(REDACTED));
+        assert!(exported.custom_metrics.iter().any(|metric| metric.name == METRIC));
+
+        // Everything moves on after the export...
+        record_request(&server).await;
+        server.state.cache_service.clear_local_cache().await;
+        set_metric(&server, 1.0).await;
+        audit.import_records(&[audit_record("getSlot"), audit_record("getSlot")]).await.unwrap();
+
+        // ...and is put back by the import
+        let response = import(&server, archive.to_vec()).await;
+        assert!(response.status().is_success());
+        let body: Value = response.json().await.unwrap();
+        assert_eq!(body["audit_records"], 1);
+
+        let stats = server.state.endpoint_manager.export_endpoint_stats().await;
+        assert_eq!(stats[&url].total_requests, 1);
+        assert_eq!(stats[&url].successful_requests, 1);
+        let entries = server.state.cache_service.export_entries().await;
+        assert_eq!(entries.len(), 1);
+        assert_eq!(entries[0].value["solana-core"], "1.18.0");
+        assert_eq!(metric_value(&server, METRIC).await, Some(7.5));
+        // Imported audit records are added to the local trail, which keeps what it logged since
+        let records = audit.export_records().await.unwrap();
+        let methods: Vec<&str> = records.iter().map(|record| record.method.as_str()).collect();
+        assert_eq!(methods, ["getBalance", "getBalance", "getSlot", "getSlot"]);
+        assert_eq!(records.iter().filter(|record| record.api_key_id.as_deref() == Some(REDACTED)).count(), 1);
+
+        // Local secrets survive the import
+        let restored = server.state.endpoint_manager.current_config().await;
+        assert_eq!(restored.auth.jwt_secret, Config::default().auth.jwt_secret);
+        assert_eq!(restored.endpoints[0].auth_token.as_deref(), Some("endpoint-secret"));
+    }
+
+    #[tokio::test]
+    async fn test_import_reconciles_live_endpoints() {
+        let dir = TempDir::new().unwrap();
+        let server = audited_server(&dir).await;
+        let mut snapshot = StateSnapshot::from_archive(&export(&server).await).unwrap();
+        let mut added = snapshot.config.endpoints[0].clone();
+        added.name = "imported".to_string();
+        added.url = "http://127.0.0.1:9/imported".to_string();
+        added.auth_token = None;
+        snapshot.config.endpoints = vec![added];
+
+        let response = import(&server, snapshot.to_archive().unwrap()).await;
+        assert!(response.status().is_success());
+
+        // Routing follows the imported config, not just /config
+        let live: Vec<String> = server.state.endpoint_manager.get_endpoint_info().await
+            .into_iter()
+            .map(|endpoint| endpoint.name)
+            .collect();
+        assert_eq!(live, ["imported"]);
+        let config = server.state.endpoint_manager.get_config().await;
+        assert_eq!(config["endpoints"][0]["name"], "imported");
+    }
+
+    #[tokio::test]
+    async fn test_rejected_import_changes_nothing() {
+        let dir = TempDir::new().unwrap();
+        let server = audited_server(&dir).await;
+        let audit = server.state.audit_service.clone().unwrap();
+        let url = record_request(&server).await;
+        audit.import_records(&[audit_record("getSlot")]).await.unwrap();
+
+        let mut snapshot = StateSnapshot::from_archive(&export(&server).await).unwrap();
+        snapshot.endpoint_stats.clear();
+        snapshot.audit_log.clear();
+        snapshot.custom_metrics.push(CustomMetricSnapshot {
+            name: REJECTED_METRIC.to_string(),
+            value: 3.0,
+            labels: HashMap::new(),
+            metric_type: CustomMetricType::Gauge,
+        });
+        // One bad entry at the end of the archive
+        snapshot.cache_entries.push(CacheEntrySnapshot {
+            key: "getVersion".to_string(),
+            value: json!("1.18.0"),
+            ttl_remaining_secs: 300,
+        });
+
+        let response = import(&server, snapshot.to_archive().unwrap()).await;
+        assert_eq!(response.status(), StatusCode::BAD_REQUEST);
+
+        assert_eq!(server.state.endpoint_manager.export_endpoint_stats().await[&url].total_requests, 1);
+        assert_eq!(audit.export_records().await.unwrap().len(), 1);
+        assert_eq!(metric_value(&server, REJECTED_METRIC).await, None);
+        assert!(server.state.cache_service.export_entries().await.is_empty());
+    }
+
+    #[test]
+    fn test_rejects_future_state_version() {
+        let mut raw = serde_json::to_value(StateSnapshot {
+            state_version: STATE_VERSION,
+            exported_at: Utc::now(),
+            config: Config::default(),
+            endpoint_stats: HashMap::new(),
+            cache_entries: Vec::new(),
+            rate_limit_stats: RateLimitCounters::default(),
+            custom_metrics: Vec::new(),
+            audit_log: Vec::new(),
+        }).unwrap();
+        raw["state_version"] = json!(STATE_VERSION + 1);
+
+        let mut encoder = GzEncoder::new(Vec::new(), Compression::default());
+        encoder.write_all(raw.to_string().as_bytes()).unwrap();
+        let archive = encoder.finish().unwrap();
+
+        assert!(StateSnapshot::from_archive(&archive).is_err());
+    }
+}
@@ -1,7 +1,7 @@
-use std::time::Duration;
-use tracing::{debug, error, info, warn, Level, Span};
+use crate::config_diff::ConfigChange;
+use tracing::{info, warn};
 use tracing_subscriber::{
-    fmt::{self, format::FmtSpan},
+    fmt,
     layer::SubscriberExt,
     util::SubscriberInitExt,
     EnvFilter, Layer,
@@ -13,6 +13,7 @@ use std::sync::Arc;
 use tokio::sync::RwLock;
 use std::collections::VecDeque;
 
+#[allow(dead_code)]
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct LogConfig {
     pub level: String,
@@ -31,6 +32,7 @@ pub struct LogConfig {
     pub sample_rate: f64,
 }
 
+#[allow(dead_code)]
 #[derive(Debug, Clone, Serialize, Deserialize)]
 #[serde(rename_all = "lowercase")]
 pub enum LogFormat {
@@ -61,6 +63,7 @@ impl Default for LogConfig {
     }
 }
 
+#[allow(dead_code)]
 #[derive(Debug, Clone)]
 pub struct RequestContext {
     pub request_id: String,
@@ -73,6 +76,7 @@ pub struct RequestContext {
     pub start_time: DateTime<Utc>,
 }
 
+#[allow(dead_code)]
 impl RequestContext {
     pub fn new() -> Self {
         Self {
@@ -178,8 +182,8 @@ impl LogBuffer {
         events.iter()
             .filter(|event| {
                 event.message.contains(query) ||
-                event.error_code.as_ref().map_or(false, |code| code.contains(query)) ||
-                event.request_id.as_ref().map_or(false, |id| id.contains(query))
+                event.error_code.as_ref().is_some_and(|code| code.contains(query)) ||
+                event.request_id.as_ref().is_some_and(|id| id.contains(query))
             })
             .take(limit)
             .cloned()
@@ -193,10 +197,12 @@ impl LogBuffer {
 }
 
 // Custom tracing layer for structured logging
+#[allow(dead_code)]
 pub struct StructuredLoggingLayer {
     buffer: Arc<LogBuffer>,
 }
 
+#[allow(dead_code)]
 impl StructuredLoggingLayer {
     pub fn new(buffer: Arc<LogBuffer>) -> Self {
         Self { buffer }
@@ -258,6 +264,7 @@ where
 }
 
 // Visitor for extracting structured data from events
+#[allow(dead_code)]
 #[derive(Default)]
 struct JsonVisitor {
     message: Option<String>,
@@ -295,6 +302,7 @@ impl tracing::field::Visit for JsonVisitor {
 }
 
 // Initialize logging system
+#[allow(dead_code)]
 pub fn init_logging(config: &LogConfig, buffer: Arc<LogBuffer>) -> anyhow::Result<()> {
     let env_filter = EnvFilter::try_from_default_env()
         .unwrap_or_else(|_| EnvFilter::new(&config.level));
@@ -325,6 +333,7 @@ pub fn init_logging(config: &LogConfig, buffer: Arc<LogBuffer>) -> anyhow::Resul
 }
 
 // Logging middleware for HTTP requests
+#[allow(dead_code)]
 pub async fn logging_middleware(
     req: axum::http::Request<axum::body::Body>,
     next: axum::middleware::Next,
@@ -403,6 +412,7 @@ macro_rules! log_request {
 
 // Audit logging for security events
 pub struct AuditLogger {
+    #[allow(dead_code)]
     buffer: Arc<LogBuffer>,
 }
 
@@ -456,6 +466,12 @@ impl AuditLogger {
             "Configuration changed"
         );
     }
+
+    pub fn log_config_diff(&self, user: &str, changes: &[ConfigChange]) {
+        for change in changes {
+            self.log_configuration_change(user, &change.field_path, &change.old_value.to_string(), &change.new_value.to_string());
+        }
+    }
 }
 
 #[cfg(test)]
@@ -496,4 +512,4 @@ mod tests {
         assert_eq!(search_results.len(), 1);
         assert_eq!(search_results[0].message, "Test message 12");
     }
-}
\ No newline at end of file
+}
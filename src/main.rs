@@ -1,49 +1,118 @@
 use axum::{
     extract::{ws::WebSocketUpgrade, State, Query},
-    response::{Json, IntoResponse},
-    routing::{get, post},
-    Router, middleware,
+    http::{header, HeaderMap, HeaderValue, StatusCode},
+    Extension,
+    response::{
+        sse::{Event, KeepAlive, Sse},
+        Json, IntoResponse, Redirect, Response,
+    },
+    routing::{delete, get, patch, post},
+    Router,
 };
-use std::sync::Arc;
-use tokio::net::TcpListener;
-use tower_http::cors::CorsLayer;
-use tracing::{info, error};
-use tracing_subscriber;
+use futures::StreamExt;
+use std::{convert::Infallible, sync::Arc};
+use tokio::{net::TcpListener, sync::broadcast::error::RecvError};
+use tokio_util::sync::CancellationToken;
+use tower_http::{cors::CorsLayer, decompression::RequestDecompressionLayer};
+use tracing::{info, warn, error};
 use std::collections::HashMap;
 use serde_json::json;
 use chrono::Utc;
 
+mod access_control;
+mod alerting;
+mod audit;
 mod auth;
 mod cache;
 mod config;
+mod config_diff;
+mod config_reload;
 mod consensus;
+mod decompression;
+mod dedup;
+mod discovery;
 mod endpoints;
+mod epoch;
+mod events;
 mod error;
 mod geo;
+mod gossip;
+mod graphql;
 mod health;
+mod histogram;
 mod metrics;
 mod rate_limit;
+mod request_trace;
 mod router;
 mod rpc;
+mod service_discovery;
 mod types;
+mod webhook;
 mod websocket;
 mod admin;
+mod aggregator;
+mod body_logger;
+mod anomaly;
+mod chaos;
+mod circuit_breaker;
 mod retry;
 mod bulkhead;
 mod logging;
+mod memory;
+mod middleware;
 mod monitoring;
+mod openmetrics;
+mod peer;
+mod prefetch;
+mod propagation;
+mod import_export;
+mod scheduler;
+mod shaping;
+mod shutdown;
+mod signing;
+mod tls_monitor;
+mod transform;
+mod transport;
+#[cfg(test)]
+mod test_server;
 
-use auth::{AuthService, AuthMiddleware};
-use cache::CacheService;
-use config::Config;
+use access_control::{AccessControlService, Whitelisted};
+use aggregator::ResponseAggregator;
+use audit::{AuditRecord, AuditService};
+use auth::{AuthContext, AuthService, AuthMiddleware};
+use body_logger::BodyLogger;
+use bulkhead::BulkheadRegistry;
+use cache::{CachedResponse, CacheService};
+use chaos::NetworkPartitionSimulator;
+use circuit_breaker::CircuitBreakerRegistry;
+use config::{Config, GraphqlConfig, RateLimitBackend};
+use config_reload::ConfigReloader;
 use consensus::ConsensusService;
+use dedup::DeduplicationService;
 use endpoints::EndpointManager;
+use epoch::EpochTracker;
+use events::EventBus;
 use crate::error::AppError;
 use geo::GeoService;
-use health::HealthService;
+use gossip::CacheGossipService;
+use health::{HealthReport, HealthService};
+use logging::{AuditLogger, LogBuffer};
+use memory::MemoryPressureReactor;
 use metrics::MetricsService;
-use rate_limit::RateLimitService;
+use middleware::BodySizeLimitLayer;
+use monitoring::PrometheusMultiProcess;
+use peer::PeerDiscovery;
+use prefetch::PrefetchHook;
+use propagation::RequestContextPropagator;
+use rate_limit::{RateLimitService, RedisWindowStore};
+use request_trace::TraceStore;
 use router::RpcRouter;
+use shaping::EgressShaper;
+use shutdown::InFlightRequests;
+use signing::RequestSignatureVerifier;
+use tls_monitor::TlsCertificateMonitor;
+use transform::{ResponseTransformPipeline, ResponseTransformer};
+use webhook::WebhookService;
 use websocket::WebSocketService;
 
 #[derive(Clone)]
@@ -58,6 +127,19 @@ pub struct AppState {
     pub metrics_service: Arc<MetricsService>,
     pub rate_limit_service: Arc<RateLimitService>,
     pub websocket_service: Arc<WebSocketService>,
+    pub egress_shaper: Option<Arc<EgressShaper>>,
+    pub cluster_metrics: Arc<PrometheusMultiProcess>,
+    pub partition_simulator: Arc<NetworkPartitionSimulator>,
+    pub event_bus: Arc<EventBus>,
+    pub audit_logger: Arc<AuditLogger>,
+    pub body_logger: Option<Arc<BodyLogger>>,
+    pub trace_store: Arc<TraceStore>,
+    pub audit_service: Option<Arc<AuditService>>,
+    pub max_request_body_bytes: usize,
+    pub access_control: Arc<AccessControlService>,
+    pub graphql: GraphqlConfig,
+    pub bulkheads: Arc<BulkheadRegistry>,
+    pub in_flight: Arc<InFlightRequests>,
 }
 
 #[tokio::main]
@@ -84,82 +166,368 @@ async fn main() -> Result<(), AppError> {
         }
     };
 
+    // Cancelled on Ctrl+C so background tasks and WebSocket connections wind down with the server
+    let shutdown = CancellationToken::new();
+
     // Initialize services
-    let endpoint_manager = Arc::new(EndpointManager::new(config.endpoints.clone(), config.clone()).await?);
-    let cache_service = Arc::new(CacheService::new(&config).await?);
-    let auth_service = Arc::new(AuthService::new(&config).await?);
-    let consensus_service = Arc::new(ConsensusService::new(config.consensus.clone()));
-    let geo_service = Arc::new(GeoService::new(&config).await?);
-    let metrics_service = Arc::new(MetricsService::new());
-    let rate_limit_service = Arc::new(RateLimitService::new(&config));
-    let websocket_service = Arc::new(WebSocketService::new(endpoint_manager.clone()));
+    let app_state = build_app_state(&config, Arc::new(MetricsService::new()), shutdown.clone()).await?;
+
+    // Start background services
+    let mut background_tasks = vec![
+        tokio::spawn({
+            let health_service = app_state.health_service.clone();
+            let shutdown = shutdown.clone();
+            async move {
+                health_service.start_monitoring(shutdown).await;
+            }
+        }),
+        tokio::spawn({
+            let endpoint_manager = app_state.endpoint_manager.clone();
+            let shutdown = shutdown.clone();
+            async move {
+                endpoint_manager.start_auto_discovery(shutdown).await;
+            }
+        }),
+        tokio::spawn({
+            let endpoint_manager = app_state.endpoint_manager.clone();
+            let shutdown = shutdown.clone();
+            async move {
+                endpoint_manager.start_slot_tracking(shutdown).await;
+            }
+        }),
+        tokio::spawn(app_state.endpoint_manager.clone().start_adaptive_weighting(shutdown.clone())),
+        tokio::spawn(app_state.endpoint_manager.clone().start_retry_reporting(shutdown.clone())),
+        tokio::spawn({
+            let monitor = Arc::new(TlsCertificateMonitor::new(
+                app_state.endpoint_manager.clone(),
+                app_state.metrics_service.clone(),
+                config.tls_monitor.clone(),
+            ));
+            let shutdown = shutdown.clone();
+            async move {
+                monitor.start(shutdown).await;
+            }
+        }),
+    ];
+
+    if config.peers.enabled {
+        let listener = TcpListener::bind(&config.peers.grpc_bind_address).await?;
+        info!("Peer gRPC service listening on {}", config.peers.grpc_bind_address);
+        let peer_discovery = Arc::new(PeerDiscovery::new(
+            &config.peers,
+            app_state.endpoint_manager.clone(),
+            app_state.metrics_service.clone(),
+            app_state.event_bus.clone(),
+        ));
+        background_tasks.push(tokio::spawn(peer_discovery.start(listener, shutdown.clone())));
+    }
+
+    if config.epoch_tracker.enabled {
+        let tracker = Arc::new(EpochTracker::new(
+            app_state.endpoint_manager.clone(),
+            app_state.cache_service.clone(),
+            app_state.consensus_service.clone(),
+            app_state.metrics_service.clone(),
+            config.epoch_tracker.clone(),
+        ));
+        background_tasks.push(tokio::spawn(tracker.start(shutdown.clone())));
+    }
+
+    let reloader = Arc::new(ConfigReloader::new(
+        app_state.endpoint_manager.clone(),
+        app_state.metrics_service.clone(),
+        app_state.audit_logger.clone(),
+        &config.auto_reload,
+    ));
+    if config.auto_reload.enabled {
+        background_tasks.push(tokio::spawn(reloader.clone().start(shutdown.clone())));
+    }
+    #[cfg(unix)]
+    background_tasks.push(tokio::spawn(reloader.start_sighup_listener(shutdown.clone())));
+
+    if let Some(service_discovery) = &config.service_discovery {
+        let discovery = service_discovery::from_config(service_discovery)?;
+        background_tasks.push(tokio::spawn(app_state.endpoint_manager.clone().start_service_discovery(
+            discovery,
+            std::time::Duration::from_secs(service_discovery.refresh_interval_secs.max(1)),
+            std::time::Duration::from_secs(service_discovery.drain_timeout_secs),
+            shutdown.clone(),
+        )));
+    }
+
+    if config.memory_pressure.enabled {
+        let reactor = Arc::new(MemoryPressureReactor::new(
+            app_state.cache_service.clone(),
+            app_state.endpoint_manager.clone(),
+            app_state.metrics_service.clone(),
+            config.memory_pressure.clone(),
+        ));
+        background_tasks.push(tokio::spawn(reactor.start(shutdown.clone())));
+    }
+
+    if !config.webhooks.is_empty() {
+        let webhooks = Arc::new(WebhookService::new(&config.webhooks, app_state.endpoint_manager.clone()));
+        background_tasks.push(tokio::spawn(webhooks.watch_circuit_breakers(shutdown.clone())));
+    }
+
+    if config.cache.enabled {
+        let websocket_service = app_state.websocket_service.clone();
+        background_tasks.push(tokio::spawn(async move { websocket_service.subscribe_slot_updates().await }));
+    }
+
+    if let Some(gossip) = app_state.cache_service.gossip() {
+        background_tasks.push(tokio::spawn(gossip.start(app_state.cache_service.clone(), shutdown.clone())));
+    }
+
+    let app = build_router(app_state.clone());
+
+    let warmup_timeout = std::time::Duration::from_secs(config.cache.warmup_timeout_secs);
+    if tokio::time::timeout(warmup_timeout, app_state.cache_service.warmup_cache(&app_state.rpc_router)).await.is_err() {
+        warn!("Cache warmup did not finish within {:?}, starting with a partially warm cache", warmup_timeout);
+        app_state.cache_service.mark_initialized();
+    }
+
+    // Start the server
+    info!("Attempting to bind to address: {}", config.bind_address);
+    let listener = match TcpListener::bind(&config.bind_address).await {
+        Ok(listener) => {
+            info!("Successfully bound to {}", config.bind_address);
+            listener
+        }
+        Err(e) => {
+            error!("Failed to bind to {}: {}", config.bind_address, e);
+            return Err(AppError::from(e));
+        }
+    };
+    
+    info!("🚀 Multi-RPC Enterprise server starting on {}", config.bind_address);
+    info!("📊 Admin dashboard available at http://{}/admin", config.bind_address);
+    info!("🔌 WebSocket endpoint available at ws://{}/ws", config.bind_address);
+    info!("🏥 Health check available at http://{}/health", config.bind_address);
+    
+    info!("Server is ready to accept connections");
+    
+    let serve_result = shutdown::serve_with_draining(
+        listener,
+        app,
+        app_state.in_flight.clone(),
+        {
+            let shutdown = shutdown.clone();
+            async move {
+                shutdown::shutdown_signal().await;
+                shutdown.cancel();
+            }
+        },
+        std::time::Duration::from_secs(config.shutdown_timeout_secs),
+    ).await;
+
+    // Make sure background tasks observed the cancellation before exiting
+    shutdown.cancel();
+    for task in background_tasks {
+        if tokio::time::timeout(std::time::Duration::from_secs(10), task).await.is_err() {
+            error!("Background task did not stop within 10s");
+        }
+    }
+
+    if config.consensus.consensus_snapshot_path.is_some() {
+        if let Err(e) = app_state.consensus_service.dump_cache_snapshot().await {
+            error!("Failed to write consensus cache snapshot: {}", e);
+        }
+    }
+
+    match serve_result {
+        Ok(drained) => {
+            info!("Server shut down gracefully after draining {} requests", drained);
+            Ok(())
+        }
+        Err(e) => {
+            error!("Server error: {}", e);
+            Err(AppError::from(e))
+        }
+    }
+}
+
+// Services are built separately from the server so tests can run the full stack in-process.
+// The metrics service is passed in because its collectors live in the global Prometheus registry.
+async fn build_app_state(
+    config: &Config,
+    metrics_service: Arc<MetricsService>,
+    shutdown: CancellationToken,
+) -> Result<Arc<AppState>, AppError> {
+    let event_bus = Arc::new(EventBus::new());
+    let circuit_breakers = Arc::new(CircuitBreakerRegistry::new(event_bus.clone()));
+    let endpoint_manager = Arc::new(EndpointManager::new(config.endpoints.clone(), config.clone(), circuit_breakers.clone())
+        .await?
+        .with_metrics_service(metrics_service.clone()));
+    let mut cache_service = CacheService::new(config).await?;
+    if config.cache.gossip.enabled {
+        let gossip = CacheGossipService::bind(&config.cache.gossip, metrics_service.clone()).await?;
+        cache_service = cache_service.with_gossip(Arc::new(gossip));
+    }
+    let cache_service = Arc::new(cache_service);
+    let auth_service = Arc::new(AuthService::new(config).await?);
+    let partition_simulator = Arc::new(NetworkPartitionSimulator::new(metrics_service.clone()));
+    let consensus_service = Arc::new(
+        ConsensusService::new(config.consensus.clone())
+            .with_partition_simulator(partition_simulator.clone()),
+    );
+    match consensus_service.bootstrap_from_snapshot().await {
+        Ok(loaded) => metrics_service.record_cache_bootstrapped_entries(loaded),
+        Err(e) => warn!("Failed to load consensus cache snapshot: {}", e),
+    }
+    let geo_service = Arc::new(GeoService::new(config).await?);
+    let access_control = Arc::new(AccessControlService::new(config));
+    let mut rate_limit_service = RateLimitService::new(config)
+        .with_metrics_service(metrics_service.clone());
+    if config.rate_limiting.enabled && config.rate_limiting.backend == RateLimitBackend::Redis {
+        let redis_url = config.rate_limiting.redis_url.as_deref().unwrap_or(&config.cache.redis_url);
+        match RedisWindowStore::connect(redis_url).await {
+            Ok(store) => rate_limit_service = rate_limit_service.with_redis_limiter(Arc::new(store)),
+            Err(e) => warn!("Failed to connect to rate limit Redis, limiting per instance: {}", e),
+        }
+    }
+    let rate_limit_service = Arc::new(rate_limit_service);
+    let mut websocket_service = WebSocketService::new(endpoint_manager.clone(), shutdown.clone())
+        .with_dedup_window(std::time::Duration::from_millis(config.websocket.dedup_window_ms))
+        .with_max_subscriptions_per_connection(config.websocket.max_subscriptions_per_connection as usize)
+        .with_metrics_service(metrics_service.clone())
+        .with_cache_service(cache_service.clone());
+    if config.message_signing.enabled {
+        websocket_service = websocket_service.with_signature_verifier(RequestSignatureVerifier::new(&config.message_signing));
+    }
+    let websocket_service = Arc::new(websocket_service);
+    let bulkheads = Arc::new(BulkheadRegistry::new(&config.bulkheads));
     
-    let rpc_router = Arc::new(RpcRouter::new(
+    let mut rpc_router = RpcRouter::new(
         endpoint_manager.clone(),
         cache_service.clone(),
         consensus_service.clone(),
         geo_service.clone(),
         metrics_service.clone(),
-    ));
+    )
+    .with_partition_simulator(partition_simulator.clone())
+    .with_response_aggregator(ResponseAggregator::new(config.batch_splitting.clone()))
+    .with_response_transforms(ResponseTransformPipeline::new(config.response_transforms.clone())?)
+    .with_response_transformer(ResponseTransformer::new(&config.transforms)?)
+    .with_streaming(&config.streaming)
+    .with_sticky_sessions(&config.sticky_sessions)
+    .with_scheduler(&config.scheduler_priorities)
+    .with_retry_strategy(&config.retry_strategy)
+    .with_bulkheads(bulkheads.clone())
+    .with_max_response_size(config.max_response_size_bytes)
+    .with_fallback_responses(config.fallback_responses.clone())
+    .with_post_request_hook(Arc::new(PrefetchHook::new(
+        config.cache.method_prefetch_rules.clone(),
+    )));
+    if config.request_deduplication.enabled {
+        rpc_router = rpc_router.with_deduplication(
+            DeduplicationService::new(std::time::Duration::from_millis(config.request_deduplication.wait_timeout_ms))
+                .with_metrics_service(metrics_service.clone()),
+        );
+    }
+    let rpc_router = Arc::new(rpc_router);
     
-    let health_service = Arc::new(HealthService::new(
-        endpoint_manager.clone(),
-    ));
+    let health_service = Arc::new(
+        HealthService::new(endpoint_manager.clone())
+            .with_config(&config.health)
+            .with_cache_service(cache_service.clone())
+            .with_geo_service(geo_service.clone())
+            .with_rate_limit_service(rate_limit_service.clone())
+            .with_metrics_service(metrics_service.clone()),
+    );
+    let audit_service = match config.audit.enabled {
+        true => Some(Arc::new(AuditService::new(&config.audit).await?)),
+        false => None,
+    };
 
-    let app_state = Arc::new(AppState {
-        endpoint_manager: endpoint_manager.clone(),
+    Ok(Arc::new(AppState {
+        endpoint_manager,
         rpc_router,
-        health_service: health_service.clone(),
-        auth_service: auth_service.clone(),
+        health_service,
+        auth_service,
         cache_service,
         consensus_service,
         geo_service,
-        metrics_service: metrics_service.clone(),
+        metrics_service,
         rate_limit_service,
         websocket_service,
-    });
-
-    // Start background services
-    tokio::spawn({
-        let health_service = health_service.clone();
-        async move {
-            health_service.start_monitoring().await;
-        }
-    });
-
-    tokio::spawn({
-        let endpoint_manager = endpoint_manager.clone();
-        async move {
-            endpoint_manager.start_auto_discovery().await;
-        }
-    });
+        egress_shaper: config.egress_rate_limit_bps
+            .and_then(EgressShaper::new)
+            .map(Arc::new),
+        cluster_metrics: Arc::new(PrometheusMultiProcess::new(&config.monitoring)),
+        partition_simulator,
+        event_bus,
+        audit_logger: Arc::new(AuditLogger::new(Arc::new(LogBuffer::new(1000)))),
+        body_logger: BodyLogger::new(&config.body_logger, &config.logging).map(Arc::new),
+        trace_store: Arc::new(TraceStore::new()),
+        audit_service,
+        max_request_body_bytes: config.max_request_body_bytes,
+        access_control,
+        graphql: config.graphql.clone(),
+        bulkheads,
+        in_flight: Arc::new(InFlightRequests::default()),
+    }))
+}
 
-    // Build the application router
-    let app = Router::new()
-        // Main RPC endpoint
-        .route("/", get(handle_root).post(handle_rpc_request))
+fn build_router(app_state: Arc<AppState>) -> Router {
+    let max_request_body_bytes = app_state.max_request_body_bytes;
+    let mut router = Router::new()
+        // Main RPC endpoint, accepting gzip, br and zstd request bodies
+        // The error types are spelled out because inference can't pick them through the chain
+        .route("/", get(handle_root).post(handle_rpc_request)
+            .layer::<_, Infallible>(axum::middleware::from_fn_with_state(app_state.clone(), body_logger::rpc_logging_middleware))
+            .layer::<_, Infallible>(axum::middleware::from_fn_with_state(app_state.in_flight.clone(), InFlightRequests::middleware))
+            .layer::<_, Infallible>(axum::middleware::from_fn_with_state(
+                app_state.clone(),
+                decompression::record_decompressed_requests,
+            ))
+            .layer::<_, Infallible>(RequestDecompressionLayer::new())
+            .layer::<_, Infallible>(axum::middleware::from_fn(decompression::tag_compressed_requests)))
         
         // WebSocket endpoint
         .route("/ws", get(handle_websocket_upgrade))
         
         // Health and status endpoints
         .route("/health", get(handle_health))
+        .route("/health/live", get(handle_liveness))
+        .route("/health/detailed", get(handle_detailed_health))
+        .route("/health/ready", get(handle_readiness))
+        .route("/events/endpoints", get(handle_sse_endpoints))
         .route("/endpoints", get(handle_endpoints))
         .route("/stats", get(handle_stats))
         
         // Metrics endpoints
         .route("/metrics", get(handle_metrics))
         .route("/metrics/prometheus", get(handle_prometheus_metrics))
+        .route("/metrics/openmetrics", get(handle_openmetrics_metrics))
+        .route("/metrics/prometheus/cluster", get(handle_cluster_prometheus_metrics))
+        .route("/metrics/alerts", get(alerting::handle_alert_rules))
         
         // Admin endpoints
         .route("/admin", get(admin::dashboard))
-        .route("/admin/endpoints", get(admin::endpoints_page))
+        .route("/admin/endpoints", get(admin::endpoints_page).post(admin::add_endpoint))
+        .route("/admin/endpoints/rows", get(admin::endpoint_rows))
+        .route("/admin/endpoints/:id", delete(admin::remove_endpoint).patch(admin::update_endpoint))
+        .route("/admin/endpoints/:id/health-check", post(admin::force_endpoint_health_check))
         .route("/admin/config", get(admin::config_page))
         .route("/admin/logs", get(admin::logs_page))
+        .route("/admin/state/export", get(import_export::handle_export))
+        .route("/admin/state/import", post(import_export::handle_import))
+        .route("/admin/websocket/broadcast", post(handle_admin_broadcast))
+        .route("/admin/audit", get(audit::handle_audit_query))
+        .route("/admin/bulkheads", get(bulkhead::handle_list_bulkheads))
+        .route("/admin/rate-limits", patch(rate_limit::handle_update_rate_limits))
+        .route("/admin/chaos/partition", get(chaos::handle_partition_status)
+            .post(chaos::handle_activate_partition)
+            .delete(chaos::handle_lift_partition))
+        
+        // Multi-endpoint pre-flight simulation
+        .route("/rpc/simulate-multi", post(handle_simulate_multi))
         
         // Configuration endpoints
         .route("/config", get(handle_get_config).post(handle_update_config))
         .route("/config/reload", post(handle_reload_config))
+        .route("/config/schema", get(handle_config_schema))
         
         // Authentication endpoints
         .route("/auth/login", post(auth::handle_login))
@@ -172,61 +540,198 @@ async fn main() -> Result<(), AppError> {
         // Debug endpoints (development only)
         .route("/debug/consensus", get(handle_debug_consensus))
         .route("/debug/cache", get(handle_debug_cache))
-        
+        .route("/debug/cache/prefill", post(handle_debug_cache_prefill))
+        .route("/debug/trace/:request_id", get(request_trace::handle_get_trace));
+
+    if app_state.graphql.enabled {
+        router = router.route("/graphql", get(graphql::handle_graphql_get)
+            .post(graphql::handle_graphql)
+            .layer(Extension(graphql::build_schema())));
+        if app_state.graphql.playground_enabled {
+            router = router.route("/graphql/playground", get(graphql::handle_playground));
+        }
+    }
+
+    router
         // Apply middleware
-        .layer(middleware::from_fn_with_state(
+        .layer(axum::middleware::from_fn_with_state(
+            app_state.clone(),
+            shaping::egress_shaping_middleware,
+        ))
+        .layer(axum::middleware::from_fn_with_state(
             app_state.clone(),
             AuthMiddleware::middleware,
         ))
+        .layer(axum::middleware::from_fn_with_state(
+            app_state.clone(),
+            body_logger::body_logging_middleware,
+        ))
+        .layer(axum::middleware::from_fn(RequestContextPropagator::middleware))
+        .layer(axum::middleware::from_fn_with_state(
+            app_state.clone(),
+            request_trace::request_tracing_middleware,
+        ))
+        .layer(BodySizeLimitLayer::new(max_request_body_bytes))
+        .layer(axum::middleware::from_fn_with_state(
+            app_state.clone(),
+            access_control::access_control_middleware,
+        ))
+        .layer(axum::middleware::from_fn(propagation::request_id_middleware))
         .layer(CorsLayer::permissive())
-        .with_state(app_state);
+        .with_state(app_state)
+}
 
-    // Start the server
-    info!("Attempting to bind to address: {}", config.bind_address);
-    let listener = match TcpListener::bind(&config.bind_address).await {
-        Ok(listener) => {
-            info!("Successfully bound to {}", config.bind_address);
-            listener
-        }
-        Err(e) => {
-            error!("Failed to bind to {}: {}", config.bind_address, e);
-            return Err(AppError::from(e));
-        }
+async fn handle_rpc_request(
+    State(state): State<Arc<AppState>>,
+    headers: HeaderMap,
+    auth: Option<Extension<AuthContext>>,
+    whitelisted: Option<Extension<Whitelisted>>,
+    Json(payload): Json<serde_json::Value>,
+) -> Result<Response, AppError> {
+    let auth = auth.map(|Extension(context)| context);
+    let whitelisted = whitelisted.is_some();
+    let Some(audit) = state.audit_service.clone() else {
+        return route_rpc_request(&state, &headers, auth.as_ref(), whitelisted, payload).await;
     };
-    
-    info!("🚀 Multi-RPC Enterprise server starting on {}", config.bind_address);
-    info!("📊 Admin dashboard available at http://{}/admin", config.bind_address);
-    info!("🔌 WebSocket endpoint available at ws://{}/ws", config.bind_address);
-    info!("🏥 Health check available at http://{}/health", config.bind_address);
-    
-    info!("Server is ready to accept connections");
-    
-    match axum::serve(listener, app).await {
-        Ok(_) => {
-            info!("Server shut down gracefully");
-            Ok(())
-        }
-        Err(e) => {
-            error!("Server error: {}", e);
-            Err(AppError::from(e))
+
+    let started = std::time::Instant::now();
+    let calls = audit::audited_calls(&payload);
+    let response = route_rpc_request(&state, &headers, auth.as_ref(), whitelisted, payload).await
+        .unwrap_or_else(IntoResponse::into_response);
+    let client_ip = audit::client_ip(auth.as_ref(), &headers);
+    let api_key_id = auth.and_then(|context| context.api_key).map(|key| audit::api_key_id(&key));
+    for (method, params_hash) in calls {
+        audit.log_request(AuditRecord {
+            timestamp: Utc::now(),
+            client_ip: client_ip.clone(),
+            api_key_id: api_key_id.clone(),
+            method,
+            params_hash,
+            status: response.status().as_u16(),
+            latency_ms: started.elapsed().as_millis() as u64,
+        }).await;
+    }
+    Ok(response)
+}
+
+async fn route_rpc_request(
+    state: &Arc<AppState>,
+    headers: &HeaderMap,
+    auth: Option<&AuthContext>,
+    whitelisted: bool,
+    payload: serde_json::Value,
+) -> Result<Response, AppError> {
+    // Whitelisted addresses aren't rate limited at all
+    if !whitelisted {
+        state.rate_limit_service.check_request(&payload, auth, headers).await?;
+    }
+    let session_id = router::session_key(headers);
+    if let Some(response) = state.rpc_router.route_streaming_request(&payload, session_id.as_deref(), Some(headers)).await? {
+        return Ok(response);
+    }
+
+    // Single calls already in L1 carry an ETag, and a client that holds that version gets a 304
+    let cached_etag = match cache::conditional_call(&payload) {
+        Some((method, params)) => state.cache_service.cached_etag(method, &params).await,
+        None => None,
+    };
+    if let Some(etag) = cached_etag.clone().filter(|etag| cache::etag_matches(headers, etag)) {
+        state.metrics_service.record_cache_hit();
+        return Ok(CachedResponse::NotModified { etag }.into_response());
+    }
+
+    let response = state.rpc_router.route_request(payload.clone(), None, session_id.as_deref(), Some(headers)).await?;
+    state.rpc_router.spawn_post_request_hooks(state.clone(), &payload, &response);
+
+    // Tell clients to slow down before the pools are exhausted and requests start failing
+    let pressure = state.endpoint_manager.pressure_factor().await;
+    if pressure > endpoints::PRESSURE_REJECT_THRESHOLD {
+        state.metrics_service.record_backpressure_signal("rejected");
+        return Err(AppError::TransactionUnderPressure(pressure));
+    }
+    let mut response = match cached_etag {
+        Some(_) => CachedResponse::Hit { etag: cache::etag(&response), body: response }.into_response(),
+        None => Json(response).into_response(),
+    };
+    if pressure > endpoints::PRESSURE_ADVISORY_THRESHOLD {
+        state.metrics_service.record_backpressure_signal("header");
+        if let Ok(value) = HeaderValue::from_str(&format!("{:.2}", pressure)) {
+            response.headers_mut().insert(error::X_BACKEND_PRESSURE, value);
         }
     }
+    Ok(response)
 }
 
-async fn handle_rpc_request(
+async fn handle_simulate_multi(
     State(state): State<Arc<AppState>>,
-    Json(payload): Json<serde_json::Value>,
-) -> Result<Json<serde_json::Value>, AppError> {
-    let response = state.rpc_router.route_request(payload, None).await?;
-    Ok(Json(response))
+    headers: HeaderMap,
+    auth: Option<Extension<AuthContext>>,
+    whitelisted: Option<Extension<Whitelisted>>,
+    Json(request): Json<consensus::SimulationRequest>,
+) -> Result<Json<consensus::SimulationConsensus>, AppError> {
+    // Limited like the simulateTransaction call it stands in for on POST /
+    if whitelisted.is_none() {
+        let call = json!({"jsonrpc": "2.0", "id": 1, "method": "simulateTransaction"});
+        let auth = auth.map(|Extension(context)| context);
+        state.rate_limit_service.check_request(&call, auth.as_ref(), &headers).await?;
+    }
+
+    let mut healthy: Vec<_> = state.endpoint_manager.get_endpoint_info().await
+        .into_iter()
+        .filter(|e| e.status == types::EndpointStatus::Healthy)
+        .collect();
+    healthy.sort_by(|a, b| b.score.success_rate.partial_cmp(&a.score.success_rate)
+        .unwrap_or(std::cmp::Ordering::Equal)
+        .then(a.score.avg_response_time.partial_cmp(&b.score.avg_response_time)
+            .unwrap_or(std::cmp::Ordering::Equal)));
+
+    let mut endpoints = Vec::new();
+    for endpoint in healthy.into_iter().take(3) {
+        if let Some(client) = state.endpoint_manager.get_endpoint_client(endpoint.id).await {
+            endpoints.push((endpoint, client));
+        }
+    }
+
+    let result = state.consensus_service
+        .validate_transaction_simulation(request, endpoints)
+        .await?;
+    if !result.consensus_achieved {
+        state.metrics_service.record_simulation_consensus_failure();
+    }
+
+    Ok(Json(result))
 }
 
 async fn handle_websocket_upgrade(
     ws: WebSocketUpgrade,
     State(state): State<Arc<AppState>>,
-) -> impl IntoResponse {
+    Query(params): Query<HashMap<String, String>>,
+    headers: HeaderMap,
+) -> Result<impl IntoResponse, AppError> {
+    let auth_context = state.auth_service
+        .authenticate_websocket(params.get("api_key").map(String::as_str), audit::client_ip(None, &headers))
+        .await?;
     let websocket_service = state.websocket_service.clone();
-    ws.on_upgrade(move |socket| websocket_service.handle_connection(socket))
+    // The connection holds its slot for as long as it stays open
+    let bulkhead = state.bulkheads.get_or_default("websocket");
+    Ok(ws.on_upgrade(move |socket| async move {
+        let connection = bulkhead.execute(|| async {
+            websocket_service.handle_connection(socket, auth_context).await;
+            Ok(())
+        });
+        if let Err(e) = connection.await {
+            warn!("Rejected WebSocket connection: {}", e);
+        }
+    }))
+}
+
+async fn handle_admin_broadcast(
+    State(state): State<Arc<AppState>>,
+    Json(notification): Json<websocket::AdminNotification>,
+) -> Result<Json<serde_json::Value>, AppError> {
+    let recipients = state.websocket_service.broadcast_admin_notification(notification).await?;
+    state.metrics_service.record_admin_broadcast(recipients);
+    Ok(Json(json!({"status": "broadcast", "recipients": recipients})))
 }
 
 async fn handle_health(
@@ -245,6 +750,59 @@ async fn handle_health(
     })))
 }
 
+async fn handle_detailed_health(State(state): State<Arc<AppState>>) -> Json<HealthReport> {
+    Json(state.health_service.detailed_report().await)
+}
+
+// One `data:` message per EndpointStatusEvent; a client that falls behind skips to the latest
+async fn handle_sse_endpoints(
+    State(state): State<Arc<AppState>>,
+) -> impl IntoResponse {
+    let receiver = state.health_service.subscribe_status_events();
+    let events = futures::stream::unfold(receiver, |mut receiver| async move {
+        loop {
+            match receiver.recv().await {
+                Ok(event) => return Some((event, receiver)),
+                Err(RecvError::Lagged(skipped)) => {
+                    warn!("Endpoint status stream skipped {} events", skipped);
+                }
+                Err(RecvError::Closed) => return None,
+            }
+        }
+    });
+    // The stream never ends, so shaping and body logging must not wait for it to finish
+    (
+        [(router::X_ACCEL_BUFFERING, "no")],
+        Sse::new(events.map(|event| Event::default().json_data(event))).keep_alive(KeepAlive::default()),
+    )
+}
+
+// Browsers and uptime probes hitting the RPC URL with a GET get something other than a 405
+async fn handle_root() -> Json<serde_json::Value> {
+    Json(serde_json::json!({
+        "service": "multi-rpc",
+        "version": env!("CARGO_PKG_VERSION"),
+    }))
+}
+
+// Liveness only asks whether the process answers at all
+async fn handle_liveness() -> Json<serde_json::Value> {
+    Json(json!({"status": "alive"}))
+}
+
+async fn handle_readiness(State(state): State<Arc<AppState>>) -> (StatusCode, Json<serde_json::Value>) {
+    let healthy_endpoints = state.health_service.healthy_endpoint_count().await;
+    let cache_initialized = state.cache_service.is_initialized();
+    let ready = state.health_service.is_ready().await && cache_initialized;
+
+    let status = if ready { StatusCode::OK } else { StatusCode::SERVICE_UNAVAILABLE };
+    (status, Json(json!({
+        "status": if ready { "ready" } else { "not_ready" },
+        "healthy_endpoints": healthy_endpoints,
+        "cache_initialized": cache_initialized,
+    })))
+}
+
 async fn handle_endpoints(
     State(state): State<Arc<AppState>>,
 ) -> Result<Json<Vec<types::EndpointInfo>>, AppError> {
@@ -268,9 +826,39 @@ async fn handle_metrics(
 
 async fn handle_prometheus_metrics(
     State(state): State<Arc<AppState>>,
-) -> Result<String, AppError> {
+    headers: HeaderMap,
+) -> Result<Response, AppError> {
+    // Scrapers that prefer OpenMetrics say so in Accept
+    let wants_openmetrics = headers.get(header::ACCEPT)
+        .and_then(|accept| accept.to_str().ok())
+        .is_some_and(|accept| accept.contains("application/openmetrics-text"));
+    if wants_openmetrics {
+        return Ok(Redirect::temporary("/metrics/openmetrics").into_response());
+    }
+
+    alerting::refresh_endpoint_gauges(&state).await;
     let metrics = state.metrics_service.get_prometheus_metrics().await;
-    Ok(metrics)
+    Ok(metrics.into_response())
+}
+
+async fn handle_openmetrics_metrics(
+    State(state): State<Arc<AppState>>,
+) -> Result<Response, AppError> {
+    alerting::refresh_endpoint_gauges(&state).await;
+    let metrics = state.metrics_service.get_openmetrics_metrics().await;
+    Ok(([(header::CONTENT_TYPE, openmetrics::OPENMETRICS_CONTENT_TYPE)], metrics).into_response())
+}
+
+async fn handle_cluster_prometheus_metrics(
+    State(state): State<Arc<AppState>>,
+) -> Result<String, AppError> {
+    alerting::refresh_endpoint_gauges(&state).await;
+    let local = state.metrics_service.get_prometheus_metrics().await;
+    let (merged, failures) = state.cluster_metrics.scrape_cluster(local).await;
+    if failures > 0 {
+        state.metrics_service.record_cluster_metrics_fetch_failures(failures);
+    }
+    Ok(merged)
 }
 
 async fn handle_get_config(
@@ -280,19 +868,32 @@ async fn handle_get_config(
     Ok(Json(config))
 }
 
+async fn handle_config_schema() -> Json<serde_json::Value> {
+    Json(Config::json_schema())
+}
+
 async fn handle_update_config(
     State(state): State<Arc<AppState>>,
+    auth: Option<Extension<AuthContext>>,
     Json(config): Json<serde_json::Value>,
 ) -> Result<Json<serde_json::Value>, AppError> {
-    state.endpoint_manager.update_config(config).await?;
-    Ok(Json(serde_json::json!({"status": "updated"})))
+    let changes = state.endpoint_manager.update_config(config).await?;
+    state.audit_logger.log_config_diff(&audit_user(auth.as_ref()), &changes);
+    Ok(Json(serde_json::json!({"status": "updated", "changes": changes})))
 }
 
 async fn handle_reload_config(
     State(state): State<Arc<AppState>>,
+    auth: Option<Extension<AuthContext>>,
 ) -> Result<Json<serde_json::Value>, AppError> {
-    state.endpoint_manager.reload_config().await?;
-    Ok(Json(serde_json::json!({"status": "reloaded"})))
+    let changes = state.endpoint_manager.reload_config().await?;
+    state.audit_logger.log_config_diff(&audit_user(auth.as_ref()), &changes);
+    Ok(Json(serde_json::json!({"status": "reloaded", "changes": changes})))
+}
+
+fn audit_user(auth: Option<&Extension<AuthContext>>) -> String {
+    auth.and_then(|Extension(context)| context.user.clone())
+        .unwrap_or_else(|| "anonymous".to_string())
 }
 
 async fn handle_geo_endpoints(
@@ -316,4 +917,27 @@ async fn handle_debug_cache(
 ) -> Result<Json<serde_json::Value>, AppError> {
     let cache_debug = state.cache_service.get_debug_info().await;
     Ok(Json(cache_debug))
+}
+
+#[derive(serde::Deserialize)]
+struct CachePrefillRequest {
+    fixture_path: String,
+}
+
+// Writes straight into the cache, so it takes the same rights as the /admin routes
+async fn handle_debug_cache_prefill(
+    State(state): State<Arc<AppState>>,
+    auth: Option<Extension<AuthContext>>,
+    Json(request): Json<CachePrefillRequest>,
+) -> Result<Json<serde_json::Value>, AppError> {
+    if !state.endpoint_manager.current_config().await.enable_debug_endpoints
+        || !state.auth_service.allows_admin(auth.as_ref().map(|Extension(context)| context)) {
+        return Err(AppError::Forbidden);
+    }
+
+    let inserted = state.cache_service.prefill_from_fixture(&request.fixture_path).await?;
+    Ok(Json(json!({
+        "status": "prefilled",
+        "entries": inserted,
+    })))
 }
\ No newline at end of file
@@ -0,0 +1,291 @@
+use crate::{
+    cache::{CacheService, DEFAULT_LOCAL_CACHE_ENTRIES},
+    config::MemoryPressureConfig,
+    endpoints::EndpointManager,
+    metrics::MetricsService,
+};
+use std::{
+    sync::{
+        atomic::{AtomicBool, Ordering},
+        Arc,
+    },
+    time::Duration,
+};
+use sysinfo::{Pid, ProcessRefreshKind, System};
+use tokio::{sync::Mutex, time::interval};
+use tokio_util::sync::CancellationToken;
+use tracing::{debug, info, warn};
+
+const BYTES_PER_MB: u64 = 1024 * 1024;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord)]
+pub enum MemoryPressure {
+    Normal,
+    Warning,
+    Critical,
+}
+
+// Source of the process's resident set size, swappable so tests can inject readings
+pub trait MemoryReader: Send + Sync {
+    fn rss_bytes(&self) -> Option<u64>;
+}
+
+// Reads the process's RSS through sysinfo; returns None where it isn't supported
+pub struct ProcessMemoryReader {
+    system: parking_lot::Mutex<System>,
+    pid: Option<Pid>,
+}
+
+impl Default for ProcessMemoryReader {
+    fn default() -> Self {
+        Self {
+            system: parking_lot::Mutex::new(System::new()),
+            pid: sysinfo::get_current_pid().ok(),
+        }
+    }
+}
+
+impl MemoryReader for ProcessMemoryReader {
+    fn rss_bytes(&self) -> Option<u64> {
+        let pid = self.pid?;
+        let mut system = self.system.lock();
+        if !system.refresh_process_specifics(pid, ProcessRefreshKind::new().with_memory()) {
+            return None;
+        }
+        system.process(pid).map(|process| process.memory())
+    }
+}
+
+// Sheds local cache and upstream connections as RSS climbs, before the OOM killer steps in.
+// Pressure only ever escalates; everything is restored at once when RSS falls below the
+// warning threshold again.
+pub struct MemoryPressureReactor {
+    cache_service: Arc<CacheService>,
+    endpoint_manager: Arc<EndpointManager>,
+    metrics_service: Arc<MetricsService>,
+    config: MemoryPressureConfig,
+    reader: Box<dyn MemoryReader>,
+    pressure: Mutex<MemoryPressure>,
+    // Set once the failure to read RSS has been logged, so it is only warned about once
+    unreadable_logged: AtomicBool,
+}
+
+impl MemoryPressureReactor {
+    pub fn new(
+        cache_service: Arc<CacheService>,
+        endpoint_manager: Arc<EndpointManager>,
+        metrics_service: Arc<MetricsService>,
+        config: MemoryPressureConfig,
+    ) -> Self {
+        Self {
+            cache_service,
+            endpoint_manager,
+            metrics_service,
+            config,
+            reader: Box::new(ProcessMemoryReader::default()),
+            pressure: Mutex::new(MemoryPressure::Normal),
+            unreadable_logged: AtomicBool::new(false),
+        }
+    }
+
+    // Tests substitute their own readings for the process RSS
+    #[cfg(test)]
+    pub fn with_reader(mut self, reader: Box<dyn MemoryReader>) -> Self {
+        self.reader = reader;
+        self
+    }
+
+    pub async fn start(self: Arc<Self>, shutdown: CancellationToken) {
+        info!("Starting memory pressure monitoring every {}s", self.config.check_interval_secs);
+        let mut interval = interval(Duration::from_secs(self.config.check_interval_secs.max(1)));
+
+        loop {
+            tokio::select! {
+                _ = shutdown.cancelled() => break,
+                _ = interval.tick() => {
+                    self.check().await;
+                }
+            }
+        }
+
+        info!("Memory pressure monitoring stopped");
+    }
+
+    pub async fn check(&self) -> MemoryPressure {
+        let mut pressure = self.pressure.lock().await;
+        let Some(rss) = self.reader.rss_bytes() else {
+            if self.unreadable_logged.swap(true, Ordering::Relaxed) {
+                debug!("Could not read process RSS, skipping memory pressure check");
+            } else {
+                warn!("Could not read process RSS on this platform; memory pressure monitoring is inactive");
+            }
+            return *pressure;
+        };
+        let rss_mb = rss / BYTES_PER_MB;
+
+        let observed = if rss_mb > self.config.memory_critical_threshold_mb {
+            MemoryPressure::Critical
+        } else if rss_mb > self.config.memory_warning_threshold_mb {
+            MemoryPressure::Warning
+        } else {
+            MemoryPressure::Normal
+        };
+
+        if observed > *pressure {
+            self.escalate(observed, rss_mb).await;
+            *pressure = observed;
+        } else if observed == MemoryPressure::Normal && *pressure != MemoryPressure::Normal {
+            self.restore(rss_mb).await;
+            *pressure = MemoryPressure::Normal;
+        }
+        *pressure
+    }
+
+    async fn escalate(&self, pressure: MemoryPressure, rss_mb: u64) {
+        self.metrics_service.record_memory_pressure_event();
+
+        let evicted = match pressure {
+            MemoryPressure::Warning => {
+                warn!("RSS {}MB above warning threshold of {}MB, halving local cache size",
+                    rss_mb, self.config.memory_warning_threshold_mb);
+                self.cache_service.set_local_cache_limit(DEFAULT_LOCAL_CACHE_ENTRIES / 2).await
+            }
+            MemoryPressure::Critical => {
+                warn!("RSS {}MB above critical threshold of {}MB, clearing local cache, pausing Redis writes \
+                    and capping endpoints at {} connections",
+                    rss_mb, self.config.memory_critical_threshold_mb, self.config.critical_max_connections);
+                self.cache_service.pause_redis_writes(true);
+                self.endpoint_manager.set_connection_limit(Some(self.config.critical_max_connections)).await;
+                self.cache_service.set_local_cache_limit(DEFAULT_LOCAL_CACHE_ENTRIES / 2).await
+                    + self.cache_service.clear_local_cache().await
+            }
+            MemoryPressure::Normal => 0,
+        };
+        self.metrics_service.record_memory_pressure_evictions(evicted);
+    }
+
+    async fn restore(&self, rss_mb: u64) {
+        info!("RSS back down to {}MB, restoring cache and connection limits", rss_mb);
+        self.cache_service.set_local_cache_limit(DEFAULT_LOCAL_CACHE_ENTRIES).await;
+        self.cache_service.pause_redis_writes(false);
+        self.endpoint_manager.set_connection_limit(None).await;
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_server::{shared_metrics, TestServerBuilder};
+    use serde_json::{json, Value};
+    use std::sync::atomic::AtomicU64;
+
+    struct FakeMemoryReader(Arc<AtomicU64>);
+
+    impl MemoryReader for FakeMemoryReader {
+        fn rss_bytes(&self) -> Option<u64> {
+            Some(self.0.load(Ordering::Relaxed) * BYTES_PER_MB)
+        }
+    }
+
+    #[test]
+    fn test_process_reader_reports_rss() {
+        if sysinfo::IS_SUPPORTED_SYSTEM {
+            assert!(ProcessMemoryReader::default().rss_bytes().unwrap() > 0);
+        }
+    }
+
+    struct UnreadableMemoryReader;
+
+    impl MemoryReader for UnreadableMemoryReader {
+        fn rss_bytes(&self) -> Option<u64> {
+            None
+        }
+    }
+
+    #[tokio::test]
+    async fn test_unreadable_rss_is_reported_and_skipped() {
+        let server = TestServerBuilder::new().start().await;
+        let reactor = MemoryPressureReactor::new(
+            server.state.cache_service.clone(),
+            server.state.endpoint_manager.clone(),
+            shared_metrics(),
+            MemoryPressureConfig::default(),
+        ).with_reader(Box::new(UnreadableMemoryReader));
+
+        assert!(!reactor.unreadable_logged.load(Ordering::Relaxed));
+        assert_eq!(reactor.check().await, MemoryPressure::Normal);
+        assert!(reactor.unreadable_logged.load(Ordering::Relaxed));
+        assert_eq!(reactor.check().await, MemoryPressure::Normal);
+    }
+
+    #[tokio::test]
+    async fn test_reacts_to_injected_memory_readings() {
+        let server = TestServerBuilder::new()
+            .with_config(|config| config.cache.enabled = true)
+            .start()
+            .await;
+        let state = &server.state;
+        let rss_mb = Arc::new(AtomicU64::new(100));
+        let reactor = MemoryPressureReactor::new(
+            state.cache_service.clone(),
+            state.endpoint_manager.clone(),
+            shared_metrics(),
+            MemoryPressureConfig {
+                enabled: true,
+                memory_warning_threshold_mb: 500,
+                memory_critical_threshold_mb: 800,
+                ..MemoryPressureConfig::default()
+            },
+        ).with_reader(Box::new(FakeMemoryReader(rss_mb.clone())));
+
+        let response = json!({"jsonrpc": "2.0", "id": 1, "result": 1});
+        for i in 0..6_000 {
+            state.cache_service.set("getAccountInfo", &json!([i.to_string()]), &response).await;
+        }
+        let local_cache_size = || async { state.cache_service.get_stats().await["local_cache_size"].as_u64().unwrap() };
+        let max_connections = || async {
+            state.endpoint_manager.get_stats().await["endpoints"].as_array().unwrap().iter()
+                .map(|endpoint| endpoint["connection_pool"]["max_connections"].as_u64().unwrap())
+                .collect::<Vec<_>>()
+        };
+        let memory_metrics = || async { shared_metrics().get_metrics().await["memory_pressure"].clone() };
+        let counter = |metrics: &Value, name: &str| metrics[name].as_u64().unwrap();
+
+        assert_eq!(reactor.check().await, MemoryPressure::Normal);
+        assert_eq!(local_cache_size().await, 6_000);
+
+        // Warning halves the local cache, evicting down to 80% of the new limit
+        let before = memory_metrics().await;
+        rss_mb.store(600, Ordering::Relaxed);
+        assert_eq!(reactor.check().await, MemoryPressure::Warning);
+        assert_eq!(state.cache_service.local_cache_limit(), DEFAULT_LOCAL_CACHE_ENTRIES / 2);
+        assert_eq!(local_cache_size().await, 4_000);
+        let after = memory_metrics().await;
+        assert_eq!(counter(&after, "events"), counter(&before, "events") + 1);
+        assert_eq!(counter(&after, "evictions"), counter(&before, "evictions") + 2_000);
+        assert!(!state.cache_service.redis_writes_paused());
+
+        // Critical clears the cache, pauses Redis writes and caps connections
+        rss_mb.store(900, Ordering::Relaxed);
+        assert_eq!(reactor.check().await, MemoryPressure::Critical);
+        assert_eq!(local_cache_size().await, 0);
+        assert!(state.cache_service.redis_writes_paused());
+        assert!(max_connections().await.iter().all(|max| *max == 10));
+        let critical = memory_metrics().await;
+        assert_eq!(counter(&critical, "events"), counter(&before, "events") + 2);
+        assert_eq!(counter(&critical, "evictions"), counter(&before, "evictions") + 6_000);
+
+        // Dropping back between the thresholds isn't enough to restore anything
+        rss_mb.store(600, Ordering::Relaxed);
+        assert_eq!(reactor.check().await, MemoryPressure::Critical);
+        assert!(state.cache_service.redis_writes_paused());
+
+        // Below the warning threshold everything goes back to the defaults
+        rss_mb.store(400, Ordering::Relaxed);
+        assert_eq!(reactor.check().await, MemoryPressure::Normal);
+        assert_eq!(state.cache_service.local_cache_limit(), DEFAULT_LOCAL_CACHE_ENTRIES);
+        assert!(!state.cache_service.redis_writes_paused());
+        assert!(max_connections().await.iter().all(|max| *max == 100));
+        assert_eq!(counter(&memory_metrics().await, "events"), counter(&before, "events") + 2);
+    }
+}
@@ -1,57 +1,86 @@
-use crate::error::AppError;
+use crate::{error::AppError, openmetrics, types::LatencyPercentiles};
 use prometheus::{
-    register_counter, register_gauge, register_histogram, register_int_counter, register_int_gauge,
-    Counter, Encoder, Gauge, Histogram, IntCounter, IntGauge, Registry, TextEncoder,
+    core::Collector, register_gauge_vec, register_histogram, register_histogram_vec, register_int_counter,
+    register_int_counter_vec, register_int_gauge, register_int_gauge_vec, GaugeVec, Histogram, HistogramVec,
+    IntCounter, IntCounterVec, IntGauge, IntGaugeVec, TextEncoder,
 };
+use serde::{Deserialize, Serialize};
 use serde_json::{json, Value};
 use std::{
     collections::HashMap,
-    sync::{
-        atomic::{AtomicU64, Ordering},
-        Arc,
-    },
-    time::{Duration, Instant},
+    sync::Arc,
+    time::{Duration, Instant, SystemTime, UNIX_EPOCH},
 };
 use tokio::sync::RwLock;
 use tracing::{debug, error};
-use uuid::Uuid;
 
 #[derive(Debug, Clone)]
 pub struct MetricsService {
-    registry: Registry,
-    
-    // Request metrics
-    requests_total: IntCounter,
-    requests_duration: Histogram,
-    requests_by_method: Arc<RwLock<HashMap<String, IntCounter>>>,
-    requests_by_endpoint: Arc<RwLock<HashMap<String, IntCounter>>>,
+    // Request metrics, labelled by endpoint and method
+    requests_total: IntCounterVec,
+    requests_duration: HistogramVec,
+    request_failures: IntCounterVec,
+    response_size_bytes: HistogramVec,
+    batch_splits: IntCounter,
+    batch_split_sub_requests: IntCounter,
+    decompressed_requests: IntCounter,
+    decompressed_bytes: IntCounter,
+    deduplicated_requests: IntCounter,
+    scheduler_queue_depth: IntGaugeVec,
     
     // Endpoint metrics
     endpoints_healthy: IntGauge,
     endpoints_total: IntGauge,
-    endpoint_response_time: Arc<RwLock<HashMap<String, Gauge>>>,
-    endpoint_success_rate: Arc<RwLock<HashMap<String, Gauge>>>,
+    endpoint_response_time: GaugeVec,
+    endpoint_success_rate: GaugeVec,
+    endpoint_latency_percentiles: GaugeVec,
+    endpoint_circuit_breaker_open: IntGaugeVec,
+    latency_anomalies: IntCounter,
+    freshest_data_slot_lag: Histogram,
+    power_of_two_selections: IntCounter,
+    avg_connection_differential: Histogram,
+    tls_cert_expiry_warnings: IntCounter,
+    endpoint_promotions: IntCounter,
+    peer_sync_events: IntCounter,
+    peer_sync_latency: Histogram,
+    epoch_transitions_detected: IntCounter,
     
     // Cache metrics
     cache_hits: IntCounter,
     cache_misses: IntCounter,
     cache_size: IntGauge,
+    prefetch_requests: IntCounter,
+    prefetch_cache_hits: IntCounter,
+    cache_bootstrapped_entries: IntCounter,
+    response_transform_applications: IntCounterVec,
+    fallback_responses_served: IntCounterVec,
+    gossip_sent: IntCounter,
+    gossip_received: IntCounter,
+    gossip_bytes: IntCounter,
     
     // WebSocket metrics
     websocket_connections: IntGauge,
     websocket_subscriptions: IntGauge,
     websocket_messages: IntCounter,
+    admin_broadcasts: IntCounter,
+    admin_broadcast_recipients: IntCounter,
+    subscription_dedup_hits: IntCounter,
     
     // Consensus metrics
     consensus_requests: IntCounter,
     consensus_successes: IntCounter,
     consensus_failures: IntCounter,
     consensus_duration: Histogram,
+    simulation_consensus_failures: IntCounter,
     
     // Error metrics
     errors_total: IntCounter,
     errors_by_type: Arc<RwLock<HashMap<String, IntCounter>>>,
     
+    // Per-tenant request counts, keyed by the JWT `tid` claim
+    tenant_requests: IntCounterVec,
+    tenant_metrics: Arc<RwLock<HashMap<String, IntCounter>>>,
+    
     // Authentication metrics
     auth_requests: IntCounter,
     auth_successes: IntCounter,
@@ -59,6 +88,29 @@ pub struct MetricsService {
     
     // Rate limiting metrics
     rate_limited_requests: IntCounter,
+    rate_limit_exemptions_applied: IntCounterVec,
+    global_method_limit_exceeded: IntCounterVec,
+    backpressure_signals: IntCounterVec,
+    egress_shaped_bytes: IntCounter,
+    egress_wait_duration: Histogram,
+    
+    // Chaos testing metrics
+    partition_blocked_requests: IntCounter,
+    
+    // Cluster aggregation metrics
+    cluster_metrics_fetch_failures: IntCounter,
+    
+    // Memory pressure metrics
+    memory_pressure_events: IntCounter,
+    memory_pressure_evictions: IntCounter,
+    
+    // Config auto-reload metrics
+    config_auto_reloads: IntCounter,
+    config_auto_reload_failures: IntCounter,
+    
+    // Service discovery metrics
+    service_discovery_endpoint_additions: IntCounter,
+    service_discovery_endpoint_removals: IntCounter,
     
     // Custom metrics storage
     custom_metrics: Arc<RwLock<HashMap<String, CustomMetric>>>,
@@ -75,7 +127,16 @@ pub struct CustomMetric {
     pub metric_type: CustomMetricType,
 }
 
-#[derive(Debug, Clone)]
+// A custom metric as carried by a state snapshot; its age restarts on import
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct CustomMetricSnapshot {
+    pub name: String,
+    pub value: f64,
+    pub labels: HashMap<String, String>,
+    pub metric_type: CustomMetricType,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
 pub enum CustomMetricType {
     Counter,
     Gauge,
@@ -84,19 +145,81 @@ pub enum CustomMetricType {
 
 impl MetricsService {
     pub fn new() -> Self {
-        let registry = Registry::new();
-        
-        let requests_total = register_int_counter!(
+        let requests_total = register_int_counter_vec!(
             "multi_rpc_requests_total",
-            "Total number of RPC requests"
+            "Total number of RPC requests sent upstream",
+            &["endpoint", "method"]
         ).expect("Failed to create requests_total metric");
         
-        let requests_duration = register_histogram!(
+        let requests_duration = register_histogram_vec!(
             "multi_rpc_request_duration_seconds",
             "Duration of RPC requests in seconds",
+            &["endpoint", "method"],
             vec![0.001, 0.005, 0.01, 0.025, 0.05, 0.1, 0.25, 0.5, 1.0, 2.5, 5.0, 10.0]
         ).expect("Failed to create requests_duration metric");
         
+        let request_failures = register_int_counter_vec!(
+            "multi_rpc_request_failures_total",
+            "Total number of RPC requests that failed upstream",
+            &["endpoint", "method"]
+        ).expect("Failed to create request_failures metric");
+        
+        let response_size_bytes = register_histogram_vec!(
+            "multi_rpc_response_size_bytes",
+            "Size of upstream RPC response bodies in bytes",
+            &["endpoint", "method"],
+            vec![256.0, 1024.0, 4096.0, 16384.0, 65536.0, 262144.0, 1048576.0, 4194304.0, 16777216.0]
+        ).expect("Failed to create response_size_bytes metric");
+        
+        let endpoint_response_time = register_gauge_vec!(
+            "multi_rpc_endpoint_response_time_ms",
+            "Response time of the last request to each endpoint in milliseconds",
+            &["endpoint"]
+        ).expect("Failed to create endpoint_response_time metric");
+        
+        let endpoint_circuit_breaker_open = register_int_gauge_vec!(
+            "multi_rpc_endpoint_circuit_breaker_open",
+            "Whether each endpoint's circuit breaker is open (1) or not (0)",
+            &["endpoint"]
+        ).expect("Failed to create endpoint_circuit_breaker_open metric");
+        
+        let endpoint_success_rate = register_gauge_vec!(
+            "multi_rpc_endpoint_success_rate",
+            "Whether the last request to each endpoint succeeded",
+            &["endpoint"]
+        ).expect("Failed to create endpoint_success_rate metric");
+        
+        let endpoint_latency_percentiles = register_gauge_vec!(
+            "multi_rpc_endpoint_latency_percentile_ms",
+            "Response time percentiles over each endpoint's most recent requests in milliseconds",
+            &["endpoint", "quantile"]
+        ).expect("Failed to create endpoint_latency_percentiles metric");
+        
+        let batch_splits = register_int_counter!(
+            "multi_rpc_batch_splits_total",
+            "Total number of requests split into several upstream calls"
+        ).expect("Failed to create batch_splits metric");
+        
+        let batch_split_sub_requests = register_int_counter!(
+            "multi_rpc_batch_split_sub_requests_total",
+            "Total number of upstream calls issued for split requests"
+        ).expect("Failed to create batch_split_sub_requests metric");
+        
+        let decompressed_requests = register_int_counter!(
+            "multi_rpc_decompressed_requests_total",
+            "Total number of requests received with a compressed body"
+        ).expect("Failed to create decompressed_requests metric");
+        
+        let decompressed_bytes = register_int_counter!(
+            "multi_rpc_decompressed_bytes_total",
+            "Total size of compressed request bodies after decompression"
+        ).expect("Failed to create decompressed_bytes metric");
+        
+        let deduplicated_requests = register_int_counter!(
+            "multi_rpc_deduplicated_requests_total",
+            "Total number of requests answered by an identical request already in flight"
+        ).expect("Failed to create deduplicated_requests metric");
+        
         let endpoints_healthy = register_int_gauge!(
             "multi_rpc_endpoints_healthy",
             "Number of healthy endpoints"
@@ -107,6 +230,98 @@ impl MetricsService {
             "Total number of configured endpoints"
         ).expect("Failed to create endpoints_total metric");
         
+        let tenant_requests = register_int_counter_vec!(
+            "multi_rpc_tenant_requests_total",
+            "Total number of requests per tenant",
+            &["tenant"]
+        ).expect("Failed to create tenant_requests metric");
+        
+        let latency_anomalies = register_int_counter!(
+            "multi_rpc_latency_anomalies_total",
+            "Total number of detected endpoint latency anomalies"
+        ).expect("Failed to create latency_anomalies metric");
+        
+        let freshest_data_slot_lag = register_histogram!(
+            "multi_rpc_freshest_data_slot_lag",
+            "Slots between the freshest known endpoint and the one selected for freshness routing",
+            vec![0.0, 1.0, 2.0, 5.0, 10.0, 25.0, 50.0, 100.0, 500.0]
+        ).expect("Failed to create freshest_data_slot_lag metric");
+        
+        let power_of_two_selections = register_int_counter!(
+            "multi_rpc_power_of_two_selections_total",
+            "Total number of endpoints selected by comparing two random candidates"
+        ).expect("Failed to create power_of_two_selections metric");
+        
+        let avg_connection_differential = register_histogram!(
+            "multi_rpc_avg_connection_differential",
+            "Difference in active connections between the two power-of-two-choices candidates",
+            vec![0.0, 1.0, 2.0, 5.0, 10.0, 25.0, 50.0, 100.0]
+        ).expect("Failed to create avg_connection_differential metric");
+        
+        let tls_cert_expiry_warnings = register_int_counter!(
+            "multi_rpc_tls_cert_expiry_warnings_total",
+            "Total number of endpoint TLS certificates found close to expiry"
+        ).expect("Failed to create tls_cert_expiry_warnings metric");
+        
+        let endpoint_promotions = register_int_counter!(
+            "multi_rpc_endpoint_promotions_total",
+            "Total number of auto-discovered endpoints promoted to managed endpoints"
+        ).expect("Failed to create endpoint_promotions metric");
+        
+        let peer_sync_events = register_int_counter!(
+            "multi_rpc_peer_sync_events_total",
+            "Total number of endpoint health updates applied from peer nodes"
+        ).expect("Failed to create peer_sync_events metric");
+        
+        let peer_sync_latency = register_histogram!(
+            "multi_rpc_peer_sync_latency_ms",
+            "Milliseconds between a peer observing an endpoint status change and this node applying it",
+            vec![1.0, 5.0, 10.0, 25.0, 50.0, 100.0, 250.0, 500.0, 1000.0, 5000.0]
+        ).expect("Failed to create peer_sync_latency metric");
+        
+        let epoch_transitions_detected = register_int_counter!(
+            "multi_rpc_epoch_transitions_detected_total",
+            "Total number of approaching Solana epoch boundaries detected"
+        ).expect("Failed to create epoch_transitions_detected metric");
+        
+        let cache_bootstrapped_entries = register_int_counter!(
+            "multi_rpc_cache_bootstrapped_entries_total",
+            "Total number of consensus cache entries pre-seeded from a snapshot on startup"
+        ).expect("Failed to create cache_bootstrapped_entries metric");
+        
+        let response_transform_applications = register_int_counter_vec!(
+            "multi_rpc_response_transform_applications_total",
+            "Total number of times a response transform step changed a response",
+            &["step"]
+        ).expect("Failed to create response_transform_applications metric");
+        
+        let fallback_responses_served = register_int_counter_vec!(
+            "multi_rpc_fallback_responses_served_total",
+            "Total number of static fallback responses served while all endpoints were unhealthy",
+            &["method"]
+        ).expect("Failed to create fallback_responses_served metric");
+        
+        let scheduler_queue_depth = register_int_gauge_vec!(
+            "multi_rpc_scheduler_queue_depth",
+            "Upstream calls waiting for a scheduler slot, by priority class",
+            &["class"]
+        ).expect("Failed to create scheduler_queue_depth metric");
+        
+        let gossip_sent = register_int_counter!(
+            "multi_rpc_gossip_sent_total",
+            "Total number of cache gossip packets sent to peers"
+        ).expect("Failed to create gossip_sent metric");
+        
+        let gossip_received = register_int_counter!(
+            "multi_rpc_gossip_received_total",
+            "Total number of cache gossip packets received from peers"
+        ).expect("Failed to create gossip_received metric");
+        
+        let gossip_bytes = register_int_counter!(
+            "multi_rpc_gossip_bytes_total",
+            "Total number of cache gossip bytes sent to peers"
+        ).expect("Failed to create gossip_bytes metric");
+        
         let cache_hits = register_int_counter!(
             "multi_rpc_cache_hits_total",
             "Total number of cache hits"
@@ -117,6 +332,16 @@ impl MetricsService {
             "Total number of cache misses"
         ).expect("Failed to create cache_misses metric");
         
+        let prefetch_requests = register_int_counter!(
+            "multi_rpc_prefetch_requests_total",
+            "Total number of speculative prefetch requests"
+        ).expect("Failed to create prefetch_requests metric");
+        
+        let prefetch_cache_hits = register_int_counter!(
+            "multi_rpc_prefetch_cache_hits_total",
+            "Total number of requests served by a prefetched entry"
+        ).expect("Failed to create prefetch_cache_hits metric");
+        
         let cache_size = register_int_gauge!(
             "multi_rpc_cache_size",
             "Current cache size in entries"
@@ -137,6 +362,21 @@ impl MetricsService {
             "Total number of WebSocket messages"
         ).expect("Failed to create websocket_messages metric");
         
+        let admin_broadcasts = register_int_counter!(
+            "multi_rpc_admin_broadcasts_total",
+            "Total number of admin notifications broadcast to WebSocket clients"
+        ).expect("Failed to create admin_broadcasts metric");
+        
+        let admin_broadcast_recipients = register_int_counter!(
+            "multi_rpc_admin_broadcast_recipients_total",
+            "Total number of WebSocket connections targeted by admin notifications"
+        ).expect("Failed to create admin_broadcast_recipients metric");
+        
+        let subscription_dedup_hits = register_int_counter!(
+            "multi_rpc_subscription_dedup_hits_total",
+            "Total number of duplicate WebSocket subscribe requests answered with an existing subscription"
+        ).expect("Failed to create subscription_dedup_hits metric");
+        
         let consensus_requests = register_int_counter!(
             "multi_rpc_consensus_requests_total",
             "Total number of consensus requests"
@@ -158,6 +398,11 @@ impl MetricsService {
             vec![0.01, 0.025, 0.05, 0.1, 0.25, 0.5, 1.0, 2.0, 5.0, 10.0]
         ).expect("Failed to create consensus_duration metric");
         
+        let simulation_consensus_failures = register_int_counter!(
+            "multi_rpc_simulation_consensus_failures_total",
+            "Total number of multi-endpoint simulations without consensus"
+        ).expect("Failed to create simulation_consensus_failures metric");
+        
         let errors_total = register_int_counter!(
             "multi_rpc_errors_total",
             "Total number of errors"
@@ -182,68 +427,160 @@ impl MetricsService {
             "multi_rpc_rate_limited_requests_total",
             "Total number of rate limited requests"
         ).expect("Failed to create rate_limited_requests metric");
+        
+        let rate_limit_exemptions_applied = register_int_counter_vec!(
+            "multi_rpc_rate_limit_exemptions_applied_total",
+            "Total number of requests that bypassed rate limiting through an exemption",
+            &["exemption"]
+        ).expect("Failed to create rate_limit_exemptions_applied metric");
+        
+        let global_method_limit_exceeded = register_int_counter_vec!(
+            "multi_rpc_global_method_limit_exceeded_total",
+            "Total number of requests rejected by a method's limit across all clients",
+            &["method"]
+        ).expect("Failed to create global_method_limit_exceeded metric");
+        
+        let backpressure_signals = register_int_counter_vec!(
+            "multi_rpc_backpressure_signals_total",
+            "Total number of responses telling the client to slow down because the connection pools are nearly full",
+            &["signal"]
+        ).expect("Failed to create backpressure_signals metric");
+        
+        let egress_shaped_bytes = register_int_counter!(
+            "multi_rpc_egress_shaped_bytes_total",
+            "Total number of response bytes passed through the egress shaper"
+        ).expect("Failed to create egress_shaped_bytes metric");
+        
+        let egress_wait_duration = register_histogram!(
+            "multi_rpc_egress_wait_duration_seconds",
+            "Time responses spent waiting for egress bandwidth in seconds",
+            vec![0.001, 0.01, 0.05, 0.1, 0.25, 0.5, 1.0, 2.5, 5.0, 10.0]
+        ).expect("Failed to create egress_wait_duration metric");
+        
+        let partition_blocked_requests = register_int_counter!(
+            "multi_rpc_partition_blocked_requests_total",
+            "Total number of requests dropped by a simulated network partition"
+        ).expect("Failed to create partition_blocked_requests metric");
+        
+        let cluster_metrics_fetch_failures = register_int_counter!(
+            "multi_rpc_cluster_metrics_fetch_failures_total",
+            "Total number of failed metrics scrapes from cluster peers"
+        ).expect("Failed to create cluster_metrics_fetch_failures metric");
+        
+        let memory_pressure_events = register_int_counter!(
+            "multi_rpc_memory_pressure_events_total",
+            "Total number of escalations to the warning or critical memory pressure level"
+        ).expect("Failed to create memory_pressure_events metric");
+        
+        let memory_pressure_evictions = register_int_counter!(
+            "multi_rpc_memory_pressure_evictions_total",
+            "Total number of local cache entries evicted to relieve memory pressure"
+        ).expect("Failed to create memory_pressure_evictions metric");
+        
+        let config_auto_reloads = register_int_counter!(
+            "multi_rpc_config_auto_reload_total",
+            "Total number of config reloads triggered by changes to the config file"
+        ).expect("Failed to create config_auto_reloads metric");
+        
+        let config_auto_reload_failures = register_int_counter!(
+            "multi_rpc_config_auto_reload_failures_total",
+            "Total number of changed config files rejected by parsing or validation"
+        ).expect("Failed to create config_auto_reload_failures metric");
+        
+        let service_discovery_endpoint_additions = register_int_counter!(
+            "multi_rpc_service_discovery_endpoint_additions_total",
+            "Total number of endpoints added because they appeared in the service registry"
+        ).expect("Failed to create service_discovery_endpoint_additions metric");
+        
+        let service_discovery_endpoint_removals = register_int_counter!(
+            "multi_rpc_service_discovery_endpoint_removals_total",
+            "Total number of endpoints drained because they disappeared from the service registry"
+        ).expect("Failed to create service_discovery_endpoint_removals metric");
 
         Self {
-            registry,
             requests_total,
             requests_duration,
-            requests_by_method: Arc::new(RwLock::new(HashMap::new())),
-            requests_by_endpoint: Arc::new(RwLock::new(HashMap::new())),
+            request_failures,
+            response_size_bytes,
+            batch_splits,
+            batch_split_sub_requests,
+            decompressed_requests,
+            decompressed_bytes,
+            deduplicated_requests,
+            scheduler_queue_depth,
             endpoints_healthy,
             endpoints_total,
-            endpoint_response_time: Arc::new(RwLock::new(HashMap::new())),
-            endpoint_success_rate: Arc::new(RwLock::new(HashMap::new())),
+            endpoint_response_time,
+            endpoint_success_rate,
+            endpoint_latency_percentiles,
+            endpoint_circuit_breaker_open,
+            latency_anomalies,
+            freshest_data_slot_lag,
+            power_of_two_selections,
+            avg_connection_differential,
+            tls_cert_expiry_warnings,
+            endpoint_promotions,
+            peer_sync_events,
+            peer_sync_latency,
+            epoch_transitions_detected,
             cache_hits,
+            prefetch_requests,
+            prefetch_cache_hits,
+            cache_bootstrapped_entries,
+            response_transform_applications,
+            fallback_responses_served,
+            gossip_sent,
+            gossip_received,
+            gossip_bytes,
             cache_misses,
             cache_size,
             websocket_connections,
             websocket_subscriptions,
             websocket_messages,
+            admin_broadcasts,
+            admin_broadcast_recipients,
+            subscription_dedup_hits,
             consensus_requests,
             consensus_successes,
             consensus_failures,
             consensus_duration,
+            simulation_consensus_failures,
             errors_total,
             errors_by_type: Arc::new(RwLock::new(HashMap::new())),
+            tenant_requests,
+            tenant_metrics: Arc::new(RwLock::new(HashMap::new())),
             auth_requests,
             auth_successes,
             auth_failures,
             rate_limited_requests,
+            rate_limit_exemptions_applied,
+            global_method_limit_exceeded,
+            backpressure_signals,
+            egress_shaped_bytes,
+            egress_wait_duration,
+            partition_blocked_requests,
+            cluster_metrics_fetch_failures,
+            memory_pressure_events,
+            memory_pressure_evictions,
+            config_auto_reloads,
+            config_auto_reload_failures,
+            service_discovery_endpoint_additions,
+            service_discovery_endpoint_removals,
             custom_metrics: Arc::new(RwLock::new(HashMap::new())),
             start_time: Instant::now(),
         }
     }
 
     // Request metrics
-    pub async fn record_request(&self, method: &str, endpoint_id: Option<Uuid>, duration: Duration) {
-        self.requests_total.inc();
-        self.requests_duration.observe(duration.as_secs_f64());
-        
-        // Track by method
-        {
-            let mut methods = self.requests_by_method.write().await;
-            let counter = methods.entry(method.to_string()).or_insert_with(|| {
-                register_int_counter!(
-                    format!("multi_rpc_requests_method_{}", method.replace(":", "_")),
-                    format!("Requests for method {}", method)
-                ).unwrap_or_else(|_| IntCounter::new("fallback", "fallback").unwrap())
-            });
-            counter.inc();
-        }
-        
-        // Track by endpoint
-        if let Some(id) = endpoint_id {
-            let mut endpoints = self.requests_by_endpoint.write().await;
-            let counter = endpoints.entry(id.to_string()).or_insert_with(|| {
-                register_int_counter!(
-                    format!("multi_rpc_requests_endpoint_{}", id.to_string().replace("-", "_")),
-                    format!("Requests for endpoint {}", id)
-                ).unwrap_or_else(|_| IntCounter::new("fallback", "fallback").unwrap())
-            });
-            counter.inc();
-        }
+    pub fn record_request(&self, endpoint: &str, method: &str, duration: Duration) {
+        self.requests_total.with_label_values(&[endpoint, method]).inc();
+        self.requests_duration.with_label_values(&[endpoint, method]).observe(duration.as_secs_f64());
         
-        debug!("Recorded request: method={}, duration={:?}", method, duration);
+        debug!("Recorded request: endpoint={}, method={}, duration={:?}", endpoint, method, duration);
+    }
+
+    pub fn record_response_size(&self, endpoint: &str, method: &str, size_bytes: usize) {
+        self.response_size_bytes.with_label_values(&[endpoint, method]).observe(size_bytes as f64);
     }
 
     // Endpoint metrics
@@ -252,43 +589,60 @@ impl MetricsService {
         self.endpoints_total.set(total_count as i64);
     }
 
-    pub async fn record_endpoint_stats(&self, endpoint_id: Uuid, endpoint_name: &str, response_time: Duration, success: bool) {
-        let sanitized_name = endpoint_name
-            .replace("https://", "")
-            .replace("http://", "")
-            .replace("/", "_")
-            .replace(":", "_")
-            .replace(".", "_")
-            .replace("-", "_")
-            .replace(" ", "_");
-        let endpoint_key = format!("{}_{}", sanitized_name, endpoint_id.to_string()[..8].to_string());
-        
-        // Response time
-        {
-            let mut response_times = self.endpoint_response_time.write().await;
-            let gauge = response_times.entry(endpoint_key.clone()).or_insert_with(|| {
-                register_gauge!(
-                    format!("multi_rpc_endpoint_response_time_{}", endpoint_key),
-                    format!("Response time for endpoint {}", endpoint_name)
-                ).unwrap_or_else(|_| Gauge::new("fallback", "fallback").unwrap())
-            });
-            gauge.set(response_time.as_millis() as f64);
+    pub fn update_circuit_breaker_open(&self, endpoint: &str, open: bool) {
+        self.endpoint_circuit_breaker_open.with_label_values(&[endpoint]).set(open as i64);
+    }
+
+    pub fn record_endpoint_stats(&self, endpoint: &str, method: &str, response_time: Duration, success: bool) {
+        if !success {
+            self.request_failures.with_label_values(&[endpoint, method]).inc();
         }
-        
-        // Success rate (simplified - in practice you'd track this over time)
-        {
-            let mut success_rates = self.endpoint_success_rate.write().await;
-            let gauge = success_rates.entry(endpoint_key.clone()).or_insert_with(|| {
-                register_gauge!(
-                    format!("multi_rpc_endpoint_success_rate_{}", endpoint_key),
-                    format!("Success rate for endpoint {}", endpoint_name)
-                ).unwrap_or_else(|_| Gauge::new("fallback", "fallback").unwrap())
-            });
-            // This is a simplified version - you'd want to track this as a rolling average
-            gauge.set(if success { 1.0 } else { 0.0 });
+        self.endpoint_response_time.with_label_values(&[endpoint]).set(response_time.as_millis() as f64);
+        // This is a simplified version - you'd want to track this as a rolling average
+        self.endpoint_success_rate.with_label_values(&[endpoint]).set(if success { 1.0 } else { 0.0 });
+    }
+
+    pub fn record_endpoint_latency_percentiles(&self, endpoint: &str, percentiles: &LatencyPercentiles) {
+        for (quantile, value) in [
+            ("0.5", percentiles.p50),
+            ("0.95", percentiles.p95),
+            ("0.99", percentiles.p99),
+            ("0.999", percentiles.p999),
+        ] {
+            self.endpoint_latency_percentiles.with_label_values(&[endpoint, quantile]).set(value);
         }
     }
 
+    pub fn record_latency_anomaly(&self) {
+        self.latency_anomalies.inc();
+    }
+
+    pub fn record_freshest_data_slot_lag(&self, lag: u64) {
+        self.freshest_data_slot_lag.observe(lag as f64);
+    }
+
+    pub fn record_power_of_two_selection(&self, connection_differential: u32) {
+        self.power_of_two_selections.inc();
+        self.avg_connection_differential.observe(connection_differential as f64);
+    }
+
+    pub fn record_tls_cert_expiry_warning(&self) {
+        self.tls_cert_expiry_warnings.inc();
+    }
+
+    pub fn record_endpoint_promotion(&self) {
+        self.endpoint_promotions.inc();
+    }
+
+    pub fn record_peer_sync(&self, latency_ms: f64) {
+        self.peer_sync_events.inc();
+        self.peer_sync_latency.observe(latency_ms);
+    }
+
+    pub fn record_epoch_transition(&self) {
+        self.epoch_transitions_detected.inc();
+    }
+
     // Cache metrics
     pub fn record_cache_hit(&self) {
         self.cache_hits.inc();
@@ -298,10 +652,57 @@ impl MetricsService {
         self.cache_misses.inc();
     }
 
+    pub fn record_cache_bootstrapped_entries(&self, count: usize) {
+        self.cache_bootstrapped_entries.inc_by(count as u64);
+    }
+
+    pub fn record_gossip_sent(&self, bytes: usize) {
+        self.gossip_sent.inc();
+        self.gossip_bytes.inc_by(bytes as u64);
+    }
+
+    pub fn record_gossip_received(&self) {
+        self.gossip_received.inc();
+    }
+
+    pub fn record_response_transform(&self, step: &str) {
+        self.response_transform_applications.with_label_values(&[step]).inc();
+    }
+
+    pub fn record_fallback_response(&self, method: &str) {
+        self.fallback_responses_served.with_label_values(&[method]).inc();
+    }
+
     pub fn update_cache_size(&self, size: usize) {
         self.cache_size.set(size as i64);
     }
 
+    pub fn record_batch_split(&self, sub_requests: usize) {
+        self.batch_splits.inc();
+        self.batch_split_sub_requests.inc_by(sub_requests as u64);
+    }
+
+    pub fn record_decompressed_request(&self, decompressed_bytes: usize) {
+        self.decompressed_requests.inc();
+        self.decompressed_bytes.inc_by(decompressed_bytes as u64);
+    }
+
+    pub fn record_deduplicated_request(&self) {
+        self.deduplicated_requests.inc();
+    }
+
+    pub fn update_scheduler_queue_depth(&self, class: &str, depth: usize) {
+        self.scheduler_queue_depth.with_label_values(&[class]).set(depth as i64);
+    }
+
+    pub fn record_prefetch_request(&self) {
+        self.prefetch_requests.inc();
+    }
+
+    pub fn record_prefetch_cache_hit(&self) {
+        self.prefetch_cache_hits.inc();
+    }
+
     // WebSocket metrics
     pub fn update_websocket_connections(&self, count: usize) {
         self.websocket_connections.set(count as i64);
@@ -315,6 +716,15 @@ impl MetricsService {
         self.websocket_messages.inc();
     }
 
+    pub fn record_admin_broadcast(&self, recipients: usize) {
+        self.admin_broadcasts.inc();
+        self.admin_broadcast_recipients.inc_by(recipients as u64);
+    }
+
+    pub fn record_subscription_dedup_hit(&self) {
+        self.subscription_dedup_hits.inc();
+    }
+
     // Consensus metrics
     pub fn record_consensus_request(&self, duration: Duration, success: bool) {
         self.consensus_requests.inc();
@@ -327,6 +737,10 @@ impl MetricsService {
         }
     }
 
+    pub fn record_simulation_consensus_failure(&self) {
+        self.simulation_consensus_failures.inc();
+    }
+
     // Error metrics
     pub async fn record_error(&self, error_type: &str) {
         self.errors_total.inc();
@@ -341,6 +755,13 @@ impl MetricsService {
         counter.inc();
     }
 
+    pub async fn record_tenant_request(&self, tenant_id: &str) {
+        let mut tenants = self.tenant_metrics.write().await;
+        tenants.entry(tenant_id.to_string())
+            .or_insert_with(|| self.tenant_requests.with_label_values(&[tenant_id]))
+            .inc();
+    }
+
     // Authentication metrics
     pub fn record_auth_request(&self, success: bool) {
         self.auth_requests.inc();
@@ -356,6 +777,56 @@ impl MetricsService {
         self.rate_limited_requests.inc();
     }
 
+    pub fn record_rate_limit_exemption(&self, exemption_index: usize) {
+        self.rate_limit_exemptions_applied.with_label_values(&[&exemption_index.to_string()]).inc();
+    }
+
+    pub fn record_global_method_limit_exceeded(&self, method: &str) {
+        self.global_method_limit_exceeded.with_label_values(&[method]).inc();
+    }
+
+    // `signal` is "header" when the response only carried X-Backend-Pressure, "rejected" for a 429
+    pub fn record_backpressure_signal(&self, signal: &str) {
+        self.backpressure_signals.with_label_values(&[signal]).inc();
+    }
+
+    pub fn record_egress_shaping(&self, bytes: usize, waited: Duration) {
+        self.egress_shaped_bytes.inc_by(bytes as u64);
+        self.egress_wait_duration.observe(waited.as_secs_f64());
+    }
+
+    pub fn record_partition_blocked_request(&self) {
+        self.partition_blocked_requests.inc();
+    }
+
+    pub fn record_cluster_metrics_fetch_failures(&self, failures: usize) {
+        self.cluster_metrics_fetch_failures.inc_by(failures as u64);
+    }
+
+    // Memory pressure metrics
+    pub fn record_memory_pressure_event(&self) {
+        self.memory_pressure_events.inc();
+    }
+
+    pub fn record_memory_pressure_evictions(&self, evicted: usize) {
+        self.memory_pressure_evictions.inc_by(evicted as u64);
+    }
+
+    // Config auto-reload metrics
+    pub fn record_config_auto_reload(&self, success: bool) {
+        if success {
+            self.config_auto_reloads.inc();
+        } else {
+            self.config_auto_reload_failures.inc();
+        }
+    }
+
+    // Service discovery metrics
+    pub fn record_service_discovery_changes(&self, added: usize, removed: usize) {
+        self.service_discovery_endpoint_additions.inc_by(added as u64);
+        self.service_discovery_endpoint_removals.inc_by(removed as u64);
+    }
+
     // Custom metrics
     pub async fn record_custom_metric(&self, name: &str, value: f64, labels: HashMap<String, String>, metric_type: CustomMetricType) {
         let mut metrics = self.custom_metrics.write().await;
@@ -367,44 +838,96 @@ impl MetricsService {
         });
     }
 
+    pub async fn export_custom_metrics(&self) -> Vec<CustomMetricSnapshot> {
+        self.custom_metrics.read().await
+            .iter()
+            .map(|(name, metric)| CustomMetricSnapshot {
+                name: name.clone(),
+                value: metric.value,
+                labels: metric.labels.clone(),
+                metric_type: metric.metric_type.clone(),
+            })
+            .collect()
+    }
+
+    // Imported metrics replace any of the same name; the rest are kept
+    pub async fn import_custom_metrics(&self, snapshots: Vec<CustomMetricSnapshot>) {
+        let now = Instant::now();
+        let mut metrics = self.custom_metrics.write().await;
+        for snapshot in snapshots {
+            metrics.insert(snapshot.name, CustomMetric {
+                value: snapshot.value,
+                timestamp: now,
+                labels: snapshot.labels,
+                metric_type: snapshot.metric_type,
+            });
+        }
+    }
+
     // Get metrics in various formats
     pub async fn get_metrics(&self) -> Value {
         let uptime = self.start_time.elapsed();
         
-        let requests_by_method = self.get_method_stats().await;
         let errors_by_type = self.get_error_stats().await;
+        let tenant_requests = self.get_tenant_stats().await;
         
         json!({
             "uptime_seconds": uptime.as_secs(),
             "requests": {
-                "total": self.requests_total.get(),
-                "by_method": requests_by_method,
+                "total": label_totals(&self.requests_total, 0).values().sum::<u64>(),
+                "by_endpoint": label_totals(&self.requests_total, 0),
+                "by_method": label_totals(&self.requests_total, 1),
+                "failures_by_endpoint": label_totals(&self.request_failures, 0),
+                "batch_splits": self.batch_splits.get(),
+                "batch_split_sub_requests": self.batch_split_sub_requests.get(),
+                "decompressed": self.decompressed_requests.get(),
+                "decompressed_bytes": self.decompressed_bytes.get(),
+                "deduplicated": self.deduplicated_requests.get(),
             },
             "endpoints": {
                 "healthy": self.endpoints_healthy.get(),
                 "total": self.endpoints_total.get(),
+                "latency_anomalies": self.latency_anomalies.get(),
+                "freshest_data_selections": self.freshest_data_slot_lag.get_sample_count(),
+                "power_of_two_selections": self.power_of_two_selections.get(),
+                "tls_cert_expiry_warnings": self.tls_cert_expiry_warnings.get(),
+                "promotions": self.endpoint_promotions.get(),
+                "peer_sync_events": self.peer_sync_events.get(),
+                "epoch_transitions": self.epoch_transitions_detected.get(),
+                "fallback_responses_served": label_counts(&self.fallback_responses_served),
             },
             "cache": {
                 "hits": self.cache_hits.get(),
                 "misses": self.cache_misses.get(),
                 "size": self.cache_size.get(),
                 "hit_rate": self.calculate_cache_hit_rate(),
+                "prefetch_requests": self.prefetch_requests.get(),
+                "prefetch_hits": self.prefetch_cache_hits.get(),
+                "bootstrapped_entries": self.cache_bootstrapped_entries.get(),
+                "gossip_sent": self.gossip_sent.get(),
+                "gossip_received": self.gossip_received.get(),
+                "gossip_bytes": self.gossip_bytes.get(),
             },
             "websocket": {
                 "connections": self.websocket_connections.get(),
                 "subscriptions": self.websocket_subscriptions.get(),
                 "messages": self.websocket_messages.get(),
+                "admin_broadcasts": self.admin_broadcasts.get(),
+                "admin_broadcast_recipients": self.admin_broadcast_recipients.get(),
+                "subscription_dedup_hits": self.subscription_dedup_hits.get(),
             },
             "consensus": {
                 "requests": self.consensus_requests.get(),
                 "successes": self.consensus_successes.get(),
                 "failures": self.consensus_failures.get(),
                 "success_rate": self.calculate_consensus_success_rate(),
+                "simulation_failures": self.simulation_consensus_failures.get(),
             },
             "errors": {
                 "total": self.errors_total.get(),
                 "by_type": errors_by_type,
             },
+            "tenants": tenant_requests,
             "authentication": {
                 "requests": self.auth_requests.get(),
                 "successes": self.auth_successes.get(),
@@ -413,15 +936,37 @@ impl MetricsService {
             },
             "rate_limiting": {
                 "blocked_requests": self.rate_limited_requests.get(),
+                "global_method_limit_exceeded": label_counts(&self.global_method_limit_exceeded),
+                "backpressure_signals": label_counts(&self.backpressure_signals),
+                "egress_shaped_bytes": self.egress_shaped_bytes.get(),
+                "egress_wait_count": self.egress_wait_duration.get_sample_count(),
+            },
+            "chaos": {
+                "partition_blocked_requests": self.partition_blocked_requests.get(),
+            },
+            "cluster": {
+                "metrics_fetch_failures": self.cluster_metrics_fetch_failures.get(),
+            },
+            "memory_pressure": {
+                "events": self.memory_pressure_events.get(),
+                "evictions": self.memory_pressure_evictions.get(),
+            },
+            "config": {
+                "auto_reloads": self.config_auto_reloads.get(),
+                "auto_reload_failures": self.config_auto_reload_failures.get(),
+            },
+            "service_discovery": {
+                "endpoint_additions": self.service_discovery_endpoint_additions.get(),
+                "endpoint_removals": self.service_discovery_endpoint_removals.get(),
             },
             "custom_metrics": self.get_custom_metrics_summary().await,
         })
     }
 
-    async fn get_method_stats(&self) -> HashMap<String, i64> {
-        let methods = self.requests_by_method.read().await;
-        methods.iter()
-            .map(|(method, counter)| (method.clone(), counter.get() as i64))
+    async fn get_tenant_stats(&self) -> HashMap<String, i64> {
+        let tenants = self.tenant_metrics.read().await;
+        tenants.iter()
+            .map(|(tenant_id, counter)| (tenant_id.clone(), counter.get() as i64))
             .collect()
     }
 
@@ -488,7 +1033,8 @@ impl MetricsService {
 
     pub async fn get_prometheus_metrics(&self) -> String {
         let encoder = TextEncoder::new();
-        let metric_families = self.registry.gather();
+        // The register_* macros above put every collector in the default registry
+        let metric_families = prometheus::gather();
         
         match encoder.encode_to_string(&metric_families) {
             Ok(output) => output,
@@ -499,22 +1045,25 @@ impl MetricsService {
         }
     }
 
+    pub async fn get_openmetrics_metrics(&self) -> String {
+        // Collectors are all registered in new(), so that is when every series was created
+        let created = SystemTime::now()
+            .checked_sub(self.start_time.elapsed())
+            .and_then(|created| created.duration_since(UNIX_EPOCH).ok())
+            .map(|created| created.as_secs_f64())
+            .unwrap_or_default();
+        openmetrics::encode(&prometheus::gather(), created)
+    }
+
     pub async fn reset_metrics(&self) {
         // Reset counters and gauges to zero
         // Note: This is a simplified implementation
         // In practice, you might want to preserve some metrics
         
-        // Clear method-specific counters
-        {
-            let mut methods = self.requests_by_method.write().await;
-            methods.clear();
-        }
-        
-        // Clear endpoint-specific counters
-        {
-            let mut endpoints = self.requests_by_endpoint.write().await;
-            endpoints.clear();
-        }
+        // Clear endpoint and method counters
+        self.requests_total.reset();
+        self.requests_duration.reset();
+        self.request_failures.reset();
         
         // Clear error counters
         {
@@ -532,6 +1081,7 @@ impl MetricsService {
     }
 
     pub async fn get_health_metrics(&self) -> Value {
+        let total_requests: u64 = label_totals(&self.requests_total, 0).values().sum();
         json!({
             "healthy_endpoints": self.endpoints_healthy.get(),
             "total_endpoints": self.endpoints_total.get(),
@@ -540,8 +1090,8 @@ impl MetricsService {
             } else {
                 0.0
             },
-            "error_rate": if self.requests_total.get() > 0 {
-                self.errors_total.get() as f64 / self.requests_total.get() as f64
+            "error_rate": if total_requests > 0 {
+                self.errors_total.get() as f64 / total_requests as f64
             } else {
                 0.0
             },
@@ -549,6 +1099,14 @@ impl MetricsService {
         })
     }
 
+    // Methods with the most upstream requests, busiest first
+    pub fn top_methods(&self, limit: usize) -> Vec<(String, u64)> {
+        let mut methods: Vec<(String, u64)> = label_totals(&self.requests_total, 1).into_iter().collect();
+        methods.sort_by(|a, b| b.1.cmp(&a.1).then_with(|| a.0.cmp(&b.0)));
+        methods.truncate(limit);
+        methods
+    }
+
     pub fn get_uptime(&self) -> Duration {
         self.start_time.elapsed()
     }
@@ -559,4 +1117,28 @@ impl MetricsService {
             .map_err(|e| AppError::internal(&format!("Failed to write metrics to file: {}", e)))?;
         Ok(())
     }
-}
\ No newline at end of file
+}
+// Counts of a multi-label counter vec summed over every label except the one at `label_index`
+fn label_totals(counter_vec: &IntCounterVec, label_index: usize) -> HashMap<String, u64> {
+    let mut totals = HashMap::new();
+    for family in counter_vec.collect() {
+        for metric in family.get_metric() {
+            if let Some(label) = metric.get_label().get(label_index) {
+                *totals.entry(label.get_value().to_string()).or_default() += metric.get_counter().get_value() as u64;
+            }
+        }
+    }
+    totals
+}
+
+// Current value of each label of a single-label counter vec, for the JSON metrics view
+fn label_counts(counter_vec: &IntCounterVec) -> HashMap<String, u64> {
+    counter_vec.collect()
+        .iter()
+        .flat_map(|family| family.get_metric())
+        .filter_map(|metric| {
+            let label = metric.get_label().first()?.get_value().to_string();
+            Some((label, metric.get_counter().get_value() as u64))
+        })
+        .collect()
+}
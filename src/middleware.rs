@@ -0,0 +1,176 @@
+use crate::error::AppError;
+use axum::{
+    body::Body,
+    extract::Request,
+    http::{header::CONTENT_LENGTH, HeaderMap, StatusCode},
+    response::{IntoResponse, Response},
+};
+use futures::future::BoxFuture;
+use std::{
+    convert::Infallible,
+    task::{Context, Poll},
+};
+use tower::{Layer, Service};
+use tracing::debug;
+
+pub const DEFAULT_MAX_REQUEST_BODY_BYTES: usize = 1024 * 1024;
+
+// Rejects request bodies larger than `max_bytes` with 413 before any handler buffers them.
+// A declared Content-Length is checked up front; chunked bodies are buffered up to the limit.
+#[derive(Debug, Clone, Copy)]
+pub struct BodySizeLimitLayer {
+    max_bytes: usize,
+}
+
+impl BodySizeLimitLayer {
+    pub fn new(max_bytes: usize) -> Self {
+        Self { max_bytes }
+    }
+}
+
+impl<S> Layer<S> for BodySizeLimitLayer {
+    type Service = BodySizeLimit<S>;
+
+    fn layer(&self, inner: S) -> Self::Service {
+        BodySizeLimit {
+            inner,
+            max_bytes: self.max_bytes,
+        }
+    }
+}
+
+#[derive(Debug, Clone)]
+pub struct BodySizeLimit<S> {
+    inner: S,
+    max_bytes: usize,
+}
+
+impl<S> Service<Request> for BodySizeLimit<S>
+where
+    S: Service<Request, Response = Response, Error = Infallible> + Clone + Send + 'static,
+    S::Future: Send + 'static,
+{
+    type Response = Response;
+    type Error = Infallible;
+    type Future = BoxFuture<'static, Result<Response, Infallible>>;
+
+    fn poll_ready(&mut self, cx: &mut Context<'_>) -> Poll<Result<(), Self::Error>> {
+        self.inner.poll_ready(cx)
+    }
+
+    fn call(&mut self, request: Request) -> Self::Future {
+        // The clone that was polled ready is the one that handles this request
+        let clone = self.inner.clone();
+        let mut inner = std::mem::replace(&mut self.inner, clone);
+        let max_bytes = self.max_bytes;
+
+        Box::pin(async move {
+            match content_length(request.headers()) {
+                Some(length) if length > max_bytes as u64 => {
+                    debug!("Rejected request body of {} bytes, limit is {}", length, max_bytes);
+                    return Ok(body_too_large());
+                }
+                Some(_) => return inner.call(request).await,
+                None => {}
+            }
+
+            let (parts, body) = request.into_parts();
+            let bytes = match axum::body::to_bytes(body, max_bytes).await {
+                Ok(bytes) => bytes,
+                // Also hit when the client aborts mid-body, which gets no response anyway
+                Err(e) => {
+                    debug!("Rejected chunked request body: {}", e);
+                    return Ok(body_too_large());
+                }
+            };
+            inner.call(Request::from_parts(parts, Body::from(bytes))).await
+        })
+    }
+}
+
+fn content_length(headers: &HeaderMap) -> Option<u64> {
+    headers.get(CONTENT_LENGTH)
+        .and_then(|value| value.to_str().ok())
+        .and_then(|value| value.parse().ok())
+}
+
+pub fn body_too_large() -> Response {
+    let mut response = AppError::invalid_request("request body too large").into_response();
+    *response.status_mut() = StatusCode::PAYLOAD_TOO_LARGE;
+    response
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use axum::{body::Bytes, routing::post, Router};
+    use tower::ServiceExt;
+
+    const LIMIT: usize = 64;
+
+    fn app() -> Router {
+        Router::new()
+            .route("/", post(|body: Bytes| async move { body.len().to_string() }))
+            .layer(BodySizeLimitLayer::new(LIMIT))
+    }
+
+    fn sized_request(len: usize) -> Request {
+        Request::post("/")
+            .header(CONTENT_LENGTH, len)
+            .body(Body::from(vec![b'x'; len]))
+            .unwrap()
+    }
+
+    // No Content-Length, as with Transfer-Encoding: chunked
+    fn chunked_request(len: usize) -> Request {
+        let chunks = vec![b'x'; len]
+            .chunks(10)
+            .map(|chunk| Ok::<_, Infallible>(Bytes::copy_from_slice(chunk)))
+            .collect::<Vec<_>>();
+        Request::post("/")
+            .body(Body::from_stream(futures::stream::iter(chunks)))
+            .unwrap()
+    }
+
+    async fn body_string(response: Response) -> String {
+        let bytes = axum::body::to_bytes(response.into_body(), usize::MAX).await.unwrap();
+        String::from_utf8(bytes.to_vec()).unwrap()
+    }
+
+    #[tokio::test]
+    async fn test_body_at_limit_is_accepted() {
+        let response = app().oneshot(sized_request(LIMIT)).await.unwrap();
+        assert_eq!(response.status(), StatusCode::OK);
+        assert_eq!(body_string(response).await, LIMIT.to_string());
+
+        let response = app().oneshot(chunked_request(LIMIT)).await.unwrap();
+        assert_eq!(response.status(), StatusCode::OK);
+        assert_eq!(body_string(response).await, LIMIT.to_string());
+    }
+
+    #[tokio::test]
+    async fn test_body_over_limit_is_rejected() {
+        let response = app().oneshot(sized_request(LIMIT + 1)).await.unwrap();
+        assert_eq!(response.status(), StatusCode::PAYLOAD_TOO_LARGE);
+        assert!(body_string(response).await.contains("INVALID_RPC_REQUEST"));
+
+        let response = app().oneshot(chunked_request(LIMIT + 1)).await.unwrap();
+        assert_eq!(response.status(), StatusCode::PAYLOAD_TOO_LARGE);
+    }
+
+    #[tokio::test]
+    async fn test_oversized_batch_rejected_by_server() {
+        use crate::test_server::TestServerBuilder;
+
+        let server = TestServerBuilder::new()
+            .with_config(|config| config.max_request_body_bytes = 1024)
+            .start()
+            .await;
+        let batch: Vec<_> = (0..100)
+            .map(|id| serde_json::json!({"jsonrpc": "2.0", "id": id, "method": "getSlot"}))
+            .collect();
+
+        let response = server.rpc(serde_json::Value::Array(batch)).await;
+        assert_eq!(response.status(), reqwest::StatusCode::PAYLOAD_TOO_LARGE);
+    }
+}
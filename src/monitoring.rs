@@ -2,7 +2,7 @@ use std::sync::Arc;
 use std::time::{Duration, Instant};
 use opentelemetry::{
     global,
-    trace::{Span, SpanKind, Status, TraceContextExt, Tracer, TracerProvider},
+    trace::{SpanKind, TraceContextExt, Tracer, TracerProvider},
     Context, KeyValue,
 };
 use opentelemetry_sdk::{
@@ -10,16 +10,25 @@ use opentelemetry_sdk::{
     trace::{self, RandomIdGenerator, Sampler},
     Resource,
 };
-use opentelemetry_otlp::{ExportConfig, WithExportConfig};
+use opentelemetry_otlp::WithExportConfig;
 use prometheus::{
     Encoder, Histogram, HistogramOpts, IntCounter, IntGauge, Registry, TextEncoder,
 };
 use serde::{Deserialize, Serialize};
-use tracing::{debug, error, info, warn};
-use tracing_opentelemetry::OpenTelemetryLayer;
-use tracing_subscriber::{layer::SubscriberExt, util::SubscriberInitExt};
+use schemars::JsonSchema;
+use tokio::sync::broadcast::error::RecvError;
+use tokio_util::sync::CancellationToken;
+use tracing::{debug, warn};
+use uuid::Uuid;
 
-#[derive(Debug, Clone, Serialize, Deserialize)]
+use crate::{
+    circuit_breaker::CircuitBreakerRegistry,
+    events::SystemEvent,
+    types::CircuitBreakerState,
+};
+
+#[derive(Debug, Clone, Serialize, Deserialize, JsonSchema)]
+#[serde(default)]
 pub struct MonitoringConfig {
     pub enable_tracing: bool,
     pub enable_metrics: bool,
@@ -31,6 +40,9 @@ pub struct MonitoringConfig {
     pub metrics_port: u16,
     pub export_interval: Duration,
     pub export_timeout: Duration,
+    /// Other multi-rpc instances whose metrics are merged into the cluster scrape
+    pub cluster_peer_urls: Vec<String>,
+    pub cluster_scrape_timeout_ms: u64,
 }
 
 impl Default for MonitoringConfig {
@@ -46,12 +58,16 @@ impl Default for MonitoringConfig {
             metrics_port: 9090,
             export_interval: Duration::from_secs(10),
             export_timeout: Duration::from_secs(5),
+            cluster_peer_urls: Vec::new(),
+            cluster_scrape_timeout_ms: 2000,
         }
     }
 }
 
+#[allow(dead_code)]
 pub struct MonitoringService {
     config: MonitoringConfig,
+    circuit_breakers: Arc<CircuitBreakerRegistry>,
     tracer: Option<opentelemetry_sdk::trace::Tracer>,
     metrics_registry: Registry,
     
@@ -96,8 +112,9 @@ pub struct MonitoringService {
     system_goroutines: IntGauge,
 }
 
+#[allow(dead_code)]
 impl MonitoringService {
-    pub fn new(config: MonitoringConfig) -> anyhow::Result<Self> {
+    pub fn new(config: MonitoringConfig, circuit_breakers: Arc<CircuitBreakerRegistry>) -> anyhow::Result<Self> {
         let registry = Registry::new();
         
         // Initialize metrics
@@ -220,7 +237,7 @@ impl MonitoringService {
         
         let circuit_breaker_state = IntGauge::new(
             "circuit_breaker_state",
-            "Circuit breaker state (0=closed, 1=open, 2=half-open)",
+            "State of the most recent circuit breaker transition (0=closed, 1=open, 2=half-open)",
         )?;
         registry.register(Box::new(circuit_breaker_state.clone()))?;
         
@@ -282,6 +299,7 @@ impl MonitoringService {
         Ok(Self {
             config,
             tracer,
+            circuit_breakers,
             metrics_registry: registry,
             http_requests_total,
             http_request_duration,
@@ -362,11 +380,11 @@ impl MonitoringService {
     }
     
     // Endpoint metrics
-    pub fn update_endpoint_health(&self, endpoint: &str, health_score: u8) {
+    pub fn update_endpoint_health(&self, _endpoint: &str, health_score: u8) {
         self.endpoint_health_score.set(health_score as i64);
     }
     
-    pub fn record_endpoint_request(&self, endpoint: &str, success: bool, latency: Duration) {
+    pub fn record_endpoint_request(&self, _endpoint: &str, success: bool, latency: Duration) {
         self.endpoint_request_total.inc();
         self.endpoint_latency.observe(latency.as_secs_f64());
         
@@ -392,21 +410,45 @@ impl MonitoringService {
         self.cache_size_bytes.set(size_bytes as i64);
     }
     
-    // Circuit breaker metrics
-    pub fn update_circuit_breaker_state(&self, name: &str, state: CircuitBreakerState) {
-        let state_value = match state {
+    // Circuit breaker metrics. State lives in the shared registry; this only reads it.
+    pub fn circuit_breaker_state(&self, endpoint_id: Uuid) -> Option<CircuitBreakerState> {
+        self.circuit_breakers.get_state(endpoint_id)
+    }
+    
+    pub fn record_circuit_breaker_event(&self, event: &SystemEvent) {
+        let SystemEvent::CircuitBreakerStateChanged { to, .. } = event else {
+            return;
+        };
+        let state_value = match to {
             CircuitBreakerState::Closed => 0,
             CircuitBreakerState::Open => 1,
             CircuitBreakerState::HalfOpen => 2,
         };
         self.circuit_breaker_state.set(state_value);
         
-        if state == CircuitBreakerState::Open {
+        if *to == CircuitBreakerState::Open {
             self.circuit_breaker_opens_total.inc();
         }
     }
     
-    pub fn record_circuit_breaker_result(&self, name: &str, success: bool) {
+    // Follows registry transitions on the event bus until shutdown
+    pub async fn watch_circuit_breakers(self: Arc<Self>, shutdown: CancellationToken) {
+        let mut events = self.circuit_breakers.event_bus().subscribe();
+        loop {
+            tokio::select! {
+                _ = shutdown.cancelled() => break,
+                event = events.recv() => match event {
+                    Ok(event) => self.record_circuit_breaker_event(&event),
+                    Err(RecvError::Lagged(skipped)) => {
+                        warn!("Circuit breaker watcher skipped {} events", skipped);
+                    }
+                    Err(RecvError::Closed) => break,
+                },
+            }
+        }
+    }
+    
+    pub fn record_circuit_breaker_result(&self, _name: &str, success: bool) {
         if success {
             self.circuit_breaker_success_total.inc();
         } else {
@@ -459,14 +501,8 @@ impl MonitoringService {
     }
 }
 
-#[derive(Debug, Clone, Copy, PartialEq)]
-pub enum CircuitBreakerState {
-    Closed,
-    Open,
-    HalfOpen,
-}
-
 // Initialize OpenTelemetry tracer
+#[allow(dead_code)]
 fn init_tracer(config: &MonitoringConfig) -> anyhow::Result<opentelemetry_sdk::trace::Tracer> {
     global::set_text_map_propagator(TraceContextPropagator::new());
     
@@ -485,7 +521,7 @@ fn init_tracer(config: &MonitoringConfig) -> anyhow::Result<opentelemetry_sdk::t
     };
     
     let tracer = if let Some(endpoint) = &config.otlp_endpoint {
-        let tracer = opentelemetry_otlp::new_pipeline()
+        opentelemetry_otlp::new_pipeline()
             .tracing()
             .with_exporter(
                 opentelemetry_otlp::new_exporter()
@@ -499,8 +535,7 @@ fn init_tracer(config: &MonitoringConfig) -> anyhow::Result<opentelemetry_sdk::t
                     .with_id_generator(RandomIdGenerator::default())
                     .with_resource(resource),
             )
-            .install_batch(opentelemetry_sdk::runtime::Tokio)?;
-        tracer
+            .install_batch(opentelemetry_sdk::runtime::Tokio)?
     } else {
         let provider = opentelemetry_sdk::trace::TracerProvider::builder()
             .with_config(
@@ -517,18 +552,21 @@ fn init_tracer(config: &MonitoringConfig) -> anyhow::Result<opentelemetry_sdk::t
 }
 
 // System metrics helpers
+#[allow(dead_code)]
 fn get_cpu_usage() -> anyhow::Result<f64> {
     // This is a simplified implementation
     // In production, use proper system metrics libraries
     Ok(0.0)
 }
 
+#[allow(dead_code)]
 fn get_memory_usage() -> anyhow::Result<usize> {
     // This is a simplified implementation
     // In production, use proper system metrics libraries
     Ok(0)
 }
 
+#[allow(dead_code)]
 fn get_thread_count() -> usize {
     // Get approximate thread count
     std::thread::available_parallelism()
@@ -537,6 +575,7 @@ fn get_thread_count() -> usize {
 }
 
 // Health check data
+#[allow(dead_code)]
 #[derive(Debug, Clone, Serialize)]
 pub struct HealthMetrics {
     pub uptime_seconds: u64,
@@ -550,6 +589,7 @@ pub struct HealthMetrics {
 }
 
 // SLA monitoring
+#[allow(dead_code)]
 pub struct SlaMonitor {
     target_availability: f64,
     target_latency_p99: Duration,
@@ -557,6 +597,7 @@ pub struct SlaMonitor {
     violations: Vec<SlaViolation>,
 }
 
+#[allow(dead_code)]
 #[derive(Debug, Clone)]
 pub struct SlaViolation {
     pub timestamp: Instant,
@@ -565,6 +606,7 @@ pub struct SlaViolation {
     pub details: String,
 }
 
+#[allow(dead_code)]
 #[derive(Debug, Clone)]
 pub enum SlaViolationType {
     Availability,
@@ -572,12 +614,14 @@ pub enum SlaViolationType {
     ErrorRate,
 }
 
+#[allow(dead_code)]
 #[derive(Debug, Clone)]
 pub enum ViolationSeverity {
     Warning,
     Critical,
 }
 
+#[allow(dead_code)]
 impl SlaMonitor {
     pub fn new(target_availability: f64, target_latency_p99: Duration) -> Self {
         Self {
@@ -635,9 +679,148 @@ impl SlaMonitor {
         &self.violations
     }
     
+    // Any violation in the window breaks the SLA; severity only says by how much
     pub fn is_sla_met(&self) -> bool {
-        self.violations.iter().all(|v| matches!(v.severity, ViolationSeverity::Warning))
+        self.violations.is_empty()
+    }
+}
+
+// Aggregates the Prometheus output of every instance in a cluster into one scrape target
+#[derive(Debug, Clone)]
+pub struct PrometheusMultiProcess {
+    client: reqwest::Client,
+    peer_urls: Vec<String>,
+}
+
+#[derive(Debug, Default)]
+struct MetricFamily {
+    help: Option<String>,
+    kind: Option<String>,
+    // (series, summed value, number of instances reporting it)
+    series: Vec<(String, f64, usize)>,
+}
+
+impl PrometheusMultiProcess {
+    pub fn new(config: &MonitoringConfig) -> Self {
+        let client = reqwest::Client::builder()
+            .timeout(Duration::from_millis(config.cluster_scrape_timeout_ms))
+            .build()
+            .unwrap_or_default();
+
+        Self {
+            client,
+            peer_urls: config.cluster_peer_urls.clone(),
+        }
+    }
+
+    // Returns the merged exposition text and the number of peers that could not be scraped
+    pub async fn scrape_cluster(&self, local_metrics: String) -> (String, usize) {
+        let scrapes = self.peer_urls.iter().map(|peer| {
+            let url = format!("{}/metrics/prometheus", peer.trim_end_matches('/'));
+            async move {
+                let response = self.client.get(&url).send().await?.error_for_status()?;
+                response.text().await
+            }
+        });
+
+        let mut sources = vec![local_metrics];
+        let mut failures = 0;
+        for (peer, result) in self.peer_urls.iter().zip(futures::future::join_all(scrapes).await) {
+            match result {
+                Ok(text) => sources.push(text),
+                Err(e) => {
+                    warn!("Failed to scrape metrics from peer {}: {}", peer, e);
+                    failures += 1;
+                }
+            }
+        }
+
+        (merge_prometheus_text(&sources), failures)
+    }
+}
+
+// Counters and histogram buckets are summed; gauges are averaged across the instances reporting them
+pub fn merge_prometheus_text(sources: &[String]) -> String {
+    let mut families: Vec<(String, MetricFamily)> = Vec::new();
+    let mut family_index: std::collections::HashMap<String, usize> = std::collections::HashMap::new();
+    let mut series_index: std::collections::HashMap<String, (usize, usize)> = std::collections::HashMap::new();
+
+    fn family_for(
+        name: &str,
+        families: &mut Vec<(String, MetricFamily)>,
+        family_index: &mut std::collections::HashMap<String, usize>,
+    ) -> usize {
+        *family_index.entry(name.to_string()).or_insert_with(|| {
+            families.push((name.to_string(), MetricFamily::default()));
+            families.len() - 1
+        })
     }
+
+    for source in sources {
+        for line in source.lines().map(str::trim).filter(|l| !l.is_empty()) {
+            if let Some(rest) = line.strip_prefix("# HELP ") {
+                let (name, help) = rest.split_once(' ').unwrap_or((rest, ""));
+                let idx = family_for(name, &mut families, &mut family_index);
+                families[idx].1.help.get_or_insert_with(|| help.to_string());
+                continue;
+            }
+            if let Some(rest) = line.strip_prefix("# TYPE ") {
+                let (name, kind) = rest.split_once(' ').unwrap_or((rest, "untyped"));
+                let idx = family_for(name, &mut families, &mut family_index);
+                families[idx].1.kind.get_or_insert_with(|| kind.to_string());
+                continue;
+            }
+            if line.starts_with('#') {
+                continue;
+            }
+
+            let (series, rest) = match line.rfind('}') {
+                Some(end) => (&line[..=end], &line[end + 1..]),
+                None => line.split_once(char::is_whitespace).unwrap_or((line, "")),
+            };
+            let Some(value) = rest.split_whitespace().next().and_then(|v| v.parse::<f64>().ok()) else {
+                continue;
+            };
+
+            let metric_name = series.split('{').next().unwrap_or(series);
+            let family_name = ["_bucket", "_sum", "_count"].iter()
+                .filter_map(|suffix| metric_name.strip_suffix(suffix))
+                .find(|base| family_index.contains_key(*base))
+                .unwrap_or(metric_name)
+                .to_string();
+            let idx = family_for(&family_name, &mut families, &mut family_index);
+
+            match series_index.get(series) {
+                Some(&(family, position)) => {
+                    let entry = &mut families[family].1.series[position];
+                    entry.1 += value;
+                    entry.2 += 1;
+                }
+                None => {
+                    families[idx].1.series.push((series.to_string(), value, 1));
+                    series_index.insert(series.to_string(), (idx, families[idx].1.series.len() - 1));
+                }
+            }
+        }
+    }
+
+    let mut output = String::new();
+    for (name, family) in families.iter().filter(|(_, f)| !f.series.is_empty()) {
+        if let Some(help) = &family.help {
+            output.push_str(&format!("# HELP {} {}\n", name, help));
+        }
+        if let Some(kind) = &family.kind {
+            output.push_str(&format!("# TYPE {} {}\n", name, kind));
+        }
+
+        let is_gauge = family.kind.as_deref() == Some("gauge");
+        for (series, value, reporters) in &family.series {
+            let merged = if is_gauge { value / *reporters as f64 } else { *value };
+            output.push_str(&format!("{} {}\n", series, merged));
+        }
+    }
+
+    output
 }
 
 #[cfg(test)]
@@ -647,7 +830,7 @@ mod tests {
     #[test]
     fn test_monitoring_service_creation() {
         let config = MonitoringConfig::default();
-        let service = MonitoringService::new(config).unwrap();
+        let service = MonitoringService::new(config, Arc::new(CircuitBreakerRegistry::default())).unwrap();
         
         service.record_http_request("GET", "/api/test", 200, Duration::from_millis(100), 1024, 2048);
         service.record_cache_hit();
@@ -658,6 +841,40 @@ mod tests {
         assert!(metrics.contains("cache_hits_total"));
     }
     
+    #[tokio::test]
+    async fn test_circuit_breaker_state_shared_with_endpoint_manager() {
+        use crate::{config::Config, endpoints::EndpointManager};
+
+        let registry = Arc::new(CircuitBreakerRegistry::default());
+        let mut config = Config::default();
+        config.endpoints.truncate(1);
+        let manager = EndpointManager::new(config.endpoints.clone(), config, registry.clone()).await.unwrap();
+        let monitoring = Arc::new(MonitoringService::new(MonitoringConfig::default(), registry.clone()).unwrap());
+        let id = manager.get_endpoint_info().await[0].id;
+
+        let shutdown = CancellationToken::new();
+        let watcher = tokio::spawn(monitoring.clone().watch_circuit_breakers(shutdown.clone()));
+        tokio::time::sleep(Duration::from_millis(50)).await;
+
+        for _ in 0..5 {
+            manager.update_endpoint_stats(id, false, Duration::from_millis(10)).await;
+        }
+        assert_eq!(manager.circuit_breaker_states().await[&id], "open");
+        assert_eq!(monitoring.circuit_breaker_state(id), Some(CircuitBreakerState::Open));
+
+        manager.update_endpoint_stats(id, true, Duration::from_millis(10)).await;
+        assert_eq!(manager.circuit_breaker_states().await[&id], "closed");
+        assert_eq!(monitoring.circuit_breaker_state(id), Some(CircuitBreakerState::Closed));
+
+        tokio::time::sleep(Duration::from_millis(50)).await;
+        let metrics = monitoring.export_metrics().unwrap();
+        assert!(metrics.contains("circuit_breaker_opens_total 1"));
+        assert!(metrics.contains("circuit_breaker_state 0"));
+
+        shutdown.cancel();
+        watcher.await.unwrap();
+    }
+    
     #[test]
     fn test_sla_monitor() {
         let mut monitor = SlaMonitor::new(0.99, Duration::from_millis(100));
@@ -677,4 +894,67 @@ mod tests {
         assert!(!monitor.is_sla_met());
         assert_eq!(monitor.get_violations().len(), 2);
     }
+
+    async fn mock_metrics_peer(body: &'static str) -> String {
+        let app = axum::Router::new()
+            .route("/metrics/prometheus", axum::routing::get(move || async move { body }));
+        let listener = tokio::net::TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let addr = listener.local_addr().unwrap();
+        tokio::spawn(async move {
+            axum::serve(listener, app).await.unwrap();
+        });
+        format!("http://{}", addr)
+    }
+
+    #[tokio::test]
+    async fn test_cluster_scrape_sums_counters() {
+        let peer_a = mock_metrics_peer(
+            "# HELP multi_rpc_requests_total Total number of RPC requests\n\
+             # TYPE multi_rpc_requests_total counter\n\
+             multi_rpc_requests_total 10\n\
+             # TYPE multi_rpc_endpoints_healthy gauge\n\
+             multi_rpc_endpoints_healthy 4\n"
+        ).await;
+        let peer_b = mock_metrics_peer(
+            "# HELP multi_rpc_requests_total Total number of RPC requests\n\
+             # TYPE multi_rpc_requests_total counter\n\
+             multi_rpc_requests_total 32\n\
+             # TYPE multi_rpc_endpoints_healthy gauge\n\
+             multi_rpc_endpoints_healthy 2\n"
+        ).await;
+
+        let config = MonitoringConfig {
+            cluster_peer_urls: vec![peer_a, peer_b, "http://127.0.0.1:1".to_string()],
+            cluster_scrape_timeout_ms: 500,
+            ..MonitoringConfig::default()
+        };
+        let (merged, failures) = PrometheusMultiProcess::new(&config)
+            .scrape_cluster(String::new())
+            .await;
+
+        assert_eq!(failures, 1);
+        assert!(merged.contains("# TYPE multi_rpc_requests_total counter"));
+        assert!(merged.contains("multi_rpc_requests_total 42\n"));
+        assert!(merged.contains("multi_rpc_endpoints_healthy 3\n"));
+    }
+
+    #[test]
+    fn test_merge_histograms_and_labels() {
+        let a = "# TYPE latency histogram\n\
+                 latency_bucket{le=\"0.1\"} 3\n\
+                 latency_bucket{le=\"+Inf\"} 5\n\
+                 latency_sum 1.5\n\
+                 latency_count 5\n".to_string();
+        let b = "# TYPE latency histogram\n\
+                 latency_bucket{le=\"0.1\"} 1\n\
+                 latency_bucket{le=\"+Inf\"} 2\n\
+                 latency_sum 0.5\n\
+                 latency_count 2\n".to_string();
+
+        let merged = merge_prometheus_text(&[a, b]);
+        assert!(merged.contains("latency_bucket{le=\"0.1\"} 4\n"));
+        assert!(merged.contains("latency_bucket{le=\"+Inf\"} 7\n"));
+        assert!(merged.contains("latency_sum 2\n"));
+        assert!(merged.contains("latency_count 7\n"));
+    }
 }
\ No newline at end of file
@@ -0,0 +1,327 @@
+use prometheus::proto::{LabelPair, MetricFamily, MetricType};
+use std::fmt::Write;
+
+pub const OPENMETRICS_CONTENT_TYPE: &str = "application/openmetrics-text; version=1.0.0; charset=utf-8";
+
+// Suffixes that are OpenMetrics base units and get a `# UNIT` line
+const BASE_UNITS: [&str; 3] = ["seconds", "bytes", "ratio"];
+
+// Encodes gathered metric families in the OpenMetrics 1.0 text format. The prometheus
+// crate only ships the classic text encoder, so this follows the same walk over the
+// protobuf families. Counters, histograms and summaries get a `_created` sample at
+// `created` (unix seconds), since every collector is registered when the service starts.
+pub fn encode(families: &[MetricFamily], created: f64) -> String {
+    let mut out = String::new();
+
+    for family in families {
+        let metric_type = family.get_field_type();
+        let name = match metric_type {
+            // The family is named without the suffix its samples carry
+            MetricType::COUNTER => family.get_name().strip_suffix("_total").unwrap_or(family.get_name()),
+            _ => family.get_name(),
+        };
+
+        let _ = writeln!(out, "# TYPE {} {}", name, type_name(metric_type));
+        if let Some(unit) = BASE_UNITS.iter().find(|unit| name.ends_with(&format!("_{}", unit))) {
+            let _ = writeln!(out, "# UNIT {} {}", name, unit);
+        }
+        if !family.get_help().is_empty() {
+            let _ = writeln!(out, "# HELP {} {}", name, escape(family.get_help()));
+        }
+
+        for metric in family.get_metric() {
+            let labels = metric.get_label();
+            match metric_type {
+                MetricType::COUNTER => {
+                    write_sample(&mut out, name, "_total", labels, None, metric.get_counter().get_value());
+                    write_sample(&mut out, name, "_created", labels, None, created);
+                }
+                MetricType::GAUGE => write_sample(&mut out, name, "", labels, None, metric.get_gauge().get_value()),
+                MetricType::UNTYPED => write_sample(&mut out, name, "", labels, None, metric.get_untyped().get_value()),
+                MetricType::HISTOGRAM => {
+                    let histogram = metric.get_histogram();
+                    let mut has_inf = false;
+                    for bucket in histogram.get_bucket() {
+                        has_inf |= bucket.get_upper_bound() == f64::INFINITY;
+                        let le = format_value(bucket.get_upper_bound());
+                        write_sample(&mut out, name, "_bucket", labels, Some(("le", &le)), bucket.get_cumulative_count() as f64);
+                    }
+                    // OpenMetrics requires the +Inf bucket, which the registry leaves implicit
+                    if !has_inf {
+                        write_sample(&mut out, name, "_bucket", labels, Some(("le", "+Inf")), histogram.get_sample_count() as f64);
+                    }
+                    write_sample(&mut out, name, "_count", labels, None, histogram.get_sample_count() as f64);
+                    write_sample(&mut out, name, "_sum", labels, None, histogram.get_sample_sum());
+                    write_sample(&mut out, name, "_created", labels, None, created);
+                }
+                MetricType::SUMMARY => {
+                    let summary = metric.get_summary();
+                    for quantile in summary.get_quantile() {
+                        let q = format_value(quantile.get_quantile());
+                        write_sample(&mut out, name, "", labels, Some(("quantile", &q)), quantile.get_value());
+                    }
+                    write_sample(&mut out, name, "_count", labels, None, summary.get_sample_count() as f64);
+                    write_sample(&mut out, name, "_sum", labels, None, summary.get_sample_sum());
+                    write_sample(&mut out, name, "_created", labels, None, created);
+                }
+            }
+        }
+    }
+
+    out.push_str("# EOF\n");
+    out
+}
+
+fn type_name(metric_type: MetricType) -> &'static str {
+    match metric_type {
+        MetricType::COUNTER => "counter",
+        MetricType::GAUGE => "gauge",
+        MetricType::HISTOGRAM => "histogram",
+        MetricType::SUMMARY => "summary",
+        MetricType::UNTYPED => "unknown",
+    }
+}
+
+fn write_sample(
+    out: &mut String,
+    name: &str,
+    suffix: &str,
+    labels: &[LabelPair],
+    extra: Option<(&str, &str)>,
+    value: f64,
+) {
+    out.push_str(name);
+    out.push_str(suffix);
+
+    let pairs: Vec<(&str, &str)> = labels.iter()
+        .map(|label| (label.get_name(), label.get_value()))
+        .chain(extra)
+        .collect();
+    if !pairs.is_empty() {
+        out.push('{');
+        for (i, (label, label_value)) in pairs.iter().enumerate() {
+            if i > 0 {
+                out.push(',');
+            }
+            let _ = write!(out, "{}=\"{}\"", label, escape(label_value));
+        }
+        out.push('}');
+    }
+
+    let _ = writeln!(out, " {}", format_value(value));
+}
+
+fn format_value(value: f64) -> String {
+    if value.is_nan() {
+        "NaN".to_string()
+    } else if value == f64::INFINITY {
+        "+Inf".to_string()
+    } else if value == f64::NEG_INFINITY {
+        "-Inf".to_string()
+    } else {
+        value.to_string()
+    }
+}
+
+// Label values and help text escape backslashes, double quotes and newlines
+fn escape(value: &str) -> String {
+    value.replace('\\', r"\\").replace('"', "\\\"").replace('\n', r"\n")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_server::{shared_metrics, TestServerBuilder};
+    use prometheus::{
+        core::Collector, Counter, CounterVec, Gauge, Histogram, HistogramOpts, Opts,
+    };
+    use std::collections::{HashMap, HashSet};
+
+    // Checks text against the OpenMetrics 1.0 exposition rules this encoder has to follow:
+    // metadata before samples, no interleaved families, suffixes that match the family type,
+    // well-formed labels and values, a +Inf bucket per histogram and a final `# EOF`.
+    // Returns the samples keyed by name and rendered labels.
+    fn parse_openmetrics(text: &str) -> Result<HashMap<String, f64>, String> {
+        let body = text.strip_suffix("# EOF\n").ok_or("missing trailing # EOF")?;
+        let mut families: HashMap<String, String> = HashMap::new();
+        let mut finished: HashSet<String> = HashSet::new();
+        let mut current: Option<String> = None;
+        let mut inf_buckets: HashMap<String, bool> = HashMap::new();
+        let mut samples = HashMap::new();
+
+        for line in body.lines() {
+            if line.is_empty() {
+                return Err("blank line".to_string());
+            }
+            if let Some(comment) = line.strip_prefix("# ") {
+                let mut parts = comment.splitn(3, ' ');
+                let (keyword, family, rest) = (parts.next().unwrap(), parts.next().ok_or(line)?, parts.next().unwrap_or(""));
+                if current.as_deref() != Some(family) {
+                    if finished.contains(family) {
+                        return Err(format!("family {} is interleaved", family));
+                    }
+                    if let Some(previous) = current.replace(family.to_string()) {
+                        finished.insert(previous);
+                    }
+                }
+                match keyword {
+                    "TYPE" => {
+                        if !["counter", "gauge", "histogram", "summary", "unknown", "info", "stateset", "gaugehistogram"].contains(&rest) {
+                            return Err(format!("unknown type {}", rest));
+                        }
+                        families.insert(family.to_string(), rest.to_string());
+                    }
+                    "UNIT" => {
+                        if !family.ends_with(&format!("_{}", rest)) {
+                            return Err(format!("{} does not end in its unit {}", family, rest));
+                        }
+                    }
+                    "HELP" => {}
+                    _ => return Err(format!("unexpected comment: {}", line)),
+                }
+                continue;
+            }
+
+            let family = current.clone().ok_or("sample before any metadata")?;
+            let metric_type = families.get(&family).ok_or(format!("{} has no TYPE", family))?;
+            let (series, value) = line.rsplit_once(' ').ok_or(line)?;
+            if !["+Inf", "-Inf", "NaN"].contains(&value) {
+                value.parse::<f64>().map_err(|_| format!("bad value in {}", line))?;
+            }
+            let (name, labels) = match series.split_once('{') {
+                Some((name, labels)) => (name, labels.strip_suffix('}').ok_or(line)?),
+                None => (series, ""),
+            };
+            let suffix = name.strip_prefix(family.as_str()).ok_or(format!("{} outside family {}", name, family))?;
+            let allowed: &[&str] = match metric_type.as_str() {
+                "counter" => &["_total", "_created"],
+                "histogram" => &["_bucket", "_count", "_sum", "_created"],
+                "summary" => &["", "_count", "_sum", "_created"],
+                _ => &[""],
+            };
+            if !allowed.contains(&suffix) {
+                return Err(format!("{} is not a valid {} sample", name, metric_type));
+            }
+
+            // label="value" pairs, where values may contain escaped quotes and commas
+            let mut rest = labels;
+            let mut le = None;
+            let mut other_labels = Vec::new();
+            while !rest.is_empty() {
+                let (label, after) = rest.split_once("=\"").ok_or(format!("bad labels in {}", line))?;
+                if label.is_empty() || !label.chars().all(|c| c.is_ascii_alphanumeric() || c == '_') {
+                    return Err(format!("bad label name {:?}", label));
+                }
+                let mut end = None;
+                let mut escaped = false;
+                for (i, c) in after.char_indices() {
+                    match (escaped, c) {
+                        (true, '\\' | '"' | 'n') => escaped = false,
+                        (true, _) => return Err(format!("bad escape in {}", line)),
+                        (false, '\\') => escaped = true,
+                        (false, '"') => {
+                            end = Some(i);
+                            break;
+                        }
+                        _ => {}
+                    }
+                }
+                let end = end.ok_or(format!("unterminated label in {}", line))?;
+                if label == "le" {
+                    le = Some(&after[..end]);
+                } else {
+                    other_labels.push(&after[..end]);
+                }
+                rest = &after[end + 1..];
+                rest = rest.strip_prefix(',').unwrap_or(rest);
+            }
+            if suffix == "_bucket" {
+                let inf = inf_buckets.entry(format!("{}{:?}", family, other_labels)).or_default();
+                *inf |= le.ok_or(format!("bucket without le in {}", line))? == "+Inf";
+            }
+            samples.insert(series.to_string(), value.parse().unwrap_or(f64::NAN));
+        }
+
+        if let Some((series, _)) = inf_buckets.iter().find(|(_, has_inf)| !**has_inf) {
+            return Err(format!("{} has no +Inf bucket", series));
+        }
+        Ok(samples)
+    }
+
+    fn gather(collectors: &[&dyn Collector]) -> Vec<MetricFamily> {
+        collectors.iter().flat_map(|collector| collector.collect()).collect()
+    }
+
+    #[test]
+    fn test_encodes_each_metric_type() {
+        let requests = CounterVec::new(Opts::new("test_requests_total", "Requests \"served\"\nper path"), &["path"]).unwrap();
+        requests.with_label_values(&["/a\\b"]).inc_by(3.0);
+        let uptime = Counter::new("test_uptime_seconds", "Seconds up").unwrap();
+        let queue = Gauge::new("test_queue_depth", "Queued calls").unwrap();
+        queue.set(-2.5);
+        let latency = Histogram::with_opts(
+            HistogramOpts::new("test_latency_seconds", "Latency").buckets(vec![0.1, 1.0]),
+        ).unwrap();
+        latency.observe(0.0625);
+        latency.observe(4.0);
+
+        let text = encode(&gather(&[&requests, &uptime, &queue, &latency]), 1700000000.5);
+        let samples = parse_openmetrics(&text).unwrap_or_else(|e| panic!("{}\n{}", e, text));
+
+        assert!(text.contains("# TYPE test_requests counter\n"));
+        assert!(text.contains("# HELP test_requests Requests \\\"served\\\"\\nper path\n"));
+        assert_eq!(samples[r#"test_requests_total{path="/a\\b"}"#], 3.0);
+        assert_eq!(samples[r#"test_requests_created{path="/a\\b"}"#], 1700000000.5);
+        assert!(text.contains("# UNIT test_uptime_seconds seconds\n"));
+        assert_eq!(samples["test_uptime_seconds_total"], 0.0);
+        assert_eq!(samples["test_queue_depth"], -2.5);
+        assert_eq!(samples[r#"test_latency_seconds_bucket{le="0.1"}"#], 1.0);
+        assert_eq!(samples[r#"test_latency_seconds_bucket{le="1"}"#], 1.0);
+        assert_eq!(samples[r#"test_latency_seconds_bucket{le="+Inf"}"#], 2.0);
+        assert_eq!(samples["test_latency_seconds_count"], 2.0);
+        assert_eq!(samples["test_latency_seconds_sum"], 4.0625);
+        assert!(text.ends_with("# EOF\n"));
+    }
+
+    #[test]
+    fn test_parser_rejects_invalid_exposition() {
+        assert!(parse_openmetrics("# TYPE a gauge\na 1\n").is_err());
+        assert!(parse_openmetrics("a 1\n# EOF\n").is_err());
+        assert!(parse_openmetrics("# TYPE a counter\na 1\n# EOF\n").is_err());
+        assert!(parse_openmetrics("# TYPE a histogram\na_bucket{le=\"1\"} 1\n# EOF\n").is_err());
+        assert!(parse_openmetrics("# TYPE a gauge\n# TYPE b gauge\n# HELP a late\n# EOF\n").is_err());
+    }
+
+    #[tokio::test]
+    async fn test_full_registry_parses_as_openmetrics() {
+        let metrics = shared_metrics();
+        metrics.record_cache_hit();
+        let text = metrics.get_openmetrics_metrics().await;
+
+        let samples = parse_openmetrics(&text).unwrap_or_else(|e| panic!("{}\n{}", e, text));
+        assert!(samples["multi_rpc_cache_hits_total"] >= 1.0);
+        assert!(samples.contains_key("multi_rpc_cache_hits_created"));
+    }
+
+    #[tokio::test]
+    async fn test_openmetrics_endpoint_and_content_negotiation() {
+        let server = TestServerBuilder::new().start().await;
+
+        let response = server.client.get(server.url("/metrics/openmetrics")).send().await.unwrap();
+        assert_eq!(response.headers()["content-type"], OPENMETRICS_CONTENT_TYPE);
+        assert!(parse_openmetrics(&response.text().await.unwrap()).is_ok());
+
+        // Scrapers asking the Prometheus endpoint for OpenMetrics are sent to the new one
+        let response = server.client.get(server.url("/metrics/prometheus"))
+            .header("accept", "application/openmetrics-text;version=1.0.0,text/plain;version=0.0.4;q=0.5")
+            .send()
+            .await
+            .unwrap();
+        assert_eq!(response.url().path(), "/metrics/openmetrics");
+        assert_eq!(response.headers()["content-type"], OPENMETRICS_CONTENT_TYPE);
+
+        let response = server.client.get(server.url("/metrics/prometheus")).send().await.unwrap();
+        assert_eq!(response.url().path(), "/metrics/prometheus");
+        assert!(!response.text().await.unwrap().contains("# EOF"));
+    }
+}
@@ -0,0 +1,592 @@
+use crate::{
+    config::PeerConfig,
+    endpoints::EndpointManager,
+    error::AppError,
+    events::{EventBus, SystemEvent},
+    metrics::MetricsService,
+    types::EndpointStatus,
+};
+use chrono::{DateTime, Utc};
+use dashmap::DashMap;
+use std::{sync::Arc, time::Duration};
+use tokio::{
+    net::TcpListener,
+    sync::broadcast::{self, error::RecvError},
+    time::{interval_at, Instant},
+};
+use tokio_stream::wrappers::TcpListenerStream;
+use tokio_util::sync::CancellationToken;
+use tonic::{transport::Channel, Request, Response, Status};
+use tracing::{debug, info, warn};
+
+pub mod proto {
+    include!(concat!(env!("OUT_DIR"), "/multi_rpc.peer.PeerService.rs"));
+}
+
+use proto::{
+    peer_service_client::PeerServiceClient,
+    peer_service_server::{PeerService, PeerServiceServer},
+};
+
+// Metadata key carrying the shared secret on every peer call
+const PEER_SECRET_HEADER: &str = "x-peer-secret";
+
+#[derive(Clone, PartialEq, prost::Message)]
+pub struct EndpointHealthUpdate {
+    // Advertise address of the node that observed the change
+    #[prost(string, tag = "1")]
+    pub origin: String,
+    #[prost(string, tag = "2")]
+    pub endpoint_url: String,
+    #[prost(string, tag = "3")]
+    pub endpoint_name: String,
+    #[prost(string, tag = "4")]
+    pub status: String,
+    #[prost(int64, tag = "5")]
+    pub timestamp_ms: i64,
+}
+
+#[derive(Clone, PartialEq, prost::Message)]
+pub struct AnnounceResponse {
+    // False if the receiver already had a newer status for the endpoint
+    #[prost(bool, tag = "1")]
+    pub applied: bool,
+}
+
+#[derive(Clone, PartialEq, prost::Message)]
+pub struct GetPeersRequest {
+    // Advertise address of the caller, which the receiver adds to its own peer list
+    #[prost(string, tag = "1")]
+    pub address: String,
+}
+
+#[derive(Clone, PartialEq, prost::Message)]
+pub struct PeersResponse {
+    #[prost(string, repeated, tag = "1")]
+    pub peers: Vec<String>,
+}
+
+#[derive(Debug, Clone)]
+pub struct PeerState {
+    pub added_at: DateTime<Utc>,
+    pub last_seen: Option<DateTime<Utc>>,
+    pub consecutive_failures: u32,
+    client: PeerServiceClient<Channel>,
+}
+
+// Lets multi-rpc instances find each other and share endpoint health. Every call carries
+// the shared secret. Local status changes are pushed to every known peer; updates from
+// peers are applied only to endpoints configured here, and only if they are newer than
+// what this node last saw for the endpoint.
+pub struct PeerDiscovery {
+    self_address: String,
+    seed_peers: Vec<String>,
+    shared_secret: String,
+    peer_timeout_secs: u64,
+    endpoint_manager: Arc<EndpointManager>,
+    metrics_service: Arc<MetricsService>,
+    event_bus: Arc<EventBus>,
+    peers: Arc<DashMap<String, PeerState>>,
+    // Timestamp of the latest status applied or announced per endpoint URL
+    endpoint_versions: DashMap<String, i64>,
+}
+
+impl PeerDiscovery {
+    pub fn new(
+        config: &PeerConfig,
+        endpoint_manager: Arc<EndpointManager>,
+        metrics_service: Arc<MetricsService>,
+        event_bus: Arc<EventBus>,
+    ) -> Self {
+        let self_address = config.advertise_address.clone()
+            .unwrap_or_else(|| format!("http://{}", config.grpc_bind_address));
+
+        Self {
+            self_address,
+            seed_peers: config.seed_peers.clone(),
+            shared_secret: config.shared_secret.clone(),
+            peer_timeout_secs: config.peer_timeout_secs,
+            endpoint_manager,
+            metrics_service,
+            event_bus,
+            peers: Arc::new(DashMap::new()),
+            endpoint_versions: DashMap::new(),
+        }
+    }
+
+    // Runs the gRPC server, registers with the seed peers and then forwards local
+    // status changes until shutdown
+    pub async fn start(self: Arc<Self>, listener: TcpListener, shutdown: CancellationToken) {
+        // Subscribe first so no change made while registering is missed
+        let events = self.event_bus.subscribe();
+        let server = tonic::transport::Server::builder()
+            .add_service(PeerServiceServer::from_arc(self.clone()))
+            .serve_with_incoming_shutdown(TcpListenerStream::new(listener), {
+                let shutdown = shutdown.clone();
+                async move { shutdown.cancelled().await }
+            });
+        let server = tokio::spawn(server);
+
+        self.register_with_seeds().await;
+        self.forward_local_updates(events, shutdown).await;
+
+        match server.await {
+            Ok(Err(e)) => warn!("Peer gRPC server failed: {}", e),
+            Err(e) => warn!("Peer gRPC server task failed: {}", e),
+            Ok(Ok(())) => {}
+        }
+        info!("Peer discovery stopped");
+    }
+
+    async fn register_with_seeds(&self) {
+        for seed in &self.seed_peers {
+            if *seed == self.self_address {
+                continue;
+            }
+            let mut client = match self.add_peer(seed) {
+                Ok(client) => client,
+                Err(e) => {
+                    warn!("Invalid seed peer {}: {}", seed, e);
+                    continue;
+                }
+            };
+
+            let request = self.request(GetPeersRequest { address: self.self_address.clone() });
+            match client.get_peers(request).await {
+                Ok(response) => {
+                    self.mark_seen(seed);
+                    self.learn_peers(response.into_inner().peers);
+                    info!("Registered with seed peer {} ({} peers known)", seed, self.peers.len());
+                }
+                Err(e) => {
+                    self.mark_failed(seed);
+                    warn!("Failed to register with seed peer {}: {}", seed, e);
+                }
+            }
+        }
+    }
+
+    async fn forward_local_updates(&self, mut events: broadcast::Receiver<SystemEvent>, shutdown: CancellationToken) {
+        let period = Duration::from_secs((self.peer_timeout_secs / 2).max(1));
+        let mut refresh = interval_at(Instant::now() + period, period);
+        loop {
+            let event = tokio::select! {
+                _ = shutdown.cancelled() => break,
+                _ = refresh.tick() => {
+                    self.refresh_peers().await;
+                    continue;
+                }
+                event = events.recv() => event,
+            };
+
+            match event {
+                Ok(SystemEvent::EndpointStatusChanged { url, name, status, timestamp, .. }) => {
+                    let update = EndpointHealthUpdate {
+                        origin: self.self_address.clone(),
+                        endpoint_url: url,
+                        endpoint_name: name,
+                        status: status.to_string(),
+                        timestamp_ms: timestamp.timestamp_millis(),
+                    };
+                    self.endpoint_versions.insert(update.endpoint_url.clone(), update.timestamp_ms);
+                    self.broadcast(update).await;
+                }
+                Ok(_) => {}
+                Err(RecvError::Lagged(skipped)) => warn!("Peer sync skipped {} local events", skipped),
+                Err(RecvError::Closed) => break,
+            }
+        }
+    }
+
+    pub async fn broadcast(&self, update: EndpointHealthUpdate) {
+        let announcements = self.peer_clients().into_iter().map(|(address, mut client)| {
+            let request = self.request(update.clone());
+            async move {
+                let result = client.announce(request).await;
+                (address, result)
+            }
+        });
+
+        for (address, result) in futures::future::join_all(announcements).await {
+            match result {
+                Ok(_) => self.mark_seen(&address),
+                Err(e) => {
+                    self.mark_failed(&address);
+                    debug!("Failed to announce to peer {}: {}", address, e);
+                }
+            }
+        }
+    }
+
+    // Asks every peer for its peer list, which doubles as a liveness check, then drops
+    // peers that have been silent for longer than the timeout
+    async fn refresh_peers(&self) {
+        let calls = self.peer_clients().into_iter().map(|(address, mut client)| {
+            let request = self.request(GetPeersRequest { address: self.self_address.clone() });
+            async move {
+                let result = client.get_peers(request).await;
+                (address, result)
+            }
+        });
+
+        for (address, result) in futures::future::join_all(calls).await {
+            match result {
+                Ok(response) => {
+                    self.mark_seen(&address);
+                    self.learn_peers(response.into_inner().peers);
+                }
+                Err(e) => {
+                    self.mark_failed(&address);
+                    debug!("Failed to refresh peer {}: {}", address, e);
+                }
+            }
+        }
+        self.prune_stale_peers();
+    }
+
+    fn prune_stale_peers(&self) {
+        let cutoff = Utc::now() - chrono::Duration::seconds(self.peer_timeout_secs as i64);
+        self.peers.retain(|address, peer| {
+            let keep = self.seed_peers.contains(address) || peer.last_seen.unwrap_or(peer.added_at) >= cutoff;
+            if !keep {
+                info!("Dropping unresponsive peer {} ({} failed calls)", address, peer.consecutive_failures);
+            }
+            keep
+        });
+    }
+
+    // Returns whether the update was newer than anything seen for the endpoint
+    pub async fn apply_update(&self, update: &EndpointHealthUpdate) -> Result<bool, AppError> {
+        let status = parse_status(&update.status)?;
+
+        // Peers only report on endpoints configured here; gossip never adds one
+        if self.endpoint_manager.endpoint_id_for_url(&update.endpoint_url).await.is_none() {
+            debug!("Ignoring update for unknown endpoint {} ({}) from {}", update.endpoint_name, update.endpoint_url, update.origin);
+            return Ok(false);
+        }
+
+        {
+            let mut version = self.endpoint_versions.entry(update.endpoint_url.clone()).or_insert(i64::MIN);
+            if update.timestamp_ms <= *version {
+                debug!("Ignoring stale update for {} from {}", update.endpoint_url, update.origin);
+                return Ok(false);
+            }
+            *version = update.timestamp_ms;
+        }
+
+        if self.endpoint_manager.apply_peer_status(&update.endpoint_url, status).await.is_none() {
+            return Ok(false);
+        }
+
+        let latency_ms = (Utc::now().timestamp_millis() - update.timestamp_ms).max(0);
+        self.metrics_service.record_peer_sync(latency_ms as f64);
+        Ok(true)
+    }
+
+    fn add_peer(&self, address: &str) -> Result<PeerServiceClient<Channel>, AppError> {
+        if address == self.self_address {
+            return Err(AppError::validation("Peer address is this node"));
+        }
+        if let Some(peer) = self.peers.get(address) {
+            return Ok(peer.client.clone());
+        }
+
+        let channel = Channel::from_shared(address.to_string())
+            .map_err(|e| AppError::validation(&format!("Invalid peer address: {}", e)))?
+            .connect_lazy();
+        let client = PeerServiceClient::new(channel);
+        self.peers.insert(address.to_string(), PeerState {
+            added_at: Utc::now(),
+            last_seen: None,
+            consecutive_failures: 0,
+            client: client.clone(),
+        });
+        info!("Added peer {}", address);
+        Ok(client)
+    }
+
+    fn learn_peers(&self, peers: Vec<String>) {
+        for peer in peers {
+            if let Err(e) = self.add_peer(&peer) {
+                debug!("Ignoring invalid peer address {}: {}", peer, e);
+            }
+        }
+    }
+
+    fn peer_clients(&self) -> Vec<(String, PeerServiceClient<Channel>)> {
+        self.peers.iter()
+            .map(|entry| (entry.key().clone(), entry.value().client.clone()))
+            .collect()
+    }
+
+    fn request<T>(&self, message: T) -> Request<T> {
+        let mut request = Request::new(message);
+        // Config validation already checked the secret is printable ASCII
+        if let Ok(secret) = self.shared_secret.parse() {
+            request.metadata_mut().insert(PEER_SECRET_HEADER, secret);
+        }
+        request
+    }
+
+    fn is_authorized<T>(&self, request: &Request<T>) -> bool {
+        let presented = request.metadata()
+            .get(PEER_SECRET_HEADER)
+            .map(|secret| secret.as_bytes())
+            .unwrap_or_default();
+        !self.shared_secret.is_empty() && constant_time_eq(presented, self.shared_secret.as_bytes())
+    }
+
+    fn mark_seen(&self, address: &str) {
+        if let Some(mut peer) = self.peers.get_mut(address) {
+            peer.last_seen = Some(Utc::now());
+            peer.consecutive_failures = 0;
+        }
+    }
+
+    fn mark_failed(&self, address: &str) {
+        if let Some(mut peer) = self.peers.get_mut(address) {
+            peer.consecutive_failures += 1;
+        }
+    }
+}
+
+#[tonic::async_trait]
+impl PeerService for PeerDiscovery {
+    async fn announce(&self, request: Request<EndpointHealthUpdate>) -> Result<Response<AnnounceResponse>, Status> {
+        if !self.is_authorized(&request) {
+            return Err(Status::unauthenticated("Missing or invalid peer secret"));
+        }
+        let update = request.into_inner();
+        // A node that announces to us is a peer even if it never registered
+        if !update.origin.is_empty() && self.add_peer(&update.origin).is_ok() {
+            self.mark_seen(&update.origin);
+        }
+
+        let applied = self.apply_update(&update).await
+            .map_err(|e| Status::invalid_argument(e.to_string()))?;
+        Ok(Response::new(AnnounceResponse { applied }))
+    }
+
+    async fn get_peers(&self, request: Request<GetPeersRequest>) -> Result<Response<PeersResponse>, Status> {
+        if !self.is_authorized(&request) {
+            return Err(Status::unauthenticated("Missing or invalid peer secret"));
+        }
+        let caller = request.into_inner().address;
+        let peers = self.peers.iter()
+            .map(|entry| entry.key().clone())
+            .filter(|address| *address != caller)
+            .collect();
+
+        if !caller.is_empty() && self.add_peer(&caller).is_ok() {
+            self.mark_seen(&caller);
+        }
+        Ok(Response::new(PeersResponse { peers }))
+    }
+}
+
+// Compares every byte so the time taken doesn't reveal how much of the secret matched
+fn constant_time_eq(a: &[u8], b: &[u8]) -> bool {
+    a.len() == b.len() && a.iter().zip(b).fold(0u8, |diff, (x, y)| diff | (x ^ y)) == 0
+}
+
+fn parse_status(status: &str) -> Result<EndpointStatus, AppError> {
+    match status {
+        "healthy" => Ok(EndpointStatus::Healthy),
+        "degraded" => Ok(EndpointStatus::Degraded),
+        "unhealthy" => Ok(EndpointStatus::Unhealthy),
+        "unknown" => Ok(EndpointStatus::Unknown),
+        other => Err(AppError::validation(&format!("Unknown endpoint status: {}", other))),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{
+        circuit_breaker::CircuitBreakerRegistry,
+        config::{Config, EndpointConfig},
+        test_server::shared_metrics,
+    };
+
+    const SECRET: &str = "peer-secret-shared-by-every-test-node";
+
+    struct Node {
+        discovery: Arc<PeerDiscovery>,
+        manager: Arc<EndpointManager>,
+        address: String,
+    }
+
+    async fn start_node(seed_peers: Vec<String>, shutdown: &CancellationToken) -> Node {
+        let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let address = format!("http://{}", listener.local_addr().unwrap());
+
+        let mut config = Config::default();
+        let template = config.endpoints[0].clone();
+        config.endpoints = vec![EndpointConfig {
+            url: "http://127.0.0.1:9200".to_string(),
+            name: "shared".to_string(),
+            ..template
+        }];
+        let event_bus = Arc::new(EventBus::new());
+        let manager = Arc::new(
+            EndpointManager::new(
+                config.endpoints.clone(),
+                config,
+                Arc::new(CircuitBreakerRegistry::new(event_bus.clone())),
+            ).await.unwrap(),
+        );
+        let peer_config = PeerConfig {
+            enabled: true,
+            advertise_address: Some(address.clone()),
+            seed_peers,
+            shared_secret: SECRET.to_string(),
+            ..PeerConfig::default()
+        };
+        let discovery = Arc::new(PeerDiscovery::new(&peer_config, manager.clone(), shared_metrics(), event_bus));
+        tokio::spawn(discovery.clone().start(listener, shutdown.clone()));
+
+        Node { discovery, manager, address }
+    }
+
+    async fn status_of(manager: &EndpointManager, url: &str) -> Option<EndpointStatus> {
+        manager.get_endpoint_info().await
+            .into_iter()
+            .find(|e| e.url == url)
+            .map(|e| e.status)
+    }
+
+    async fn wait_for<F, Fut>(mut condition: F)
+    where
+        F: FnMut() -> Fut,
+        Fut: std::future::Future<Output = bool>,
+    {
+        for _ in 0..100 {
+            if condition().await {
+                return;
+            }
+            tokio::time::sleep(Duration::from_millis(20)).await;
+        }
+        panic!("condition not met in time");
+    }
+
+    #[tokio::test]
+    async fn test_two_nodes_share_endpoint_health() {
+        let shutdown = CancellationToken::new();
+        let seed = start_node(Vec::new(), &shutdown).await;
+        let node = start_node(vec![seed.address.clone()], &shutdown).await;
+
+        // Registration makes each node a peer of the other
+        wait_for(|| async { seed.discovery.peers.contains_key(&node.address) }).await;
+        assert!(node.discovery.peers.contains_key(&seed.address));
+
+        let url = "http://127.0.0.1:9200";
+        let id = node.manager.get_endpoint_info().await[0].id;
+        node.manager.update_endpoint_status(id, EndpointStatus::Unhealthy).await;
+        wait_for(|| async { status_of(&seed.manager, url).await == Some(EndpointStatus::Unhealthy) }).await;
+
+        // And in the other direction
+        let seed_id = seed.manager.get_endpoint_info().await[0].id;
+        seed.manager.update_endpoint_status(seed_id, EndpointStatus::Healthy).await;
+        wait_for(|| async { status_of(&node.manager, url).await == Some(EndpointStatus::Healthy) }).await;
+
+        // A status for an endpoint the other node doesn't have never adds it there
+        node.discovery.broadcast(EndpointHealthUpdate {
+            origin: node.address.clone(),
+            endpoint_url: "http://127.0.0.1:9201".to_string(),
+            endpoint_name: "discovered".to_string(),
+            status: "healthy".to_string(),
+            timestamp_ms: Utc::now().timestamp_millis(),
+        }).await;
+        assert_eq!(status_of(&seed.manager, "http://127.0.0.1:9201").await, None);
+        assert_eq!(seed.manager.get_endpoint_info().await.len(), 1);
+
+        shutdown.cancel();
+    }
+
+    #[tokio::test]
+    async fn test_stale_updates_are_ignored() {
+        let shutdown = CancellationToken::new();
+        let node = start_node(Vec::new(), &shutdown).await;
+        let url = "http://127.0.0.1:9200";
+        let update = |status: &str, timestamp_ms: i64| EndpointHealthUpdate {
+            origin: String::new(),
+            endpoint_url: url.to_string(),
+            endpoint_name: "shared".to_string(),
+            status: status.to_string(),
+            timestamp_ms,
+        };
+
+        assert!(node.discovery.apply_update(&update("unhealthy", 2_000)).await.unwrap());
+        assert!(!node.discovery.apply_update(&update("healthy", 1_000)).await.unwrap());
+        assert!(!node.discovery.apply_update(&update("healthy", 2_000)).await.unwrap());
+        assert_eq!(status_of(&node.manager, url).await, Some(EndpointStatus::Unhealthy));
+
+        assert!(node.discovery.apply_update(&update("healthy", 3_000)).await.unwrap());
+        assert_eq!(status_of(&node.manager, url).await, Some(EndpointStatus::Healthy));
+
+        shutdown.cancel();
+    }
+
+    fn with_secret<T>(secret: &str, message: T) -> Request<T> {
+        let mut request = Request::new(message);
+        request.metadata_mut().insert(PEER_SECRET_HEADER, secret.parse().unwrap());
+        request
+    }
+
+    #[tokio::test]
+    async fn test_calls_without_the_secret_are_rejected() {
+        let shutdown = CancellationToken::new();
+        let node = start_node(Vec::new(), &shutdown).await;
+        let mut client = PeerServiceClient::new(Channel::from_shared(node.address.clone()).unwrap().connect_lazy());
+        let intruder = "http://127.0.0.1:9300".to_string();
+
+        let error = client.get_peers(GetPeersRequest { address: intruder.clone() }).await.unwrap_err();
+        assert_eq!(error.code(), tonic::Code::Unauthenticated);
+        let error = client.get_peers(with_secret("guessed", GetPeersRequest { address: intruder.clone() })).await.unwrap_err();
+        assert_eq!(error.code(), tonic::Code::Unauthenticated);
+
+        let update = EndpointHealthUpdate {
+            origin: intruder.clone(),
+            endpoint_url: "http://127.0.0.1:9200".to_string(),
+            endpoint_name: "shared".to_string(),
+            status: "unhealthy".to_string(),
+            timestamp_ms: Utc::now().timestamp_millis(),
+        };
+        let error = client.announce(update.clone()).await.unwrap_err();
+        assert_eq!(error.code(), tonic::Code::Unauthenticated);
+        assert!(!node.discovery.peers.contains_key(&intruder));
+        assert_eq!(status_of(&node.manager, "http://127.0.0.1:9200").await, Some(EndpointStatus::Unknown));
+
+        // The same calls with the secret go through
+        let response = client.announce(with_secret(SECRET, update)).await.unwrap();
+        assert!(response.into_inner().applied);
+        assert!(node.discovery.peers.contains_key(&intruder));
+
+        shutdown.cancel();
+    }
+
+    #[tokio::test]
+    async fn test_silent_peers_are_dropped() {
+        let shutdown = CancellationToken::new();
+        let seed = "http://127.0.0.1:9400".to_string();
+        let node = start_node(vec![seed.clone()], &shutdown).await;
+        wait_for(|| async { node.discovery.peers.contains_key(&seed) }).await;
+        let (silent, recent) = ("http://127.0.0.1:9401", "http://127.0.0.1:9402");
+        node.discovery.add_peer(silent).unwrap();
+        node.discovery.add_peer(recent).unwrap();
+
+        let long_ago = Utc::now() - chrono::Duration::seconds(node.discovery.peer_timeout_secs as i64 + 1);
+        for mut peer in node.discovery.peers.iter_mut() {
+            peer.added_at = long_ago;
+        }
+        node.discovery.mark_seen(recent);
+        node.discovery.prune_stale_peers();
+
+        // Seed peers are configured, so they stay even while unreachable
+        assert!(node.discovery.peers.contains_key(&seed));
+        assert!(node.discovery.peers.contains_key(recent));
+        assert!(!node.discovery.peers.contains_key(silent));
+
+        shutdown.cancel();
+    }
+}
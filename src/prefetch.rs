@@ -0,0 +1,169 @@
+use crate::{
+    config::PrefetchRule,
+    router::PostRequestHook,
+    AppState,
+};
+use async_trait::async_trait;
+use dashmap::DashMap;
+use serde_json::{json, Value};
+use std::{
+    collections::HashMap,
+    time::{Duration, Instant},
+};
+use tracing::debug;
+
+// Prefetched keys older than this no longer count towards prefetch hits
+const PREFETCH_TRACKING_WINDOW: Duration = Duration::from_secs(300);
+const MAX_TRACKED_PREFETCHES: usize = 10_000;
+
+pub struct PrefetchHook {
+    rules: HashMap<String, Vec<PrefetchRule>>,
+    // Requests issued speculatively and not yet asked for by a client
+    prefetched: DashMap<String, Instant>,
+}
+
+impl PrefetchHook {
+    pub fn new(rules: HashMap<String, Vec<PrefetchRule>>) -> Self {
+        Self {
+            rules,
+            prefetched: DashMap::new(),
+        }
+    }
+
+    // Builds the speculative requests for a completed call, skipping rules whose
+    // placeholders can't be resolved
+    fn plan_requests(&self, method: &str, params: &Value, response: &Value) -> Vec<(String, Value)> {
+        let Some(rules) = self.rules.get(method) else {
+            return Vec::new();
+        };
+
+        rules.iter()
+            .filter_map(|rule| {
+                let params = rule.params.iter()
+                    .map(|param| resolve_placeholder(param, params, response))
+                    .collect::<Option<Vec<_>>>()?;
+                Some((rule.method.clone(), Value::Array(params)))
+            })
+            .collect()
+    }
+
+    // Returns true if this call was for something we prefetched recently
+    fn take_prefetched(&self, method: &str, params: &Value) -> bool {
+        self.prefetched
+            .remove(&prefetch_key(method, params))
+            .is_some_and(|(_, issued_at)| issued_at.elapsed() < PREFETCH_TRACKING_WINDOW)
+    }
+
+    fn track_prefetched(&self, method: &str, params: &Value) {
+        if self.prefetched.len() >= MAX_TRACKED_PREFETCHES {
+            self.prefetched.retain(|_, issued_at| issued_at.elapsed() < PREFETCH_TRACKING_WINDOW);
+        }
+        self.prefetched.insert(prefetch_key(method, params), Instant::now());
+    }
+}
+
+#[async_trait]
+impl PostRequestHook for PrefetchHook {
+    async fn on_success(&self, method: &str, params: &Value, response: &Value, state: &AppState) {
+        if self.take_prefetched(method, params) {
+            state.metrics_service.record_prefetch_cache_hit();
+        }
+
+        for (prefetch_method, prefetch_params) in self.plan_requests(method, params, response) {
+            let payload = json!({
+                "jsonrpc": "2.0",
+                "id": format!("prefetch-{}", uuid::Uuid::new_v4()),
+                "method": prefetch_method,
+                "params": prefetch_params,
+            });
+
+            state.metrics_service.record_prefetch_request();
+            match state.rpc_router.route_request(payload, None, None, None).await {
+                Ok(_) => self.track_prefetched(&prefetch_method, &prefetch_params),
+                Err(e) => debug!("Prefetch of {} after {} failed: {}", prefetch_method, method, e),
+            }
+        }
+    }
+}
+
+fn prefetch_key(method: &str, params: &Value) -> String {
+    format!("{}:{}", method, params)
+}
+
+fn resolve_placeholder(template: &Value, params: &Value, response: &Value) -> Option<Value> {
+    match template {
+        Value::String(s) => {
+            if let Some(index) = s.strip_prefix("$params[").and_then(|rest| rest.strip_suffix(']')) {
+                let index: usize = index.parse().ok()?;
+                params.get(index).cloned()
+            } else if let Some(pointer) = s.strip_prefix("$result") {
+                response.get("result")?.pointer(pointer).cloned()
+            } else {
+                Some(template.clone())
+            }
+        }
+        Value::Array(items) => items.iter()
+            .map(|item| resolve_placeholder(item, params, response))
+            .collect::<Option<Vec<_>>>()
+            .map(Value::Array),
+        Value::Object(fields) => fields.iter()
+            .map(|(key, value)| Some((key.clone(), resolve_placeholder(value, params, response)?)))
+            .collect::<Option<serde_json::Map<_, _>>>()
+            .map(Value::Object),
+        other => Some(other.clone()),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn hook() -> PrefetchHook {
+        let mut rules = HashMap::new();
+        rules.insert("getAccountInfo".to_string(), vec![
+            PrefetchRule {
+                method: "getBalance".to_string(),
+                params: vec![json!("$params[0]")],
+            },
+            PrefetchRule {
+                method: "getAccountInfo".to_string(),
+                params: vec![json!("$result/value/owner"), json!({"encoding": "base64"})],
+            },
+        ]);
+        PrefetchHook::new(rules)
+    }
+
+    #[test]
+    fn test_prefetch_rules_resolve_placeholders() {
+        let hook = hook();
+        let params = json!(["AddressA", {"encoding": "base64"}]);
+        let response = json!({
+            "jsonrpc": "2.0",
+            "id": 1,
+            "result": {"context": {"slot": 1}, "value": {"owner": "OwnerB", "lamports": 10}}
+        });
+
+        let planned = hook.plan_requests("getAccountInfo", &params, &response);
+        assert_eq!(planned, vec![
+            ("getBalance".to_string(), json!(["AddressA"])),
+            ("getAccountInfo".to_string(), json!(["OwnerB", {"encoding": "base64"}])),
+        ]);
+
+        // Unresolvable placeholders drop the rule instead of sending a bad request
+        let planned = hook.plan_requests("getAccountInfo", &params, &json!({"result": {"value": null}}));
+        assert_eq!(planned, vec![("getBalance".to_string(), json!(["AddressA"]))]);
+
+        assert!(hook.plan_requests("getSlot", &json!([]), &response).is_empty());
+    }
+
+    #[test]
+    fn test_prefetch_hits_are_counted_once() {
+        let hook = hook();
+        let params = json!(["OwnerB", {"encoding": "base64"}]);
+
+        assert!(!hook.take_prefetched("getAccountInfo", &params));
+        hook.track_prefetched("getAccountInfo", &params);
+        assert!(hook.take_prefetched("getAccountInfo", &params));
+        assert!(!hook.take_prefetched("getAccountInfo", &params));
+    }
+}
@@ -0,0 +1,244 @@
+use axum::{
+    extract::Request,
+    http::{HeaderMap, HeaderValue},
+    middleware::Next,
+    response::Response,
+};
+use opentelemetry::{
+    baggage::BaggageExt,
+    propagation::{Extractor, TextMapPropagator},
+    trace::FutureExt,
+    Context,
+};
+use opentelemetry_sdk::propagation::BaggagePropagator;
+use std::collections::HashMap;
+use tracing::Instrument;
+use uuid::Uuid;
+
+pub const BAGGAGE_HEADER: &str = "baggage";
+pub const USER_ID_BAGGAGE_KEY: &str = "userId";
+pub const X_REQUEST_ID: &str = "x-request-id";
+// Longer client-supplied ids are replaced rather than forwarded
+const MAX_REQUEST_ID_LEN: usize = 128;
+
+// Correlation id of the request being served, kept in the request extensions and in the
+// OpenTelemetry context so it follows the request onto spawned upstream calls
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct RequestId(pub String);
+
+struct HeaderExtractor<'a>(&'a HeaderMap);
+
+impl Extractor for HeaderExtractor<'_> {
+    fn get(&self, key: &str) -> Option<&str> {
+        self.0.get(key).and_then(|value| value.to_str().ok())
+    }
+
+    fn keys(&self) -> Vec<&str> {
+        self.0.keys().map(|key| key.as_str()).collect()
+    }
+}
+
+// Carries W3C baggage (userId, requestTier, chainId, ...) from the incoming request
+// through the proxy so upstream endpoints see the same context as we did
+#[derive(Debug, Default)]
+pub struct RequestContextPropagator {
+    propagator: BaggagePropagator,
+}
+
+impl RequestContextPropagator {
+    pub fn new() -> Self {
+        Self {
+            propagator: BaggagePropagator::new(),
+        }
+    }
+
+    pub fn extract(&self, headers: &HeaderMap) -> Context {
+        self.propagator.extract(&HeaderExtractor(headers))
+    }
+
+    // Outbound headers for the baggage in the given context; empty if there is none
+    pub fn inject(&self, cx: &Context) -> HashMap<String, String> {
+        let mut headers = HashMap::new();
+        if !cx.baggage().is_empty() {
+            self.propagator.inject_context(cx, &mut headers);
+        }
+        headers
+    }
+
+    pub fn user_id(cx: &Context) -> Option<String> {
+        cx.baggage()
+            .get(USER_ID_BAGGAGE_KEY)
+            .map(|value| value.as_str().to_string())
+    }
+
+    // Stores the extracted context in the request extensions for later middleware and
+    // attaches it to the handler future so outbound calls can pick it up
+    pub async fn middleware(mut request: Request, next: Next) -> Response {
+        if !request.headers().contains_key(BAGGAGE_HEADER) {
+            return next.run(request).await;
+        }
+
+        let cx = RequestContextPropagator::new().extract(request.headers());
+        request.extensions_mut().insert(cx.clone());
+        next.run(request).with_context(cx).await
+    }
+}
+
+pub fn current_request_id() -> Option<String> {
+    Context::current().get::<RequestId>().map(|id| id.0.clone())
+}
+
+// The caller's X-Request-ID if it is usable as-is, otherwise a fresh one
+fn request_id_from(headers: &HeaderMap) -> String {
+    headers.get(X_REQUEST_ID)
+        .and_then(|value| value.to_str().ok())
+        .map(str::trim)
+        .filter(|id| !id.is_empty() && id.len() <= MAX_REQUEST_ID_LEN)
+        .map(str::to_string)
+        .unwrap_or_else(|| Uuid::new_v4().to_string())
+}
+
+// Outermost so that every log line, trace span, error body and upstream call for the
+// request carries the same id, and the client gets it back on the response
+pub async fn request_id_middleware(mut request: Request, next: Next) -> Response {
+    let request_id = request_id_from(request.headers());
+    request.extensions_mut().insert(RequestId(request_id.clone()));
+
+    let span = tracing::info_span!("request", request_id = %request_id);
+    let cx = Context::current_with_value(RequestId(request_id.clone()));
+    let mut response = next.run(request).with_context(cx).instrument(span).await;
+
+    if let Ok(value) = HeaderValue::from_str(&request_id) {
+        response.headers_mut().insert(X_REQUEST_ID, value);
+    }
+    response
+}
+
+// Forwards the current request's baggage and X-Request-ID on an outbound upstream call
+pub fn with_baggage(builder: reqwest::RequestBuilder) -> reqwest::RequestBuilder {
+    let builder = match current_request_id() {
+        Some(request_id) => builder.header(X_REQUEST_ID, request_id),
+        None => builder,
+    };
+    RequestContextPropagator::new()
+        .inject(&Context::current())
+        .into_iter()
+        .fold(builder, |builder, (name, value)| builder.header(name, value))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_server::TestServerBuilder;
+    use serde_json::json;
+    use wiremock::{matchers::method, Mock, ResponseTemplate};
+
+    #[test]
+    fn test_extract_baggage_items() {
+        let mut headers = HeaderMap::new();
+        headers.insert(BAGGAGE_HEADER, HeaderValue::from_static("userId=alice,requestTier=premium,chainId=mainnet"));
+
+        let propagator = RequestContextPropagator::new();
+        let cx = propagator.extract(&headers);
+        assert_eq!(RequestContextPropagator::user_id(&cx).as_deref(), Some("alice"));
+        assert_eq!(cx.baggage().get("requestTier").map(|v| v.as_str().to_string()).as_deref(), Some("premium"));
+
+        let outbound = propagator.inject(&cx);
+        let forwarded = propagator.extract(&outbound.iter()
+            .map(|(k, v)| (k.parse().unwrap(), HeaderValue::from_str(v).unwrap()))
+            .collect());
+        assert_eq!(forwarded.baggage().len(), 3);
+        assert_eq!(forwarded.baggage().get("chainId").map(|v| v.as_str().to_string()).as_deref(), Some("mainnet"));
+    }
+
+    #[test]
+    fn test_no_baggage_injects_nothing() {
+        let propagator = RequestContextPropagator::new();
+        let cx = propagator.extract(&HeaderMap::new());
+        assert!(RequestContextPropagator::user_id(&cx).is_none());
+        assert!(propagator.inject(&cx).is_empty());
+    }
+
+    #[tokio::test]
+    async fn test_baggage_survives_proxy_round_trip() {
+        let server = TestServerBuilder::new().start().await;
+        Mock::given(method("POST"))
+            .respond_with(crate::test_server::rpc_result(json!(42)))
+            .expect(2)
+            .mount(server.endpoint_mock("primary"))
+            .await;
+
+        for (id, baggage) in [(1, "userId=alice"), (2, "requestTier=premium, chainId=mainnet")] {
+            let response: serde_json::Value = server.client.post(&server.base_url)
+                .header(BAGGAGE_HEADER, baggage)
+                .json(&json!({"jsonrpc": "2.0", "id": id, "method": "getSlot"}))
+                .send()
+                .await
+                .unwrap()
+                .json()
+                .await
+                .unwrap();
+            assert_eq!(response["result"], 42);
+        }
+
+        let requests = server.endpoint_mock("primary").received_requests().await.unwrap();
+        let forwarded: Vec<Vec<String>> = requests.iter()
+            .map(|request| {
+                // Items come back in whatever order the propagator emits them
+                let mut items: Vec<String> = request.headers.get(BAGGAGE_HEADER)
+                    .map(|value| value.to_str().unwrap().split(',').map(|item| item.trim().to_string()).collect())
+                    .unwrap_or_default();
+                items.sort();
+                items
+            })
+            .collect();
+        assert_eq!(forwarded, vec![
+            vec!["userId=alice".to_string()],
+            vec!["chainId=mainnet".to_string(), "requestTier=premium".to_string()],
+        ]);
+    }
+
+    #[test]
+    fn test_request_id_from_headers() {
+        let mut headers = HeaderMap::new();
+        headers.insert(X_REQUEST_ID, HeaderValue::from_static("client-abc-123"));
+        assert_eq!(request_id_from(&headers), "client-abc-123");
+
+        headers.insert(X_REQUEST_ID, HeaderValue::from_str(&"x".repeat(MAX_REQUEST_ID_LEN + 1)).unwrap());
+        assert!(Uuid::parse_str(&request_id_from(&headers)).is_ok());
+        assert!(Uuid::parse_str(&request_id_from(&HeaderMap::new())).is_ok());
+    }
+
+    #[tokio::test]
+    async fn test_request_id_follows_cache_miss_to_upstream_and_back() {
+        let server = TestServerBuilder::new()
+            .with_config(|config| config.cache.enabled = true)
+            .start()
+            .await;
+        Mock::given(method("POST"))
+            .respond_with(ResponseTemplate::new(200).set_body_json(json!({"jsonrpc": "2.0", "id": 1, "result": {"value": 7}})))
+            .mount(server.endpoint_mock("primary"))
+            .await;
+
+        let response = server.client.post(&server.base_url)
+            .header(X_REQUEST_ID, "corr-0001")
+            .json(&json!({"jsonrpc": "2.0", "id": 1, "method": "getBalance", "params": ["11111111111111111111111111111111"]}))
+            .send()
+            .await
+            .unwrap();
+        assert_eq!(response.headers()[X_REQUEST_ID], "corr-0001");
+        let body: serde_json::Value = response.json().await.unwrap();
+        assert_eq!(body["result"]["value"], 7);
+
+        let requests = server.endpoint_mock("primary").received_requests().await.unwrap();
+        assert!(!requests.is_empty());
+        for request in &requests {
+            assert_eq!(request.headers.get(X_REQUEST_ID).unwrap(), "corr-0001");
+        }
+
+        // Without one, the proxy mints an id and still hands it back
+        let response = server.client.get(server.url("/health")).send().await.unwrap();
+        let generated = response.headers()[X_REQUEST_ID].to_str().unwrap();
+        assert!(Uuid::parse_str(generated).is_ok());
+    }
+}
@@ -1,18 +1,31 @@
 use crate::{
-    config::{Config, RateLimit, RateLimitConfig},
+    audit::client_ip,
+    auth::AuthContext,
+    config::{Config, RateLimit, RateLimitBackend, RateLimitConfig},
     error::AppError,
+    metrics::MetricsService,
+    AppState,
+};
+use async_trait::async_trait;
+use axum::{
+    extract::State,
+    http::{header::USER_AGENT, HeaderMap},
+    Extension, Json,
 };
 use governor::{
     clock::{Clock, DefaultClock},
     state::{InMemoryState, NotKeyed},
     Quota, RateLimiter,
 };
+use redis::{aio::ConnectionManager, Client};
+use serde::{Deserialize, Serialize};
 use serde_json::{json, Value};
 use std::{
     collections::HashMap,
+    net::{IpAddr, Ipv6Addr},
     num::NonZeroU32,
     sync::Arc,
-    time::{Duration, Instant},
+    time::{Duration, Instant, SystemTime, UNIX_EPOCH},
 };
 use tokio::sync::RwLock;
 use tracing::{debug, warn};
@@ -22,14 +35,57 @@ type RateLimiterType = RateLimiter<NotKeyed, InMemoryState, DefaultClock>;
 #[derive(Debug, Clone)]
 pub struct RateLimitService {
     config: RateLimitConfig,
-    global_limiter: Option<Arc<RateLimiterType>>,
+    // Seeded from the config; PATCH /admin/rate-limits replaces entries while running
+    limits: Arc<RwLock<ActiveLimits>>,
+    global_limiter: Arc<RwLock<Option<Arc<RateLimiterType>>>>,
     method_limiters: Arc<RwLock<HashMap<String, Arc<RateLimiterType>>>>,
     ip_limiters: Arc<RwLock<HashMap<String, Arc<RateLimiterType>>>>,
     api_key_limiters: Arc<RwLock<HashMap<String, Arc<RateLimiterType>>>>,
+    // One limiter per method in `global_method_limits`, shared by every client and tenant
+    global_method_limiters: Arc<HashMap<String, Arc<RateLimiterType>>>,
     rate_limit_stats: Arc<RwLock<RateLimitStats>>,
+    metrics_service: Option<Arc<MetricsService>>,
+    // Replaces the in-memory limiters when the Redis backend is configured
+    redis_limiter: Option<Arc<RedisRateLimiter>>,
 }
 
 #[derive(Debug, Clone)]
+struct ActiveLimits {
+    default_rate: u32,
+    default_burst: u32,
+    per_method_limits: HashMap<String, RateLimit>,
+    per_ip_limits: HashMap<String, RateLimit>,
+}
+
+#[derive(Debug, Clone, Copy, Deserialize)]
+pub struct RateLimitValues {
+    pub rate: NonZeroU32,
+    pub burst: NonZeroU32,
+}
+
+impl From<RateLimitValues> for RateLimit {
+    fn from(values: RateLimitValues) -> Self {
+        RateLimit {
+            rate: values.rate.get(),
+            burst: values.burst.get(),
+            window_seconds: 1,
+        }
+    }
+}
+
+// Body of PATCH /admin/rate-limits; anything left out keeps its current limit
+#[derive(Debug, Default, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct RateLimitUpdate {
+    #[serde(default)]
+    pub global: Option<RateLimitValues>,
+    #[serde(default)]
+    pub methods: HashMap<String, RateLimitValues>,
+    #[serde(default)]
+    pub ips: HashMap<String, RateLimitValues>,
+}
+
+#[derive(Debug, Clone, Default)]
 struct RateLimitStats {
     total_requests: u64,
     blocked_requests: u64,
@@ -37,6 +93,7 @@ struct RateLimitStats {
     blocked_by_method: u64,
     blocked_by_ip: u64,
     blocked_by_api_key: u64,
+    blocked_by_global_method: u64,
     method_stats: HashMap<String, MethodStats>,
     ip_stats: HashMap<String, IpStats>,
     api_key_stats: HashMap<String, ApiKeyStats>,
@@ -62,23 +119,21 @@ struct ApiKeyStats {
     requests: u64,
     blocked: u64,
     last_request: Instant,
+    #[allow(dead_code)]
     first_seen: Instant,
 }
 
-impl Default for RateLimitStats {
-    fn default() -> Self {
-        Self {
-            total_requests: 0,
-            blocked_requests: 0,
-            blocked_by_global: 0,
-            blocked_by_method: 0,
-            blocked_by_ip: 0,
-            blocked_by_api_key: 0,
-            method_stats: HashMap::new(),
-            ip_stats: HashMap::new(),
-            api_key_stats: HashMap::new(),
-        }
-    }
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct RateLimitCounters {
+    pub total_requests: u64,
+    pub blocked_requests: u64,
+    pub blocked_by_global: u64,
+    pub blocked_by_method: u64,
+    pub blocked_by_ip: u64,
+    pub blocked_by_api_key: u64,
+    #[serde(default)]
+    pub blocked_by_global_method: u64,
+    pub method_requests: HashMap<String, (u64, u64)>,
 }
 
 #[derive(Debug, Clone)]
@@ -87,6 +142,17 @@ pub struct RateLimitContext {
     pub api_key: Option<String>,
     pub method: String,
     pub user_agent: Option<String>,
+    pub tenant_id: Option<String>,
+}
+
+impl RateLimitContext {
+    // Tenants get isolated limiter buckets by prefixing every key with their id
+    fn limiter_key(&self, key: &str) -> String {
+        match &self.tenant_id {
+            Some(tenant_id) => format!("{}:{}", tenant_id, key),
+            None => key.to_string(),
+        }
+    }
 }
 
 #[derive(Debug, Clone)]
@@ -108,25 +174,91 @@ impl RateLimitService {
                 NonZeroU32::new(rate_config.default_rate),
                 NonZeroU32::new(rate_config.default_burst)
             ) {
-                let quota = Quota::per_second(rate).allow_burst(burst);
-                Some(Arc::new(RateLimiter::direct(quota)))
+                Some(Arc::new(direct_limiter(RateLimitValues { rate, burst })))
             } else {
                 None
             }
         } else {
             None
         };
+        let limits = ActiveLimits {
+            default_rate: rate_config.default_rate,
+            default_burst: rate_config.default_burst,
+            per_method_limits: rate_config.per_method_limits.clone(),
+            per_ip_limits: bucket_ip_limits(&rate_config.per_ip_limits, rate_config.ipv6_prefix_length),
+        };
+
+        let global_method_limiters = rate_config.global_method_limits.iter()
+            .filter_map(|(method, max_rps)| match NonZeroU32::new(*max_rps) {
+                Some(rate) => Some((method.clone(), Arc::new(RateLimiter::direct(Quota::per_second(rate))))),
+                None => {
+                    warn!("Ignoring global limit of 0 requests per second for {}", method);
+                    None
+                }
+            })
+            .collect();
 
         Self {
             config: rate_config,
-            global_limiter,
+            limits: Arc::new(RwLock::new(limits)),
+            global_limiter: Arc::new(RwLock::new(global_limiter)),
             method_limiters: Arc::new(RwLock::new(HashMap::new())),
             ip_limiters: Arc::new(RwLock::new(HashMap::new())),
             api_key_limiters: Arc::new(RwLock::new(HashMap::new())),
+            global_method_limiters: Arc::new(global_method_limiters),
             rate_limit_stats: Arc::new(RwLock::new(RateLimitStats::default())),
+            metrics_service: None,
+            redis_limiter: None,
         }
     }
 
+    pub fn with_metrics_service(mut self, metrics_service: Arc<MetricsService>) -> Self {
+        self.metrics_service = Some(metrics_service);
+        self
+    }
+
+    pub fn with_redis_limiter(mut self, store: Arc<dyn SlidingWindowStore>) -> Self {
+        self.redis_limiter = Some(Arc::new(RedisRateLimiter::new(self.config.clone(), store)));
+        self
+    }
+
+    // Index of the first exemption covering this key and method
+    fn find_exemption(&self, api_key: &str, method: &str) -> Option<usize> {
+        self.config.exemptions.iter().position(|exemption| {
+            exemption.methods.iter().any(|m| m == method)
+                && glob_matches(&exemption.api_key_pattern, api_key)
+        })
+    }
+
+    // Every call in a single or batch payload counts against the limits, under the caller's IP,
+    // API key and tenant; the whole request is turned away once any call goes over
+    pub async fn check_request(
+        &self,
+        payload: &Value,
+        auth: Option<&AuthContext>,
+        headers: &HeaderMap,
+    ) -> Result<(), AppError> {
+        if !self.config.enabled {
+            return Ok(());
+        }
+        let calls = payload.as_array().map_or(std::slice::from_ref(payload), Vec::as_slice);
+        let methods = calls.iter().filter_map(|call| call.get("method").and_then(Value::as_str));
+        for method in methods {
+            let result = self.check_rate_limit(RateLimitContext {
+                ip_address: client_ip(auth, headers),
+                api_key: auth.and_then(|context| context.api_key.clone()),
+                method: method.to_string(),
+                user_agent: headers.get(USER_AGENT).and_then(|value| value.to_str().ok()).map(str::to_string),
+                tenant_id: auth.and_then(|context| context.tenant_id.clone()),
+            }).await;
+            if !result.allowed {
+                debug!("Rejected {}: {}", method, result.reason.unwrap_or_default());
+                return Err(AppError::RateLimitExceeded);
+            }
+        }
+        Ok(())
+    }
+
     pub async fn check_rate_limit(&self, context: RateLimitContext) -> RateLimitResult {
         if !self.config.enabled {
             return RateLimitResult {
@@ -138,6 +270,23 @@ impl RateLimitService {
             };
         }
 
+        // Exempt keys skip every limiter, including the global one
+        if let Some(api_key) = &context.api_key {
+            if let Some(index) = self.find_exemption(api_key, &context.method) {
+                debug!("Rate limit exemption {} applied for method {}", index, context.method);
+                if let Some(metrics_service) = &self.metrics_service {
+                    metrics_service.record_rate_limit_exemption(index);
+                }
+                return RateLimitResult {
+                    allowed: true,
+                    reason: None,
+                    retry_after: None,
+                    remaining_requests: None,
+                    reset_time: None,
+                };
+            }
+        }
+
         let mut stats = self.rate_limit_stats.write().await;
         stats.total_requests += 1;
 
@@ -176,8 +325,30 @@ impl RateLimitService {
 
         drop(stats); // Release the write lock
 
+        if let Some(redis_limiter) = &self.redis_limiter {
+            return match redis_limiter.first_exceeded(&context).await {
+                Some((scope, result)) => {
+                    self.record_blocked_request(scope, &context).await;
+                    if scope == "global_method" {
+                        if let Some(metrics_service) = &self.metrics_service {
+                            metrics_service.record_global_method_limit_exceeded(&context.method);
+                        }
+                    }
+                    result
+                }
+                None => RateLimitResult {
+                    allowed: true,
+                    reason: None,
+                    retry_after: None,
+                    remaining_requests: None,
+                    reset_time: None,
+                },
+            };
+        }
+
         // Check global rate limit first
-        if let Some(global_limiter) = &self.global_limiter {
+        let global_limiter = self.global_limiter.read().await.clone();
+        if let Some(global_limiter) = global_limiter {
             match global_limiter.check() {
                 Ok(_) => {} // Allowed
                 Err(not_until) => {
@@ -194,8 +365,9 @@ impl RateLimitService {
         }
 
         // Check method-specific rate limit
-        if let Some(method_limit) = self.config.per_method_limits.get(&context.method) {
-            let limiter = self.get_or_create_method_limiter(&context.method, method_limit).await;
+        let method_limit = self.limits.read().await.per_method_limits.get(&context.method).cloned();
+        if let Some(method_limit) = method_limit {
+            let limiter = self.get_or_create_method_limiter(&context.limiter_key(&context.method), &method_limit).await;
             match limiter.check() {
                 Ok(_) => {} // Allowed
                 Err(not_until) => {
@@ -213,8 +385,10 @@ impl RateLimitService {
 
         // Check IP-specific rate limit
         if let Some(ip) = &context.ip_address {
-            if let Some(ip_limit) = self.config.per_ip_limits.get(ip) {
-                let limiter = self.get_or_create_ip_limiter(ip, ip_limit).await;
+            let ip = ip_bucket(ip, self.config.ipv6_prefix_length);
+            let ip_limit = self.limits.read().await.per_ip_limits.get(&ip).cloned();
+            if let Some(ip_limit) = ip_limit {
+                let limiter = self.get_or_create_ip_limiter(&context.limiter_key(&ip), &ip_limit).await;
                 match limiter.check() {
                     Ok(_) => {} // Allowed
                     Err(not_until) => {
@@ -231,17 +405,30 @@ impl RateLimitService {
             }
         }
 
+        // Check the method's limit across all clients
+        if let Some(limiter) = self.global_method_limiters.get(&context.method) {
+            if let Err(not_until) = limiter.check() {
+                self.record_blocked_request("global_method", &context).await;
+                if let Some(metrics_service) = &self.metrics_service {
+                    metrics_service.record_global_method_limit_exceeded(&context.method);
+                }
+                return RateLimitResult {
+                    allowed: false,
+                    reason: Some(format!("Global rate limit exceeded for {}", context.method)),
+                    retry_after: Some(not_until.wait_time_from(DefaultClock::default().now())),
+                    remaining_requests: Some(0),
+                    reset_time: Some(Instant::now() + not_until.wait_time_from(DefaultClock::default().now())),
+                };
+            }
+        }
+
         // Check API key rate limit (if not already checked by auth service)
         if let Some(api_key) = &context.api_key {
             // This would typically be configured per API key
             // For now, use a default rate limit for API keys
-            let default_limit = RateLimit {
-                rate: 1000,
-                burst: 100,
-                window_seconds: 60,
-            };
+            let default_limit = default_api_key_limit();
             
-            let limiter = self.get_or_create_api_key_limiter(api_key, &default_limit).await;
+            let limiter = self.get_or_create_api_key_limiter(&context.limiter_key(api_key), &default_limit).await;
             match limiter.check() {
                 Ok(_) => {} // Allowed
                 Err(not_until) => {
@@ -329,6 +516,12 @@ impl RateLimitService {
                     }
                 }
             }
+            "global_method" => {
+                stats.blocked_by_global_method += 1;
+                if let Some(method_stats) = stats.method_stats.get_mut(&context.method) {
+                    method_stats.blocked += 1;
+                }
+            }
             "api_key" => {
                 stats.blocked_by_api_key += 1;
                 if let Some(api_key) = &context.api_key {
@@ -344,10 +537,10 @@ impl RateLimitService {
             reason, context.method, context.ip_address, context.api_key);
     }
 
-    async fn get_remaining_requests(&self, context: &RateLimitContext) -> Option<u32> {
+    async fn get_remaining_requests(&self, _context: &RateLimitContext) -> Option<u32> {
         // This is a simplified implementation
         // In practice, you'd want to check the actual limiter state
-        if let Some(global_limiter) = &self.global_limiter {
+        if let Some(_global_limiter) = &*self.global_limiter.read().await {
             // Return a rough estimate based on global limiter
             // Note: governor doesn't provide direct access to remaining tokens
             return Some(10); // Placeholder
@@ -357,6 +550,7 @@ impl RateLimitService {
 
     pub async fn get_stats(&self) -> Value {
         let stats = self.rate_limit_stats.read().await;
+        let limits = self.limits.read().await;
         
         let block_rate = if stats.total_requests > 0 {
             stats.blocked_requests as f64 / stats.total_requests as f64
@@ -410,6 +604,7 @@ impl RateLimitService {
                     "method": stats.blocked_by_method,
                     "ip": stats.blocked_by_ip,
                     "api_key": stats.blocked_by_api_key,
+                    "global_method": stats.blocked_by_global_method,
                 }
             },
             "method_stats": method_stats,
@@ -418,12 +613,15 @@ impl RateLimitService {
                 "methods": self.method_limiters.read().await.len(),
                 "ips": self.ip_limiters.read().await.len(),
                 "api_keys": self.api_key_limiters.read().await.len(),
+                "global_methods": self.global_method_limiters.len(),
             },
             "config": {
-                "default_rate": self.config.default_rate,
-                "default_burst": self.config.default_burst,
-                "method_limits_count": self.config.per_method_limits.len(),
-                "ip_limits_count": self.config.per_ip_limits.len(),
+                "default_rate": limits.default_rate,
+                "default_burst": limits.default_burst,
+                "method_limits_count": limits.per_method_limits.len(),
+                "ip_limits_count": limits.per_ip_limits.len(),
+                "method_limits": limits.per_method_limits,
+                "ip_limits": limits.per_ip_limits,
             }
         })
     }
@@ -434,16 +632,69 @@ impl RateLimitService {
         debug!("Rate limiting stats cleared");
     }
 
-    pub async fn update_limits(&self, method: Option<String>, new_limit: RateLimit) {
-        if let Some(method_name) = method {
-            // Update method-specific limit
-            let mut limiters = self.method_limiters.write().await;
-            let quota = Quota::per_second(NonZeroU32::new(new_limit.rate).unwrap_or(NonZeroU32::new(1).unwrap()))
-                .allow_burst(NonZeroU32::new(new_limit.burst).unwrap_or(NonZeroU32::new(1).unwrap()));
-            let limiter = Arc::new(RateLimiter::direct(quota));
-            limiters.insert(method_name, limiter);
+    pub async fn export_counters(&self) -> RateLimitCounters {
+        let stats = self.rate_limit_stats.read().await;
+        RateLimitCounters {
+            total_requests: stats.total_requests,
+            blocked_requests: stats.blocked_requests,
+            blocked_by_global: stats.blocked_by_global,
+            blocked_by_method: stats.blocked_by_method,
+            blocked_by_ip: stats.blocked_by_ip,
+            blocked_by_api_key: stats.blocked_by_api_key,
+            blocked_by_global_method: stats.blocked_by_global_method,
+            method_requests: stats.method_stats.iter()
+                .map(|(method, stat)| (method.clone(), (stat.requests, stat.blocked)))
+                .collect(),
         }
-        // Could also update IP or API key limits here
+    }
+
+    pub async fn import_counters(&self, counters: RateLimitCounters) {
+        let mut stats = self.rate_limit_stats.write().await;
+        let now = Instant::now();
+
+        *stats = RateLimitStats {
+            total_requests: counters.total_requests,
+            blocked_requests: counters.blocked_requests,
+            blocked_by_global: counters.blocked_by_global,
+            blocked_by_method: counters.blocked_by_method,
+            blocked_by_ip: counters.blocked_by_ip,
+            blocked_by_api_key: counters.blocked_by_api_key,
+            blocked_by_global_method: counters.blocked_by_global_method,
+            method_stats: counters.method_requests.into_iter()
+                .map(|(method, (requests, blocked))| (method, MethodStats {
+                    requests,
+                    blocked,
+                    last_request: now,
+                }))
+                .collect(),
+            ..RateLimitStats::default()
+        };
+        debug!("Rate limiting stats imported");
+    }
+
+    // Limiters already built for a changed method or IP, including tenant-prefixed ones, are
+    // dropped and rebuilt from the new limit on their next request
+    pub async fn update_limits(&self, update: RateLimitUpdate) -> Result<(), AppError> {
+        if self.redis_limiter.is_some() {
+            return Err(AppError::invalid_request("Rate limits can't be changed at runtime with the Redis backend"));
+        }
+
+        let mut limits = self.limits.write().await;
+        if let Some(global) = update.global {
+            limits.default_rate = global.rate.get();
+            limits.default_burst = global.burst.get();
+            *self.global_limiter.write().await = Some(Arc::new(direct_limiter(global)));
+        }
+        for (method, values) in update.methods {
+            drop_limiters(&self.method_limiters, &method).await;
+            limits.per_method_limits.insert(method, values.into());
+        }
+        for (ip, values) in update.ips {
+            let ip = ip_bucket(&ip, self.config.ipv6_prefix_length);
+            drop_limiters(&self.ip_limiters, &ip).await;
+            limits.per_ip_limits.insert(ip, values.into());
+        }
+        Ok(())
     }
 
     pub async fn whitelist_ip(&self, ip: &str) -> Result<(), AppError> {
@@ -483,7 +734,7 @@ impl RateLimitService {
             .map(|(ip, stat)| (ip.clone(), stat.requests))
             .collect();
         
-        ip_requests.sort_by(|a, b| b.1.cmp(&a.1));
+        ip_requests.sort_by_key(|(_, requests)| std::cmp::Reverse(*requests));
         ip_requests.truncate(limit);
         ip_requests
     }
@@ -494,7 +745,7 @@ impl RateLimitService {
             .map(|(method, stat)| (method.clone(), stat.requests))
             .collect();
         
-        method_requests.sort_by(|a, b| b.1.cmp(&a.1));
+        method_requests.sort_by_key(|(_, requests)| std::cmp::Reverse(*requests));
         method_requests.truncate(limit);
         method_requests
     }
@@ -526,9 +777,667 @@ impl RateLimitService {
         self.config.enabled
     }
 
+    // False when the Redis backend is configured but limits fell back to each instance on its own
+    pub fn backend_available(&self) -> bool {
+        self.config.backend != RateLimitBackend::Redis || self.redis_limiter.is_some()
+    }
+
+    pub fn backend_name(&self) -> &'static str {
+        if self.redis_limiter.is_some() { "redis" } else { "memory" }
+    }
+
     pub async fn emergency_disable(&self) {
         // In an emergency, you might want to disable rate limiting
         // This would require making config mutable or using an atomic flag
         warn!("Emergency rate limiting disable requested");
     }
-}
\ No newline at end of file
+}
+
+fn default_api_key_limit() -> RateLimit {
+    RateLimit {
+        rate: 1000,
+        burst: 100,
+        window_seconds: 60,
+    }
+}
+
+// Counts each hit inside the window and deletes the ones that have slid out of it, all in one
+// round trip so concurrent instances can't both squeeze past the limit
+const SLIDING_WINDOW_SCRIPT: &str = r#"
+local key = KEYS[1]
+local now = tonumber(ARGV[1])
+local window = tonumber(ARGV[2])
+local limit = tonumber(ARGV[3])
+redis.call('ZREMRANGEBYSCORE', key, '-inf', now - window)
+local count = redis.call('ZCARD', key)
+if count >= limit then
+    local oldest = redis.call('ZRANGEBYSCORE', key, '-inf', '+inf', 'WITHSCORES', 'LIMIT', 0, 1)
+    return {0, count, tonumber(oldest[2]) + window - now}
+end
+redis.call('ZADD', key, now, ARGV[4])
+redis.call('PEXPIRE', key, window)
+return {1, count + 1, 0}
+"#;
+
+// Outcome of recording one request in a sliding window
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct WindowHit {
+    pub allowed: bool,
+    // Requests in the window, including this one when it was allowed
+    pub count: u32,
+    // Until the oldest request leaves the window, when blocked
+    pub retry_after_ms: u64,
+}
+
+// Shared storage for sliding windows
+#[async_trait]
+pub trait SlidingWindowStore: Send + Sync + std::fmt::Debug {
+    // Records a hit at `now_ms` under `member` unless `limit` hits already fall within the window
+    async fn record_hit(&self, key: &str, now_ms: u64, window_ms: u64, limit: u32, member: &str) -> Result<WindowHit, AppError>;
+}
+
+pub struct RedisWindowStore {
+    connection: ConnectionManager,
+}
+
+impl std::fmt::Debug for RedisWindowStore {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("RedisWindowStore").finish_non_exhaustive()
+    }
+}
+
+impl RedisWindowStore {
+    pub async fn connect(redis_url: &str) -> Result<Self, AppError> {
+        let client = Client::open(redis_url)
+            .map_err(|e| AppError::config(&format!("Invalid rate limit Redis url: {}", e)))?;
+        let connection = ConnectionManager::new(client).await?;
+        Ok(Self { connection })
+    }
+}
+
+#[async_trait]
+impl SlidingWindowStore for RedisWindowStore {
+    async fn record_hit(&self, key: &str, now_ms: u64, window_ms: u64, limit: u32, member: &str) -> Result<WindowHit, AppError> {
+        // EVAL directly, since the redis crate's Script helper sits behind a feature we don't enable
+        let (allowed, count, retry_after_ms): (u8, u32, u64) = redis::cmd("EVAL")
+            .arg(SLIDING_WINDOW_SCRIPT)
+            .arg(1)
+            .arg(key)
+            .arg(now_ms)
+            .arg(window_ms)
+            .arg(limit)
+            .arg(member)
+            .query_async(&mut self.connection.clone())
+            .await?;
+        Ok(WindowHit { allowed: allowed == 1, count, retry_after_ms })
+    }
+}
+
+// The same limits as RateLimitService, counted in sliding windows that every proxy instance
+// shares through a SlidingWindowStore. A sliding window has no separate burst allowance.
+#[derive(Debug)]
+pub struct RedisRateLimiter {
+    config: RateLimitConfig,
+    store: Arc<dyn SlidingWindowStore>,
+}
+
+impl RedisRateLimiter {
+    pub fn new(mut config: RateLimitConfig, store: Arc<dyn SlidingWindowStore>) -> Self {
+        config.per_ip_limits = bucket_ip_limits(&config.per_ip_limits, config.ipv6_prefix_length);
+        Self { config, store }
+    }
+
+    // The scope and result of the first limit this request goes over, checked in the same
+    // order as the in-memory limiters
+    async fn first_exceeded(&self, context: &RateLimitContext) -> Option<(&'static str, RateLimitResult)> {
+        let mut checks = Vec::new();
+        if self.config.default_rate > 0 {
+            checks.push(WindowCheck::per_second("global", "global".to_string(), self.config.default_rate,
+                "Global rate limit exceeded".to_string()));
+        }
+        if let Some(limit) = self.config.per_method_limits.get(&context.method) {
+            checks.push(WindowCheck::new("method", context.limiter_key(&context.method), limit,
+                format!("Method rate limit exceeded for {}", context.method)));
+        }
+        if let Some(ip) = &context.ip_address {
+            let ip = ip_bucket(ip, self.config.ipv6_prefix_length);
+            if let Some(limit) = self.config.per_ip_limits.get(&ip) {
+                checks.push(WindowCheck::new("ip", context.limiter_key(&ip), limit, format!("IP rate limit exceeded for {}", ip)));
+            }
+        }
+        if let Some(&max_rps) = self.config.global_method_limits.get(&context.method).filter(|rps| **rps > 0) {
+            checks.push(WindowCheck::per_second("global_method", context.method.clone(), max_rps,
+                format!("Global rate limit exceeded for {}", context.method)));
+        }
+        if let Some(api_key) = &context.api_key {
+            checks.push(WindowCheck::new("api_key", context.limiter_key(api_key), &default_api_key_limit(),
+                "API key rate limit exceeded".to_string()));
+        }
+
+        let now_ms = SystemTime::now().duration_since(UNIX_EPOCH).unwrap_or_default().as_millis() as u64;
+        let member = format!("{}-{}", now_ms, uuid::Uuid::new_v4());
+        for WindowCheck { scope, key, window_secs, limit, reason } in checks {
+            let key = format!("multi_rpc:rate_limit:{}:{}", scope, key);
+            match self.store.record_hit(&key, now_ms, window_secs * 1000, limit, &member).await {
+                Ok(hit) if !hit.allowed => {
+                    let retry_after = Duration::from_millis(hit.retry_after_ms);
+                    return Some((scope, RateLimitResult {
+                        allowed: false,
+                        reason: Some(reason),
+                        retry_after: Some(retry_after),
+                        remaining_requests: Some(0),
+                        reset_time: Some(Instant::now() + retry_after),
+                    }));
+                }
+                Ok(_) => {}
+                // Fail open: an unreachable Redis shouldn't take every request down with it
+                Err(e) => warn!("Rate limit check against Redis failed for {}: {}", key, e),
+            }
+        }
+        None
+    }
+}
+
+struct WindowCheck {
+    scope: &'static str,
+    key: String,
+    window_secs: u64,
+    limit: u32,
+    reason: String,
+}
+
+impl WindowCheck {
+    // `rate` per second held over the limit's whole window
+    fn new(scope: &'static str, key: String, limit: &RateLimit, reason: String) -> Self {
+        let window_secs = limit.window_seconds.max(1);
+        let max_requests = (limit.rate as u64 * window_secs).min(u32::MAX as u64) as u32;
+        Self { scope, key, window_secs, limit: max_requests.max(1), reason }
+    }
+
+    fn per_second(scope: &'static str, key: String, limit: u32, reason: String) -> Self {
+        Self { scope, key, window_secs: 1, limit, reason }
+    }
+}
+
+// IPv6 addresses truncated to their first `ipv6_prefix` bits, so a client can't get a fresh bucket
+// by hopping between addresses in its own /64. IPv4 (and IPv4-mapped) addresses are left as they are.
+pub fn normalize_ip(ip: IpAddr, ipv6_prefix: u8) -> String {
+    let ipv6 = match ip {
+        IpAddr::V4(ipv4) => return ipv4.to_string(),
+        IpAddr::V6(ipv6) => match ipv6.to_ipv4_mapped() {
+            Some(ipv4) => return ipv4.to_string(),
+            None => ipv6,
+        },
+    };
+    let host_bits = 128 - u32::from(ipv6_prefix.min(128));
+    let mask = u128::MAX.checked_shl(host_bits).unwrap_or(0);
+    Ipv6Addr::from(u128::from(ipv6) & mask).to_string()
+}
+
+// The key an address is limited under; anything that doesn't parse as an IP is used as it is
+fn ip_bucket(ip: &str, ipv6_prefix: u8) -> String {
+    ip.parse().map_or_else(|_| ip.to_string(), |ip| normalize_ip(ip, ipv6_prefix))
+}
+
+// Per-IP limits keyed by the bucket their address falls in, so a limit set for one IPv6
+// address covers the whole prefix it is limited under
+fn bucket_ip_limits(limits: &HashMap<String, RateLimit>, ipv6_prefix: u8) -> HashMap<String, RateLimit> {
+    limits.iter().map(|(ip, limit)| (ip_bucket(ip, ipv6_prefix), limit.clone())).collect()
+}
+
+// Glob match where `*` stands for any run of characters, e.g. "monitor_*" or "*-healthcheck"
+fn glob_matches(pattern: &str, value: &str) -> bool {
+    let mut parts = pattern.split('*');
+    let first = parts.next().unwrap_or_default();
+    let Some(mut rest) = value.strip_prefix(first) else {
+        return false;
+    };
+
+    let mut parts: Vec<&str> = parts.collect();
+    // No wildcard at all means an exact match
+    let Some(last) = parts.pop() else {
+        return rest.is_empty();
+    };
+
+    for part in parts {
+        match rest.find(part) {
+            Some(index) => rest = &rest[index + part.len()..],
+            None => return false,
+        }
+    }
+    rest.len() >= last.len() && rest.ends_with(last)
+}
+
+fn direct_limiter(values: RateLimitValues) -> RateLimiterType {
+    RateLimiter::direct(Quota::per_second(values.rate).allow_burst(values.burst))
+}
+
+async fn drop_limiters(limiters: &RwLock<HashMap<String, Arc<RateLimiterType>>>, key: &str) {
+    let tenant_suffix = format!(":{}", key);
+    limiters.write().await.retain(|existing, _| existing != key && !existing.ends_with(&tenant_suffix));
+}
+
+pub async fn handle_update_rate_limits(
+    State(state): State<Arc<AppState>>,
+    auth: Option<Extension<AuthContext>>,
+    Json(update): Json<RateLimitUpdate>,
+) -> Result<Json<Value>, AppError> {
+    if !state.auth_service.allows_admin(auth.as_ref().map(|Extension(context)| context)) {
+        return Err(AppError::Forbidden);
+    }
+    state.rate_limit_service.update_limits(update).await?;
+    Ok(Json(state.rate_limit_service.get_stats().await))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{
+        config::RateLimitExemption,
+        config::ApiKeyConfig,
+        test_server::{rpc_result, shared_metrics, TestServer, TestServerBuilder},
+    };
+    use reqwest::StatusCode;
+    use wiremock::{matchers::method, Mock};
+
+    fn context(api_key: &str, method: &str) -> RateLimitContext {
+        RateLimitContext {
+            ip_address: Some("10.0.0.1".to_string()),
+            api_key: Some(api_key.to_string()),
+            method: method.to_string(),
+            user_agent: None,
+            tenant_id: None,
+        }
+    }
+
+    // A server with rate limiting on, no per-method limits, and an upstream answering every call
+    async fn limited_server(configure: impl FnOnce(&mut Config)) -> TestServer {
+        let server = TestServerBuilder::new()
+            .with_config(|config| {
+                config.rate_limiting.enabled = true;
+                config.rate_limiting.per_method_limits.clear();
+                configure(config);
+            })
+            .start()
+            .await;
+        Mock::given(method("POST"))
+            .respond_with(rpc_result(json!(42)))
+            .mount(server.endpoint_mock("primary"))
+            .await;
+        server
+    }
+
+    async fn rpc_status(server: &TestServer, rpc_method: &str, address: &str, api_key: Option<&str>) -> StatusCode {
+        let mut request = server.client.post(&server.base_url)
+            .header("x-forwarded-for", address)
+            .json(&json!({"jsonrpc": "2.0", "id": 1, "method": rpc_method}));
+        if let Some(api_key) = api_key {
+            request = request.header("x-api-key", api_key);
+        }
+        request.send().await.unwrap().status()
+    }
+
+    #[test]
+    fn test_glob_matches() {
+        assert!(glob_matches("monitor_*", "monitor_prod"));
+        assert!(glob_matches("monitor_*", "monitor_"));
+        assert!(!glob_matches("monitor_*", "xmonitor_prod"));
+        assert!(glob_matches("*-healthcheck", "eu-healthcheck"));
+        assert!(glob_matches("bot_*_prod", "bot_eu_west_prod"));
+        assert!(!glob_matches("bot_*_prod", "bot_eu_staging"));
+        assert!(glob_matches("*", "anything"));
+        assert!(glob_matches("exact-key", "exact-key"));
+        assert!(!glob_matches("exact-key", "exact-key-2"));
+        // The suffix can't reuse characters already consumed by the prefix
+        assert!(!glob_matches("ab*ba", "aba"));
+    }
+
+    #[test]
+    fn test_normalize_ip_leaves_ipv4_unchanged() {
+        assert_eq!(normalize_ip("10.0.0.1".parse().unwrap(), 64), "10.0.0.1");
+        assert_eq!(normalize_ip("::ffff:10.0.0.1".parse().unwrap(), 64), "10.0.0.1");
+    }
+
+    #[test]
+    fn test_normalize_ip_buckets_ipv6_by_prefix() {
+        let first = normalize_ip("2001:db8:1:2:aaaa::1".parse().unwrap(), 64);
+        let second = normalize_ip("2001:db8:1:2:ffff:ffff:ffff:ffff".parse().unwrap(), 64);
+        assert_eq!(first, "2001:db8:1:2::");
+        assert_eq!(first, second);
+        assert_ne!(first, normalize_ip("2001:db8:1:3::1".parse().unwrap(), 64));
+        assert_eq!(normalize_ip("2001:db8::1".parse().unwrap(), 0), "::");
+    }
+
+    #[test]
+    fn test_normalize_ip_per_address_at_128() {
+        assert_eq!(normalize_ip("2001:db8:1:2:aaaa::1".parse().unwrap(), 128), "2001:db8:1:2:aaaa::1");
+        assert_ne!(
+            normalize_ip("2001:db8:1:2::1".parse().unwrap(), 128),
+            normalize_ip("2001:db8:1:2::2".parse().unwrap(), 128),
+        );
+    }
+
+    #[tokio::test]
+    async fn test_ipv6_clients_in_one_prefix_share_a_limit() {
+        let mut config = Config::default();
+        config.rate_limiting.enabled = true;
+        config.rate_limiting.default_rate = 1000;
+        config.rate_limiting.default_burst = 1000;
+        config.rate_limiting.per_ip_limits = HashMap::from([
+            ("2001:db8:1:2::1".to_string(), RateLimit { rate: 1, burst: 1, window_seconds: 1 }),
+        ]);
+        let service = RateLimitService::new(&config);
+        let from = |ip: &str| RateLimitContext { ip_address: Some(ip.to_string()), ..context("k", "getSlot") };
+
+        assert!(service.check_rate_limit(from("2001:db8:1:2::1")).await.allowed);
+        let blocked = service.check_rate_limit(from("2001:db8:1:2::99")).await;
+        assert_eq!(blocked.reason.as_deref(), Some("IP rate limit exceeded for 2001:db8:1:2::"));
+
+        config.rate_limiting.ipv6_prefix_length = 128;
+        let service = RateLimitService::new(&config);
+        assert!(service.check_rate_limit(from("2001:db8:1:2::1")).await.allowed);
+        assert!(service.check_rate_limit(from("2001:db8:1:2::99")).await.allowed);
+    }
+
+    #[tokio::test]
+    async fn test_exemption_is_method_specific() {
+        let mut config = Config::default();
+        config.rate_limiting.enabled = true;
+        config.rate_limiting.default_rate = 1;
+        config.rate_limiting.default_burst = 1;
+        config.rate_limiting.exemptions = vec![RateLimitExemption {
+            api_key_pattern: "monitor_*".to_string(),
+            methods: vec!["getHealth".to_string()],
+        }];
+        let service = RateLimitService::new(&config).with_metrics_service(shared_metrics());
+
+        // Exempt on the listed method no matter how often it is called
+        for _ in 0..10 {
+            assert!(service.check_rate_limit(context("monitor_eu", "getHealth")).await.allowed);
+        }
+        assert_eq!(service.find_exemption("monitor_eu", "getHealth"), Some(0));
+
+        // Other methods and non-matching keys share the single-request global budget
+        assert!(service.check_rate_limit(context("monitor_eu", "getSlot")).await.allowed);
+        assert!(!service.check_rate_limit(context("monitor_eu", "getSlot")).await.allowed);
+        assert!(!service.check_rate_limit(context("client_1", "getHealth")).await.allowed);
+        assert_eq!(service.find_exemption("client_1", "getHealth"), None);
+    }
+
+    #[tokio::test]
+    async fn test_exempt_key_is_not_limited_over_http() {
+        let server = limited_server(|config| {
+            config.auth.enabled = true;
+            config.auth.api_keys = ["monitor_eu", "client_1"].into_iter()
+                .map(|key| (key.to_string(), ApiKeyConfig {
+                    name: key.to_string(),
+                    rate_limit: 1000,
+                    allowed_methods: None,
+                    allowed_ips: None,
+                    created_at: "2024-01-01T00:00:00Z".to_string(),
+                    expires_at: None,
+                }))
+                .collect();
+            config.rate_limiting.default_rate = 1;
+            config.rate_limiting.default_burst = 1;
+            config.rate_limiting.exemptions = vec![RateLimitExemption {
+                api_key_pattern: "monitor_*".to_string(),
+                methods: vec!["getHealth".to_string()],
+            }];
+        }).await;
+
+        for _ in 0..5 {
+            assert_eq!(rpc_status(&server, "getHealth", "10.0.0.1", Some("monitor_eu")).await, StatusCode::OK);
+        }
+        // The same method under a key without an exemption uses up the global budget
+        assert_eq!(rpc_status(&server, "getHealth", "10.0.0.1", Some("client_1")).await, StatusCode::OK);
+        assert_eq!(rpc_status(&server, "getHealth", "10.0.0.1", Some("client_1")).await, StatusCode::TOO_MANY_REQUESTS);
+        assert_eq!(rpc_status(&server, "getSlot", "10.0.0.1", Some("monitor_eu")).await, StatusCode::TOO_MANY_REQUESTS);
+    }
+
+    #[tokio::test]
+    async fn test_global_method_limit_is_shared_by_all_clients() {
+        let mut config = Config::default();
+        config.rate_limiting.enabled = true;
+        config.rate_limiting.default_rate = 100_000;
+        config.rate_limiting.default_burst = 100_000;
+        config.rate_limiting.global_method_limits.insert("getProgramAccounts".to_string(), 100);
+        let service = Arc::new(RateLimitService::new(&config).with_metrics_service(shared_metrics()));
+        let exceeded = || async {
+            shared_metrics().get_metrics().await["rate_limiting"]["global_method_limit_exceeded"]["getProgramAccounts"]
+                .as_u64()
+                .unwrap_or(0)
+        };
+        let exceeded_before = exceeded().await;
+
+        // 5 clients at 200 RPS each, 1000 RPS combined, for one second
+        let started = Instant::now();
+        let mut clients = Vec::new();
+        for client in 0..5 {
+            let service = service.clone();
+            clients.push(tokio::spawn(async move {
+                let mut allowed = 0;
+                let mut ticker = tokio::time::interval(Duration::from_millis(5));
+                for _ in 0..200 {
+                    ticker.tick().await;
+                    let context = RateLimitContext {
+                        ip_address: Some(format!("10.0.0.{}", client)),
+                        api_key: None,
+                        method: "getProgramAccounts".to_string(),
+                        user_agent: None,
+                        tenant_id: None,
+                    };
+                    if service.check_rate_limit(context).await.allowed {
+                        allowed += 1;
+                    }
+                }
+                allowed
+            }));
+        }
+        let mut allowed = 0;
+        for client in clients {
+            allowed += client.await.unwrap();
+        }
+        let elapsed = started.elapsed().as_secs_f64();
+
+        // One second's worth of burst plus whatever refilled while the clients were sending
+        let cap = 100 + (100.0 * elapsed).ceil() as u64;
+        assert!(allowed >= 100, "only {} requests allowed", allowed);
+        assert!(allowed <= cap, "{} requests allowed in {:.2}s, cap is {}", allowed, elapsed, cap);
+        assert_eq!(exceeded().await - exceeded_before, 1000 - allowed);
+
+        // Other methods aren't affected
+        assert!(service.check_rate_limit(context("client_1", "getSlot")).await.allowed);
+        assert_eq!(service.get_stats().await["global"]["blocked_by"]["global_method"], 1000 - allowed);
+    }
+
+    #[tokio::test]
+    async fn test_global_method_limit_applies_across_clients_over_http() {
+        let server = limited_server(|config| {
+            config.rate_limiting.default_rate = 100_000;
+            config.rate_limiting.default_burst = 100_000;
+            config.rate_limiting.global_method_limits.insert("getProgramAccounts".to_string(), 2);
+        }).await;
+
+        assert_eq!(rpc_status(&server, "getProgramAccounts", "10.0.0.1", None).await, StatusCode::OK);
+        assert_eq!(rpc_status(&server, "getProgramAccounts", "10.0.0.2", None).await, StatusCode::OK);
+        // A third client finds the method's budget already spent by the other two
+        assert_eq!(rpc_status(&server, "getProgramAccounts", "10.0.0.3", None).await, StatusCode::TOO_MANY_REQUESTS);
+        assert_eq!(rpc_status(&server, "getSlot", "10.0.0.3", None).await, StatusCode::OK);
+    }
+
+    #[tokio::test]
+    async fn test_ipv6_clients_share_their_prefix_bucket_over_http() {
+        let server = limited_server(|config| {
+            config.rate_limiting.default_rate = 100_000;
+            config.rate_limiting.default_burst = 100_000;
+            config.rate_limiting.per_ip_limits.insert("2001:db8:1:2::1".to_string(), RateLimit {
+                rate: 1,
+                burst: 1,
+                window_seconds: 1,
+            });
+        }).await;
+
+        assert_eq!(rpc_status(&server, "getSlot", "2001:db8:1:2::1", None).await, StatusCode::OK);
+        // Another address in the same /64 is counted against the same limit
+        assert_eq!(rpc_status(&server, "getSlot", "2001:db8:1:2:ffff::99", None).await, StatusCode::TOO_MANY_REQUESTS);
+        assert_eq!(rpc_status(&server, "getSlot", "2001:db8:1:3::1", None).await, StatusCode::OK);
+    }
+
+    // Stands in for Redis, applying the sliding window script's steps to an in-memory sorted set
+    #[derive(Debug, Default)]
+    struct MockRedis {
+        sorted_sets: std::sync::Mutex<HashMap<String, Vec<(u64, String)>>>,
+    }
+
+    #[async_trait]
+    impl SlidingWindowStore for MockRedis {
+        async fn record_hit(&self, key: &str, now_ms: u64, window_ms: u64, limit: u32, member: &str) -> Result<WindowHit, AppError> {
+            let mut sorted_sets = self.sorted_sets.lock().unwrap();
+            let set = sorted_sets.entry(key.to_string()).or_default();
+            // ZREMRANGEBYSCORE -inf (now - window)
+            set.retain(|(score, _)| *score > now_ms.saturating_sub(window_ms));
+            let count = set.len() as u32;
+            if count >= limit {
+                let oldest = set.iter().map(|(score, _)| *score).min().unwrap_or(now_ms);
+                return Ok(WindowHit { allowed: false, count, retry_after_ms: oldest + window_ms - now_ms });
+            }
+            set.push((now_ms, member.to_string()));
+            Ok(WindowHit { allowed: true, count: count + 1, retry_after_ms: 0 })
+        }
+    }
+
+    #[tokio::test]
+    async fn test_redis_window_is_shared_between_instances() {
+        let mut config = Config::default();
+        config.rate_limiting.enabled = true;
+        config.rate_limiting.backend = crate::config::RateLimitBackend::Redis;
+        config.rate_limiting.default_rate = 100_000;
+        config.rate_limiting.per_method_limits.insert("getBlock".to_string(), RateLimit {
+            rate: 5,
+            burst: 5,
+            window_seconds: 1,
+        });
+        let redis = Arc::new(MockRedis::default());
+        let instances = [
+            RateLimitService::new(&config).with_redis_limiter(redis.clone()),
+            RateLimitService::new(&config).with_redis_limiter(redis.clone()),
+        ];
+        let request = || RateLimitContext { api_key: None, ..context("unused", "getBlock") };
+
+        // Alternating between the instances, the fifth request uses up the shared window
+        for i in 0..5 {
+            assert!(instances[i % 2].check_rate_limit(request()).await.allowed, "request {} blocked", i);
+        }
+        for instance in &instances {
+            let blocked = instance.check_rate_limit(request()).await;
+            assert!(!blocked.allowed);
+            assert_eq!(blocked.reason.as_deref(), Some("Method rate limit exceeded for getBlock"));
+            assert!(blocked.retry_after.unwrap() <= Duration::from_secs(1));
+        }
+        assert_eq!(instances[0].get_stats().await["global"]["blocked_by"]["method"], 1);
+
+        // Other methods have their own windows
+        assert!(instances[1].check_rate_limit(RateLimitContext { api_key: None, ..context("unused", "getSlot") }).await.allowed);
+
+        // Once the first requests slide out of the window, both instances admit traffic again
+        tokio::time::sleep(Duration::from_millis(1100)).await;
+        assert!(instances[1].check_rate_limit(request()).await.allowed);
+        assert!(instances[0].check_rate_limit(request()).await.allowed);
+    }
+
+    #[tokio::test]
+    async fn test_redis_backend_applies_global_limit() {
+        let mut config = Config::default();
+        config.rate_limiting.enabled = true;
+        config.rate_limiting.default_rate = 3;
+        let service = RateLimitService::new(&config).with_redis_limiter(Arc::new(MockRedis::default()));
+        for _ in 0..3 {
+            assert!(service.check_rate_limit(context("client_1", "getSlot")).await.allowed);
+        }
+        let blocked = service.check_rate_limit(context("client_2", "getSlot")).await;
+        assert_eq!(blocked.reason.as_deref(), Some("Global rate limit exceeded"));
+        assert_eq!(service.get_stats().await["global"]["blocked_by"]["global"], 1);
+    }
+
+    #[tokio::test]
+    async fn test_patched_method_limit_is_enforced() {
+        let server = TestServerBuilder::new()
+            .with_config(|config| {
+                config.rate_limiting.enabled = true;
+                config.rate_limiting.default_rate = 1000;
+                config.rate_limiting.default_burst = 1000;
+                config.rate_limiting.per_method_limits.clear();
+            })
+            .start()
+            .await;
+        Mock::given(method("POST"))
+            .respond_with(rpc_result(json!([])))
+            .mount(server.endpoint_mock("primary"))
+            .await;
+        let call = |rpc_method: &str| {
+            let request = server.rpc(json!({"jsonrpc": "2.0", "id": 1, "method": rpc_method}));
+            async move { request.await.status() }
+        };
+        for _ in 0..5 {
+            assert_eq!(call("getProgramAccounts").await, StatusCode::OK);
+        }
+
+        let response = server.client.patch(server.url("/admin/rate-limits"))
+            .json(&json!({"methods": {"getProgramAccounts": {"rate": 1, "burst": 2}}}))
+            .send()
+            .await
+            .unwrap();
+        assert_eq!(response.status(), StatusCode::OK);
+        let stats: Value = response.json().await.unwrap();
+        assert_eq!(stats["config"]["method_limits"]["getProgramAccounts"]["burst"], 2);
+
+        assert_eq!(call("getProgramAccounts").await, StatusCode::OK);
+        assert_eq!(call("getProgramAccounts").await, StatusCode::OK);
+        assert_eq!(call("getProgramAccounts").await, StatusCode::TOO_MANY_REQUESTS);
+        // Other methods are untouched
+        assert_eq!(call("getSlot").await, StatusCode::OK);
+    }
+
+    #[tokio::test]
+    async fn test_rate_limit_patch_rejects_zero_values() {
+        let server = TestServerBuilder::new().start().await;
+
+        for body in [
+            json!({"global": {"rate": 0, "burst": 10}}),
+            json!({"ips": {"10.0.0.1": {"rate": 5, "burst": 0}}}),
+            json!({"methods": {"getSlot": {"rate": 5}}}),
+        ] {
+            let response = server.client.patch(server.url("/admin/rate-limits")).json(&body).send().await.unwrap();
+            assert_eq!(response.status(), StatusCode::UNPROCESSABLE_ENTITY, "{}", body);
+        }
+    }
+
+    #[tokio::test]
+    async fn test_update_replaces_global_and_ip_limits() {
+        let mut config = Config::default();
+        config.rate_limiting.enabled = true;
+        config.rate_limiting.default_rate = 1000;
+        config.rate_limiting.default_burst = 1000;
+        config.rate_limiting.per_ip_limits.clear();
+        let service = RateLimitService::new(&config);
+        let limit = |rate, burst| RateLimitValues {
+            rate: NonZeroU32::new(rate).unwrap(),
+            burst: NonZeroU32::new(burst).unwrap(),
+        };
+
+        service.update_limits(RateLimitUpdate {
+            ips: HashMap::from([("10.0.0.1".to_string(), limit(1, 1))]),
+            ..RateLimitUpdate::default()
+        }).await.unwrap();
+        assert!(service.check_rate_limit(context("k", "getSlot")).await.allowed);
+        let blocked = service.check_rate_limit(context("k", "getSlot")).await;
+        assert_eq!(blocked.reason.as_deref(), Some("IP rate limit exceeded for 10.0.0.1"));
+
+        service.update_limits(RateLimitUpdate { global: Some(limit(1, 1)), ..RateLimitUpdate::default() }).await.unwrap();
+        let other_ip = || RateLimitContext { ip_address: Some("10.0.0.2".to_string()), ..context("k", "getSlot") };
+        assert!(service.check_rate_limit(other_ip()).await.allowed);
+        assert_eq!(service.check_rate_limit(other_ip()).await.reason.as_deref(), Some("Global rate limit exceeded"));
+        assert_eq!(service.get_stats().await["config"]["default_rate"], 1);
+    }
+}
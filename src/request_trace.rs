@@ -0,0 +1,419 @@
+use crate::{error::AppError, propagation::current_request_id, AppState};
+use axum::{
+    extract::{Path, Query, Request, State},
+    http::{header, HeaderValue},
+    middleware::Next,
+    response::{IntoResponse, Json, Response},
+};
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+use std::{
+    collections::{BTreeMap, HashMap, VecDeque},
+    future::Future,
+    sync::{Arc, Mutex},
+    time::Instant,
+};
+use uuid::Uuid;
+
+pub const X_TRACE_ID: &str = "x-trace-id";
+// Oldest traces are dropped once this many have been kept
+const MAX_STORED_TRACES: usize = 1000;
+const FLAMEGRAPH_WIDTH: usize = 60;
+
+tokio::task_local! {
+    static ACTIVE_SPAN: SpanHandle;
+}
+
+#[derive(Debug)]
+struct SpanRecord {
+    name: String,
+    parent: Option<usize>,
+    start: Instant,
+    end: Option<Instant>,
+    attributes: BTreeMap<String, Value>,
+}
+
+// Collects the spans of a single request while it is being handled
+#[derive(Debug)]
+pub struct TraceRecorder {
+    trace_id: String,
+    started_at: DateTime<Utc>,
+    spans: Mutex<Vec<SpanRecord>>,
+}
+
+impl TraceRecorder {
+    pub fn new(trace_id: String) -> Self {
+        Self {
+            trace_id,
+            started_at: Utc::now(),
+            spans: Mutex::new(Vec::new()),
+        }
+    }
+
+    fn start(&self, name: &str, parent: Option<usize>) -> usize {
+        let mut attributes = BTreeMap::new();
+        if let Some(request_id) = current_request_id() {
+            attributes.insert("request_id".to_string(), Value::from(request_id));
+        }
+        let mut spans = self.spans.lock().unwrap();
+        spans.push(SpanRecord {
+            name: name.to_string(),
+            parent,
+            start: Instant::now(),
+            end: None,
+            attributes,
+        });
+        spans.len() - 1
+    }
+
+    fn end(&self, span: usize) {
+        if let Some(record) = self.spans.lock().unwrap().get_mut(span) {
+            record.end.get_or_insert_with(Instant::now);
+        }
+    }
+
+    fn set_attribute(&self, span: usize, key: &str, value: Value) {
+        if let Some(record) = self.spans.lock().unwrap().get_mut(span) {
+            record.attributes.insert(key.to_string(), value);
+        }
+    }
+
+    // Builds the span tree; spans still open are cut off where their parent ended
+    pub fn finish(&self) -> Option<RequestTrace> {
+        let spans = self.spans.lock().unwrap();
+        let root = spans.iter().position(|span| span.parent.is_none())?;
+        let origin = spans[root].start;
+        let root_end = spans[root].end.unwrap_or_else(Instant::now);
+
+        let root = build_span(&spans, root, origin, root_end);
+        Some(RequestTrace {
+            trace_id: self.trace_id.clone(),
+            started_at: self.started_at,
+            duration_ms: root.duration_ms,
+            critical_path: critical_path(&root),
+            root,
+        })
+    }
+}
+
+fn millis(from: Instant, to: Instant) -> f64 {
+    (to.saturating_duration_since(from).as_secs_f64() * 1_000_000.0).round() / 1000.0
+}
+
+fn build_span(spans: &[SpanRecord], index: usize, origin: Instant, parent_end: Instant) -> TraceSpan {
+    let record = &spans[index];
+    let end = record.end.unwrap_or(parent_end).min(parent_end).max(record.start);
+
+    let mut children: Vec<TraceSpan> = spans.iter()
+        .enumerate()
+        .filter(|(_, span)| span.parent == Some(index))
+        .map(|(child, _)| build_span(spans, child, origin, end))
+        .collect();
+    children.sort_by(|a, b| a.start_offset_ms.total_cmp(&b.start_offset_ms));
+
+    TraceSpan {
+        name: record.name.clone(),
+        start_offset_ms: millis(origin, record.start),
+        duration_ms: millis(record.start, end),
+        attributes: record.attributes.clone(),
+        children,
+    }
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct TraceSpan {
+    pub name: String,
+    // Relative to the start of the request
+    pub start_offset_ms: f64,
+    pub duration_ms: f64,
+    pub attributes: BTreeMap<String, Value>,
+    pub children: Vec<TraceSpan>,
+}
+
+impl TraceSpan {
+    pub fn end_offset_ms(&self) -> f64 {
+        self.start_offset_ms + self.duration_ms
+    }
+
+    // Name plus the endpoint, if any, so parallel upstream calls can be told apart
+    fn label(&self) -> String {
+        match self.attributes.get("endpoint").and_then(Value::as_str) {
+            Some(endpoint) => format!("{}({})", self.name, endpoint),
+            None => self.name.clone(),
+        }
+    }
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct RequestTrace {
+    pub trace_id: String,
+    pub started_at: DateTime<Utc>,
+    pub duration_ms: f64,
+    pub root: TraceSpan,
+    // Chain of spans, from the root down, that determined when the request finished
+    pub critical_path: Vec<String>,
+}
+
+// Follows the child that finished last at every level
+fn critical_path(root: &TraceSpan) -> Vec<String> {
+    let mut path = vec![root.label()];
+    let mut current = root;
+    while let Some(last) = current.children.iter().max_by(|a, b| a.end_offset_ms().total_cmp(&b.end_offset_ms())) {
+        path.push(last.label());
+        current = last;
+    }
+    path
+}
+
+// One line per span with its position on a shared timeline, e.g.
+// request                         |############################################################|  52.1ms
+//   cache_check                   |##                                                          |   2.0ms
+pub fn render_flamegraph(trace: &RequestTrace) -> String {
+    let mut output = String::new();
+    render_span(&trace.root, 0, trace.duration_ms, &mut output);
+    output
+}
+
+fn render_span(span: &TraceSpan, depth: usize, total_ms: f64, output: &mut String) {
+    let scale = |ms: f64| if total_ms > 0.0 { (ms / total_ms * FLAMEGRAPH_WIDTH as f64).round() as usize } else { 0 };
+    let offset = scale(span.start_offset_ms).min(FLAMEGRAPH_WIDTH - 1);
+    let width = scale(span.duration_ms).clamp(1, FLAMEGRAPH_WIDTH - offset);
+    let bar = format!("{}{}{}", " ".repeat(offset), "#".repeat(width), " ".repeat(FLAMEGRAPH_WIDTH - offset - width));
+    let label = format!("{}{}", "  ".repeat(depth), span.label());
+
+    output.push_str(&format!("{:<32}|{}| {:>7.1}ms\n", label, bar, span.duration_ms));
+    for child in &span.children {
+        render_span(child, depth + 1, total_ms, output);
+    }
+}
+
+// The span code is currently running in, passed to tasks that are spawned for the request
+#[derive(Debug, Clone)]
+pub struct SpanHandle {
+    recorder: Arc<TraceRecorder>,
+    span: usize,
+}
+
+pub fn current_span() -> Option<SpanHandle> {
+    ACTIVE_SPAN.try_with(SpanHandle::clone).ok()
+}
+
+// Runs `future` as a child of the current span; untraced outside of a request
+pub async fn in_span<F: Future>(name: &str, future: F) -> F::Output {
+    in_span_of(current_span(), name, future).await
+}
+
+// Like in_span, for futures that run on another task than the one `parent` came from
+pub async fn in_span_of<F: Future>(parent: Option<SpanHandle>, name: &str, future: F) -> F::Output {
+    let Some(parent) = parent else {
+        return future.await;
+    };
+    let span = parent.recorder.start(name, Some(parent.span));
+    let handle = SpanHandle { recorder: parent.recorder.clone(), span };
+    let output = ACTIVE_SPAN.scope(handle, future).await;
+    parent.recorder.end(span);
+    output
+}
+
+pub fn set_attribute(key: &str, value: impl Into<Value>) {
+    let _ = ACTIVE_SPAN.try_with(|active| active.recorder.set_attribute(active.span, key, value.into()));
+}
+
+// Traces by id, along with their ids oldest first for eviction
+type TraceIndex = (HashMap<String, Arc<RequestTrace>>, VecDeque<String>);
+
+// Most recent request traces, looked up by trace id
+#[derive(Debug, Default)]
+pub struct TraceStore {
+    traces: Mutex<TraceIndex>,
+}
+
+impl TraceStore {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn insert(&self, trace: RequestTrace) {
+        let mut traces = self.traces.lock().unwrap();
+        let (by_id, order) = &mut *traces;
+        order.push_back(trace.trace_id.clone());
+        by_id.insert(trace.trace_id.clone(), Arc::new(trace));
+        while order.len() > MAX_STORED_TRACES {
+            if let Some(oldest) = order.pop_front() {
+                by_id.remove(&oldest);
+            }
+        }
+    }
+
+    pub fn get(&self, trace_id: &str) -> Option<Arc<RequestTrace>> {
+        self.traces.lock().unwrap().0.get(trace_id).cloned()
+    }
+}
+
+// Records a span tree for every request and returns its id in X-Trace-Id
+pub async fn request_tracing_middleware(
+    State(state): State<Arc<AppState>>,
+    request: Request,
+    next: Next,
+) -> Response {
+    let trace_id = Uuid::new_v4().to_string();
+    let recorder = Arc::new(TraceRecorder::new(trace_id.clone()));
+    let root = recorder.start("request", None);
+    recorder.set_attribute(root, "http.method", Value::from(request.method().as_str()));
+    recorder.set_attribute(root, "http.path", Value::from(request.uri().path()));
+
+    let handle = SpanHandle { recorder: recorder.clone(), span: root };
+    let mut response = ACTIVE_SPAN.scope(handle, next.run(request)).await;
+
+    recorder.set_attribute(root, "http.status", Value::from(response.status().as_u16()));
+    recorder.end(root);
+    if let Some(trace) = recorder.finish() {
+        state.trace_store.insert(trace);
+    }
+    if let Ok(value) = HeaderValue::from_str(&trace_id) {
+        response.headers_mut().insert(X_TRACE_ID, value);
+    }
+    response
+}
+
+pub async fn handle_get_trace(
+    State(state): State<Arc<AppState>>,
+    Path(trace_id): Path<String>,
+    Query(params): Query<HashMap<String, String>>,
+) -> Result<Response, AppError> {
+    let trace = state.trace_store.get(&trace_id)
+        .ok_or_else(|| AppError::validation(&format!("No trace recorded for {}", trace_id)))?;
+
+    if params.get("format").map(String::as_str) == Some("flamegraph") {
+        return Ok(([(header::CONTENT_TYPE, "text/plain; charset=utf-8")], render_flamegraph(&trace)).into_response());
+    }
+    let mut body = serde_json::to_value(trace.as_ref())?;
+    body["flamegraph"] = Value::from(render_flamegraph(&trace));
+    Ok(Json(body).into_response())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_server::TestServerBuilder;
+    use serde_json::json;
+    use std::time::Duration;
+    use wiremock::{matchers::method, Mock, ResponseTemplate};
+
+    const EPSILON_MS: f64 = 0.001;
+
+    // Every child sits inside its parent, and siblings are in start order
+    fn assert_well_formed(span: &TraceSpan) {
+        assert!(span.duration_ms >= 0.0);
+        for child in &span.children {
+            assert!(child.start_offset_ms + EPSILON_MS >= span.start_offset_ms, "{} starts before {}", child.name, span.name);
+            assert!(child.end_offset_ms() <= span.end_offset_ms() + EPSILON_MS, "{} ends after {}", child.name, span.name);
+            assert_well_formed(child);
+        }
+        assert!(span.children.windows(2).all(|pair| pair[0].start_offset_ms <= pair[1].start_offset_ms));
+    }
+
+    fn count_spans(span: &TraceSpan) -> usize {
+        1 + span.children.iter().map(count_spans).sum::<usize>()
+    }
+
+    #[tokio::test]
+    async fn test_parallel_spans_and_critical_path() {
+        let recorder = Arc::new(TraceRecorder::new("test".to_string()));
+        let root = recorder.start("request", None);
+        let handle = SpanHandle { recorder: recorder.clone(), span: root };
+
+        ACTIVE_SPAN.scope(handle, async {
+            in_span("cache_check", tokio::time::sleep(Duration::from_millis(5))).await;
+            in_span("consensus", async {
+                let parent = current_span();
+                let calls: Vec<_> = [("a", 20), ("b", 60)].into_iter()
+                    .map(|(endpoint, ms)| tokio::spawn(in_span_of(parent.clone(), "upstream_request", async move {
+                        set_attribute("endpoint", endpoint);
+                        tokio::time::sleep(Duration::from_millis(ms)).await;
+                    })))
+                    .collect();
+                for call in calls {
+                    call.await.unwrap();
+                }
+            }).await;
+            in_span("response_cached", async {}).await;
+        }).await;
+        recorder.end(root);
+
+        let trace = recorder.finish().unwrap();
+        assert_well_formed(&trace.root);
+        let names: Vec<&str> = trace.root.children.iter().map(|span| span.name.as_str()).collect();
+        assert_eq!(names, vec!["cache_check", "consensus", "response_cached"]);
+
+        let consensus = &trace.root.children[1];
+        assert_eq!(consensus.children.len(), 2);
+        // The calls overlap, so the consensus span lasts about as long as the slower one
+        assert!(consensus.duration_ms >= 60.0);
+        assert!(consensus.duration_ms < consensus.children.iter().map(|c| c.duration_ms).sum::<f64>());
+        assert!(trace.root.children[0].end_offset_ms() <= consensus.start_offset_ms + EPSILON_MS);
+
+        // response_cached ends last among the root's children, but the slow endpoint held up consensus
+        assert_eq!(trace.critical_path, vec!["request", "response_cached"]);
+        let consensus_path = critical_path(consensus);
+        assert_eq!(consensus_path, vec!["consensus", "upstream_request(b)"]);
+
+        let flamegraph = render_flamegraph(&trace);
+        assert_eq!(flamegraph.lines().count(), count_spans(&trace.root));
+        assert!(flamegraph.lines().all(|line| line.matches('|').count() == 2));
+        assert!(flamegraph.starts_with("request "));
+    }
+
+    #[tokio::test]
+    async fn test_untraced_code_is_unaffected() {
+        assert!(current_span().is_none());
+        assert_eq!(in_span("cache_check", async { 7 }).await, 7);
+        set_attribute("ignored", true);
+    }
+
+    #[tokio::test]
+    async fn test_trace_endpoint_returns_request_span_tree() {
+        let server = TestServerBuilder::new().start().await;
+        Mock::given(method("POST"))
+            .respond_with(ResponseTemplate::new(200).set_body_json(json!({"jsonrpc": "2.0", "id": 1, "result": 42})))
+            .mount(server.endpoint_mock("primary"))
+            .await;
+
+        let response = server.rpc(json!({"jsonrpc": "2.0", "id": 1, "method": "getSlot"})).await;
+        let trace_id = response.headers()[X_TRACE_ID].to_str().unwrap().to_string();
+        assert_eq!(response.json::<Value>().await.unwrap()["result"], 42);
+
+        // Every response carries a trace id, not just RPC calls
+        let health = server.client.get(server.url("/health")).send().await.unwrap();
+        assert!(health.headers().contains_key(X_TRACE_ID));
+
+        let trace: Value = server.client.get(server.url(&format!("/debug/trace/{}", trace_id)))
+            .send().await.unwrap()
+            .json().await.unwrap();
+        assert_eq!(trace["trace_id"], trace_id);
+        let root: TraceSpan = serde_json::from_value(trace["root"].clone()).unwrap();
+        assert_well_formed(&root);
+        assert_eq!(root.name, "request");
+        assert_eq!(root.attributes["rpc.method"], "getSlot");
+        assert_eq!(root.attributes["http.status"], 200);
+        assert_eq!(trace["duration_ms"], json!(root.duration_ms));
+
+        let phases: Vec<&str> = root.children.iter().map(|span| span.name.as_str()).collect();
+        assert_eq!(phases, vec!["cache_check", "endpoint_selection", "upstream_request", "response_cached"]);
+        let upstream = &root.children[2];
+        assert_eq!(upstream.attributes["endpoint"], server.endpoint_mock("primary").uri().as_str());
+        assert_eq!(upstream.attributes["attempt"], 1);
+        assert!(root.children.iter().map(|span| span.duration_ms).sum::<f64>() <= root.duration_ms + EPSILON_MS);
+        assert_eq!(trace["critical_path"][0], "request");
+
+        let flamegraph = server.client.get(server.url(&format!("/debug/trace/{}?format=flamegraph", trace_id)))
+            .send().await.unwrap()
+            .text().await.unwrap();
+        assert_eq!(flamegraph, trace["flamegraph"].as_str().unwrap());
+        assert_eq!(flamegraph.lines().count(), count_spans(&root));
+
+        let missing = server.client.get(server.url("/debug/trace/unknown")).send().await.unwrap();
+        assert!(missing.status().is_client_error());
+    }
+}
@@ -3,6 +3,8 @@ use std::time::{Duration, Instant};
 use tokio::time::sleep;
 use tracing::{debug, warn, error, instrument};
 use rand::{thread_rng, Rng};
+use serde::{Deserialize, Serialize};
+use schemars::JsonSchema;
 use crate::error::{AppError, AppResult};
 
 #[derive(Debug, Clone)]
@@ -12,6 +14,7 @@ pub struct RetryConfig {
     pub max_delay: Duration,
     pub exponential_base: f64,
     pub jitter_factor: f64,
+    #[allow(dead_code)]
     pub timeout: Duration,
     pub circuit_breaker_threshold: u32,
     pub circuit_breaker_duration: Duration,
@@ -64,9 +67,41 @@ impl Clone for RetryStrategy {
     }
 }
 
+// Config-file form of RetryStrategy, e.g. `retry_strategy = { type = "custom", delays_ms = [100, 500] }`
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize, JsonSchema)]
+#[serde(tag = "type", rename_all = "snake_case")]
+pub enum RetryStrategyConfig {
+    #[default]
+    Exponential,
+    Linear,
+    Fixed,
+    Fibonacci,
+    /// Delay before retry N is delays_ms[N - 1], repeating the last entry once the list runs out
+    Custom { delays_ms: Vec<u64> },
+}
+
+impl From<&RetryStrategyConfig> for RetryStrategy {
+    fn from(config: &RetryStrategyConfig) -> Self {
+        match config {
+            RetryStrategyConfig::Exponential => Self::Exponential,
+            RetryStrategyConfig::Linear => Self::Linear,
+            RetryStrategyConfig::Fixed => Self::Fixed,
+            RetryStrategyConfig::Fibonacci => Self::Fibonacci,
+            RetryStrategyConfig::Custom { delays_ms } => {
+                let delays: Vec<Duration> = delays_ms.iter().copied().map(Duration::from_millis).collect();
+                Self::Custom(Box::new(move |attempt| {
+                    let index = (attempt.max(1) as usize - 1).min(delays.len().saturating_sub(1));
+                    delays.get(index).copied().unwrap_or_default()
+                }))
+            }
+        }
+    }
+}
+
 pub struct RetryPolicy {
     config: RetryConfig,
     strategy: RetryStrategy,
+    #[allow(dead_code)]
     current_attempt: u32,
     start_time: Instant,
     last_error: Option<String>,
@@ -74,6 +109,7 @@ pub struct RetryPolicy {
     circuit_breaker_opened_at: Option<Instant>,
 }
 
+#[allow(dead_code)]
 impl RetryPolicy {
     pub fn new(config: RetryConfig, strategy: RetryStrategy) -> Self {
         Self {
@@ -99,12 +135,23 @@ impl RetryPolicy {
         Self::new(RetryConfig::default(), RetryStrategy::Fixed)
     }
 
+    // Backoff for the router's own retry loop: 100ms base and no jitter, so the
+    // default exponential strategy waits 100ms, 200ms, 400ms, ...
+    pub fn from_strategy_config(strategy: &RetryStrategyConfig) -> Self {
+        let config = RetryConfig {
+            jitter_factor: 0.0,
+            ..RetryConfig::default()
+        };
+        Self::new(config, strategy.into())
+    }
+
     pub fn with_config(mut self, config: RetryConfig) -> Self {
         self.config = config;
         self
     }
 
-    fn calculate_delay(&self, attempt: u32) -> Duration {
+    // `attempt` is the 1-based number of the attempt that just failed
+    pub fn calculate_delay(&self, attempt: u32) -> Duration {
         let base_delay = match &self.strategy {
             RetryStrategy::Exponential => {
                 let multiplier = self.config.exponential_base.powi(attempt as i32 - 1);
@@ -125,25 +172,18 @@ impl RetryPolicy {
             RetryStrategy::Custom(f) => f(attempt),
         };
 
-        // Apply jitter
+        // Apply jitter, which can be negative but never takes the delay below zero
         let jitter = if self.config.jitter_factor > 0.0 {
             let mut rng = thread_rng();
             let jitter_range = base_delay.as_secs_f64() * self.config.jitter_factor;
-            let jitter = rng.gen_range(-jitter_range..=jitter_range);
-            Duration::from_secs_f64(jitter)
+            rng.gen_range(-jitter_range..=jitter_range)
         } else {
-            Duration::from_secs(0)
+            0.0
         };
 
         // Apply max delay cap
-        let final_delay = base_delay + jitter;
-        if final_delay > self.config.max_delay {
-            self.config.max_delay
-        } else if final_delay < Duration::from_millis(0) {
-            Duration::from_millis(0)
-        } else {
-            final_delay
-        }
+        let final_delay = Duration::from_secs_f64((base_delay.as_secs_f64() + jitter).max(0.0));
+        final_delay.min(self.config.max_delay)
     }
 
     fn should_retry(&self, error: &AppError) -> bool {
@@ -238,7 +278,11 @@ impl RetryPolicy {
     }
 }
 
+// One hedged attempt, failing with Elapsed if it outran its timeout
+type TimedOperation<T> = std::pin::Pin<Box<dyn Future<Output = Result<AppResult<T>, tokio::time::error::Elapsed>> + Send>>;
+
 // Hedged requests - send multiple requests and use the first successful response
+#[allow(dead_code)]
 #[derive(Debug)]
 pub struct HedgedRequest {
     pub primary_delay: Duration,
@@ -246,6 +290,7 @@ pub struct HedgedRequest {
     pub max_hedges: usize,
 }
 
+#[allow(dead_code)]
 impl HedgedRequest {
     pub fn new(primary_delay: Duration, hedge_delay: Duration, max_hedges: usize) -> Self {
         Self {
@@ -262,14 +307,13 @@ impl HedgedRequest {
         Fut: Future<Output = AppResult<T>> + Send + 'static,
         T: Send + 'static,
     {
-        use tokio::select;
         use tokio::time::timeout;
 
         if operations.is_empty() {
             return Err(AppError::internal("No operations provided for hedged request"));
         }
 
-        let mut futures: Vec<std::pin::Pin<Box<dyn Future<Output = Result<AppResult<T>, tokio::time::error::Elapsed>> + Send>>> = Vec::new();
+        let mut futures: Vec<TimedOperation<T>> = Vec::new();
         let mut hedge_count = 0;
 
         // Start primary request
@@ -285,7 +329,7 @@ impl HedgedRequest {
                     debug!(hedge_count, "Hedged request succeeded");
                     return Ok(value);
                 }
-                Ok(Err(timeout_err)) => {
+                Ok(Err(_timeout_err)) => {
                     // Timeout occurred, start a hedge if available
                     if hedge_count < self.max_hedges && hedge_count < operations.len() - 1 {
                         hedge_count += 1;
@@ -318,6 +362,7 @@ impl HedgedRequest {
 }
 
 // Helper function to select the first completed future
+#[allow(dead_code)]
 async fn select_any<T>(
     futures: Vec<std::pin::Pin<Box<dyn Future<Output = T> + Send>>>,
 ) -> (T, usize, Vec<std::pin::Pin<Box<dyn Future<Output = T> + Send>>>) {
@@ -346,11 +391,13 @@ fn fibonacci(n: u32) -> u32 {
 }
 
 // Retry with fallback
+#[allow(dead_code)]
 pub struct RetryWithFallback {
     primary_policy: RetryPolicy,
     fallback_policy: RetryPolicy,
 }
 
+#[allow(dead_code)]
 impl RetryWithFallback {
     pub fn new(primary_policy: RetryPolicy, fallback_policy: RetryPolicy) -> Self {
         Self {
@@ -389,17 +436,33 @@ impl RetryWithFallback {
 }
 
 // Adaptive retry that adjusts strategy based on error patterns
+#[derive(Debug, Clone)]
 pub struct AdaptiveRetry {
     base_config: RetryConfig,
+    // The base config as adjusted so far; each adjustment builds on the previous one
+    current_config: RetryConfig,
     success_count: u32,
     failure_count: u32,
     last_adjustment: Instant,
     adjustment_interval: Duration,
 }
 
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct AdaptiveRetryStats {
+    pub success_count: u32,
+    pub failure_count: u32,
+    pub max_attempts: u32,
+    pub initial_delay_ms: u64,
+}
+
+// Halving stops here so a long healthy stretch can't take the backoff to nothing
+const MIN_ADAPTIVE_INITIAL_DELAY: Duration = Duration::from_millis(10);
+
+#[allow(dead_code)]
 impl AdaptiveRetry {
     pub fn new(base_config: RetryConfig) -> Self {
         Self {
+            current_config: base_config.clone(),
             base_config,
             success_count: 0,
             failure_count: 0,
@@ -408,37 +471,62 @@ impl AdaptiveRetry {
         }
     }
 
-    fn adjust_config(&mut self) -> RetryConfig {
+    pub fn with_adjustment_interval(mut self, adjustment_interval: Duration) -> Self {
+        self.adjustment_interval = adjustment_interval;
+        self
+    }
+
+    pub fn record(&mut self, success: bool) {
+        if success {
+            self.success_count += 1;
+        } else {
+            self.failure_count += 1;
+        }
+    }
+
+    pub fn reset(&mut self) {
+        self.success_count = 0;
+        self.failure_count = 0;
+        self.last_adjustment = Instant::now();
+    }
+
+    pub fn get_stats(&self) -> AdaptiveRetryStats {
+        AdaptiveRetryStats {
+            success_count: self.success_count,
+            failure_count: self.failure_count,
+            max_attempts: self.current_config.max_attempts,
+            initial_delay_ms: self.current_config.initial_delay.as_millis() as u64,
+        }
+    }
+
+    // Moves the config one step based on the failure rate since the last adjustment, once the
+    // adjustment interval has passed
+    pub fn adjust_config(&mut self) -> RetryConfig {
         if self.last_adjustment.elapsed() < self.adjustment_interval {
-            return self.base_config.clone();
+            return self.current_config.clone();
         }
 
         let total = self.success_count + self.failure_count;
         if total == 0 {
-            return self.base_config.clone();
+            return self.current_config.clone();
         }
 
         let failure_rate = self.failure_count as f64 / total as f64;
-        let mut config = self.base_config.clone();
+        let config = &mut self.current_config;
 
         // Adjust retry parameters based on failure rate
         if failure_rate > 0.5 {
             // High failure rate: be more aggressive
             config.max_attempts = (config.max_attempts + 1).min(10);
-            config.initial_delay = config.initial_delay * 2;
+            config.initial_delay = (config.initial_delay * 2).min(self.base_config.max_delay);
             config.jitter_factor = (config.jitter_factor + 0.1).min(0.5);
         } else if failure_rate < 0.1 {
             // Low failure rate: be less aggressive
-            config.max_attempts = (config.max_attempts - 1).max(1);
-            config.initial_delay = config.initial_delay / 2;
+            config.max_attempts = config.max_attempts.saturating_sub(1).max(1);
+            config.initial_delay = (config.initial_delay / 2).max(MIN_ADAPTIVE_INITIAL_DELAY);
             config.jitter_factor = (config.jitter_factor - 0.05).max(0.0);
         }
 
-        // Reset counters
-        self.success_count = 0;
-        self.failure_count = 0;
-        self.last_adjustment = Instant::now();
-
         debug!(
             failure_rate,
             max_attempts = config.max_attempts,
@@ -446,6 +534,8 @@ impl AdaptiveRetry {
             "Adjusted retry configuration"
         );
 
+        let config = config.clone();
+        self.reset();
         config
     }
 
@@ -457,26 +547,25 @@ impl AdaptiveRetry {
         let config = self.adjust_config();
         let mut policy = RetryPolicy::new(config, RetryStrategy::Exponential);
         
-        match policy.execute(operation).await {
-            Ok(result) => {
-                self.success_count += 1;
-                Ok(result)
-            }
-            Err(error) => {
-                self.failure_count += 1;
-                Err(error)
-            }
-        }
+        let result = policy.execute(operation).await;
+        self.record(result.is_ok());
+        result
     }
 }
 
 #[cfg(test)]
 mod tests {
     use super::*;
+    use std::sync::atomic::{AtomicUsize, Ordering};
+
+    // reqwest has no public constructor for its errors, so make one the way a bad URL would
+    fn network_error() -> reqwest::Error {
+        reqwest::Client::new().get("not a url").build().unwrap_err()
+    }
 
     #[tokio::test]
     async fn test_exponential_retry() {
-        let mut attempt = 0;
+        let attempts = AtomicUsize::new(0);
         let mut policy = RetryPolicy::exponential()
             .with_config(RetryConfig {
                 max_attempts: 3,
@@ -484,17 +573,17 @@ mod tests {
                 ..Default::default()
             });
 
-        let result = policy.execute(|| async {
-            attempt += 1;
-            if attempt < 3 {
-                Err(AppError::NetworkError(reqwest::Error::new()))
+        let counter = &attempts;
+        let result = policy.execute(|| async move {
+            if counter.fetch_add(1, Ordering::SeqCst) + 1 < 3 {
+                Err(AppError::NetworkError(network_error()))
             } else {
                 Ok(42)
             }
         }).await;
 
         assert_eq!(result.unwrap(), 42);
-        assert_eq!(attempt, 3);
+        assert_eq!(attempts.load(Ordering::SeqCst), 3);
     }
 
     #[test]
@@ -518,13 +607,118 @@ mod tests {
                 ..Default::default()
             });
 
-        let mut attempt = 0;
-        let result = policy.execute(|| async {
-            attempt += 1;
-            Err(AppError::NetworkError(reqwest::Error::new()))
+        let attempts = AtomicUsize::new(0);
+        let counter = &attempts;
+        let result: Result<(), AppError> = policy.execute(|| async move {
+            counter.fetch_add(1, Ordering::SeqCst);
+            Err(AppError::NetworkError(network_error()))
         }).await;
 
         assert!(matches!(result, Err(AppError::CircuitBreakerOpen)));
-        assert_eq!(attempt, 3); // Should stop after circuit breaker threshold
+        assert_eq!(attempts.load(Ordering::SeqCst), 3); // Should stop after circuit breaker threshold
+    }
+
+    #[test]
+    fn test_strategy_config_roundtrip() {
+        for strategy in [
+            RetryStrategyConfig::Exponential,
+            RetryStrategyConfig::Linear,
+            RetryStrategyConfig::Fixed,
+            RetryStrategyConfig::Fibonacci,
+            RetryStrategyConfig::Custom { delays_ms: vec![50, 250, 1000] },
+        ] {
+            let json = serde_json::to_string(&strategy).unwrap();
+            assert_eq!(serde_json::from_str::<RetryStrategyConfig>(&json).unwrap(), strategy);
+            let toml = toml::to_string(&strategy).unwrap();
+            assert_eq!(toml::from_str::<RetryStrategyConfig>(&toml).unwrap(), strategy);
+        }
+
+        let parsed: RetryStrategyConfig = serde_json::from_value(serde_json::json!({"type": "fibonacci"})).unwrap();
+        assert_eq!(parsed, RetryStrategyConfig::Fibonacci);
+        let parsed: RetryStrategyConfig = toml::from_str("type = \"custom\"\ndelays_ms = [10, 20]").unwrap();
+        assert_eq!(parsed, RetryStrategyConfig::Custom { delays_ms: vec![10, 20] });
+    }
+
+    #[test]
+    fn test_fibonacci_strategy_delays() {
+        let policy = RetryPolicy::from_strategy_config(&RetryStrategyConfig::Fibonacci);
+        let delays: Vec<u128> = (1..=7).map(|attempt| policy.calculate_delay(attempt).as_millis()).collect();
+        assert_eq!(delays, vec![100, 100, 200, 300, 500, 800, 1300]);
+    }
+
+    #[test]
+    fn test_custom_strategy_saturates_at_last_delay() {
+        let policy = RetryPolicy::from_strategy_config(&RetryStrategyConfig::Custom { delays_ms: vec![50, 200] });
+        let delays: Vec<u128> = (1..=4).map(|attempt| policy.calculate_delay(attempt).as_millis()).collect();
+        assert_eq!(delays, vec![50, 200, 200, 200]);
+
+        let empty = RetryPolicy::from_strategy_config(&RetryStrategyConfig::Custom { delays_ms: Vec::new() });
+        assert_eq!(empty.calculate_delay(1), Duration::ZERO);
     }
-}
\ No newline at end of file
+
+    fn fast_adjusting() -> AdaptiveRetry {
+        let config = RetryConfig {
+            max_attempts: 5,
+            initial_delay: Duration::from_millis(400),
+            ..RetryConfig::default()
+        };
+        AdaptiveRetry::new(config).with_adjustment_interval(Duration::ZERO)
+    }
+
+    #[test]
+    fn test_adaptive_retry_converges_to_fewer_retries() {
+        let mut retry = fast_adjusting();
+
+        let mut attempts = Vec::new();
+        for _ in 0..6 {
+            for i in 0..100 {
+                // 2% failures
+                retry.record(i % 50 != 0);
+            }
+            attempts.push(retry.adjust_config().max_attempts);
+        }
+        assert_eq!(attempts, vec![4, 3, 2, 1, 1, 1]);
+
+        let stats = retry.get_stats();
+        assert_eq!(stats.max_attempts, 1);
+        assert_eq!(stats.initial_delay_ms, MIN_ADAPTIVE_INITIAL_DELAY.as_millis() as u64);
+        assert_eq!((stats.success_count, stats.failure_count), (0, 0));
+    }
+
+    #[test]
+    fn test_adaptive_retry_backs_off_under_failures() {
+        let mut retry = fast_adjusting();
+        for _ in 0..10 {
+            retry.record(false);
+        }
+        let config = retry.adjust_config();
+        assert_eq!(config.max_attempts, 6);
+        assert_eq!(config.initial_delay, Duration::from_millis(800));
+
+        // A middling failure rate leaves the config where it is
+        for i in 0..10 {
+            retry.record(i % 2 == 0);
+        }
+        assert_eq!(retry.adjust_config().max_attempts, 6);
+    }
+
+    #[test]
+    fn test_adaptive_retry_reset_and_interval() {
+        let mut retry = AdaptiveRetry::new(RetryConfig::default());
+        retry.record(true);
+        retry.record(false);
+        assert_eq!((retry.get_stats().success_count, retry.get_stats().failure_count), (1, 1));
+
+        // Inside the adjustment interval nothing changes and the counts are kept
+        assert_eq!(retry.adjust_config().max_attempts, RetryConfig::default().max_attempts);
+        assert_eq!(retry.get_stats().success_count, 1);
+
+        retry.reset();
+        assert_eq!(retry.get_stats(), AdaptiveRetryStats {
+            success_count: 0,
+            failure_count: 0,
+            max_attempts: RetryConfig::default().max_attempts,
+            initial_delay_ms: 100,
+        });
+    }
+}
@@ -1,32 +1,168 @@
 use crate::{
-    auth::AuthContext,
+    aggregator::ResponseAggregator,
+    bulkhead::BulkheadRegistry,
+    chaos::NetworkPartitionSimulator,
     cache::CacheService,
+    config::{SchedulerPriorities, StickySessionConfig, StreamingConfig},
     consensus::{ConsensusService, ConsensusRequest},
-    endpoints::EndpointManager,
+    dedup::DeduplicationService,
+    endpoints::{ConnectionGuard, EndpointManager},
     error::AppError,
     geo::GeoService,
     metrics::MetricsService,
-    rate_limit::{RateLimitContext, RateLimitService},
-    rpc::{get_method_category, validate_rpc_request, RpcMethodCategory},
-    types::{RpcRequest, RpcResponse, RpcError},
+    propagation::with_baggage,
+    request_trace::{in_span, set_attribute},
+    retry::{RetryPolicy, RetryStrategyConfig},
+    scheduler::{Scheduler, SchedulerClass},
+    rpc::{get_method_category, validate_rpc_request, validate_rpc_response, RpcMethodCategory},
+    signing::RequestSigner,
+    transform::{ResponseTransformPipeline, ResponseTransformer},
+    transport::forward_client_headers,
+    types::{EndpointKind, RpcRequest},
+    AppState,
 };
-use axum::extract::Request;
+use async_trait::async_trait;
+use dashmap::DashMap;
+use axum::{
+    body::{Body, Bytes},
+    http::{header::CONTENT_TYPE, HeaderMap, HeaderName, StatusCode},
+    response::Response,
+};
+use futures::{stream, StreamExt};
+use opentelemetry::trace::FutureExt;
 use serde_json::{json, Value};
 use std::{
-    collections::HashMap,
+    collections::{HashMap, VecDeque},
     sync::Arc,
     time::{Duration, Instant},
 };
 use tokio::time::timeout;
-use tracing::{debug, error, info, warn};
+use tracing::{debug, error, info, warn, Instrument};
 use uuid::Uuid;
 
+// Tells nginx not to buffer streamed responses so chunks reach the client as they arrive
+pub const X_ACCEL_BUFFERING: &str = "x-accel-buffering";
+
+// Runs after a request has completed successfully, off the response path
+#[async_trait]
+pub trait PostRequestHook {
+    async fn on_success(&self, method: &str, params: &Value, response: &Value, state: &AppState);
+}
+
+pub const X_SESSION_ID: &str = "x-session-id";
+// Idle sessions are only swept once the map grows past this
+const STICKY_SESSION_SWEEP_THRESHOLD: usize = 10_000;
+const MAX_BATCH_SIZE: usize = 100;
+// Responses per method that the median size is taken over
+const RESPONSE_SIZE_WINDOW: usize = 100;
+// Answered by the proxy itself, see handle_multi_call
+pub const MULTI_CALL_METHOD: &str = "multi_rpc.multiCall";
+
+// Session key for sticky routing: the client's X-Session-Id, or else its forwarded IP
+pub fn session_key(headers: &HeaderMap) -> Option<String> {
+    let header = |name: &str| headers.get(name)
+        .and_then(|value| value.to_str().ok())
+        .map(|value| value.split(',').next().unwrap_or("").trim().to_string())
+        .filter(|value| !value.is_empty());
+    header(X_SESSION_ID)
+        .or_else(|| header("x-forwarded-for"))
+        .or_else(|| header("x-real-ip"))
+}
+
+// Pins each session to one endpoint so a client's consecutive calls see the same node
+pub struct StickySessionRouter {
+    // Session key -> pinned endpoint and when the session last used it
+    sessions: DashMap<String, (Uuid, Instant)>,
+    ttl: Duration,
+}
+
+impl StickySessionRouter {
+    pub fn new(ttl: Duration) -> Self {
+        Self {
+            sessions: DashMap::new(),
+            ttl,
+        }
+    }
+
+    // The session's endpoint while it is pinned and still available; otherwise the
+    // load balancer's next pick, which becomes the new pin
+    pub async fn select_endpoint(
+        &self,
+        session_id: &str,
+        method: &str,
+        excluded: &[Uuid],
+        endpoint_manager: &EndpointManager,
+    ) -> Result<(Uuid, reqwest::Client), AppError> {
+        let pinned = self.sessions.get(session_id)
+            .map(|entry| *entry.value())
+            .filter(|(_, last_used)| last_used.elapsed() < self.ttl);
+        if let Some((endpoint_id, _)) = pinned {
+            if let Some(client) = endpoint_manager.available_endpoint_client(endpoint_id, method).await {
+                self.sessions.insert(session_id.to_string(), (endpoint_id, Instant::now()));
+                return Ok((endpoint_id, client));
+            }
+            debug!("Sticky endpoint {} for session {} is unavailable, re-pinning", endpoint_id, session_id);
+        }
+
+        let (endpoint_id, client) = endpoint_manager.select_endpoint_for_method_excluding(method, excluded).await?;
+        if self.sessions.len() >= STICKY_SESSION_SWEEP_THRESHOLD {
+            self.sessions.retain(|_, (_, last_used)| last_used.elapsed() < self.ttl);
+        }
+        self.sessions.insert(session_id.to_string(), (endpoint_id, Instant::now()));
+        Ok((endpoint_id, client))
+    }
+
+    pub fn release(&self, session_id: &str) {
+        self.sessions.remove(session_id);
+    }
+}
+
+// Sizes of the most recent upstream responses to each method
+#[derive(Debug, Default)]
+struct ResponseSizeTracker {
+    samples: DashMap<String, VecDeque<usize>>,
+}
+
+impl ResponseSizeTracker {
+    fn record(&self, method: &str, size_bytes: usize) {
+        let mut samples = self.samples.entry(method.to_string()).or_default();
+        if samples.len() == RESPONSE_SIZE_WINDOW {
+            samples.pop_front();
+        }
+        samples.push_back(size_bytes);
+    }
+
+    fn median(&self, method: &str) -> Option<usize> {
+        let mut sizes: Vec<usize> = self.samples.get(method)?.iter().copied().collect();
+        sizes.sort_unstable();
+        sizes.get(sizes.len() / 2).copied()
+    }
+}
+
 pub struct RpcRouter {
     endpoint_manager: Arc<EndpointManager>,
     cache_service: Arc<CacheService>,
     consensus_service: Arc<ConsensusService>,
     geo_service: Arc<GeoService>,
     metrics_service: Arc<MetricsService>,
+    post_request_hooks: Vec<Arc<dyn PostRequestHook + Send + Sync>>,
+    partition_simulator: Option<Arc<NetworkPartitionSimulator>>,
+    response_aggregator: Arc<ResponseAggregator>,
+    response_transforms: Arc<ResponseTransformPipeline>,
+    response_transformer: Arc<ResponseTransformer>,
+    // Static responses per method, served when no endpoint is available
+    fallback_responses: Arc<HashMap<String, Value>>,
+    fallbacks_configured_at: Instant,
+    // Upstream responses larger than this are piped through unbuffered; None disables streaming
+    stream_threshold_bytes: Option<u64>,
+    sticky_sessions: Option<Arc<StickySessionRouter>>,
+    deduplication: Option<Arc<DeduplicationService>>,
+    scheduler: Arc<Scheduler>,
+    retry_policy: Arc<RetryPolicy>,
+    bulkheads: Arc<BulkheadRegistry>,
+    // Responses are refused while their method's median size is above this; None disables the check
+    max_response_size_bytes: Option<usize>,
+    response_sizes: Arc<ResponseSizeTracker>,
     max_retries: usize,
     request_timeout: Duration,
 }
@@ -39,108 +175,425 @@ impl RpcRouter {
         geo_service: Arc<GeoService>,
         metrics_service: Arc<MetricsService>,
     ) -> Self {
+        let scheduler = Arc::new(Scheduler::new(&SchedulerPriorities::default(), metrics_service.clone()));
         Self {
             endpoint_manager,
             cache_service,
             consensus_service,
             geo_service,
             metrics_service,
+            post_request_hooks: Vec::new(),
+            partition_simulator: None,
+            response_aggregator: Arc::new(ResponseAggregator::default()),
+            response_transforms: Arc::new(ResponseTransformPipeline::default()),
+            response_transformer: Arc::new(ResponseTransformer::default()),
+            fallback_responses: Arc::new(HashMap::new()),
+            fallbacks_configured_at: Instant::now(),
+            stream_threshold_bytes: None,
+            sticky_sessions: None,
+            deduplication: None,
+            scheduler,
+            retry_policy: Arc::new(RetryPolicy::from_strategy_config(&RetryStrategyConfig::default())),
+            bulkheads: Arc::new(BulkheadRegistry::default()),
+            max_response_size_bytes: None,
+            response_sizes: Arc::new(ResponseSizeTracker::default()),
             max_retries: 3,
             request_timeout: Duration::from_secs(10),
         }
     }
+
+    pub fn with_partition_simulator(mut self, simulator: Arc<NetworkPartitionSimulator>) -> Self {
+        self.partition_simulator = Some(simulator);
+        self
+    }
+
+    pub fn with_response_aggregator(mut self, aggregator: ResponseAggregator) -> Self {
+        self.response_aggregator = Arc::new(aggregator);
+        self
+    }
+
+    pub fn with_response_transforms(mut self, pipeline: ResponseTransformPipeline) -> Self {
+        self.response_transforms = Arc::new(pipeline);
+        self
+    }
+
+    pub fn with_response_transformer(mut self, transformer: ResponseTransformer) -> Self {
+        self.response_transformer = Arc::new(transformer);
+        self
+    }
+
+    pub fn with_fallback_responses(mut self, fallback_responses: HashMap<String, Value>) -> Self {
+        self.fallback_responses = Arc::new(fallback_responses);
+        self.fallbacks_configured_at = Instant::now();
+        self
+    }
+
+    pub fn with_streaming(mut self, config: &StreamingConfig) -> Self {
+        self.stream_threshold_bytes = config.enabled.then_some(config.stream_threshold_bytes);
+        self
+    }
+
+    pub fn with_sticky_sessions(mut self, config: &StickySessionConfig) -> Self {
+        self.sticky_sessions = config.enabled
+            .then(|| Arc::new(StickySessionRouter::new(Duration::from_secs(config.ttl_secs))));
+        self
+    }
+
+    pub fn with_deduplication(mut self, deduplication: DeduplicationService) -> Self {
+        self.deduplication = Some(Arc::new(deduplication));
+        self
+    }
+
+    pub fn with_scheduler(mut self, priorities: &SchedulerPriorities) -> Self {
+        self.scheduler = Arc::new(Scheduler::new(priorities, self.metrics_service.clone()));
+        self
+    }
+
+    pub fn with_retry_strategy(mut self, strategy: &RetryStrategyConfig) -> Self {
+        self.retry_policy = Arc::new(RetryPolicy::from_strategy_config(strategy));
+        self
+    }
+
+    pub fn with_bulkheads(mut self, bulkheads: Arc<BulkheadRegistry>) -> Self {
+        self.bulkheads = bulkheads;
+        self
+    }
+
+    pub fn with_max_response_size(mut self, max_response_size_bytes: Option<usize>) -> Self {
+        self.max_response_size_bytes = max_response_size_bytes;
+        self
+    }
+
+    pub fn with_post_request_hook(mut self, hook: Arc<dyn PostRequestHook + Send + Sync>) -> Self {
+        self.post_request_hooks.push(hook);
+        self
+    }
+
+    // Fires every registered hook for each successful call in `payload` without blocking the caller
+    pub fn spawn_post_request_hooks(&self, state: Arc<AppState>, payload: &Value, response: &Value) {
+        if self.post_request_hooks.is_empty() {
+            return;
+        }
+
+        let calls: Vec<(Value, Value)> = match (payload, response) {
+            (Value::Array(requests), Value::Array(responses)) => requests.iter()
+                .cloned()
+                .zip(responses.iter().cloned())
+                .collect(),
+            _ => vec![(payload.clone(), response.clone())],
+        };
+
+        for (request, response) in calls {
+            if response.get("error").is_some() {
+                continue;
+            }
+            let Some(method) = request.get("method").and_then(|m| m.as_str()).map(str::to_string) else {
+                continue;
+            };
+            let params = request.get("params").cloned().unwrap_or(Value::Null);
+            let hooks = self.post_request_hooks.clone();
+            let state = state.clone();
+
+            tokio::spawn(async move {
+                futures::future::join_all(
+                    hooks.iter().map(|hook| hook.on_success(&method, &params, &response, &state))
+                ).await;
+            });
+        }
+    }
     
+    // `session_id` pins the request to the session's endpoint when sticky sessions are enabled.
+    // `headers` are the client's, of which each endpoint gets the ones in its forward_headers.
     pub async fn route_request(
         &self, 
         payload: Value, 
-        client_ip: Option<String>
+        client_ip: Option<String>,
+        session_id: Option<&str>,
+        headers: Option<&HeaderMap>,
     ) -> Result<Value, AppError> {
-        let start_time = Instant::now();
-        
-        // Clone payload for metrics recording
-        let payload_for_metrics = payload.clone();
-        
         // Handle both single requests and batch requests
         let result = if payload.is_array() {
-            self.handle_batch_request(payload, client_ip).await
+            self.handle_batch_request(payload, client_ip, session_id, headers).await
         } else {
-            self.handle_single_request(payload, client_ip).await
+            self.handle_single_request(payload, client_ip, session_id, headers).await
         };
         
-        let duration = start_time.elapsed();
-        
-        // Record metrics regardless of success/failure
-        if let Ok(ref response) = result {
-            if let Some(method) = self.extract_method_from_payload(&payload_for_metrics) {
-                self.metrics_service.record_request(&method, None, duration).await;
-            }
-        } else {
+        // Upstream calls are recorded per endpoint where they are made
+        if result.is_err() {
             self.metrics_service.record_error("request_failed").await;
         }
         
-        result
+        result.map(|mut response| {
+            self.response_transformer.apply(&mut response);
+            response
+        })
     }
     
-    async fn handle_single_request(&self, payload: Value, client_ip: Option<String>) -> Result<Value, AppError> {
+    async fn handle_single_request(
+        &self,
+        payload: Value,
+        client_ip: Option<String>,
+        session_id: Option<&str>,
+        headers: Option<&HeaderMap>,
+    ) -> Result<Value, AppError> {
         // Validate and parse the RPC request
         let rpc_request = validate_rpc_request(&payload)
             .map_err(|e| AppError::invalid_request(&e))?;
         
         debug!("Processing RPC request: method={}, id={:?}", 
             rpc_request.method, rpc_request.id);
+        set_attribute("rpc.method", rpc_request.method.as_str());
         
-        // Check cache first for cacheable methods
-        let cache_params = rpc_request.params.clone().unwrap_or(Value::Null);
-        if let Some(cached_response) = self.cache_service.get(&rpc_request.method, &cache_params).await {
-            debug!("Cache hit for method: {}", rpc_request.method);
-            self.metrics_service.record_cache_hit();
-            return Ok(cached_response);
-        } else {
-            self.metrics_service.record_cache_miss();
+        if rpc_request.method == MULTI_CALL_METHOD {
+            return self.handle_multi_call(rpc_request, client_ip, session_id, headers).await;
         }
         
-        // Determine if consensus is needed
-        let requires_consensus = self.should_use_consensus(&rpc_request.method);
-        
-        // Get optimal endpoints based on geographic routing
-        let available_endpoints = self.endpoint_manager.get_endpoint_info().await;
-        let sorted_endpoints = if self.geo_service.is_enabled() {
-            self.geo_service.sort_endpoints_by_proximity(
-                available_endpoints,
-                client_ip.as_deref(),
-            ).await
+        let method = rpc_request.method.clone();
+        let id = rpc_request.id.clone();
+        let result = if let Some(sub_requests) = self.response_aggregator.split(&rpc_request) {
+            self.handle_split_request(rpc_request, sub_requests, client_ip, session_id, headers).await
         } else {
-            available_endpoints.into_iter()
-                .map(|endpoint| crate::geo::GeoSortedEndpoint {
-                    score: 100.0 - endpoint.priority as f64,
-                    distance_km: None,
-                    latency_penalty_ms: 0.0,
-                    region_weight: 1.0,
-                    endpoint,
+            self.route_validated_request(rpc_request, client_ip, session_id, headers, true).await
+        };
+        
+        match result {
+            Err(AppError::AllEndpointsUnhealthy) if self.fallback_responses.contains_key(&method) => {
+                Ok(self.fallback_response(&method, id))
+            }
+            // Upstream JSON-RPC errors reach the client as the node sent them
+            Err(AppError::RpcError { code, message, data }) => Ok(rpc_error_response(id, code, message, data)),
+            result => result,
+        }
+    }
+    
+    // The configured fallback is the `result`; the markers tell clients it didn't come from upstream
+    fn fallback_response(&self, method: &str, id: Option<Value>) -> Value {
+        warn!("All endpoints unhealthy, serving fallback response for {}", method);
+        self.metrics_service.record_fallback_response(method);
+        json!({
+            "jsonrpc": "2.0",
+            "id": id,
+            "result": self.fallback_responses[method],
+            "_fallback": true,
+            "_fallback_age_seconds": self.fallbacks_configured_at.elapsed().as_secs(),
+        })
+    }
+    
+    // Fans an oversized list call out across several upstream calls and merges the results
+    async fn handle_split_request(
+        &self,
+        rpc_request: RpcRequest,
+        sub_requests: Vec<RpcRequest>,
+        client_ip: Option<String>,
+        session_id: Option<&str>,
+        headers: Option<&HeaderMap>,
+    ) -> Result<Value, AppError> {
+        debug!("Splitting {} into {} sub-requests", rpc_request.method, sub_requests.len());
+        self.metrics_service.record_batch_split(sub_requests.len());
+        
+        let responses = self.response_aggregator
+            .dispatch(sub_requests, |sub_request| self.route_validated_request(sub_request, client_ip.clone(), session_id, headers, true))
+            .await?;
+        ResponseAggregator::merge(rpc_request.id, responses)
+    }
+    
+    // Runs every call in `params` concurrently and answers with their outcomes in the same order.
+    // One failing call doesn't fail the others: its slot holds an `error` instead of a `result`.
+    async fn handle_multi_call(
+        &self,
+        rpc_request: RpcRequest,
+        client_ip: Option<String>,
+        session_id: Option<&str>,
+        headers: Option<&HeaderMap>,
+    ) -> Result<Value, AppError> {
+        let calls = rpc_request.params.as_ref()
+            .and_then(Value::as_array)
+            .ok_or_else(|| AppError::invalid_request("multiCall params must be an array of calls"))?;
+        if calls.is_empty() {
+            return Err(AppError::invalid_request("Empty multiCall request"));
+        }
+        if calls.len() > MAX_BATCH_SIZE {
+            return Err(AppError::invalid_request("Batch size too large"));
+        }
+        
+        let sub_requests = calls.iter()
+            .enumerate()
+            .map(|(index, call)| {
+                let method = call.get("method")
+                    .and_then(Value::as_str)
+                    .filter(|method| !method.is_empty() && *method != MULTI_CALL_METHOD)
+                    .ok_or_else(|| AppError::invalid_request(&format!("Invalid method in multiCall entry {}", index)))?;
+                Ok(RpcRequest {
+                    id: Some(json!(index)),
+                    method: method.to_string(),
+                    params: call.get("params").cloned(),
+                    jsonrpc: "2.0".to_string(),
                 })
-                .collect()
+            })
+            .collect::<Result<Vec<_>, AppError>>()?;
+        debug!("Processing multiCall with {} calls", sub_requests.len());
+        
+        // Consensus would multiply the upstream calls per entry, so each one takes the regular path
+        let results = futures::future::join_all(sub_requests.into_iter().map(|sub_request| {
+            self.route_validated_request(sub_request, client_ip.clone(), session_id, headers, false)
+        })).await;
+        
+        let results = results.into_iter()
+            .map(|result| match result {
+                Ok(response) => match response.get("error") {
+                    Some(error) => json!({"error": error}),
+                    None => json!({"result": response.get("result").cloned().unwrap_or(Value::Null)}),
+                },
+                Err(AppError::RpcError { code, message, data }) => json!({"error": rpc_error(code, message, data)}),
+                Err(e) => json!({"error": {"code": -32603, "message": "Internal error", "data": e.to_string()}}),
+            })
+            .collect::<Vec<_>>();
+        Ok(json!({"jsonrpc": "2.0", "id": rpc_request.id, "result": results}))
+    }
+    
+    // `allow_consensus` is false for callers that want the regular path even for consensus methods
+    async fn route_validated_request(
+        &self,
+        rpc_request: RpcRequest,
+        client_ip: Option<String>,
+        session_id: Option<&str>,
+        headers: Option<&HeaderMap>,
+        allow_consensus: bool,
+    ) -> Result<Value, AppError> {
+        let cache_params = rpc_request.params.clone().unwrap_or(Value::Null);
+        let method = rpc_request.method.clone();
+        // Left as None when the response came out of the cache, see get_or_insert
+        let mut fetched = None;
+        let fetched_slot = &mut fetched;
+        let response = self.cache_service.get_or_insert(&method, &cache_params, || async move {
+            let (response, canary_response) = self.fetch_response(rpc_request, client_ip, session_id, headers, allow_consensus).await?;
+            *fetched_slot = Some(canary_response);
+            Ok(response)
+        }).await?;
+        
+        let Some(canary_response) = fetched else {
+            debug!("Cache hit for method: {}", method);
+            self.metrics_service.record_cache_hit();
+            return Ok(response);
         };
+        self.metrics_service.record_cache_miss();
+        
+        // The canary's answer is what the client gets, but only the primary's is ever cached
+        if let Some(mut canary_response) = canary_response {
+            self.response_transforms.apply(&mut canary_response);
+            return Ok(canary_response);
+        }
+        Ok(response)
+    }
+    
+    // Get optimal endpoints based on geographic routing
+    async fn sorted_endpoints(&self, client_ip: Option<&str>) -> Vec<crate::geo::GeoSortedEndpoint> {
+        in_span("endpoint_selection", async {
+            let available_endpoints = self.endpoint_manager.get_endpoint_info().await;
+            if self.geo_service.is_enabled() {
+                self.geo_service.sort_endpoints_by_proximity(available_endpoints, client_ip).await
+            } else {
+                available_endpoints.into_iter()
+                    .map(|endpoint| crate::geo::GeoSortedEndpoint {
+                        score: 100.0 - endpoint.effective_priority() as f64,
+                        distance_km: None,
+                        latency_penalty_ms: 0.0,
+                        region_weight: 1.0,
+                        endpoint,
+                    })
+                    .collect()
+            }
+        }).await
+    }
+    
+    // The primary response, transformed and ready to cache, along with the canary's if one was asked
+    async fn fetch_response(
+        &self,
+        rpc_request: RpcRequest,
+        client_ip: Option<String>,
+        session_id: Option<&str>,
+        headers: Option<&HeaderMap>,
+        allow_consensus: bool,
+    ) -> Result<(Value, Option<Value>), AppError> {
+        // Determine if consensus is needed
+        let requires_consensus = allow_consensus && self.should_use_consensus(&rpc_request.method);
+        let method = rpc_request.method.clone();
+        
+        let sorted_endpoints = self.sorted_endpoints(client_ip.as_deref()).await;
         
-        let response = if requires_consensus {
-            self.handle_consensus_request(rpc_request, sorted_endpoints).await?
+        let canary = if requires_consensus { None } else { self.endpoint_manager.select_canary(&method).await };
+        let (mut response, canary_response) = if requires_consensus {
+            (self.handle_consensus_request(rpc_request, sorted_endpoints, headers).await?, None)
+        } else if let Some(canary_id) = canary {
+            // The regular path still runs so there is an answer to compare against and fall back to
+            let (response, canary_response) = tokio::join!(
+                self.handle_standard_request(rpc_request.clone(), sorted_endpoints, session_id, headers),
+                self.canary_request(canary_id, &rpc_request),
+            );
+            let response = response?;
+            if let Some(canary_response) = &canary_response {
+                self.compare_canary(canary_id, &method, &response, canary_response).await;
+            }
+            (response, canary_response)
         } else {
-            self.handle_standard_request(rpc_request, sorted_endpoints).await?
+            (self.handle_standard_request(rpc_request, sorted_endpoints, session_id, headers).await?, None)
         };
         
-        // Cache the response if appropriate
-        if let Ok(ref rpc_req) = validate_rpc_request(&payload) {
-            let cache_params = rpc_req.params.clone().unwrap_or(Value::Null);
-            self.cache_service.set(
-                &rpc_req.method,
-                &cache_params,
-                &response
-            ).await;
+        // Sanitize before caching so the cached value never holds stripped fields
+        for step in self.response_transforms.apply(&mut response) {
+            self.metrics_service.record_response_transform(step);
         }
-        
-        Ok(response)
+        Ok((response, canary_response))
     }
     
-    async fn handle_batch_request(&self, payload: Value, client_ip: Option<String>) -> Result<Value, AppError> {
+    // None on any failure, including a JSON-RPC error, so the primary response is used instead
+    async fn canary_request(&self, canary_id: Uuid, rpc_request: &RpcRequest) -> Option<Value> {
+        let request_payload = json!({
+            "jsonrpc": rpc_request.jsonrpc,
+            "id": rpc_request.id,
+            "method": rpc_request.method,
+            "params": rpc_request.params
+        });
+        let start_time = Instant::now();
+        let outcome = timeout(self.request_timeout, self.endpoint_manager.send_rpc_request(canary_id, request_payload)).await;
+        let response = match outcome {
+            Ok(Ok(response)) if response.get("error").is_none() => Some(response),
+            Ok(Ok(response)) => {
+                debug!("Canary {} returned an error for {}: {}", canary_id, rpc_request.method, response["error"]);
+                None
+            }
+            Ok(Err(e)) => {
+                debug!("Canary {} failed for {}: {}", canary_id, rpc_request.method, e);
+                None
+            }
+            Err(_) => {
+                debug!("Canary {} timed out for {}", canary_id, rpc_request.method);
+                None
+            }
+        };
+        self.endpoint_manager.update_endpoint_stats(canary_id, response.is_some(), start_time.elapsed()).await;
+        response
+    }
+    
+    async fn compare_canary(&self, canary_id: Uuid, method: &str, primary: &Value, canary: &Value) {
+        if primary.get("result") == canary.get("result") {
+            return;
+        }
+        let canary_name = self.endpoint_manager.get_endpoint_name(canary_id).await
+            .unwrap_or_else(|| canary_id.to_string());
+        warn!(
+            "Canary {} disagrees with primary on {}: primary={} canary={}",
+            canary_name, method, primary["result"], canary["result"],
+        );
+    }
+    
+    async fn handle_batch_request(
+        &self,
+        payload: Value,
+        client_ip: Option<String>,
+        session_id: Option<&str>,
+        headers: Option<&HeaderMap>,
+    ) -> Result<Value, AppError> {
         let requests = payload.as_array()
             .ok_or_else(|| AppError::invalid_request("Invalid batch request"))?;
         
@@ -148,7 +601,7 @@ impl RpcRouter {
             return Err(AppError::invalid_request("Empty batch request"));
         }
         
-        if requests.len() > 100 {
+        if requests.len() > MAX_BATCH_SIZE {
             return Err(AppError::invalid_request("Batch size too large"));
         }
         
@@ -162,12 +615,14 @@ impl RpcRouter {
             let permit = semaphore.clone().acquire_owned().await.unwrap();
             let router = self.clone();
             let client_ip_clone = client_ip.clone();
+            let session_id = session_id.map(str::to_string);
+            let headers = headers.cloned();
             let request_clone = request.clone();
             
             let task = tokio::spawn(async move {
                 let _permit = permit;
-                router.handle_single_request(request_clone, client_ip_clone).await
-            });
+                router.handle_single_request(request_clone, client_ip_clone, session_id.as_deref(), headers.as_ref()).await
+            }.with_current_context().in_current_span());
             
             tasks.push(task);
         }
@@ -209,38 +664,44 @@ impl RpcRouter {
         &self,
         rpc_request: RpcRequest,
         sorted_endpoints: Vec<crate::geo::GeoSortedEndpoint>,
+        headers: Option<&HeaderMap>,
     ) -> Result<Value, AppError> {
         let consensus_start = Instant::now();
         
-        // Select top endpoints for consensus
-        let top_endpoints: Vec<_> = sorted_endpoints
-            .into_iter()
-            .take(5) // Use top 5 endpoints for consensus
-            .map(|ge| ge.endpoint)
-            .collect();
+        // Ask the top 5 available endpoints, each through its own client
+        let mut top_endpoints = Vec::new();
+        let mut clients = HashMap::new();
+        for ge in sorted_endpoints {
+            if clients.len() == 5 {
+                break;
+            }
+            if let Some(client) = self.endpoint_manager.available_endpoint_client(ge.endpoint.id, &rpc_request.method).await {
+                clients.insert(ge.endpoint.id, client);
+                top_endpoints.push(ge.endpoint);
+            }
+        }
         
         if top_endpoints.len() < 2 {
             warn!("Insufficient endpoints for consensus, falling back to single endpoint");
-            return self.handle_standard_request(rpc_request, vec![]).await;
+            return self.handle_standard_request(rpc_request, vec![], None, headers).await;
         }
         
-        // Create HTTP clients for selected endpoints
-        let mut clients = HashMap::new();
-        for endpoint in &top_endpoints {
-            if let Ok((endpoint_id, client)) = self.endpoint_manager.select_endpoint().await {
-                clients.insert(endpoint_id, client);
+        let mut forwarded_headers = HashMap::new();
+        if let Some(headers) = headers {
+            for endpoint in &top_endpoints {
+                forwarded_headers.insert(endpoint.id, self.endpoint_manager.forwarded_headers(endpoint.id, headers).await);
             }
         }
-        
         let consensus_request = ConsensusRequest {
             method: rpc_request.method.clone(),
             params: rpc_request.params.unwrap_or(Value::Null),
             endpoints: top_endpoints,
             require_consensus: true,
+            forwarded_headers,
         };
         
-        let consensus_result = self.consensus_service
-            .validate_response(consensus_request, clients)
+        let consensus_result = self.bulkheads.get_or_default("consensus")
+            .execute(|| in_span("consensus", self.consensus_service.validate_response(consensus_request, clients)))
             .await?;
         
         let consensus_duration = consensus_start.elapsed();
@@ -271,23 +732,35 @@ impl RpcRouter {
         &self,
         rpc_request: RpcRequest,
         sorted_endpoints: Vec<crate::geo::GeoSortedEndpoint>,
+        session_id: Option<&str>,
+        headers: Option<&HeaderMap>,
     ) -> Result<Value, AppError> {
+        let class = SchedulerClass::for_method(&rpc_request.method);
+        // Endpoints this request has been sent to, so each retry can go somewhere else
+        let mut tried = Vec::new();
         // Try the request with retries and failover
         for attempt in 0..=self.max_retries {
-            match self.try_request(&rpc_request, attempt, &sorted_endpoints).await {
+            let upstream_request = in_span("upstream_request", async {
+                set_attribute("attempt", attempt + 1);
+                // Held per attempt so the backoff below doesn't occupy a slot
+                let _permit = self.scheduler.acquire(class).await;
+                self.try_request(&rpc_request, attempt, &sorted_endpoints, &mut tried, session_id, headers).await
+            });
+            match upstream_request.await {
                 Ok(response) => {
                     debug!("Request successful on attempt {}", attempt + 1);
+                    self.check_response_size(&rpc_request.method)?;
                     return Ok(response);
                 }
+                // The node answered; another endpoint would most likely give the same answer
+                Err(e @ AppError::RpcError { .. }) => return Err(e),
                 Err(e) => {
                     if attempt == self.max_retries {
                         error!("Request failed after {} attempts: {}", attempt + 1, e);
                         return Err(e);
                     } else {
                         warn!("Request failed on attempt {}, retrying: {}", attempt + 1, e);
-                        // Exponential backoff
-                        let delay = Duration::from_millis(100 * (1 << attempt));
-                        tokio::time::sleep(delay).await;
+                        tokio::time::sleep(self.retry_policy.calculate_delay(attempt as u32 + 1)).await;
                     }
                 }
             }
@@ -296,30 +769,122 @@ impl RpcRouter {
         Err(AppError::internal("Max retries exceeded"))
     }
     
+    // Checked once per request rather than per attempt, since retrying elsewhere wouldn't shrink the responses
+    fn check_response_size(&self, method: &str) -> Result<(), AppError> {
+        let Some(limit) = self.max_response_size_bytes else {
+            return Ok(());
+        };
+        match self.response_sizes.median(method) {
+            Some(median) if median > limit => {
+                warn!("Median response size for {} is {} bytes, above the {} byte limit", method, median, limit);
+                Err(AppError::endpoint("response too large"))
+            }
+            _ => Ok(()),
+        }
+    }
+
     async fn try_request(
         &self,
         rpc_request: &RpcRequest,
         attempt: usize,
         sorted_endpoints: &[crate::geo::GeoSortedEndpoint],
+        tried: &mut Vec<Uuid>,
+        session_id: Option<&str>,
+        headers: Option<&HeaderMap>,
     ) -> Result<Value, AppError> {
-        let start_time = Instant::now();
-        
-        // Select endpoint based on attempt and availability
-        let (endpoint_id, client) = if sorted_endpoints.is_empty() {
-            self.endpoint_manager.select_endpoint().await?
+        // Transactions always go upstream themselves, even when a client resubmits one
+        let deduplication = self.deduplication.as_ref()
+            .filter(|_| get_method_category(&rpc_request.method) != RpcMethodCategory::Transaction);
+        let Some(deduplication) = deduplication else {
+            return self.forward_request(rpc_request, attempt, sorted_endpoints, tried, session_id, headers).await;
+        };
+
+        let key = self.cache_service.create_cache_key(
+            &rpc_request.method,
+            rpc_request.params.as_ref().unwrap_or(&Value::Null),
+        );
+        let mut response = deduplication
+            .execute(key, || self.forward_request(rpc_request, attempt, sorted_endpoints, tried, session_id, headers))
+            .await?;
+        // A shared response carries the id of whichever request went upstream
+        if let Some(object) = response.as_object_mut() {
+            object.insert("id".to_string(), rpc_request.id.clone().unwrap_or(Value::Null));
+        }
+        Ok(response)
+    }
+
+    // The closest endpoint not yet tried when geo routing is on, otherwise the load balancer's
+    // pick among the untried ones
+    async fn select_untried_endpoint(
+        &self,
+        method: &str,
+        sorted_endpoints: &[crate::geo::GeoSortedEndpoint],
+        tried: &[Uuid],
+    ) -> Result<(Uuid, reqwest::Client), AppError> {
+        if self.geo_service.is_enabled() {
+            let untried = sorted_endpoints.iter()
+                .map(|sorted| sorted.endpoint.id)
+                .filter(|endpoint_id| !tried.contains(endpoint_id));
+            for endpoint_id in untried {
+                if let Some(client) = self.endpoint_manager.available_endpoint_client(endpoint_id, method).await {
+                    return Ok((endpoint_id, client));
+                }
+            }
+        }
+        self.endpoint_manager.select_endpoint_for_method_excluding(method, tried).await
+    }
+
+    // Select endpoint based on attempt and availability: the session's endpoint when sticky
+    // sessions are on, otherwise the best one not yet tried
+    async fn select_forward_endpoint(
+        &self,
+        method: &str,
+        attempt: usize,
+        sorted_endpoints: &[crate::geo::GeoSortedEndpoint],
+        tried: &[Uuid],
+        session_id: Option<&str>,
+    ) -> Result<Uuid, AppError> {
+        let sticky_session = session_id.zip(self.sticky_sessions.as_ref());
+        let (endpoint_id, _) = if let Some((session_id, sticky_sessions)) = sticky_session {
+            // A retry means the pinned endpoint just failed, so move the session elsewhere
+            if attempt > 0 {
+                sticky_sessions.release(session_id);
+            }
+            sticky_sessions.select_endpoint(session_id, method, tried, &self.endpoint_manager).await?
         } else {
-            // Use geographic preference but fall back to health-based selection
-            let endpoint_index = attempt % sorted_endpoints.len();
-            let selected_endpoint = &sorted_endpoints[endpoint_index].endpoint;
-            
-            // Get client for this specific endpoint
-            self.endpoint_manager.select_endpoint().await? // Simplified for now
+            self.select_untried_endpoint(method, sorted_endpoints, tried).await?
         };
+        Ok(endpoint_id)
+    }
+
+    async fn forward_request(
+        &self,
+        rpc_request: &RpcRequest,
+        attempt: usize,
+        sorted_endpoints: &[crate::geo::GeoSortedEndpoint],
+        tried: &mut Vec<Uuid>,
+        session_id: Option<&str>,
+        headers: Option<&HeaderMap>,
+    ) -> Result<Value, AppError> {
+        let start_time = Instant::now();
+        let endpoint_id = self.select_forward_endpoint(&rpc_request.method, attempt, sorted_endpoints, tried, session_id).await?;
+        tried.push(endpoint_id);
         
         let endpoint_url = self.endpoint_manager.get_endpoint_url(endpoint_id).await
             .ok_or_else(|| AppError::endpoint("Endpoint not found"))?;
+        let endpoint_name = self.endpoint_manager.get_endpoint_name(endpoint_id).await
+            .unwrap_or_else(|| endpoint_url.clone());
+        // Held until this attempt returns, however it ends
+        let _connection = self.endpoint_manager.acquire_connection(endpoint_id).await;
+        
+        if self.partition_simulator.as_ref().is_some_and(|simulator| simulator.check_blocked(endpoint_id)) {
+            debug!("Endpoint {} unreachable due to simulated partition", endpoint_url);
+            self.endpoint_manager.update_endpoint_stats(endpoint_id, false, start_time.elapsed()).await;
+            return Err(AppError::RequestTimeout);
+        }
         
         debug!("Attempting request to endpoint {} (attempt {})", endpoint_url, attempt + 1);
+        set_attribute("endpoint", endpoint_url.as_str());
         
         // Prepare request payload
         let request_payload = json!({
@@ -329,47 +894,45 @@ impl RpcRouter {
             "params": rpc_request.params
         });
         
-        // Make the request with timeout
-        let request_future = client
-            .post(&endpoint_url)
-            .header("Content-Type", "application/json")
-            .header("User-Agent", "Multi-RPC/1.0")
-            .json(&request_payload)
-            .send();
-        
-        let response = match timeout(self.request_timeout, request_future).await {
-            Ok(Ok(response)) => response,
+        // Make the request with timeout, over HTTP or gRPC depending on the endpoint
+        let request_future = self.endpoint_manager.send_measured_rpc_request(endpoint_id, request_payload, headers);
+        let response_json = match timeout(self.request_timeout, request_future).await {
+            Ok(Ok((response, size_bytes))) => {
+                self.metrics_service.record_response_size(&endpoint_name, &rpc_request.method, size_bytes);
+                if self.max_response_size_bytes.is_some() {
+                    self.response_sizes.record(&rpc_request.method, size_bytes);
+                }
+                let expected_id = rpc_request.id.clone().unwrap_or(Value::Null);
+                if let Err(e) = validate_rpc_response(&response, &expected_id) {
+                    // Counted against the endpoint so the retry is steered elsewhere
+                    warn!("Endpoint {} returned an invalid response to {}: {}", endpoint_name, rpc_request.method, e);
+                    let elapsed = start_time.elapsed();
+                    self.endpoint_manager.update_endpoint_stats(endpoint_id, false, elapsed).await;
+                    self.record_upstream_call(&endpoint_name, &rpc_request.method, elapsed, false);
+                    return Err(e);
+                }
+                response
+            }
+            // An error the transport answered with itself, e.g. a method gRPC can't serve
+            Ok(Err(AppError::RpcError { code, message, data })) => {
+                rpc_error_response(rpc_request.id.clone(), code, message, data)
+            }
             Ok(Err(e)) => {
                 let elapsed = start_time.elapsed();
                 self.endpoint_manager.update_endpoint_stats(endpoint_id, false, elapsed).await;
-                return Err(AppError::NetworkError(e));
+                self.record_upstream_call(&endpoint_name, &rpc_request.method, elapsed, false);
+                return Err(e);
             }
             Err(_) => {
                 let elapsed = start_time.elapsed();
                 self.endpoint_manager.update_endpoint_stats(endpoint_id, false, elapsed).await;
+                self.record_upstream_call(&endpoint_name, &rpc_request.method, elapsed, false);
                 return Err(AppError::RequestTimeout);
             }
         };
         
         let elapsed = start_time.elapsed();
         
-        if !response.status().is_success() {
-            self.endpoint_manager.update_endpoint_stats(endpoint_id, false, elapsed).await;
-            return Err(AppError::endpoint(&format!(
-                "HTTP {}: {}", response.status(), endpoint_url
-            )));
-        }
-        
-        // Parse the response
-        let response_text = response.text().await
-            .map_err(|e| {
-                // Note: We can't call async function in map_err closure
-                AppError::NetworkError(e)
-            })?;
-        
-        let response_json: Value = serde_json::from_str(&response_text)
-            .map_err(|e| AppError::JsonError(e))?;
-        
         // Check if the response contains an error
         let is_success = if let Some(error) = response_json.get("error") {
             // Some errors are expected (like "method not found") and shouldn't be retried
@@ -386,22 +949,27 @@ impl RpcRouter {
         };
         
         // Update endpoint statistics
-        self.endpoint_manager.update_endpoint_stats(endpoint_id, is_success, elapsed).await;
+        if self.endpoint_manager.update_endpoint_stats(endpoint_id, is_success, elapsed).await.is_some() {
+            self.metrics_service.record_latency_anomaly();
+        }
         
         // Record endpoint-specific metrics
-        self.metrics_service.record_endpoint_stats(
-            endpoint_id,
-            &endpoint_url,
-            elapsed,
-            is_success
-        ).await;
+        self.record_upstream_call(&endpoint_name, &rpc_request.method, elapsed, is_success);
         
         debug!("Request completed: endpoint={}, success={}, time={}ms", 
             endpoint_url, is_success, elapsed.as_millis());
         
+        if let Some(rpc_error) = response_json.get("error").and_then(AppError::from_rpc_error) {
+            return Err(rpc_error);
+        }
         Ok(response_json)
     }
     
+    fn record_upstream_call(&self, endpoint_name: &str, method: &str, elapsed: Duration, success: bool) {
+        self.metrics_service.record_request(endpoint_name, method, elapsed);
+        self.metrics_service.record_endpoint_stats(endpoint_name, method, elapsed, success);
+    }
+    
     fn should_use_consensus(&self, method: &str) -> bool {
         // Determine if method requires consensus validation
         matches!(method,
@@ -410,13 +978,7 @@ impl RpcRouter {
             "getBalance" |
             "getSignatureStatuses" |
             "getTransaction"
-        )
-    }
-    
-    fn extract_method_from_payload(&self, payload: &Value) -> Option<String> {
-        payload.get("method")
-            .and_then(|m| m.as_str())
-            .map(|s| s.to_string())
+        ) || self.consensus_service.has_threshold_override(method)
     }
     
     pub fn set_max_retries(&mut self, max_retries: usize) {
@@ -454,7 +1016,7 @@ impl RpcRouter {
                     "method": rpc_request.method,
                     "params": rpc_request.params
                 });
-                self.handle_single_request(payload, client_ip).await
+                self.handle_single_request(payload, client_ip, None, None).await
             }
         }
     }
@@ -462,7 +1024,7 @@ impl RpcRouter {
     async fn route_to_fastest_endpoint(&self, rpc_request: &RpcRequest) -> Result<Value, AppError> {
         // Select the endpoint with lowest latency
         let endpoints = self.endpoint_manager.get_endpoint_info().await;
-        let fastest_endpoint = endpoints
+        let _fastest_endpoint = endpoints
             .into_iter()
             .min_by(|a, b| a.score.avg_response_time.partial_cmp(&b.score.avg_response_time).unwrap_or(std::cmp::Ordering::Equal))
             .ok_or_else(|| AppError::AllEndpointsUnhealthy)?;
@@ -480,8 +1042,7 @@ impl RpcRouter {
         });
         
         let start_time = Instant::now();
-        let response = client
-            .post(&endpoint_url)
+        let response = with_baggage(client.post(&endpoint_url))
             .json(&request_payload)
             .send()
             .await?;
@@ -489,11 +1050,151 @@ impl RpcRouter {
         let elapsed = start_time.elapsed();
         let response_json: Value = response.json().await?;
         
-        self.endpoint_manager.update_endpoint_stats(endpoint_id, true, elapsed).await;
+        if self.endpoint_manager.update_endpoint_stats(endpoint_id, true, elapsed).await.is_some() {
+            self.metrics_service.record_latency_anomaly();
+        }
         
         Ok(response_json)
     }
     
+    // Streams single calls that are plain-forwarded. Returns None for anything that needs the
+    // parsed response (cached, consensus, split or transformed calls) so it goes through route_request.
+    // The endpoint is picked as forward_request picks it (geo order, sticky sessions), and the call
+    // holds a scheduler permit, the endpoint's connection slot and its forward_headers the same way.
+    // The body passes through unparsed, so it isn't validated, deduplicated or retried elsewhere;
+    // its size is recorded once the stream ends.
+    pub async fn route_streaming_request(
+        &self,
+        payload: &Value,
+        session_id: Option<&str>,
+        headers: Option<&HeaderMap>,
+    ) -> Result<Option<Response>, AppError> {
+        if self.stream_threshold_bytes.is_none()
+            || payload.is_array()
+            || !self.response_transforms.is_empty()
+            || !self.response_transformer.is_empty()
+        {
+            return Ok(None);
+        }
+        let rpc_request = validate_rpc_request(payload)
+            .map_err(|e| AppError::invalid_request(&e))?;
+        if self.should_use_consensus(&rpc_request.method)
+            || self.cache_service.caches(&rpc_request.method)
+            || self.response_aggregator.split(&rpc_request).is_some()
+            || self.fallback_responses.contains_key(&rpc_request.method)
+        {
+            return Ok(None);
+        }
+
+        let sorted_endpoints = self.sorted_endpoints(None).await;
+        let endpoint_id = self.select_forward_endpoint(&rpc_request.method, 0, &sorted_endpoints, &[], session_id).await?;
+        let endpoint_config = self.endpoint_manager.get_endpoint_config(endpoint_id).await
+            .ok_or_else(|| AppError::endpoint("Endpoint not found"))?;
+        // gRPC answers are rebuilt into JSON, so there is no upstream body to stream
+        if endpoint_config.kind == EndpointKind::Grpc {
+            return Ok(None);
+        }
+        // Streamed calls can't be refused after the fact, so the size limit is checked up front
+        self.check_response_size(&rpc_request.method)?;
+        let client = self.endpoint_manager.get_endpoint_client(endpoint_id).await
+            .ok_or_else(|| AppError::endpoint("Endpoint not found"))?;
+        let endpoint_url = endpoint_config.url;
+        let endpoint_name = endpoint_config.name;
+
+        let start_time = Instant::now();
+        let _permit = self.scheduler.acquire(SchedulerClass::for_method(&rpc_request.method)).await;
+        let connection = self.endpoint_manager.acquire_connection(endpoint_id).await;
+        if self.partition_simulator.as_ref().is_some_and(|simulator| simulator.check_blocked(endpoint_id)) {
+            debug!("Endpoint {} unreachable due to simulated partition", endpoint_url);
+            self.endpoint_manager.update_endpoint_stats(endpoint_id, false, start_time.elapsed()).await;
+            return Err(AppError::RequestTimeout);
+        }
+
+        let request_payload = json!({
+            "jsonrpc": rpc_request.jsonrpc,
+            "id": rpc_request.id,
+            "method": rpc_request.method,
+            "params": rpc_request.params
+        });
+        // Invalid names were already refused when the endpoint was created
+        let forward_headers: Vec<HeaderName> = endpoint_config.forward_headers.iter()
+            .filter_map(|name| name.parse().ok())
+            .collect();
+        let builder = forward_client_headers(with_baggage(client.post(&endpoint_url)), &forward_headers, headers);
+        let signer = endpoint_config.signing.as_ref().map(RequestSigner::new);
+        let call = StreamedCall {
+            _connection: connection,
+            endpoint_name: endpoint_name.clone(),
+            method: rpc_request.method.clone(),
+            metrics_service: self.metrics_service.clone(),
+            response_sizes: self.max_response_size_bytes.map(|_| self.response_sizes.clone()),
+        };
+        let result = self.stream_response(builder, &endpoint_url, &request_payload, signer.as_ref(), Some(call)).await;
+        let elapsed = start_time.elapsed();
+
+        // Only the time to first byte is known here; a mid-stream failure is logged by the stream
+        let success = result.as_ref().is_ok_and(|response| response.status().is_success());
+        if self.endpoint_manager.update_endpoint_stats(endpoint_id, success, elapsed).await.is_some() {
+            self.metrics_service.record_latency_anomaly();
+        }
+        self.record_upstream_call(&endpoint_name, &rpc_request.method, elapsed, success);
+        if result.is_err() {
+            self.metrics_service.record_error("request_failed").await;
+        }
+
+        result.map(Some)
+    }
+
+    // Forwards the upstream response body as it arrives once it is over the streaming
+    // threshold (or of unknown length); smaller responses are buffered as usual. `call`, if
+    // given, is held until the body has been sent and records its size.
+    pub async fn stream_response(
+        &self,
+        builder: reqwest::RequestBuilder,
+        endpoint_url: &str,
+        request_payload: &Value,
+        signer: Option<&RequestSigner>,
+        call: Option<StreamedCall>,
+    ) -> Result<Response, AppError> {
+        let builder = match signer {
+            Some(signer) => signer.sign(builder, request_payload)?,
+            None => builder.json(request_payload),
+        };
+        let upstream = timeout(self.request_timeout, builder.send()).await??;
+
+        let status = StatusCode::from_u16(upstream.status().as_u16()).unwrap_or(StatusCode::BAD_GATEWAY);
+        let content_type = upstream.headers()
+            .get(reqwest::header::CONTENT_TYPE)
+            .and_then(|value| value.to_str().ok())
+            .unwrap_or("application/json")
+            .to_string();
+        let response = Response::builder()
+            .status(status)
+            .header(CONTENT_TYPE, content_type);
+
+        let threshold = self.stream_threshold_bytes.unwrap_or(u64::MAX);
+        if upstream.content_length().is_some_and(|length| length <= threshold) {
+            let body = upstream.bytes().await?;
+            if let Some(call) = &call {
+                call.record_size(body.len());
+            }
+            return response.body(Body::from(body))
+                .map_err(|e| AppError::internal(&format!("Failed to build response: {}", e)));
+        }
+
+        debug!("Streaming response from {}", endpoint_url);
+        let body = guard_upstream_stream(
+            upstream.bytes_stream(),
+            endpoint_url.to_string(),
+            request_payload.get("id").cloned().unwrap_or(Value::Null),
+            call,
+        );
+        response
+            .header(X_ACCEL_BUFFERING, "no")
+            .body(Body::from_stream(body))
+            .map_err(|e| AppError::internal(&format!("Failed to build response: {}", e)))
+    }
+    
     async fn route_with_aggressive_caching(&self, rpc_request: &RpcRequest) -> Result<Value, AppError> {
         // Check cache with longer TTL for static methods
         let params = rpc_request.params.as_ref().unwrap_or(&Value::Null);
@@ -510,7 +1211,7 @@ impl RpcRouter {
             "params": rpc_request.params
         });
         
-        let response = self.handle_single_request(payload, None).await?;
+        let response = self.handle_single_request(payload, None, None, None).await?;
         
         // Cache with extended TTL for static data
         self.cache_service.set(&rpc_request.method, params, &response).await;
@@ -525,7 +1226,7 @@ impl RpcRouter {
             client_ip.as_deref(),
         ).await;
         
-        self.handle_consensus_request(rpc_request.clone(), sorted_endpoints).await
+        self.handle_consensus_request(rpc_request.clone(), sorted_endpoints, None).await
     }
 }
 
@@ -538,8 +1239,570 @@ impl Clone for RpcRouter {
             consensus_service: self.consensus_service.clone(),
             geo_service: self.geo_service.clone(),
             metrics_service: self.metrics_service.clone(),
+            post_request_hooks: self.post_request_hooks.clone(),
+            partition_simulator: self.partition_simulator.clone(),
+            response_aggregator: self.response_aggregator.clone(),
+            response_transforms: self.response_transforms.clone(),
+            response_transformer: self.response_transformer.clone(),
+            fallback_responses: self.fallback_responses.clone(),
+            fallbacks_configured_at: self.fallbacks_configured_at,
+            stream_threshold_bytes: self.stream_threshold_bytes,
+            sticky_sessions: self.sticky_sessions.clone(),
+            deduplication: self.deduplication.clone(),
+            scheduler: self.scheduler.clone(),
+            retry_policy: self.retry_policy.clone(),
+            bulkheads: self.bulkheads.clone(),
+            max_response_size_bytes: self.max_response_size_bytes,
+            response_sizes: self.response_sizes.clone(),
             max_retries: self.max_retries,
             request_timeout: self.request_timeout,
         }
     }
-}
\ No newline at end of file
+}
+
+fn rpc_error(code: i64, message: String, data: Option<Value>) -> Value {
+    let mut error = json!({"code": code, "message": message});
+    if let Some(data) = data {
+        error["data"] = data;
+    }
+    error
+}
+
+// Rebuilds the upstream node's JSON-RPC error, answering the client's request id
+fn rpc_error_response(id: Option<Value>, code: i64, message: String, data: Option<Value>) -> Value {
+    json!({"jsonrpc": "2.0", "id": id, "error": rpc_error(code, message, data)})
+}
+
+// If the upstream fails before sending anything the client still gets a well-formed JSON-RPC
+// error. Once bytes are out the response can't be repaired, so the stream is aborted instead,
+// which the client sees as a truncated body rather than a silently incomplete result.
+fn guard_upstream_stream(
+    upstream: impl futures::Stream<Item = reqwest::Result<Bytes>> + Send + 'static,
+    endpoint_url: String,
+    id: Value,
+    call: Option<StreamedCall>,
+) -> impl futures::Stream<Item = Result<Bytes, std::io::Error>> + Send + 'static {
+    stream::unfold(Some((upstream.boxed(), 0, call)), move |state| {
+        let endpoint_url = endpoint_url.clone();
+        let id = id.clone();
+        async move {
+            let (mut upstream, sent_bytes, call) = state?;
+            match upstream.next().await {
+                None => {
+                    if let Some(call) = call {
+                        call.record_size(sent_bytes);
+                    }
+                    None
+                }
+                Some(Ok(chunk)) => {
+                    let sent_bytes = sent_bytes + chunk.len();
+                    Some((Ok(chunk), Some((upstream, sent_bytes, call))))
+                }
+                Some(Err(e)) if sent_bytes == 0 => {
+                    warn!("Upstream {} failed before sending a response: {}", endpoint_url, e);
+                    let error = json!({
+                        "jsonrpc": "2.0",
+                        "id": id,
+                        "error": {
+                            "code": -32603,
+                            "message": "Upstream connection closed before a response was received"
+                        }
+                    });
+                    Some((Ok(Bytes::from(error.to_string())), None))
+                }
+                Some(Err(e)) => {
+                    error!("Upstream {} closed mid-stream, aborting response: {}", endpoint_url, e);
+                    Some((Err(std::io::Error::new(std::io::ErrorKind::UnexpectedEof, e)), None))
+                }
+            }
+        }
+    })
+}
+
+// Travels with a streamed body until it is dropped: holds the endpoint's connection slot,
+// and records the response size the way forward_request does
+pub struct StreamedCall {
+    _connection: Option<ConnectionGuard>,
+    endpoint_name: String,
+    method: String,
+    metrics_service: Arc<MetricsService>,
+    // Only kept while a response size limit is configured
+    response_sizes: Option<Arc<ResponseSizeTracker>>,
+}
+
+impl StreamedCall {
+    fn record_size(&self, size_bytes: usize) {
+        self.metrics_service.record_response_size(&self.endpoint_name, &self.method, size_bytes);
+        if let Some(response_sizes) = &self.response_sizes {
+            response_sizes.record(&self.method, size_bytes);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_server::{rpc_result, TestServerBuilder};
+    use tokio::{io::AsyncWriteExt, net::TcpListener};
+    use wiremock::{matchers::method, Mock, ResponseTemplate};
+
+    // Sends the response headers and `chunk` (if any) with chunked encoding, then drops
+    // the connection without the terminating chunk
+    async fn truncating_upstream(chunk: &'static str) -> String {
+        let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let url = format!("http://{}", listener.local_addr().unwrap());
+        tokio::spawn(async move {
+            let (mut socket, _) = listener.accept().await.unwrap();
+            // Read the request before answering so the client sees a response, not a reset
+            let mut request = vec![0u8; 8192];
+            let _ = tokio::io::AsyncReadExt::read(&mut socket, &mut request).await;
+
+            let mut response = String::from(
+                "HTTP/1.1 200 OK\r\nContent-Type: application/json; charset=utf-8\r\nTransfer-Encoding: chunked\r\n\r\n",
+            );
+            if !chunk.is_empty() {
+                response.push_str(&format!("{:x}\r\n{}\r\n", chunk.len(), chunk));
+            }
+            socket.write_all(response.as_bytes()).await.unwrap();
+            socket.flush().await.unwrap();
+        });
+        url
+    }
+
+    fn streaming_server() -> TestServerBuilder {
+        TestServerBuilder::new().with_config(|config| {
+            config.streaming.enabled = true;
+            config.streaming.stream_threshold_bytes = 16;
+        })
+    }
+
+    fn payload() -> Value {
+        json!({"jsonrpc": "2.0", "id": 7, "method": "getProgramAccounts", "params": ["Program1"]})
+    }
+
+    #[tokio::test]
+    async fn test_large_response_is_streamed() {
+        let server = streaming_server().start().await;
+        let accounts: Vec<Value> = (0..100).map(|i| json!({"pubkey": format!("Account{}", i)})).collect();
+        Mock::given(method("POST"))
+            .respond_with(ResponseTemplate::new(200).set_body_json(json!({"jsonrpc": "2.0", "id": 7, "result": accounts})))
+            .mount(server.endpoint_mock("primary"))
+            .await;
+
+        let response = server.rpc(payload()).await;
+        assert_eq!(response.headers()[X_ACCEL_BUFFERING], "no");
+        assert_eq!(response.headers()[reqwest::header::CONTENT_TYPE], "application/json");
+        let body: Value = response.json().await.unwrap();
+        assert_eq!(body["result"].as_array().unwrap().len(), 100);
+    }
+
+    #[tokio::test]
+    async fn test_streamed_call_holds_connection_and_forwards_headers() {
+        let server = streaming_server()
+            .with_endpoint_config("primary", |endpoint| endpoint.forward_headers = vec!["X-Solana-Commitment".to_string()])
+            .start()
+            .await;
+        let accounts: Vec<Value> = (0..100).map(|i| json!({"pubkey": format!("Account{}", i)})).collect();
+        Mock::given(method("POST"))
+            .and(wiremock::matchers::header("x-solana-commitment", "confirmed"))
+            .respond_with(ResponseTemplate::new(200).set_body_json(json!({"jsonrpc": "2.0", "id": 7, "result": accounts})))
+            .mount(server.endpoint_mock("primary"))
+            .await;
+        let mut headers = HeaderMap::new();
+        headers.insert("x-solana-commitment", "confirmed".parse().unwrap());
+        headers.insert("x-vendor-key", "secret".parse().unwrap());
+        let active_connections = || async {
+            server.state.endpoint_manager.get_stats().await["endpoints"][0]["connection_pool"]["active_connections"].clone()
+        };
+
+        let response = server.state.rpc_router
+            .route_streaming_request(&payload(), None, Some(&headers))
+            .await
+            .unwrap()
+            .unwrap();
+        assert_eq!(response.status(), StatusCode::OK);
+        assert_eq!(response.headers()[X_ACCEL_BUFFERING], "no");
+        // The slot is held until the body has been sent
+        assert_eq!(active_connections().await, 1);
+        let body = axum::body::to_bytes(response.into_body(), usize::MAX).await.unwrap();
+        let body: Value = serde_json::from_slice(&body).unwrap();
+        assert_eq!(body["result"].as_array().unwrap().len(), 100);
+        assert_eq!(active_connections().await, 0);
+
+        let requests = server.endpoint_mock("primary").received_requests().await.unwrap();
+        assert!(requests[0].headers.get("x-vendor-key").is_none());
+    }
+
+    #[tokio::test]
+    async fn test_upstream_closing_before_any_bytes_sends_json_error() {
+        let server = streaming_server().start().await;
+        let upstream = truncating_upstream("").await;
+
+        let response = server.state.rpc_router
+            .stream_response(reqwest::Client::new().post(&upstream), &upstream, &payload(), None, None)
+            .await
+            .unwrap();
+        assert_eq!(response.headers()[X_ACCEL_BUFFERING], "no");
+        assert_eq!(response.headers()[CONTENT_TYPE], "application/json; charset=utf-8");
+
+        let body = axum::body::to_bytes(response.into_body(), usize::MAX).await.unwrap();
+        let body: Value = serde_json::from_slice(&body).unwrap();
+        assert_eq!(body["id"], 7);
+        assert_eq!(body["error"]["code"], -32603);
+    }
+
+    #[tokio::test]
+    async fn test_upstream_closing_mid_stream_aborts_response() {
+        let server = streaming_server().start().await;
+        let upstream = truncating_upstream(r#"{"jsonrpc":"2.0","id":7,"result":["#).await;
+
+        let response = server.state.rpc_router
+            .stream_response(reqwest::Client::new().post(&upstream), &upstream, &payload(), None, None)
+            .await
+            .unwrap();
+
+        let mut body = response.into_body().into_data_stream();
+        let first = body.next().await.unwrap().unwrap();
+        assert!(first.starts_with(br#"{"jsonrpc""#));
+        assert!(body.next().await.unwrap().is_err());
+    }
+
+    #[tokio::test]
+    async fn test_fallback_served_when_all_endpoints_unhealthy() {
+        let server = TestServerBuilder::new()
+            .with_endpoint("primary")
+            .with_endpoint("secondary")
+            .with_config(|config| {
+                config.fallback_responses.insert("getHealth".to_string(), json!("ok"));
+            })
+            .start()
+            .await;
+        for endpoint in server.state.endpoint_manager.get_endpoint_info().await {
+            server.state.endpoint_manager
+                .update_endpoint_status(endpoint.id, crate::types::EndpointStatus::Unhealthy)
+                .await;
+        }
+        let served = || async {
+            server.state.metrics_service.get_metrics().await["endpoints"]["fallback_responses_served"]["getHealth"]
+                .as_u64()
+                .unwrap_or(0)
+        };
+        let before = served().await;
+
+        let body: Value = server.rpc(json!({"jsonrpc": "2.0", "id": 42, "method": "getHealth"})).await
+            .json().await.unwrap();
+        assert_eq!(body["id"], 42);
+        assert_eq!(body["result"], "ok");
+        assert_eq!(body["_fallback"], true);
+        assert!(body["_fallback_age_seconds"].is_u64());
+        assert_eq!(served().await, before + 1);
+
+        // Methods without a fallback still fail
+        let response = server.rpc(json!({"jsonrpc": "2.0", "id": 43, "method": "getSlot"})).await;
+        assert_eq!(response.status(), reqwest::StatusCode::SERVICE_UNAVAILABLE);
+    }
+
+    async fn sticky_server() -> crate::test_server::TestServer {
+        let server = TestServerBuilder::new()
+            .with_endpoint("alpha")
+            .with_endpoint("beta")
+            .with_endpoint("gamma")
+            .with_config(|config| {
+                // Without pinning, round robin would spread consecutive calls over every endpoint
+                config.load_balancing_strategy = crate::types::LoadBalancingStrategy::RoundRobin;
+                config.sticky_sessions.enabled = true;
+            })
+            .start()
+            .await;
+        for name in ["alpha", "beta", "gamma"] {
+            Mock::given(method("POST"))
+                .respond_with(ResponseTemplate::new(200).set_body_json(json!({"jsonrpc": "2.0", "id": 1, "result": name})))
+                .mount(server.endpoint_mock(name))
+                .await;
+        }
+        server
+    }
+
+    async fn served_by(server: &crate::test_server::TestServer, header: (&str, &str)) -> String {
+        let body: Value = server.client.post(&server.base_url)
+            .header(header.0, header.1)
+            .json(&json!({"jsonrpc": "2.0", "id": 1, "method": "getSlot"}))
+            .send().await.unwrap()
+            .json().await.unwrap();
+        body["result"].as_str().unwrap().to_string()
+    }
+
+    #[tokio::test]
+    async fn test_sticky_session_stays_on_one_endpoint() {
+        let server = sticky_server().await;
+
+        for header in [(X_SESSION_ID, "wallet-1"), ("x-forwarded-for", "203.0.113.9, 10.0.0.1")] {
+            let pinned = served_by(&server, header).await;
+            for _ in 0..5 {
+                assert_eq!(served_by(&server, header).await, pinned, "session {:?} moved", header);
+            }
+        }
+
+        // Once the pinned endpoint goes unhealthy the session moves, and sticks to its new endpoint
+        let pinned = served_by(&server, (X_SESSION_ID, "wallet-2")).await;
+        let pinned_id = server.state.endpoint_manager.get_endpoint_info().await
+            .into_iter()
+            .find(|endpoint| endpoint.name == pinned)
+            .unwrap()
+            .id;
+        server.state.endpoint_manager.update_endpoint_status(pinned_id, crate::types::EndpointStatus::Unhealthy).await;
+        let moved = served_by(&server, (X_SESSION_ID, "wallet-2")).await;
+        assert_ne!(moved, pinned);
+        for _ in 0..3 {
+            assert_eq!(served_by(&server, (X_SESSION_ID, "wallet-2")).await, moved);
+        }
+    }
+
+    #[tokio::test]
+    async fn test_sticky_session_expires() {
+        let server = sticky_server().await;
+        let manager = &server.state.endpoint_manager;
+        let sticky = StickySessionRouter::new(Duration::from_millis(50));
+
+        let (first, _) = sticky.select_endpoint("wallet", "getSlot", &[], manager).await.unwrap();
+        let (second, _) = sticky.select_endpoint("wallet", "getSlot", &[], manager).await.unwrap();
+        assert_eq!(first, second);
+
+        // Round robin hands out the next endpoint, so a fresh pick lands elsewhere
+        tokio::time::sleep(Duration::from_millis(80)).await;
+        let (expired, _) = sticky.select_endpoint("wallet", "getSlot", &[], manager).await.unwrap();
+        assert_ne!(expired, first);
+        assert_eq!(sticky.sessions.get("wallet").unwrap().0, expired);
+    }
+
+    #[tokio::test]
+    async fn test_upstream_rpc_error_forwarded_unchanged() {
+        let server = TestServerBuilder::new().start().await;
+        let upstream_error = json!({
+            "code": -32002,
+            "message": "Transaction simulation failed: Blockhash not found",
+            "data": {"accounts": null, "err": "BlockhashNotFound", "logs": [], "unitsConsumed": 0}
+        });
+        Mock::given(method("POST"))
+            .respond_with(ResponseTemplate::new(200)
+                .set_body_json(json!({"jsonrpc": "2.0", "id": 42, "error": upstream_error})))
+            .mount(server.endpoint_mock("primary"))
+            .await;
+
+        let response = server.rpc(json!({"jsonrpc": "2.0", "id": 42, "method": "sendTransaction", "params": ["AQAB"]})).await;
+        assert_eq!(response.status(), reqwest::StatusCode::OK);
+        let body: Value = response.json().await.unwrap();
+        assert_eq!(body, json!({"jsonrpc": "2.0", "id": 42, "error": upstream_error}));
+        // The node answered, so the error isn't retried on another attempt
+        assert_eq!(server.endpoint_mock("primary").received_requests().await.unwrap().len(), 1);
+    }
+
+    // Balances echo the address length; the address "missing" gets an upstream error instead
+    async fn mount_balances(server: &crate::test_server::TestServer) {
+        Mock::given(method("POST"))
+            .respond_with(|request: &wiremock::Request| {
+                let body: Value = request.body_json().unwrap();
+                let address = body["params"][0].as_str().unwrap_or_default();
+                let reply = if address == "missing" {
+                    json!({"jsonrpc": "2.0", "id": body["id"], "error": {"code": -32602, "message": "Invalid param"}})
+                } else {
+                    json!({"jsonrpc": "2.0", "id": body["id"], "result": {"value": address.len()}})
+                };
+                ResponseTemplate::new(200).set_body_json(reply)
+            })
+            .mount(server.endpoint_mock("primary"))
+            .await;
+    }
+
+    fn multi_call(addresses: &[&str]) -> Value {
+        let calls: Vec<Value> = addresses.iter()
+            .map(|address| json!({"method": "getBalance", "params": [address]}))
+            .collect();
+        json!({"jsonrpc": "2.0", "id": 3, "method": MULTI_CALL_METHOD, "params": calls})
+    }
+
+    #[tokio::test]
+    async fn test_multi_call_keeps_order_around_failed_call() {
+        let server = TestServerBuilder::new().start().await;
+        mount_balances(&server).await;
+
+        let response = server.rpc(multi_call(&["a", "missing", "abc"])).await;
+        assert_eq!(response.status(), reqwest::StatusCode::OK);
+        let body: Value = response.json().await.unwrap();
+        assert_eq!(body["id"], 3);
+        assert_eq!(body["result"], json!([
+            {"result": {"value": 1}},
+            {"error": {"code": -32602, "message": "Invalid param"}},
+            {"result": {"value": 3}},
+        ]));
+        // getBalance normally goes through consensus, which multiCall entries skip
+        assert_eq!(server.endpoint_mock("primary").received_requests().await.unwrap().len(), 3);
+    }
+
+    #[tokio::test]
+    async fn test_multi_call_entries_cached_individually() {
+        let server = TestServerBuilder::new().with_config(|config| config.cache.enabled = true).start().await;
+        mount_balances(&server).await;
+
+        server.rpc(multi_call(&["a", "ab"])).await;
+        let body: Value = server.rpc(multi_call(&["ab", "abc"])).await.json().await.unwrap();
+        assert_eq!(body["result"], json!([{"result": {"value": 2}}, {"result": {"value": 3}}]));
+        assert_eq!(server.endpoint_mock("primary").received_requests().await.unwrap().len(), 3);
+    }
+
+    #[tokio::test]
+    async fn test_multi_call_rejects_oversized_and_malformed_calls() {
+        let server = TestServerBuilder::new().start().await;
+        let router = &server.state.rpc_router;
+
+        let addresses = vec!["a"; MAX_BATCH_SIZE + 1];
+        assert!(matches!(router.route_request(multi_call(&addresses), None, None, None).await, Err(AppError::InvalidRpcRequest(_))));
+        assert!(matches!(router.route_request(multi_call(&[]), None, None, None).await, Err(AppError::InvalidRpcRequest(_))));
+
+        let nested = json!({"jsonrpc": "2.0", "id": 1, "method": MULTI_CALL_METHOD, "params": [{"method": MULTI_CALL_METHOD}]});
+        assert!(matches!(router.route_request(nested, None, None, None).await, Err(AppError::InvalidRpcRequest(_))));
+        assert!(server.endpoint_mock("primary").received_requests().await.unwrap().is_empty());
+    }
+
+    #[test]
+    fn test_response_size_median_over_recent_window() {
+        let tracker = ResponseSizeTracker::default();
+        assert_eq!(tracker.median("getBlock"), None);
+
+        for size in [10, 5000, 20] {
+            tracker.record("getBlock", size);
+        }
+        assert_eq!(tracker.median("getBlock"), Some(20));
+
+        // Only the most recent responses count
+        for _ in 0..RESPONSE_SIZE_WINDOW {
+            tracker.record("getBlock", 5000);
+        }
+        assert_eq!(tracker.median("getBlock"), Some(5000));
+        assert_eq!(tracker.median("getSlot"), None);
+    }
+
+    #[tokio::test]
+    async fn test_methods_with_oversized_responses_are_refused() {
+        let server = TestServerBuilder::new()
+            .with_config(|config| config.max_response_size_bytes = Some(256))
+            .start()
+            .await;
+        let blocks = json!({"jsonrpc": "2.0", "id": 1, "method": "getBlocks", "params": [1]});
+        let slot = json!({"jsonrpc": "2.0", "id": 1, "method": "getSlot"});
+        Mock::given(method("POST"))
+            .and(wiremock::matchers::body_partial_json(json!({"method": "getBlocks"})))
+            .respond_with(rpc_result(json!((0..200).collect::<Vec<u64>>())))
+            .mount(server.endpoint_mock("primary"))
+            .await;
+        Mock::given(method("POST"))
+            .respond_with(rpc_result(json!(42)))
+            .mount(server.endpoint_mock("primary"))
+            .await;
+        let router = &server.state.rpc_router;
+
+        let refused = router.route_request(blocks, None, None, None).await;
+        assert!(matches!(refused, Err(AppError::EndpointError(ref message)) if message == "response too large"));
+        // The limit is per method and retrying elsewhere wouldn't help, so there's one upstream call
+        assert_eq!(server.endpoint_mock("primary").received_requests().await.unwrap().len(), 1);
+        assert_eq!(router.route_request(slot, None, None, None).await.unwrap()["result"], 42);
+
+        let exposition = server.state.metrics_service.get_prometheus_metrics().await;
+        assert!(exposition.contains("multi_rpc_response_size_bytes_bucket"));
+    }
+
+    #[tokio::test]
+    async fn test_only_listed_client_headers_reach_upstream() {
+        let server = TestServerBuilder::new()
+            .with_endpoint_config("primary", |endpoint| endpoint.forward_headers = vec!["X-Solana-Commitment".to_string()])
+            .start()
+            .await;
+        Mock::given(method("POST"))
+            .respond_with(rpc_result(json!(42)))
+            .mount(server.endpoint_mock("primary"))
+            .await;
+
+        for payload in [
+            json!({"jsonrpc": "2.0", "id": 1, "method": "getSlot"}),
+            json!([{"jsonrpc": "2.0", "id": 2, "method": "getBlockHeight"}]),
+        ] {
+            let response = server.client.post(&server.base_url)
+                .header("x-solana-commitment", "confirmed")
+                .header("x-vendor-key", "secret")
+                .json(&payload)
+                .send()
+                .await
+                .unwrap();
+            assert!(response.status().is_success());
+        }
+
+        let requests = server.endpoint_mock("primary").received_requests().await.unwrap();
+        assert_eq!(requests.len(), 2);
+        for request in &requests {
+            assert_eq!(request.headers.get("x-solana-commitment").unwrap(), "confirmed");
+            assert!(request.headers.get("x-vendor-key").is_none());
+        }
+    }
+
+    #[tokio::test]
+    async fn test_consensus_calls_forward_listed_client_headers() {
+        let forward = |endpoint: &mut crate::config::EndpointConfig| endpoint.forward_headers = vec!["X-Solana-Commitment".to_string()];
+        // With one endpoint consensus falls back to a standard request; with two it fans out
+        for names in [vec!["primary"], vec!["primary", "secondary"]] {
+            let mut builder = TestServerBuilder::new();
+            for name in &names {
+                builder = builder.with_endpoint_config(name, forward);
+            }
+            let server = builder.start().await;
+            for name in &names {
+                Mock::given(method("POST"))
+                    .respond_with(rpc_result(json!({"context": {"slot": 1}, "value": 42})))
+                    .mount(server.endpoint_mock(name))
+                    .await;
+            }
+
+            let response = server.client.post(&server.base_url)
+                .header("x-solana-commitment", "finalized")
+                .header("x-vendor-key", "secret")
+                .json(&json!({"jsonrpc": "2.0", "id": 1, "method": "getBalance", "params": ["Account1"]}))
+                .send()
+                .await
+                .unwrap();
+            assert!(response.status().is_success());
+
+            for name in &names {
+                let requests = server.endpoint_mock(name).received_requests().await.unwrap();
+                assert!(!requests.is_empty());
+                for request in &requests {
+                    assert_eq!(request.headers.get("x-solana-commitment").unwrap(), "finalized");
+                    assert!(request.headers.get("x-vendor-key").is_none());
+                }
+            }
+        }
+    }
+
+    #[tokio::test]
+    async fn test_client_headers_dropped_without_forward_headers() {
+        let server = TestServerBuilder::new().start().await;
+        Mock::given(method("POST"))
+            .respond_with(rpc_result(json!(42)))
+            .mount(server.endpoint_mock("primary"))
+            .await;
+
+        let mut headers = HeaderMap::new();
+        headers.insert("x-solana-commitment", "confirmed".parse().unwrap());
+        let slot = json!({"jsonrpc": "2.0", "id": 1, "method": "getSlot"});
+        let response = server.state.rpc_router.route_request(slot, None, None, Some(&headers)).await.unwrap();
+        assert_eq!(response["result"], 42);
+
+        let requests = server.endpoint_mock("primary").received_requests().await.unwrap();
+        assert!(requests[0].headers.get("x-solana-commitment").is_none());
+    }
+
+    #[tokio::test]
+    async fn test_upstream_rpc_error_parsed_into_variant() {
+        let error = AppError::from_rpc_error(&json!({"code": -32002, "message": "Blockhash not found"})).unwrap();
+        assert!(matches!(error, AppError::RpcError { code: -32002, ref message, data: None } if message == "Blockhash not found"));
+        assert!(AppError::from_rpc_error(&json!("not an error object")).is_none());
+    }
+}
@@ -1,4 +1,8 @@
-use crate::types::{RpcRequest, RpcResponse, RpcError};
+// The complete Solana method catalogue, including methods nothing routes specially yet
+#![allow(dead_code)]
+
+use crate::error::AppError;
+use crate::types::RpcRequest;
 use serde_json::Value;
 
 /// Solana RPC method categories for routing optimization
@@ -103,6 +107,42 @@ pub fn validate_rpc_request(request: &Value) -> Result<RpcRequest, String> {
     })
 }
 
+/// Check that an upstream response is a JSON-RPC 2.0 response to the request with `expected_id`
+pub fn validate_rpc_response(response: &Value, expected_id: &Value) -> Result<(), AppError> {
+    let malformed = |reason: &str| AppError::endpoint(&format!("Malformed JSON-RPC response: {}", reason));
+    let response = response.as_object()
+        .ok_or_else(|| malformed("not a JSON object"))?;
+    
+    match response.get("jsonrpc") {
+        Some(Value::String(version)) if version == "2.0" => {}
+        Some(_) => return Err(malformed("jsonrpc must be \"2.0\"")),
+        None => return Err(malformed("missing jsonrpc field")),
+    }
+    
+    let id = response.get("id").ok_or_else(|| malformed("missing id field"))?;
+    if !matches!(id, Value::Null | Value::Number(_) | Value::String(_)) {
+        return Err(malformed("id must be a string, number or null"));
+    }
+    if id != expected_id {
+        return Err(malformed(&format!("id {} does not match request id {}", id, expected_id)));
+    }
+    
+    match (response.get("result"), response.get("error")) {
+        (Some(_), None) => Ok(()),
+        (None, Some(error)) => {
+            let well_formed = error.get("code").is_some_and(|code| code.is_i64())
+                && error.get("message").is_some_and(Value::is_string);
+            if well_formed {
+                Ok(())
+            } else {
+                Err(malformed("error must be an object with an integer code and a string message"))
+            }
+        }
+        (Some(_), Some(_)) => Err(malformed("both result and error present")),
+        (None, None) => Err(malformed("neither result nor error present")),
+    }
+}
+
 /// Create an RPC error response
 pub fn create_error_response(id: Option<Value>, code: i32, message: &str, data: Option<Value>) -> Value {
     serde_json::json!({
@@ -196,4 +236,72 @@ mod tests {
         
         assert!(validate_rpc_request(&invalid_request).is_err());
     }
+
+    #[test]
+    fn test_validate_rpc_response() {
+        let id = json!(1);
+        assert!(validate_rpc_response(&json!({"jsonrpc": "2.0", "id": 1, "result": 42}), &id).is_ok());
+        assert!(validate_rpc_response(&json!({"jsonrpc": "2.0", "id": 1, "result": null}), &id).is_ok());
+        assert!(validate_rpc_response(
+            &json!({"jsonrpc": "2.0", "id": 1, "error": {"code": -32601, "message": "Method not found"}}),
+            &id,
+        ).is_ok());
+        assert!(validate_rpc_response(&json!({"jsonrpc": "2.0", "id": "abc", "result": 1}), &json!("abc")).is_ok());
+        assert!(validate_rpc_response(&json!({"jsonrpc": "2.0", "id": null, "result": 1}), &Value::Null).is_ok());
+    }
+    
+    #[test]
+    fn test_validate_rpc_response_missing_fields() {
+        let id = json!(1);
+        let missing = [
+            json!({"id": 1, "result": 42}),
+            json!({"jsonrpc": "2.0", "result": 42}),
+            json!({"jsonrpc": "2.0", "id": 1}),
+        ];
+        for response in missing {
+            let error = validate_rpc_response(&response, &id).unwrap_err();
+            assert!(matches!(error, AppError::EndpointError(_)), "{} accepted", response);
+        }
+    }
+    
+    #[test]
+    fn test_validate_rpc_response_type_mismatches() {
+        let id = json!(1);
+        let mismatched = [
+            json!("not an object"),
+            json!([{"jsonrpc": "2.0", "id": 1, "result": 42}]),
+            json!({"jsonrpc": 2.0, "id": 1, "result": 42}),
+            json!({"jsonrpc": "1.0", "id": 1, "result": 42}),
+            json!({"jsonrpc": "2.0", "id": {"nested": 1}, "result": 42}),
+            json!({"jsonrpc": "2.0", "id": [1], "result": 42}),
+            json!({"jsonrpc": "2.0", "id": 1, "error": "boom"}),
+            json!({"jsonrpc": "2.0", "id": 1, "error": {"code": "-32000", "message": "boom"}}),
+            json!({"jsonrpc": "2.0", "id": 1, "error": {"code": -32000}}),
+        ];
+        for response in mismatched {
+            assert!(validate_rpc_response(&response, &id).is_err(), "{} accepted", response);
+        }
+    }
+    
+    #[test]
+    fn test_validate_rpc_response_result_and_error_both_present() {
+        let response = json!({
+            "jsonrpc": "2.0",
+            "id": 1,
+            "result": 42,
+            "error": {"code": -32000, "message": "boom"}
+        });
+        let error = validate_rpc_response(&response, &json!(1)).unwrap_err();
+        assert!(error.to_string().contains("both result and error"));
+    }
+    
+    #[test]
+    fn test_validate_rpc_response_id_mismatch() {
+        let response = json!({"jsonrpc": "2.0", "id": 2, "result": 42});
+        let error = validate_rpc_response(&response, &json!(1)).unwrap_err();
+        assert!(error.to_string().contains("does not match request id"));
+        // Same value, different type
+        assert!(validate_rpc_response(&json!({"jsonrpc": "2.0", "id": "1", "result": 42}), &json!(1)).is_err());
+        assert!(validate_rpc_response(&json!({"jsonrpc": "2.0", "id": 1, "result": 42}), &Value::Null).is_err());
+    }
 }
\ No newline at end of file
@@ -0,0 +1,247 @@
+use crate::{
+    config::SchedulerPriorities,
+    metrics::MetricsService,
+    rpc::{get_method_category, RpcMethodCategory},
+};
+use std::{
+    collections::VecDeque,
+    sync::{Arc, Mutex},
+};
+use tokio::sync::oneshot;
+
+// Queue an upstream call waits in once the router is saturated
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum SchedulerClass {
+    Realtime,
+    Static,
+    Transaction,
+}
+
+impl SchedulerClass {
+    const ALL: [SchedulerClass; 3] = [Self::Realtime, Self::Static, Self::Transaction];
+
+    // Account, block and subscription calls share the realtime queue
+    pub fn for_method(method: &str) -> Self {
+        match get_method_category(method) {
+            RpcMethodCategory::Transaction => Self::Transaction,
+            RpcMethodCategory::Static => Self::Static,
+            _ => Self::Realtime,
+        }
+    }
+
+    pub fn label(&self) -> &'static str {
+        match self {
+            Self::Realtime => "realtime",
+            Self::Static => "static",
+            Self::Transaction => "transaction",
+        }
+    }
+
+    fn index(&self) -> usize {
+        *self as usize
+    }
+}
+
+struct SchedulerState {
+    available: usize,
+    queues: [VecDeque<oneshot::Sender<()>>; 3],
+}
+
+// Bounds in-flight upstream calls and hands freed slots to the highest-priority waiter first
+pub struct Scheduler {
+    state: Mutex<SchedulerState>,
+    // Class indices, highest configured priority first
+    dispatch_order: [usize; 3],
+    metrics_service: Arc<MetricsService>,
+}
+
+impl Scheduler {
+    pub fn new(config: &SchedulerPriorities, metrics_service: Arc<MetricsService>) -> Self {
+        let mut order = SchedulerClass::ALL;
+        // Stable sort, so equal priorities keep the realtime/static/transaction order
+        order.sort_by_key(|class| std::cmp::Reverse(config.priority(*class)));
+
+        Self {
+            state: Mutex::new(SchedulerState {
+                available: config.max_concurrent.max(1),
+                queues: Default::default(),
+            }),
+            dispatch_order: order.map(|class| class.index()),
+            metrics_service,
+        }
+    }
+
+    // Resolves once a slot is free; the slot is held until the permit is dropped
+    pub async fn acquire(&self, class: SchedulerClass) -> SchedulerPermit<'_> {
+        let rx = {
+            let mut state = self.state.lock().unwrap();
+            if state.available > 0 && state.queues.iter().all(VecDeque::is_empty) {
+                state.available -= 1;
+                return SchedulerPermit { scheduler: self };
+            }
+            let (tx, rx) = oneshot::channel();
+            state.queues[class.index()].push_back(tx);
+            self.record_queue_depth(&state, class);
+            rx
+        };
+
+        let mut waiter = Waiter { scheduler: self, rx };
+        // The sender is only dropped without a slot if the scheduler itself goes away
+        let _ = (&mut waiter.rx).await;
+        SchedulerPermit { scheduler: self }
+    }
+
+    // Read straight from the queues; the multi_rpc_scheduler_queue_depth gauge is shared between
+    // every scheduler in the test binary
+    #[cfg(test)]
+    pub fn queue_depth(&self, class: SchedulerClass) -> usize {
+        self.state.lock().unwrap().queues[class.index()].len()
+    }
+
+    fn release(&self) {
+        let mut state = self.state.lock().unwrap();
+        for index in self.dispatch_order {
+            while let Some(tx) = state.queues[index].pop_front() {
+                // A waiter that gave up has dropped its receiver; skip to the next one
+                if tx.send(()).is_ok() {
+                    self.record_queue_depth(&state, SchedulerClass::ALL[index]);
+                    return;
+                }
+            }
+            self.record_queue_depth(&state, SchedulerClass::ALL[index]);
+        }
+        state.available += 1;
+    }
+
+    fn record_queue_depth(&self, state: &SchedulerState, class: SchedulerClass) {
+        self.metrics_service.update_scheduler_queue_depth(class.label(), state.queues[class.index()].len());
+    }
+}
+
+pub struct SchedulerPermit<'a> {
+    scheduler: &'a Scheduler,
+}
+
+impl Drop for SchedulerPermit<'_> {
+    fn drop(&mut self) {
+        self.scheduler.release();
+    }
+}
+
+// Passes the slot on if the caller is cancelled after being woken but before taking it
+struct Waiter<'a> {
+    scheduler: &'a Scheduler,
+    rx: oneshot::Receiver<()>,
+}
+
+impl Drop for Waiter<'_> {
+    fn drop(&mut self) {
+        self.rx.close();
+        if self.rx.try_recv().is_ok() {
+            self.scheduler.release();
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_server::shared_metrics;
+    use std::time::Duration;
+
+    fn scheduler(max_concurrent: usize) -> Arc<Scheduler> {
+        let config = SchedulerPriorities {
+            max_concurrent,
+            ..SchedulerPriorities::default()
+        };
+        Arc::new(Scheduler::new(&config, shared_metrics()))
+    }
+
+    #[test]
+    fn classifies_methods_by_category() {
+        assert_eq!(SchedulerClass::for_method("sendTransaction"), SchedulerClass::Transaction);
+        assert_eq!(SchedulerClass::for_method("getSlot"), SchedulerClass::Realtime);
+        assert_eq!(SchedulerClass::for_method("getAccountInfo"), SchedulerClass::Realtime);
+        assert_eq!(SchedulerClass::for_method("getGenesisHash"), SchedulerClass::Static);
+    }
+
+    #[tokio::test]
+    async fn high_priority_requests_drain_first_when_saturated() {
+        let scheduler = scheduler(1);
+        let blocker = scheduler.acquire(SchedulerClass::Realtime).await;
+
+        let completed = Arc::new(Mutex::new(Vec::new()));
+        let mut tasks = Vec::new();
+        // Low-priority work is queued first so FIFO order would favour it
+        for class in [SchedulerClass::Transaction; 5].into_iter().chain([SchedulerClass::Realtime; 5]) {
+            let scheduler = scheduler.clone();
+            let completed = completed.clone();
+            tasks.push(tokio::spawn(async move {
+                let _permit = scheduler.acquire(class).await;
+                tokio::time::sleep(Duration::from_millis(5)).await;
+                completed.lock().unwrap().push(class);
+            }));
+            tokio::time::sleep(Duration::from_millis(5)).await;
+        }
+        assert_eq!(scheduler.queue_depth(SchedulerClass::Transaction), 5);
+        assert_eq!(scheduler.queue_depth(SchedulerClass::Realtime), 5);
+
+        drop(blocker);
+        for task in tasks {
+            task.await.unwrap();
+        }
+
+        let completed = completed.lock().unwrap().clone();
+        assert_eq!(completed[..5], [SchedulerClass::Realtime; 5]);
+        assert_eq!(completed[5..], [SchedulerClass::Transaction; 5]);
+    }
+
+    #[tokio::test]
+    async fn configured_priorities_change_dispatch_order() {
+        let config = SchedulerPriorities {
+            max_concurrent: 1,
+            realtime: 0,
+            transaction: 5,
+            ..SchedulerPriorities::default()
+        };
+        let scheduler = Arc::new(Scheduler::new(&config, shared_metrics()));
+        let blocker = scheduler.acquire(SchedulerClass::Static).await;
+
+        let realtime = tokio::spawn({
+            let scheduler = scheduler.clone();
+            async move { drop(scheduler.acquire(SchedulerClass::Realtime).await) }
+        });
+        tokio::time::sleep(Duration::from_millis(5)).await;
+        let transaction = {
+            let scheduler = scheduler.clone();
+            tokio::spawn(async move {
+                let permit = scheduler.acquire(SchedulerClass::Transaction).await;
+                // Realtime is still queued behind the transaction holding the slot
+                let waiting = scheduler.queue_depth(SchedulerClass::Realtime);
+                drop(permit);
+                waiting
+            })
+        };
+        tokio::time::sleep(Duration::from_millis(5)).await;
+
+        drop(blocker);
+        assert_eq!(transaction.await.unwrap(), 1);
+        realtime.await.unwrap();
+    }
+
+    #[tokio::test]
+    async fn cancelled_waiters_do_not_leak_slots() {
+        let scheduler = scheduler(1);
+        let blocker = scheduler.acquire(SchedulerClass::Realtime).await;
+
+        let cancelled = tokio::time::timeout(
+            Duration::from_millis(10),
+            scheduler.acquire(SchedulerClass::Realtime),
+        ).await;
+        assert!(cancelled.is_err());
+
+        drop(blocker);
+        let permit = tokio::time::timeout(Duration::from_millis(100), scheduler.acquire(SchedulerClass::Static)).await;
+        assert!(permit.is_ok());
+    }
+}
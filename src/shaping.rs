@@ -0,0 +1,195 @@
+use crate::AppState;
+use axum::{
+    body::{Body, Bytes},
+    extract::{Request, State},
+    middleware::Next,
+    response::Response,
+};
+use futures::StreamExt;
+use governor::{
+    clock::{Clock, DefaultClock},
+    state::{InMemoryState, NotKeyed},
+    Quota, RateLimiter,
+};
+use std::{
+    num::NonZeroU32,
+    sync::Arc,
+    time::{Duration, Instant},
+};
+use tracing::error;
+
+type ByteLimiter = RateLimiter<NotKeyed, InMemoryState, DefaultClock>;
+
+// Token bucket where one cell is one byte of response body
+#[derive(Debug)]
+pub struct EgressShaper {
+    limiter: ByteLimiter,
+    clock: DefaultClock,
+    burst: u32,
+}
+
+impl EgressShaper {
+    pub fn new(bytes_per_second: u64) -> Option<Self> {
+        let rate = NonZeroU32::new(u32::try_from(bytes_per_second).unwrap_or(u32::MAX))?;
+        // One second worth of bytes may go out in a single burst
+        let quota = Quota::per_second(rate).allow_burst(rate);
+
+        Some(Self {
+            limiter: RateLimiter::direct(quota),
+            clock: DefaultClock::default(),
+            burst: rate.get(),
+        })
+    }
+
+    // Waits until `bytes` worth of tokens are available and returns how long that took
+    pub async fn acquire(&self, bytes: usize) -> Duration {
+        let started = Instant::now();
+        let mut remaining = bytes;
+
+        // Bodies larger than the bucket are paid for in bucket-sized slices
+        while remaining > 0 {
+            let chunk = remaining.min(self.burst as usize) as u32;
+            let Some(cells) = NonZeroU32::new(chunk) else {
+                break;
+            };
+
+            loop {
+                match self.limiter.check_n(cells) {
+                    Ok(Ok(())) => break,
+                    Ok(Err(not_until)) => {
+                        tokio::time::sleep(not_until.wait_time_from(self.clock.now())).await;
+                    }
+                    Err(e) => {
+                        // Chunks never exceed the burst size, so this is unreachable in practice
+                        error!("Egress shaper capacity error: {}", e);
+                        return started.elapsed();
+                    }
+                }
+            }
+
+            remaining -= chunk as usize;
+        }
+
+        started.elapsed()
+    }
+}
+
+pub async fn egress_shaping_middleware(
+    State(state): State<Arc<AppState>>,
+    request: Request,
+    next: Next,
+) -> Response {
+    let response = next.run(request).await;
+
+    let Some(shaper) = state.egress_shaper.clone() else {
+        return response;
+    };
+    let metrics_service = state.metrics_service.clone();
+
+    // Bodies go out in bucket-sized slices as tokens come in, so nothing waits for the whole response
+    let (parts, body) = response.into_parts();
+    let burst = shaper.burst as usize;
+    let shaped = body.into_data_stream()
+        .flat_map(move |chunk| futures::stream::iter(slices(chunk, burst)))
+        .then(move |slice| {
+            let shaper = shaper.clone();
+            let metrics_service = metrics_service.clone();
+            async move {
+                if let Ok(bytes) = &slice {
+                    let waited = shaper.acquire(bytes.len()).await;
+                    metrics_service.record_egress_shaping(bytes.len(), waited);
+                }
+                slice
+            }
+        });
+
+    Response::from_parts(parts, Body::from_stream(shaped))
+}
+
+fn slices(chunk: Result<Bytes, axum::Error>, size: usize) -> Vec<Result<Bytes, axum::Error>> {
+    match chunk {
+        Ok(bytes) => (0..bytes.len())
+            .step_by(size)
+            .map(|start| Ok(bytes.slice(start..bytes.len().min(start + size))))
+            .collect(),
+        Err(e) => vec![Err(e)],
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_server::{rpc_result, TestServerBuilder};
+    use serde_json::json;
+    use wiremock::{matchers::method, Mock};
+
+    #[tokio::test]
+    async fn test_throughput_within_limit() {
+        let limit = 50_000u64;
+        let shaper = EgressShaper::new(limit).unwrap();
+
+        // Drain the initial burst so only the sustained rate is measured
+        shaper.acquire(limit as usize).await;
+
+        let started = Instant::now();
+        let total = 100_000usize;
+        for _ in 0..(total / 5_000) {
+            shaper.acquire(5_000).await;
+        }
+        let throughput = total as f64 / started.elapsed().as_secs_f64();
+
+        let deviation = (throughput - limit as f64).abs() / limit as f64;
+        assert!(deviation <= 0.10, "throughput {} deviates {:.1}% from {}", throughput, deviation * 100.0, limit);
+    }
+
+    #[tokio::test]
+    async fn test_bodies_larger_than_burst() {
+        let shaper = EgressShaper::new(10_000).unwrap();
+        let waited = shaper.acquire(25_000).await;
+
+        // 10k burst is free, the remaining 15k takes about 1.5s
+        assert!(waited >= Duration::from_millis(1_300));
+    }
+
+    #[tokio::test]
+    async fn test_large_responses_start_before_they_are_paid_for() {
+        let server = TestServerBuilder::new()
+            .with_endpoint("primary")
+            .with_config(|config| config.egress_rate_limit_bps = Some(20_000))
+            .start()
+            .await;
+        Mock::given(method("POST"))
+            .respond_with(rpc_result(json!("x".repeat(50_000))))
+            .mount(server.endpoint_mock("primary"))
+            .await;
+
+        // The first 20k are within the burst, the other 30k take about 1.5s
+        let started = Instant::now();
+        let mut response = server.rpc(json!({"jsonrpc": "2.0", "id": 1, "method": "getAccountInfo"})).await;
+        let first = response.chunk().await.unwrap().unwrap();
+        assert!(started.elapsed() < Duration::from_millis(500), "first bytes took {:?}", started.elapsed());
+        assert!(first.len() <= 20_000);
+
+        let mut received = first.len();
+        while let Some(chunk) = response.chunk().await.unwrap() {
+            received += chunk.len();
+        }
+        assert!(received > 50_000);
+        assert!(started.elapsed() >= Duration::from_millis(1_300));
+    }
+
+    #[test]
+    fn test_slices_never_exceed_the_burst() {
+        let sizes: Vec<usize> = slices(Ok(Bytes::from(vec![0u8; 25])), 10)
+            .into_iter()
+            .map(|slice| slice.unwrap().len())
+            .collect();
+        assert_eq!(sizes, [10, 10, 5]);
+        assert!(slices(Ok(Bytes::new()), 10).is_empty());
+    }
+
+    #[test]
+    fn test_zero_rate_disables_shaping() {
+        assert!(EgressShaper::new(0).is_none());
+    }
+}
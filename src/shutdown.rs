@@ -0,0 +1,236 @@
+use axum::{
+    extract::{Request, State},
+    middleware::Next,
+    response::Response,
+    Router,
+};
+use std::{
+    future::{Future, IntoFuture},
+    io,
+    sync::{
+        atomic::{AtomicUsize, Ordering},
+        Arc,
+    },
+    time::Duration,
+};
+use tokio::{net::TcpListener, sync::Notify};
+use tokio_util::sync::CancellationToken;
+use tracing::{error, info, warn};
+
+pub const DEFAULT_SHUTDOWN_TIMEOUT_SECS: u64 = 30;
+
+// Counts RPC requests that have started but not yet produced a response
+#[derive(Debug, Default)]
+pub struct InFlightRequests {
+    count: AtomicUsize,
+    idle: Notify,
+}
+
+impl InFlightRequests {
+    pub fn count(&self) -> usize {
+        self.count.load(Ordering::SeqCst)
+    }
+
+    // Resolves as soon as nothing is in flight
+    pub async fn drained(&self) {
+        loop {
+            let notified = self.idle.notified();
+            tokio::pin!(notified);
+            notified.as_mut().enable();
+            if self.count() == 0 {
+                return;
+            }
+            notified.await;
+        }
+    }
+
+    pub async fn middleware(
+        State(in_flight): State<Arc<InFlightRequests>>,
+        request: Request,
+        next: Next,
+    ) -> Response {
+        in_flight.count.fetch_add(1, Ordering::SeqCst);
+        // Decrements on drop, so a client that disconnects mid-request isn't counted forever
+        let _guard = InFlightGuard(&in_flight);
+        next.run(request).await
+    }
+}
+
+struct InFlightGuard<'a>(&'a InFlightRequests);
+
+impl Drop for InFlightGuard<'_> {
+    fn drop(&mut self) {
+        if self.0.count.fetch_sub(1, Ordering::SeqCst) == 1 {
+            self.0.idle.notify_waiters();
+        }
+    }
+}
+
+// SIGINT or, on unix, SIGTERM as sent by Kubernetes and systemd
+pub async fn shutdown_signal() {
+    let interrupt = async {
+        if let Err(e) = tokio::signal::ctrl_c().await {
+            error!("Failed to listen for SIGINT: {}", e);
+            std::future::pending::<()>().await;
+        }
+    };
+
+    #[cfg(unix)]
+    let terminate = async {
+        use tokio::signal::unix::{signal, SignalKind};
+        match signal(SignalKind::terminate()) {
+            Ok(mut terminate) => {
+                terminate.recv().await;
+            }
+            Err(e) => {
+                error!("Failed to listen for SIGTERM: {}", e);
+                std::future::pending::<()>().await;
+            }
+        }
+    };
+    #[cfg(not(unix))]
+    let terminate = std::future::pending::<()>();
+
+    tokio::select! {
+        _ = interrupt => info!("SIGINT received"),
+        _ = terminate => info!("SIGTERM received"),
+    }
+}
+
+// Serves until `signal` resolves, then stops accepting connections and gives in-flight requests
+// up to `timeout` to finish before the server is torn down regardless. Returns how many
+// requests were drained.
+pub async fn serve_with_draining(
+    listener: TcpListener,
+    app: Router,
+    in_flight: Arc<InFlightRequests>,
+    signal: impl Future<Output = ()> + Send + 'static,
+    timeout: Duration,
+) -> io::Result<usize> {
+    let stopping = CancellationToken::new();
+    let mut server = tokio::spawn(
+        axum::serve(listener, app)
+            .with_graceful_shutdown({
+                let stopping = stopping.clone();
+                async move {
+                    signal.await;
+                    stopping.cancel();
+                }
+            })
+            .into_future(),
+    );
+
+    tokio::select! {
+        result = &mut server => return result.map_err(io::Error::other)?.map(|_| 0),
+        _ = stopping.cancelled() => {}
+    }
+
+    let pending = in_flight.count();
+    info!("Draining {} in-flight requests (timeout {:?})", pending, timeout);
+    let deadline = tokio::time::Instant::now() + timeout;
+    if tokio::time::timeout_at(deadline, in_flight.drained()).await.is_err() {
+        let abandoned = in_flight.count();
+        warn!("{} requests still in flight after {:?}, shutting down anyway", abandoned, timeout);
+        server.abort();
+        return Ok(pending.saturating_sub(abandoned));
+    }
+    info!("Drained {} in-flight requests", pending);
+
+    // Idle keep-alive connections are closed by the server itself once it sees the signal
+    match tokio::time::timeout_at(deadline, &mut server).await {
+        Ok(result) => result.map_err(io::Error::other)??,
+        Err(_) => {
+            warn!("Connections still open after {:?}, closing them", timeout);
+            server.abort();
+        }
+    }
+    Ok(pending)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use axum::routing::post;
+    use tokio::sync::oneshot;
+
+    async fn slow_server(
+        request_duration: Duration,
+        timeout: Duration,
+    ) -> (String, Arc<InFlightRequests>, oneshot::Sender<()>, tokio::task::JoinHandle<io::Result<usize>>) {
+        let in_flight = Arc::new(InFlightRequests::default());
+        let app = Router::new()
+            .route("/", post(move || async move {
+                tokio::time::sleep(request_duration).await;
+                "done"
+            }))
+            .layer(axum::middleware::from_fn_with_state(in_flight.clone(), InFlightRequests::middleware));
+        let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let url = format!("http://{}/", listener.local_addr().unwrap());
+
+        let (stop, stopped) = oneshot::channel();
+        let server = tokio::spawn(serve_with_draining(
+            listener,
+            app,
+            in_flight.clone(),
+            async move {
+                let _ = stopped.await;
+            },
+            timeout,
+        ));
+        (url, in_flight, stop, server)
+    }
+
+    async fn wait_for_in_flight(in_flight: &InFlightRequests, expected: usize) {
+        for _ in 0..100 {
+            if in_flight.count() == expected {
+                return;
+            }
+            tokio::time::sleep(Duration::from_millis(5)).await;
+        }
+        panic!("expected {} requests in flight, found {}", expected, in_flight.count());
+    }
+
+    #[tokio::test]
+    async fn test_shutdown_waits_for_long_running_request() {
+        let (url, in_flight, stop, server) = slow_server(Duration::from_millis(300), Duration::from_secs(5)).await;
+
+        let request = tokio::spawn(reqwest::Client::new().post(&url).send());
+        wait_for_in_flight(&in_flight, 1).await;
+        stop.send(()).unwrap();
+
+        tokio::time::sleep(Duration::from_millis(100)).await;
+        assert!(!server.is_finished());
+
+        let response = request.await.unwrap().unwrap();
+        assert!(response.status().is_success());
+        assert_eq!(response.text().await.unwrap(), "done");
+        assert_eq!(server.await.unwrap().unwrap(), 1);
+        assert_eq!(in_flight.count(), 0);
+    }
+
+    #[tokio::test]
+    async fn test_shutdown_gives_up_after_timeout() {
+        let (url, in_flight, stop, server) = slow_server(Duration::from_secs(30), Duration::from_millis(100)).await;
+
+        let request = tokio::spawn(reqwest::Client::new().post(&url).send());
+        wait_for_in_flight(&in_flight, 1).await;
+        stop.send(()).unwrap();
+
+        let drained = tokio::time::timeout(Duration::from_secs(2), server).await
+            .expect("server kept waiting past the shutdown timeout");
+        assert_eq!(drained.unwrap().unwrap(), 0);
+        // Still running; in the server process, returning from main is what ends it
+        assert_eq!(in_flight.count(), 1);
+        request.abort();
+    }
+
+    #[tokio::test]
+    async fn test_idle_server_shuts_down_immediately() {
+        let (_, in_flight, stop, server) = slow_server(Duration::ZERO, Duration::from_secs(5)).await;
+        in_flight.drained().await;
+
+        stop.send(()).unwrap();
+        let drained = tokio::time::timeout(Duration::from_secs(1), server).await.unwrap();
+        assert_eq!(drained.unwrap().unwrap(), 0);
+    }
+}
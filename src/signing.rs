@@ -0,0 +1,396 @@
+use crate::{
+    auth::AuthContext,
+    config::{MessageSigningConfig, SignatureAlgorithm, SigningConfig},
+    error::AppError,
+};
+use dashmap::{mapref::entry::Entry, DashMap};
+use hmac::{Hmac, Mac};
+use serde_json::{Map, Value};
+use sha2::Sha256;
+use std::{
+    sync::Arc,
+    time::{SystemTime, UNIX_EPOCH},
+};
+
+// Expired nonces are swept once this many are remembered
+const NONCE_SWEEP_THRESHOLD: usize = 10_000;
+
+// Verifies per-message signatures from dApps that authenticate WebSocket
+// messages without HTTP-level auth. The signature covers the canonical JSON
+// of the message with the `signature` and `pubkey` fields removed, including
+// the `timestamp` and `nonce` that keep a captured message from being replayed.
+#[derive(Debug, Clone)]
+pub struct RequestSignatureVerifier {
+    algorithm: SignatureAlgorithm,
+    max_clock_skew_secs: u64,
+    // "<pubkey>:<nonce>" -> unix time after which the message is too old to replay anyway
+    seen_nonces: Arc<DashMap<String, u64>>,
+}
+
+impl RequestSignatureVerifier {
+    pub fn new(config: &MessageSigningConfig) -> Self {
+        Self {
+            algorithm: config.algorithm,
+            max_clock_skew_secs: config.max_clock_skew_secs,
+            seen_nonces: Arc::new(DashMap::new()),
+        }
+    }
+
+    // Returns the message without its signing fields, plus the auth context for the signer
+    pub fn verify(&self, message: &Value) -> Result<(Value, AuthContext), AppError> {
+        let mut fields = message.as_object()
+            .cloned()
+            .ok_or_else(|| AppError::validation("Signed message must be a JSON object"))?;
+
+        let signature = take_base58(&mut fields, "signature")?;
+        let pubkey_field = fields.get("pubkey").and_then(|p| p.as_str()).map(str::to_string);
+        let pubkey = take_base58(&mut fields, "pubkey")?;
+        let signed_bytes = canonical_json(&Value::Object(fields.clone()));
+
+        match self.algorithm {
+            SignatureAlgorithm::Ed25519 => verify_ed25519(&pubkey, &signature, signed_bytes.as_bytes())?,
+            SignatureAlgorithm::Secp256k1 => verify_secp256k1(&pubkey, &signature, signed_bytes.as_bytes())?,
+        }
+
+        // Only checked once the signature holds, so forged messages can't use up nonces
+        let timestamp = fields.remove("timestamp")
+            .and_then(|timestamp| timestamp.as_u64())
+            .ok_or_else(|| AppError::validation("Missing timestamp field"))?;
+        let nonce = fields.remove("nonce")
+            .and_then(|nonce| nonce.as_str().map(str::to_string))
+            .filter(|nonce| !nonce.is_empty())
+            .ok_or_else(|| AppError::validation("Missing nonce field"))?;
+        self.check_fresh(&bs58::encode(&pubkey).into_string(), &nonce, timestamp)?;
+        let payload = Value::Object(fields);
+
+        let context = AuthContext {
+            api_key: None,
+            user: pubkey_field,
+            scope: Vec::new(),
+            ip_address: None,
+            authenticated: true,
+            tenant_id: None,
+            baggage_user_id: None,
+        };
+        Ok((payload, context))
+    }
+
+    fn check_fresh(&self, pubkey: &str, nonce: &str, timestamp: u64) -> Result<(), AppError> {
+        let now = unix_now();
+        if timestamp.abs_diff(now) > self.max_clock_skew_secs {
+            return Err(AppError::validation(&format!("Message timestamp {} is outside the accepted window", timestamp)));
+        }
+
+        if self.seen_nonces.len() >= NONCE_SWEEP_THRESHOLD {
+            self.seen_nonces.retain(|_, expires_at| *expires_at >= now);
+        }
+        let expires_at = timestamp + self.max_clock_skew_secs;
+        match self.seen_nonces.entry(format!("{}:{}", pubkey, nonce)) {
+            Entry::Occupied(seen) if *seen.get() >= now => {
+                Err(AppError::validation("Nonce has already been used"))
+            }
+            Entry::Occupied(mut seen) => {
+                seen.insert(expires_at);
+                Ok(())
+            }
+            Entry::Vacant(entry) => {
+                entry.insert(expires_at);
+                Ok(())
+            }
+        }
+    }
+}
+
+fn unix_now() -> u64 {
+    SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map(|elapsed| elapsed.as_secs())
+        .unwrap_or_default()
+}
+
+// Signs outbound requests for endpoints behind an HMAC-authenticated gateway.
+// The signature covers "<timestamp>.<body>" so the gateway can bound replays to
+// its accepted clock window (30 seconds is expected).
+#[derive(Debug, Clone)]
+pub struct RequestSigner {
+    secret: Vec<u8>,
+    header_name: String,
+    timestamp_header: String,
+}
+
+impl RequestSigner {
+    pub fn new(config: &SigningConfig) -> Self {
+        Self {
+            secret: config.secret.as_bytes().to_vec(),
+            header_name: config.header_name.clone(),
+            timestamp_header: config.timestamp_header.clone(),
+        }
+    }
+
+    // Hex-encoded HMAC-SHA256 of the timestamp and body
+    pub fn signature(&self, timestamp: u64, body: &[u8]) -> String {
+        let mut mac = Hmac::<Sha256>::new_from_slice(&self.secret)
+            .expect("HMAC accepts keys of any length");
+        mac.update(timestamp.to_string().as_bytes());
+        mac.update(b".");
+        mac.update(body);
+        to_hex(&mac.finalize().into_bytes())
+    }
+
+    // Serializes the payload once so the signed bytes are exactly the bytes sent
+    pub fn sign(&self, builder: reqwest::RequestBuilder, payload: &Value) -> Result<reqwest::RequestBuilder, AppError> {
+        let body = serde_json::to_vec(payload)?;
+        let timestamp = unix_now();
+        Ok(builder
+            .header("Content-Type", "application/json")
+            .header(self.timestamp_header.as_str(), timestamp.to_string())
+            .header(self.header_name.as_str(), self.signature(timestamp, &body))
+            .body(body))
+    }
+}
+
+fn to_hex(bytes: &[u8]) -> String {
+    bytes.iter().map(|b| format!("{:02x}", b)).collect()
+}
+
+fn take_base58(fields: &mut Map<String, Value>, name: &str) -> Result<Vec<u8>, AppError> {
+    let encoded = fields.remove(name)
+        .and_then(|v| v.as_str().map(str::to_string))
+        .ok_or_else(|| AppError::validation(&format!("Missing {} field", name)))?;
+    bs58::decode(&encoded)
+        .into_vec()
+        .map_err(|e| AppError::validation(&format!("Invalid base58 in {}: {}", name, e)))
+}
+
+fn verify_ed25519(pubkey: &[u8], signature: &[u8], message: &[u8]) -> Result<(), AppError> {
+    use ed25519_dalek::{Signature, VerifyingKey};
+
+    let pubkey: [u8; 32] = pubkey.try_into()
+        .map_err(|_| AppError::validation("ed25519 public key must be 32 bytes"))?;
+    let key = VerifyingKey::from_bytes(&pubkey)
+        .map_err(|e| AppError::validation(&format!("Invalid ed25519 public key: {}", e)))?;
+    let signature = Signature::from_slice(signature)
+        .map_err(|e| AppError::validation(&format!("Invalid ed25519 signature: {}", e)))?;
+
+    key.verify_strict(message, &signature)
+        .map_err(|_| AppError::validation("ed25519 signature verification failed"))
+}
+
+fn verify_secp256k1(pubkey: &[u8], signature: &[u8], message: &[u8]) -> Result<(), AppError> {
+    use k256::ecdsa::{signature::Verifier, Signature, VerifyingKey};
+
+    let key = VerifyingKey::from_sec1_bytes(pubkey)
+        .map_err(|e| AppError::validation(&format!("Invalid secp256k1 public key: {}", e)))?;
+    let signature = Signature::from_slice(signature)
+        .map_err(|e| AppError::validation(&format!("Invalid secp256k1 signature: {}", e)))?;
+
+    key.verify(message, &signature)
+        .map_err(|_| AppError::validation("secp256k1 signature verification failed"))
+}
+
+// Serializes with object keys sorted at every level so signers and the proxy
+// agree on the exact bytes regardless of field order on the wire
+pub fn canonical_json(value: &Value) -> String {
+    match value {
+        Value::Object(fields) => {
+            let mut keys: Vec<&String> = fields.keys().collect();
+            keys.sort();
+            let entries: Vec<String> = keys.into_iter()
+                .map(|key| format!("{}:{}", Value::String(key.clone()), canonical_json(&fields[key])))
+                .collect();
+            format!("{{{}}}", entries.join(","))
+        }
+        Value::Array(items) => {
+            let items: Vec<String> = items.iter().map(canonical_json).collect();
+            format!("[{}]", items.join(","))
+        }
+        other => other.to_string(),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    fn request() -> Value {
+        json!({
+            "jsonrpc": "2.0",
+            "id": 1,
+            "method": "getBalance",
+            "params": ["Account1", {"commitment": "finalized"}]
+        })
+    }
+
+    // Freshness fields every signed message carries
+    fn stamped(mut message: Value) -> Value {
+        message["timestamp"] = json!(unix_now());
+        message["nonce"] = json!(uuid::Uuid::new_v4().to_string());
+        message
+    }
+
+    fn attach(mut message: Value, pubkey: &[u8], signature: &[u8]) -> Value {
+        message["pubkey"] = json!(bs58::encode(pubkey).into_string());
+        message["signature"] = json!(bs58::encode(signature).into_string());
+        message
+    }
+
+    fn verifier(algorithm: SignatureAlgorithm) -> RequestSignatureVerifier {
+        RequestSignatureVerifier::new(&MessageSigningConfig { enabled: true, algorithm, max_clock_skew_secs: 30 })
+    }
+
+    fn sign_ed25519(message: Value) -> Value {
+        use ed25519_dalek::{Signer, SigningKey};
+
+        let key = SigningKey::from_bytes(&[7u8; 32]);
+        let signature = key.sign(canonical_json(&message).as_bytes());
+        attach(message, key.verifying_key().as_bytes(), &signature.to_bytes())
+    }
+
+    #[test]
+    fn test_ed25519_signed_message() {
+        let verifier = verifier(SignatureAlgorithm::Ed25519);
+        let signed = sign_ed25519(stamped(request()));
+
+        let (payload, context) = verifier.verify(&signed).unwrap();
+        assert_eq!(payload, request());
+        assert!(context.authenticated);
+        assert_eq!(context.user.as_deref(), signed["pubkey"].as_str());
+
+        let mut tampered = signed.clone();
+        tampered["params"][0] = json!("Account2");
+        assert!(verifier.verify(&tampered).is_err());
+
+        // Wrong algorithm for the key type
+        assert!(self::verifier(SignatureAlgorithm::Secp256k1).verify(&signed).is_err());
+    }
+
+    #[test]
+    fn test_secp256k1_signed_message() {
+        use k256::ecdsa::{signature::Signer, Signature, SigningKey};
+
+        let verifier = verifier(SignatureAlgorithm::Secp256k1);
+        let key = SigningKey::from_slice(&[9u8; 32]).unwrap();
+        let message = stamped(request());
+        let signature: Signature = key.sign(canonical_json(&message).as_bytes());
+        let pubkey = key.verifying_key().to_encoded_point(true);
+        let signed = attach(message, pubkey.as_bytes(), &signature.to_bytes());
+
+        let (payload, context) = verifier.verify(&signed).unwrap();
+        assert_eq!(payload, request());
+        assert_eq!(context.user.as_deref(), signed["pubkey"].as_str());
+
+        let mut tampered = signed.clone();
+        tampered["method"] = json!("getSlot");
+        assert!(verifier.verify(&tampered).is_err());
+    }
+
+    #[test]
+    fn test_unsigned_message_rejected() {
+        assert!(verifier(SignatureAlgorithm::Ed25519).verify(&request()).is_err());
+    }
+
+    #[test]
+    fn test_replayed_and_stale_messages_rejected() {
+        let verifier = verifier(SignatureAlgorithm::Ed25519);
+        let signed = sign_ed25519(stamped(request()));
+        assert!(verifier.verify(&signed).is_ok());
+        let error = verifier.verify(&signed).unwrap_err();
+        assert!(error.to_string().contains("Nonce has already been used"), "{}", error);
+
+        // A fresh nonce from the same signer is still accepted
+        assert!(verifier.verify(&sign_ed25519(stamped(request()))).is_ok());
+
+        let mut stale = stamped(request());
+        stale["timestamp"] = json!(unix_now() - 31);
+        assert!(verifier.verify(&sign_ed25519(stale)).is_err());
+        let mut early = stamped(request());
+        early["timestamp"] = json!(unix_now() + 31);
+        assert!(verifier.verify(&sign_ed25519(early)).is_err());
+
+        // Both fields are required, and covered by the signature
+        let mut unstamped = stamped(request());
+        unstamped.as_object_mut().unwrap().remove("nonce");
+        assert!(verifier.verify(&sign_ed25519(unstamped)).is_err());
+        let mut renonced = sign_ed25519(stamped(request()));
+        renonced["nonce"] = json!("another-nonce");
+        assert!(verifier.verify(&renonced).is_err());
+    }
+
+    #[test]
+    fn test_canonical_json_sorts_keys() {
+        let a = json!({"b": 1, "a": {"d": [1, {"z": 0, "y": 1}], "c": "x"}});
+        assert_eq!(canonical_json(&a), r#"{"a":{"c":"x","d":[1,{"y":1,"z":0}]},"b":1}"#);
+    }
+
+    fn signer(secret: &str) -> RequestSigner {
+        RequestSigner::new(&SigningConfig {
+            secret: secret.to_string(),
+            header_name: "X-Signature".to_string(),
+            timestamp_header: "X-Timestamp".to_string(),
+        })
+    }
+
+    // HMAC-SHA256 built directly from the RFC 2104 definition
+    fn reference_hmac(key: &[u8], message: &[u8]) -> String {
+        use sha2::Digest;
+
+        let mut block = [0u8; 64];
+        if key.len() > 64 {
+            block[..32].copy_from_slice(&Sha256::digest(key));
+        } else {
+            block[..key.len()].copy_from_slice(key);
+        }
+        let ipad: Vec<u8> = block.iter().map(|b| b ^ 0x36).collect();
+        let opad: Vec<u8> = block.iter().map(|b| b ^ 0x5c).collect();
+        let inner = Sha256::new().chain_update(&ipad).chain_update(message).finalize();
+        let outer = Sha256::new().chain_update(&opad).chain_update(inner).finalize();
+        to_hex(&outer)
+    }
+
+    #[test]
+    fn test_hmac_signature_matches_reference() {
+        let body = serde_json::to_vec(&request()).unwrap();
+        let signature = signer("gateway-secret").signature(1_700_000_000, &body);
+
+        let mut message = b"1700000000.".to_vec();
+        message.extend_from_slice(&body);
+        assert_eq!(signature, reference_hmac(b"gateway-secret", &message));
+
+        let long_secret = "k".repeat(100);
+        assert_eq!(
+            signer(&long_secret).signature(1_700_000_000, &body),
+            reference_hmac(long_secret.as_bytes(), &message)
+        );
+    }
+
+    #[test]
+    fn test_hmac_rfc4231_vector() {
+        // RFC 4231 test case 2
+        let mut mac = Hmac::<Sha256>::new_from_slice(b"Jefe").unwrap();
+        mac.update(b"what do ya want for nothing?");
+        assert_eq!(
+            to_hex(&mac.finalize().into_bytes()),
+            "5bdcc146bf60754e6a042426089575c75a003f089d2739839dec58b964ec3843"
+        );
+        assert_eq!(
+            reference_hmac(b"Jefe", b"what do ya want for nothing?"),
+            "5bdcc146bf60754e6a042426089575c75a003f089d2739839dec58b964ec3843"
+        );
+    }
+
+    #[test]
+    fn test_signed_request_carries_headers() {
+        let signer = signer("gateway-secret");
+        let signed = signer
+            .sign(reqwest::Client::new().post("http://localhost/"), &request())
+            .unwrap()
+            .build()
+            .unwrap();
+
+        let timestamp: u64 = signed.headers()["X-Timestamp"].to_str().unwrap().parse().unwrap();
+        let body = signed.body().and_then(|b| b.as_bytes()).unwrap();
+        assert_eq!(signed.headers()["X-Signature"].to_str().unwrap(), signer.signature(timestamp, body));
+        assert_eq!(serde_json::from_slice::<Value>(body).unwrap(), request());
+    }
+}
@@ -0,0 +1,630 @@
+use crate::{
+    build_app_state, build_router,
+    config::{default_health_status_codes, Config, EndpointConfig},
+    metrics::MetricsService,
+    types::EndpointKind,
+    AppState,
+};
+use serde_json::{json, Value};
+use std::sync::{Arc, OnceLock};
+use tokio::net::TcpListener;
+use tokio_util::sync::CancellationToken;
+use wiremock::{MockServer, Request, Respond, ResponseTemplate};
+
+// Prometheus collectors are registered globally, so every test server shares one metrics service
+pub fn shared_metrics() -> Arc<MetricsService> {
+    static METRICS: OnceLock<Arc<MetricsService>> = OnceLock::new();
+    METRICS.get_or_init(|| Arc::new(MetricsService::new())).clone()
+}
+
+// Answers with `result` under the id of whichever request it gets, as a real node would
+pub fn rpc_result(result: Value) -> impl Respond {
+    move |request: &Request| {
+        let id = request.body_json::<Value>().map(|body| body["id"].clone()).unwrap_or(Value::Null);
+        ResponseTemplate::new(200).set_body_json(json!({"jsonrpc": "2.0", "id": id, "result": result}))
+    }
+}
+
+type ConfigureEndpoint = Box<dyn FnOnce(&mut EndpointConfig) + Send>;
+
+// Starts the full multi-rpc stack on an ephemeral port with wiremock servers as upstream endpoints
+pub struct TestServerBuilder {
+    config: Config,
+    endpoints: Vec<(String, ConfigureEndpoint)>,
+}
+
+pub struct TestServer {
+    pub client: reqwest::Client,
+    pub base_url: String,
+    pub mocks: Vec<MockServer>,
+    pub state: Arc<AppState>,
+    endpoint_names: Vec<String>,
+    shutdown: CancellationToken,
+}
+
+impl TestServerBuilder {
+    pub fn new() -> Self {
+        let mut config = Config::default();
+        config.endpoints.clear();
+        config.bind_address = "127.0.0.1:0".to_string();
+        config.auth.enabled = false;
+        config.rate_limiting.enabled = false;
+        config.cache.enabled = false;
+        // Unreachable on purpose: tests only exercise the in-process tiers
+        config.cache.redis_url = "redis://127.0.0.1:1".to_string();
+        config.geo.enabled = false;
+        config.discovery.enabled = false;
+
+        Self {
+            config,
+            endpoints: Vec::new(),
+        }
+    }
+
+    pub fn with_endpoint(self, name: &str) -> Self {
+        self.with_endpoint_config(name, |_| {})
+    }
+
+    // `configure` sees the endpoint's config once its mock server is up, so it can change
+    // anything but the URL
+    pub fn with_endpoint_config(
+        mut self,
+        name: &str,
+        configure: impl FnOnce(&mut EndpointConfig) + Send + 'static,
+    ) -> Self {
+        self.endpoints.push((name.to_string(), Box::new(configure)));
+        self
+    }
+
+    pub fn with_config(mut self, configure: impl FnOnce(&mut Config)) -> Self {
+        configure(&mut self.config);
+        self
+    }
+
+    pub async fn start(mut self) -> TestServer {
+        if self.endpoints.is_empty() {
+            self = self.with_endpoint("primary");
+        }
+
+        let mut mocks = Vec::with_capacity(self.endpoints.len());
+        let mut endpoint_names = Vec::with_capacity(self.endpoints.len());
+        for (index, (name, configure)) in self.endpoints.into_iter().enumerate() {
+            let mock = MockServer::start().await;
+            let mut endpoint = EndpointConfig {
+                url: mock.uri(),
+                name: name.clone(),
+                weight: 100,
+                priority: index as u8 + 1,
+                region: Some("global".to_string()),
+                latitude: None,
+                longitude: None,
+                features: vec!["full".to_string()],
+                max_connections: Some(50),
+                auth_token: None,
+                tls_cert_path: None,
+                tls_key_path: None,
+                tls_ca_path: None,
+                health_status_codes: default_health_status_codes(),
+                health_response_key: None,
+                kind: EndpointKind::Http,
+                signing: None,
+                tags: Vec::new(),
+                canary: false,
+                canary_weight: 0,
+                allowed_methods: None,
+                denied_methods: None,
+                forward_headers: Vec::new(),
+            };
+            configure(&mut endpoint);
+            self.config.endpoints.push(endpoint);
+            endpoint_names.push(name);
+            mocks.push(mock);
+        }
+
+        let shutdown = CancellationToken::new();
+        let state = build_app_state(&self.config, shared_metrics(), shutdown.clone())
+            .await
+            .expect("Failed to build test application state");
+        // Tests that need warmup run it themselves; the server is otherwise ready as started
+        state.cache_service.mark_initialized();
+        let app = build_router(state.clone());
+
+        let listener = TcpListener::bind(&self.config.bind_address).await
+            .expect("Failed to bind test server");
+        let base_url = format!("http://{}", listener.local_addr().unwrap());
+
+        tokio::spawn({
+            let shutdown = shutdown.clone();
+            async move {
+                axum::serve(listener, app)
+                    .with_graceful_shutdown(async move { shutdown.cancelled().await })
+                    .await
+                    .expect("Test server failed");
+            }
+        });
+
+        TestServer {
+            client: reqwest::Client::new(),
+            base_url,
+            mocks,
+            state,
+            endpoint_names,
+            shutdown,
+        }
+    }
+}
+
+impl TestServer {
+    pub fn endpoint_mock(&self, name: &str) -> &MockServer {
+        let index = self.endpoint_names.iter()
+            .position(|n| n == name)
+            .unwrap_or_else(|| panic!("No mock endpoint named {}", name));
+        &self.mocks[index]
+    }
+
+    pub fn url(&self, path: &str) -> String {
+        format!("{}{}", self.base_url, path)
+    }
+
+    pub async fn rpc(&self, payload: Value) -> reqwest::Response {
+        self.client.post(&self.base_url)
+            .json(&payload)
+            .send()
+            .await
+            .expect("Failed to send RPC request")
+    }
+}
+
+impl Drop for TestServer {
+    fn drop(&mut self) {
+        self.shutdown.cancel();
+        // A MockServer checks its expectations with a blocking wait on its own tokio lock when
+        // dropped. On a test's runtime thread that wait can find the task's coop budget spent and
+        // park for good, so the mocks go away on a thread of their own
+        let mocks = std::mem::take(&mut self.mocks);
+        if let Err(panic) = std::thread::spawn(move || drop(mocks)).join() {
+            if !std::thread::panicking() {
+                std::panic::resume_unwind(panic);
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use wiremock::{
+        matchers::{body_partial_json, method},
+        Mock,
+    };
+
+    async fn mount_method(mock: &MockServer, rpc_method: &str, response: impl Respond + 'static) {
+        Mock::given(method("POST"))
+            .and(body_partial_json(json!({"method": rpc_method})))
+            .respond_with(response)
+            .mount(mock)
+            .await;
+    }
+
+    #[tokio::test]
+    async fn test_health_reports_configured_endpoints() {
+        let server = TestServerBuilder::new()
+            .with_endpoint("primary")
+            .with_endpoint("secondary")
+            .start()
+            .await;
+
+        let health: Value = server.client.get(server.url("/health")).send().await.unwrap()
+            .json().await.unwrap();
+        assert_eq!(health["status"], "healthy");
+        assert_eq!(health["endpoints_configured"], 2);
+    }
+
+    #[tokio::test]
+    async fn test_single_request_is_forwarded_upstream() {
+        let server = TestServerBuilder::new().start().await;
+        Mock::given(method("POST"))
+            .and(body_partial_json(json!({"jsonrpc": "2.0", "method": "getSlot"})))
+            .respond_with(rpc_result(json!(123456)))
+            .expect(1)
+            .mount(server.endpoint_mock("primary"))
+            .await;
+
+        let response = server.rpc(json!({"jsonrpc": "2.0", "id": 1, "method": "getSlot"})).await;
+        assert!(response.status().is_success());
+        let body: Value = response.json().await.unwrap();
+        assert_eq!(body["result"], 123456);
+    }
+
+    #[tokio::test]
+    async fn test_params_are_passed_through() {
+        let server = TestServerBuilder::new().start().await;
+        Mock::given(method("POST"))
+            .and(body_partial_json(json!({"method": "getBlockHeight", "params": [{"commitment": "finalized"}]})))
+            .respond_with(rpc_result(json!(99)))
+            .expect(1)
+            .mount(server.endpoint_mock("primary"))
+            .await;
+
+        let body: Value = server.rpc(json!({
+            "jsonrpc": "2.0",
+            "id": 1,
+            "method": "getBlockHeight",
+            "params": [{"commitment": "finalized"}]
+        })).await.json().await.unwrap();
+        assert_eq!(body["result"], 99);
+    }
+
+    #[tokio::test]
+    async fn test_batch_request_preserves_order() {
+        let server = TestServerBuilder::new().start().await;
+        let mock = server.endpoint_mock("primary");
+        mount_method(mock, "getSlot", rpc_result(json!(1))).await;
+        mount_method(mock, "getBlockHeight", rpc_result(json!(2))).await;
+        mount_method(mock, "getHealth", rpc_result(json!("ok"))).await;
+
+        let body: Value = server.rpc(json!([
+            {"jsonrpc": "2.0", "id": 1, "method": "getSlot"},
+            {"jsonrpc": "2.0", "id": 2, "method": "getBlockHeight"},
+            {"jsonrpc": "2.0", "id": 3, "method": "getHealth"},
+        ])).await.json().await.unwrap();
+
+        let results: Vec<_> = body.as_array().unwrap().iter().map(|r| r["result"].clone()).collect();
+        assert_eq!(results, vec![json!(1), json!(2), json!("ok")]);
+    }
+
+    #[tokio::test]
+    async fn test_invalid_request_is_rejected_without_upstream_call() {
+        let server = TestServerBuilder::new().start().await;
+        Mock::given(method("POST"))
+            .respond_with(rpc_result(json!(null)))
+            .expect(0)
+            .mount(server.endpoint_mock("primary"))
+            .await;
+
+        let response = server.rpc(json!({"jsonrpc": "2.0", "id": 1})).await;
+        assert_eq!(response.status(), reqwest::StatusCode::BAD_REQUEST);
+    }
+
+    #[tokio::test]
+    async fn test_empty_batch_is_rejected() {
+        let server = TestServerBuilder::new().start().await;
+
+        let response = server.rpc(json!([])).await;
+        assert_eq!(response.status(), reqwest::StatusCode::BAD_REQUEST);
+    }
+
+    #[tokio::test]
+    async fn test_upstream_rpc_error_is_returned_to_client() {
+        let server = TestServerBuilder::new().start().await;
+        mount_method(
+            server.endpoint_mock("primary"),
+            "getFoo",
+            ResponseTemplate::new(200).set_body_json(json!({
+                "jsonrpc": "2.0",
+                "id": 1,
+                "error": {"code": -32601, "message": "Method not found"}
+            })),
+        ).await;
+
+        let body: Value = server.rpc(json!({"jsonrpc": "2.0", "id": 1, "method": "getFoo"})).await
+            .json().await.unwrap();
+        assert_eq!(body["error"]["code"], -32601);
+    }
+
+    #[tokio::test]
+    async fn test_failover_to_healthy_endpoint() {
+        let server = TestServerBuilder::new()
+            .with_endpoint("primary")
+            .with_endpoint("secondary")
+            .start()
+            .await;
+        mount_method(server.endpoint_mock("primary"), "getSlot", ResponseTemplate::new(500)).await;
+        mount_method(server.endpoint_mock("secondary"), "getSlot", rpc_result(json!(777))).await;
+
+        let response = server.rpc(json!({"jsonrpc": "2.0", "id": 1, "method": "getSlot"})).await;
+        assert!(response.status().is_success());
+        let body: Value = response.json().await.unwrap();
+        assert_eq!(body["result"], 777);
+    }
+
+    #[tokio::test]
+    async fn test_malformed_response_fails_over_to_another_endpoint() {
+        let server = TestServerBuilder::new()
+            .with_endpoint("primary")
+            .with_endpoint("secondary")
+            .start()
+            .await;
+        let malformed = ResponseTemplate::new(200).set_body_json(json!({
+            "jsonrpc": "2.0",
+            "id": 1,
+            "result": 1,
+            "error": {"code": -32000, "message": "boom"}
+        }));
+        mount_method(server.endpoint_mock("primary"), "getSlot", malformed).await;
+        mount_method(server.endpoint_mock("secondary"), "getSlot", rpc_result(json!(777))).await;
+
+        let response = server.rpc(json!({"jsonrpc": "2.0", "id": 1, "method": "getSlot"})).await;
+        assert!(response.status().is_success());
+        let body: Value = response.json().await.unwrap();
+        assert_eq!(body["result"], 777);
+
+        let stats = server.state.endpoint_manager.export_endpoint_stats().await;
+        let primary = &stats[&server.endpoint_mock("primary").uri()];
+        assert!(primary.total_requests > primary.successful_requests);
+    }
+
+    #[tokio::test]
+    async fn test_all_endpoints_failing_returns_gateway_error() {
+        let server = TestServerBuilder::new().start().await;
+        mount_method(server.endpoint_mock("primary"), "getSlot", ResponseTemplate::new(503)).await;
+
+        let response = server.rpc(json!({"jsonrpc": "2.0", "id": 1, "method": "getSlot"})).await;
+        assert!(response.status().is_server_error());
+    }
+
+    #[tokio::test]
+    async fn test_cacheable_response_is_served_from_cache() {
+        let server = TestServerBuilder::new()
+            .with_config(|config| config.cache.enabled = true)
+            .start()
+            .await;
+        Mock::given(method("POST"))
+            .and(body_partial_json(json!({"method": "getGenesisHash"})))
+            .respond_with(rpc_result(json!("genesis")))
+            .expect(1)
+            .mount(server.endpoint_mock("primary"))
+            .await;
+
+        for _ in 0..3 {
+            let body: Value = server.rpc(json!({"jsonrpc": "2.0", "id": 1, "method": "getGenesisHash"})).await
+                .json().await.unwrap();
+            assert_eq!(body["result"], "genesis");
+        }
+    }
+
+    #[tokio::test]
+    async fn test_matching_if_none_match_returns_304() {
+        let server = TestServerBuilder::new()
+            .with_config(|config| config.cache.enabled = true)
+            .start()
+            .await;
+        Mock::given(method("POST"))
+            .and(body_partial_json(json!({"method": "getGenesisHash"})))
+            .respond_with(rpc_result(json!("genesis")))
+            .expect(1)
+            .mount(server.endpoint_mock("primary"))
+            .await;
+        let request = json!({"jsonrpc": "2.0", "id": 1, "method": "getGenesisHash"});
+
+        // The miss that fills the cache has nothing to compare against
+        let first = server.rpc(request.clone()).await;
+        assert!(first.headers().get("etag").is_none());
+
+        let hit = server.rpc(request.clone()).await;
+        assert_eq!(hit.status(), reqwest::StatusCode::OK);
+        let etag = hit.headers().get("etag").unwrap().to_str().unwrap().to_string();
+        assert_eq!(hit.json::<Value>().await.unwrap()["result"], "genesis");
+
+        let not_modified = server.client.post(server.url("/"))
+            .header("If-None-Match", &etag)
+            .json(&request)
+            .send()
+            .await
+            .unwrap();
+        assert_eq!(not_modified.status(), reqwest::StatusCode::NOT_MODIFIED);
+        assert_eq!(not_modified.headers().get("etag").unwrap().to_str().unwrap(), etag);
+        assert!(not_modified.bytes().await.unwrap().is_empty());
+
+        let stale = server.client.post(server.url("/"))
+            .header("If-None-Match", "\"0000000000000000\"")
+            .json(&request)
+            .send()
+            .await
+            .unwrap();
+        assert_eq!(stale.status(), reqwest::StatusCode::OK);
+    }
+
+    #[tokio::test]
+    async fn test_send_transaction_never_gets_an_etag() {
+        let server = TestServerBuilder::new()
+            .with_config(|config| config.cache.enabled = true)
+            .start()
+            .await;
+        mount_method(server.endpoint_mock("primary"), "sendTransaction", rpc_result(json!("signature"))).await;
+        let request = json!({"jsonrpc": "2.0", "id": 1, "method": "sendTransaction", "params": ["AQAB"]});
+
+        for _ in 0..2 {
+            let response = server.client.post(server.url("/"))
+                .header("If-None-Match", "*")
+                .json(&request)
+                .send()
+                .await
+                .unwrap();
+            assert!(response.headers().get("etag").is_none());
+            assert_ne!(response.status(), reqwest::StatusCode::NOT_MODIFIED);
+        }
+    }
+
+    #[tokio::test]
+    async fn test_endpoint_stats_updated_after_request() {
+        let server = TestServerBuilder::new().start().await;
+        mount_method(server.endpoint_mock("primary"), "getSlot", rpc_result(json!(1))).await;
+
+        server.rpc(json!({"jsonrpc": "2.0", "id": 1, "method": "getSlot"})).await;
+
+        let stats = server.state.endpoint_manager.export_endpoint_stats().await;
+        let primary = &stats[&server.endpoint_mock("primary").uri()];
+        assert_eq!(primary.total_requests, 1);
+        assert_eq!(primary.successful_requests, 1);
+    }
+
+    #[tokio::test]
+    async fn test_endpoints_listing_uses_configured_names() {
+        let server = TestServerBuilder::new()
+            .with_endpoint("alpha")
+            .with_endpoint("beta")
+            .start()
+            .await;
+
+        let endpoints: Vec<Value> = server.client.get(server.url("/endpoints")).send().await.unwrap()
+            .json().await.unwrap();
+        let mut names: Vec<_> = endpoints.iter().map(|e| e["name"].as_str().unwrap().to_string()).collect();
+        names.sort();
+        assert_eq!(names, vec!["alpha", "beta"]);
+    }
+
+    #[tokio::test]
+    async fn test_prometheus_metrics_exposed() {
+        let server = TestServerBuilder::new().start().await;
+        mount_method(server.endpoint_mock("primary"), "getSlot", rpc_result(json!(1))).await;
+        server.rpc(json!({"jsonrpc": "2.0", "id": 1, "method": "getSlot"})).await;
+
+        let body = server.client.get(server.url("/metrics/prometheus")).send().await.unwrap()
+            .text().await.unwrap();
+        assert!(body.contains("multi_rpc_requests_total"));
+    }
+
+    #[tokio::test]
+    async fn test_prometheus_metrics_labelled_by_endpoint_and_method() {
+        let server = TestServerBuilder::new().start().await;
+        mount_method(server.endpoint_mock("primary"), "getBlockHeight", rpc_result(json!(250_000_000))).await;
+        mount_method(server.endpoint_mock("primary"), "getBlockTime", ResponseTemplate::new(500)).await;
+        server.rpc(json!({"jsonrpc": "2.0", "id": 1, "method": "getBlockHeight"})).await;
+        server.rpc(json!({"jsonrpc": "2.0", "id": 2, "method": "getBlockTime", "params": [1]})).await;
+
+        let body = server.client.get(server.url("/metrics/prometheus")).send().await.unwrap()
+            .text().await.unwrap();
+        assert!(body.contains(r#"multi_rpc_requests_total{endpoint="primary",method="getBlockHeight"}"#));
+        assert!(body.contains(r#"multi_rpc_request_duration_seconds_count{endpoint="primary",method="getBlockHeight"}"#));
+        assert!(body.contains(r#"multi_rpc_request_failures_total{endpoint="primary",method="getBlockTime"}"#));
+
+        let metrics: Value = server.client.get(server.url("/metrics")).send().await.unwrap()
+            .json().await.unwrap();
+        assert!(metrics["requests"]["by_endpoint"]["primary"].as_u64().unwrap() >= 2);
+    }
+
+    #[tokio::test]
+    async fn test_api_key_required_when_auth_enabled() {
+        let server = TestServerBuilder::new()
+            .with_config(|config| {
+                config.auth.enabled = true;
+                config.auth.api_keys.insert("test-key".to_string(), crate::config::ApiKeyConfig {
+                    name: "integration".to_string(),
+                    rate_limit: 1000,
+                    allowed_methods: None,
+                    allowed_ips: None,
+                    created_at: "2024-01-01T00:00:00Z".to_string(),
+                    expires_at: None,
+                });
+            })
+            .start()
+            .await;
+        mount_method(server.endpoint_mock("primary"), "getSlot", rpc_result(json!(5))).await;
+        let request = json!({"jsonrpc": "2.0", "id": 1, "method": "getSlot"});
+
+        let anonymous = server.rpc(request.clone()).await;
+        assert_eq!(anonymous.status(), reqwest::StatusCode::UNAUTHORIZED);
+
+        let authorized = server.client.post(&server.base_url)
+            .header("x-api-key", "test-key")
+            .json(&request)
+            .send()
+            .await
+            .unwrap();
+        assert!(authorized.status().is_success());
+        let body: Value = authorized.json().await.unwrap();
+        assert_eq!(body["result"], 5);
+    }
+
+    #[tokio::test]
+    async fn test_simulate_multi_is_authenticated_and_rate_limited() {
+        let server = TestServerBuilder::new()
+            .with_config(|config| {
+                config.auth.enabled = true;
+                config.auth.api_keys.insert("test-key".to_string(), crate::config::ApiKeyConfig {
+                    name: "integration".to_string(),
+                    rate_limit: 1000,
+                    allowed_methods: None,
+                    allowed_ips: None,
+                    created_at: "2024-01-01T00:00:00Z".to_string(),
+                    expires_at: None,
+                });
+                config.rate_limiting.enabled = true;
+                config.rate_limiting.per_method_limits.clear();
+                config.rate_limiting.default_rate = 1;
+                config.rate_limiting.default_burst = 1;
+            })
+            .start()
+            .await;
+        let simulate = |api_key: Option<&str>| {
+            let mut request = server.client.post(server.url("/rpc/simulate-multi"))
+                .json(&json!({"transaction_base64": "AQID"}));
+            if let Some(api_key) = api_key {
+                request = request.header("x-api-key", api_key);
+            }
+            request.send()
+        };
+
+        assert_eq!(simulate(None).await.unwrap().status(), reqwest::StatusCode::UNAUTHORIZED);
+        assert_ne!(simulate(Some("test-key")).await.unwrap().status(), reqwest::StatusCode::TOO_MANY_REQUESTS);
+        assert_eq!(simulate(Some("test-key")).await.unwrap().status(), reqwest::StatusCode::TOO_MANY_REQUESTS);
+    }
+
+    #[tokio::test]
+    async fn test_state_export_returns_archive() {
+        let server = TestServerBuilder::new().start().await;
+
+        let response = server.client.get(server.url("/admin/state/export")).send().await.unwrap();
+        assert!(response.status().is_success());
+        assert_eq!(response.headers()["content-type"], "application/gzip");
+
+        let archive = response.bytes().await.unwrap();
+        let snapshot = crate::import_export::StateSnapshot::from_archive(&archive).unwrap();
+        assert_eq!(snapshot.config.endpoints.len(), 1);
+        assert_eq!(snapshot.config.endpoints[0].url, server.endpoint_mock("primary").uri());
+    }
+
+    #[tokio::test]
+    async fn test_canary_answers_without_being_cached() {
+        let server = TestServerBuilder::new()
+            .with_endpoint("primary")
+            .with_endpoint_config("canary", |endpoint| {
+                endpoint.canary = true;
+                endpoint.canary_weight = 100;
+            })
+            .with_config(|config| config.cache.enabled = true)
+            .start()
+            .await;
+        mount_method(server.endpoint_mock("primary"), "getGenesisHash", rpc_result(json!("genesis"))).await;
+        mount_method(server.endpoint_mock("canary"), "getGenesisHash", rpc_result(json!("canary-genesis"))).await;
+        let request = json!({"jsonrpc": "2.0", "id": 1, "method": "getGenesisHash"});
+
+        let body: Value = server.rpc(request.clone()).await.json().await.unwrap();
+        assert_eq!(body["result"], "canary-genesis");
+        // The primary's answer is the one that was cached
+        let body: Value = server.rpc(request).await.json().await.unwrap();
+        assert_eq!(body["result"], "genesis");
+        assert_eq!(server.endpoint_mock("primary").received_requests().await.unwrap().len(), 1);
+    }
+
+    #[tokio::test]
+    async fn test_failing_canary_falls_back_to_primary() {
+        let server = TestServerBuilder::new()
+            .with_endpoint("primary")
+            .with_endpoint_config("canary", |endpoint| {
+                endpoint.canary = true;
+                endpoint.canary_weight = 100;
+            })
+            .start()
+            .await;
+        mount_method(server.endpoint_mock("primary"), "getSlot", rpc_result(json!(42))).await;
+        mount_method(server.endpoint_mock("canary"), "getSlot", ResponseTemplate::new(500)).await;
+
+        let response = server.rpc(json!({"jsonrpc": "2.0", "id": 1, "method": "getSlot"})).await;
+        assert!(response.status().is_success());
+        let body: Value = response.json().await.unwrap();
+        assert_eq!(body["result"], 42);
+        assert_eq!(server.endpoint_mock("canary").received_requests().await.unwrap().len(), 1);
+    }
+}
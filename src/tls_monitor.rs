@@ -0,0 +1,271 @@
+use crate::{
+    config::TlsMonitorConfig,
+    endpoints::EndpointManager,
+    error::AppError,
+    metrics::MetricsService,
+};
+use chrono::{DateTime, TimeZone, Utc};
+use rustls::{
+    client::{ServerCertVerified, ServerCertVerifier},
+    Certificate, ClientConfig, ServerName,
+};
+use serde_json::json;
+use std::{sync::Arc, time::{Duration, SystemTime}};
+use tokio::{net::TcpStream, time::interval};
+use tokio_rustls::TlsConnector;
+use tokio_util::sync::CancellationToken;
+use tracing::{debug, info, warn};
+use uuid::Uuid;
+
+const TLS_CONNECT_TIMEOUT: Duration = Duration::from_secs(10);
+
+// The handshake is only used to read the presented chain, so every certificate is
+// accepted. An expired or untrusted chain is exactly what we want to report on.
+struct AcceptAnyCertificate;
+
+impl ServerCertVerifier for AcceptAnyCertificate {
+    fn verify_server_cert(
+        &self,
+        _end_entity: &Certificate,
+        _intermediates: &[Certificate],
+        _server_name: &ServerName,
+        _scts: &mut dyn Iterator<Item = &[u8]>,
+        _ocsp_response: &[u8],
+        _now: SystemTime,
+    ) -> Result<ServerCertVerified, rustls::Error> {
+        Ok(ServerCertVerified::assertion())
+    }
+}
+
+// Periodically checks when the TLS certificates of HTTPS endpoints expire
+pub struct TlsCertificateMonitor {
+    endpoint_manager: Arc<EndpointManager>,
+    metrics_service: Arc<MetricsService>,
+    config: TlsMonitorConfig,
+    connector: TlsConnector,
+    webhook_client: reqwest::Client,
+}
+
+impl TlsCertificateMonitor {
+    pub fn new(
+        endpoint_manager: Arc<EndpointManager>,
+        metrics_service: Arc<MetricsService>,
+        config: TlsMonitorConfig,
+    ) -> Self {
+        let client_config = ClientConfig::builder()
+            .with_safe_defaults()
+            .with_custom_certificate_verifier(Arc::new(AcceptAnyCertificate))
+            .with_no_client_auth();
+
+        Self {
+            endpoint_manager,
+            metrics_service,
+            config,
+            connector: TlsConnector::from(Arc::new(client_config)),
+            webhook_client: reqwest::Client::new(),
+        }
+    }
+
+    pub async fn start(self: Arc<Self>, shutdown: CancellationToken) {
+        let check_interval = Duration::from_secs(self.config.tls_check_interval_hours.max(1) * 3600);
+        info!("Starting TLS certificate monitoring every {}h", check_interval.as_secs() / 3600);
+        let mut interval = interval(check_interval);
+
+        loop {
+            tokio::select! {
+                _ = shutdown.cancelled() => break,
+                _ = interval.tick() => {}
+            }
+
+            tokio::select! {
+                _ = shutdown.cancelled() => break,
+                _ = self.check_all() => {}
+            }
+        }
+
+        info!("TLS certificate monitoring stopped");
+    }
+
+    pub async fn check_all(&self) {
+        let targets: Vec<_> = self.endpoint_manager.get_endpoint_info().await
+            .into_iter()
+            .filter(|info| info.url.starts_with("https://"))
+            .map(|info| (info.id, info.name, info.url))
+            .collect();
+
+        let checks = targets.iter().map(|(id, name, url)| async move {
+            match Self::fetch_certificate_expiry(&self.connector, url).await {
+                Ok(expires_at) => self.record_expiry(*id, name, expires_at).await,
+                Err(e) => warn!("TLS certificate check failed for {}: {}", url, e),
+            }
+        });
+        futures::future::join_all(checks).await;
+    }
+
+    // Earliest not_after across the presented chain, since any expired link breaks it
+    async fn fetch_certificate_expiry(connector: &TlsConnector, url: &str) -> Result<DateTime<Utc>, AppError> {
+        let parsed = reqwest::Url::parse(url)
+            .map_err(|e| AppError::validation(&format!("Invalid endpoint URL: {}", e)))?;
+        let host = parsed.host_str()
+            .ok_or_else(|| AppError::validation("Endpoint URL has no host"))?;
+        let port = parsed.port_or_known_default().unwrap_or(443);
+        let server_name = ServerName::try_from(host)
+            .map_err(|e| AppError::validation(&format!("Invalid TLS server name {}: {}", host, e)))?;
+
+        let stream = tokio::time::timeout(TLS_CONNECT_TIMEOUT, async {
+            let tcp = TcpStream::connect((host, port)).await?;
+            connector.connect(server_name, tcp).await
+        }).await??;
+
+        let (_, connection) = stream.get_ref();
+        let chain = connection.peer_certificates()
+            .ok_or_else(|| AppError::endpoint("Endpoint presented no TLS certificates"))?;
+
+        chain.iter()
+            .map(|cert| {
+                let (_, parsed) = x509_parser::parse_x509_certificate(&cert.0)
+                    .map_err(|e| AppError::endpoint(&format!("Invalid TLS certificate: {}", e)))?;
+                let not_after = parsed.validity().not_after.timestamp();
+                Utc.timestamp_opt(not_after, 0)
+                    .single()
+                    .ok_or_else(|| AppError::endpoint("TLS certificate expiry out of range"))
+            })
+            .collect::<Result<Vec<_>, _>>()?
+            .into_iter()
+            .min()
+            .ok_or_else(|| AppError::endpoint("Endpoint presented no TLS certificates"))
+    }
+
+    async fn record_expiry(&self, endpoint_id: Uuid, name: &str, expires_at: DateTime<Utc>) {
+        let Some(days_remaining) = self.endpoint_manager.update_tls_certificate(endpoint_id, expires_at).await else {
+            return;
+        };
+        debug!("TLS certificate for {} expires at {} ({} days)", name, expires_at, days_remaining);
+
+        if days_remaining < self.config.tls_expiry_warning_days {
+            warn!("TLS certificate for endpoint {} expires in {} days ({})", name, days_remaining, expires_at);
+            self.metrics_service.record_tls_cert_expiry_warning();
+            self.alert(endpoint_id, name, expires_at, days_remaining);
+        }
+    }
+
+    fn alert(&self, endpoint_id: Uuid, name: &str, expires_at: DateTime<Utc>, days_remaining: i64) {
+        let Some(webhook_url) = self.config.webhook_url.clone() else {
+            return;
+        };
+        let client = self.webhook_client.clone();
+        let payload = json!({
+            "event": "TlsCertificateExpiring",
+            "data": {
+                "endpoint_id": endpoint_id,
+                "endpoint_name": name,
+                "expires_at": expires_at,
+                "days_remaining": days_remaining,
+            },
+        });
+
+        tokio::spawn(async move {
+            if let Err(e) = client.post(&webhook_url).json(&payload).send().await {
+                warn!("Failed to deliver TLS certificate webhook to {}: {}", webhook_url, e);
+            }
+        });
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{
+        circuit_breaker::CircuitBreakerRegistry,
+        config::{Config, EndpointConfig},
+        test_server::shared_metrics,
+    };
+    use chrono::Datelike;
+    use rcgen::{BasicConstraints, CertificateParams, DistinguishedName, IsCa};
+    use tokio::net::TcpListener;
+    use tokio_rustls::TlsAcceptor;
+    use wiremock::{matchers::method, Mock, MockServer, ResponseTemplate};
+
+    // Serves a leaf certificate for localhost, issued by a throwaway CA, that expires in three days
+    async fn short_lived_tls_server() -> u16 {
+        let mut ca_params = CertificateParams::new(Vec::new());
+        ca_params.distinguished_name = DistinguishedName::new();
+        ca_params.is_ca = IsCa::Ca(BasicConstraints::Unconstrained);
+        let ca = rcgen::Certificate::from_params(ca_params).unwrap();
+
+        let expiry = Utc::now() + chrono::Duration::days(3);
+        let mut leaf_params = CertificateParams::new(vec!["localhost".to_string()]);
+        leaf_params.not_after = rcgen::date_time_ymd(expiry.year(), expiry.month() as u8, expiry.day() as u8);
+        let leaf = rcgen::Certificate::from_params(leaf_params).unwrap();
+
+        let chain = vec![
+            Certificate(leaf.serialize_der_with_signer(&ca).unwrap()),
+            Certificate(ca.serialize_der().unwrap()),
+        ];
+        let server_config = rustls::ServerConfig::builder()
+            .with_safe_defaults()
+            .with_no_client_auth()
+            .with_single_cert(chain, rustls::PrivateKey(leaf.serialize_private_key_der()))
+            .unwrap();
+        let acceptor = TlsAcceptor::from(Arc::new(server_config));
+
+        let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let port = listener.local_addr().unwrap().port();
+        tokio::spawn(async move {
+            while let Ok((tcp, _)) = listener.accept().await {
+                let acceptor = acceptor.clone();
+                tokio::spawn(async move {
+                    let _ = acceptor.accept(tcp).await;
+                });
+            }
+        });
+        port
+    }
+
+    #[tokio::test]
+    async fn test_short_lived_certificate_raises_warning() {
+        let port = short_lived_tls_server().await;
+        let webhook = MockServer::start().await;
+        Mock::given(method("POST"))
+            .respond_with(ResponseTemplate::new(200))
+            .mount(&webhook)
+            .await;
+
+        let mut config = Config::default();
+        let template = config.endpoints[0].clone();
+        config.endpoints = vec![EndpointConfig {
+            url: format!("https://localhost:{}", port),
+            name: "short-lived".to_string(),
+            ..template
+        }];
+        let manager = Arc::new(
+            EndpointManager::new(config.endpoints.clone(), config, Arc::new(CircuitBreakerRegistry::default()))
+                .await
+                .unwrap(),
+        );
+        let monitor = TlsCertificateMonitor::new(manager.clone(), shared_metrics(), TlsMonitorConfig {
+            webhook_url: Some(webhook.uri()),
+            ..TlsMonitorConfig::default()
+        });
+
+        monitor.check_all().await;
+
+        let info = manager.get_endpoint_info().await.remove(0);
+        assert!(info.tls_cert_expires_at.is_some());
+        let days = info.tls_cert_days_remaining.unwrap();
+        assert!((2..=3).contains(&days), "unexpected days remaining: {}", days);
+
+        // The webhook is delivered from a spawned task
+        for _ in 0..50 {
+            if !webhook.received_requests().await.unwrap().is_empty() {
+                break;
+            }
+            tokio::time::sleep(Duration::from_millis(20)).await;
+        }
+        let requests = webhook.received_requests().await.unwrap();
+        assert_eq!(requests.len(), 1);
+        let payload: serde_json::Value = serde_json::from_slice(&requests[0].body).unwrap();
+        assert_eq!(payload["event"], "TlsCertificateExpiring");
+        assert_eq!(payload["data"]["endpoint_name"], "short-lived");
+    }
+}
@@ -0,0 +1,501 @@
+use crate::{
+    config::{ResponseTransformStep, TransformConfig},
+    error::AppError,
+};
+use serde_json::Value;
+
+#[derive(Debug, Clone, PartialEq)]
+enum PathSegment {
+    Key(String),
+    Index(usize),
+    // `[*]`, every element of an array
+    Each,
+}
+
+#[derive(Debug, Clone)]
+struct CompiledStep {
+    step: ResponseTransformStep,
+    path: Vec<PathSegment>,
+}
+
+// Sanitizes upstream responses before they are cached or returned, so internal
+// metadata never reaches clients or the cache
+#[derive(Debug, Clone, Default)]
+pub struct ResponseTransformPipeline {
+    steps: Vec<CompiledStep>,
+}
+
+impl ResponseTransformPipeline {
+    pub fn new(steps: Vec<ResponseTransformStep>) -> Result<Self, AppError> {
+        let steps = steps.into_iter()
+            .map(|step| {
+                let path = parse_path(step_path(&step))?;
+                Ok(CompiledStep { step, path })
+            })
+            .collect::<Result<Vec<_>, AppError>>()?;
+        Ok(Self { steps })
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.steps.is_empty()
+    }
+
+    // Runs every step in order and returns the names of the steps that changed the response.
+    // Steps whose path doesn't exist in the response are no-ops.
+    pub fn apply(&self, response: &mut Value) -> Vec<&'static str> {
+        self.steps.iter()
+            .filter(|compiled| apply_step(&compiled.step, &compiled.path, response))
+            .map(|compiled| step_name(&compiled.step))
+            .collect()
+    }
+}
+
+// Renamed and injected fields are held as (path to the parent object, field name)
+#[derive(Debug, Clone)]
+struct CompiledTransform {
+    strip: Vec<Vec<PathSegment>>,
+    rename: Vec<((Vec<PathSegment>, String), String)>,
+    inject: Vec<((Vec<PathSegment>, String), Value)>,
+}
+
+// Reshapes responses on their way to the client, after caching, so cached entries keep
+// their upstream shape. Each transform strips, then renames, then injects; transforms run
+// in declaration order, so later ones see the fields earlier ones renamed or injected.
+#[derive(Debug, Clone, Default)]
+pub struct ResponseTransformer {
+    transforms: Vec<CompiledTransform>,
+}
+
+impl ResponseTransformer {
+    pub fn new(transforms: &[TransformConfig]) -> Result<Self, AppError> {
+        let transforms = transforms.iter()
+            .map(|transform| {
+                // Map order is arbitrary; sorting keeps overlapping renames deterministic
+                let mut rename: Vec<_> = transform.rename_fields.iter().collect();
+                rename.sort();
+                let mut inject: Vec<_> = transform.inject_fields.iter().collect();
+                inject.sort_by(|a, b| a.0.cmp(b.0));
+
+                Ok(CompiledTransform {
+                    strip: transform.strip_fields.iter()
+                        .map(|path| parse_path(path))
+                        .collect::<Result<_, AppError>>()?,
+                    rename: rename.into_iter()
+                        .map(|(path, name)| Ok((parse_field_path(path)?, name.clone())))
+                        .collect::<Result<_, AppError>>()?,
+                    inject: inject.into_iter()
+                        .map(|(path, value)| Ok((parse_field_path(path)?, value.clone())))
+                        .collect::<Result<_, AppError>>()?,
+                })
+            })
+            .collect::<Result<Vec<_>, AppError>>()?;
+        Ok(Self { transforms })
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.transforms.is_empty()
+    }
+
+    // Batch responses are transformed element by element
+    pub fn apply(&self, response: &mut Value) {
+        if let Value::Array(responses) = response {
+            responses.iter_mut().for_each(|response| self.apply(response));
+            return;
+        }
+
+        for transform in &self.transforms {
+            for path in &transform.strip {
+                remove_at(path, response);
+            }
+            for ((parent, key), name) in &transform.rename {
+                visit(response, parent, &mut |value| match value {
+                    Value::Object(map) => match map.remove(key) {
+                        Some(field) => {
+                            map.insert(name.clone(), field);
+                            true
+                        }
+                        None => false,
+                    },
+                    _ => false,
+                });
+            }
+            for ((parent, key), injected) in &transform.inject {
+                visit(response, parent, &mut |value| match value {
+                    Value::Object(map) => {
+                        map.insert(key.clone(), injected.clone());
+                        true
+                    }
+                    _ => false,
+                });
+            }
+        }
+    }
+}
+
+// Renamed and injected paths have to name an object field, not an array element
+fn parse_field_path(path: &str) -> Result<(Vec<PathSegment>, String), AppError> {
+    let mut segments = parse_path(path)?;
+    match segments.pop() {
+        Some(PathSegment::Key(key)) => Ok((segments, key)),
+        _ => Err(AppError::config(&format!("Response transform path must end in a field name: {}", path))),
+    }
+}
+
+fn step_name(step: &ResponseTransformStep) -> &'static str {
+    match step {
+        ResponseTransformStep::MaskField { .. } => "mask_field",
+        ResponseTransformStep::RemoveField { .. } => "remove_field",
+        ResponseTransformStep::TruncateString { .. } => "truncate_string",
+    }
+}
+
+fn step_path(step: &ResponseTransformStep) -> &str {
+    match step {
+        ResponseTransformStep::MaskField { path, .. }
+        | ResponseTransformStep::RemoveField { path }
+        | ResponseTransformStep::TruncateString { path, .. } => path,
+    }
+}
+
+// "result.value[*].account.owner" -> [result, value, *, account, owner]
+fn parse_path(path: &str) -> Result<Vec<PathSegment>, AppError> {
+    let invalid = || AppError::config(&format!("Invalid response transform path: {}", path));
+    let mut segments = Vec::new();
+
+    for part in path.split('.') {
+        let (key, mut indexes) = part.split_once('[')
+            .map(|(key, rest)| (key, Some(rest)))
+            .unwrap_or((part, None));
+        if !key.is_empty() {
+            segments.push(PathSegment::Key(key.to_string()));
+        } else if indexes.is_none() {
+            return Err(invalid());
+        }
+
+        // Remaining text looks like `0]` or `*][1]`
+        while let Some(rest) = indexes {
+            let (index, after) = rest.split_once(']').ok_or_else(invalid)?;
+            segments.push(match index {
+                "*" => PathSegment::Each,
+                index => PathSegment::Index(index.parse().map_err(|_| invalid())?),
+            });
+            indexes = match after {
+                "" => None,
+                after => Some(after.strip_prefix('[').ok_or_else(invalid)?),
+            };
+        }
+    }
+
+    if segments.is_empty() {
+        return Err(invalid());
+    }
+    Ok(segments)
+}
+
+// Calls `f` on every value the path resolves to and reports whether any call changed something
+fn visit<F>(value: &mut Value, path: &[PathSegment], f: &mut F) -> bool
+where
+    F: FnMut(&mut Value) -> bool,
+{
+    let Some((segment, rest)) = path.split_first() else {
+        return f(value);
+    };
+
+    match (segment, value) {
+        (PathSegment::Key(key), Value::Object(map)) => map.get_mut(key)
+            .is_some_and(|child| visit(child, rest, f)),
+        (PathSegment::Index(index), Value::Array(items)) => items.get_mut(*index)
+            .is_some_and(|child| visit(child, rest, f)),
+        (PathSegment::Each, Value::Array(items)) => items.iter_mut()
+            .fold(false, |changed, child| visit(child, rest, f) | changed),
+        _ => false,
+    }
+}
+
+fn apply_step(step: &ResponseTransformStep, path: &[PathSegment], response: &mut Value) -> bool {
+    match step {
+        ResponseTransformStep::MaskField { mask, .. } => visit(response, path, &mut |value| {
+            *value = Value::String(mask.clone());
+            true
+        }),
+        ResponseTransformStep::TruncateString { max_len, .. } => visit(response, path, &mut |value| {
+            match value {
+                Value::String(s) if s.chars().count() > *max_len => {
+                    *s = s.chars().take(*max_len).collect();
+                    true
+                }
+                _ => false,
+            }
+        }),
+        ResponseTransformStep::RemoveField { .. } => remove_at(path, response),
+    }
+}
+
+// Resolves the parent and drops the last segment from it
+fn remove_at(path: &[PathSegment], response: &mut Value) -> bool {
+    let (last, parent) = path.split_last().expect("parsed paths are never empty");
+    visit(response, parent, &mut |value| match (last, value) {
+        (PathSegment::Key(key), Value::Object(map)) => map.remove(key).is_some(),
+        (PathSegment::Index(index), Value::Array(items)) if *index < items.len() => {
+            items.remove(*index);
+            true
+        }
+        (PathSegment::Each, Value::Array(items)) if !items.is_empty() => {
+            items.clear();
+            true
+        }
+        _ => false,
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_server::TestServerBuilder;
+    use serde_json::json;
+    use wiremock::{matchers::{body_partial_json, method}, Mock, ResponseTemplate};
+
+    fn pipeline(steps: Vec<ResponseTransformStep>) -> ResponseTransformPipeline {
+        ResponseTransformPipeline::new(steps).unwrap()
+    }
+
+    fn mask(path: &str) -> ResponseTransformStep {
+        ResponseTransformStep::MaskField { path: path.to_string(), mask: "***".to_string() }
+    }
+
+    fn remove(path: &str) -> ResponseTransformStep {
+        ResponseTransformStep::RemoveField { path: path.to_string() }
+    }
+
+    fn truncate(path: &str, max_len: usize) -> ResponseTransformStep {
+        ResponseTransformStep::TruncateString { path: path.to_string(), max_len }
+    }
+
+    fn account_response() -> Value {
+        json!({
+            "jsonrpc": "2.0",
+            "id": 1,
+            "result": {
+                "context": { "slot": 100, "validator": "node-7.internal" },
+                "value": [
+                    { "pubkey": "A", "account": { "owner": "Sys1", "data": "abcdefgh" } },
+                    { "pubkey": "B", "account": { "owner": "Sys2", "data": "ijkl" } }
+                ]
+            }
+        })
+    }
+
+    #[test]
+    fn test_parse_path() {
+        assert_eq!(parse_path("result.value[*].account").unwrap(), vec![
+            PathSegment::Key("result".to_string()),
+            PathSegment::Key("value".to_string()),
+            PathSegment::Each,
+            PathSegment::Key("account".to_string()),
+        ]);
+        assert_eq!(parse_path("matrix[1][0]").unwrap(), vec![
+            PathSegment::Key("matrix".to_string()),
+            PathSegment::Index(1),
+            PathSegment::Index(0),
+        ]);
+        for invalid in ["", "result..value", "value[x]", "value[0", "value[0]x"] {
+            assert!(parse_path(invalid).is_err(), "{} should not parse", invalid);
+        }
+    }
+
+    #[test]
+    fn test_mask_field() {
+        let mut response = account_response();
+        let applied = pipeline(vec![
+            mask("result.context.validator"),
+            mask("result.value[*].account.owner"),
+        ]).apply(&mut response);
+
+        assert_eq!(applied, vec!["mask_field", "mask_field"]);
+        assert_eq!(response["result"]["context"]["validator"], "***");
+        assert_eq!(response["result"]["context"]["slot"], 100);
+        assert_eq!(response["result"]["value"][0]["account"]["owner"], "***");
+        assert_eq!(response["result"]["value"][1]["account"]["owner"], "***");
+    }
+
+    #[test]
+    fn test_remove_field() {
+        let mut response = account_response();
+        let applied = pipeline(vec![
+            remove("result.context.validator"),
+            remove("result.value[0].account.data"),
+            remove("result.value[1]"),
+        ]).apply(&mut response);
+
+        assert_eq!(applied.len(), 3);
+        assert!(response["result"]["context"].get("validator").is_none());
+        assert!(response["result"]["value"][0]["account"].get("data").is_none());
+        assert_eq!(response["result"]["value"].as_array().unwrap().len(), 1);
+    }
+
+    #[test]
+    fn test_truncate_string() {
+        let mut response = account_response();
+        let applied = pipeline(vec![truncate("result.value[*].account.data", 5)]).apply(&mut response);
+
+        assert_eq!(applied, vec!["truncate_string"]);
+        assert_eq!(response["result"]["value"][0]["account"]["data"], "abcde");
+        // Already short enough
+        assert_eq!(response["result"]["value"][1]["account"]["data"], "ijkl");
+
+        // Non-string values are left alone
+        let mut numeric = json!({"result": {"slot": 123456}});
+        assert!(pipeline(vec![truncate("result.slot", 2)]).apply(&mut numeric).is_empty());
+        assert_eq!(numeric["result"]["slot"], 123456);
+    }
+
+    #[test]
+    fn test_missing_paths_are_noops() {
+        let original = account_response();
+        let mut response = original.clone();
+        let applied = pipeline(vec![
+            mask("result.missing"),
+            remove("result.value[5]"),
+            remove("result.context.slot.inner"),
+            truncate("error.message", 3),
+            mask("result.context[*]"),
+        ]).apply(&mut response);
+
+        assert!(applied.is_empty());
+        assert_eq!(response, original);
+    }
+
+    #[tokio::test]
+    async fn test_cached_response_is_already_sanitized() {
+        let server = TestServerBuilder::new()
+            .with_config(|config| {
+                config.cache.enabled = true;
+                config.response_transforms = vec![mask("result.identity")];
+            })
+            .start()
+            .await;
+        Mock::given(method("POST"))
+            .and(body_partial_json(json!({"method": "getGenesisHash"})))
+            .respond_with(ResponseTemplate::new(200).set_body_json(json!({
+                "jsonrpc": "2.0",
+                "id": 1,
+                "result": { "hash": "genesis", "identity": "validator-secret" }
+            })))
+            .expect(1)
+            .mount(server.endpoint_mock("primary"))
+            .await;
+
+        for _ in 0..2 {
+            let body: Value = server.rpc(json!({"jsonrpc": "2.0", "id": 1, "method": "getGenesisHash"})).await
+                .json().await.unwrap();
+            assert_eq!(body["result"]["hash"], "genesis");
+            assert_eq!(body["result"]["identity"], "***");
+        }
+    }
+
+    fn transform(strip: &[&str], rename: &[(&str, &str)], inject: &[(&str, Value)]) -> TransformConfig {
+        TransformConfig {
+            strip_fields: strip.iter().map(|path| path.to_string()).collect(),
+            rename_fields: rename.iter().map(|(path, name)| (path.to_string(), name.to_string())).collect(),
+            inject_fields: inject.iter().map(|(path, value)| (path.to_string(), value.clone())).collect(),
+        }
+    }
+
+    fn consensus_response() -> Value {
+        json!({
+            "jsonrpc": "2.0",
+            "id": 1,
+            "result": { "context": { "slot": 100 }, "value": 5000 },
+            "consensus_meta": { "confidence": 1.0, "endpoint_count": 3 }
+        })
+    }
+
+    fn transformed(transforms: &[TransformConfig], mut response: Value) -> Value {
+        ResponseTransformer::new(transforms).unwrap().apply(&mut response);
+        response
+    }
+
+    #[test]
+    fn test_transformer_strips_fields() {
+        let response = transformed(&[transform(&["consensus_meta", "result.context.slot", "result.missing"], &[], &[])], consensus_response());
+        assert_eq!(response, json!({"jsonrpc": "2.0", "id": 1, "result": {"context": {}, "value": 5000}}));
+    }
+
+    #[test]
+    fn test_transformer_renames_fields() {
+        let response = transformed(&[transform(&[], &[("result.context", "ctx"), ("result.missing", "other")], &[])], consensus_response());
+        assert_eq!(response["result"], json!({"ctx": {"slot": 100}, "value": 5000}));
+        assert!(response["consensus_meta"].is_object());
+    }
+
+    #[test]
+    fn test_transformer_injects_fields() {
+        let response = transformed(&[transform(&[], &[], &[
+            ("proxied_by", json!("multi-rpc")),
+            ("result.context.region", json!("eu")),
+            // No parent to inject into
+            ("result.value.nested", json!(1)),
+        ])], consensus_response());
+        assert_eq!(response["proxied_by"], "multi-rpc");
+        assert_eq!(response["result"]["context"], json!({"slot": 100, "region": "eu"}));
+        assert_eq!(response["result"]["value"], 5000);
+    }
+
+    #[test]
+    fn test_transformer_rules_combine_and_compose_in_order() {
+        let transforms = [
+            transform(&["consensus_meta"], &[("result.value", "lamports")], &[("proxied_by", json!("multi-rpc"))]),
+            // Sees the field the first transform renamed and the one it injected
+            transform(&["proxied_by"], &[("result.lamports", "balance")], &[("result.proxied", json!(true))]),
+        ];
+
+        let response = transformed(&transforms, consensus_response());
+        assert_eq!(response, json!({
+            "jsonrpc": "2.0",
+            "id": 1,
+            "result": { "context": { "slot": 100 }, "balance": 5000, "proxied": true }
+        }));
+
+        let batch = transformed(&transforms, json!([consensus_response(), consensus_response()]));
+        assert_eq!(batch[0], response);
+        assert_eq!(batch[1], response);
+    }
+
+    #[test]
+    fn test_transformer_rejects_invalid_paths() {
+        assert!(ResponseTransformer::new(&[transform(&["result..x"], &[], &[])]).is_err());
+        assert!(ResponseTransformer::new(&[transform(&[], &[("result.value[0]", "first")], &[])]).is_err());
+        assert!(ResponseTransformer::new(&[transform(&[], &[], &[("result[*]", json!(1))])]).is_err());
+    }
+
+    #[tokio::test]
+    async fn test_transforms_reach_the_client_but_not_the_cache() {
+        let server = TestServerBuilder::new()
+            .with_config(|config| {
+                config.cache.enabled = true;
+                config.transforms = vec![transform(&["result.identity"], &[], &[("proxied_by", json!("multi-rpc"))])];
+            })
+            .start()
+            .await;
+        Mock::given(method("POST"))
+            .and(body_partial_json(json!({"method": "getGenesisHash"})))
+            .respond_with(ResponseTemplate::new(200).set_body_json(json!({
+                "jsonrpc": "2.0",
+                "id": 1,
+                "result": { "hash": "genesis", "identity": "validator" }
+            })))
+            .expect(1)
+            .mount(server.endpoint_mock("primary"))
+            .await;
+
+        for _ in 0..2 {
+            let body: Value = server.rpc(json!({"jsonrpc": "2.0", "id": 1, "method": "getGenesisHash"})).await
+                .json().await.unwrap();
+            assert_eq!(body["result"], json!({"hash": "genesis"}));
+            assert_eq!(body["proxied_by"], "multi-rpc");
+        }
+        let cached = server.state.cache_service.get("getGenesisHash", &Value::Null).await.unwrap();
+        assert_eq!(cached["result"]["identity"], "validator");
+        assert!(cached.get("proxied_by").is_none());
+    }
+}
@@ -0,0 +1,410 @@
+use crate::{config::EndpointConfig, error::AppError, propagation::with_baggage, signing::RequestSigner};
+use async_trait::async_trait;
+use axum::http::{HeaderMap, HeaderName};
+use serde_json::{json, Value};
+use tonic::{
+    metadata::{Ascii, MetadataValue},
+    service::{interceptor::InterceptedService, Interceptor},
+    transport::Channel,
+    Request, Status,
+};
+
+pub mod geyser_proto {
+    include!(concat!(env!("OUT_DIR"), "/geyser.Geyser.rs"));
+}
+
+use geyser_proto::geyser_client::GeyserClient;
+
+// How a JSON-RPC request reaches an upstream endpoint
+#[async_trait]
+pub trait RpcTransport: Send + Sync + std::fmt::Debug {
+    async fn send(&self, request: Value) -> Result<Value, AppError>;
+
+    // Like send, along with the size of the response in bytes. Transports without a JSON body
+    // of their own report the size the response serializes to. `headers` are the client's, for
+    // transports that forward some of them; the rest ignore them.
+    async fn send_measured(&self, request: Value, _headers: Option<&HeaderMap>) -> Result<(Value, usize), AppError> {
+        let response = self.send(request).await?;
+        let size = serde_json::to_vec(&response)?.len();
+        Ok((response, size))
+    }
+}
+
+// Client headers not listed in forward_headers never leave the proxy
+pub fn forward_client_headers(
+    mut builder: reqwest::RequestBuilder,
+    forward_headers: &[HeaderName],
+    headers: Option<&HeaderMap>,
+) -> reqwest::RequestBuilder {
+    for name in forward_headers {
+        for value in headers.into_iter().flat_map(|headers| headers.get_all(name)) {
+            builder = builder.header(name.as_str(), value.as_bytes());
+        }
+    }
+    builder
+}
+
+#[derive(Debug)]
+pub struct HttpTransport {
+    client: reqwest::Client,
+    url: String,
+    signer: Option<RequestSigner>,
+    forward_headers: Vec<HeaderName>,
+}
+
+impl HttpTransport {
+    pub fn new(client: reqwest::Client, url: String) -> Self {
+        Self { client, url, signer: None, forward_headers: Vec::new() }
+    }
+
+    pub fn with_signer(mut self, signer: Option<RequestSigner>) -> Self {
+        self.signer = signer;
+        self
+    }
+
+    pub fn with_forward_headers(mut self, forward_headers: Vec<HeaderName>) -> Self {
+        self.forward_headers = forward_headers;
+        self
+    }
+}
+
+#[async_trait]
+impl RpcTransport for HttpTransport {
+    async fn send(&self, request: Value) -> Result<Value, AppError> {
+        Ok(self.send_measured(request, None).await?.0)
+    }
+
+    async fn send_measured(&self, request: Value, headers: Option<&HeaderMap>) -> Result<(Value, usize), AppError> {
+        let builder = with_baggage(self.client.post(&self.url))
+            .header("Content-Type", "application/json")
+            .header("User-Agent", "Multi-RPC/1.0");
+        let builder = forward_client_headers(builder, &self.forward_headers, headers);
+        let builder = match &self.signer {
+            Some(signer) => signer.sign(builder, &request)?,
+            None => builder.json(&request),
+        };
+        let response = builder.send().await?;
+
+        if !response.status().is_success() {
+            return Err(AppError::endpoint(&format!("HTTP {}: {}", response.status(), self.url)));
+        }
+
+        let response_text = response.text().await?;
+        Ok((serde_json::from_str(&response_text)?, response_text.len()))
+    }
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash, PartialOrd, Ord, prost::Enumeration)]
+#[repr(i32)]
+pub enum CommitmentLevel {
+    Processed = 0,
+    Confirmed = 1,
+    Finalized = 2,
+}
+
+#[derive(Clone, PartialEq, prost::Message)]
+pub struct GetSlotRequest {
+    #[prost(enumeration = "CommitmentLevel", optional, tag = "1")]
+    pub commitment: Option<i32>,
+}
+
+#[derive(Clone, PartialEq, prost::Message)]
+pub struct GetSlotResponse {
+    #[prost(uint64, tag = "1")]
+    pub slot: u64,
+}
+
+#[derive(Clone, PartialEq, prost::Message)]
+pub struct GetBlockHeightRequest {
+    #[prost(enumeration = "CommitmentLevel", optional, tag = "1")]
+    pub commitment: Option<i32>,
+}
+
+#[derive(Clone, PartialEq, prost::Message)]
+pub struct GetBlockHeightResponse {
+    #[prost(uint64, tag = "1")]
+    pub block_height: u64,
+}
+
+#[derive(Clone, PartialEq, prost::Message)]
+pub struct GetLatestBlockhashRequest {
+    #[prost(enumeration = "CommitmentLevel", optional, tag = "1")]
+    pub commitment: Option<i32>,
+}
+
+#[derive(Clone, PartialEq, prost::Message)]
+pub struct GetLatestBlockhashResponse {
+    #[prost(uint64, tag = "1")]
+    pub slot: u64,
+    #[prost(string, tag = "2")]
+    pub blockhash: String,
+    #[prost(uint64, tag = "3")]
+    pub last_valid_block_height: u64,
+}
+
+#[derive(Clone, PartialEq, prost::Message)]
+pub struct IsBlockhashValidRequest {
+    #[prost(string, tag = "1")]
+    pub blockhash: String,
+    #[prost(enumeration = "CommitmentLevel", optional, tag = "2")]
+    pub commitment: Option<i32>,
+}
+
+#[derive(Clone, PartialEq, prost::Message)]
+pub struct IsBlockhashValidResponse {
+    #[prost(uint64, tag = "1")]
+    pub slot: u64,
+    #[prost(bool, tag = "2")]
+    pub valid: bool,
+}
+
+// Geyser authenticates with an `x-token` header rather than a bearer token
+#[derive(Debug, Clone)]
+pub struct XTokenInterceptor {
+    token: Option<MetadataValue<Ascii>>,
+}
+
+impl Interceptor for XTokenInterceptor {
+    fn call(&mut self, mut request: Request<()>) -> Result<Request<()>, Status> {
+        if let Some(token) = &self.token {
+            request.metadata_mut().insert("x-token", token.clone());
+        }
+        Ok(request)
+    }
+}
+
+// Answers the JSON-RPC methods that have a unary Geyser equivalent. The channel connects
+// lazily, so an unreachable node shows up on the first request rather than at startup.
+#[derive(Debug)]
+pub struct GrpcTransport {
+    client: GeyserClient<InterceptedService<Channel, XTokenInterceptor>>,
+}
+
+impl GrpcTransport {
+    pub fn new(config: &EndpointConfig) -> Result<Self, AppError> {
+        let channel = Channel::from_shared(config.url.clone())
+            .map_err(|e| AppError::config(&format!("Invalid gRPC endpoint URL {}: {}", config.url, e)))?
+            .connect_lazy();
+        let token = config.auth_token.as_deref()
+            .map(|token| token.parse())
+            .transpose()
+            .map_err(|e| AppError::config(&format!("Invalid auth token for {}: {}", config.name, e)))?;
+
+        Ok(Self {
+            client: GeyserClient::with_interceptor(channel, XTokenInterceptor { token }),
+        })
+    }
+}
+
+#[async_trait]
+impl RpcTransport for GrpcTransport {
+    async fn send(&self, request: Value) -> Result<Value, AppError> {
+        let method = request["method"].as_str().unwrap_or_default();
+        let params = request["params"].as_array().cloned().unwrap_or_default();
+        // The generated client needs &mut self; clones share the underlying channel
+        let mut client = self.client.clone();
+
+        let result = match method {
+            "getSlot" => {
+                let request = GetSlotRequest { commitment: commitment(params.first()) };
+                json!(client.get_slot(request).await.map_err(grpc_error)?.into_inner().slot)
+            }
+            "getBlockHeight" => {
+                let request = GetBlockHeightRequest { commitment: commitment(params.first()) };
+                json!(client.get_block_height(request).await.map_err(grpc_error)?.into_inner().block_height)
+            }
+            "getLatestBlockhash" => {
+                let request = GetLatestBlockhashRequest { commitment: commitment(params.first()) };
+                let response = client.get_latest_blockhash(request).await.map_err(grpc_error)?.into_inner();
+                json!({
+                    "context": {"slot": response.slot},
+                    "value": {
+                        "blockhash": response.blockhash,
+                        "lastValidBlockHeight": response.last_valid_block_height,
+                    }
+                })
+            }
+            "isBlockhashValid" => {
+                let blockhash = params.first().and_then(Value::as_str)
+                    .ok_or_else(|| AppError::invalid_request("isBlockhashValid needs a blockhash"))?;
+                let request = IsBlockhashValidRequest {
+                    blockhash: blockhash.to_string(),
+                    commitment: commitment(params.get(1)),
+                };
+                let response = client.is_blockhash_valid(request).await.map_err(grpc_error)?.into_inner();
+                json!({"context": {"slot": response.slot}, "value": response.valid})
+            }
+            _ => return Err(AppError::RpcError {
+                code: -32601,
+                message: format!("Method {} is not available on gRPC endpoints", method),
+                data: None,
+            }),
+        };
+
+        Ok(json!({"jsonrpc": "2.0", "id": request["id"], "result": result}))
+    }
+}
+
+// Reads `commitment` from a JSON-RPC config object; the node's default applies when absent
+fn commitment(config: Option<&Value>) -> Option<i32> {
+    let level = match config?.get("commitment")?.as_str()? {
+        "processed" => CommitmentLevel::Processed,
+        "confirmed" => CommitmentLevel::Confirmed,
+        "finalized" => CommitmentLevel::Finalized,
+        _ => return None,
+    };
+    Some(level as i32)
+}
+
+fn grpc_error(status: Status) -> AppError {
+    AppError::endpoint(&format!("gRPC {:?}: {}", status.code(), status.message()))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{config::Config, test_server::TestServerBuilder, types::{EndpointKind, EndpointStatus}};
+    use geyser_proto::geyser_server::{Geyser, GeyserServer};
+    use std::sync::{Arc, Mutex};
+    use tokio::net::TcpListener;
+    use tokio_stream::wrappers::TcpListenerStream;
+    use tonic::Response;
+
+    // Commitment and x-token of each call, in order
+    type SeenCalls = Arc<Mutex<Vec<(Option<i32>, Option<String>)>>>;
+
+    // Answers every call with fixed values and records the commitment and token it was sent
+    #[derive(Default)]
+    struct MockGeyser {
+        seen: SeenCalls,
+    }
+
+    impl MockGeyser {
+        fn record<T>(&self, request: &Request<T>, commitment: Option<i32>) {
+            let token = request.metadata().get("x-token").map(|token| token.to_str().unwrap().to_string());
+            self.seen.lock().unwrap().push((commitment, token));
+        }
+    }
+
+    #[tonic::async_trait]
+    impl Geyser for MockGeyser {
+        async fn get_slot(&self, request: Request<GetSlotRequest>) -> Result<Response<GetSlotResponse>, Status> {
+            self.record(&request, request.get_ref().commitment);
+            Ok(Response::new(GetSlotResponse { slot: 310_000_000 }))
+        }
+
+        async fn get_block_height(
+            &self,
+            request: Request<GetBlockHeightRequest>,
+        ) -> Result<Response<GetBlockHeightResponse>, Status> {
+            self.record(&request, request.get_ref().commitment);
+            Err(Status::unavailable("node is catching up"))
+        }
+
+        async fn get_latest_blockhash(
+            &self,
+            request: Request<GetLatestBlockhashRequest>,
+        ) -> Result<Response<GetLatestBlockhashResponse>, Status> {
+            self.record(&request, request.get_ref().commitment);
+            Ok(Response::new(GetLatestBlockhashResponse {
+                slot: 310_000_000,
+                blockhash: "EkSnNWid2cvwEVnVx9aBqawnmiCNiDgp3gUdkDPTKN1N".to_string(),
+                last_valid_block_height: 290_000_150,
+            }))
+        }
+
+        async fn is_blockhash_valid(
+            &self,
+            request: Request<IsBlockhashValidRequest>,
+        ) -> Result<Response<IsBlockhashValidResponse>, Status> {
+            self.record(&request, request.get_ref().commitment);
+            Ok(Response::new(IsBlockhashValidResponse { slot: 310_000_000, valid: true }))
+        }
+    }
+
+    async fn mock_geyser() -> (String, Arc<Mutex<Vec<(Option<i32>, Option<String>)>>>) {
+        let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let url = format!("http://{}", listener.local_addr().unwrap());
+        let geyser = MockGeyser::default();
+        let seen = geyser.seen.clone();
+        tokio::spawn(
+            tonic::transport::Server::builder()
+                .add_service(GeyserServer::new(geyser))
+                .serve_with_incoming(TcpListenerStream::new(listener)),
+        );
+        (url, seen)
+    }
+
+    fn grpc_endpoint(url: String) -> EndpointConfig {
+        EndpointConfig {
+            url,
+            name: "geyser".to_string(),
+            auth_token: Some("geyser-token".to_string()),
+            kind: EndpointKind::Grpc,
+            ..Config::default().endpoints[0].clone()
+        }
+    }
+
+    #[tokio::test]
+    async fn test_grpc_transport_answers_json_rpc() {
+        let (url, seen) = mock_geyser().await;
+        let transport = GrpcTransport::new(&grpc_endpoint(url)).unwrap();
+
+        let response = transport.send(json!({
+            "jsonrpc": "2.0", "id": 3, "method": "getSlot", "params": [{"commitment": "confirmed"}]
+        })).await.unwrap();
+        assert_eq!(response, json!({"jsonrpc": "2.0", "id": 3, "result": 310_000_000}));
+
+        let response = transport.send(json!({"jsonrpc": "2.0", "id": 4, "method": "getLatestBlockhash"})).await.unwrap();
+        assert_eq!(response["result"]["value"]["lastValidBlockHeight"], 290_000_150);
+        assert_eq!(response["result"]["context"]["slot"], 310_000_000);
+
+        let response = transport.send(json!({
+            "jsonrpc": "2.0", "id": 5, "method": "isBlockhashValid",
+            "params": ["EkSnNWid2cvwEVnVx9aBqawnmiCNiDgp3gUdkDPTKN1N", {"commitment": "processed"}]
+        })).await.unwrap();
+        assert_eq!(response["result"]["value"], true);
+
+        let seen = seen.lock().unwrap().clone();
+        let token = Some("geyser-token".to_string());
+        assert_eq!(seen, vec![
+            (Some(CommitmentLevel::Confirmed as i32), token.clone()),
+            (None, token.clone()),
+            (Some(CommitmentLevel::Processed as i32), token),
+        ]);
+    }
+
+    #[tokio::test]
+    async fn test_grpc_transport_errors() {
+        let (url, _) = mock_geyser().await;
+        let transport = GrpcTransport::new(&grpc_endpoint(url)).unwrap();
+
+        let unavailable = transport.send(json!({"jsonrpc": "2.0", "id": 1, "method": "getBlockHeight"})).await;
+        assert!(matches!(unavailable, Err(AppError::EndpointError(ref msg)) if msg.contains("node is catching up")));
+
+        let unsupported = transport.send(json!({"jsonrpc": "2.0", "id": 1, "method": "getProgramAccounts"})).await;
+        assert!(matches!(unsupported, Err(AppError::RpcError { code: -32601, .. })));
+    }
+
+    #[tokio::test]
+    async fn test_json_rpc_served_by_grpc_endpoint() {
+        let (url, _) = mock_geyser().await;
+        let server = TestServerBuilder::new()
+            .with_config(|config| config.endpoints.push(grpc_endpoint(url)))
+            .start()
+            .await;
+        // Leave the gRPC endpoint as the only one to route to
+        let manager = &server.state.endpoint_manager;
+        let primary = manager.get_endpoint_info().await.into_iter().find(|e| e.name == "primary").unwrap();
+        manager.update_endpoint_status(primary.id, EndpointStatus::Unhealthy).await;
+
+        let body: Value = server.rpc(json!({"jsonrpc": "2.0", "id": 9, "method": "getSlot"})).await.json().await.unwrap();
+        assert_eq!(body, json!({"jsonrpc": "2.0", "id": 9, "result": 310_000_000}));
+
+        let body: Value = server.rpc(json!({"jsonrpc": "2.0", "id": 10, "method": "getProgramAccounts", "params": ["Program1"]}))
+            .await.json().await.unwrap();
+        assert_eq!(body["id"], 10);
+        assert_eq!(body["error"]["code"], -32601);
+        assert!(server.endpoint_mock("primary").received_requests().await.unwrap().is_empty());
+    }
+}
@@ -1,6 +1,12 @@
+// Shared API and wire types. Not every one is read by the proxy itself, but they're kept
+// together as the full set clients and handlers can draw on.
+#![allow(dead_code)]
+
+use crate::histogram::ResponseHistogram;
 use chrono::{DateTime, Utc};
 use serde::{Deserialize, Serialize};
-use std::time::Duration;
+use schemars::JsonSchema;
+use std::{collections::VecDeque, time::Duration};
 use uuid::Uuid;
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
@@ -16,6 +22,24 @@ pub struct EndpointInfo {
     pub region: Option<String>,
     pub latitude: Option<f64>,
     pub longitude: Option<f64>,
+    // Earliest expiry in the endpoint's TLS chain, filled in by the certificate monitor
+    #[serde(default)]
+    pub tls_cert_expires_at: Option<DateTime<Utc>>,
+    #[serde(default)]
+    pub tls_cert_days_remaining: Option<i64>,
+    // Slots behind the freshest endpoint, as of the last slot poll
+    #[serde(default)]
+    pub slot_lag: Option<u64>,
+    // Priority tier for that lag, kept apart from the configured priority so it can't clobber it
+    #[serde(default)]
+    pub slot_lag_priority: Option<u8>,
+}
+
+impl EndpointInfo {
+    // Priority routing goes by: lag only ever pushes an endpoint below its configured priority
+    pub fn effective_priority(&self) -> u8 {
+        self.priority.max(self.slot_lag_priority.unwrap_or(0))
+    }
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
@@ -45,6 +69,9 @@ pub struct EndpointScore {
     pub uptime_percentage: f64,
     pub feature_support: u8,
     pub last_updated: DateTime<Utc>,
+    // Percentiles over the endpoint's most recent responses
+    #[serde(default)]
+    pub recent_latency: LatencyPercentiles,
 }
 
 impl Default for EndpointScore {
@@ -56,6 +83,7 @@ impl Default for EndpointScore {
             uptime_percentage: 0.0,
             feature_support: 0,
             last_updated: Utc::now(),
+            recent_latency: LatencyPercentiles::default(),
         }
     }
 }
@@ -97,9 +125,29 @@ pub struct EndpointStats {
     pub total_requests: u64,
     pub successful_requests: u64,
     pub failed_requests: u64,
-    pub avg_response_time: f64,
+    #[serde(default)]
+    pub response_times: ResponseHistogram,
     pub last_success: Option<DateTime<Utc>>,
     pub last_failure: Option<DateTime<Utc>>,
+    // Exponentially weighted latency statistics used for anomaly detection
+    #[serde(default)]
+    pub latency_samples: u64,
+    #[serde(default)]
+    pub latency_ema_ms: f64,
+    #[serde(default)]
+    pub latency_variance: f64,
+    // Sliding window of recent response times, so regressions show up in the percentiles
+    // instead of being averaged away by the endpoint's whole history
+    #[serde(default)]
+    pub recent_latencies: LatencyHistogram,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct EndpointLatencyAnomaly {
+    pub endpoint_id: Uuid,
+    pub current_ms: f64,
+    pub ema_ms: f64,
+    pub std_ms: f64,
 }
 
 impl Default for EndpointStats {
@@ -108,11 +156,109 @@ impl Default for EndpointStats {
             total_requests: 0,
             successful_requests: 0,
             failed_requests: 0,
-            avg_response_time: 0.0,
+            response_times: ResponseHistogram::new(),
             last_success: None,
             last_failure: None,
+            latency_samples: 0,
+            latency_ema_ms: 0.0,
+            latency_variance: 0.0,
+            recent_latencies: LatencyHistogram::default(),
+        }
+    }
+}
+
+pub const DEFAULT_LATENCY_WINDOW: usize = 1000;
+
+// The last `capacity` response times in milliseconds, oldest first
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct LatencyHistogram {
+    samples: VecDeque<f64>,
+    capacity: usize,
+}
+
+impl Default for LatencyHistogram {
+    fn default() -> Self {
+        Self::new(DEFAULT_LATENCY_WINDOW)
+    }
+}
+
+#[derive(Debug, Clone, Copy, Default, PartialEq, Serialize, Deserialize)]
+pub struct LatencyPercentiles {
+    pub p50: f64,
+    pub p95: f64,
+    pub p99: f64,
+    pub p999: f64,
+}
+
+impl LatencyHistogram {
+    pub fn new(capacity: usize) -> Self {
+        let capacity = capacity.max(1);
+        Self {
+            samples: VecDeque::with_capacity(capacity),
+            capacity,
+        }
+    }
+
+    pub fn record(&mut self, ms: f64) {
+        if self.samples.len() == self.capacity {
+            self.samples.pop_front();
+        }
+        self.samples.push_back(ms);
+    }
+
+    pub fn len(&self) -> usize {
+        self.samples.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.samples.is_empty()
+    }
+
+    pub fn p50(&self) -> f64 {
+        self.percentile(0.50)
+    }
+
+    pub fn p95(&self) -> f64 {
+        self.percentile(0.95)
+    }
+
+    pub fn p99(&self) -> f64 {
+        self.percentile(0.99)
+    }
+
+    pub fn p999(&self) -> f64 {
+        self.percentile(0.999)
+    }
+
+    // Nearest-rank percentile of the window, or 0 when it is empty
+    pub fn percentile(&self, quantile: f64) -> f64 {
+        nearest_rank(&self.sorted(), quantile)
+    }
+
+    // All four percentiles from a single sort
+    pub fn percentiles(&self) -> LatencyPercentiles {
+        let sorted = self.sorted();
+        LatencyPercentiles {
+            p50: nearest_rank(&sorted, 0.50),
+            p95: nearest_rank(&sorted, 0.95),
+            p99: nearest_rank(&sorted, 0.99),
+            p999: nearest_rank(&sorted, 0.999),
         }
     }
+
+    fn sorted(&self) -> Vec<f64> {
+        let mut sorted: Vec<f64> = self.samples.iter().copied().collect();
+        sorted.sort_unstable_by(f64::total_cmp);
+        sorted
+    }
+}
+
+fn nearest_rank(sorted: &[f64], quantile: f64) -> f64 {
+    if sorted.is_empty() {
+        return 0.0;
+    }
+    let rank = ((quantile.clamp(0.0, 1.0) * sorted.len() as f64).ceil() as usize).max(1);
+    sorted[rank - 1]
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
@@ -128,12 +274,28 @@ pub struct SystemHealth {
     pub avg_response_time: f64,
 }
 
-#[derive(Debug, Clone, Serialize, Deserialize)]
+#[derive(Debug, Clone, Default, Serialize, Deserialize, JsonSchema)]
 pub enum LoadBalancingStrategy {
     RoundRobin,
     Weighted,
     LeastLatency,
+    #[default]
     HealthBased,
+    FreshestData,
+    /// Two random candidates, the one with fewer active connections wins
+    PowerOfTwoChoices,
+    /// Fewest requests in flight right now
+    LeastConnections,
+}
+
+// Protocol an upstream endpoint speaks
+#[derive(Debug, Clone, Copy, Default, PartialEq, Serialize, Deserialize, JsonSchema)]
+pub enum EndpointKind {
+    /// JSON-RPC over HTTP
+    #[default]
+    Http,
+    /// Yellowstone Geyser gRPC
+    Grpc,
 }
 
 // WebSocket specific types
@@ -375,13 +537,23 @@ pub struct CircuitBreakerStatus {
     pub next_attempt_at: Option<DateTime<Utc>>,
 }
 
-#[derive(Debug, Clone, Serialize, Deserialize)]
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
 pub enum CircuitBreakerState {
     Closed,
     Open,
     HalfOpen,
 }
 
+impl CircuitBreakerState {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            CircuitBreakerState::Closed => "closed",
+            CircuitBreakerState::Open => "open",
+            CircuitBreakerState::HalfOpen => "half_open",
+        }
+    }
+}
+
 // Load balancer types
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct LoadBalancerStats {
@@ -484,4 +656,81 @@ pub struct ErrorStats {
     pub errors_by_method: std::collections::HashMap<String, u64>,
     pub error_rate: f64,
     pub recent_errors: Vec<ErrorReport>,
-}
\ No newline at end of file
+}
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use rand::{rngs::StdRng, Rng, SeedableRng};
+
+    fn random_samples(rng: &mut StdRng) -> Vec<f64> {
+        (0..rng.gen_range(1..3_000))
+            .map(|_| if rng.gen_bool(0.9) { rng.gen_range(1.0..200.0) } else { rng.gen_range(200.0..30_000.0) })
+            .collect()
+    }
+
+    #[test]
+    fn test_empty_window() {
+        let histogram = LatencyHistogram::default();
+        assert!(histogram.is_empty());
+        assert_eq!(histogram.percentiles(), LatencyPercentiles::default());
+    }
+
+    #[test]
+    fn test_small_window_is_exact() {
+        let mut histogram = LatencyHistogram::new(1000);
+        for ms in 1..=1000 {
+            histogram.record(ms as f64);
+        }
+        assert_eq!(histogram.p50(), 500.0);
+        assert_eq!(histogram.p95(), 950.0);
+        assert_eq!(histogram.p99(), 990.0);
+        assert_eq!(histogram.p999(), 999.0);
+    }
+
+    #[test]
+    fn test_percentiles_hold_for_random_windows() {
+        let mut rng = StdRng::seed_from_u64(11);
+        for _ in 0..200 {
+            let capacity = rng.gen_range(1..2_000);
+            let samples = random_samples(&mut rng);
+            let mut histogram = LatencyHistogram::new(capacity);
+            for &sample in &samples {
+                histogram.record(sample);
+            }
+
+            // Only the newest `capacity` samples are kept
+            let window = &samples[samples.len().saturating_sub(capacity)..];
+            assert_eq!(histogram.len(), window.len());
+
+            let percentiles = histogram.percentiles();
+            let values = [percentiles.p50, percentiles.p95, percentiles.p99, percentiles.p999];
+            assert!(values.windows(2).all(|pair| pair[0] <= pair[1]), "not monotonic: {:?}", values);
+
+            for (quantile, value) in [0.50, 0.95, 0.99, 0.999].into_iter().zip(values) {
+                assert_eq!(histogram.percentile(quantile), value);
+                // The result is a recorded sample with at least `quantile` of the window at or
+                // below it and less than `quantile` strictly below it
+                assert!(window.contains(&value));
+                let at_or_below = window.iter().filter(|&&s| s <= value).count() as f64;
+                let below = window.iter().filter(|&&s| s < value).count() as f64;
+                assert!(at_or_below >= quantile * window.len() as f64);
+                assert!(below < quantile * window.len() as f64);
+            }
+        }
+    }
+
+    #[test]
+    fn test_old_samples_leave_the_window() {
+        let mut histogram = LatencyHistogram::new(100);
+        for _ in 0..100 {
+            histogram.record(5_000.0);
+        }
+        assert_eq!(histogram.p50(), 5_000.0);
+
+        // A recovered endpoint's percentiles drop once the slow responses have aged out
+        for _ in 0..100 {
+            histogram.record(20.0);
+        }
+        assert_eq!(histogram.p999(), 20.0);
+    }
+}
@@ -0,0 +1,302 @@
+use crate::{
+    config::{SigningConfig, WebhookConfig},
+    endpoints::EndpointManager,
+    events::SystemEvent,
+    signing::RequestSigner,
+    types::CircuitBreakerState,
+};
+use chrono::{DateTime, Utc};
+use serde::Serialize;
+use serde_json::Value;
+use std::{sync::Arc, time::Duration};
+use tokio::sync::broadcast::error::RecvError;
+use tokio_util::sync::CancellationToken;
+use tracing::{debug, warn};
+use uuid::Uuid;
+
+const DELIVERY_RETRIES: usize = 3;
+const DEFAULT_RETRY_DELAY: Duration = Duration::from_secs(1);
+const WEBHOOK_TIMEOUT: Duration = Duration::from_secs(10);
+
+#[derive(Debug, Clone, Serialize)]
+pub struct CircuitBreakerNotification {
+    pub endpoint_id: Uuid,
+    pub endpoint_name: String,
+    pub old_state: &'static str,
+    pub new_state: &'static str,
+    pub failure_count: u32,
+    pub timestamp: DateTime<Utc>,
+}
+
+struct Webhook {
+    url: String,
+    signer: Option<RequestSigner>,
+}
+
+// Posts circuit breaker openings and closings to the configured URLs
+pub struct WebhookService {
+    webhooks: Arc<Vec<Webhook>>,
+    endpoint_manager: Arc<EndpointManager>,
+    client: reqwest::Client,
+    retry_delay: Duration,
+}
+
+impl WebhookService {
+    pub fn new(webhooks: &[WebhookConfig], endpoint_manager: Arc<EndpointManager>) -> Self {
+        let webhooks = webhooks.iter()
+            .map(|webhook| Webhook {
+                url: webhook.url.clone(),
+                // The signature goes in X-Webhook-Signature, over "<X-Webhook-Timestamp>.<body>"
+                signer: webhook.secret.as_ref().map(|secret| RequestSigner::new(&SigningConfig {
+                    secret: secret.clone(),
+                    header_name: "X-Webhook-Signature".to_string(),
+                    timestamp_header: "X-Webhook-Timestamp".to_string(),
+                })),
+            })
+            .collect();
+
+        Self {
+            webhooks: Arc::new(webhooks),
+            endpoint_manager,
+            client: reqwest::Client::builder()
+                .timeout(WEBHOOK_TIMEOUT)
+                .build()
+                .unwrap_or_default(),
+            retry_delay: DEFAULT_RETRY_DELAY,
+        }
+    }
+
+    // Lets tests exercise the retry without waiting out the real delay
+    #[cfg(test)]
+    pub fn with_retry_delay(mut self, retry_delay: Duration) -> Self {
+        self.retry_delay = retry_delay;
+        self
+    }
+
+    // Only openings and closings are reported; half-open trials are internal detail
+    async fn notification(&self, event: &SystemEvent) -> Option<CircuitBreakerNotification> {
+        let SystemEvent::CircuitBreakerStateChanged { endpoint_id, from, to } = event else {
+            return None;
+        };
+        if !matches!(to, CircuitBreakerState::Open | CircuitBreakerState::Closed) {
+            return None;
+        }
+
+        let failure_count = self.endpoint_manager.circuit_breakers()
+            .snapshot(*endpoint_id)
+            .map(|snapshot| snapshot.failure_count)
+            .unwrap_or_default();
+        Some(CircuitBreakerNotification {
+            endpoint_id: *endpoint_id,
+            endpoint_name: self.endpoint_manager.get_endpoint_name(*endpoint_id).await.unwrap_or_default(),
+            old_state: from.as_str(),
+            new_state: to.as_str(),
+            failure_count,
+            timestamp: Utc::now(),
+        })
+    }
+
+    // Fire-and-forget: each webhook is delivered on its own task so a slow receiver holds nothing up
+    pub fn notify(&self, notification: &CircuitBreakerNotification) {
+        let payload = match serde_json::to_value(notification) {
+            Ok(payload) => payload,
+            Err(e) => {
+                warn!("Failed to serialize webhook payload: {}", e);
+                return;
+            }
+        };
+
+        for index in 0..self.webhooks.len() {
+            let webhooks = self.webhooks.clone();
+            let client = self.client.clone();
+            let payload = payload.clone();
+            let retry_delay = self.retry_delay;
+            tokio::spawn(async move {
+                deliver(&client, &webhooks[index], &payload, retry_delay).await;
+            });
+        }
+    }
+
+    // Follows registry transitions on the event bus until shutdown
+    pub async fn watch_circuit_breakers(self: Arc<Self>, shutdown: CancellationToken) {
+        let mut events = self.endpoint_manager.circuit_breakers().event_bus().subscribe();
+        loop {
+            tokio::select! {
+                _ = shutdown.cancelled() => break,
+                event = events.recv() => match event {
+                    Ok(event) => {
+                        if let Some(notification) = self.notification(&event).await {
+                            self.notify(&notification);
+                        }
+                    }
+                    Err(RecvError::Lagged(skipped)) => {
+                        warn!("Webhook watcher skipped {} events", skipped);
+                    }
+                    Err(RecvError::Closed) => break,
+                },
+            }
+        }
+    }
+}
+
+async fn deliver(client: &reqwest::Client, webhook: &Webhook, payload: &Value, retry_delay: Duration) -> bool {
+    for attempt in 0..=DELIVERY_RETRIES {
+        if attempt > 0 {
+            tokio::time::sleep(retry_delay * attempt as u32).await;
+        }
+
+        let request = client.post(&webhook.url);
+        // Signed per attempt so every retry carries a fresh timestamp
+        let request = match &webhook.signer {
+            Some(signer) => match signer.sign(request, payload) {
+                Ok(request) => request,
+                Err(e) => {
+                    warn!("Failed to sign webhook for {}: {}", webhook.url, e);
+                    return false;
+                }
+            },
+            None => request.json(payload),
+        };
+
+        match request.send().await {
+            Ok(response) if response.status().is_success() => {
+                debug!("Delivered circuit breaker webhook to {}", webhook.url);
+                return true;
+            }
+            Ok(response) => warn!("Webhook {} answered {} (attempt {})", webhook.url, response.status(), attempt + 1),
+            Err(e) => warn!("Webhook {} failed (attempt {}): {}", webhook.url, attempt + 1, e),
+        }
+    }
+
+    warn!("Giving up on webhook {} after {} attempts", webhook.url, DELIVERY_RETRIES + 1);
+    false
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{circuit_breaker::CircuitBreakerRegistry, config::Config};
+    use wiremock::{
+        matchers::{header_exists, method},
+        Mock, MockServer, ResponseTemplate,
+    };
+
+    async fn setup(webhooks: Vec<WebhookConfig>) -> (Arc<WebhookService>, Uuid, CancellationToken) {
+        let mut config = Config::default();
+        config.endpoints.truncate(1);
+        config.webhooks = webhooks;
+        let circuit_breakers = Arc::new(CircuitBreakerRegistry::default().with_failure_threshold(2));
+        let endpoint_manager = Arc::new(
+            EndpointManager::new(config.endpoints.clone(), config.clone(), circuit_breakers).await.unwrap(),
+        );
+        let endpoint_id = endpoint_manager.get_endpoint_info().await[0].id;
+
+        let service = Arc::new(
+            WebhookService::new(&config.webhooks, endpoint_manager).with_retry_delay(Duration::from_millis(10)),
+        );
+        let shutdown = CancellationToken::new();
+        tokio::spawn(service.clone().watch_circuit_breakers(shutdown.clone()));
+        // Let the watcher subscribe before anything is published
+        tokio::task::yield_now().await;
+        (service, endpoint_id, shutdown)
+    }
+
+    async fn received_bodies(server: &MockServer, expected: usize) -> Vec<Value> {
+        for _ in 0..100 {
+            let requests = server.received_requests().await.unwrap_or_default();
+            if requests.len() >= expected {
+                return requests.iter().map(|request| serde_json::from_slice(&request.body).unwrap()).collect();
+            }
+            tokio::time::sleep(Duration::from_millis(10)).await;
+        }
+        panic!("expected {} webhook deliveries", expected);
+    }
+
+    #[tokio::test]
+    async fn test_open_and_close_are_posted() {
+        let server = MockServer::start().await;
+        Mock::given(method("POST"))
+            .and(header_exists("X-Webhook-Signature"))
+            .and(header_exists("X-Webhook-Timestamp"))
+            .respond_with(ResponseTemplate::new(200))
+            .mount(&server)
+            .await;
+        let (service, endpoint_id, shutdown) = setup(vec![WebhookConfig {
+            url: server.uri(),
+            secret: Some("webhook-secret".to_string()),
+        }]).await;
+        let circuit_breakers = service.endpoint_manager.circuit_breakers();
+
+        circuit_breakers.record_failure(endpoint_id);
+        circuit_breakers.record_failure(endpoint_id);
+        received_bodies(&server, 1).await;
+        circuit_breakers.record_success(endpoint_id);
+
+        let bodies = received_bodies(&server, 2).await;
+        assert_eq!(bodies[0]["endpoint_id"], endpoint_id.to_string());
+        assert!(!bodies[0]["endpoint_name"].as_str().unwrap().is_empty());
+        assert_eq!(bodies[0]["old_state"], "closed");
+        assert_eq!(bodies[0]["new_state"], "open");
+        assert_eq!(bodies[0]["failure_count"], 2);
+        assert!(bodies[0]["timestamp"].is_string());
+        assert_eq!(bodies[1]["old_state"], "open");
+        assert_eq!(bodies[1]["new_state"], "closed");
+        assert_eq!(bodies[1]["failure_count"], 0);
+        shutdown.cancel();
+    }
+
+    #[tokio::test]
+    async fn test_signature_covers_timestamp_and_body() {
+        let server = MockServer::start().await;
+        Mock::given(method("POST")).respond_with(ResponseTemplate::new(200)).mount(&server).await;
+        let (service, endpoint_id, shutdown) = setup(vec![WebhookConfig {
+            url: server.uri(),
+            secret: Some("webhook-secret".to_string()),
+        }]).await;
+
+        service.endpoint_manager.circuit_breakers().record_failure(endpoint_id);
+        service.endpoint_manager.circuit_breakers().record_failure(endpoint_id);
+        received_bodies(&server, 1).await;
+
+        let request = &server.received_requests().await.unwrap()[0];
+        let timestamp: u64 = request.headers.get("X-Webhook-Timestamp").unwrap().to_str().unwrap().parse().unwrap();
+        let signer = RequestSigner::new(&SigningConfig {
+            secret: "webhook-secret".to_string(),
+            header_name: "X-Webhook-Signature".to_string(),
+            timestamp_header: "X-Webhook-Timestamp".to_string(),
+        });
+        assert_eq!(
+            request.headers.get("X-Webhook-Signature").unwrap().to_str().unwrap(),
+            signer.signature(timestamp, &request.body),
+        );
+        shutdown.cancel();
+    }
+
+    #[tokio::test]
+    async fn test_failed_delivery_is_retried_three_times() {
+        let failing = MockServer::start().await;
+        Mock::given(method("POST")).respond_with(ResponseTemplate::new(500)).mount(&failing).await;
+        let (service, endpoint_id, shutdown) = setup(vec![WebhookConfig { url: failing.uri(), secret: None }]).await;
+
+        service.endpoint_manager.circuit_breakers().record_failure(endpoint_id);
+        service.endpoint_manager.circuit_breakers().record_failure(endpoint_id);
+
+        assert_eq!(received_bodies(&failing, 4).await.len(), 4);
+        tokio::time::sleep(Duration::from_millis(100)).await;
+        assert_eq!(failing.received_requests().await.unwrap().len(), 4);
+        shutdown.cancel();
+    }
+
+    #[tokio::test]
+    async fn test_half_open_transitions_are_not_posted() {
+        let (service, endpoint_id, shutdown) = setup(Vec::new()).await;
+
+        let event = SystemEvent::CircuitBreakerStateChanged {
+            endpoint_id,
+            from: CircuitBreakerState::Open,
+            to: CircuitBreakerState::HalfOpen,
+        };
+        assert!(service.notification(&event).await.is_none());
+        shutdown.cancel();
+    }
+}
@@ -1,28 +1,37 @@
 use crate::{
+    auth::AuthContext,
+    cache::CacheService,
     endpoints::EndpointManager,
     error::AppError,
+    metrics::MetricsService,
+    signing::RequestSignatureVerifier,
     types::RpcRequest,
 };
 use axum::extract::ws::{Message, WebSocket};
 use futures_util::{
-    stream::{SplitSink, SplitStream},
+    stream::SplitStream,
     SinkExt, StreamExt,
 };
+use lru::LruCache;
+use serde::{Deserialize, Serialize};
 use serde_json::{json, Value};
 use std::{
-    collections::HashMap,
+    collections::{hash_map::DefaultHasher, HashMap, HashSet, VecDeque},
+    hash::{Hash, Hasher},
+    num::NonZeroUsize,
     sync::{
         atomic::{AtomicU64, Ordering},
-        Arc,
+        Arc, Mutex,
     },
-    time::Duration,
+    time::{Duration, Instant},
 };
 use tokio::{
     sync::{RwLock, broadcast, mpsc},
-    time::{interval, timeout},
+    time::{interval, sleep},
     select,
 };
-use tokio_tungstenite::{connect_async, tungstenite::Message as TungsteniteMessage};
+use tokio_util::sync::CancellationToken;
+use tokio_tungstenite::{connect_async, tungstenite::Message as TungsteniteMessage, MaybeTlsStream, WebSocketStream};
 use tracing::{debug, error, info, warn};
 use uuid::Uuid;
 
@@ -33,31 +42,250 @@ pub struct WebSocketService {
     subscriptions: Arc<RwLock<HashMap<String, SubscriptionInfo>>>,
     connection_counter: Arc<AtomicU64>,
     broadcast_tx: broadcast::Sender<BroadcastMessage>,
+    signature_verifier: Option<Arc<RequestSignatureVerifier>>,
+    recent_subscriptions: Arc<RwLock<RecentSubscriptionCache>>,
+    dedup_window: Duration,
+    metrics_service: Option<Arc<MetricsService>>,
+    max_subscriptions_per_connection: usize,
+    // Shared connection to each upstream endpoint, keyed on its WebSocket URL
+    upstream_connections: Arc<Mutex<HashMap<String, mpsc::UnboundedSender<UpstreamCommand>>>>,
+    // Slot notifications from upstream drop slot-dependent entries from this cache
+    cache_service: Option<Arc<CacheService>>,
+    last_invalidated_slot: Arc<AtomicU64>,
+    shutdown: CancellationToken,
+}
+
+const RECENT_SUBSCRIPTION_CAPACITY: usize = 1000;
+const DEFAULT_DEDUP_WINDOW: Duration = Duration::from_millis(500);
+const DEFAULT_MAX_SUBSCRIPTIONS_PER_CONNECTION: usize = 100;
+// Upstream endpoints each client subscription is opened on
+const MAX_UPSTREAM_SUBSCRIPTIONS: usize = 3;
+const UPSTREAM_RECONNECT_INITIAL: Duration = Duration::from_millis(100);
+const UPSTREAM_RECONNECT_MAX: Duration = Duration::from_secs(30);
+const NOTIFICATION_DEDUP_CAPACITY: usize = 1024;
+
+// Subscribe requests seen recently, keyed on connection, caller, method and params, so a client
+// hammering the same subscribe gets its existing subscription back. Bounded, evicting the
+// least recently created entry.
+#[derive(Debug)]
+struct RecentSubscriptionCache {
+    entries: LruCache<u64, (String, Instant)>,
+}
+
+impl Default for RecentSubscriptionCache {
+    fn default() -> Self {
+        Self {
+            entries: LruCache::new(NonZeroUsize::new(RECENT_SUBSCRIPTION_CAPACITY).expect("non-zero capacity")),
+        }
+    }
+}
+
+impl RecentSubscriptionCache {
+    fn key(connection_id: Uuid, owner: Option<&str>, method: &str, params: &Value) -> u64 {
+        let mut hasher = DefaultHasher::new();
+        connection_id.hash(&mut hasher);
+        owner.hash(&mut hasher);
+        method.hash(&mut hasher);
+        // Object keys serialize in sorted order, so equal params hash equally
+        params.to_string().hash(&mut hasher);
+        hasher.finish()
+    }
+
+    // Peeks, so a hit doesn't keep an entry alive past the ones created after it
+    fn get(&self, key: u64, window: Duration) -> Option<&str> {
+        self.entries.peek(&key)
+            .filter(|(_, created)| created.elapsed() < window)
+            .map(|(subscription_id, _)| subscription_id.as_str())
+    }
+
+    fn insert(&mut self, key: u64, subscription_id: String) {
+        self.entries.put(key, (subscription_id, Instant::now()));
+    }
+
+    fn remove_subscription(&mut self, subscription_id: &str) {
+        let keys: Vec<u64> = self.entries.iter()
+            .filter(|(_, (id, _))| id == subscription_id)
+            .map(|(key, _)| *key)
+            .collect();
+        for key in keys {
+            self.entries.pop(&key);
+        }
+    }
+}
+
+// Notifications already forwarded for one client subscription, so the copy every upstream
+// endpoint sends only reaches the client once. Remembers the most recent hashes only.
+#[derive(Debug, Default)]
+struct NotificationDeduplicator {
+    seen: HashSet<u64>,
+    order: VecDeque<u64>,
+}
+
+impl NotificationDeduplicator {
+    fn first_sighting(&mut self, payload: &Value) -> bool {
+        let mut hasher = DefaultHasher::new();
+        payload.to_string().hash(&mut hasher);
+        let hash = hasher.finish();
+
+        if !self.seen.insert(hash) {
+            return false;
+        }
+        self.order.push_back(hash);
+        if self.order.len() > NOTIFICATION_DEDUP_CAPACITY {
+            if let Some(oldest) = self.order.pop_front() {
+                self.seen.remove(&oldest);
+            }
+        }
+        true
+    }
+}
+
+type UpstreamSocket = WebSocketStream<MaybeTlsStream<tokio::net::TcpStream>>;
+
+// One client subscription mirrored on one upstream endpoint
+#[derive(Debug, Clone)]
+struct UpstreamSubscription {
+    subscription_id: String,
+    connection_id: Uuid,
+    endpoint_id: Uuid,
+    ws_url: String,
+    method: String,
+    params: Value,
+    dedup: Arc<Mutex<NotificationDeduplicator>>,
+    cancel: CancellationToken,
+}
+
+#[derive(Debug)]
+enum UpstreamCommand {
+    Subscribe(UpstreamSubscription),
+    // Client subscription id
+    Unsubscribe(String),
+}
+
+// Client subscriptions mirrored over one upstream connection, and the ids the upstream gave
+// them on the current socket
+#[derive(Debug, Default)]
+struct UpstreamConnectionState {
+    subscriptions: HashMap<String, UpstreamSubscription>,
+    // Request id of each subscribe awaiting its reply
+    pending: HashMap<u64, UpstreamSubscription>,
+    // Upstream subscription id, as serialized JSON, -> client subscription id
+    routes: HashMap<String, String>,
+    // Client subscription id -> upstream subscription id
+    upstream_ids: HashMap<String, Value>,
+    next_request_id: u64,
+}
+
+impl UpstreamConnectionState {
+    fn subscribe_request(&mut self, upstream: UpstreamSubscription) -> Value {
+        self.next_request_id += 1;
+        let request = json!({"jsonrpc": "2.0", "id": self.next_request_id, "method": upstream.method, "params": upstream.params});
+        self.pending.insert(self.next_request_id, upstream);
+        request
+    }
+
+    fn unsubscribe_request(&mut self, upstream: &UpstreamSubscription, upstream_id: Value) -> Value {
+        // Replies to unsubscribes aren't tracked
+        self.next_request_id += 1;
+        json!({
+            "jsonrpc": "2.0",
+            "id": self.next_request_id,
+            "method": upstream.method.replace("Subscribe", "Unsubscribe"),
+            "params": [upstream_id],
+        })
+    }
+
+    // Subscribes for every registered subscription on a fresh socket
+    fn resubscribe_requests(&mut self) -> Vec<Value> {
+        self.pending.clear();
+        self.routes.clear();
+        self.upstream_ids.clear();
+        let subscriptions: Vec<UpstreamSubscription> = self.subscriptions.values().cloned().collect();
+        subscriptions.into_iter().map(|upstream| self.subscribe_request(upstream)).collect()
+    }
 }
 
 #[derive(Debug, Clone)]
 struct ConnectionInfo {
+    #[allow(dead_code)]
     id: Uuid,
     subscriptions: Vec<String>,
     last_ping: chrono::DateTime<chrono::Utc>,
+    #[allow(dead_code)]
     client_ip: Option<String>,
+    // Key presented at the upgrade; unsigned messages on the connection act as this caller
+    auth_context: Option<AuthContext>,
 }
 
 #[derive(Debug, Clone)]
 struct SubscriptionInfo {
     id: String,
     connection_id: Uuid,
+    // API key or signing pubkey of the caller that subscribed; only they may unsubscribe
+    owner: Option<String>,
     method: String,
     params: Value,
     endpoint_subscriptions: HashMap<Uuid, String>, // endpoint_id -> subscription_id
+    // Stops the upstream subscriptions when the client unsubscribes or disconnects
+    cancel: CancellationToken,
 }
 
 #[derive(Debug, Clone)]
-struct BroadcastMessage {
-    subscription_id: String,
-    data: Value,
+enum BroadcastMessage {
+    Subscription {
+        subscription_id: String,
+        // Only the connection that owns the subscription receives it
+        connection_id: Uuid,
+        // Notification method as the upstream sent it, e.g. accountNotification
+        method: String,
+        data: Value,
+    },
+    Admin {
+        payload: Value,
+        // None means every connection
+        recipients: Option<Arc<HashSet<Uuid>>>,
+    },
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum NotificationLevel {
+    Info,
+    Warn,
+    Critical,
+}
+
+#[derive(Debug, Clone, Deserialize)]
+pub struct AdminNotification {
+    pub message: String,
+    pub level: NotificationLevel,
+    #[serde(default = "default_notification_target")]
+    pub target: String,
+}
+
+fn default_notification_target() -> String {
+    "all".to_string()
+}
+
+#[derive(Debug, Clone, PartialEq)]
+enum NotificationTarget {
+    All,
+    Method(String),
+}
+
+impl NotificationTarget {
+    fn parse(target: &str) -> Result<Self, AppError> {
+        match target {
+            "all" => Ok(NotificationTarget::All),
+            _ => match target.strip_prefix("method:") {
+                Some(method) if !method.is_empty() => Ok(NotificationTarget::Method(method.to_string())),
+                _ => Err(AppError::validation(&format!("Invalid broadcast target: {}", target))),
+            },
+        }
+    }
 }
 
+#[allow(dead_code)]
 #[derive(Debug, Clone)]
 pub struct EndpointWebSocket {
     endpoint_id: Uuid,
@@ -66,8 +294,9 @@ pub struct EndpointWebSocket {
     tx: mpsc::UnboundedSender<TungsteniteMessage>,
 }
 
+#[allow(dead_code)]
 impl WebSocketService {
-    pub fn new(endpoint_manager: Arc<EndpointManager>) -> Self {
+    pub fn new(endpoint_manager: Arc<EndpointManager>, shutdown: CancellationToken) -> Self {
         let (broadcast_tx, _) = broadcast::channel(10000);
         
         Self {
@@ -76,10 +305,88 @@ impl WebSocketService {
             subscriptions: Arc::new(RwLock::new(HashMap::new())),
             connection_counter: Arc::new(AtomicU64::new(0)),
             broadcast_tx,
+            signature_verifier: None,
+            recent_subscriptions: Arc::new(RwLock::new(RecentSubscriptionCache::default())),
+            dedup_window: DEFAULT_DEDUP_WINDOW,
+            metrics_service: None,
+            max_subscriptions_per_connection: DEFAULT_MAX_SUBSCRIPTIONS_PER_CONNECTION,
+            upstream_connections: Arc::new(Mutex::new(HashMap::new())),
+            cache_service: None,
+            last_invalidated_slot: Arc::new(AtomicU64::new(0)),
+            shutdown,
         }
     }
 
-    pub async fn handle_connection(self: Arc<Self>, mut socket: WebSocket) {
+    pub fn with_dedup_window(mut self, dedup_window: Duration) -> Self {
+        self.dedup_window = dedup_window;
+        self
+    }
+
+    pub fn with_max_subscriptions_per_connection(mut self, limit: usize) -> Self {
+        self.max_subscriptions_per_connection = limit;
+        self
+    }
+
+    pub fn with_metrics_service(mut self, metrics_service: Arc<MetricsService>) -> Self {
+        self.metrics_service = Some(metrics_service);
+        self
+    }
+
+    pub fn with_signature_verifier(mut self, verifier: RequestSignatureVerifier) -> Self {
+        self.signature_verifier = Some(Arc::new(verifier));
+        self
+    }
+
+    pub fn with_cache_service(mut self, cache_service: Arc<CacheService>) -> Self {
+        self.cache_service = Some(cache_service);
+        self
+    }
+
+    // Keeps a slot subscription open upstream for as long as the service runs, so the cache
+    // hears about every new slot. Retries until some endpoint is able to serve it.
+    pub async fn subscribe_slot_updates(&self) {
+        let subscription = SubscriptionInfo {
+            id: Uuid::new_v4().to_string(),
+            // No client owns it, so its notifications only reach the cache
+            connection_id: Uuid::nil(),
+            owner: None,
+            method: "slotSubscribe".to_string(),
+            params: json!([]),
+            endpoint_subscriptions: HashMap::new(),
+            cancel: self.shutdown.child_token(),
+        };
+
+        let mut backoff = UPSTREAM_RECONNECT_INITIAL;
+        loop {
+            match self.create_endpoint_subscriptions(&subscription).await {
+                Ok(()) => {
+                    info!("Subscribed to upstream slot updates for cache invalidation");
+                    return;
+                }
+                Err(e) => debug!("Slot update subscription not started, retrying in {:?}: {}", backoff, e),
+            }
+            select! {
+                _ = self.shutdown.cancelled() => return,
+                _ = sleep(backoff) => {}
+            }
+            backoff = (backoff * 2).min(UPSTREAM_RECONNECT_MAX);
+        }
+    }
+
+    async fn invalidate_slot(&self, slot: u64) {
+        let Some(cache_service) = &self.cache_service else {
+            return;
+        };
+        // Every endpoint and every client slot subscription reports the same slots, so only
+        // the first report of a newer slot invalidates
+        if self.last_invalidated_slot.fetch_max(slot, Ordering::Relaxed) >= slot {
+            return;
+        }
+        debug!("Invalidating slot-dependent cache entries for slot {}", slot);
+        cache_service.invalidate_slot_based(slot).await;
+    }
+
+    pub async fn handle_connection(self: Arc<Self>, mut socket: WebSocket, auth_context: Option<AuthContext>) {
         let connection_id = Uuid::new_v4();
         let count = self.connection_counter.fetch_add(1, Ordering::Relaxed) + 1;
         
@@ -105,7 +412,8 @@ impl WebSocketService {
             id: connection_id,
             subscriptions: Vec::new(),
             last_ping: chrono::Utc::now(),
-            client_ip: None,
+            client_ip: auth_context.as_ref().and_then(|context| context.ip_address.clone()),
+            auth_context,
         };
 
         {
@@ -143,14 +451,23 @@ impl WebSocketService {
                     broadcast_msg = broadcast_rx.recv() => {
                         match broadcast_msg {
                             Ok(msg) => {
-                                let response = json!({
-                                    "jsonrpc": "2.0",
-                                    "method": "subscription",
-                                    "params": {
-                                        "subscription": msg.subscription_id,
-                                        "result": msg.data
+                                let response = match msg {
+                                    BroadcastMessage::Subscription { connection_id: owner, .. } if owner != connection_id => continue,
+                                    BroadcastMessage::Subscription { subscription_id, method, data, .. } => json!({
+                                        "jsonrpc": "2.0",
+                                        "method": method,
+                                        "params": {
+                                            "subscription": subscription_id,
+                                            "result": data
+                                        }
+                                    }),
+                                    BroadcastMessage::Admin { payload, recipients } => {
+                                        if recipients.is_some_and(|r| !r.contains(&connection_id)) {
+                                            continue;
+                                        }
+                                        payload
                                     }
-                                });
+                                };
                                 
                                 let ws_msg = Message::Text(response.to_string());
                                 if sender.send(ws_msg).await.is_err() {
@@ -190,7 +507,19 @@ impl WebSocketService {
         mut receiver: SplitStream<WebSocket>,
         tx: mpsc::UnboundedSender<Message>,
     ) {
-        while let Some(msg) = receiver.next().await {
+        loop {
+            let msg = select! {
+                _ = self.shutdown.cancelled() => {
+                    info!("Closing WebSocket connection for shutdown: {}", connection_id);
+                    let _ = tx.send(Message::Close(None));
+                    break;
+                }
+                msg = receiver.next() => match msg {
+                    Some(msg) => msg,
+                    None => break,
+                },
+            };
+
             match msg {
                 Ok(Message::Text(text)) => {
                     if let Err(e) = self.handle_text_message(connection_id, &text, &tx).await {
@@ -240,63 +569,146 @@ impl WebSocketService {
         tx: &mpsc::UnboundedSender<Message>,
     ) -> Result<(), AppError> {
         let request: Value = serde_json::from_str(text)?;
-        
-        // Handle batch requests
-        if request.is_array() {
-            let responses = self.handle_batch_request(connection_id, request).await?;
-            let response_text = serde_json::to_string(&responses)?;
-            tx.send(Message::Text(response_text)).map_err(|_| AppError::websocket("Failed to send response"))?;
-            return Ok(());
-        }
+        let is_batch = request.is_array();
 
-        // Handle single request
-        let rpc_request: RpcRequest = serde_json::from_value(request.clone())?;
-        
-        match rpc_request.method.as_str() {
-            // Subscription methods
-            method if method.ends_with("Subscribe") => {
-                let response = self.handle_subscribe(connection_id, &rpc_request).await?;
-                let response_text = serde_json::to_string(&response)?;
-                tx.send(Message::Text(response_text)).map_err(|_| AppError::websocket("Failed to send response"))?;
+        let calls = match self.authenticate_message(connection_id, request).await {
+            Ok(calls) => calls,
+            Err((id, e)) => {
+                // Keep the connection open so the client can retry with a valid signature
+                debug!("Rejected WebSocket message on {}: {}", connection_id, e);
+                let error_response = json!({
+                    "jsonrpc": "2.0",
+                    "id": id,
+                    "error": {
+                        "code": -32001,
+                        "message": "Invalid signature"
+                    }
+                });
+                tx.send(Message::Text(error_response.to_string())).map_err(|_| AppError::websocket("Failed to send response"))?;
+                return Ok(());
             }
-            
-            method if method.ends_with("Unsubscribe") => {
-                let response = self.handle_unsubscribe(connection_id, &rpc_request).await?;
-                let response_text = serde_json::to_string(&response)?;
-                tx.send(Message::Text(response_text)).map_err(|_| AppError::websocket("Failed to send response"))?;
+        };
+
+        // Each request in a batch runs as its own caller
+        let mut responses = Vec::with_capacity(calls.len());
+        for (call, caller) in calls {
+            let rpc_request: RpcRequest = serde_json::from_value(call)?;
+            responses.push(self.handle_call(connection_id, &rpc_request, caller.as_ref()).await?);
+        }
+
+        let response_text = match responses.pop() {
+            Some(response) if !is_batch => serde_json::to_string(&response)?,
+            last => {
+                responses.extend(last);
+                serde_json::to_string(&responses)?
             }
-            
+        };
+        tx.send(Message::Text(response_text)).map_err(|_| AppError::websocket("Failed to send response"))?;
+
+        Ok(())
+    }
+
+    async fn handle_call(
+        &self,
+        connection_id: Uuid,
+        request: &RpcRequest,
+        caller: Option<&AuthContext>,
+    ) -> Result<Value, AppError> {
+        match request.method.as_str() {
+            // Subscription methods
+            method if method.ends_with("Subscribe") => self.handle_subscribe(connection_id, request, caller).await,
+            method if method.ends_with("Unsubscribe") => self.handle_unsubscribe(connection_id, request, caller).await,
             // Regular RPC methods - proxy to endpoints
-            _ => {
-                let response = self.handle_rpc_request(&rpc_request).await?;
-                let response_text = serde_json::to_string(&response)?;
-                tx.send(Message::Text(response_text)).map_err(|_| AppError::websocket("Failed to send response"))?;
-            }
+            _ => self.handle_rpc_request(request).await,
         }
+    }
 
-        Ok(())
+    // Splits a message into its requests, each paired with the caller it runs as. With
+    // signing enabled every request must carry its own valid signature and runs as its
+    // signer; the error carries the offending request id. Otherwise requests run as the
+    // key presented at the upgrade.
+    async fn authenticate_message(
+        &self,
+        connection_id: Uuid,
+        request: Value,
+    ) -> Result<Vec<(Value, Option<AuthContext>)>, (Value, AppError)> {
+        let messages = match request {
+            Value::Array(messages) => messages,
+            message => vec![message],
+        };
+
+        let Some(verifier) = &self.signature_verifier else {
+            let caller = self.connections.read().await
+                .get(&connection_id)
+                .and_then(|conn| conn.auth_context.clone());
+            return Ok(messages.into_iter().map(|message| (message, caller.clone())).collect());
+        };
+
+        messages.iter()
+            .map(|message| {
+                verifier.verify(message)
+                    .map(|(payload, context)| (payload, Some(context)))
+                    .map_err(|e| (message.get("id").cloned().unwrap_or(Value::Null), e))
+            })
+            .collect()
     }
 
     async fn handle_subscribe(
         &self,
         connection_id: Uuid,
         request: &RpcRequest,
+        caller: Option<&AuthContext>,
     ) -> Result<Value, AppError> {
+        let params = request.params.clone().unwrap_or(Value::Null);
+        let owner = subscription_owner(caller);
+        let dedup_key = RecentSubscriptionCache::key(connection_id, owner.as_deref(), &request.method, &params);
         let subscription_id = Uuid::new_v4().to_string();
-        
+
+        // Claim the key before subscribing so concurrent duplicates see it too
+        {
+            let mut recent = self.recent_subscriptions.write().await;
+            if let Some(existing) = recent.get(dedup_key, self.dedup_window) {
+                debug!("Duplicate {} on connection {}, reusing subscription {}", request.method, connection_id, existing);
+                if let Some(metrics_service) = &self.metrics_service {
+                    metrics_service.record_subscription_dedup_hit();
+                }
+                return Ok(json!({
+                    "jsonrpc": "2.0",
+                    "id": request.id,
+                    "result": existing
+                }));
+            }
+            recent.insert(dedup_key, subscription_id.clone());
+        }
+
         // Create subscription info
         let sub_info = SubscriptionInfo {
             id: subscription_id.clone(),
             connection_id,
+            owner,
             method: request.method.clone(),
-            params: request.params.clone().unwrap_or(Value::Null),
+            params,
             endpoint_subscriptions: HashMap::new(),
+            cancel: self.shutdown.child_token(),
         };
 
-        // Add to connection's subscription list
+        // Add to connection's subscription list, unless it already holds as many as allowed
         {
             let mut connections = self.connections.write().await;
             if let Some(conn) = connections.get_mut(&connection_id) {
+                if conn.subscriptions.len() >= self.max_subscriptions_per_connection {
+                    drop(connections);
+                    self.recent_subscriptions.write().await.remove_subscription(&subscription_id);
+                    debug!("Subscription limit reached on connection {}", connection_id);
+                    return Ok(json!({
+                        "jsonrpc": "2.0",
+                        "id": request.id,
+                        "error": {
+                            "code": -32000,
+                            "message": "Subscription limit exceeded"
+                        }
+                    }));
+                }
                 conn.subscriptions.push(subscription_id.clone());
             }
         }
@@ -304,11 +716,16 @@ impl WebSocketService {
         // Store subscription
         {
             let mut subscriptions = self.subscriptions.write().await;
-            subscriptions.insert(subscription_id.clone(), sub_info);
+            subscriptions.insert(subscription_id.clone(), sub_info.clone());
         }
 
         // Subscribe to multiple endpoints for redundancy
-        self.create_endpoint_subscriptions(&subscription_id, request).await?;
+        if let Err(e) = self.create_endpoint_subscriptions(&sub_info).await {
+            self.recent_subscriptions.write().await.remove_subscription(&subscription_id);
+            self.subscriptions.write().await.remove(&subscription_id);
+            sub_info.cancel.cancel();
+            return Err(e);
+        }
 
         Ok(json!({
             "jsonrpc": "2.0",
@@ -321,6 +738,7 @@ impl WebSocketService {
         &self,
         connection_id: Uuid,
         request: &RpcRequest,
+        caller: Option<&AuthContext>,
     ) -> Result<Value, AppError> {
         let subscription_id = request.params
             .as_ref()
@@ -329,11 +747,25 @@ impl WebSocketService {
             .and_then(|v| v.as_str())
             .ok_or_else(|| AppError::invalid_request("Missing subscription ID"))?;
 
-        // Remove subscription
+        // Remove the subscription only for the connection and caller that own it
+        let owner = subscription_owner(caller);
         let removed = {
             let mut subscriptions = self.subscriptions.write().await;
-            subscriptions.remove(subscription_id).is_some()
+            match subscriptions.get(subscription_id) {
+                Some(subscription) if subscription.connection_id == connection_id && subscription.owner == owner => {
+                    subscriptions.remove(subscription_id)
+                }
+                _ => None,
+            }
         };
+        let Some(removed) = removed else {
+            return Ok(json!({
+                "jsonrpc": "2.0",
+                "id": request.id,
+                "result": false
+            }));
+        };
+        self.recent_subscriptions.write().await.remove_subscription(subscription_id);
 
         // Remove from connection
         {
@@ -344,12 +776,12 @@ impl WebSocketService {
         }
 
         // Cleanup endpoint subscriptions
-        self.cleanup_endpoint_subscriptions(subscription_id).await;
+        self.cleanup_endpoint_subscriptions(&removed);
 
         Ok(json!({
             "jsonrpc": "2.0",
             "id": request.id,
-            "result": removed
+            "result": true
         }))
     }
 
@@ -373,81 +805,280 @@ impl WebSocketService {
         Ok(response_json)
     }
 
-    async fn handle_batch_request(
-        &self,
-        connection_id: Uuid,
-        batch: Value,
-    ) -> Result<Vec<Value>, AppError> {
-        let requests = batch.as_array()
-            .ok_or_else(|| AppError::invalid_request("Invalid batch request"))?;
+    async fn create_endpoint_subscriptions(&self, subscription: &SubscriptionInfo) -> Result<(), AppError> {
+        // Healthy endpoints that serve WebSocket subscriptions
+        let mut ws_endpoints = Vec::new();
+        for endpoint in self.endpoint_manager.get_endpoint_info().await {
+            if endpoint.status != crate::types::EndpointStatus::Healthy {
+                continue;
+            }
+            let websocket_feature = self.endpoint_manager.get_endpoint_config(endpoint.id).await
+                .is_some_and(|config| config.features.iter().any(|feature| feature == "websocket"));
+            if websocket_feature || endpoint.url.starts_with("wss://") || endpoint.url.starts_with("ws://") {
+                ws_endpoints.push(endpoint);
+            }
+            if ws_endpoints.len() == MAX_UPSTREAM_SUBSCRIPTIONS {
+                break;
+            }
+        }
 
-        let mut responses = Vec::new();
-        
-        for request_value in requests {
-            let request: RpcRequest = serde_json::from_value(request_value.clone())?;
-            
-            let response = match request.method.as_str() {
-                method if method.ends_with("Subscribe") => {
-                    self.handle_subscribe(connection_id, &request).await?
-                }
-                method if method.ends_with("Unsubscribe") => {
-                    self.handle_unsubscribe(connection_id, &request).await?
-                }
+        if ws_endpoints.is_empty() {
+            return Err(AppError::websocket("No WebSocket endpoints available"));
+        }
+
+        // Shared by every upstream copy so each notification is forwarded once
+        let dedup = Arc::new(Mutex::new(NotificationDeduplicator::default()));
+        for endpoint in ws_endpoints {
+            self.subscribe_upstream(UpstreamSubscription {
+                subscription_id: subscription.id.clone(),
+                connection_id: subscription.connection_id,
+                endpoint_id: endpoint.id,
+                ws_url: endpoint.url.replace("https://", "wss://").replace("http://", "ws://"),
+                method: subscription.method.clone(),
+                params: subscription.params.clone(),
+                dedup: dedup.clone(),
+                cancel: subscription.cancel.clone(),
+            });
+        }
+
+        Ok(())
+    }
+
+    // Mirrors the subscription on the endpoint over the one connection every subscription there
+    // shares, opening it if there is none yet. Cancelling the subscription removes it again.
+    fn subscribe_upstream(&self, upstream: UpstreamSubscription) {
+        let (subscription_id, cancel) = (upstream.subscription_id.clone(), upstream.cancel.clone());
+        let commands = {
+            let mut connections = self.upstream_connections.lock().unwrap();
+            let commands = match connections.get(&upstream.ws_url) {
+                Some(commands) if !commands.is_closed() => commands.clone(),
                 _ => {
-                    self.handle_rpc_request(&request).await?
+                    let (commands, receiver) = mpsc::unbounded_channel();
+                    let service = self.clone();
+                    let ws_url = upstream.ws_url.clone();
+                    tokio::spawn(async move {
+                        service.run_upstream_connection(&ws_url, receiver).await;
+                        debug!("Upstream connection to {} closed", ws_url);
+                    });
+                    connections.insert(upstream.ws_url.clone(), commands.clone());
+                    commands
                 }
             };
-            
-            responses.push(response);
-        }
+            // Sent under the lock so the connection can't retire before it sees the subscribe
+            let _ = commands.send(UpstreamCommand::Subscribe(upstream));
+            commands
+        };
 
-        Ok(responses)
+        tokio::spawn(async move {
+            cancel.cancelled().await;
+            let _ = commands.send(UpstreamCommand::Unsubscribe(subscription_id));
+        });
     }
 
-    async fn create_endpoint_subscriptions(
-        &self,
-        subscription_id: &str,
-        request: &RpcRequest,
-    ) -> Result<(), AppError> {
-        // Get healthy endpoints that support WebSocket
-        let endpoints = self.endpoint_manager.get_endpoint_info().await;
-        let ws_endpoints: Vec<_> = endpoints
-            .into_iter()
-            .filter(|e| e.status == crate::types::EndpointStatus::Healthy)
-            .filter(|e| e.url.starts_with("wss://") || e.url.starts_with("ws://")) // WebSocket endpoints
-            .take(3) // Subscribe to top 3 endpoints
-            .collect();
+    // Keeps the connection to one endpoint open, reconnecting with exponential backoff and
+    // resubscribing everything on it whenever the socket drops, until no subscription is left
+    async fn run_upstream_connection(&self, ws_url: &str, mut commands: mpsc::UnboundedReceiver<UpstreamCommand>) {
+        let mut state = UpstreamConnectionState::default();
+        let mut backoff = UPSTREAM_RECONNECT_INITIAL;
+        loop {
+            match self.stream_upstream_connection(ws_url, &mut commands, &mut state, &mut backoff).await {
+                Ok(()) => return,
+                Err(e) => warn!("Upstream connection to {} lost, retrying in {:?}: {}", ws_url, backoff, e),
+            }
 
-        if ws_endpoints.is_empty() {
-            return Err(AppError::websocket("No WebSocket endpoints available"));
+            // Keep taking commands while disconnected, so subscriptions can still come and go
+            let retry = sleep(backoff);
+            tokio::pin!(retry);
+            loop {
+                select! {
+                    _ = self.shutdown.cancelled() => return,
+                    _ = &mut retry => break,
+                    command = commands.recv() => {
+                        let Some(command) = command else { return };
+                        if let Ok(false) = self.apply_upstream_command(ws_url, &mut commands, &mut state, command, None).await {
+                            return;
+                        }
+                    }
+                }
+            }
+            backoff = (backoff * 2).min(UPSTREAM_RECONNECT_MAX);
         }
+    }
 
-        for endpoint in ws_endpoints {
-            self.create_single_endpoint_subscription(subscription_id, &endpoint.url, request).await?;
+    // Applies a command, over the socket when connected. Returns false once the connection has
+    // retired for lack of subscriptions.
+    async fn apply_upstream_command(
+        &self,
+        ws_url: &str,
+        commands: &mut mpsc::UnboundedReceiver<UpstreamCommand>,
+        state: &mut UpstreamConnectionState,
+        command: UpstreamCommand,
+        mut socket: Option<&mut UpstreamSocket>,
+    ) -> Result<bool, AppError> {
+        let mut next = Some(command);
+        while let Some(command) = next.take() {
+            let request = match command {
+                UpstreamCommand::Subscribe(upstream) => {
+                    state.subscriptions.insert(upstream.subscription_id.clone(), upstream.clone());
+                    socket.is_some().then(|| state.subscribe_request(upstream))
+                }
+                UpstreamCommand::Unsubscribe(subscription_id) => {
+                    let upstream = state.subscriptions.remove(&subscription_id);
+                    let upstream_id = state.upstream_ids.remove(&subscription_id);
+                    match (upstream, upstream_id) {
+                        (Some(upstream), Some(upstream_id)) => {
+                            state.routes.remove(&upstream_id.to_string());
+                            Some(state.unsubscribe_request(&upstream, upstream_id))
+                        }
+                        _ => None,
+                    }
+                }
+            };
+            if let (Some(socket), Some(request)) = (socket.as_deref_mut(), request) {
+                socket.send(TungsteniteMessage::Text(request.to_string())).await
+                    .map_err(|e| AppError::websocket(&format!("Failed to send to upstream: {}", e)))?;
+            }
+
+            if state.subscriptions.is_empty() {
+                next = self.retire_upstream_connection(ws_url, commands);
+                if next.is_none() {
+                    return Ok(false);
+                }
+            }
         }
+        Ok(true)
+    }
 
-        Ok(())
+    // Unregisters a connection left without subscriptions, unless a command reached it in the
+    // meantime, which is returned instead. Subscribes are sent under the same lock, so none
+    // can slip in after the check.
+    fn retire_upstream_connection(
+        &self,
+        ws_url: &str,
+        commands: &mut mpsc::UnboundedReceiver<UpstreamCommand>,
+    ) -> Option<UpstreamCommand> {
+        let mut connections = self.upstream_connections.lock().unwrap();
+        let command = commands.try_recv().ok();
+        if command.is_none() {
+            connections.remove(ws_url);
+        }
+        command
     }
 
-    async fn create_single_endpoint_subscription(
+    // Connects, subscribes everything registered on the connection and forwards notifications.
+    // Returns Ok once retired or shut down, and an error when the socket fails.
+    async fn stream_upstream_connection(
         &self,
-        _subscription_id: &str,
-        endpoint_url: &str,
-        _request: &RpcRequest,
+        ws_url: &str,
+        commands: &mut mpsc::UnboundedReceiver<UpstreamCommand>,
+        state: &mut UpstreamConnectionState,
+        backoff: &mut Duration,
     ) -> Result<(), AppError> {
-        // Convert HTTP(S) URL to WebSocket URL
-        let ws_url = endpoint_url.replace("https://", "wss://").replace("http://", "ws://");
-        
-        // This is a simplified implementation
-        // In practice, you'd maintain persistent connections to endpoints
-        debug!("Would create subscription to endpoint: {}", ws_url);
-        
-        Ok(())
+        let connected = select! {
+            _ = self.shutdown.cancelled() => return Ok(()),
+            connected = connect_async(ws_url) => connected,
+        };
+        let (mut socket, _) = connected
+            .map_err(|e| AppError::websocket(&format!("Failed to connect: {}", e)))?;
+
+        for request in state.resubscribe_requests() {
+            socket.send(TungsteniteMessage::Text(request.to_string())).await
+                .map_err(|e| AppError::websocket(&format!("Failed to subscribe: {}", e)))?;
+        }
+
+        loop {
+            let message = select! {
+                _ = self.shutdown.cancelled() => {
+                    let _ = socket.close(None).await;
+                    return Ok(());
+                }
+                command = commands.recv() => {
+                    let Some(command) = command else { return Ok(()) };
+                    if !self.apply_upstream_command(ws_url, commands, state, command, Some(&mut socket)).await? {
+                        // Closing the socket drops whatever the endpoint still had open on it
+                        let _ = socket.close(None).await;
+                        return Ok(());
+                    }
+                    continue;
+                }
+                message = socket.next() => message,
+            };
+
+            let text = match message {
+                Some(Ok(TungsteniteMessage::Text(text))) => text,
+                Some(Ok(TungsteniteMessage::Close(_))) | None => {
+                    return Err(AppError::websocket("Upstream closed the connection"));
+                }
+                Some(Err(e)) => return Err(AppError::websocket(&e.to_string())),
+                Some(Ok(_)) => continue,
+            };
+            let Ok(message) = serde_json::from_str::<Value>(&text) else {
+                continue;
+            };
+
+            if let Some(request_id) = message.get("id").and_then(Value::as_u64) {
+                let Some(upstream) = state.pending.remove(&request_id) else {
+                    continue;
+                };
+                if let Some(error) = message.get("error") {
+                    // Tried again on the next reconnect
+                    warn!("{} rejected subscription {}: {}", ws_url, upstream.subscription_id, error);
+                    continue;
+                }
+                let Some(id) = message.get("result").cloned() else {
+                    continue;
+                };
+                if !state.subscriptions.contains_key(&upstream.subscription_id) {
+                    // Unsubscribed while the subscribe was in flight
+                    let unsubscribe = state.unsubscribe_request(&upstream, id);
+                    socket.send(TungsteniteMessage::Text(unsubscribe.to_string())).await
+                        .map_err(|e| AppError::websocket(&format!("Failed to unsubscribe: {}", e)))?;
+                    continue;
+                }
+                if let Some(subscription) = self.subscriptions.write().await.get_mut(&upstream.subscription_id) {
+                    let endpoint_subscription_id = id.as_str().map(str::to_string).unwrap_or_else(|| id.to_string());
+                    subscription.endpoint_subscriptions.insert(upstream.endpoint_id, endpoint_subscription_id);
+                }
+                // Subscribed again, so the next failure starts from a short delay
+                *backoff = UPSTREAM_RECONNECT_INITIAL;
+                state.routes.insert(id.to_string(), upstream.subscription_id.clone());
+                state.upstream_ids.insert(upstream.subscription_id, id);
+                continue;
+            }
+
+            let params = &message["params"];
+            let Some(upstream) = params.get("subscription")
+                .and_then(|id| state.routes.get(&id.to_string()))
+                .and_then(|subscription_id| state.subscriptions.get(subscription_id))
+            else {
+                continue;
+            };
+            let data = params.get("result").cloned().unwrap_or(Value::Null);
+            if !upstream.dedup.lock().unwrap().first_sighting(&data) {
+                continue;
+            }
+            if upstream.method == "slotSubscribe" {
+                if let Some(slot) = data.get("slot").and_then(Value::as_u64) {
+                    self.invalidate_slot(slot).await;
+                }
+            }
+            if upstream.connection_id.is_nil() {
+                continue;
+            }
+            // A send error only means nobody is listening right now
+            let _ = self.broadcast_tx.send(BroadcastMessage::Subscription {
+                subscription_id: upstream.subscription_id.clone(),
+                connection_id: upstream.connection_id,
+                method: message["method"].as_str().unwrap_or("subscription").to_string(),
+                data,
+            });
+        }
     }
 
-    async fn cleanup_endpoint_subscriptions(&self, _subscription_id: &str) {
-        // Cleanup subscriptions on all endpoints
-        debug!("Cleaning up endpoint subscriptions");
+    fn cleanup_endpoint_subscriptions(&self, subscription: &SubscriptionInfo) {
+        // Each shared upstream connection unsubscribes it from its endpoint
+        subscription.cancel.cancel();
+        debug!("Cleaning up endpoint subscriptions for {}", subscription.id);
     }
 
     async fn cleanup_connection(&self, connection_id: Uuid) {
@@ -463,8 +1094,9 @@ impl WebSocketService {
         {
             let mut subs = self.subscriptions.write().await;
             for sub_id in subscriptions {
-                subs.remove(&sub_id);
-                self.cleanup_endpoint_subscriptions(&sub_id).await;
+                if let Some(subscription) = subs.remove(&sub_id) {
+                    self.cleanup_endpoint_subscriptions(&subscription);
+                }
             }
         }
     }
@@ -490,6 +1122,46 @@ impl WebSocketService {
         Ok(())
     }
 
+    pub async fn broadcast_admin_notification(&self, notification: AdminNotification) -> Result<usize, AppError> {
+        let target = NotificationTarget::parse(&notification.target)?;
+        let recipients = self.resolve_notification_recipients(&target).await;
+        let recipient_count = match &recipients {
+            Some(recipients) => recipients.len(),
+            None => self.connections.read().await.len(),
+        };
+
+        let payload = json!({
+            "jsonrpc": "2.0",
+            "method": "adminNotification",
+            "params": {
+                "level": notification.level,
+                "message": notification.message,
+            }
+        });
+
+        // A send error only means nobody is listening right now
+        let _ = self.broadcast_tx.send(BroadcastMessage::Admin {
+            payload,
+            recipients: recipients.map(Arc::new),
+        });
+
+        info!("Admin notification broadcast to {} connections (target: {})", recipient_count, notification.target);
+        Ok(recipient_count)
+    }
+
+    async fn resolve_notification_recipients(&self, target: &NotificationTarget) -> Option<HashSet<Uuid>> {
+        match target {
+            NotificationTarget::All => None,
+            NotificationTarget::Method(method) => {
+                let subscriptions = self.subscriptions.read().await;
+                Some(subscriptions.values()
+                    .filter(|sub| &sub.method == method)
+                    .map(|sub| sub.connection_id)
+                    .collect())
+            }
+        }
+    }
+
     pub async fn get_connection_stats(&self) -> serde_json::Value {
         let connections = self.connections.read().await;
         let subscriptions = self.subscriptions.read().await;
@@ -502,4 +1174,554 @@ impl WebSocketService {
             }
         })
     }
-}
\ No newline at end of file
+}
+
+// Identity a subscription belongs to: the caller's API key, or the pubkey that signed
+// the subscribe
+fn subscription_owner(caller: Option<&AuthContext>) -> Option<String> {
+    caller.and_then(|context| context.api_key.clone().or_else(|| context.user.clone()))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{circuit_breaker::CircuitBreakerRegistry, config::Config};
+    use tokio::time::timeout;
+
+    async fn service_with_subscriptions(subs: &[(Uuid, &str)]) -> WebSocketService {
+        let endpoint_manager = Arc::new(EndpointManager::new(Vec::new(), Config::default(), Arc::new(CircuitBreakerRegistry::default())).await.unwrap());
+        let service = WebSocketService::new(endpoint_manager, CancellationToken::new());
+
+        let mut connections = service.connections.write().await;
+        let mut subscriptions = service.subscriptions.write().await;
+        for (connection_id, method) in subs {
+            let subscription_id = Uuid::new_v4().to_string();
+            connections.entry(*connection_id).or_insert_with(|| ConnectionInfo {
+                id: *connection_id,
+                subscriptions: Vec::new(),
+                last_ping: chrono::Utc::now(),
+                client_ip: None,
+                auth_context: None,
+            }).subscriptions.push(subscription_id.clone());
+            subscriptions.insert(subscription_id.clone(), SubscriptionInfo {
+                id: subscription_id,
+                connection_id: *connection_id,
+                owner: None,
+                method: method.to_string(),
+                params: Value::Null,
+                endpoint_subscriptions: HashMap::new(),
+                cancel: CancellationToken::new(),
+            });
+        }
+        drop(subscriptions);
+        drop(connections);
+
+        service
+    }
+
+    fn notification(target: &str) -> AdminNotification {
+        AdminNotification {
+            message: "network congestion, retry later".to_string(),
+            level: NotificationLevel::Warn,
+            target: target.to_string(),
+        }
+    }
+
+    #[tokio::test]
+    async fn test_broadcast_to_all() {
+        let (a, b) = (Uuid::new_v4(), Uuid::new_v4());
+        let service = service_with_subscriptions(&[(a, "slotSubscribe"), (b, "accountSubscribe")]).await;
+        let mut rx = service.broadcast_tx.subscribe();
+
+        let recipients = service.broadcast_admin_notification(notification("all")).await.unwrap();
+        assert_eq!(recipients, 2);
+
+        match rx.recv().await.unwrap() {
+            BroadcastMessage::Admin { payload, recipients } => {
+                assert!(recipients.is_none());
+                assert_eq!(payload["method"], "adminNotification");
+                assert_eq!(payload["params"]["level"], "warn");
+                assert_eq!(payload["params"]["message"], "network congestion, retry later");
+            }
+            other => panic!("unexpected broadcast: {:?}", other),
+        }
+    }
+
+    #[tokio::test]
+    async fn test_broadcast_to_method_subscribers() {
+        let (a, b) = (Uuid::new_v4(), Uuid::new_v4());
+        let service = service_with_subscriptions(&[(a, "slotSubscribe"), (b, "accountSubscribe")]).await;
+        let mut rx = service.broadcast_tx.subscribe();
+
+        let recipients = service.broadcast_admin_notification(notification("method:slotSubscribe")).await.unwrap();
+        assert_eq!(recipients, 1);
+
+        match rx.recv().await.unwrap() {
+            BroadcastMessage::Admin { recipients, .. } => {
+                let recipients = recipients.unwrap();
+                assert!(recipients.contains(&a));
+                assert!(!recipients.contains(&b));
+            }
+            other => panic!("unexpected broadcast: {:?}", other),
+        }
+    }
+
+    #[test]
+    fn test_invalid_target_rejected() {
+        assert!(NotificationTarget::parse("method:").is_err());
+        assert!(NotificationTarget::parse("everyone").is_err());
+        assert_eq!(
+            NotificationTarget::parse("method:slotSubscribe").unwrap(),
+            NotificationTarget::Method("slotSubscribe".to_string())
+        );
+    }
+
+    #[tokio::test]
+    async fn test_invalid_signature_rejected_without_disconnect() {
+        use crate::config::{MessageSigningConfig, SignatureAlgorithm};
+
+        let connection_id = Uuid::new_v4();
+        let service = service_with_subscriptions(&[]).await
+            .with_signature_verifier(RequestSignatureVerifier::new(&MessageSigningConfig {
+                enabled: true,
+                algorithm: SignatureAlgorithm::Ed25519,
+                ..MessageSigningConfig::default()
+            }));
+        let (tx, mut rx) = mpsc::unbounded_channel();
+
+        let forged = json!({
+            "jsonrpc": "2.0",
+            "id": 7,
+            "method": "getSlot",
+            "pubkey": bs58::encode([1u8; 32]).into_string(),
+            "signature": bs58::encode([2u8; 64]).into_string(),
+        });
+        service.handle_text_message(connection_id, &forged.to_string(), &tx).await.unwrap();
+
+        let Some(Message::Text(reply)) = rx.recv().await else {
+            panic!("expected an error reply");
+        };
+        let reply: Value = serde_json::from_str(&reply).unwrap();
+        assert_eq!(reply["id"], 7);
+        assert_eq!(reply["error"]["code"], -32001);
+        assert_eq!(reply["error"]["message"], "Invalid signature");
+    }
+
+    // Service with one healthy endpoint that accepts subscribes, whose upstream never connects
+    async fn service_with_unreachable_upstream() -> WebSocketService {
+        let mut config = Config::default();
+        let mut endpoint = config.endpoints[0].clone();
+        endpoint.url = "ws://127.0.0.1:1".to_string();
+        config.endpoints = vec![endpoint.clone()];
+        let endpoint_manager = Arc::new(EndpointManager::new(vec![endpoint], config, Arc::new(CircuitBreakerRegistry::default())).await.unwrap());
+        let endpoint_id = endpoint_manager.get_endpoint_info().await[0].id;
+        endpoint_manager.update_endpoint_status(endpoint_id, crate::types::EndpointStatus::Healthy).await;
+        WebSocketService::new(endpoint_manager, CancellationToken::new())
+    }
+
+    #[tokio::test]
+    async fn test_duplicate_subscribes_reuse_subscription() {
+        let metrics_service = crate::test_server::shared_metrics();
+        let service = service_with_unreachable_upstream().await
+            .with_metrics_service(metrics_service.clone());
+        let dedup_hits = || async { metrics_service.get_metrics().await["websocket"]["subscription_dedup_hits"].as_u64().unwrap() };
+        let before = dedup_hits().await;
+
+        let connection_id = Uuid::new_v4();
+        let request: RpcRequest = serde_json::from_value(json!({
+            "jsonrpc": "2.0",
+            "id": 1,
+            "method": "accountSubscribe",
+            "params": ["A", {"encoding": "base64", "commitment": "confirmed"}]
+        })).unwrap();
+
+        let mut ids = HashSet::new();
+        for _ in 0..10 {
+            let response = service.handle_subscribe(connection_id, &request, None).await.unwrap();
+            ids.insert(response["result"].as_str().unwrap().to_string());
+            tokio::time::sleep(Duration::from_millis(5)).await;
+        }
+        assert_eq!(ids.len(), 1);
+        assert_eq!(service.subscriptions.read().await.len(), 1);
+        assert_eq!(dedup_hits().await, before + 9);
+
+        // The same params on another connection are a separate subscription
+        let other = service.handle_subscribe(Uuid::new_v4(), &request, None).await.unwrap();
+        assert!(!ids.contains(other["result"].as_str().unwrap()));
+
+        // After unsubscribing, subscribing again creates a fresh subscription
+        let subscription_id = ids.into_iter().next().unwrap();
+        let unsubscribe: RpcRequest = serde_json::from_value(json!({
+            "jsonrpc": "2.0", "id": 2, "method": "accountUnsubscribe", "params": [subscription_id]
+        })).unwrap();
+        service.handle_unsubscribe(connection_id, &unsubscribe, None).await.unwrap();
+        let resubscribed = service.handle_subscribe(connection_id, &request, None).await.unwrap();
+        assert_ne!(resubscribed["result"], subscription_id);
+    }
+
+    fn signed_by(seed: u8, mut message: Value) -> Value {
+        use ed25519_dalek::{Signer, SigningKey};
+
+        message["timestamp"] = json!(chrono::Utc::now().timestamp());
+        message["nonce"] = json!(Uuid::new_v4().to_string());
+        let key = SigningKey::from_bytes(&[seed; 32]);
+        let signature = key.sign(crate::signing::canonical_json(&message).as_bytes());
+        message["pubkey"] = json!(bs58::encode(key.verifying_key().as_bytes()).into_string());
+        message["signature"] = json!(bs58::encode(signature.to_bytes()).into_string());
+        message
+    }
+
+    async fn exchange(service: &WebSocketService, connection_id: Uuid, message: &Value) -> Value {
+        let (tx, mut rx) = mpsc::unbounded_channel();
+        service.handle_text_message(connection_id, &message.to_string(), &tx).await.unwrap();
+        let Some(Message::Text(reply)) = rx.recv().await else {
+            panic!("expected a reply");
+        };
+        serde_json::from_str(&reply).unwrap()
+    }
+
+    #[tokio::test]
+    async fn test_signed_batches_act_as_each_signer() {
+        use crate::config::{MessageSigningConfig, SignatureAlgorithm};
+
+        let service = service_with_unreachable_upstream().await
+            .with_signature_verifier(RequestSignatureVerifier::new(&MessageSigningConfig {
+                enabled: true,
+                algorithm: SignatureAlgorithm::Ed25519,
+                ..MessageSigningConfig::default()
+            }));
+        let connection_id = Uuid::new_v4();
+        let subscribe = |id: u64| json!({"jsonrpc": "2.0", "id": id, "method": "slotSubscribe", "params": []});
+        let unsubscribe = |subscription_id: &Value| {
+            json!({"jsonrpc": "2.0", "id": 9, "method": "slotUnsubscribe", "params": [subscription_id]})
+        };
+
+        // Two signers in one batch each get their own subscription
+        let batch = json!([signed_by(1, subscribe(1)), signed_by(2, subscribe(2))]);
+        let replies = exchange(&service, connection_id, &batch).await;
+        let (first, second) = (&replies[0]["result"], &replies[1]["result"]);
+        assert_ne!(first, second);
+        let owner = |seed: u8| {
+            let key = ed25519_dalek::SigningKey::from_bytes(&[seed; 32]);
+            Some(bs58::encode(key.verifying_key().as_bytes()).into_string())
+        };
+        let subscriptions = service.subscriptions.read().await;
+        assert_eq!(subscriptions[first.as_str().unwrap()].owner, owner(1));
+        assert_eq!(subscriptions[second.as_str().unwrap()].owner, owner(2));
+        drop(subscriptions);
+
+        // Neither another signer nor another connection can cancel the first subscription
+        let reply = exchange(&service, connection_id, &signed_by(2, unsubscribe(first))).await;
+        assert_eq!(reply["result"], false);
+        let reply = exchange(&service, Uuid::new_v4(), &signed_by(1, unsubscribe(first))).await;
+        assert_eq!(reply["result"], false);
+        assert!(service.subscriptions.read().await.contains_key(first.as_str().unwrap()));
+
+        let owned = signed_by(1, unsubscribe(first));
+        assert_eq!(exchange(&service, connection_id, &owned).await["result"], true);
+
+        // Replaying a captured message is refused
+        let replayed = exchange(&service, connection_id, &owned).await;
+        assert_eq!(replayed["error"]["message"], "Invalid signature");
+    }
+
+    #[test]
+    fn test_recent_subscription_cache_is_bounded() {
+        let mut recent = RecentSubscriptionCache::default();
+        for i in 0..RECENT_SUBSCRIPTION_CAPACITY as u64 + 10 {
+            recent.insert(i, i.to_string());
+        }
+        assert_eq!(recent.entries.len(), RECENT_SUBSCRIPTION_CAPACITY);
+        assert_eq!(recent.get(RECENT_SUBSCRIPTION_CAPACITY as u64 + 9, Duration::from_secs(1)), Some("1009"));
+        assert_eq!(recent.get(1, Duration::ZERO), None);
+        // The first ones created are the ones evicted
+        assert_eq!(recent.get(9, Duration::from_secs(1)), None);
+        assert_eq!(recent.get(10, Duration::from_secs(1)), Some("10"));
+
+        recent.remove_subscription("10");
+        assert_eq!(recent.get(10, Duration::from_secs(1)), None);
+    }
+
+    // Upstream node that answers each subscribe with subscription 7, 8, ... and then sends
+    // `notifications` on it. With `drop_first`, the first connection is closed right after the
+    // handshake.
+    async fn mock_upstream(notifications: Vec<Value>, drop_first: bool) -> (String, Arc<AtomicU64>, Arc<Mutex<Vec<String>>>) {
+        let listener = tokio::net::TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let url = format!("http://{}", listener.local_addr().unwrap());
+        let connections = Arc::new(AtomicU64::new(0));
+        let received = Arc::new(Mutex::new(Vec::new()));
+
+        tokio::spawn({
+            let (connections, received) = (connections.clone(), received.clone());
+            async move {
+                loop {
+                    let (stream, _) = listener.accept().await.unwrap();
+                    let mut socket = tokio_tungstenite::accept_async(stream).await.unwrap();
+                    if connections.fetch_add(1, Ordering::SeqCst) == 0 && drop_first {
+                        let _ = socket.close(None).await;
+                        continue;
+                    }
+                    let notifications = notifications.clone();
+                    let received = received.clone();
+                    tokio::spawn(async move {
+                        let mut next_subscription = 7;
+                        while let Some(Ok(TungsteniteMessage::Text(text))) = socket.next().await {
+                            let request: Value = serde_json::from_str(&text).unwrap();
+                            let method = request["method"].as_str().unwrap().to_string();
+                            received.lock().unwrap().push(method.clone());
+                            if !method.ends_with("Subscribe") {
+                                continue;
+                            }
+                            let subscription = next_subscription;
+                            next_subscription += 1;
+                            let reply = json!({"jsonrpc": "2.0", "id": request["id"], "result": subscription});
+                            socket.send(TungsteniteMessage::Text(reply.to_string())).await.unwrap();
+                            for result in &notifications {
+                                let notification = json!({
+                                    "jsonrpc": "2.0",
+                                    "method": "slotNotification",
+                                    "params": {"subscription": subscription, "result": result},
+                                });
+                                socket.send(TungsteniteMessage::Text(notification.to_string())).await.unwrap();
+                            }
+                        }
+                    });
+                }
+            }
+        });
+        (url, connections, received)
+    }
+
+    #[tokio::test]
+    async fn test_subscription_fans_out_to_upstream_endpoints() {
+        let slot = |n: u64| json!({"slot": n, "parent": n - 1, "root": n - 32});
+        let (flaky_url, flaky_connections, flaky_received) = mock_upstream(vec![slot(100), slot(101)], true).await;
+        let (steady_url, _, steady_received) = mock_upstream(vec![slot(100), slot(101), slot(102)], false).await;
+
+        let mut config = Config::default();
+        let template = config.endpoints[0].clone();
+        config.endpoints = [("flaky", flaky_url), ("steady", steady_url)].into_iter()
+            .map(|(name, url)| crate::config::EndpointConfig {
+                name: name.to_string(),
+                url,
+                features: vec!["websocket".to_string()],
+                ..template.clone()
+            })
+            .collect();
+        let endpoint_manager = Arc::new(EndpointManager::new(config.endpoints.clone(), config, Arc::new(CircuitBreakerRegistry::default())).await.unwrap());
+        for endpoint in endpoint_manager.get_endpoint_info().await {
+            endpoint_manager.update_endpoint_status(endpoint.id, crate::types::EndpointStatus::Healthy).await;
+        }
+        let service = WebSocketService::new(endpoint_manager, CancellationToken::new());
+        let mut rx = service.broadcast_tx.subscribe();
+
+        let connection_id = Uuid::new_v4();
+        let request: RpcRequest = serde_json::from_value(json!({
+            "jsonrpc": "2.0", "id": 1, "method": "slotSubscribe", "params": []
+        })).unwrap();
+        let response = service.handle_subscribe(connection_id, &request, None).await.unwrap();
+        let subscription_id = response["result"].as_str().unwrap().to_string();
+
+        // Both endpoints send slots 100 and 101, but each reaches the client once
+        let mut slots = Vec::new();
+        while slots.len() < 3 {
+            match timeout(Duration::from_secs(5), rx.recv()).await.expect("missing notification").unwrap() {
+                BroadcastMessage::Subscription { subscription_id: id, connection_id: owner, method, data } => {
+                    assert_eq!((id.as_str(), owner, method.as_str()), (subscription_id.as_str(), connection_id, "slotNotification"));
+                    slots.push(data["slot"].as_u64().unwrap());
+                }
+                other => panic!("unexpected broadcast: {:?}", other),
+            }
+        }
+        slots.sort_unstable();
+        assert_eq!(slots, vec![100, 101, 102]);
+        assert!(timeout(Duration::from_millis(300), rx.recv()).await.is_err(), "duplicate notification forwarded");
+
+        // The endpoint that dropped the first connection was reconnected and resubscribed
+        assert_eq!(flaky_connections.load(Ordering::SeqCst), 2);
+        assert_eq!(service.subscriptions.read().await[&subscription_id].endpoint_subscriptions.len(), 2);
+
+        // Unsubscribing closes the subscription on every endpoint
+        let unsubscribe: RpcRequest = serde_json::from_value(json!({
+            "jsonrpc": "2.0", "id": 2, "method": "slotUnsubscribe", "params": [subscription_id]
+        })).unwrap();
+        service.handle_unsubscribe(connection_id, &unsubscribe, None).await.unwrap();
+        tokio::time::sleep(Duration::from_millis(200)).await;
+        for received in [flaky_received, steady_received] {
+            assert_eq!(*received.lock().unwrap(), vec!["slotSubscribe", "slotUnsubscribe"]);
+        }
+    }
+
+    #[tokio::test]
+    async fn test_subscriptions_share_upstream_connection_and_are_limited() {
+        let (url, connections, received) = mock_upstream(vec![json!({"slot": 100})], false).await;
+        let mut config = Config::default();
+        config.endpoints = vec![crate::config::EndpointConfig {
+            url,
+            features: vec!["websocket".to_string()],
+            ..config.endpoints[0].clone()
+        }];
+        let endpoint_manager = Arc::new(EndpointManager::new(config.endpoints.clone(), config, Arc::new(CircuitBreakerRegistry::default())).await.unwrap());
+        let endpoint_id = endpoint_manager.get_endpoint_info().await[0].id;
+        endpoint_manager.update_endpoint_status(endpoint_id, crate::types::EndpointStatus::Healthy).await;
+        let service = WebSocketService::new(endpoint_manager, CancellationToken::new())
+            .with_max_subscriptions_per_connection(2);
+        let mut rx = service.broadcast_tx.subscribe();
+
+        let connection_id = Uuid::new_v4();
+        service.connections.write().await.insert(connection_id, ConnectionInfo {
+            id: connection_id,
+            subscriptions: Vec::new(),
+            last_ping: chrono::Utc::now(),
+            client_ip: None,
+            auth_context: None,
+        });
+        let subscribe = |account: &str| -> RpcRequest {
+            serde_json::from_value(json!({"jsonrpc": "2.0", "id": 1, "method": "accountSubscribe", "params": [account]})).unwrap()
+        };
+
+        let mut subscription_ids = HashSet::new();
+        for account in ["A", "B"] {
+            let response = service.handle_subscribe(connection_id, &subscribe(account), None).await.unwrap();
+            subscription_ids.insert(response["result"].as_str().unwrap().to_string());
+        }
+        // A repeat is answered with the existing subscription, but a third one is refused
+        let repeat = service.handle_subscribe(connection_id, &subscribe("B"), None).await.unwrap();
+        assert!(subscription_ids.contains(repeat["result"].as_str().unwrap()));
+        let refused = service.handle_subscribe(connection_id, &subscribe("C"), None).await.unwrap();
+        assert_eq!(refused["error"]["message"], "Subscription limit exceeded");
+        assert_eq!(service.subscriptions.read().await.len(), 2);
+
+        // Both subscriptions are mirrored over a single upstream connection
+        let mut notified = HashSet::new();
+        while notified.len() < 2 {
+            match timeout(Duration::from_secs(5), rx.recv()).await.expect("missing notification").unwrap() {
+                BroadcastMessage::Subscription { subscription_id, .. } => notified.insert(subscription_id),
+                other => panic!("unexpected broadcast: {:?}", other),
+            };
+        }
+        assert_eq!(notified, subscription_ids);
+        assert_eq!(connections.load(Ordering::SeqCst), 1);
+        assert_eq!(*received.lock().unwrap(), vec!["accountSubscribe", "accountSubscribe"]);
+
+        // The connection closes once its last subscription is gone
+        service.cleanup_connection(connection_id).await;
+        for _ in 0..50 {
+            if service.upstream_connections.lock().unwrap().is_empty() {
+                break;
+            }
+            sleep(Duration::from_millis(10)).await;
+        }
+        assert!(service.upstream_connections.lock().unwrap().is_empty());
+        assert_eq!(*received.lock().unwrap(), vec!["accountSubscribe", "accountSubscribe", "accountUnsubscribe", "accountUnsubscribe"]);
+    }
+
+    #[tokio::test]
+    async fn test_slot_notifications_invalidate_cache() {
+        let slot = |n: u64| json!({"slot": n, "parent": n - 1, "root": n - 32});
+        let (url, _, received) = mock_upstream(vec![slot(100), slot(101)], false).await;
+
+        let mut config = Config::default();
+        config.cache.enabled = true;
+        config.cache.redis_url = "redis://127.0.0.1:1".to_string();
+        config.cache.sled_cache_path = None;
+        config.endpoints = vec![crate::config::EndpointConfig {
+            url,
+            features: vec!["websocket".to_string()],
+            ..config.endpoints[0].clone()
+        }];
+        let cache_service = Arc::new(CacheService::new(&config).await.unwrap());
+        let endpoint_manager = Arc::new(EndpointManager::new(config.endpoints.clone(), config, Arc::new(CircuitBreakerRegistry::default())).await.unwrap());
+        let endpoint_id = endpoint_manager.get_endpoint_info().await[0].id;
+
+        let entry = |method: &str| crate::cache::CacheEntrySnapshot {
+            key: cache_service.create_cache_key(method, &Value::Null),
+            value: json!({"jsonrpc": "2.0", "id": 1, "result": method}),
+            ttl_remaining_secs: 60,
+        };
+        cache_service.import_entries(vec![entry("getLatestBlockhash"), entry("getGenesisHash")]).await;
+
+        let service = Arc::new(WebSocketService::new(endpoint_manager.clone(), CancellationToken::new())
+            .with_cache_service(cache_service.clone()));
+        // Keeps retrying while no endpoint is healthy yet
+        let subscribing = tokio::spawn({
+            let service = service.clone();
+            async move { service.subscribe_slot_updates().await }
+        });
+        tokio::time::sleep(Duration::from_millis(150)).await;
+        assert!(received.lock().unwrap().is_empty());
+        endpoint_manager.update_endpoint_status(endpoint_id, crate::types::EndpointStatus::Healthy).await;
+        timeout(Duration::from_secs(5), subscribing).await.expect("slot subscription never started").unwrap();
+
+        let cached = || async {
+            let mut keys: Vec<String> = cache_service.export_entries().await.into_iter().map(|e| e.key).collect();
+            keys.sort();
+            keys
+        };
+        for _ in 0..50 {
+            if service.last_invalidated_slot.load(Ordering::Relaxed) == 101 {
+                break;
+            }
+            tokio::time::sleep(Duration::from_millis(50)).await;
+        }
+        assert_eq!(service.last_invalidated_slot.load(Ordering::Relaxed), 101);
+        assert_eq!(cached().await, vec![cache_service.create_cache_key("getGenesisHash", &Value::Null)]);
+        assert_eq!(*received.lock().unwrap(), vec!["slotSubscribe"]);
+        // No client owns the subscription, so it isn't listed as one
+        assert!(service.subscriptions.read().await.is_empty());
+
+        // Slots that arrive late or twice don't invalidate again
+        cache_service.import_entries(vec![entry("getLatestBlockhash")]).await;
+        service.invalidate_slot(100).await;
+        service.invalidate_slot(101).await;
+        assert_eq!(cached().await.len(), 1);
+        service.invalidate_slot(102).await;
+        assert!(cached().await.is_empty());
+    }
+
+    #[tokio::test]
+    async fn upgrade_requires_valid_query_api_key_when_auth_enabled() {
+        use tokio_tungstenite::tungstenite::Error as TungsteniteError;
+
+        let server = crate::test_server::TestServerBuilder::new()
+            .with_config(|config| config.auth.enabled = true)
+            .start()
+            .await;
+        let ws_url = server.url("/ws").replacen("http", "ws", 1);
+
+        for url in [ws_url.clone(), format!("{}?api_key=wrong_key", ws_url)] {
+            match connect_async(url.as_str()).await {
+                Err(TungsteniteError::Http(response)) => assert_eq!(response.status(), 401, "{}", url),
+                other => panic!("expected 401 for {}, got {:?}", url, other.map(|(_, response)| response.status())),
+            }
+        }
+        assert!(server.state.websocket_service.connections.read().await.is_empty());
+
+        let (mut socket, _) = connect_async(format!("{}?api_key=demo_key_123", ws_url).as_str()).await.unwrap();
+        // The connection is registered once the upgrade completes on the server side
+        let mut context = None;
+        for _ in 0..50 {
+            context = server.state.websocket_service.connections.read().await
+                .values()
+                .find_map(|connection| connection.auth_context.clone());
+            if context.is_some() {
+                break;
+            }
+            sleep(Duration::from_millis(10)).await;
+        }
+        let context = context.expect("authenticated connection not registered");
+        assert!(context.authenticated);
+        assert_eq!(context.api_key.as_deref(), Some("demo_key_123"));
+        assert_eq!(context.user.as_deref(), Some("Demo API Key"));
+        socket.close(None).await.unwrap();
+    }
+
+    #[tokio::test]
+    async fn upgrade_is_open_when_auth_disabled() {
+        let server = crate::test_server::TestServerBuilder::new().start().await;
+        let ws_url = server.url("/ws").replacen("http", "ws", 1);
+
+        let (mut socket, _) = connect_async(ws_url.as_str()).await.unwrap();
+        socket.close(None).await.unwrap();
+    }
+}
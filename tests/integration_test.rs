@@ -1,16 +1,16 @@
-use tokio::test;
 use serde_json::{json, Value};
 use reqwest::Client;
 use std::time::Duration;
-use uuid::Uuid;
 
+// These run against a live server: start one, then `cargo test --test integration_test -- --ignored`
 const BASE_URL: &str = "http://localhost:8080";
 
 #[tokio::test]
+#[ignore = "needs a multi-rpc server running at localhost:8080"]
 async fn test_health_endpoint() {
     let client = Client::new();
     let response = client
-        .get(&format!("{}/health", BASE_URL))
+        .get(format!("{}/health", BASE_URL))
         .send()
         .await
         .expect("Failed to send request");
@@ -24,6 +24,7 @@ async fn test_health_endpoint() {
 }
 
 #[tokio::test]
+#[ignore = "needs a multi-rpc server running at localhost:8080"]
 async fn test_basic_rpc_request() {
     let client = Client::new();
     let rpc_request = json!({
@@ -49,6 +50,7 @@ async fn test_basic_rpc_request() {
 }
 
 #[tokio::test]
+#[ignore = "needs a multi-rpc server running at localhost:8080"]
 async fn test_batch_rpc_request() {
     let client = Client::new();
     let batch_request = json!([
@@ -86,6 +88,7 @@ async fn test_batch_rpc_request() {
 }
 
 #[tokio::test]
+#[ignore = "needs a multi-rpc server running at localhost:8080"]
 async fn test_invalid_rpc_request() {
     let client = Client::new();
     let invalid_request = json!({
@@ -112,10 +115,11 @@ async fn test_invalid_rpc_request() {
 }
 
 #[tokio::test]
+#[ignore = "needs a multi-rpc server running at localhost:8080"]
 async fn test_endpoints_info() {
     let client = Client::new();
     let response = client
-        .get(&format!("{}/endpoints", BASE_URL))
+        .get(format!("{}/endpoints", BASE_URL))
         .send()
         .await
         .expect("Failed to send request");
@@ -138,10 +142,11 @@ async fn test_endpoints_info() {
 }
 
 #[tokio::test]
+#[ignore = "needs a multi-rpc server running at localhost:8080"]
 async fn test_stats_endpoint() {
     let client = Client::new();
     let response = client
-        .get(&format!("{}/stats", BASE_URL))
+        .get(format!("{}/stats", BASE_URL))
         .send()
         .await
         .expect("Failed to send request");
@@ -157,10 +162,11 @@ async fn test_stats_endpoint() {
 }
 
 #[tokio::test]
+#[ignore = "needs a multi-rpc server running at localhost:8080"]
 async fn test_metrics_endpoint() {
     let client = Client::new();
     let response = client
-        .get(&format!("{}/metrics", BASE_URL))
+        .get(format!("{}/metrics", BASE_URL))
         .send()
         .await
         .expect("Failed to send request");
@@ -174,10 +180,11 @@ async fn test_metrics_endpoint() {
 }
 
 #[tokio::test]
+#[ignore = "needs a multi-rpc server running at localhost:8080"]
 async fn test_prometheus_metrics() {
     let client = Client::new();
     let response = client
-        .get(&format!("{}/metrics/prometheus", BASE_URL))
+        .get(format!("{}/metrics/prometheus", BASE_URL))
         .send()
         .await
         .expect("Failed to send request");
@@ -190,6 +197,7 @@ async fn test_prometheus_metrics() {
 }
 
 #[tokio::test]
+#[ignore = "needs a multi-rpc server running at localhost:8080"]
 async fn test_rate_limiting() {
     let client = Client::new();
     let rpc_request = json!({
@@ -232,12 +240,13 @@ async fn test_rate_limiting() {
 }
 
 #[tokio::test]
+#[ignore = "needs a multi-rpc server running at localhost:8080"]
 async fn test_authentication() {
     let client = Client::new();
     
     // Test without authentication (should work for health endpoint)
     let response = client
-        .get(&format!("{}/health", BASE_URL))
+        .get(format!("{}/health", BASE_URL))
         .send()
         .await
         .expect("Failed to send request");
@@ -245,7 +254,7 @@ async fn test_authentication() {
     
     // Test admin endpoint without auth (should require auth)
     let response = client
-        .get(&format!("{}/admin", BASE_URL))
+        .get(format!("{}/admin", BASE_URL))
         .send()
         .await
         .expect("Failed to send request");
@@ -255,10 +264,12 @@ async fn test_authentication() {
 }
 
 #[tokio::test]
+#[ignore = "needs a multi-rpc server running at localhost:8080"]
 async fn test_websocket_connection() {
+    use futures::{SinkExt, StreamExt};
     use tokio_tungstenite::{connect_async, tungstenite::Message};
     
-    let ws_url = format!("ws://localhost:8080/ws");
+    let ws_url = "ws://localhost:8080/ws".to_string();
     let (ws_stream, _) = connect_async(&ws_url).await.expect("Failed to connect to WebSocket");
     
     let (mut ws_sender, mut ws_receiver) = ws_stream.split();
@@ -285,6 +296,7 @@ async fn test_websocket_connection() {
 }
 
 #[tokio::test]
+#[ignore = "needs a multi-rpc server running at localhost:8080"]
 async fn test_concurrent_requests() {
     let client = Client::new();
     let rpc_request = json!({
@@ -326,6 +338,7 @@ async fn test_concurrent_requests() {
 }
 
 #[tokio::test]
+#[ignore = "needs a multi-rpc server running at localhost:8080"]
 async fn test_failover_behavior() {
     // This test would require the ability to temporarily disable endpoints
     // For now, we'll test that the system continues to work even with errors
@@ -359,6 +372,7 @@ async fn test_failover_behavior() {
 }
 
 #[tokio::test]
+#[ignore = "needs a multi-rpc server running at localhost:8080"]
 async fn test_cache_behavior() {
     let client = Client::new();
     
@@ -404,18 +418,8 @@ async fn test_cache_behavior() {
     println!("First request: {:?}, Second request: {:?}", duration1, duration2);
 }
 
-// Helper function to setup test environment
-async fn setup_test_environment() {
-    // This would start a test instance of Multi-RPC
-    // For now, assumes the service is already running
-}
-
-// Helper function to cleanup test environment
-async fn cleanup_test_environment() {
-    // This would clean up any test data
-}
-
 #[tokio::test]
+#[ignore = "needs a multi-rpc server running at localhost:8080"]
 async fn test_geographic_routing() {
     let client = Client::new();
     
@@ -455,6 +459,7 @@ async fn test_geographic_routing() {
 }
 
 #[tokio::test]
+#[ignore = "needs a multi-rpc server running at localhost:8080"]
 async fn test_consensus_validation() {
     let client = Client::new();
     